@@ -0,0 +1,296 @@
+use super::sources::Erc20;
+use super::PortfolioError;
+use ethers::abi::{encode, Token};
+use ethers::prelude::*;
+use ethers::utils::hash_message;
+use mevdetector::compat::keccak256;
+use mevdetector::store::{SharedStore, StoreExt};
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+/// Версия формата документа аттестации
+pub const ATTESTATION_VERSION: u32 = 1;
+
+/// Пространство имён подписанных аттестаций; ключ — номер блока с ведущими нулями
+const ATTESTATIONS_NS: &str = "reserve_attestations";
+
+/// Актив резерва; `token: None` — нативный ETH
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReserveAsset {
+    pub symbol: String,
+    pub token: Option<Address>,
+}
+
+/// Баланс одного адреса хранения в одном активе — лист дерева Меркла
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustodyBalance {
+    pub address: Address,
+    pub symbol: String,
+    pub token: Option<Address>,
+    pub balance: U256,
+}
+
+impl CustodyBalance {
+    /// `keccak256(abi.encode(address, token, balance))`; токен нативного ETH — нулевой адрес
+    pub fn leaf(&self) -> H256 {
+        keccak256(encode(&[
+            Token::Address(self.address),
+            Token::Address(self.token.unwrap_or_default()),
+            Token::Uint(self.balance),
+        ]))
+    }
+}
+
+fn hash_pair(a: H256, b: H256) -> H256 {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([lo.as_bytes(), hi.as_bytes()].concat())
+}
+
+/// Дерево Меркла с сортированными парами (как `MerkleProof` OpenZeppelin):
+/// доказательство проверяется без указания сторон
+pub struct MerkleTree {
+    levels: Vec<Vec<H256>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<H256>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(*a, *b),
+                    // Непарный узел поднимается без изменений
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Нулевой корень — пустое дерево
+    pub fn root(&self) -> H256 {
+        self.levels.last().and_then(|l| l.first()).copied().unwrap_or_default()
+    }
+
+    pub fn proof(&self, mut index: usize) -> Vec<H256> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+
+    pub fn verify(root: H256, leaf: H256, proof: &[H256]) -> bool {
+        proof.iter().fold(leaf, |acc, sibling| hash_pair(acc, *sibling)) == root
+    }
+}
+
+/// Документ аттестации резервов на блоке
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationDocument {
+    pub version: u32,
+    pub chain_id: u64,
+    pub block: u64,
+    pub block_hash: H256,
+    pub block_timestamp: u64,
+    pub generated_at: u64,
+    /// Сумма по активу по всем адресам хранения
+    pub totals: BTreeMap<String, U256>,
+    pub merkle_root: H256,
+    /// Листья в порядке дерева с доказательствами включения
+    pub balances: Vec<(CustodyBalance, Vec<H256>)>,
+}
+
+impl AttestationDocument {
+    /// Хэш канонического JSON документа — то, что подписывается
+    pub fn digest(&self) -> H256 {
+        keccak256(serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+/// Документ с подписью EIP-191 над `digest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    pub document: AttestationDocument,
+    pub signer: Address,
+    pub signature: String,
+}
+
+impl SignedAttestation {
+    /// Подпись принадлежит `signer`, а доказательства сходятся к корню
+    pub fn verify(&self) -> bool {
+        let Ok(signature) = self.signature.parse::<Signature>() else {
+            return false;
+        };
+        let signed = signature.verify(self.document.digest().as_bytes(), self.signer).is_ok();
+        let root = self.document.merkle_root;
+        signed && self.document.balances.iter().all(|(b, proof)| MerkleTree::verify(root, b.leaf(), proof))
+    }
+}
+
+/// Генератор аттестаций резервов для наблюдаемых адресов хранения
+pub struct ReserveAttestor<M> {
+    provider: Arc<M>,
+    custody: Vec<Address>,
+    assets: Vec<ReserveAsset>,
+    signer: LocalWallet,
+    store: Option<SharedStore>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> ReserveAttestor<M> {
+    pub fn new(provider: Arc<M>, custody: Vec<Address>, assets: Vec<ReserveAsset>, signer: LocalWallet) -> Self {
+        Self { provider, custody, assets, signer, store: None, errors: TaskErrors::default() }
+    }
+
+    /// Неудачные аттестации фонового цикла
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Сохранять подписанные аттестации в хранилище
+    pub fn with_store(mut self, store: SharedStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    async fn balance(&self, address: Address, asset: &ReserveAsset, block: BlockId) -> Result<U256, PortfolioError> {
+        match asset.token {
+            None => self
+                .provider
+                .get_balance(address, Some(block))
+                .await
+                .map_err(|e| PortfolioError::ContractError(e.to_string())),
+            Some(token) => Erc20::new(token, self.provider.clone())
+                .balance_of(address)
+                .block(block)
+                .call()
+                .await
+                .map_err(|e| PortfolioError::ContractError(e.to_string())),
+        }
+    }
+
+    /// Снимок балансов на блоке с корнем Меркла; все чтения — на одном блоке
+    pub async fn snapshot(&self, block: u64) -> Result<AttestationDocument, PortfolioError> {
+        let contract_err = |e: M::Error| PortfolioError::ContractError(e.to_string());
+        let header = self
+            .provider
+            .get_block(block)
+            .await
+            .map_err(contract_err)?
+            .ok_or_else(|| PortfolioError::ContractError(format!("block {} not found", block)))?;
+        let chain_id = self.provider.get_chainid().await.map_err(contract_err)?.as_u64();
+        let at = BlockId::Number(BlockNumber::Number(block.into()));
+
+        let mut custody = self.custody.clone();
+        custody.sort();
+        custody.dedup();
+
+        let mut balances = Vec::new();
+        let mut totals: BTreeMap<String, U256> = BTreeMap::new();
+        for address in custody {
+            for asset in &self.assets {
+                let balance = self.balance(address, asset, at).await?;
+                let total = totals.entry(asset.symbol.clone()).or_default();
+                *total = total.saturating_add(balance);
+                balances.push(CustodyBalance { address, symbol: asset.symbol.clone(), token: asset.token, balance });
+            }
+        }
+
+        let tree = MerkleTree::new(balances.iter().map(CustodyBalance::leaf).collect());
+        let balances = balances.into_iter().enumerate().map(|(i, b)| (b, tree.proof(i))).collect();
+
+        Ok(AttestationDocument {
+            version: ATTESTATION_VERSION,
+            chain_id,
+            block,
+            block_hash: header.hash.unwrap_or_default(),
+            block_timestamp: header.timestamp.as_u64(),
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            totals,
+            merkle_root: tree.root(),
+            balances,
+        })
+    }
+
+    /// Снимок, подпись и (если задано хранилище) сохранение
+    pub async fn attest(&self, block: u64) -> Result<SignedAttestation, PortfolioError> {
+        let document = self.snapshot(block).await?;
+        let signature = self
+            .signer
+            .sign_hash(hash_message(document.digest().as_bytes()))
+            .map_err(|e| PortfolioError::ContractError(e.to_string()))?;
+        let attestation = SignedAttestation { document, signer: self.signer.address(), signature: signature.to_string() };
+
+        if let Some(store) = &self.store {
+            store
+                .put_json(ATTESTATIONS_NS, &format!("{:020}", block), &attestation)
+                .map_err(|e| PortfolioError::ContractError(e.to_string()))?;
+        }
+        Ok(attestation)
+    }
+
+    /// Аттестация каждые `every_blocks` блоков из `heads`
+    pub async fn run(&self, mut heads: watch::Receiver<u64>, every_blocks: u64) {
+        let mut last = None;
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            if last.is_some_and(|l: u64| block < l + every_blocks) {
+                continue;
+            }
+            match self.attest(block).await {
+                Ok(_) => last = Some(block),
+                Err(e) => self.errors.record(format!("reserve attestation at block {} failed: {}", block, e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_balance_proves_into_signed_root() {
+        let balances: Vec<CustodyBalance> = (1..=5u8)
+            .map(|i| CustodyBalance {
+                address: Address::repeat_byte(i),
+                symbol: "ETH".into(),
+                token: None,
+                balance: U256::from(i as u64 * 1_000),
+            })
+            .collect();
+        let tree = MerkleTree::new(balances.iter().map(CustodyBalance::leaf).collect());
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+
+        let document = AttestationDocument {
+            version: ATTESTATION_VERSION,
+            chain_id: 1,
+            block: 100,
+            block_hash: H256::zero(),
+            block_timestamp: 0,
+            generated_at: 0,
+            totals: BTreeMap::from([("ETH".to_string(), U256::from(15_000u64))]),
+            merkle_root: tree.root(),
+            balances: balances.iter().cloned().enumerate().map(|(i, b)| (b, tree.proof(i))).collect(),
+        };
+        let signature = wallet.sign_hash(hash_message(document.digest().as_bytes())).unwrap();
+        let mut attestation = SignedAttestation { document, signer: wallet.address(), signature: signature.to_string() };
+        assert!(attestation.verify());
+
+        attestation.document.balances[3].0.balance += U256::one();
+        assert!(!attestation.verify());
+    }
+}
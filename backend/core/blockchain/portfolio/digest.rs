@@ -0,0 +1,78 @@
+use super::{PortfolioSnapshot, PortfolioTracker, PositionKind};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use mevdetector::digest::{DigestRow, DigestSection, DigestSource, DigestTenant};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Изменения меньше этой доли позиции в дайджест не попадают
+const MIN_DELTA_FRACTION: f64 = 0.001;
+
+fn position_label(kind: &PositionKind) -> String {
+    match kind {
+        PositionKind::Native => "ETH".into(),
+        PositionKind::Erc20 { symbol, .. } | PositionKind::Staking { symbol, .. } => symbol.clone(),
+        PositionKind::EigenShares { strategy, .. } => format!("EigenLayer {:?}", strategy),
+    }
+}
+
+fn amounts(snapshot: &PortfolioSnapshot) -> HashMap<(u64, String), f64> {
+    let mut amounts = HashMap::new();
+    for position in &snapshot.positions {
+        *amounts.entry((position.chain_id, position_label(&position.kind))).or_insert(0.0) += position.amount;
+    }
+    amounts
+}
+
+/// Раздел дайджеста с изменениями позиций с прошлого дайджеста тенанта
+pub struct PortfolioDigest<M> {
+    tracker: Arc<PortfolioTracker<M>>,
+    /// Тенант -> адрес -> суммы на момент прошлого дайджеста
+    previous: Mutex<HashMap<String, HashMap<Address, HashMap<(u64, String), f64>>>>,
+}
+
+impl<M: Middleware + 'static> PortfolioDigest<M> {
+    pub fn new(tracker: Arc<PortfolioTracker<M>>) -> Self {
+        Self { tracker, previous: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> DigestSource for PortfolioDigest<M> {
+    async fn section(&self, tenant: &DigestTenant, _since: u64, _until: u64) -> Option<DigestSection> {
+        let mut previous = self.previous.lock().unwrap();
+        let previous = previous.entry(tenant.name.clone()).or_default();
+
+        let mut addresses: Vec<Address> =
+            self.tracker.tracked().into_iter().filter(|a| tenant.covers(&format!("{:?}", a))).collect();
+        addresses.sort();
+
+        let mut rows = Vec::new();
+        for address in addresses {
+            let Some(snapshot) = self.tracker.snapshot(address) else {
+                continue;
+            };
+            let current = amounts(&snapshot);
+            // Первый дайджест только запоминает суммы
+            if let Some(before) = previous.get(&address) {
+                let mut keys: Vec<&(u64, String)> = current.keys().chain(before.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let (was, now) = (before.get(key).copied().unwrap_or(0.0), current.get(key).copied().unwrap_or(0.0));
+                    let delta = now - was;
+                    if delta.abs() <= was.abs().max(now.abs()) * MIN_DELTA_FRACTION {
+                        continue;
+                    }
+                    rows.push(DigestRow {
+                        label: format!("{:?} {} (chain {})", address, key.1, key.0),
+                        value: format!("{:+.4} ({:.4} -> {:.4})", delta, was, now),
+                    });
+                }
+            }
+            previous.insert(address, current);
+        }
+
+        (!rows.is_empty()).then(|| DigestSection { title: "Portfolio changes".into(), rows })
+    }
+}
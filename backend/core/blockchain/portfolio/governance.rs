@@ -0,0 +1,466 @@
+use super::lending::{read_health, units, AavePool, Comet, HealthReading, LendingMarket};
+use super::PortfolioError;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::id;
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+abigen!(
+    Governor,
+    r#"[
+        event ProposalCreated(uint256 proposalId, address proposer, address[] targets, uint256[] values, string[] signatures, bytes[] calldatas, uint256 voteStart, uint256 voteEnd, string description)
+        function timelock() external view returns (address)
+    ]"#
+);
+
+const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+#[derive(Debug, Error)]
+pub enum GovernanceSimError {
+    #[error("Fork error: {0}")]
+    ForkError(String),
+
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Portfolio(#[from] PortfolioError),
+}
+
+fn provider_error(e: impl std::fmt::Display) -> GovernanceSimError {
+    GovernanceSimError::ProviderError(e.to_string())
+}
+
+/// Один вызов из предложения
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalAction {
+    pub target: Address,
+    pub value: U256,
+    pub calldata: Bytes,
+}
+
+/// Предложение OpenZeppelin Governor или Governor Bravo
+#[derive(Debug, Clone, Serialize)]
+pub struct GovernanceProposal {
+    pub governor: Address,
+    pub proposal_id: U256,
+    pub proposer: Address,
+    pub actions: Vec<ProposalAction>,
+    pub description: String,
+    pub created_block: u64,
+}
+
+impl GovernanceProposal {
+    fn from_event(governor: Address, block: u64, event: ProposalCreatedFilter) -> Self {
+        let actions = event
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| {
+                let data = event.calldatas.get(i).cloned().unwrap_or_default();
+                // Bravo хранит сигнатуру отдельно от аргументов
+                let calldata = match event.signatures.get(i).filter(|s| !s.is_empty()) {
+                    Some(signature) => [id(signature).as_slice(), data.as_ref()].concat().into(),
+                    None => data,
+                };
+                ProposalAction {
+                    target: *target,
+                    value: event.values.get(i).copied().unwrap_or_default(),
+                    calldata,
+                }
+            })
+            .collect();
+        Self {
+            governor,
+            proposal_id: event.proposal_id,
+            proposer: event.proposer,
+            actions,
+            description: event.description,
+            created_block: block,
+        }
+    }
+}
+
+/// Параметры локального форка на anvil
+#[derive(Debug, Clone)]
+pub struct ForkConfig {
+    pub anvil: PathBuf,
+    pub fork_url: String,
+    pub startup_timeout: Duration,
+}
+
+impl ForkConfig {
+    pub fn new(fork_url: &str) -> Self {
+        Self {
+            anvil: PathBuf::from("anvil"),
+            fork_url: fork_url.to_string(),
+            startup_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Процесс anvil; останавливается вместе со значением
+struct Fork {
+    child: Child,
+    provider: Arc<Provider<Http>>,
+}
+
+impl Drop for Fork {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Fork {
+    async fn start(config: &ForkConfig, block: u64) -> Result<Self, GovernanceSimError> {
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let child = Command::new(&config.anvil)
+            .args(["--fork-url", &config.fork_url])
+            .args(["--fork-block-number", &block.to_string()])
+            .args(["--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let provider = Provider::<Http>::try_from(format!("http://127.0.0.1:{}", port))
+            .map_err(|e| GovernanceSimError::ForkError(e.to_string()))?;
+        let fork = Self { child, provider: Arc::new(provider) };
+
+        let started = Instant::now();
+        while fork.provider.get_block_number().await.is_err() {
+            if started.elapsed() > config.startup_timeout {
+                return Err(GovernanceSimError::ForkError("anvil did not start in time".into()));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        Ok(fork)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    /// Годовая ставка
+    Rate,
+    CollateralFactor,
+    /// 1.0 — приостановлено
+    Paused,
+}
+
+/// Параметры рынка, которые может поменять governance
+pub type MarketParams = BTreeMap<(ParamKind, String), f64>;
+
+/// Снимает ставки, залоговые коэффициенты и флаги паузы рынка.
+/// Ставки Aave пересчитываются только при следующем действии с резервом,
+/// поэтому смена стратегии ставок видна не сразу
+pub async fn read_market_params<M: Middleware + 'static>(
+    provider: Arc<M>,
+    market: &LendingMarket,
+) -> Result<MarketParams, GovernanceSimError> {
+    let mut params = MarketParams::new();
+    match market {
+        LendingMarket::AaveV3 { pool, .. } => {
+            let pool = AavePool::new(*pool, provider.clone());
+            for asset in pool.get_reserves_list().call().await.map_err(provider_error)? {
+                let (config, _, liquidity_rate, _, variable_borrow_rate, ..) =
+                    pool.get_reserve_data(asset).call().await.map_err(provider_error)?;
+                let bits = |from: usize, len: usize| ((config >> from) & ((U256::one() << len) - 1)).as_u64();
+                let key = format!("{:?}", asset);
+                params.insert((ParamKind::CollateralFactor, key.clone()), bits(16, 16) as f64 / 10_000.0);
+                params.insert((ParamKind::Paused, format!("{}:frozen", key)), bits(57, 1) as f64);
+                params.insert((ParamKind::Paused, format!("{}:paused", key)), bits(60, 1) as f64);
+                params.insert(
+                    (ParamKind::Rate, format!("{}:borrow", key)),
+                    units(variable_borrow_rate.into(), 27),
+                );
+                params.insert(
+                    (ParamKind::Rate, format!("{}:supply", key)),
+                    units(liquidity_rate.into(), 27),
+                );
+            }
+        }
+        LendingMarket::CompoundV3 { comet } => {
+            let comet = Comet::new(*comet, provider.clone());
+            let utilization = comet.get_utilization().call().await.map_err(provider_error)?;
+            let borrow = comet.get_borrow_rate(utilization).call().await.map_err(provider_error)?;
+            let supply = comet.get_supply_rate(utilization).call().await.map_err(provider_error)?;
+            params.insert((ParamKind::Rate, "borrow".into()), borrow as f64 / 1e18 * SECONDS_PER_YEAR);
+            params.insert((ParamKind::Rate, "supply".into()), supply as f64 / 1e18 * SECONDS_PER_YEAR);
+
+            for (name, paused) in [
+                ("supply", comet.is_supply_paused().call().await),
+                ("withdraw", comet.is_withdraw_paused().call().await),
+                ("absorb", comet.is_absorb_paused().call().await),
+            ] {
+                params.insert((ParamKind::Paused, name.into()), paused.map_err(provider_error)? as u8 as f64);
+            }
+
+            for i in 0..comet.num_assets().call().await.map_err(provider_error)? {
+                let (_, asset, _, _, _, liquidate_collateral_factor, _, _) =
+                    comet.get_asset_info(i).call().await.map_err(provider_error)?;
+                params.insert(
+                    (ParamKind::CollateralFactor, format!("{:?}", asset)),
+                    liquidate_collateral_factor as f64 / 1e18,
+                );
+            }
+        }
+        // Параметры рынков Morpho Blue неизменяемы; влияние видно только через health factor
+        LendingMarket::MorphoBlue { .. } => {}
+    }
+    Ok(params)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParamChange {
+    pub market: LendingMarket,
+    pub kind: ParamKind,
+    pub key: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthChange {
+    pub account: Address,
+    pub market: LendingMarket,
+    pub before: HealthReading,
+    pub after: HealthReading,
+}
+
+/// Результат исполнения предложения на форке
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalImpact {
+    pub proposal_id: U256,
+    pub executor: Address,
+    pub fork_block: u64,
+    /// Индекс и причина первого откатившегося вызова
+    pub reverted: Option<(usize, String)>,
+    pub param_changes: Vec<ParamChange>,
+    pub health_changes: Vec<HealthChange>,
+}
+
+impl ProposalImpact {
+    /// Уровень алерта: ликвидация на форке — критично, пауза или заметное падение health factor — высокий
+    pub fn level(&self) -> AlertLevel {
+        let liquidatable = self.health_changes.iter().any(|c| c.after.health_factor < 1.0);
+        let paused = self
+            .param_changes
+            .iter()
+            .any(|c| c.kind == ParamKind::Paused && c.after > c.before);
+        let health_drop = self
+            .health_changes
+            .iter()
+            .any(|c| c.after.health_factor < c.before.health_factor * 0.9);
+
+        if liquidatable {
+            AlertLevel::Critical
+        } else if paused || health_drop {
+            AlertLevel::High
+        } else if !self.param_changes.is_empty() || !self.health_changes.is_empty() {
+            AlertLevel::Medium
+        } else if self.reverted.is_some() {
+            AlertLevel::Low
+        } else {
+            AlertLevel::Info
+        }
+    }
+}
+
+fn diff_params(market: &LendingMarket, before: &MarketParams, after: &MarketParams) -> Vec<ParamChange> {
+    let keys: HashSet<&(ParamKind, String)> = before.keys().chain(after.keys()).collect();
+    let mut changes: Vec<ParamChange> = keys
+        .into_iter()
+        .filter_map(|key| {
+            let b = before.get(key).copied().unwrap_or(0.0);
+            let a = after.get(key).copied().unwrap_or(0.0);
+            (b != a).then(|| ParamChange {
+                market: market.clone(),
+                kind: key.0,
+                key: key.1.clone(),
+                before: b,
+                after: a,
+            })
+        })
+        .collect();
+    changes.sort_by(|x, y| (x.kind, &x.key).cmp(&(y.kind, &y.key)));
+    changes
+}
+
+/// Исполняет действия предложения на форке от имени исполнителя (таймлока или самого governor),
+/// минуя голосование и очередь
+pub struct ProposalSimulator {
+    config: ForkConfig,
+}
+
+impl ProposalSimulator {
+    pub fn new(config: ForkConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn simulate(
+        &self,
+        proposal: &GovernanceProposal,
+        block: u64,
+        watched: &[(Address, LendingMarket)],
+    ) -> Result<ProposalImpact, GovernanceSimError> {
+        let fork = Fork::start(&self.config, block).await?;
+        let provider = fork.provider.clone();
+
+        let executor = match Governor::new(proposal.governor, provider.clone()).timelock().call().await {
+            Ok(timelock) if !timelock.is_zero() => timelock,
+            _ => proposal.governor,
+        };
+        provider
+            .request::<_, ()>("anvil_impersonateAccount", (executor,))
+            .await
+            .map_err(provider_error)?;
+        provider
+            .request::<_, ()>("anvil_setBalance", (executor, U256::exp10(24)))
+            .await
+            .map_err(provider_error)?;
+
+        let mut markets: Vec<LendingMarket> = Vec::new();
+        for (_, market) in watched {
+            if !markets.contains(market) {
+                markets.push(market.clone());
+            }
+        }
+
+        let mut params_before = Vec::new();
+        for market in &markets {
+            params_before.push(read_market_params(provider.clone(), market).await?);
+        }
+        let mut health_before = Vec::new();
+        for (account, market) in watched {
+            health_before.push(read_health(provider.clone(), *account, market, block).await?);
+        }
+
+        let mut reverted = None;
+        for (i, action) in proposal.actions.iter().enumerate() {
+            let tx: TypedTransaction = TransactionRequest::new()
+                .from(executor)
+                .to(action.target)
+                .value(action.value)
+                .data(action.calldata.clone())
+                .into();
+            // Пробный вызов даёт причину отката, которую receipt не содержит
+            if let Err(e) = provider.call(&tx, None).await {
+                reverted = Some((i, e.to_string()));
+                break;
+            }
+            let receipt = provider
+                .send_transaction(tx, None)
+                .await
+                .map_err(provider_error)?
+                .await
+                .map_err(provider_error)?;
+            if receipt.and_then(|r| r.status) != Some(U64::one()) {
+                reverted = Some((i, "reverted".to_string()));
+                break;
+            }
+        }
+
+        let after_block = provider.get_block_number().await.map_err(provider_error)?.as_u64();
+        let mut param_changes = Vec::new();
+        for (market, before) in markets.iter().zip(&params_before) {
+            let after = read_market_params(provider.clone(), market).await?;
+            param_changes.extend(diff_params(market, before, &after));
+        }
+        let mut health_changes = Vec::new();
+        for ((account, market), before) in watched.iter().zip(health_before) {
+            let after = read_health(provider.clone(), *account, market, after_block).await?;
+            if after.health_factor != before.health_factor {
+                health_changes.push(HealthChange {
+                    account: *account,
+                    market: market.clone(),
+                    before,
+                    after,
+                });
+            }
+        }
+
+        Ok(ProposalImpact {
+            proposal_id: proposal.proposal_id,
+            executor,
+            fork_block: block,
+            reverted,
+            param_changes,
+            health_changes,
+        })
+    }
+}
+
+/// Следит за новыми предложениями governor-контрактов и публикует алерт с анализом влияния
+pub struct ProposalWatcher<M> {
+    provider: Arc<M>,
+    bus: AlertBus,
+    simulator: ProposalSimulator,
+    governors: Vec<Address>,
+    last_block: u64,
+}
+
+impl<M: Middleware + 'static> ProposalWatcher<M> {
+    pub fn new(provider: Arc<M>, bus: AlertBus, simulator: ProposalSimulator, governors: Vec<Address>, from_block: u64) -> Self {
+        Self {
+            provider,
+            bus,
+            simulator,
+            governors,
+            last_block: from_block,
+        }
+    }
+
+    /// Новые предложения до `block` включительно; ошибка симуляции не скрывает алерт о предложении
+    pub async fn poll(&mut self, block: u64, watched: &[(Address, LendingMarket)]) -> Result<Vec<GovernanceProposal>, GovernanceSimError> {
+        if block <= self.last_block {
+            return Ok(Vec::new());
+        }
+
+        let mut proposals = Vec::new();
+        for governor in &self.governors {
+            let events = Governor::new(*governor, self.provider.clone())
+                .event::<ProposalCreatedFilter>()
+                .from_block(self.last_block + 1)
+                .to_block(block)
+                .query_with_meta()
+                .await
+                .map_err(provider_error)?;
+            for (event, meta) in events {
+                proposals.push(GovernanceProposal::from_event(*governor, meta.block_number.as_u64(), event));
+            }
+        }
+        self.last_block = block;
+
+        for proposal in &proposals {
+            let (level, impact) = match self.simulator.simulate(proposal, block, watched).await {
+                Ok(impact) => (impact.level(), json!(impact)),
+                Err(e) => (AlertLevel::Low, json!({ "error": e.to_string() })),
+            };
+            let title = proposal.description.lines().next().unwrap_or_default().to_string();
+            self.bus.publish(
+                BusAlert::new(
+                    "governance",
+                    "proposal",
+                    level,
+                    format!("{:?}", proposal.governor),
+                    format!("Proposal {}: {}", proposal.proposal_id, title),
+                )
+                .with_payload(json!({ "proposal": proposal, "impact": impact })),
+            );
+        }
+
+        Ok(proposals)
+    }
+}
@@ -0,0 +1,468 @@
+use super::sources::Erc20;
+use super::PortfolioError;
+use ethers::abi::AbiEncode;
+use ethers::prelude::*;
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+abigen!(
+    AavePool,
+    r#"[
+        struct ReserveConfigurationMap { uint256 data; }
+        struct ReserveData { ReserveConfigurationMap configuration; uint128 liquidityIndex; uint128 currentLiquidityRate; uint128 variableBorrowIndex; uint128 currentVariableBorrowRate; uint128 currentStableBorrowRate; uint40 lastUpdateTimestamp; uint16 id; address aTokenAddress; address stableDebtTokenAddress; address variableDebtTokenAddress; address interestRateStrategyAddress; uint128 accruedToTreasury; uint128 unbacked; uint128 isolationModeTotalDebt; }
+        function getUserAccountData(address user) external view returns (uint256 totalCollateralBase, uint256 totalDebtBase, uint256 availableBorrowsBase, uint256 currentLiquidationThreshold, uint256 ltv, uint256 healthFactor)
+        function getReservesList() external view returns (address[])
+        function getReserveData(address asset) external view returns (ReserveData)
+        function repay(address asset, uint256 amount, uint256 interestRateMode, address onBehalfOf) external returns (uint256)
+    ]"#
+);
+
+abigen!(
+    AaveOracle,
+    r#"[
+        function getAssetPrice(address asset) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    Comet,
+    r#"[
+        function numAssets() external view returns (uint8)
+        struct AssetInfo { uint8 offset; address asset; address priceFeed; uint64 scale; uint64 borrowCollateralFactor; uint64 liquidateCollateralFactor; uint64 liquidationFactor; uint128 supplyCap; }
+        function getAssetInfo(uint8 i) external view returns (AssetInfo)
+        function collateralBalanceOf(address account, address asset) external view returns (uint128)
+        function borrowBalanceOf(address account) external view returns (uint256)
+        function getPrice(address priceFeed) external view returns (uint256)
+        function baseToken() external view returns (address)
+        function baseTokenPriceFeed() external view returns (address)
+        function baseScale() external view returns (uint256)
+        function supply(address asset, uint256 amount) external
+        function getUtilization() external view returns (uint256)
+        function getSupplyRate(uint256 utilization) external view returns (uint64)
+        function getBorrowRate(uint256 utilization) external view returns (uint64)
+        function isSupplyPaused() external view returns (bool)
+        function isWithdrawPaused() external view returns (bool)
+        function isAbsorbPaused() external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    MorphoBlue,
+    r#"[
+        function position(bytes32 id, address user) external view returns (uint256 supplyShares, uint128 borrowShares, uint128 collateral)
+        function market(bytes32 id) external view returns (uint128 totalSupplyAssets, uint128 totalSupplyShares, uint128 totalBorrowAssets, uint128 totalBorrowShares, uint128 lastUpdate, uint128 fee)
+        function idToMarketParams(bytes32 id) external view returns (address loanToken, address collateralToken, address oracle, address irm, uint256 lltv)
+    ]"#
+);
+
+abigen!(
+    MorphoOracle,
+    r#"[
+        function price() external view returns (uint256)
+    ]"#
+);
+
+/// Лендинговая позиция под наблюдением
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "protocol", rename_all = "snake_case")]
+pub enum LendingMarket {
+    /// `oracle` и `debt_asset` нужны только для подсказки погашения: Aave отдаёт долг в базовой валюте
+    AaveV3 { pool: Address, oracle: Option<Address>, debt_asset: Option<Address> },
+    CompoundV3 { comet: Address },
+    MorphoBlue { morpho: Address, market_id: H256 },
+}
+
+/// Пороги health factor и соответствующие уровни алертов; проверяются сверху вниз
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthConfig {
+    pub levels: Vec<(f64, AlertLevel)>,
+    /// Наблюдений для оценки тренда
+    pub trend_window: usize,
+    /// Целевой health factor для подсказки погашения; `None` — без подсказок
+    pub deleverage_target: Option<f64>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            levels: vec![
+                (1.03, AlertLevel::Critical),
+                (1.1, AlertLevel::High),
+                (1.25, AlertLevel::Medium),
+                (1.5, AlertLevel::Low),
+            ],
+            trend_window: 20,
+            deleverage_target: None,
+        }
+    }
+}
+
+impl HealthConfig {
+    fn level(&self, health_factor: f64) -> Option<AlertLevel> {
+        self.levels
+            .iter()
+            .find(|(threshold, _)| health_factor < *threshold)
+            .map(|(_, level)| *level)
+    }
+}
+
+/// Снятое состояние позиции; суммы в единицах актива долга (для Aave — базовая валюта пула)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReading {
+    /// Залог, взвешенный порогом ликвидации
+    pub weighted_collateral: f64,
+    pub debt: f64,
+    pub health_factor: f64,
+}
+
+/// Предлагаемое погашение до целевого health factor; транзакция не отправляется
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleverageSuggestion {
+    pub target_health_factor: f64,
+    pub repay_amount: f64,
+    /// Адрес контракта и calldata, если актив долга известен
+    pub to: Option<Address>,
+    pub calldata: Option<Bytes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthAlert {
+    pub account: Address,
+    pub market: LendingMarket,
+    pub block: u64,
+    pub reading: HealthReading,
+    pub level: AlertLevel,
+    /// Изменение health factor в секунду по тренду
+    pub trend_per_second: f64,
+    /// Оценка времени до health factor 1.0 при сохранении тренда
+    pub seconds_to_liquidation: Option<f64>,
+    pub suggestion: Option<DeleverageSuggestion>,
+}
+
+struct TrackedPosition {
+    history: VecDeque<(u64, f64)>,
+    last_level: Option<AlertLevel>,
+}
+
+/// Наклон регрессии health factor по времени
+fn trend(history: &VecDeque<(u64, f64)>) -> f64 {
+    if history.len() < 2 {
+        return 0.0;
+    }
+    let n = history.len() as f64;
+    let t0 = history[0].0 as f64;
+    let mean_t = history.iter().map(|(t, _)| *t as f64 - t0).sum::<f64>() / n;
+    let mean_h = history.iter().map(|(_, h)| *h).sum::<f64>() / n;
+    let (mut cov, mut var) = (0.0, 0.0);
+    for (t, h) in history {
+        let dt = *t as f64 - t0 - mean_t;
+        cov += dt * (h - mean_h);
+        var += dt * dt;
+    }
+    if var == 0.0 {
+        0.0
+    } else {
+        cov / var
+    }
+}
+
+/// Через сколько секунд health factor дойдёт до 1.0 при наклоне `slope`
+pub fn time_to_liquidation(health_factor: f64, slope: f64) -> Option<f64> {
+    if health_factor <= 1.0 {
+        Some(0.0)
+    } else if slope < 0.0 {
+        Some((health_factor - 1.0) / -slope)
+    } else {
+        None
+    }
+}
+
+/// Сумма погашения, после которой `collateral / (debt - x) = target`
+pub fn repay_to_target(reading: &HealthReading, target: f64) -> f64 {
+    (reading.debt - reading.weighted_collateral / target).max(0.0)
+}
+
+pub(crate) fn units(raw: U256, decimals: u32) -> f64 {
+    ethers::utils::format_units(raw, decimals)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn contract_error(e: impl std::fmt::Display) -> PortfolioError {
+    PortfolioError::ContractError(e.to_string())
+}
+
+/// Текущее состояние позиции в протоколе
+pub async fn read_health<M: Middleware + 'static>(
+    provider: Arc<M>,
+    account: Address,
+    market: &LendingMarket,
+    block: u64,
+) -> Result<HealthReading, PortfolioError> {
+    let block_id = BlockId::Number(block.into());
+    match market {
+        LendingMarket::AaveV3 { pool, .. } => {
+            let (collateral, debt, _, threshold_bps, _, health) = AavePool::new(*pool, provider.clone())
+                .get_user_account_data(account)
+                .block(block_id)
+                .call()
+                .await
+                .map_err(contract_error)?;
+            // Базовая валюта Aave V3 — USD с 8 знаками, порог — в базисных пунктах
+            let debt = units(debt, 8);
+            Ok(HealthReading {
+                weighted_collateral: units(collateral, 8) * units(threshold_bps, 4),
+                debt,
+                health_factor: if debt == 0.0 { f64::INFINITY } else { units(health, 18) },
+            })
+        }
+        LendingMarket::CompoundV3 { comet } => {
+            let comet = Comet::new(*comet, provider.clone());
+            let base_price = comet
+                .get_price(comet.base_token_price_feed().block(block_id).call().await.map_err(contract_error)?)
+                .block(block_id)
+                .call()
+                .await
+                .map_err(contract_error)?;
+            let base_scale = comet.base_scale().block(block_id).call().await.map_err(contract_error)?;
+            let borrow = comet.borrow_balance_of(account).block(block_id).call().await.map_err(contract_error)?;
+            // Цены фидов Comet — с 8 знаками
+            let debt = units(borrow, 0) / units(base_scale, 0);
+
+            let mut weighted = 0.0;
+            for i in 0..comet.num_assets().block(block_id).call().await.map_err(contract_error)? {
+                let (_, asset, price_feed, scale, _, liquidate_collateral_factor, _, _) =
+                    comet.get_asset_info(i).block(block_id).call().await.map_err(contract_error)?;
+                let balance = comet
+                    .collateral_balance_of(account, asset)
+                    .block(block_id)
+                    .call()
+                    .await
+                    .map_err(contract_error)?;
+                if balance == 0 {
+                    continue;
+                }
+                let price = comet.get_price(price_feed).block(block_id).call().await.map_err(contract_error)?;
+                weighted += balance as f64 / scale as f64
+                    * units(price, 8)
+                    * liquidate_collateral_factor as f64
+                    / 1e18;
+            }
+
+            // Залог пересчитывается в базовый токен
+            let weighted = weighted / units(base_price, 8);
+            Ok(HealthReading {
+                weighted_collateral: weighted,
+                debt,
+                health_factor: if debt == 0.0 { f64::INFINITY } else { weighted / debt },
+            })
+        }
+        LendingMarket::MorphoBlue { morpho, market_id } => {
+            let morpho = MorphoBlue::new(*morpho, provider.clone());
+            let id: [u8; 32] = market_id.0;
+            let (_, borrow_shares, collateral) = morpho.position(id, account).block(block_id).call().await.map_err(contract_error)?;
+            let (_, _, total_borrow_assets, total_borrow_shares, _, _) =
+                morpho.market(id).block(block_id).call().await.map_err(contract_error)?;
+            let (_, _, oracle, _, lltv) = morpho.id_to_market_params(id).block(block_id).call().await.map_err(contract_error)?;
+            let price = MorphoOracle::new(oracle, provider.clone())
+                .price()
+                .block(block_id)
+                .call()
+                .await
+                .map_err(contract_error)?;
+
+            // Виртуальные доли Morpho: assets = shares * (total + 1) / (totalShares + 1e6)
+            let debt = borrow_shares as f64 * (total_borrow_assets as f64 + 1.0) / (total_borrow_shares as f64 + 1e6);
+            // Цена оракула масштабирована на 1e36 и переводит залог в единицы долга
+            let collateral_in_loan = collateral as f64 * units(price, 36);
+            let weighted = collateral_in_loan * units(lltv, 18);
+
+            Ok(HealthReading {
+                weighted_collateral: weighted,
+                debt,
+                health_factor: if debt == 0.0 { f64::INFINITY } else { weighted / debt },
+            })
+        }
+    }
+}
+
+/// Мониторинг health factor позиций в Aave, Compound и Morpho с эскалацией алертов
+pub struct HealthMonitor<M> {
+    provider: Arc<M>,
+    bus: AlertBus,
+    config: HealthConfig,
+    positions: HashMap<(Address, LendingMarket), TrackedPosition>,
+    /// Неудачные чтения позиций
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> HealthMonitor<M> {
+    pub fn new(provider: Arc<M>, bus: AlertBus, config: HealthConfig) -> Self {
+        Self {
+            provider,
+            bus,
+            config,
+            positions: HashMap::new(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn watch(&mut self, account: Address, market: LendingMarket) {
+        self.positions.entry((account, market)).or_insert(TrackedPosition {
+            history: VecDeque::new(),
+            last_level: None,
+        });
+    }
+
+    pub fn unwatch(&mut self, account: Address) {
+        self.positions.retain(|(a, _), _| *a != account);
+    }
+
+    pub fn watched(&self) -> Vec<(Address, LendingMarket)> {
+        self.positions.keys().cloned().collect()
+    }
+
+    /// Текущее состояние позиции в протоколе
+    pub async fn read(&self, account: Address, market: &LendingMarket, block: u64) -> Result<HealthReading, PortfolioError> {
+        read_health(self.provider.clone(), account, market, block).await
+    }
+
+    async fn suggest(&self, account: Address, market: &LendingMarket, reading: &HealthReading) -> Option<DeleverageSuggestion> {
+        let target = self.config.deleverage_target?;
+        let repay_amount = repay_to_target(reading, target);
+        if repay_amount == 0.0 {
+            return None;
+        }
+
+        let (to, calldata) = match market {
+            LendingMarket::AaveV3 { pool, oracle: Some(oracle), debt_asset: Some(asset) } => {
+                let price = AaveOracle::new(*oracle, self.provider.clone()).get_asset_price(*asset).call().await.ok()?;
+                let decimals = Erc20::new(*asset, self.provider.clone()).decimals().call().await.ok()?;
+                let tokens = repay_amount / units(price, 8);
+                let call = RepayCall {
+                    asset: *asset,
+                    amount: ethers::utils::parse_units(format!("{:.*}", decimals as usize, tokens), decimals as u32)
+                        .ok()?
+                        .into(),
+                    // Переменная ставка
+                    interest_rate_mode: U256::from(2),
+                    on_behalf_of: account,
+                };
+                (Some(*pool), Some(Bytes::from(call.encode())))
+            }
+            LendingMarket::CompoundV3 { comet } => {
+                let contract = Comet::new(*comet, self.provider.clone());
+                let base = contract.base_token().call().await.ok()?;
+                let scale = contract.base_scale().call().await.ok()?;
+                let call = SupplyCall {
+                    asset: base,
+                    amount: U256::from((repay_amount * units(scale, 0)) as u128),
+                };
+                (Some(*comet), Some(Bytes::from(call.encode())))
+            }
+            _ => (None, None),
+        };
+
+        Some(DeleverageSuggestion {
+            target_health_factor: target,
+            repay_amount,
+            to,
+            calldata,
+        })
+    }
+
+    /// Снимает health factor всех позиций; алерт публикуется только при росте уровня.
+    /// Позиция, которую не удалось прочитать, пропускается до следующего опроса
+    pub async fn poll(&mut self, block: u64, timestamp: u64) -> Vec<HealthAlert> {
+        let keys: Vec<(Address, LendingMarket)> = self.positions.keys().cloned().collect();
+        let mut alerts = Vec::new();
+
+        for (account, market) in keys {
+            let reading = match self.read(account, &market, block).await {
+                Ok(reading) => reading,
+                Err(e) => {
+                    self.errors.record(format!("{:?} in {:?}: {}", account, market, e));
+                    continue;
+                }
+            };
+            let level = self.config.level(reading.health_factor);
+
+            let tracked = self.positions.get_mut(&(account, market.clone())).expect("position is tracked");
+            if reading.health_factor.is_finite() {
+                tracked.history.push_back((timestamp, reading.health_factor));
+                while tracked.history.len() > self.config.trend_window {
+                    tracked.history.pop_front();
+                }
+            }
+            let slope = trend(&tracked.history);
+            let escalated = level.is_some() && level > tracked.last_level;
+            tracked.last_level = level;
+
+            if let (true, Some(level)) = (escalated, level) {
+                let suggestion = self.suggest(account, &market, &reading).await;
+                alerts.push(HealthAlert {
+                    account,
+                    market,
+                    block,
+                    seconds_to_liquidation: time_to_liquidation(reading.health_factor, slope),
+                    reading,
+                    level,
+                    trend_per_second: slope,
+                    suggestion,
+                });
+            }
+        }
+
+        for alert in &alerts {
+            let eta = match alert.seconds_to_liquidation {
+                Some(s) => format!(", ~{:.1}h to liquidation", s / 3600.0),
+                None => String::new(),
+            };
+            self.bus.publish(
+                BusAlert::new(
+                    "lending",
+                    "health_factor",
+                    alert.level,
+                    format!("{:?}", alert.account),
+                    format!("Health factor {:.3}{}", alert.reading.health_factor, eta),
+                )
+                .with_payload(json!(alert)),
+            );
+        }
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_and_deleverage() {
+        // Health factor падает на 0.01 в час
+        let history: VecDeque<(u64, f64)> = (0..10).map(|h| (h * 3600, 1.3 - 0.01 * h as f64)).collect();
+        let slope = trend(&history);
+        assert!((slope * 3600.0 + 0.01).abs() < 1e-9);
+        let eta = time_to_liquidation(1.21, slope).unwrap();
+        assert!((eta / 3600.0 - 21.0).abs() < 1e-6);
+        assert_eq!(time_to_liquidation(1.5, 0.001), None);
+
+        let reading = HealthReading { weighted_collateral: 1100.0, debt: 1000.0, health_factor: 1.1 };
+        assert!((repay_to_target(&reading, 1.5) - 266.666).abs() < 1e-2);
+
+        let config = HealthConfig::default();
+        assert_eq!(config.level(1.2), Some(AlertLevel::Medium));
+        assert_eq!(config.level(1.02), Some(AlertLevel::Critical));
+        assert_eq!(config.level(2.0), None);
+    }
+}
@@ -0,0 +1,340 @@
+use super::sources::Erc20;
+use super::PortfolioError;
+use ethers::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+abigen!(
+    UniswapV2Pair,
+    r#"[
+        function balanceOf(address owner) external view returns (uint256)
+        function totalSupply() external view returns (uint256)
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    NonfungiblePositionManager,
+    r#"[
+        function balanceOf(address owner) external view returns (uint256)
+        function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256)
+        function positions(uint256 tokenId) external view returns (uint96 nonce, address operator, address token0, address token1, uint24 fee, int24 tickLower, int24 tickUpper, uint128 liquidity, uint256 feeGrowthInside0LastX128, uint256 feeGrowthInside1LastX128, uint128 tokensOwed0, uint128 tokensOwed1)
+    ]"#
+);
+
+abigen!(
+    UniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function feeGrowthGlobal0X128() external view returns (uint256)
+        function feeGrowthGlobal1X128() external view returns (uint256)
+        function ticks(int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+    ]"#
+);
+
+/// Источники LP-позиций Uniswap в сети
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UniswapSources {
+    /// Пары V2, в которых ищутся LP-токены
+    #[serde(default)]
+    pub v2_pairs: Vec<Address>,
+    #[serde(default)]
+    pub v3_position_manager: Option<Address>,
+    #[serde(default)]
+    pub v3_factory: Option<Address>,
+}
+
+/// Сценарий изменения цены token0 относительно token1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceScenario {
+    pub name: String,
+    /// Множитель цены: 0.5 — падение вдвое, 2.0 — рост вдвое
+    pub price_ratio: f64,
+}
+
+impl PriceScenario {
+    pub fn defaults() -> Vec<PriceScenario> {
+        [("-50%", 0.5), ("-20%", 0.8), ("+20%", 1.2), ("+100%", 2.0)]
+            .into_iter()
+            .map(|(name, price_ratio)| PriceScenario { name: name.into(), price_ratio })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "protocol", rename_all = "snake_case")]
+pub enum LpKind {
+    UniswapV2 {
+        #[schema(value_type = String)]
+        lp_balance: U256,
+        #[schema(value_type = String)]
+        total_supply: U256,
+    },
+    UniswapV3 {
+        #[schema(value_type = String)]
+        token_id: U256,
+        fee: u32,
+        tick_lower: i32,
+        tick_upper: i32,
+        current_tick: i32,
+        liquidity: u128,
+    },
+}
+
+/// Оценка непостоянных потерь в одном сценарии
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IlEstimate {
+    pub scenario: String,
+    pub price_ratio: f64,
+    /// Потери относительно простого удержания активов (0.05 — минус 5%)
+    pub impermanent_loss: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LpPosition {
+    pub chain_id: u64,
+    #[schema(value_type = String)]
+    pub pool: Address,
+    #[schema(value_type = String)]
+    pub token0: Address,
+    #[schema(value_type = String)]
+    pub token1: Address,
+    #[serde(flatten)]
+    pub kind: LpKind,
+    /// Доля позиции в резервах, в целых единицах токенов
+    pub amount0: f64,
+    pub amount1: f64,
+    /// Несобранные комиссии (только V3; у V2 комиссии уже внутри резервов)
+    pub fees0: f64,
+    pub fees1: f64,
+    pub in_range: bool,
+    pub il: Vec<IlEstimate>,
+}
+
+impl LpPosition {
+    pub fn worst_il(&self) -> f64 {
+        self.il.iter().map(|e| e.impermanent_loss).fold(0.0, f64::max)
+    }
+}
+
+/// Непостоянные потери V2 (полный диапазон) при изменении цены в `r` раз
+pub fn il_v2(r: f64) -> f64 {
+    1.0 - 2.0 * r.sqrt() / (1.0 + r)
+}
+
+fn sqrt_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+/// Количества токенов V3-позиции (в сырых единицах) при корне цены `sqrt_p`
+pub fn v3_amounts(liquidity: f64, sqrt_p: f64, tick_lower: i32, tick_upper: i32) -> (f64, f64) {
+    let (sa, sb) = (sqrt_at_tick(tick_lower), sqrt_at_tick(tick_upper));
+    if sqrt_p <= sa {
+        (liquidity * (sb - sa) / (sa * sb), 0.0)
+    } else if sqrt_p < sb {
+        (liquidity * (sb - sqrt_p) / (sqrt_p * sb), liquidity * (sqrt_p - sa))
+    } else {
+        (0.0, liquidity * (sb - sa))
+    }
+}
+
+/// Непостоянные потери V3: стоимость позиции после изменения цены против удержания исходных количеств
+pub fn il_v3(sqrt_p: f64, tick_lower: i32, tick_upper: i32, r: f64) -> f64 {
+    // Ликвидность сокращается в отношении, поэтому берём единичную
+    let (a0, a1) = v3_amounts(1.0, sqrt_p, tick_lower, tick_upper);
+    let new_sqrt = sqrt_p * r.sqrt();
+    let new_price = new_sqrt * new_sqrt;
+    let (b0, b1) = v3_amounts(1.0, new_sqrt, tick_lower, tick_upper);
+
+    let hold = a0 * new_price + a1;
+    if hold <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - (b0 * new_price + b1) / hold).max(0.0)
+}
+
+/// Комиссии, накопленные с последнего сбора: `liquidity * Δ(feeGrowthInside) / 2^128`
+pub fn uncollected_fees(liquidity: u128, growth_inside: U256, growth_inside_last: U256, owed: u128) -> U256 {
+    let delta = growth_inside.overflowing_sub(growth_inside_last).0;
+    let accrued = delta.full_mul(U256::from(liquidity)) >> 128;
+    U256::try_from(accrued).unwrap_or(U256::MAX).saturating_add(U256::from(owed))
+}
+
+/// feeGrowthInside по правилам пула (вычитания по модулю 2^256)
+fn growth_inside(global: U256, outside_lower: U256, outside_upper: U256, tick: i32, lower: i32, upper: i32) -> U256 {
+    let below = if tick >= lower { outside_lower } else { global.overflowing_sub(outside_lower).0 };
+    let above = if tick < upper { outside_upper } else { global.overflowing_sub(outside_upper).0 };
+    global.overflowing_sub(below).0.overflowing_sub(above).0
+}
+
+fn contract_error(e: impl std::fmt::Display) -> PortfolioError {
+    PortfolioError::ContractError(e.to_string())
+}
+
+fn to_units(raw: f64, decimals: u8) -> f64 {
+    raw / 10f64.powi(decimals as i32)
+}
+
+fn u256_units(raw: U256, decimals: u8) -> f64 {
+    ethers::utils::format_units(raw, decimals as u32)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0)
+}
+
+async fn decimals<M: Middleware + 'static>(provider: &Arc<M>, token: Address, block: BlockId) -> Result<u8, PortfolioError> {
+    Erc20::new(token, provider.clone()).decimals().block(block).call().await.map_err(contract_error)
+}
+
+/// Все LP-позиции адреса в сети с оценкой потерь по сценариям
+pub async fn fetch_lp_positions<M: Middleware + 'static>(
+    provider: Arc<M>,
+    chain_id: u64,
+    sources: &UniswapSources,
+    owner: Address,
+    block: u64,
+    scenarios: &[PriceScenario],
+) -> Result<Vec<LpPosition>, PortfolioError> {
+    let block_id = BlockId::Number(block.into());
+    let mut positions = Vec::new();
+
+    for pair_address in &sources.v2_pairs {
+        let pair = UniswapV2Pair::new(*pair_address, provider.clone());
+        let lp_balance = pair.balance_of(owner).block(block_id).call().await.map_err(contract_error)?;
+        if lp_balance.is_zero() {
+            continue;
+        }
+        let total_supply = pair.total_supply().block(block_id).call().await.map_err(contract_error)?;
+        let (reserve0, reserve1, _) = pair.get_reserves().block(block_id).call().await.map_err(contract_error)?;
+        let token0 = pair.token_0().block(block_id).call().await.map_err(contract_error)?;
+        let token1 = pair.token_1().block(block_id).call().await.map_err(contract_error)?;
+        let (d0, d1) = (decimals(&provider, token0, block_id).await?, decimals(&provider, token1, block_id).await?);
+
+        let share = u256_units(lp_balance, 18) / u256_units(total_supply, 18).max(f64::MIN_POSITIVE);
+        positions.push(LpPosition {
+            chain_id,
+            pool: *pair_address,
+            token0,
+            token1,
+            kind: LpKind::UniswapV2 { lp_balance, total_supply },
+            amount0: to_units(reserve0 as f64, d0) * share,
+            amount1: to_units(reserve1 as f64, d1) * share,
+            fees0: 0.0,
+            fees1: 0.0,
+            in_range: true,
+            il: scenarios
+                .iter()
+                .map(|s| IlEstimate { scenario: s.name.clone(), price_ratio: s.price_ratio, impermanent_loss: il_v2(s.price_ratio) })
+                .collect(),
+        });
+    }
+
+    let (Some(manager_address), Some(factory_address)) = (sources.v3_position_manager, sources.v3_factory) else {
+        return Ok(positions);
+    };
+    let manager = NonfungiblePositionManager::new(manager_address, provider.clone());
+    let factory = UniswapV3Factory::new(factory_address, provider.clone());
+
+    let count = manager.balance_of(owner).block(block_id).call().await.map_err(contract_error)?;
+    for index in 0..count.as_u64() {
+        let token_id = manager
+            .token_of_owner_by_index(owner, index.into())
+            .block(block_id)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        let (_, _, token0, token1, fee, tick_lower, tick_upper, liquidity, last0, last1, owed0, owed1) =
+            manager.positions(token_id).block(block_id).call().await.map_err(contract_error)?;
+        // Закрытые позиции остаются NFT с нулевой ликвидностью
+        if liquidity == 0 && owed0 == 0 && owed1 == 0 {
+            continue;
+        }
+
+        let pool_address = factory.get_pool(token0, token1, fee).block(block_id).call().await.map_err(contract_error)?;
+        let pool = UniswapV3Pool::new(pool_address, provider.clone());
+        let (sqrt_price_x96, tick, ..) = pool.slot_0().block(block_id).call().await.map_err(contract_error)?;
+        let global0 = pool.fee_growth_global_0x128().block(block_id).call().await.map_err(contract_error)?;
+        let global1 = pool.fee_growth_global_1x128().block(block_id).call().await.map_err(contract_error)?;
+        let (_, _, lower0, lower1, ..) = pool.ticks(tick_lower).block(block_id).call().await.map_err(contract_error)?;
+        let (_, _, upper0, upper1, ..) = pool.ticks(tick_upper).block(block_id).call().await.map_err(contract_error)?;
+        let (d0, d1) = (decimals(&provider, token0, block_id).await?, decimals(&provider, token1, block_id).await?);
+
+        let sqrt_p = u256_units(sqrt_price_x96, 0) / 2f64.powi(96);
+        let (raw0, raw1) = v3_amounts(liquidity as f64, sqrt_p, tick_lower, tick_upper);
+        let fees0 = uncollected_fees(liquidity, growth_inside(global0, lower0, upper0, tick, tick_lower, tick_upper), last0, owed0);
+        let fees1 = uncollected_fees(liquidity, growth_inside(global1, lower1, upper1, tick, tick_lower, tick_upper), last1, owed1);
+
+        positions.push(LpPosition {
+            chain_id,
+            pool: pool_address,
+            token0,
+            token1,
+            kind: LpKind::UniswapV3 { token_id, fee, tick_lower, tick_upper, current_tick: tick, liquidity },
+            amount0: to_units(raw0, d0),
+            amount1: to_units(raw1, d1),
+            fees0: u256_units(fees0, d0),
+            fees1: u256_units(fees1, d1),
+            in_range: tick >= tick_lower && tick < tick_upper,
+            il: scenarios
+                .iter()
+                .map(|s| IlEstimate {
+                    scenario: s.name.clone(),
+                    price_ratio: s.price_ratio,
+                    impermanent_loss: il_v3(sqrt_p, tick_lower, tick_upper, s.price_ratio),
+                })
+                .collect(),
+        });
+    }
+
+    Ok(positions)
+}
+
+/// Компонент `lp_risk` (0.0-1.0): средние по позициям худшие потери; позиция вне диапазона — не меньше 0.5
+pub fn lp_risk(positions: &[LpPosition]) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = positions
+        .iter()
+        .map(|p| if p.in_range { p.worst_il() } else { p.worst_il().max(0.5) })
+        .sum();
+    (total / positions.len() as f64).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impermanent_loss() {
+        // Классические значения V2: x2 цены — 5.7%, x4 — 20%
+        assert!((il_v2(2.0) - 0.0572).abs() < 1e-3);
+        assert!((il_v2(4.0) - 0.2).abs() < 1e-9);
+        assert_eq!(il_v2(1.0), 0.0);
+
+        // Узкий диапазон вокруг текущей цены теряет больше полного
+        let sqrt_p = sqrt_at_tick(0);
+        assert!(il_v3(sqrt_p, -1000, 1000, 2.0) > il_v2(2.0));
+        // Очень широкий диапазон приближается к V2
+        assert!((il_v3(sqrt_p, -800_000, 800_000, 2.0) - il_v2(2.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_uncollected_fees_wrap_around() {
+        let q128 = U256::one() << 128;
+        // Рост счётчика через переполнение: last близко к MAX, текущее значение — маленькое
+        let last = U256::MAX - q128 + U256::one();
+        let fees = uncollected_fees(10, q128 * 2, last, 5);
+        assert_eq!(fees, U256::from(35));
+    }
+}
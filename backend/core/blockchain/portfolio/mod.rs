@@ -0,0 +1,354 @@
+pub mod attestation;
+pub mod digest;
+pub mod governance;
+pub mod lending;
+pub mod lp;
+pub mod nav;
+pub mod sources;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ethers::prelude::*;
+use mevdetector::chain::{adapter_for, SharedChainAdapter};
+use mevdetector::shutdown::ShutdownSignal;
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use lp::{IlEstimate, LpKind, LpPosition, PriceScenario};
+use nav::{DilutionEvent, NavTracker, PositionNav, RestakingNav, SharePricePoint};
+use sources::ChainSources;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use utoipa::{OpenApi, ToSchema};
+
+#[derive(Debug, Error)]
+pub enum PortfolioError {
+    #[error("Contract error: {0}")]
+    ContractError(String),
+
+    #[error("Chain {0} is not configured")]
+    UnknownChain(u64),
+
+    #[error("Address {0:?} is not tracked")]
+    NotTracked(Address),
+}
+
+/// Вид позиции
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PositionKind {
+    Native,
+    Erc20 {
+        #[schema(value_type = String)]
+        token: Address,
+        symbol: String,
+    },
+    /// Токен ликвидного стейкинга
+    Staking {
+        protocol: String,
+        #[schema(value_type = String)]
+        token: Address,
+        symbol: String,
+    },
+    /// Доли в стратегии EigenLayer; `amount` — в базовом активе
+    EigenShares {
+        #[schema(value_type = String)]
+        strategy: Address,
+        #[schema(value_type = String)]
+        underlying: Address,
+        #[schema(value_type = String)]
+        shares: U256,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Position {
+    pub chain_id: u64,
+    #[serde(flatten)]
+    pub kind: PositionKind,
+    /// Сырое значение в минимальных единицах
+    #[schema(value_type = String)]
+    pub raw: U256,
+    /// В целых единицах актива
+    pub amount: f64,
+}
+
+/// Позиции адреса во всех сетях
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PortfolioSnapshot {
+    #[schema(value_type = String)]
+    pub address: Address,
+    /// chain_id -> блок, на котором сняты позиции
+    pub blocks: HashMap<u64, u64>,
+    pub positions: Vec<Position>,
+    #[serde(default)]
+    pub lp_positions: Vec<LpPosition>,
+    pub updated_at: u64,
+}
+
+impl PortfolioSnapshot {
+    /// Суммарно застейкано и зарестейкано (в ETH-эквиваленте базовых активов)
+    pub fn staked_amount(&self) -> f64 {
+        self.positions
+            .iter()
+            .filter(|p| matches!(p.kind, PositionKind::Staking { .. } | PositionKind::EigenShares { .. }))
+            .map(|p| p.amount)
+            .sum()
+    }
+
+    /// Базовые активы стратегий EigenLayer — вход для оценки концентрации
+    pub fn restaked_assets(&self) -> Vec<Address> {
+        self.positions
+            .iter()
+            .filter_map(|p| match &p.kind {
+                PositionKind::EigenShares { underlying, .. } => Some(*underlying),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Подключение к одной сети
+pub struct ChainClient<M> {
+    pub provider: Arc<M>,
+    pub sources: ChainSources,
+    pub chain: SharedChainAdapter,
+}
+
+impl<M> ChainClient<M> {
+    /// Правила сети по `sources.chain_id`
+    pub fn new(provider: Arc<M>, sources: ChainSources) -> Result<Self, PortfolioError> {
+        let chain = adapter_for(sources.chain_id).ok_or(PortfolioError::UnknownChain(sources.chain_id))?;
+        Ok(Self { provider, sources, chain })
+    }
+}
+
+/// Отслеживание портфелей набора адресов по всем настроенным сетям
+pub struct PortfolioTracker<M> {
+    chains: Vec<ChainClient<M>>,
+    tracked: RwLock<HashSet<Address>>,
+    snapshots: RwLock<HashMap<Address, PortfolioSnapshot>>,
+    scenarios: Vec<PriceScenario>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> PortfolioTracker<M> {
+    pub fn new(chains: Vec<ChainClient<M>>) -> Self {
+        Self {
+            chains,
+            tracked: RwLock::new(HashSet::new()),
+            snapshots: RwLock::new(HashMap::new()),
+            scenarios: PriceScenario::defaults(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Сценарии цены для оценки непостоянных потерь LP-позиций
+    pub fn with_scenarios(mut self, scenarios: Vec<PriceScenario>) -> Self {
+        self.scenarios = scenarios;
+        self
+    }
+
+    /// Неудачные обновления портфелей на новых блоках
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn track(&self, address: Address) {
+        self.tracked.write().unwrap().insert(address);
+    }
+
+    pub fn untrack(&self, address: Address) {
+        self.tracked.write().unwrap().remove(&address);
+        self.snapshots.write().unwrap().remove(&address);
+    }
+
+    pub fn tracked(&self) -> Vec<Address> {
+        self.tracked.read().unwrap().iter().copied().collect()
+    }
+
+    /// Последний снятый снимок без обращения к сети
+    pub fn snapshot(&self, address: Address) -> Option<PortfolioSnapshot> {
+        self.snapshots.read().unwrap().get(&address).cloned()
+    }
+
+    /// Обновляет позиции адреса в одной сети на указанном блоке
+    async fn refresh_chain(&self, chain: &ChainClient<M>, address: Address, block: u64) -> Result<(), PortfolioError> {
+        let positions = sources::fetch_positions(chain.provider.clone(), &chain.sources, address, block).await?;
+        let chain_id = chain.sources.chain_id;
+        let lp_positions = match &chain.sources.uniswap {
+            Some(uniswap) => {
+                lp::fetch_lp_positions(chain.provider.clone(), chain_id, uniswap, address, block, &self.scenarios).await?
+            }
+            None => Vec::new(),
+        };
+
+        let mut snapshots = self.snapshots.write().unwrap();
+        let snapshot = snapshots.entry(address).or_insert_with(|| PortfolioSnapshot {
+            address,
+            blocks: HashMap::new(),
+            positions: Vec::new(),
+            lp_positions: Vec::new(),
+            updated_at: 0,
+        });
+        snapshot.positions.retain(|p| p.chain_id != chain_id);
+        snapshot.positions.extend(positions);
+        snapshot.lp_positions.retain(|p| p.chain_id != chain_id);
+        snapshot.lp_positions.extend(lp_positions);
+        snapshot.blocks.insert(chain_id, block);
+        snapshot.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Ok(())
+    }
+
+    /// Обновление по запросу: все сети на последнем блоке
+    pub async fn refresh(&self, address: Address) -> Result<PortfolioSnapshot, PortfolioError> {
+        if !self.tracked.read().unwrap().contains(&address) {
+            return Err(PortfolioError::NotTracked(address));
+        }
+        for chain in &self.chains {
+            let block = chain
+                .provider
+                .get_block_number()
+                .await
+                .map_err(|e| PortfolioError::ContractError(e.to_string()))?
+                .as_u64();
+            self.refresh_chain(chain, address, block).await?;
+        }
+        Ok(self.snapshot(address).unwrap())
+    }
+
+    /// Обновляет все адреса в сети на новом блоке; ошибки по отдельным адресам не прерывают проход
+    pub async fn on_block(&self, chain_id: u64, block: u64) -> Result<usize, PortfolioError> {
+        let chain = self
+            .chains
+            .iter()
+            .find(|c| c.sources.chain_id == chain_id)
+            .ok_or(PortfolioError::UnknownChain(chain_id))?;
+
+        let mut refreshed = 0;
+        for address in self.tracked() {
+            match self.refresh_chain(chain, address, block).await {
+                Ok(()) => refreshed += 1,
+                Err(e) => self.errors.record(format!("portfolio refresh for {:?} on chain {} failed: {}", address, chain_id, e)),
+            }
+        }
+        Ok(refreshed)
+    }
+
+    /// Опрашивает сети и обновляет портфели на каждом новом блоке до сигнала остановки.
+    /// Сеть опрашивается не чаще своего времени блока
+    pub async fn run(&self, poll_interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut last_blocks: HashMap<u64, u64> = HashMap::new();
+        let mut last_polls: HashMap<u64, Instant> = HashMap::new();
+        let mut ticker = tokio::time::interval(poll_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            for chain in &self.chains {
+                let chain_id = chain.sources.chain_id;
+                if last_polls.get(&chain_id).is_some_and(|at| at.elapsed() < chain.chain.block_time()) {
+                    continue;
+                }
+                last_polls.insert(chain_id, Instant::now());
+                let Ok(block) = chain.provider.get_block_number().await else {
+                    continue;
+                };
+                let block = block.as_u64();
+                if last_blocks.get(&chain_id) != Some(&block) {
+                    let _ = self.on_block(chain_id, block).await;
+                    last_blocks.insert(chain_id, block);
+                }
+            }
+        }
+    }
+}
+
+impl IntoResponse for PortfolioError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            PortfolioError::NotTracked(_) | PortfolioError::UnknownChain(_) => StatusCode::NOT_FOUND,
+            PortfolioError::ContractError(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, Json(serde_json::json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/portfolio/{address}",
+    tag = "portfolio",
+    params(("address" = String, Path, description = "Tracked address")),
+    responses(
+        (status = 200, description = "Cached snapshot, refreshed on first request", body = PortfolioSnapshot),
+        (status = 404, description = "Address or chain is not configured"),
+        (status = 502, description = "Chain read failed"),
+    )
+)]
+async fn get_portfolio<M: Middleware + 'static>(
+    State(tracker): State<Arc<PortfolioTracker<M>>>,
+    Path(address): Path<Address>,
+) -> Result<Json<PortfolioSnapshot>, PortfolioError> {
+    match tracker.snapshot(address) {
+        Some(snapshot) => Ok(Json(snapshot)),
+        None => Ok(Json(tracker.refresh(address).await?)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/portfolio/{address}/refresh",
+    tag = "portfolio",
+    params(("address" = String, Path, description = "Tracked address")),
+    responses(
+        (status = 200, description = "Fresh snapshot read from every chain", body = PortfolioSnapshot),
+        (status = 404, description = "Address or chain is not configured"),
+        (status = 502, description = "Chain read failed"),
+    )
+)]
+async fn refresh_portfolio<M: Middleware + 'static>(
+    State(tracker): State<Arc<PortfolioTracker<M>>>,
+    Path(address): Path<Address>,
+) -> Result<Json<PortfolioSnapshot>, PortfolioError> {
+    Ok(Json(tracker.refresh(address).await?))
+}
+
+/// Спецификация маршрутов портфеля; объединяется с `mevdetector::openapi::merged`
+#[derive(OpenApi)]
+#[openapi(
+    paths(get_portfolio, refresh_portfolio, nav::get_nav, nav::get_share_prices),
+    components(schemas(
+        PortfolioSnapshot,
+        Position,
+        PositionKind,
+        LpPosition,
+        LpKind,
+        IlEstimate,
+        RestakingNav,
+        PositionNav,
+        SharePricePoint,
+        DilutionEvent,
+    )),
+    tags((name = "portfolio", description = "Positions of tracked addresses across chains"))
+)]
+pub struct PortfolioApi;
+
+/// Маршруты портфеля; с `nav` — и NAV рестейкинга
+pub fn router<M: Middleware + 'static>(tracker: Arc<PortfolioTracker<M>>, nav: Option<Arc<NavTracker<M>>>) -> Router {
+    let routes = Router::new()
+        .route("/portfolio/:address", get(get_portfolio::<M>))
+        .route("/portfolio/:address/refresh", post(refresh_portfolio::<M>))
+        .with_state(tracker);
+    match nav {
+        Some(nav) => routes.merge(nav::router(nav)),
+        None => routes,
+    }
+}
@@ -0,0 +1,450 @@
+use super::sources::{EigenSources, Strategy};
+use super::{PortfolioError, PortfolioTracker, PositionKind};
+use async_trait::async_trait;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use ethers::prelude::*;
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use mevdetector::digest::{DigestRow, DigestSection, DigestSource, DigestTenant};
+use mevdetector::shutdown::ShutdownSignal;
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Падение цены доли больше этого — событие размытия (слэшинг, убыток стратегии)
+const DEFAULT_DILUTION_BPS: u64 = 10;
+/// Точек истории цены на стратегию
+const MAX_PRICE_POINTS: usize = 10_000;
+/// Событий размытия в памяти для дайджестов; старые вытесняются
+const MAX_DILUTIONS: usize = 1_000;
+
+/// Цена доли стратегии на блоке
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SharePricePoint {
+    pub block: u64,
+    pub timestamp: u64,
+    /// Базового актива за одну долю
+    pub price: f64,
+    #[schema(value_type = String)]
+    pub total_shares: U256,
+}
+
+/// Падение цены доли между двумя наблюдениями
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DilutionEvent {
+    #[schema(value_type = String)]
+    pub strategy: Address,
+    pub block: u64,
+    pub timestamp: u64,
+    pub price_before: f64,
+    pub price_after: f64,
+    pub drop_bps: u64,
+}
+
+/// Учёт позиции по средней цене входа; доли добавляются и списываются по цене наблюдения
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PositionBook {
+    pub shares: f64,
+    /// Стоимость входа в базовом активе
+    pub cost_basis: f64,
+    pub realized_pnl: f64,
+}
+
+impl PositionBook {
+    pub fn observe(&mut self, shares: f64, price: f64) {
+        if shares > self.shares {
+            self.cost_basis += (shares - self.shares) * price;
+        } else if shares < self.shares && self.shares > 0.0 {
+            let sold = self.shares - shares;
+            let average = self.cost_basis / self.shares;
+            self.realized_pnl += sold * (price - average);
+            self.cost_basis -= sold * average;
+        }
+        self.shares = shares;
+    }
+
+    pub fn nav(&self, price: f64) -> f64 {
+        self.shares * price
+    }
+
+    pub fn unrealized_pnl(&self, price: f64) -> f64 {
+        self.nav(price) - self.cost_basis
+    }
+}
+
+/// NAV одной позиции рестейкинга
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PositionNav {
+    pub chain_id: u64,
+    #[schema(value_type = String)]
+    pub strategy: Address,
+    #[schema(value_type = String)]
+    pub underlying: Address,
+    pub shares: f64,
+    pub share_price: f64,
+    /// В базовом активе
+    pub nav: f64,
+    pub cost_basis: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestakingNav {
+    #[schema(value_type = String)]
+    pub staker: Address,
+    pub positions: Vec<PositionNav>,
+    /// Сумма по позициям в ETH-эквиваленте базовых активов, как `staked_amount`
+    pub total_nav: f64,
+    pub total_unrealized_pnl: f64,
+    pub total_realized_pnl: f64,
+}
+
+type BookKey = (Address, u64, Address);
+
+/// Цены долей стратегий EigenLayer во времени и NAV/P&L позиций отслеживаемых стейкеров
+pub struct NavTracker<M> {
+    tracker: Arc<PortfolioTracker<M>>,
+    provider: Arc<M>,
+    chain_id: u64,
+    eigen: EigenSources,
+    dilution_bps: u64,
+    prices: RwLock<HashMap<Address, Vec<SharePricePoint>>>,
+    books: RwLock<HashMap<BookKey, PositionBook>>,
+    dilutions: RwLock<Vec<DilutionEvent>>,
+    /// Тенант -> реализованный P&L на момент прошлого дайджеста
+    digested: Mutex<HashMap<String, HashMap<BookKey, f64>>>,
+    /// Неудачные чтения цен долей
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> NavTracker<M> {
+    pub fn new(tracker: Arc<PortfolioTracker<M>>, provider: Arc<M>, chain_id: u64, eigen: EigenSources) -> Self {
+        Self {
+            tracker,
+            provider,
+            chain_id,
+            eigen,
+            dilution_bps: DEFAULT_DILUTION_BPS,
+            prices: RwLock::new(HashMap::new()),
+            books: RwLock::new(HashMap::new()),
+            dilutions: RwLock::new(Vec::new()),
+            digested: Mutex::new(HashMap::new()),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// NAV рестейкинга по сети трекера с источниками EigenLayer; EigenLayer живёт в одной сети,
+    /// поэтому берётся первая такая
+    pub fn from_tracker(tracker: Arc<PortfolioTracker<M>>) -> Option<Self> {
+        let chain = tracker.chains.iter().find(|c| c.sources.eigen.is_some())?;
+        let (provider, chain_id, eigen) = (chain.provider.clone(), chain.sources.chain_id, chain.sources.eigen.clone()?);
+        Some(Self::new(tracker, provider, chain_id, eigen))
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn with_dilution_threshold(mut self, bps: u64) -> Self {
+        self.dilution_bps = bps;
+        self
+    }
+
+    /// Доли EigenPod нативного рестейкинга всегда 1:1 к ETH
+    async fn share_price(&self, strategy: Address, block: BlockId) -> Result<(f64, U256), PortfolioError> {
+        if strategy == self.eigen.eigen_pod_manager {
+            return Ok((1.0, U256::zero()));
+        }
+        let contract = Strategy::new(strategy, self.provider.clone());
+        let contract_err = |e: ContractError<M>| PortfolioError::ContractError(e.to_string());
+        let one = U256::exp10(18);
+        let underlying = contract.shares_to_underlying_view(one).block(block).call().await.map_err(contract_err)?;
+        let total_shares = contract.total_shares().block(block).call().await.map_err(contract_err)?;
+        let price = ethers::utils::format_units(underlying, 18).ok().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+        Ok((price, total_shares))
+    }
+
+    pub fn price(&self, strategy: Address) -> Option<f64> {
+        self.prices.read().unwrap().get(&strategy).and_then(|h| h.last()).map(|p| p.price)
+    }
+
+    pub fn price_history(&self, strategy: Address) -> Vec<SharePricePoint> {
+        self.prices.read().unwrap().get(&strategy).cloned().unwrap_or_default()
+    }
+
+    pub fn dilutions(&self, since: u64) -> Vec<DilutionEvent> {
+        self.dilutions.read().unwrap().iter().filter(|d| d.timestamp >= since).cloned().collect()
+    }
+
+    /// Снимает цены долей на блоке и обновляет учёт позиций по снимкам портфеля.
+    /// Возвращает алерты о размытии долей для стейкеров с позицией в стратегии; стратегия,
+    /// цену которой не удалось прочитать, пропускается до следующего блока
+    pub async fn on_block(&self, block: u64) -> Vec<BusAlert> {
+        let at = BlockId::Number(block.into());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut events = Vec::new();
+        for strategy in self.eigen.strategies.iter().chain([&self.eigen.eigen_pod_manager]) {
+            let (price, total_shares) = match self.share_price(*strategy, at).await {
+                Ok(price) => price,
+                Err(e) => {
+                    self.errors.record(format!("share price of {:?} at block {}: {}", strategy, block, e));
+                    continue;
+                }
+            };
+            let mut prices = self.prices.write().unwrap();
+            let history = prices.entry(*strategy).or_default();
+            if let Some(last) = history.last().filter(|p| p.price > 0.0 && price < p.price) {
+                let drop_bps = ((last.price - price) / last.price * 10_000.0) as u64;
+                if drop_bps >= self.dilution_bps {
+                    events.push(DilutionEvent {
+                        strategy: *strategy,
+                        block,
+                        timestamp: now,
+                        price_before: last.price,
+                        price_after: price,
+                        drop_bps,
+                    });
+                }
+            }
+            history.push(SharePricePoint { block, timestamp: now, price, total_shares });
+            if history.len() > MAX_PRICE_POINTS {
+                history.remove(0);
+            }
+        }
+        self.update_books();
+        let mut dilutions = self.dilutions.write().unwrap();
+        dilutions.extend(events.iter().cloned());
+        let overflow = dilutions.len().saturating_sub(MAX_DILUTIONS);
+        dilutions.drain(..overflow);
+        drop(dilutions);
+
+        let books = self.books.read().unwrap();
+        let mut alerts = Vec::new();
+        for event in &events {
+            for ((staker, _, strategy), book) in books.iter() {
+                if *strategy != event.strategy || book.shares <= 0.0 {
+                    continue;
+                }
+                let loss = book.shares * (event.price_before - event.price_after);
+                let level = if event.drop_bps >= 100 { AlertLevel::High } else { AlertLevel::Medium };
+                alerts.push(
+                    BusAlert::new(
+                        "restaking",
+                        "dilution",
+                        level,
+                        format!("{:?}", staker),
+                        format!("EigenLayer strategy {:?} share price fell {} bps", strategy, event.drop_bps),
+                    )
+                    .with_payload(json!({ "event": event, "shares": book.shares, "loss": loss })),
+                );
+            }
+        }
+        alerts
+    }
+
+    /// Опрашивает сеть и на каждом новом блоке снимает цены долей до сигнала остановки
+    pub async fn run(&self, bus: AlertBus, poll_interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut last_block = None;
+        let mut ticker = tokio::time::interval(poll_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            let block = match self.provider.get_block_number().await {
+                Ok(block) => block.as_u64(),
+                Err(e) => {
+                    self.errors.record(format!("block number on chain {}: {}", self.chain_id, e));
+                    continue;
+                }
+            };
+            if last_block == Some(block) {
+                continue;
+            }
+            for alert in self.on_block(block).await {
+                bus.publish(alert);
+            }
+            last_block = Some(block);
+        }
+    }
+
+    /// Сверяет доли из снимков портфеля с учётом позиций по последней цене
+    fn update_books(&self) {
+        let mut books = self.books.write().unwrap();
+        for staker in self.tracker.tracked() {
+            let Some(snapshot) = self.tracker.snapshot(staker) else {
+                continue;
+            };
+            let mut seen = Vec::new();
+            for position in snapshot.positions.iter().filter(|p| p.chain_id == self.chain_id) {
+                let PositionKind::EigenShares { strategy, shares, .. } = &position.kind else {
+                    continue;
+                };
+                let Some(price) = self.price(*strategy) else {
+                    continue;
+                };
+                let shares = ethers::utils::format_units(*shares, 18).ok().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+                books.entry((staker, self.chain_id, *strategy)).or_default().observe(shares, price);
+                seen.push(*strategy);
+            }
+            // Позиция исчезла из снимка — доли выведены полностью
+            for ((owner, chain_id, strategy), book) in books.iter_mut() {
+                if *owner == staker && *chain_id == self.chain_id && !seen.contains(strategy) {
+                    if let Some(price) = self.price(*strategy) {
+                        book.observe(0.0, price);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn nav(&self, staker: Address) -> Result<RestakingNav, PortfolioError> {
+        if !self.tracker.tracked().contains(&staker) {
+            return Err(PortfolioError::NotTracked(staker));
+        }
+        let underlyings: HashMap<Address, Address> = self
+            .tracker
+            .snapshot(staker)
+            .map(|s| {
+                s.positions
+                    .iter()
+                    .filter_map(|p| match &p.kind {
+                        PositionKind::EigenShares { strategy, underlying, .. } => Some((*strategy, *underlying)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let books = self.books.read().unwrap();
+        let mut positions: Vec<PositionNav> = books
+            .iter()
+            .filter(|((owner, _, _), _)| *owner == staker)
+            .filter_map(|((_, chain_id, strategy), book)| {
+                let price = self.price(*strategy)?;
+                Some(PositionNav {
+                    chain_id: *chain_id,
+                    strategy: *strategy,
+                    underlying: underlyings.get(strategy).copied().unwrap_or_default(),
+                    shares: book.shares,
+                    share_price: price,
+                    nav: book.nav(price),
+                    cost_basis: book.cost_basis,
+                    unrealized_pnl: book.unrealized_pnl(price),
+                    realized_pnl: book.realized_pnl,
+                })
+            })
+            .collect();
+        positions.sort_by_key(|p| (p.chain_id, p.strategy));
+
+        Ok(RestakingNav {
+            staker,
+            total_nav: positions.iter().map(|p| p.nav).sum(),
+            total_unrealized_pnl: positions.iter().map(|p| p.unrealized_pnl).sum(),
+            total_realized_pnl: positions.iter().map(|p| p.realized_pnl).sum(),
+            positions,
+        })
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> DigestSource for NavTracker<M> {
+    async fn section(&self, tenant: &DigestTenant, since: u64, _until: u64) -> Option<DigestSection> {
+        let mut digested = self.digested.lock().unwrap();
+        let previous = digested.entry(tenant.name.clone()).or_default();
+
+        let mut stakers: Vec<Address> =
+            self.tracker.tracked().into_iter().filter(|a| tenant.covers(&format!("{:?}", a))).collect();
+        stakers.sort();
+
+        let mut rows = Vec::new();
+        for staker in stakers {
+            let Ok(nav) = self.nav(staker) else {
+                continue;
+            };
+            for position in nav.positions.iter().filter(|p| p.shares > 0.0 || p.realized_pnl != 0.0) {
+                let key = (staker, position.chain_id, position.strategy);
+                let realized = position.realized_pnl - previous.insert(key, position.realized_pnl).unwrap_or(0.0);
+                rows.push(DigestRow {
+                    label: format!("{:?} EigenLayer {:?}", staker, position.strategy),
+                    value: format!(
+                        "NAV {:.4} @ {:.6}, unrealized {:+.4}, realized {:+.4}",
+                        position.nav, position.share_price, position.unrealized_pnl, realized
+                    ),
+                });
+            }
+        }
+        for event in self.dilutions(since) {
+            rows.push(DigestRow {
+                label: format!("Dilution of {:?} at block {}", event.strategy, event.block),
+                value: format!("-{} bps ({:.6} -> {:.6})", event.drop_bps, event.price_before, event.price_after),
+            });
+        }
+
+        (!rows.is_empty()).then(|| DigestSection { title: "Restaking NAV".into(), rows })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/portfolio/{address}/restaking",
+    tag = "portfolio",
+    params(("address" = String, Path, description = "Tracked staker")),
+    responses(
+        (status = 200, description = "NAV and P&L of EigenLayer positions", body = RestakingNav),
+        (status = 404, description = "Address is not tracked"),
+    )
+)]
+pub(super) async fn get_nav<M: Middleware + 'static>(
+    State(nav): State<Arc<NavTracker<M>>>,
+    Path(address): Path<Address>,
+) -> Result<Json<RestakingNav>, PortfolioError> {
+    Ok(Json(nav.nav(address)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/restaking/strategies/{strategy}/share-price",
+    tag = "portfolio",
+    params(("strategy" = String, Path, description = "EigenLayer strategy")),
+    responses((status = 200, description = "Observed share prices, oldest first", body = [SharePricePoint]))
+)]
+pub(super) async fn get_share_prices<M: Middleware + 'static>(
+    State(nav): State<Arc<NavTracker<M>>>,
+    Path(strategy): Path<Address>,
+) -> Json<Vec<SharePricePoint>> {
+    Json(nav.price_history(strategy))
+}
+
+/// Маршруты NAV рестейкинга; подключаются в `portfolio::router`
+pub fn router<M: Middleware + 'static>(nav: Arc<NavTracker<M>>) -> Router {
+    Router::new()
+        .route("/portfolio/:address/restaking", get(get_nav::<M>))
+        .route("/restaking/strategies/:strategy/share-price", get(get_share_prices::<M>))
+        .with_state(nav)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_cost_book_splits_realized_and_unrealized() {
+        let mut book = PositionBook::default();
+        book.observe(10.0, 1.0);
+        book.observe(20.0, 1.1);
+        assert!((book.cost_basis - 21.0).abs() < 1e-9);
+
+        // Выход половины по 1.2: средняя цена 1.05
+        book.observe(10.0, 1.2);
+        assert!((book.realized_pnl - 1.5).abs() < 1e-9);
+        assert!((book.cost_basis - 10.5).abs() < 1e-9);
+        assert!((book.unrealized_pnl(1.2) - 1.5).abs() < 1e-9);
+    }
+}
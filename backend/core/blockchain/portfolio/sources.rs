@@ -0,0 +1,202 @@
+use super::lp::UniswapSources;
+use super::{Position, PositionKind, PortfolioError};
+use ethers::prelude::*;
+use mevdetector::multicall::{Batch, BatchReader, MulticallError};
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+
+abigen!(
+    Erc20,
+    r#"[
+        function balanceOf(address owner) external view returns (uint256)
+        function decimals() external view returns (uint8)
+    ]"#
+);
+
+abigen!(
+    StrategyManager,
+    r#"[
+        function stakerStrategyShares(address staker, address strategy) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    Strategy,
+    r#"[
+        function sharesToUnderlyingView(uint256 amountShares) external view returns (uint256)
+        function underlyingToken() external view returns (address)
+        function totalShares() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    EigenPodManager,
+    r#"[
+        function podOwnerShares(address podOwner) external view returns (int256)
+    ]"#
+);
+
+/// Отслеживаемый ERC-20
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenSpec {
+    pub address: Address,
+    pub symbol: String,
+    pub decimals: u32,
+    /// Протокол ликвидного стейкинга (`lido`, `rocketpool`); `None` — обычный токен
+    #[serde(default)]
+    pub staking_protocol: Option<String>,
+}
+
+/// Контракты EigenLayer в сети
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EigenSources {
+    pub strategy_manager: Address,
+    pub eigen_pod_manager: Address,
+    pub strategies: Vec<Address>,
+}
+
+/// Что отслеживается в одной сети
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSources {
+    pub chain_id: u64,
+    #[serde(default)]
+    pub tokens: Vec<TokenSpec>,
+    #[serde(default)]
+    pub eigen: Option<EigenSources>,
+    #[serde(default)]
+    pub uniswap: Option<UniswapSources>,
+    /// Multicall3, если он развёрнут не по каноническому адресу
+    #[serde(default)]
+    pub multicall: Option<Address>,
+}
+
+fn amount(raw: U256, decimals: u32) -> f64 {
+    ethers::utils::format_units(raw, decimals)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn contract_error(e: impl std::fmt::Display) -> PortfolioError {
+    PortfolioError::ContractError(e.to_string())
+}
+
+/// Все позиции адреса в одной сети на заданном блоке; нулевые балансы пропускаются.
+/// Чтения контрактов идут двумя пакетами Multicall3 вместо `eth_call` на каждый токен и стратегию
+pub async fn fetch_positions<M: Middleware + 'static>(
+    provider: Arc<M>,
+    sources: &ChainSources,
+    owner: Address,
+    block: u64,
+) -> Result<Vec<Position>, PortfolioError> {
+    let block_id = BlockId::Number(block.into());
+    let reader = match sources.multicall {
+        Some(address) => BatchReader::with_address(provider.clone(), address),
+        None => BatchReader::new(provider.clone()),
+    };
+    let mut positions = Vec::new();
+    let mut push = |kind: PositionKind, raw: U256, decimals: u32| {
+        if !raw.is_zero() {
+            positions.push(Position {
+                chain_id: sources.chain_id,
+                kind,
+                raw,
+                amount: amount(raw, decimals),
+            });
+        }
+    };
+
+    let native = provider.get_balance(owner, Some(block_id)).await.map_err(contract_error)?;
+    push(PositionKind::Native, native, 18);
+
+    // Первый пакет: балансы токенов, доли в стратегиях и доли EigenPod
+    let mut batch = Batch::new();
+    let balances = sources
+        .tokens
+        .iter()
+        .map(|token| batch.add(&Erc20::new(token.address, provider.clone()).balance_of(owner)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(contract_error)?;
+    let eigen = match &sources.eigen {
+        Some(eigen) => {
+            let manager = StrategyManager::new(eigen.strategy_manager, provider.clone());
+            let shares = eigen
+                .strategies
+                .iter()
+                .map(|strategy| batch.add(&manager.staker_strategy_shares(owner, *strategy)))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(contract_error)?;
+            let pod = batch
+                .add(&EigenPodManager::new(eigen.eigen_pod_manager, provider.clone()).pod_owner_shares(owner))
+                .map_err(contract_error)?;
+            Some((eigen, shares, pod))
+        }
+        None => None,
+    };
+    let results = reader.execute(&batch, Some(block_id)).await.map_err(contract_error)?;
+
+    for (token, handle) in sources.tokens.iter().zip(&balances) {
+        let balance = results.get(handle).map_err(contract_error)?;
+        let kind = match &token.staking_protocol {
+            Some(protocol) => PositionKind::Staking {
+                protocol: protocol.clone(),
+                token: token.address,
+                symbol: token.symbol.clone(),
+            },
+            None => PositionKind::Erc20 {
+                token: token.address,
+                symbol: token.symbol.clone(),
+            },
+        };
+        push(kind, balance, token.decimals);
+    }
+
+    if let Some((eigen, share_handles, pod_handle)) = eigen {
+        // Второй пакет: базовый актив и его количество только для ненулевых долей
+        let mut held = Vec::new();
+        for (strategy, handle) in eigen.strategies.iter().zip(&share_handles) {
+            let shares = results.get(handle).map_err(contract_error)?;
+            if !shares.is_zero() {
+                held.push((*strategy, shares));
+            }
+        }
+        let mut batch = Batch::new();
+        let lookups = held
+            .iter()
+            .map(|(address, shares)| {
+                let strategy = Strategy::new(*address, provider.clone());
+                Ok((batch.add(&strategy.underlying_token())?, batch.add(&strategy.shares_to_underlying_view(*shares))?))
+            })
+            .collect::<Result<Vec<_>, MulticallError>>()
+            .map_err(contract_error)?;
+        let underlying_results = reader.execute(&batch, Some(block_id)).await.map_err(contract_error)?;
+        for ((strategy, shares), (underlying, underlying_amount)) in held.into_iter().zip(&lookups) {
+            push(
+                PositionKind::EigenShares {
+                    strategy,
+                    underlying: underlying_results.get(underlying).map_err(contract_error)?,
+                    shares,
+                },
+                underlying_results.get(underlying_amount).map_err(contract_error)?,
+                18,
+            );
+        }
+
+        // Нативный рестейкинг через EigenPod: доли 1:1 к ETH на beacon chain; отрицательные — долг после слэшинга
+        let pod_shares = results.get(&pod_handle).map_err(contract_error)?;
+        if pod_shares > I256::zero() {
+            let shares = pod_shares.into_raw();
+            push(
+                PositionKind::EigenShares {
+                    strategy: eigen.eigen_pod_manager,
+                    underlying: Address::zero(),
+                    shares,
+                },
+                shares,
+                18,
+            );
+        }
+    }
+
+    Ok(positions)
+}
@@ -0,0 +1,191 @@
+use super::risks::{RiskAnalyzer, ValidatorData};
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BacktestError {
+    #[error("No historical data source configured")]
+    NoHistory,
+
+    #[error("Indexer error: {0}")]
+    IndexerError(String),
+
+    #[error("Invalid block range {0}..{1}")]
+    InvalidRange(u64, u64),
+}
+
+/// Состояние валидатора на конкретном блоке
+#[derive(Debug, Clone)]
+pub struct ValidatorSnapshot {
+    pub block: u64,
+    pub data: ValidatorData,
+    /// Эффективный баланс (стейк + рестейк) для расчёта просадки
+    pub balance: U256,
+}
+
+/// Источник исторических данных валидаторов (индексер)
+pub trait ValidatorHistory: Send + Sync {
+    /// Снимки по возрастанию блока в диапазоне `[from_block, to_block]`
+    fn snapshots(
+        &self,
+        validator: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ValidatorSnapshot>, BacktestError>;
+}
+
+/// Предсказание модели и то, что произошло до следующего снимка
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestSample {
+    pub validator: Address,
+    pub block: u64,
+    pub predicted_slashing: f64,
+    /// Среднее трёх компонент риска
+    pub predicted_total: f64,
+    pub slashed: bool,
+    /// Максимальная просадка баланса до конца диапазона (0.0 - 1.0)
+    pub drawdown: f64,
+}
+
+/// Сводка бэктеста
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub validators: usize,
+    pub samples: Vec<BacktestSample>,
+    pub slashing_rate: f64,
+    pub mean_predicted_slashing: f64,
+    /// Средний квадрат ошибки вероятности слэшинга (меньше — лучше)
+    pub brier_score: f64,
+    /// Корреляция прогноза слэшинга с фактом (point-biserial)
+    pub slashing_correlation: Option<f64>,
+    /// Корреляция общего риска с реализованной просадкой
+    pub drawdown_correlation: Option<f64>,
+}
+
+/// Корреляция Пирсона; `None`, если у одной из выборок нет дисперсии
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = xs[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = ys[..n].iter().sum::<f64>() / n as f64;
+
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs[..n].iter().zip(&ys[..n]) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
+}
+
+fn drawdown(from: U256, future: &[ValidatorSnapshot]) -> f64 {
+    let Some(min) = future.iter().map(|s| s.balance).min() else {
+        return 0.0;
+    };
+    if from.is_zero() || min >= from {
+        return 0.0;
+    }
+    ((from - min) * U256::from(10_000u64) / from).as_u64() as f64 / 10_000.0
+}
+
+impl RiskAnalyzer {
+    /// Прогоняет модель по историческим снимкам и сравнивает прогноз с фактом:
+    /// слэшинг до следующего снимка и максимальную просадку до конца диапазона
+    pub fn backtest(
+        &self,
+        validators: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<BacktestReport, BacktestError> {
+        if from_block > to_block {
+            return Err(BacktestError::InvalidRange(from_block, to_block));
+        }
+        let history = self.history().ok_or(BacktestError::NoHistory)?;
+
+        let mut samples = Vec::new();
+        for validator in validators {
+            let snapshots = history.snapshots(*validator, from_block, to_block)?;
+            for (i, window) in snapshots.windows(2).enumerate() {
+                let (current, next) = (&window[0], &window[1]);
+                let risks = self.calculate_risks(&current.data);
+
+                samples.push(BacktestSample {
+                    validator: *validator,
+                    block: current.block,
+                    predicted_slashing: risks.slashing_risk,
+                    predicted_total: (risks.slashing_risk + risks.liquidity_risk + risks.concentration_risk) / 3.0,
+                    slashed: next.data.slash_history > current.data.slash_history,
+                    drawdown: drawdown(current.balance, &snapshots[i + 1..]),
+                });
+            }
+        }
+
+        let n = samples.len().max(1) as f64;
+        let realized: Vec<f64> = samples.iter().map(|s| if s.slashed { 1.0 } else { 0.0 }).collect();
+        let predicted: Vec<f64> = samples.iter().map(|s| s.predicted_slashing).collect();
+        let totals: Vec<f64> = samples.iter().map(|s| s.predicted_total).collect();
+        let drawdowns: Vec<f64> = samples.iter().map(|s| s.drawdown).collect();
+
+        Ok(BacktestReport {
+            from_block,
+            to_block,
+            validators: validators.len(),
+            slashing_rate: realized.iter().sum::<f64>() / n,
+            mean_predicted_slashing: predicted.iter().sum::<f64>() / n,
+            brier_score: predicted.iter().zip(&realized).map(|(p, r)| (p - r).powi(2)).sum::<f64>() / n,
+            slashing_correlation: pearson(&predicted, &realized),
+            drawdown_correlation: pearson(&totals, &drawdowns),
+            samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::risks::RiskModelConfig;
+    use std::sync::Arc;
+
+    struct FixedHistory;
+
+    impl ValidatorHistory for FixedHistory {
+        fn snapshots(&self, validator: Address, _: u64, _: u64) -> Result<Vec<ValidatorSnapshot>, BacktestError> {
+            // Валидатор 1 с плохим аптаймом слэшится, валидатор 2 — нет
+            let bad = validator == Address::repeat_byte(1);
+            Ok((0..4u32)
+                .map(|i| ValidatorSnapshot {
+                    block: 100 + i as u64,
+                    data: ValidatorData {
+                        total_staked: U256::from(10u64.pow(18)),
+                        restaked_assets: vec![],
+                        slash_history: if bad { i } else { 0 },
+                        avg_uptime: if bad { 0.8 } else { 0.99 },
+                        avs: vec![],
+                    },
+                    balance: U256::from(100 - if bad { i * 10 } else { 0 }),
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_backtest_correlates_with_slashing() {
+        let analyzer = RiskAnalyzer::new(RiskModelConfig::default()).with_history(Arc::new(FixedHistory));
+        let report = analyzer
+            .backtest(&[Address::repeat_byte(1), Address::repeat_byte(2)], 100, 103)
+            .unwrap();
+
+        assert_eq!(report.samples.len(), 6);
+        assert_eq!(report.slashing_rate, 0.5);
+        assert!(report.slashing_correlation.unwrap() > 0.8);
+    }
+}
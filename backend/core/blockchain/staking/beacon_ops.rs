@@ -0,0 +1,246 @@
+use ethers::utils::hex;
+use mevdetector::capability::SigningCapability;
+use mevdetector::remote_signer::{DepositMessage, Fork, ForkInfo, RemoteSignerError, VoluntaryExit, Web3Signer};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Полный депозит валидатора, gwei
+pub const MAX_EFFECTIVE_BALANCE_GWEI: u64 = 32_000_000_000;
+const SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(Debug, Error)]
+pub enum BeaconOpsError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Remote signer error: {0}")]
+    SignerError(#[from] RemoteSignerError),
+
+    #[error("Beacon API returned unexpected data: {0}")]
+    InvalidResponse(String),
+
+    #[error("Validator {0} cannot exit: status {1}")]
+    NotExitable(String, String),
+
+    #[error("Invalid deposit: {0}")]
+    InvalidDeposit(String),
+}
+
+/// Подписанный выход в формате `POST /eth/v1/beacon/pool/voluntary_exits`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedVoluntaryExit {
+    pub message: VoluntaryExit,
+    pub signature: String,
+}
+
+/// Запись `deposit_data-*.json` в формате staking-deposit-cli
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositData {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: u64,
+    pub signature: String,
+    pub deposit_message_root: String,
+    pub deposit_data_root: String,
+    pub fork_version: String,
+    pub network_name: String,
+}
+
+fn decode_hex(value: &str, len: usize, name: &str) -> Result<Vec<u8>, BeaconOpsError> {
+    let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|e| BeaconOpsError::InvalidDeposit(format!("{}: {}", name, e)))?;
+    if bytes.len() != len {
+        return Err(BeaconOpsError::InvalidDeposit(format!("{} must be {} bytes", name, len)));
+    }
+    Ok(bytes)
+}
+
+fn sha256_pair(a: &[u8], b: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(a);
+    hasher.update(b);
+    hasher.finalize().into()
+}
+
+/// `hash_tree_root` байтового вектора: чанки по 32 байта, дополненные до степени двойки
+fn bytes_root(bytes: &[u8]) -> [u8; 32] {
+    let mut chunks: Vec<[u8; 32]> = bytes
+        .chunks(32)
+        .map(|c| {
+            let mut chunk = [0u8; 32];
+            chunk[..c.len()].copy_from_slice(c);
+            chunk
+        })
+        .collect();
+    chunks.resize(chunks.len().next_power_of_two(), [0u8; 32]);
+    while chunks.len() > 1 {
+        chunks = chunks.chunks(2).map(|pair| sha256_pair(&pair[0], &pair[1])).collect();
+    }
+    chunks[0]
+}
+
+fn uint64_root(value: u64) -> [u8; 32] {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    chunk
+}
+
+/// `hash_tree_root(DepositMessage)` и `hash_tree_root(DepositData)` по SSZ
+fn deposit_roots(pubkey: &[u8], withdrawal_credentials: &[u8], amount: u64, signature: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let pubkey_root = bytes_root(pubkey);
+    let left = sha256_pair(&pubkey_root, &bytes_root(withdrawal_credentials));
+    let message_root = sha256_pair(&left, &sha256_pair(&uint64_root(amount), &[0u8; 32]));
+    let data_root = sha256_pair(&left, &sha256_pair(&uint64_root(amount), &bytes_root(signature)));
+    (message_root, data_root)
+}
+
+fn field<'a>(value: &'a Value, path: &[&str]) -> Result<&'a Value, BeaconOpsError> {
+    path.iter()
+        .try_fold(value, |v, key| v.get(key))
+        .ok_or_else(|| BeaconOpsError::InvalidResponse(format!("missing {}", path.join("."))))
+}
+
+fn string_field(value: &Value, path: &[&str]) -> Result<String, BeaconOpsError> {
+    field(value, path)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| BeaconOpsError::InvalidResponse(format!("{} is not a string", path.join("."))))
+}
+
+/// Операции с валидаторами через Web3Signer: выходы и депозиты подписываются
+/// удалённо, BLS-ключи в процесс не попадают
+pub struct BeaconOperations {
+    http: reqwest::Client,
+    beacon_url: String,
+    signer: Web3Signer,
+    signing: SigningCapability,
+}
+
+impl BeaconOperations {
+    pub fn new(beacon_url: &str, signer: Web3Signer, signing: SigningCapability) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            beacon_url: beacon_url.trim_end_matches('/').to_string(),
+            signer,
+            signing,
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Value, BeaconOpsError> {
+        Ok(self.http.get(format!("{}{}", self.beacon_url, path)).send().await?.error_for_status()?.json().await?)
+    }
+
+    /// Домен выхода по EIP-7044: начиная с Deneb выходы подписываются версией Capella
+    /// и не устаревают со следующими форками
+    async fn exit_fork_info(&self) -> Result<ForkInfo, BeaconOpsError> {
+        let genesis = self.get("/eth/v1/beacon/genesis").await?;
+        let spec = self.get("/eth/v1/config/spec").await?;
+        let capella = string_field(&spec, &["data", "CAPELLA_FORK_VERSION"])?;
+        Ok(ForkInfo {
+            fork: Fork {
+                previous_version: capella.clone(),
+                current_version: capella,
+                epoch: string_field(&spec, &["data", "CAPELLA_FORK_EPOCH"])?,
+            },
+            genesis_validators_root: string_field(&genesis, &["data", "genesis_validators_root"])?,
+        })
+    }
+
+    /// Индекс валидатора; выйти может только активный валидатор без инициированного выхода
+    async fn exitable_index(&self, public_key: &str) -> Result<String, BeaconOpsError> {
+        let validator = self.get(&format!("/eth/v1/beacon/states/head/validators/{}", public_key)).await?;
+        let status = string_field(&validator, &["data", "status"])?;
+        if status != "active_ongoing" {
+            return Err(BeaconOpsError::NotExitable(public_key.to_string(), status));
+        }
+        string_field(&validator, &["data", "index"])
+    }
+
+    /// Подписывает добровольный выход; `epoch: None` — текущая эпоха
+    pub async fn sign_exit(&self, public_key: &str, epoch: Option<u64>) -> Result<SignedVoluntaryExit, BeaconOpsError> {
+        let validator_index = self.exitable_index(public_key).await?;
+        let epoch = match epoch {
+            Some(epoch) => epoch.to_string(),
+            None => {
+                let head = self.get("/eth/v1/beacon/headers/head").await?;
+                let slot: u64 = string_field(&head, &["data", "header", "message", "slot"])?
+                    .parse()
+                    .map_err(|_| BeaconOpsError::InvalidResponse("slot is not a number".into()))?;
+                (slot / SLOTS_PER_EPOCH).to_string()
+            }
+        };
+        let message = VoluntaryExit { epoch, validator_index };
+        let fork_info = self.exit_fork_info().await?;
+        let signature = self.signer.sign_voluntary_exit(public_key, &fork_info, &message, &self.signing).await?;
+        Ok(SignedVoluntaryExit { message, signature })
+    }
+
+    /// Отправляет подписанный выход в пул beacon-узла
+    pub async fn submit_exit(&self, exit: &SignedVoluntaryExit) -> Result<(), BeaconOpsError> {
+        self.http
+            .post(format!("{}/eth/v1/beacon/pool/voluntary_exits", self.beacon_url))
+            .json(&json!({ "message": exit.message, "signature": exit.signature }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Данные депозита с подписью подписанта и корнями для контракта депозитов
+    pub async fn deposit_data(
+        &self,
+        public_key: &str,
+        withdrawal_credentials: &str,
+        amount_gwei: u64,
+        network_name: &str,
+    ) -> Result<DepositData, BeaconOpsError> {
+        let pubkey = decode_hex(public_key, 48, "pubkey")?;
+        let credentials = decode_hex(withdrawal_credentials, 32, "withdrawal_credentials")?;
+        if !matches!(credentials[0], 0x01 | 0x02) {
+            return Err(BeaconOpsError::InvalidDeposit("withdrawal credentials must be 0x01 or 0x02 (execution address)".into()));
+        }
+        if amount_gwei < 1_000_000_000 {
+            return Err(BeaconOpsError::InvalidDeposit("amount must be at least 1 ETH".into()));
+        }
+
+        let genesis = self.get("/eth/v1/beacon/genesis").await?;
+        let fork_version = string_field(&genesis, &["data", "genesis_fork_version"])?;
+        let message = DepositMessage {
+            pubkey: format!("0x{}", hex::encode(&pubkey)),
+            withdrawal_credentials: format!("0x{}", hex::encode(&credentials)),
+            amount: amount_gwei.to_string(),
+            genesis_fork_version: fork_version.clone(),
+        };
+        let signature = self.signer.sign_deposit(public_key, &message, &self.signing).await?;
+        let (message_root, data_root) = deposit_roots(&pubkey, &credentials, amount_gwei, &decode_hex(&signature, 96, "signature")?);
+
+        Ok(DepositData {
+            pubkey: hex::encode(&pubkey),
+            withdrawal_credentials: hex::encode(&credentials),
+            amount: amount_gwei,
+            signature: signature.trim_start_matches("0x").to_string(),
+            deposit_message_root: hex::encode(message_root),
+            deposit_data_root: hex::encode(data_root),
+            fork_version: fork_version.trim_start_matches("0x").to_string(),
+            network_name: network_name.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_roots() {
+        // Ожидаемые корни посчитаны независимой реализацией SSZ по спецификации
+        let pubkey: Vec<u8> = (1..=48).collect();
+        let mut credentials = vec![0x01];
+        credentials.extend_from_slice(&[0u8; 11]);
+        credentials.extend_from_slice(&[0xaa; 20]);
+        let (message_root, data_root) = deposit_roots(&pubkey, &credentials, MAX_EFFECTIVE_BALANCE_GWEI, &[0x42; 96]);
+        assert_eq!(hex::encode(message_root), "b018380e12865dc1c6a376b3e6563c1d88c979aee5db3e46cbc14a28a5868f8c");
+        assert_eq!(hex::encode(data_root), "d9c8fb440562f6434e92e35c95e614d5aaeee29b8d1f12020e19ef7b0548c6cd");
+    }
+}
@@ -0,0 +1,387 @@
+use ethers::types::U256;
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use mevdetector::shutdown::ShutdownSignal;
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+const SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(Debug, Error)]
+pub enum DvtError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("{0} API returned unexpected data: {1}")]
+    InvalidResponse(&'static str, String),
+}
+
+/// Кластер распределённого валидатора под наблюдением
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DvtCluster {
+    /// Кластер SSV: операторы по id, валидаторы по BLS-ключам
+    Ssv { name: String, operator_ids: Vec<u64>, validators: Vec<String> },
+    /// Кластер Obol: lock из Obol API, узлы Charon операторов
+    Obol { name: String, lock_hash: String, charon_endpoints: Vec<String> },
+}
+
+impl DvtCluster {
+    pub fn name(&self) -> &str {
+        match self {
+            DvtCluster::Ssv { name, .. } | DvtCluster::Obol { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DvtConfig {
+    /// Например `https://api.ssv.network/api/v4/mainnet`
+    pub ssv_api: String,
+    pub obol_api: String,
+    pub beacon_url: String,
+    /// Доля выполненных обязанностей за 24 ч, ниже которой оператор считается отказавшим
+    pub min_performance: f64,
+    /// Сколько операторов сверх порога подписи должно оставаться исправными
+    pub min_spare_operators: usize,
+    /// Доля валидаторов кластера, отметившихся в прошлой эпохе
+    pub min_participation: f64,
+}
+
+impl Default for DvtConfig {
+    fn default() -> Self {
+        Self {
+            ssv_api: "https://api.ssv.network/api/v4/mainnet".into(),
+            obol_api: "https://api.obol.tech/v1".into(),
+            beacon_url: "http://localhost:5052".into(),
+            min_performance: 0.9,
+            min_spare_operators: 1,
+            min_participation: 0.95,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DvtOperatorHealth {
+    pub id: String,
+    pub healthy: bool,
+    /// Доля выполненных обязанностей за 24 ч (SSV)
+    pub performance: Option<f64>,
+    /// Комиссия оператора SSV за блок, в wei SSV
+    pub fee: Option<U256>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterHealth {
+    pub cluster: String,
+    pub operators: Vec<DvtOperatorHealth>,
+    /// Сколько операторов нужно для подписи
+    pub threshold: usize,
+    /// Доля валидаторов кластера, живых в прошлой эпохе
+    pub participation: Option<f64>,
+}
+
+impl ClusterHealth {
+    pub fn healthy_operators(&self) -> usize {
+        self.operators.iter().filter(|o| o.healthy).count()
+    }
+
+    /// Запас исправных операторов над порогом; отрицательный — кластер не подписывает
+    pub fn spare(&self) -> i64 {
+        self.healthy_operators() as i64 - self.threshold as i64
+    }
+}
+
+/// Порог подписи SSV при `n = 3f + 1` операторах
+fn ssv_threshold(operators: usize) -> usize {
+    operators - (operators.saturating_sub(1)) / 3
+}
+
+/// Здоровье кластеров DVT: производительность и комиссии операторов SSV, готовность
+/// узлов Charon и участие валидаторов Obol в аттестациях
+pub struct DvtMonitor {
+    http: reqwest::Client,
+    config: DvtConfig,
+    clusters: Vec<DvtCluster>,
+    /// Последние известные комиссии операторов SSV
+    fees: Mutex<HashMap<u64, U256>>,
+    /// Кластеры, по которым уже поднят алерт о деградации
+    degraded: Mutex<HashMap<String, i64>>,
+    /// Кластеры, по которым уже поднят алерт о низком участии
+    low_participation: Mutex<HashSet<String>>,
+    errors: TaskErrors,
+}
+
+impl DvtMonitor {
+    pub fn new(config: DvtConfig, clusters: Vec<DvtCluster>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config,
+            clusters,
+            fees: Mutex::new(HashMap::new()),
+            degraded: Mutex::new(HashMap::new()),
+            low_participation: Mutex::new(HashSet::new()),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Кластеры, которые не удалось проверить
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    async fn get(&self, url: String) -> Result<Value, DvtError> {
+        Ok(self.http.get(url).send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn ssv_operator(&self, id: u64) -> Result<DvtOperatorHealth, DvtError> {
+        let operator = self.get(format!("{}/operators/{}", self.config.ssv_api.trim_end_matches('/'), id)).await?;
+        let performance = operator["performance"]["24h"].as_f64().map(|p| p / 100.0);
+        let fee = operator["fee"].as_str().and_then(|f| U256::from_dec_str(f).ok());
+        let active = operator["is_active"].as_u64() == Some(1) || operator["is_active"].as_bool() == Some(true);
+        let status = operator["status"].as_str().unwrap_or("unknown").to_string();
+        let performing = performance.map_or(true, |p| p >= self.config.min_performance);
+        Ok(DvtOperatorHealth {
+            id: id.to_string(),
+            healthy: active && performing,
+            performance,
+            fee,
+            detail: status,
+        })
+    }
+
+    /// Живость валидаторов за прошлую эпоху по beacon API; `None` — ключей нет
+    async fn participation(&self, validators: &[String]) -> Result<Option<f64>, DvtError> {
+        if validators.is_empty() {
+            return Ok(None);
+        }
+        let beacon = self.config.beacon_url.trim_end_matches('/');
+        let head = self.get(format!("{}/eth/v1/beacon/headers/head", beacon)).await?;
+        let slot: u64 = head["data"]["header"]["message"]["slot"]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| DvtError::InvalidResponse("beacon", "head slot".into()))?;
+        let epoch = (slot / SLOTS_PER_EPOCH).saturating_sub(1);
+
+        let states = self
+            .get(format!("{}/eth/v1/beacon/states/head/validators?id={}", beacon, validators.join(",")))
+            .await?;
+        let indices: Vec<String> = states["data"]
+            .as_array()
+            .map(|list| list.iter().filter_map(|v| v["index"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        if indices.is_empty() {
+            return Ok(None);
+        }
+
+        let liveness: Value = self
+            .http
+            .post(format!("{}/eth/v1/validator/liveness/{}", beacon, epoch))
+            .json(&indices)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let live = liveness["data"].as_array().map_or(0, |list| list.iter().filter(|v| v["is_live"] == true).count());
+        Ok(Some(live as f64 / indices.len() as f64))
+    }
+
+    async fn ssv_cluster(&self, name: &str, operator_ids: &[u64], validators: &[String]) -> Result<ClusterHealth, DvtError> {
+        let mut operators = Vec::new();
+        for id in operator_ids {
+            operators.push(match self.ssv_operator(*id).await {
+                Ok(health) => health,
+                Err(e) => DvtOperatorHealth { id: id.to_string(), healthy: false, performance: None, fee: None, detail: e.to_string() },
+            });
+        }
+        Ok(ClusterHealth {
+            cluster: name.to_string(),
+            threshold: ssv_threshold(operator_ids.len()),
+            operators,
+            participation: self.participation(validators).await?,
+        })
+    }
+
+    async fn obol_cluster(&self, name: &str, lock_hash: &str, charon_endpoints: &[String]) -> Result<ClusterHealth, DvtError> {
+        let lock = self.get(format!("{}/lock/{}", self.config.obol_api.trim_end_matches('/'), lock_hash)).await?;
+        let definition = &lock["cluster_definition"];
+        let threshold = definition["threshold"]
+            .as_u64()
+            .ok_or_else(|| DvtError::InvalidResponse("obol", "cluster_definition.threshold".into()))? as usize;
+        let validators: Vec<String> = lock["distributed_validators"]
+            .as_array()
+            .map(|list| list.iter().filter_map(|v| v["distributed_public_key"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let mut operators = Vec::new();
+        for endpoint in charon_endpoints {
+            // `/readyz` Charon отвечает 200, когда узел видит кворум пиров и beacon-узел синхронизирован
+            let ready = self.http.get(format!("{}/readyz", endpoint.trim_end_matches('/'))).send().await;
+            let (healthy, detail) = match ready {
+                Ok(response) if response.status().is_success() => (true, "ready".to_string()),
+                Ok(response) => (false, response.text().await.unwrap_or_default().trim().to_string()),
+                Err(e) => (false, e.to_string()),
+            };
+            operators.push(DvtOperatorHealth { id: endpoint.clone(), healthy, performance: None, fee: None, detail });
+        }
+        // Операторы без известного эндпоинта не видны и считаются неисправными
+        let total = definition["operators"].as_array().map_or(operators.len(), Vec::len);
+        for i in operators.len()..total {
+            operators.push(DvtOperatorHealth {
+                id: format!("operator #{}", i),
+                healthy: false,
+                performance: None,
+                fee: None,
+                detail: "no charon endpoint configured".into(),
+            });
+        }
+
+        Ok(ClusterHealth {
+            cluster: name.to_string(),
+            operators,
+            threshold,
+            participation: self.participation(&validators).await?,
+        })
+    }
+
+    pub async fn cluster_health(&self, cluster: &DvtCluster) -> Result<ClusterHealth, DvtError> {
+        match cluster {
+            DvtCluster::Ssv { name, operator_ids, validators } => self.ssv_cluster(name, operator_ids, validators).await,
+            DvtCluster::Obol { name, lock_hash, charon_endpoints } => self.obol_cluster(name, lock_hash, charon_endpoints).await,
+        }
+    }
+
+    /// Алерты по состоянию кластера: деградация запаса и низкое участие (один раз
+    /// до восстановления), смена комиссий операторов SSV
+    pub fn alerts(&self, health: &ClusterHealth) -> Vec<BusAlert> {
+        let mut alerts = Vec::new();
+        let spare = health.spare();
+        let mut degraded = self.degraded.lock().unwrap();
+        if spare < self.config.min_spare_operators as i64 {
+            if degraded.insert(health.cluster.clone(), spare) != Some(spare) {
+                let level = if spare < 0 { AlertLevel::Critical } else { AlertLevel::High };
+                alerts.push(
+                    BusAlert::new(
+                        "dvt",
+                        "redundancy_degraded",
+                        level,
+                        health.cluster.clone(),
+                        format!(
+                            "DVT cluster {}: {} of {} operators healthy, threshold {}",
+                            health.cluster,
+                            health.healthy_operators(),
+                            health.operators.len(),
+                            health.threshold
+                        ),
+                    )
+                    .with_payload(json!({ "health": health, "spare": spare })),
+                );
+            }
+        } else {
+            degraded.remove(&health.cluster);
+        }
+
+        let low = health.participation.filter(|p| *p < self.config.min_participation);
+        let mut low_participation = self.low_participation.lock().unwrap();
+        if low.is_none() {
+            low_participation.remove(&health.cluster);
+        }
+        if let Some(participation) = low.filter(|_| low_participation.insert(health.cluster.clone())) {
+            alerts.push(
+                BusAlert::new(
+                    "dvt",
+                    "low_participation",
+                    AlertLevel::Medium,
+                    health.cluster.clone(),
+                    format!("DVT cluster {}: {:.1}% of validators attested last epoch", health.cluster, participation * 100.0),
+                )
+                .with_payload(json!({ "health": health, "participation": participation })),
+            );
+        }
+
+        let mut fees = self.fees.lock().unwrap();
+        for operator in &health.operators {
+            let (Ok(id), Some(fee)) = (operator.id.parse::<u64>(), operator.fee) else {
+                continue;
+            };
+            if let Some(before) = fees.insert(id, fee).filter(|before| *before != fee) {
+                let level = if fee > before { AlertLevel::Medium } else { AlertLevel::Info };
+                alerts.push(
+                    BusAlert::new(
+                        "dvt",
+                        "operator_fee_changed",
+                        level,
+                        format!("ssv:{}", id),
+                        format!("SSV operator {} fee changed from {} to {}", id, before, fee),
+                    )
+                    .with_payload(json!({ "cluster": health.cluster, "operator": id, "before": before, "after": fee })),
+                );
+            }
+        }
+        alerts
+    }
+
+    /// Проверка всех кластеров с интервалом; ошибка одного кластера не останавливает остальные
+    pub async fn run(&self, bus: AlertBus, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    for cluster in &self.clusters {
+                        match self.cluster_health(cluster).await {
+                            Ok(health) => {
+                                for alert in self.alerts(&health) {
+                                    bus.publish(alert);
+                                }
+                            }
+                            Err(e) => self.errors.record(format!("dvt cluster {} health check failed: {}", cluster.name(), e)),
+                        }
+                    }
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operator(id: &str, healthy: bool, fee: u64) -> DvtOperatorHealth {
+        DvtOperatorHealth { id: id.into(), healthy, performance: None, fee: Some(U256::from(fee)), detail: String::new() }
+    }
+
+    fn health(healthy: usize, participation: f64, fee: u64) -> ClusterHealth {
+        ClusterHealth {
+            cluster: "ssv-1".into(),
+            operators: (0..4).map(|i| operator(&(i + 1).to_string(), i < healthy, fee)).collect(),
+            threshold: ssv_threshold(4),
+            participation: Some(participation),
+        }
+    }
+
+    fn kinds(alerts: &[BusAlert]) -> Vec<&str> {
+        alerts.iter().map(|a| a.kind.as_str()).collect()
+    }
+
+    #[test]
+    fn test_alerts_fire_on_state_changes() {
+        let monitor = DvtMonitor::new(DvtConfig::default(), Vec::new());
+        assert!(monitor.alerts(&health(4, 1.0, 10)).is_empty());
+
+        assert_eq!(kinds(&monitor.alerts(&health(3, 0.5, 10))), ["redundancy_degraded", "low_participation"]);
+        // То же состояние на следующих тиках не повторяет алерты
+        assert!(monitor.alerts(&health(3, 0.5, 10)).is_empty());
+        assert_eq!(kinds(&monitor.alerts(&health(2, 0.5, 10))), ["redundancy_degraded"]);
+
+        assert_eq!(kinds(&monitor.alerts(&health(4, 1.0, 12))), ["operator_fee_changed"; 4]);
+        assert_eq!(kinds(&monitor.alerts(&health(4, 0.5, 12))), ["low_participation"]);
+    }
+}
@@ -0,0 +1,429 @@
+use super::operator::{AvsStakeRegistry, RegistryCoordinator};
+use ethers::contract::{parse_log, EthEvent};
+use ethers::prelude::*;
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use mevdetector::multicall::{Batch, BatchReader};
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+use thiserror::Error;
+use tokio::sync::watch;
+
+abigen!(
+    StakerDelegation,
+    r#"[
+        function delegatedTo(address staker) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    QuorumRegistry,
+    r#"[
+        function registryCoordinator() external view returns (address)
+    ]"#
+);
+
+#[derive(Debug, Error)]
+pub enum CommissionError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+fn provider_err(e: impl std::fmt::Display) -> CommissionError {
+    CommissionError::ProviderError(e.to_string())
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "OperatorAVSSplitBipsSet", abi = "OperatorAVSSplitBipsSet(address,address,address,uint32,uint16,uint16)")]
+struct OperatorAvsSplitEvent {
+    #[ethevent(indexed)]
+    caller: Address,
+    #[ethevent(indexed)]
+    operator: Address,
+    #[ethevent(indexed)]
+    avs: Address,
+    activated_at: u32,
+    old_bips: u16,
+    new_bips: u16,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "OperatorPISplitBipsSet", abi = "OperatorPISplitBipsSet(address,address,uint32,uint16,uint16)")]
+struct OperatorPiSplitEvent {
+    #[ethevent(indexed)]
+    caller: Address,
+    #[ethevent(indexed)]
+    operator: Address,
+    activated_at: u32,
+    old_bips: u16,
+    new_bips: u16,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "MinimumStakeForQuorumUpdated", abi = "MinimumStakeForQuorumUpdated(uint8,uint96)")]
+struct MinimumStakeEvent {
+    #[ethevent(indexed)]
+    quorum_number: u8,
+    minimum_stake: U256,
+}
+
+/// Изменённый параметр оператора или AVS
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "parameter", rename_all = "snake_case")]
+pub enum Parameter {
+    /// Доля оператора в наградах AVS (комиссия), б.п.
+    OperatorAvsSplit { operator: Address, avs: Address },
+    /// Доля оператора в наградах за программное стимулирование, б.п.
+    OperatorPiSplit { operator: Address },
+    /// Минимальный стейк кворума AVS
+    MinimumStake { registry: Address, quorum: u8 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterChange {
+    #[serde(flatten)]
+    pub parameter: Parameter,
+    pub before: U256,
+    pub after: U256,
+    /// UNIX-время вступления в силу; для мгновенных изменений — время блока
+    pub effective_at: u64,
+    pub block: u64,
+    pub tx_hash: H256,
+}
+
+impl ParameterChange {
+    /// Изменение не в пользу делегаторов: рост комиссии или порога стейка
+    pub fn is_adverse(&self) -> bool {
+        self.after > self.before
+    }
+
+    fn describe(&self) -> String {
+        match &self.parameter {
+            Parameter::OperatorAvsSplit { operator, avs } => {
+                format!("Operator {:?} commission for AVS {:?}: {} -> {} bps", operator, avs, self.before, self.after)
+            }
+            Parameter::OperatorPiSplit { operator } => {
+                format!("Operator {:?} programmatic incentive commission: {} -> {} bps", operator, self.before, self.after)
+            }
+            Parameter::MinimumStake { registry, quorum } => {
+                format!("AVS {:?} quorum {} minimum stake: {} -> {}", registry, quorum, self.before, self.after)
+            }
+        }
+    }
+}
+
+/// Смена доли оператора из лога `RewardsCoordinator`; прочие события — `None`
+fn split_change(log: Log) -> Option<ParameterChange> {
+    let block = log.block_number.map_or(0, |b| b.as_u64());
+    let tx_hash = log.transaction_hash.unwrap_or_default();
+    let (parameter, before, after, activated_at) = if let Ok(e) = parse_log::<OperatorAvsSplitEvent>(log.clone()) {
+        (Parameter::OperatorAvsSplit { operator: e.operator, avs: e.avs }, e.old_bips, e.new_bips, e.activated_at)
+    } else if let Ok(e) = parse_log::<OperatorPiSplitEvent>(log) {
+        (Parameter::OperatorPiSplit { operator: e.operator }, e.old_bips, e.new_bips, e.activated_at)
+    } else {
+        return None;
+    };
+    Some(ParameterChange {
+        parameter,
+        before: before.into(),
+        after: after.into(),
+        effective_at: activated_at as u64,
+        block,
+        tx_hash,
+    })
+}
+
+/// Следит за сменой комиссий операторов EigenLayer и параметров AVS и адресует алерт
+/// каждому наблюдаемому стейкеру, делегированному затронутому оператору
+pub struct CommissionWatcher<M> {
+    provider: Arc<M>,
+    reader: BatchReader<M>,
+    delegation_manager: Address,
+    rewards_coordinator: Address,
+    stake_registries: Vec<Address>,
+    watched: RwLock<HashSet<Address>>,
+    last_block: Mutex<Option<u64>>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> CommissionWatcher<M> {
+    pub fn new(provider: Arc<M>, delegation_manager: Address, rewards_coordinator: Address) -> Self {
+        Self {
+            reader: BatchReader::new(provider.clone()),
+            provider,
+            delegation_manager,
+            rewards_coordinator,
+            stake_registries: Vec::new(),
+            watched: RwLock::new(HashSet::new()),
+            last_block: Mutex::new(None),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Опросы, которые не удалось выполнить
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// `StakeRegistry` AVS, пороги которого тоже отслеживаются
+    pub fn with_stake_registry(mut self, registry: Address) -> Self {
+        self.stake_registries.push(registry);
+        self
+    }
+
+    pub fn watch(&self, staker: Address) {
+        self.watched.write().unwrap().insert(staker);
+    }
+
+    pub fn unwatch(&self, staker: Address) {
+        self.watched.write().unwrap().remove(&staker);
+    }
+
+    async fn block_timestamp(&self, block: u64) -> Result<u64, CommissionError> {
+        Ok(self
+            .provider
+            .get_block(block)
+            .await
+            .map_err(provider_err)?
+            .map_or(0, |b| b.timestamp.as_u64()))
+    }
+
+    /// Изменения параметров в диапазоне блоков
+    pub async fn changes(&self, from: u64, to: u64) -> Result<Vec<ParameterChange>, CommissionError> {
+        let filter = Filter::new().address(self.rewards_coordinator).from_block(from).to_block(to);
+        let mut changes: Vec<ParameterChange> = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(provider_err)?
+            .into_iter()
+            .filter_map(split_change)
+            .collect();
+
+        for registry in &self.stake_registries {
+            let filter = Filter::new().address(*registry).from_block(from).to_block(to);
+            for log in self.provider.get_logs(&filter).await.map_err(provider_err)? {
+                let Ok(e) = parse_log::<MinimumStakeEvent>(log.clone()) else {
+                    continue;
+                };
+                let block = log.block_number.map_or(0, |b| b.as_u64());
+                // Событие несёт только новое значение; прежнее читается на предыдущем блоке
+                let before = AvsStakeRegistry::new(*registry, self.provider.clone())
+                    .minimum_stake_for_quorum(e.quorum_number)
+                    .block(block.saturating_sub(1))
+                    .call()
+                    .await
+                    .map_err(provider_err)?;
+                changes.push(ParameterChange {
+                    parameter: Parameter::MinimumStake { registry: *registry, quorum: e.quorum_number },
+                    before: before.into(),
+                    after: e.minimum_stake,
+                    effective_at: self.block_timestamp(block).await?,
+                    block,
+                    tx_hash: log.transaction_hash.unwrap_or_default(),
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Операторы, зарегистрированные в кворуме AVS
+    async fn in_quorum(&self, registry: Address, quorum: u8, operators: &[Address], block: u64) -> Result<Vec<Address>, CommissionError> {
+        let coordinator = QuorumRegistry::new(registry, self.provider.clone())
+            .registry_coordinator()
+            .call()
+            .await
+            .map_err(provider_err)?;
+        let coordinator = RegistryCoordinator::new(coordinator, self.provider.clone());
+        let at = Some(BlockId::Number(block.into()));
+
+        // Два пакета на всех операторов: идентификаторы, затем битовые маски кворумов
+        let mut batch = Batch::new();
+        let ids = operators
+            .iter()
+            .map(|operator| batch.add(&coordinator.get_operator_id(*operator)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(provider_err)?;
+        let results = self.reader.execute(&batch, at).await.map_err(provider_err)?;
+        let ids = ids.iter().map(|id| results.get(id)).collect::<Result<Vec<_>, _>>().map_err(provider_err)?;
+
+        let mut batch = Batch::new();
+        let bitmaps = ids
+            .iter()
+            .map(|id| batch.add(&coordinator.get_current_quorum_bitmap(*id)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(provider_err)?;
+        let results = self.reader.execute(&batch, at).await.map_err(provider_err)?;
+        let mut registered = Vec::new();
+        for (operator, bitmap) in operators.iter().zip(&bitmaps) {
+            if results.get(bitmap).map_err(provider_err)?.bit(quorum as usize) {
+                registered.push(*operator);
+            }
+        }
+        Ok(registered)
+    }
+
+    /// Наблюдаемые стейкеры, чей оператор затронут изменением, с их оператором
+    pub async fn affected(&self, change: &ParameterChange) -> Result<Vec<(Address, Address)>, CommissionError> {
+        let stakers: Vec<Address> = self.watched.read().unwrap().iter().copied().collect();
+        let delegation = StakerDelegation::new(self.delegation_manager, self.provider.clone());
+        let mut batch = Batch::new();
+        let handles = stakers
+            .iter()
+            .map(|staker| batch.add(&delegation.delegated_to(*staker)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(provider_err)?;
+        let results = self.reader.execute(&batch, Some(BlockId::Number(change.block.into()))).await.map_err(provider_err)?;
+        let mut delegations = Vec::new();
+        for (staker, handle) in stakers.into_iter().zip(&handles) {
+            let operator = results.get(handle).map_err(provider_err)?;
+            if !operator.is_zero() {
+                delegations.push((staker, operator));
+            }
+        }
+
+        Ok(match &change.parameter {
+            Parameter::OperatorAvsSplit { operator, .. } | Parameter::OperatorPiSplit { operator } => {
+                delegations.into_iter().filter(|(_, o)| o == operator).collect()
+            }
+            Parameter::MinimumStake { registry, quorum } => {
+                let mut operators: Vec<Address> = delegations.iter().map(|(_, o)| *o).collect();
+                operators.sort();
+                operators.dedup();
+                let registered = self.in_quorum(*registry, *quorum, &operators, change.block).await?;
+                delegations.into_iter().filter(|(_, o)| registered.contains(o)).collect()
+            }
+        })
+    }
+
+    /// Изменения с прошлого опроса до `block` включительно
+    pub async fn poll(&self, block: u64) -> Result<Vec<BusAlert>, CommissionError> {
+        let from = match *self.last_block.lock().unwrap() {
+            Some(last) if last >= block => return Ok(Vec::new()),
+            Some(last) => last + 1,
+            None => block,
+        };
+        let mut alerts = Vec::new();
+        for change in self.changes(from, block).await? {
+            for (staker, operator) in self.affected(&change).await? {
+                let level = if change.is_adverse() { AlertLevel::High } else { AlertLevel::Info };
+                alerts.push(
+                    BusAlert::new("staking", "parameter_change", level, format!("{:?}", staker), change.describe())
+                        .with_payload(json!({ "change": change, "operator": operator })),
+                );
+            }
+        }
+        *self.last_block.lock().unwrap() = Some(block);
+        Ok(alerts)
+    }
+
+    pub async fn run(&self, mut heads: watch::Receiver<u64>, bus: AlertBus) {
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            match self.poll(block).await {
+                Ok(alerts) => {
+                    for alert in alerts {
+                        bus.publish(alert);
+                    }
+                }
+                Err(e) => self.errors.record(format!("operator parameter scan up to block {}: {}", block, e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::providers::{MockProvider, Provider};
+
+    const OPERATOR: Address = H160([0x0a; 20]);
+    const OTHER_OPERATOR: Address = H160([0x0b; 20]);
+    const AVS: Address = H160([0x0c; 20]);
+    const STAKER: Address = H160([0x51; 20]);
+
+    fn split_log(signature: H256, indexed: &[Address], activated_at: u32, old_bips: u16, new_bips: u16) -> Log {
+        let mut topics = vec![signature, H256::from(Address::repeat_byte(0xca))];
+        topics.extend(indexed.iter().map(|a| H256::from(*a)));
+        Log {
+            topics,
+            data: encode(&[
+                Token::Uint(activated_at.into()),
+                Token::Uint(old_bips.into()),
+                Token::Uint(new_bips.into()),
+            ])
+            .into(),
+            block_number: Some(100u64.into()),
+            transaction_hash: Some(H256::repeat_byte(0x77)),
+            ..Default::default()
+        }
+    }
+
+    fn avs_split(old_bips: u16, new_bips: u16) -> Log {
+        split_log(OperatorAvsSplitEvent::signature(), &[OPERATOR, AVS], 1_700_000_000, old_bips, new_bips)
+    }
+
+    fn pi_split(operator: Address, old_bips: u16, new_bips: u16) -> Log {
+        split_log(OperatorPiSplitEvent::signature(), &[operator], 1_700_000_100, old_bips, new_bips)
+    }
+
+    fn watcher(provider: Provider<MockProvider>) -> CommissionWatcher<Provider<MockProvider>> {
+        CommissionWatcher::new(Arc::new(provider), Address::repeat_byte(0xd1), Address::repeat_byte(0xc0))
+    }
+
+    #[test]
+    fn test_split_events_become_changes() {
+        let raised = split_change(avs_split(1_000, 1_500)).unwrap();
+        assert!(matches!(raised.parameter, Parameter::OperatorAvsSplit { operator, avs } if operator == OPERATOR && avs == AVS));
+        assert_eq!((raised.before, raised.after), (U256::from(1_000), U256::from(1_500)));
+        assert_eq!(raised.effective_at, 1_700_000_000);
+        assert_eq!(raised.block, 100);
+        assert!(raised.is_adverse());
+
+        let lowered = split_change(pi_split(OPERATOR, 500, 300)).unwrap();
+        assert!(matches!(lowered.parameter, Parameter::OperatorPiSplit { operator } if operator == OPERATOR));
+        assert!(!lowered.is_adverse());
+
+        let unrelated = Log { topics: vec![H256::repeat_byte(0x01)], ..avs_split(1, 2) };
+        assert!(split_change(unrelated).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_poll_alerts_delegators_of_the_changed_operator_once() {
+        let (provider, mock) = Provider::mocked();
+        let watcher = watcher(provider);
+        watcher.watch(STAKER);
+
+        // Ответы снимаются с конца: логи, код Multicall3 (нет), `delegatedTo` на каждое изменение
+        let delegated = Bytes::from(encode(&[Token::Address(OPERATOR)]));
+        mock.push::<Bytes, _>(delegated.clone()).unwrap();
+        mock.push::<Bytes, _>(delegated).unwrap();
+        mock.push::<Bytes, _>(Bytes::new()).unwrap();
+        mock.push::<Vec<Log>, _>(vec![avs_split(1_000, 1_500), pi_split(OTHER_OPERATOR, 500, 300)]).unwrap();
+
+        let alerts = watcher.poll(100).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].level, AlertLevel::High);
+        assert_eq!(alerts[0].subject, format!("{:?}", STAKER));
+
+        // Блок уже просмотрен: без запросов к узлу
+        assert!(watcher.poll(100).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_counts_failed_scans() {
+        let (provider, _mock) = Provider::mocked();
+        let watcher = watcher(provider);
+        let (heads, receiver) = watch::channel(0);
+        heads.send(100).unwrap();
+        drop(heads);
+
+        watcher.run(receiver, AlertBus::new(8)).await;
+        let errors = watcher.errors();
+        assert_eq!(errors.errors, 1);
+        assert!(errors.last.unwrap().message.contains("block 100"));
+    }
+}
@@ -1,6 +1,20 @@
+pub mod backtest;
+#[path = "../beacon_ops.rs"]
+pub mod beacon_ops;
+pub mod commission;
+#[path = "../dvt.rs"]
+pub mod dvt;
+#[path = "../income.rs"]
+pub mod income;
 pub mod validator;
+pub mod multisig;
+pub mod rebalance;
+pub mod operator;
 pub mod restaking;
 pub mod risks;
+pub mod safe;
+#[path = "../tax.rs"]
+pub mod tax;
 
 use ethers::types::{Address, U256};
 use serde::{Serialize, Deserialize};
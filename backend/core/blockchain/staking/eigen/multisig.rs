@@ -0,0 +1,564 @@
+use super::restaking::{restake_calldata, withdraw_calldata};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ethers::{
+    abi::{encode, Token},
+    prelude::*,
+    types::transaction::eip2718::TypedTransaction,
+    utils::parse_units,
+};
+use mevdetector::admin::{self, AdminState};
+use mevdetector::audit::{AuditLog, AuditResult};
+use mevdetector::capability::{Capabilities, CapabilityError, SigningCapability};
+use mevdetector::compat::keccak256;
+use mevdetector::secrets::SecretManager;
+use mevdetector::store::{SharedStore, StoreError, StoreExt};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+abigen!(
+    GnosisSafe,
+    r#"[
+        function nonce() external view returns (uint256)
+        function getThreshold() external view returns (uint256)
+        function getOwners() external view returns (address[])
+        function execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures) external payable returns (bool success)
+    ]"#
+);
+
+/// Пространство имён хранилища для предложений
+const PROPOSALS_NS: &str = "multisig_proposals";
+
+#[derive(Debug, Error)]
+pub enum MultisigError {
+    #[error("Contract error: {0}")]
+    ContractError(String),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Proposal not found: {0}")]
+    NotFound(String),
+
+    #[error("Signature is not from a Safe owner: {0:?}")]
+    NotAnOwner(Address),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Threshold not met: {got} of {threshold} signatures")]
+    ThresholdNotMet { got: usize, threshold: usize },
+
+    #[error("Proposal {0} is already closed")]
+    AlreadyClosed(String),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error(transparent)]
+    SigningDisabled(#[from] CapabilityError),
+}
+
+/// Транзакция Safe (структура `SafeTx` из контракта)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafeTx {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    /// 0 — CALL, 1 — DELEGATECALL
+    pub operation: u8,
+    pub safe_tx_gas: U256,
+    pub base_gas: U256,
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    pub nonce: U256,
+}
+
+impl SafeTx {
+    /// Обычный CALL без возмещения газа
+    pub fn call(to: Address, data: Bytes, nonce: U256) -> Self {
+        Self {
+            to,
+            value: U256::zero(),
+            data,
+            operation: 0,
+            safe_tx_gas: U256::zero(),
+            base_gas: U256::zero(),
+            gas_price: U256::zero(),
+            gas_token: Address::zero(),
+            refund_receiver: Address::zero(),
+            nonce,
+        }
+    }
+
+    /// EIP-712 хэш, который подписывают владельцы (safeTxHash)
+    pub fn hash(&self, chain_id: u64, safe: Address) -> H256 {
+        let domain_typehash = keccak256("EIP712Domain(uint256 chainId,address verifyingContract)");
+        let domain = keccak256(encode(&[
+            Token::FixedBytes(domain_typehash.as_bytes().to_vec()),
+            Token::Uint(chain_id.into()),
+            Token::Address(safe),
+        ]));
+
+        let safe_tx_typehash = keccak256(
+            "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)",
+        );
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(safe_tx_typehash.as_bytes().to_vec()),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::FixedBytes(keccak256(&self.data).as_bytes().to_vec()),
+            Token::Uint(self.operation.into()),
+            Token::Uint(self.safe_tx_gas),
+            Token::Uint(self.base_gas),
+            Token::Uint(self.gas_price),
+            Token::Address(self.gas_token),
+            Token::Address(self.refund_receiver),
+            Token::Uint(self.nonce),
+        ]));
+
+        let mut message = vec![0x19, 0x01];
+        message.extend_from_slice(domain.as_bytes());
+        message.extend_from_slice(struct_hash.as_bytes());
+        keccak256(message)
+    }
+
+    /// Типизированные данные для `eth_signTypedData_v4` (аппаратные кошельки, Safe UI)
+    pub fn typed_data(&self, chain_id: u64, safe: Address) -> serde_json::Value {
+        serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "SafeTx": [
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "operation", "type": "uint8" },
+                    { "name": "safeTxGas", "type": "uint256" },
+                    { "name": "baseGas", "type": "uint256" },
+                    { "name": "gasPrice", "type": "uint256" },
+                    { "name": "gasToken", "type": "address" },
+                    { "name": "refundReceiver", "type": "address" },
+                    { "name": "nonce", "type": "uint256" }
+                ]
+            },
+            "primaryType": "SafeTx",
+            "domain": { "chainId": chain_id, "verifyingContract": safe },
+            "message": {
+                "to": self.to,
+                "value": self.value.to_string(),
+                "data": self.data,
+                "operation": self.operation,
+                "safeTxGas": self.safe_tx_gas.to_string(),
+                "baseGas": self.base_gas.to_string(),
+                "gasPrice": self.gas_price.to_string(),
+                "gasToken": self.gas_token,
+                "refundReceiver": self.refund_receiver,
+                "nonce": self.nonce.to_string()
+            }
+        })
+    }
+}
+
+/// Операция, требующая согласования
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MultisigOperation {
+    Restake { validator: Address, amount_eth: f64 },
+    Withdraw { validator: Address, amount_eth: f64 },
+}
+
+impl MultisigOperation {
+    fn calldata(&self) -> Result<Bytes, MultisigError> {
+        let (validator, amount_eth, encoder): (_, _, fn(Address, U256) -> Bytes) = match self {
+            MultisigOperation::Restake { validator, amount_eth } => (*validator, *amount_eth, restake_calldata),
+            MultisigOperation::Withdraw { validator, amount_eth } => (*validator, *amount_eth, withdraw_calldata),
+        };
+        let amount = parse_units(amount_eth, "ether").map_err(|e| MultisigError::InvalidAmount(e.to_string()))?;
+        Ok(encoder(validator, amount.into()))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            MultisigOperation::Restake { .. } => "restake",
+            MultisigOperation::Withdraw { .. } => "withdraw",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Executed { tx_hash: H256 },
+    Failed { error: String },
+}
+
+/// Предложение: неподписанная SafeTx и собранные подписи владельцев
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    /// safeTxHash в hex
+    pub id: String,
+    pub safe: Address,
+    pub chain_id: u64,
+    pub operation: MultisigOperation,
+    pub safe_tx: SafeTx,
+    pub owners: Vec<Address>,
+    pub threshold: usize,
+    pub signatures: BTreeMap<Address, Bytes>,
+    pub status: ProposalStatus,
+    pub created_at: u64,
+}
+
+impl Proposal {
+    pub fn is_ready(&self) -> bool {
+        self.signatures.len() >= self.threshold
+    }
+
+    /// Подписи для `execTransaction`: по возрастанию адреса владельца, r||s||v
+    fn packed_signatures(&self) -> Bytes {
+        // BTreeMap<Address, _> уже упорядочен по адресу
+        self.signatures.values().flat_map(|s| s.to_vec()).collect::<Vec<u8>>().into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// EIP-712 SafeTx для подписи владельцами
+    Eip712,
+    /// Неподписанный внутренний вызов (RLP) для внешних инструментов
+    Raw,
+}
+
+/// Подготовка, согласование и исполнение транзакций казначейского Safe
+pub struct MultisigWorkflow<M> {
+    provider: Arc<M>,
+    eigen_contract: Address,
+    store: SharedStore,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl<M: Middleware + 'static> MultisigWorkflow<M> {
+    pub fn new(provider: Arc<M>, eigen_contract: Address, store: SharedStore) -> Self {
+        Self {
+            provider,
+            eigen_contract,
+            store,
+            audit: None,
+        }
+    }
+
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn provider(&self) -> &Arc<M> {
+        &self.provider
+    }
+
+    pub fn eigen_contract(&self) -> Address {
+        self.eigen_contract
+    }
+
+    fn contract_error(e: impl std::fmt::Display) -> MultisigError {
+        MultisigError::ContractError(e.to_string())
+    }
+
+    pub fn load(&self, id: &str) -> Result<Proposal, MultisigError> {
+        self.store
+            .get_json(PROPOSALS_NS, id)?
+            .ok_or_else(|| MultisigError::NotFound(id.to_string()))
+    }
+
+    pub fn save(&self, proposal: &Proposal) -> Result<(), MultisigError> {
+        Ok(self.store.put_json(PROPOSALS_NS, &proposal.id, proposal)?)
+    }
+
+    pub fn list(&self) -> Result<Vec<Proposal>, MultisigError> {
+        Ok(self.store.list_json(PROPOSALS_NS)?.into_iter().map(|(_, p)| p).collect())
+    }
+
+    /// Собирает SafeTx по текущему nonce, владельцам и порогу Safe; ничего не подписывает
+    pub async fn prepare(&self, safe: Address, operation: MultisigOperation) -> Result<Proposal, MultisigError> {
+        let contract = GnosisSafe::new(safe, self.provider.clone());
+        let nonce = contract.nonce().call().await.map_err(Self::contract_error)?;
+        let threshold = contract.get_threshold().call().await.map_err(Self::contract_error)?;
+        let owners = contract.get_owners().call().await.map_err(Self::contract_error)?;
+        let chain_id = self.provider.get_chainid().await.map_err(Self::contract_error)?.as_u64();
+
+        let safe_tx = SafeTx::call(self.eigen_contract, operation.calldata()?, nonce);
+        let proposal = Proposal {
+            id: format!("{:?}", safe_tx.hash(chain_id, safe)),
+            safe,
+            chain_id,
+            operation,
+            safe_tx,
+            owners,
+            threshold: threshold.as_usize(),
+            signatures: BTreeMap::new(),
+            status: ProposalStatus::Pending,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        self.save(&proposal)?;
+        Ok(proposal)
+    }
+
+    pub fn export(&self, id: &str, format: ExportFormat) -> Result<serde_json::Value, MultisigError> {
+        let proposal = self.load(id)?;
+        Ok(match format {
+            ExportFormat::Eip712 => serde_json::json!({
+                "safe_tx_hash": proposal.id,
+                "typed_data": proposal.safe_tx.typed_data(proposal.chain_id, proposal.safe),
+            }),
+            ExportFormat::Raw => {
+                let tx: TypedTransaction = Eip1559TransactionRequest::new()
+                    .to(proposal.safe_tx.to)
+                    .value(proposal.safe_tx.value)
+                    .data(proposal.safe_tx.data.clone())
+                    .chain_id(proposal.chain_id)
+                    .into();
+                serde_json::json!({
+                    "chain_id": proposal.chain_id,
+                    "unsigned_rlp": tx.rlp(),
+                })
+            }
+        })
+    }
+
+    /// Принимает EIP-712 подпись владельца; подписант восстанавливается из подписи
+    pub fn add_signature(&self, id: &str, signature: &str) -> Result<Proposal, MultisigError> {
+        let mut proposal = self.load(id)?;
+        if !matches!(proposal.status, ProposalStatus::Pending) {
+            return Err(MultisigError::AlreadyClosed(proposal.id));
+        }
+
+        let signature: Signature = signature
+            .parse()
+            .map_err(|e: SignatureError| MultisigError::InvalidSignature(e.to_string()))?;
+        let hash = proposal.safe_tx.hash(proposal.chain_id, proposal.safe);
+        let signer = signature
+            .recover(hash)
+            .map_err(|e| MultisigError::InvalidSignature(e.to_string()))?;
+        if !proposal.owners.contains(&signer) {
+            return Err(MultisigError::NotAnOwner(signer));
+        }
+
+        proposal.signatures.insert(signer, signature.to_vec().into());
+        self.save(&proposal)?;
+        Ok(proposal)
+    }
+
+    /// Отправляет `execTransaction` только при достигнутом пороге; газ платит `executor`
+    pub async fn execute(&self, id: &str, executor: LocalWallet, _signing: &SigningCapability) -> Result<H256, MultisigError> {
+        let mut proposal = self.load(id)?;
+        if !matches!(proposal.status, ProposalStatus::Pending) {
+            return Err(MultisigError::AlreadyClosed(proposal.id));
+        }
+        if !proposal.is_ready() {
+            return Err(MultisigError::ThresholdNotMet {
+                got: proposal.signatures.len(),
+                threshold: proposal.threshold,
+            });
+        }
+
+        let executor_address = executor.address();
+        let client = Arc::new(SignerMiddleware::new(
+            self.provider.clone(),
+            executor.with_chain_id(proposal.chain_id),
+        ));
+        let safe_tx = &proposal.safe_tx;
+        let call = GnosisSafe::new(proposal.safe, client).exec_transaction(
+            safe_tx.to,
+            safe_tx.value,
+            safe_tx.data.clone(),
+            safe_tx.operation,
+            safe_tx.safe_tx_gas,
+            safe_tx.base_gas,
+            safe_tx.gas_price,
+            safe_tx.gas_token,
+            safe_tx.refund_receiver,
+            proposal.packed_signatures(),
+        );
+
+        let sent: Result<H256, MultisigError> = async {
+            let pending = call.send().await.map_err(Self::contract_error)?;
+            let receipt = pending
+                .await
+                .map_err(Self::contract_error)?
+                .ok_or_else(|| MultisigError::ContractError("transaction dropped".into()))?;
+            Ok(receipt.transaction_hash)
+        }
+        .await;
+
+        proposal.status = match &sent {
+            Ok(tx_hash) => ProposalStatus::Executed { tx_hash: *tx_hash },
+            Err(e) => ProposalStatus::Failed { error: e.to_string() },
+        };
+        self.save(&proposal)?;
+
+        if let Some(audit) = &self.audit {
+            let outcome = match &sent {
+                Ok(tx_hash) => AuditResult::Success { tx_hash: Some(format!("{:?}", tx_hash)) },
+                Err(e) => AuditResult::Failure { error: e.to_string() },
+            };
+            let payload = serde_json::json!({
+                "safe": proposal.safe,
+                "safe_tx_hash": proposal.id,
+                "operation": proposal.operation,
+                "approvers": proposal.signatures.keys().collect::<Vec<_>>(),
+            });
+            // Сбой журнала не должен скрыть хэш уже отправленной транзакции: запись ждёт в очереди
+            audit.append_or_defer(
+                "multisig",
+                proposal.operation.name(),
+                &format!("{:?}", executor_address),
+                payload,
+                outcome,
+            );
+        }
+
+        sent
+    }
+}
+
+/// Состояние HTTP API согласования
+pub struct MultisigApi<M> {
+    pub workflow: Arc<MultisigWorkflow<M>>,
+    /// Ссылка на ключ исполнителя (`env:`, `keystore:`, `vault:`)
+    pub executor_key: String,
+    /// Без права подписи исполнение через API отклоняется
+    pub capabilities: Capabilities,
+}
+
+impl<M> Clone for MultisigApi<M> {
+    fn clone(&self) -> Self {
+        Self {
+            workflow: self.workflow.clone(),
+            executor_key: self.executor_key.clone(),
+            capabilities: self.capabilities.clone(),
+        }
+    }
+}
+
+impl IntoResponse for MultisigError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            MultisigError::NotFound(_) => StatusCode::NOT_FOUND,
+            MultisigError::NotAnOwner(_) | MultisigError::SigningDisabled(_) => StatusCode::FORBIDDEN,
+            MultisigError::InvalidSignature(_) | MultisigError::InvalidAmount(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            MultisigError::ThresholdNotMet { .. } | MultisigError::AlreadyClosed(_) => StatusCode::CONFLICT,
+            MultisigError::ContractError(_) => StatusCode::BAD_GATEWAY,
+            MultisigError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(serde_json::json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateProposal {
+    safe: Address,
+    operation: MultisigOperation,
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    format: ExportFormat,
+}
+
+#[derive(Deserialize)]
+struct SubmitSignature {
+    signature: String,
+}
+
+async fn create_proposal<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+    Json(body): Json<CreateProposal>,
+) -> Result<Json<Proposal>, MultisigError> {
+    Ok(Json(api.workflow.prepare(body.safe, body.operation).await?))
+}
+
+async fn list_proposals<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+) -> Result<Json<Vec<Proposal>>, MultisigError> {
+    Ok(Json(api.workflow.list()?))
+}
+
+async fn get_proposal<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+    Path(id): Path<String>,
+) -> Result<Json<Proposal>, MultisigError> {
+    Ok(Json(api.workflow.load(&id)?))
+}
+
+async fn export_proposal<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+    Path(id): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<Json<serde_json::Value>, MultisigError> {
+    Ok(Json(api.workflow.export(&id, params.format)?))
+}
+
+async fn submit_signature<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+    Path(id): Path<String>,
+    Json(body): Json<SubmitSignature>,
+) -> Result<Json<Proposal>, MultisigError> {
+    Ok(Json(api.workflow.add_signature(&id, &body.signature)?))
+}
+
+async fn execute_proposal<M: Middleware + 'static>(
+    State(api): State<MultisigApi<M>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, MultisigError> {
+    let signing = api.capabilities.signing()?;
+    let executor = SecretManager::from_env()
+        .wallet(&api.executor_key, &signing)
+        .await
+        .map_err(|e| MultisigError::ContractError(e.to_string()))?;
+    let tx_hash = api.workflow.execute(&id, executor, &signing).await?;
+    Ok(Json(serde_json::json!({ "tx_hash": tx_hash })))
+}
+
+/// Маршруты закрыты bearer-токеном админ-API и монтируются рядом с ним (`admin::serve`)
+pub fn router<M: Middleware + 'static>(api: MultisigApi<M>, admin: &AdminState) -> Router {
+    let routes = Router::new()
+        .route("/multisig/proposals", get(list_proposals::<M>).post(create_proposal::<M>))
+        .route("/multisig/proposals/:id", get(get_proposal::<M>))
+        .route("/multisig/proposals/:id/export", get(export_proposal::<M>))
+        .route("/multisig/proposals/:id/signatures", post(submit_signature::<M>))
+        .route("/multisig/proposals/:id/execute", post(execute_proposal::<M>))
+        .with_state(api);
+    admin::protect(routes, admin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_signature_recovers_over_safe_tx_hash() {
+        let owner: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let safe = Address::repeat_byte(0x5a);
+        let tx = SafeTx::call(Address::repeat_byte(0xee), Bytes::from(vec![0x12, 0x34]), U256::from(7));
+
+        let hash = tx.hash(1, safe);
+        assert_ne!(hash, tx.hash(17000, safe));
+
+        let signature = owner.sign_hash(hash).unwrap();
+        assert_eq!(signature.recover(hash).unwrap(), owner.address());
+    }
+}
@@ -0,0 +1,600 @@
+use ethers::abi::{encode, Token};
+use ethers::prelude::*;
+use mevdetector::audit::{AuditLog, AuditResult};
+use mevdetector::capability::SigningCapability;
+use mevdetector::compat::keccak256;
+use mevdetector::multicall::{Batch, BatchReader};
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+abigen!(
+    DelegationManager,
+    r#"[
+        function registerAsOperator(address initDelegationApprover, uint32 allocationDelay, string metadataURI) external
+        function updateOperatorMetadataURI(address operator, string metadataURI) external
+        function isOperator(address operator) external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    RegistryCoordinator,
+    r#"[
+        struct G1Point { uint256 X; uint256 Y; }
+        struct G2Point { uint256[2] X; uint256[2] Y; }
+        struct PubkeyRegistrationParams { G1Point pubkeyRegistrationSignature; G1Point pubkeyG1; G2Point pubkeyG2; }
+        struct SignatureWithSaltAndExpiry { bytes signature; bytes32 salt; uint256 expiry; }
+        struct OperatorSetParam { uint32 maxOperatorCount; uint16 kickBIPsOfOperatorStake; uint16 kickBIPsOfTotalStake; }
+        function registerOperator(bytes quorumNumbers, string socket, PubkeyRegistrationParams params, SignatureWithSaltAndExpiry operatorSignature) external
+        function deregisterOperator(bytes quorumNumbers) external
+        function quorumCount() external view returns (uint8)
+        function getOperatorStatus(address operator) external view returns (uint8)
+        function getOperatorId(address operator) external view returns (bytes32)
+        function getCurrentQuorumBitmap(bytes32 operatorId) external view returns (uint192)
+        function getOperatorSetParams(uint8 quorumNumber) external view returns (OperatorSetParam)
+        function serviceManager() external view returns (address)
+        function stakeRegistry() external view returns (address)
+        function blsApkRegistry() external view returns (address)
+        function indexRegistry() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    IndexRegistry,
+    r#"[
+        function totalOperatorsForQuorum(uint8 quorumNumber) external view returns (uint32)
+    ]"#
+);
+
+abigen!(
+    AvsStakeRegistry,
+    r#"[
+        function minimumStakeForQuorum(uint8 quorumNumber) external view returns (uint96)
+        function weightOfOperatorForQuorum(uint8 quorumNumber, address operator) external view returns (uint96)
+    ]"#
+);
+
+abigen!(
+    BlsApkRegistry,
+    r#"[
+        function operatorToPubkeyHash(address operator) external view returns (bytes32)
+    ]"#
+);
+
+abigen!(
+    AvsDirectory,
+    r#"[
+        function calculateOperatorAVSRegistrationDigestHash(address operator, address avs, bytes32 salt, uint256 expiry) external view returns (bytes32)
+        function operatorSaltIsSpent(address operator, bytes32 salt) external view returns (bool)
+    ]"#
+);
+
+/// DelegationManager EigenLayer в mainnet
+pub const MAINNET_DELEGATION_MANAGER: &str = "0x39053D51B77DC0d36036Fc1fCc8Cb819df8Ef37A";
+
+/// Ограничения метаданных оператора из документации EigenLayer
+const MAX_NAME_LEN: usize = 200;
+const MAX_DESCRIPTION_LEN: usize = 500;
+
+#[derive(Debug, Error)]
+pub enum OperatorError {
+    #[error("Contract error: {0}")]
+    ContractError(String),
+
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Invalid operator metadata: {}", .0.join("; "))]
+    InvalidMetadata(Vec<String>),
+
+    #[error("{0:?} is already registered as an operator")]
+    AlreadyRegistered(Address),
+
+    #[error("{0:?} is not registered as an operator")]
+    NotRegistered(Address),
+
+    #[error("AVS pre-flight check failed: {}", .0.join("; "))]
+    PreflightFailed(Vec<String>),
+
+    #[error("Signing error: {0}")]
+    SigningError(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorConfig {
+    pub delegation_manager: Address,
+    /// AVSDirectory: считает дайджест подписи регистрации оператора в AVS
+    pub avs_directory: Address,
+    /// Задержка вступления аллокаций в силу, в блоках
+    pub allocation_delay: u32,
+}
+
+/// Срок действия подписи регистрации в AVS
+const AVS_SIGNATURE_TTL_SECS: u64 = 3600;
+
+/// Статус оператора в `RegistryCoordinator`
+const STATUS_REGISTERED: u8 = 1;
+
+/// Стейк оператора в кворуме AVS против минимума и заполненность кворума
+#[derive(Debug, Clone, Serialize)]
+pub struct QuorumStake {
+    pub quorum: u8,
+    pub weight: U256,
+    pub minimum: U256,
+    pub operators: u32,
+    /// `maxOperatorCount` кворума
+    pub max_operators: u32,
+}
+
+impl QuorumStake {
+    pub fn is_sufficient(&self) -> bool {
+        self.weight >= self.minimum
+    }
+
+    /// В полный кворум `registerOperator` не пускает: нужна регистрация с вытеснением (churn)
+    pub fn is_full(&self) -> bool {
+        self.operators >= self.max_operators
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.is_sufficient() {
+            problems.push(format!("stake {} in quorum {} is below minimum {}", self.weight, self.quorum, self.minimum));
+        }
+        if self.is_full() {
+            problems.push(format!(
+                "quorum {} is full ({} of {} operators); registration needs churn approval",
+                self.quorum, self.operators, self.max_operators
+            ));
+        }
+        problems
+    }
+}
+
+/// Результат pre-flight проверки: всё, из-за чего транзакция откатится в контракте
+#[derive(Debug, Clone, Serialize)]
+pub struct AvsPreflight {
+    pub registry_coordinator: Address,
+    pub avs: Address,
+    pub quorums: Vec<QuorumStake>,
+    pub problems: Vec<String>,
+}
+
+impl AvsPreflight {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn into_result(self) -> Result<Self, OperatorError> {
+        if self.passed() {
+            Ok(self)
+        } else {
+            Err(OperatorError::PreflightFailed(self.problems))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AvsRegistration {
+    pub operator: Address,
+    pub avs: Address,
+    pub quorums: Vec<u8>,
+    pub tx_hash: H256,
+}
+
+/// JSON по `metadataURI`, который показывают приложение EigenLayer и AVS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorMetadata {
+    pub name: String,
+    pub website: String,
+    pub description: String,
+    /// PNG-логотип
+    pub logo: String,
+    #[serde(default)]
+    pub twitter: Option<String>,
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+impl OperatorMetadata {
+    /// Проверка схемы; возвращает все нарушения разом
+    pub fn validate(&self) -> Result<(), OperatorError> {
+        let mut problems = Vec::new();
+        if self.name.trim().is_empty() || self.name.len() > MAX_NAME_LEN {
+            problems.push(format!("name must be 1..={} characters", MAX_NAME_LEN));
+        }
+        if self.description.trim().is_empty() || self.description.len() > MAX_DESCRIPTION_LEN {
+            problems.push(format!("description must be 1..={} characters", MAX_DESCRIPTION_LEN));
+        }
+        if !is_http_url(&self.website) {
+            problems.push("website must be an http(s) URL".into());
+        }
+        if !is_http_url(&self.logo) || !self.logo.to_lowercase().ends_with(".png") {
+            problems.push("logo must be an http(s) URL of a .png image".into());
+        }
+        if let Some(twitter) = &self.twitter {
+            let host_ok = ["https://twitter.com/", "https://x.com/", "https://www.twitter.com/", "https://www.x.com/"]
+                .iter()
+                .any(|prefix| twitter.starts_with(prefix));
+            if !twitter.is_empty() && !host_ok {
+                problems.push("twitter must be a https://twitter.com/ or https://x.com/ profile URL".into());
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(OperatorError::InvalidMetadata(problems))
+        }
+    }
+}
+
+/// Скачивает метаданные по URI и проверяет схему; незнакомые поля и не-JSON — ошибка схемы
+pub async fn fetch_metadata(http: &reqwest::Client, metadata_uri: &str) -> Result<OperatorMetadata, OperatorError> {
+    if !is_http_url(metadata_uri) {
+        return Err(OperatorError::InvalidMetadata(vec!["metadata URI must be an http(s) URL".into()]));
+    }
+    let body = http.get(metadata_uri).send().await?.error_for_status()?.text().await?;
+    let metadata: OperatorMetadata = serde_json::from_str(&body)
+        .map_err(|e| OperatorError::InvalidMetadata(vec![format!("metadata is not valid JSON: {}", e)]))?;
+    metadata.validate()?;
+
+    let logo = http.head(&metadata.logo).send().await?;
+    let content_type = logo.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    if !logo.status().is_success() || !content_type.starts_with("image/png") {
+        return Err(OperatorError::InvalidMetadata(vec![format!("logo {} is not a reachable PNG", metadata.logo)]));
+    }
+    Ok(metadata)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorRegistration {
+    pub operator: Address,
+    pub tx_hash: H256,
+    pub metadata: OperatorMetadata,
+}
+
+/// Регистрация и обслуживание оператора EigenLayer от ключа оператора
+pub struct OperatorClient<M> {
+    provider: Arc<M>,
+    config: OperatorConfig,
+    wallet: LocalWallet,
+    http: reqwest::Client,
+    audit: Option<Arc<AuditLog>>,
+    _signing: SigningCapability,
+}
+
+impl<M: Middleware + 'static> OperatorClient<M> {
+    /// Клиент подписывает транзакции, поэтому собирается только с правом подписи узла
+    pub fn new(provider: Arc<M>, config: OperatorConfig, wallet: LocalWallet, signing: SigningCapability) -> Self {
+        Self { provider, config, wallet, http: reqwest::Client::new(), audit: None, _signing: signing }
+    }
+
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    fn contract_error(e: impl std::fmt::Display) -> OperatorError {
+        OperatorError::ContractError(e.to_string())
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    pub async fn is_registered(&self) -> Result<bool, OperatorError> {
+        DelegationManager::new(self.config.delegation_manager, self.provider.clone())
+            .is_operator(self.address())
+            .call()
+            .await
+            .map_err(Self::contract_error)
+    }
+
+    async fn signer(&self) -> Result<Arc<SignerMiddleware<Arc<M>, LocalWallet>>, OperatorError> {
+        let chain_id = self.provider.get_chainid().await.map_err(Self::contract_error)?.as_u64();
+        Ok(Arc::new(SignerMiddleware::new(self.provider.clone(), self.wallet.clone().with_chain_id(chain_id))))
+    }
+
+    async fn manager(&self) -> Result<DelegationManager<SignerMiddleware<Arc<M>, LocalWallet>>, OperatorError> {
+        Ok(DelegationManager::new(self.config.delegation_manager, self.signer().await?))
+    }
+
+    async fn send(&self, call: ContractCall<SignerMiddleware<Arc<M>, LocalWallet>, ()>) -> Result<H256, OperatorError> {
+        // Dry-run: откат (уже оператор, неверный approver) виден до траты газа
+        call.call().await.map_err(Self::contract_error)?;
+        let pending = call.send().await.map_err(Self::contract_error)?;
+        let receipt = pending
+            .await
+            .map_err(Self::contract_error)?
+            .ok_or_else(|| OperatorError::ContractError("transaction dropped".into()))?;
+        if receipt.status != Some(1.into()) {
+            return Err(OperatorError::ContractError(format!("transaction {:?} reverted", receipt.transaction_hash)));
+        }
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Транзакция к этому моменту уже могла уйти: сбой журнала не скрывает её хэш,
+    /// запись ждёт в очереди журнала
+    fn record(&self, action: &str, payload: serde_json::Value, result: &Result<H256, OperatorError>) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let outcome = match result {
+            Ok(tx_hash) => AuditResult::Success { tx_hash: Some(format!("{:?}", tx_hash)) },
+            Err(e) => AuditResult::Failure { error: e.to_string() },
+        };
+        audit.append_or_defer("operator", action, &format!("{:?}", self.address()), payload, outcome);
+    }
+
+    /// Регистрирует ключ клиента оператором. Метаданные скачиваются и проверяются до
+    /// отправки: с битым URI оператор не отображается в приложении EigenLayer.
+    /// `delegation_approver` — нулевой адрес, если делегировать может любой
+    pub async fn register(&self, metadata_uri: &str, delegation_approver: Address) -> Result<OperatorRegistration, OperatorError> {
+        let metadata = fetch_metadata(&self.http, metadata_uri).await?;
+        if self.is_registered().await? {
+            return Err(OperatorError::AlreadyRegistered(self.address()));
+        }
+
+        let call = self.manager().await?.register_as_operator(
+            delegation_approver,
+            self.config.allocation_delay,
+            metadata_uri.to_string(),
+        );
+        let sent = self.send(call).await;
+        let payload = serde_json::json!({
+            "delegation_manager": self.config.delegation_manager,
+            "metadata_uri": metadata_uri,
+            "delegation_approver": delegation_approver,
+            "allocation_delay": self.config.allocation_delay,
+        });
+        self.record("register_operator", payload, &sent);
+
+        Ok(OperatorRegistration { operator: self.address(), tx_hash: sent?, metadata })
+    }
+
+    /// Меняет `metadataURI` зарегистрированного оператора с той же проверкой
+    pub async fn update_metadata(&self, metadata_uri: &str) -> Result<OperatorRegistration, OperatorError> {
+        let metadata = fetch_metadata(&self.http, metadata_uri).await?;
+        if !self.is_registered().await? {
+            return Err(OperatorError::NotRegistered(self.address()));
+        }
+
+        let call = self.manager().await?.update_operator_metadata_uri(self.address(), metadata_uri.to_string());
+        let sent = self.send(call).await;
+        let payload = serde_json::json!({
+            "delegation_manager": self.config.delegation_manager,
+            "metadata_uri": metadata_uri,
+        });
+        self.record("update_operator_metadata", payload, &sent);
+
+        Ok(OperatorRegistration { operator: self.address(), tx_hash: sent?, metadata })
+    }
+
+    /// Проверки перед регистрацией в кворумах AVS: оператор EigenLayer, кворумы
+    /// существуют и не заполнены, оператор ещё не зарегистрирован, BLS-ключ заведён, стейка хватает
+    pub async fn avs_preflight(&self, registry_coordinator: Address, quorums: &[u8]) -> Result<AvsPreflight, OperatorError> {
+        let coordinator = RegistryCoordinator::new(registry_coordinator, self.provider.clone());
+        let operator = self.address();
+        let avs = coordinator.service_manager().call().await.map_err(Self::contract_error)?;
+        let mut problems = Vec::new();
+
+        if quorums.is_empty() {
+            problems.push("no quorums requested".to_string());
+        }
+        if !self.is_registered().await? {
+            problems.push(format!("{:?} is not registered as an EigenLayer operator", operator));
+        }
+        let status = coordinator.get_operator_status(operator).call().await.map_err(Self::contract_error)?;
+        if status == STATUS_REGISTERED {
+            let id = coordinator.get_operator_id(operator).call().await.map_err(Self::contract_error)?;
+            let bitmap = coordinator.get_current_quorum_bitmap(id).call().await.map_err(Self::contract_error)?;
+            for quorum in quorums.iter().filter(|q| bitmap.bit(**q as usize)) {
+                problems.push(format!("already registered in quorum {}", quorum));
+            }
+        }
+
+        let bls = coordinator.bls_apk_registry().call().await.map_err(Self::contract_error)?;
+        let pubkey_hash = BlsApkRegistry::new(bls, self.provider.clone())
+            .operator_to_pubkey_hash(operator)
+            .call()
+            .await
+            .map_err(Self::contract_error)?;
+        if pubkey_hash == [0u8; 32] {
+            problems.push("BLS public key is not registered with the AVS BLSApkRegistry".to_string());
+        }
+
+        let count = coordinator.quorum_count().call().await.map_err(Self::contract_error)?;
+        let stake_registry = AvsStakeRegistry::new(
+            coordinator.stake_registry().call().await.map_err(Self::contract_error)?,
+            self.provider.clone(),
+        );
+        let index_registry = IndexRegistry::new(
+            coordinator.index_registry().call().await.map_err(Self::contract_error)?,
+            self.provider.clone(),
+        );
+        // Параметры всех кворумов одним пакетом Multicall3
+        let mut batch = Batch::new();
+        let mut lookups = Vec::new();
+        for &quorum in quorums {
+            if quorum >= count {
+                problems.push(format!("quorum {} does not exist (AVS has {})", quorum, count));
+                continue;
+            }
+            lookups.push((
+                quorum,
+                batch.add(&stake_registry.minimum_stake_for_quorum(quorum)).map_err(Self::contract_error)?,
+                batch.add(&stake_registry.weight_of_operator_for_quorum(quorum, operator)).map_err(Self::contract_error)?,
+                batch.add(&coordinator.get_operator_set_params(quorum)).map_err(Self::contract_error)?,
+                batch.add(&index_registry.total_operators_for_quorum(quorum)).map_err(Self::contract_error)?,
+            ));
+        }
+        let results = BatchReader::new(self.provider.clone()).execute(&batch, None).await.map_err(Self::contract_error)?;
+        let mut stakes = Vec::new();
+        for (quorum, minimum, weight, params, operators) in lookups {
+            let (max_operators, _, _) = results.get(&params).map_err(Self::contract_error)?;
+            let stake = QuorumStake {
+                quorum,
+                weight: U256::from(results.get(&weight).map_err(Self::contract_error)?),
+                minimum: U256::from(results.get(&minimum).map_err(Self::contract_error)?),
+                operators: results.get(&operators).map_err(Self::contract_error)?,
+                max_operators,
+            };
+            problems.extend(stake.problems());
+            stakes.push(stake);
+        }
+
+        Ok(AvsPreflight { registry_coordinator, avs, quorums: stakes, problems })
+    }
+
+    /// Подпись оператора для AVSDirectory над дайджестом регистрации в `avs`
+    pub async fn avs_signature(&self, avs: Address) -> Result<SignatureWithSaltAndExpiry, OperatorError> {
+        let operator = self.address();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let salt = keccak256(encode(&[
+            Token::Address(operator),
+            Token::Address(avs),
+            Token::Uint(U256::from(now.as_nanos())),
+        ]))
+        .0;
+        let expiry = U256::from(now.as_secs() + AVS_SIGNATURE_TTL_SECS);
+
+        let directory = AvsDirectory::new(self.config.avs_directory, self.provider.clone());
+        if directory.operator_salt_is_spent(operator, salt).call().await.map_err(Self::contract_error)? {
+            return Err(OperatorError::SigningError("registration salt already spent".into()));
+        }
+        let digest = directory
+            .calculate_operator_avs_registration_digest_hash(operator, avs, salt, expiry)
+            .call()
+            .await
+            .map_err(Self::contract_error)?;
+        // Дайджест уже EIP-712: подписывается как есть, без префикса EIP-191
+        let signature = self
+            .wallet
+            .sign_hash(H256::from(digest))
+            .map_err(|e| OperatorError::SigningError(e.to_string()))?;
+        Ok(SignatureWithSaltAndExpiry { signature: signature.to_vec().into(), salt, expiry })
+    }
+
+    /// Регистрирует оператора в кворумах AVS после pre-flight проверки. BLS-ключ должен
+    /// быть заведён заранее: параметры ключа тогда контрактом не читаются
+    pub async fn register_with_avs(&self, registry_coordinator: Address, quorums: &[u8], socket: &str) -> Result<AvsRegistration, OperatorError> {
+        let preflight = self.avs_preflight(registry_coordinator, quorums).await?.into_result()?;
+        let signature = self.avs_signature(preflight.avs).await?;
+
+        let call = RegistryCoordinator::new(registry_coordinator, self.signer().await?).register_operator(
+            quorums.to_vec().into(),
+            socket.to_string(),
+            PubkeyRegistrationParams::default(),
+            signature,
+        );
+        let sent = self.send(call).await;
+        let payload = serde_json::json!({
+            "registry_coordinator": registry_coordinator,
+            "avs": preflight.avs,
+            "quorums": preflight.quorums,
+            "socket": socket,
+        });
+        self.record("register_avs", payload, &sent);
+
+        Ok(AvsRegistration { operator: self.address(), avs: preflight.avs, quorums: quorums.to_vec(), tx_hash: sent? })
+    }
+
+    /// Выход из кворумов AVS; кворумы, в которых оператора нет, отклоняются заранее
+    pub async fn deregister_from_avs(&self, registry_coordinator: Address, quorums: &[u8]) -> Result<AvsRegistration, OperatorError> {
+        let coordinator = RegistryCoordinator::new(registry_coordinator, self.provider.clone());
+        let operator = self.address();
+        let avs = coordinator.service_manager().call().await.map_err(Self::contract_error)?;
+
+        let mut problems = Vec::new();
+        if quorums.is_empty() {
+            problems.push("no quorums requested".to_string());
+        }
+        let status = coordinator.get_operator_status(operator).call().await.map_err(Self::contract_error)?;
+        if status != STATUS_REGISTERED {
+            problems.push(format!("{:?} is not registered with AVS {:?}", operator, avs));
+        } else {
+            let id = coordinator.get_operator_id(operator).call().await.map_err(Self::contract_error)?;
+            let bitmap = coordinator.get_current_quorum_bitmap(id).call().await.map_err(Self::contract_error)?;
+            for quorum in quorums.iter().filter(|q| !bitmap.bit(**q as usize)) {
+                problems.push(format!("not registered in quorum {}", quorum));
+            }
+        }
+        if !problems.is_empty() {
+            return Err(OperatorError::PreflightFailed(problems));
+        }
+
+        let call = RegistryCoordinator::new(registry_coordinator, self.signer().await?).deregister_operator(quorums.to_vec().into());
+        let sent = self.send(call).await;
+        let payload = serde_json::json!({
+            "registry_coordinator": registry_coordinator,
+            "avs": avs,
+            "quorums": quorums,
+        });
+        self.record("deregister_avs", payload, &sent);
+
+        Ok(AvsRegistration { operator, avs, quorums: quorums.to_vec(), tx_hash: sent? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> OperatorMetadata {
+        OperatorMetadata {
+            name: "DeFinetly".into(),
+            website: "https://definetly.example".into(),
+            description: "Restaking operator".into(),
+            logo: "https://definetly.example/logo.PNG".into(),
+            twitter: Some("https://x.com/definetly".into()),
+        }
+    }
+
+    #[test]
+    fn test_metadata_validation_reports_every_problem() {
+        metadata().validate().unwrap();
+        OperatorMetadata { twitter: Some(String::new()), ..metadata() }.validate().unwrap();
+
+        let broken = OperatorMetadata {
+            name: " ".into(),
+            website: "ftp://definetly.example".into(),
+            description: "x".repeat(MAX_DESCRIPTION_LEN + 1),
+            logo: "https://definetly.example/logo.svg".into(),
+            twitter: Some("https://mastodon.social/@definetly".into()),
+        };
+        let Err(OperatorError::InvalidMetadata(problems)) = broken.validate() else {
+            panic!("invalid metadata accepted");
+        };
+        assert_eq!(problems.len(), 5);
+
+        let unknown = r#"{"name":"a","website":"https://a","description":"b","logo":"https://a/l.png","telegram":"t"}"#;
+        assert!(serde_json::from_str::<OperatorMetadata>(unknown).is_err());
+    }
+
+    #[test]
+    fn test_quorum_preflight_problems() {
+        let stake = |weight: u64, operators| QuorumStake {
+            quorum: 0,
+            weight: weight.into(),
+            minimum: 100.into(),
+            operators,
+            max_operators: 50,
+        };
+        assert!(stake(100, 49).problems().is_empty());
+        assert_eq!(stake(99, 49).problems().len(), 1);
+        assert!(stake(100, 50).problems()[0].contains("is full"));
+        assert_eq!(stake(10, 50).problems().len(), 2);
+
+        let preflight = AvsPreflight {
+            registry_coordinator: Address::zero(),
+            avs: Address::zero(),
+            quorums: vec![stake(100, 50)],
+            problems: stake(100, 50).problems(),
+        };
+        assert!(matches!(preflight.into_result(), Err(OperatorError::PreflightFailed(p)) if p.len() == 1));
+    }
+}
@@ -5,6 +5,11 @@ use ethers::{
     signers::{LocalWallet, Signer},
     utils::{format_units, parse_units},
 };
+use mevdetector::audit::{AuditLog, AuditResult};
+use mevdetector::capability::SigningCapability;
+use mevdetector::gas_oracle::{FeeUrgency, GasOracle};
+use mevdetector::policy::{Confirmation, DryRun, PolicyEngine, PolicyError, SigningRequest};
+use mevdetector::units::Gwei;
 use serde::Serialize;
 use std::sync::Arc;
 use thiserror::Error;
@@ -14,8 +19,10 @@ use thiserror::Error;
 pub struct RestakingConfig {
     pub eigen_contract: Address,
     pub gas_limit: u64,
-    pub max_priority_fee_per_gas: f64, // В Gwei
-    pub max_fee_per_gas: f64,          // В Gwei
+    pub max_priority_fee_per_gas: Gwei,
+    pub max_fee_per_gas: Gwei,
+    /// Срочность при выборе комиссии по прогнозу; значения выше — потолки
+    pub fee_urgency: FeeUrgency,
 }
 
 /// Ошибки модуля
@@ -32,6 +39,9 @@ pub enum RestakingError {
     
     #[error("Transaction failed: {0}")]
     TransactionFailed(H256),
+
+    #[error("Signing policy violation: {0}")]
+    PolicyViolation(#[from] PolicyError),
 }
 
 /// Результат рестейкинга
@@ -46,11 +56,42 @@ pub struct RestakingResult {
 pub struct RestakingClient<M> {
     provider: Arc<M>,
     config: RestakingConfig,
+    audit: Option<Arc<AuditLog>>,
+    policy: Arc<PolicyEngine>,
+    gas_oracle: Option<Arc<GasOracle>>,
+    _signing: SigningCapability,
 }
 
 impl<M: Middleware> RestakingClient<M> {
-    pub fn new(provider: Arc<M>, config: RestakingConfig) -> Self {
-        Self { provider, config }
+    /// Клиент подписывает транзакции, поэтому собирается только с правом подписи узла
+    /// и политикой, которой проверяется каждая подпись
+    pub fn new(provider: Arc<M>, config: RestakingConfig, signing: SigningCapability, policy: Arc<PolicyEngine>) -> Self {
+        Self { provider, config, audit: None, policy, gas_oracle: None, _signing: signing }
+    }
+
+    /// Каждая подписанная транзакция (успешная или нет) записывается в журнал аудита
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Комиссия по прогнозу оракула вместо фиксированной, но не выше потолков конфигурации
+    pub fn with_gas_oracle(mut self, oracle: Arc<GasOracle>) -> Self {
+        self.gas_oracle = Some(oracle);
+        self
+    }
+
+    /// `(max_fee_per_gas, max_priority_fee_per_gas)` в wei
+    fn fees(&self) -> (U256, U256) {
+        let max_fee = self.config.max_fee_per_gas.wei();
+        let max_priority = self.config.max_priority_fee_per_gas.wei();
+        match self.gas_oracle.as_ref().and_then(|oracle| oracle.forecast(1)) {
+            Some(forecast) => {
+                let (fee, priority) = forecast.fees(self.config.fee_urgency);
+                (fee.min(max_fee), priority.min(max_priority))
+            }
+            None => (max_fee, max_priority),
+        }
     }
 
     /// Выполняет рестейкинг ETH в EigenLayer
@@ -59,33 +100,97 @@ impl<M: Middleware> RestakingClient<M> {
         wallet: LocalWallet,
         validator: Address,
         amount_eth: f64,
+    ) -> Result<RestakingResult, RestakingError> {
+        self.restake_eth_confirmed(wallet, validator, amount_eth, &[]).await
+    }
+
+    /// То же с подтверждениями операторов — для сумм выше порога политики. Операторы
+    /// подписывают `confirmation_message` запроса с nonce, который получит транзакция
+    pub async fn restake_eth_confirmed(
+        &self,
+        wallet: LocalWallet,
+        validator: Address,
+        amount_eth: f64,
+        confirmations: &[Confirmation],
+    ) -> Result<RestakingResult, RestakingError> {
+        let signer = wallet.address();
+        let result = self.send_restake(wallet, validator, amount_eth, confirmations).await;
+
+        if let Some(audit) = &self.audit {
+            let outcome = match &result {
+                Ok(r) => AuditResult::Success { tx_hash: Some(format!("{:?}", r.tx_hash)) },
+                Err(e) => AuditResult::Failure { error: e.to_string() },
+            };
+            let payload = serde_json::json!({
+                "contract": self.config.eigen_contract,
+                "validator": validator,
+                "amount_eth": amount_eth,
+            });
+            // Транзакция уже могла уйти: сбой журнала не должен скрыть её хэш
+            audit.append_or_defer("restaking", "restake_eth", &format!("{:?}", signer), payload, outcome);
+        }
+
+        result
+    }
+
+    async fn send_restake(
+        &self,
+        wallet: LocalWallet,
+        validator: Address,
+        amount_eth: f64,
+        confirmations: &[Confirmation],
     ) -> Result<RestakingResult, RestakingError> {
         // 1. Конвертация ETH в Wei
         let amount = parse_units(amount_eth, "ether")
             .map_err(|_| RestakingError::InvalidAmount("Failed to parse ETH amount".into()))?;
 
-        // 2. Формирование EIP-1559 транзакции
+        // 2. Формирование EIP-1559 транзакции; nonce фиксируется заранее, подтверждения операторов привязаны к нему
+        let (max_fee, max_priority_fee) = self.fees();
+        let nonce = self.provider.get_transaction_count(wallet.address(), Some(BlockNumber::Pending.into())).await?;
         let tx = Eip1559TransactionRequest::new()
             .to(self.config.eigen_contract)
             .chain_id(self.provider.get_chainid().await?.as_u64())
             .data(self.encode_restake_call(validator, amount))
             .gas(self.config.gas_limit)
-            .max_priority_fee_per_gas(
-                parse_units(self.config.max_priority_fee_per_gas, "gwei")?.into(),
-            )
-            .max_fee_per_gas(
-                parse_units(self.config.max_fee_per_gas, "gwei")?.into(),
-            );
-
-        // 3. Подпись и отправка
-        let signed_tx = wallet
-            .sign_transaction(&tx)
-            .await
-            .map_err(|e| RestakingError::SigningError(e.to_string()))?;
+            .max_priority_fee_per_gas(max_priority_fee)
+            .max_fee_per_gas(max_fee)
+            .nonce(nonce);
+
+        // 3. Политика: dry-run через eth_call, allowlist, лимиты; сумма резервируется
+        let call = tx.clone().from(wallet.address()).into();
+        let dry_run = match self.provider.call(&call, None).await {
+            Ok(_) => DryRun::Succeeded,
+            Err(e) => DryRun::Reverted(e.to_string()),
+        };
+        let request = SigningRequest {
+            wallet: wallet.address(),
+            to: self.config.eigen_contract,
+            value_eth: amount_eth,
+            nonce,
+            dry_run: Some(dry_run),
+            confirmations: confirmations.to_vec(),
+        };
+        self.policy.authorize(&request)?;
 
-        let pending_tx = self.provider.send_raw_transaction(signed_tx).await?;
+        // 4. Подпись и отправка; если транзакция не ушла, резерв возвращается
+        let sent: Result<_, RestakingError> = async {
+            let signed_tx = wallet
+                .sign_transaction(&tx)
+                .await
+                .map_err(|e| RestakingError::SigningError(e.to_string()))?;
+            Ok(self.provider.send_raw_transaction(signed_tx).await?)
+        }
+        .await;
 
-        // 4. Ожидание подтверждения
+        let pending_tx = match sent {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => {
+                self.policy.refund(&request)?;
+                return Err(e);
+            }
+        };
+
+        // 5. Ожидание подтверждения
         let receipt = pending_tx
             .await?
             .ok_or(RestakingError::TransactionFailed(pending_tx.tx_hash()))?;
@@ -99,46 +204,86 @@ impl<M: Middleware> RestakingClient<M> {
 
     /// Кодирует вызов метода `restake` в ABI
     fn encode_restake_call(&self, validator: Address, amount: U256) -> Bytes {
-        use ethers::abi::AbiEncode;
-        
-        // Сигнатура: restake(address validator, uint256 amount)
-        let mut data = vec![0x12, 0x34, 0x56, 0x78]; // Заглушка для примера
-        data.extend(validator.encode());
-        data.extend(amount.encode());
-        Bytes::from(data)
+        restake_calldata(validator, amount)
     }
 }
 
+/// Calldata `restake(address validator, uint256 amount)`
+pub(crate) fn restake_calldata(validator: Address, amount: U256) -> Bytes {
+    use ethers::abi::AbiEncode;
+
+    let mut data = vec![0x12, 0x34, 0x56, 0x78]; // Заглушка для примера
+    data.extend(validator.encode());
+    data.extend(amount.encode());
+    Bytes::from(data)
+}
+
+/// Calldata `withdraw(address validator, uint256 amount)`
+pub(crate) fn withdraw_calldata(validator: Address, amount: U256) -> Bytes {
+    use ethers::abi::AbiEncode;
+
+    let mut data = ethers::utils::id("withdraw(address,uint256)").to_vec();
+    data.extend(validator.encode());
+    data.extend(amount.encode());
+    Bytes::from(data)
+}
+
 /// FFI-интерфейс для Python
-#[cfg(feature = "ffi")]
+#[cfg(feature = "ffi-python")]
 pub mod ffi {
     use super::*;
+    use mevdetector::config::DefinetlyConfig;
+    use mevdetector::secrets::SecretManager;
+    use mevdetector::store::FileStore;
     use pyo3::prelude::*;
 
+    /// `key_ref` — ссылка на секрет (`env:`, `keystore:`, `vault:`), а не сам ключ.
+    /// Право подписи и политики кошельков берутся из конфига узла: узел с `read_only` не подпишет
+    /// и через FFI, а дневной лимит копится в хранилище узла
     #[pyfunction]
     fn restake_eth(
+        config_path: String,
         rpc_url: String,
         contract_addr: String,
-        priv_key: String,
+        key_ref: String,
         validator_addr: String,
         amount_eth: f64,
     ) -> PyResult<String> {
+        let node = DefinetlyConfig::load(std::path::Path::new(&config_path))
+            .map_err(|e| RestakingError::SigningError(e.to_string()))?;
+        let signing = node
+            .capabilities()
+            .signing()
+            .map_err(|e| RestakingError::SigningError(e.to_string()))?;
+
         let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet = priv_key.parse::<LocalWallet>()?;
         let config = RestakingConfig {
             eigen_contract: contract_addr.parse()?,
             gas_limit: 300_000,
-            max_priority_fee_per_gas: 2.0,
-            max_fee_per_gas: 150.0,
+            max_priority_fee_per_gas: Gwei::new(2.0).unwrap(),
+            max_fee_per_gas: Gwei::new(150.0).unwrap(),
+            fee_urgency: FeeUrgency::Normal,
         };
 
-        let client = RestakingClient::new(Arc::new(provider), config);
+        let validator: Address = validator_addr.parse()?;
+
+        let store_path = node
+            .monitor
+            .as_ref()
+            .map(|monitor| monitor.store_path.clone())
+            .ok_or_else(|| RestakingError::SigningError("signing policy needs [monitor] store_path".into()))?;
+        let store = FileStore::open(store_path).map_err(|e| RestakingError::SigningError(e.to_string()))?;
+        let policy = Arc::new(PolicyEngine::new(node.policies.clone(), Arc::new(store)));
+
+        let client = RestakingClient::new(Arc::new(provider), config, signing.clone(), policy);
         let result = tokio::runtime::Runtime::new()?
-            .block_on(client.restake_eth(
-                wallet,
-                validator_addr.parse()?,
-                amount_eth,
-            ))?;
+            .block_on(async {
+                let wallet = SecretManager::from_env()
+                    .wallet(&key_ref, &signing)
+                    .await
+                    .map_err(|e| RestakingError::SigningError(e.to_string()))?;
+                client.restake_eth(wallet, validator, amount_eth).await
+            })?;
 
         Ok(serde_json::to_string(&result)?)
     }
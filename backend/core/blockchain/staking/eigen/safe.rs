@@ -0,0 +1,214 @@
+use super::multisig::{MultisigError, MultisigOperation, MultisigWorkflow, Proposal, SafeTx};
+use ethers::prelude::*;
+use mevdetector::capability::SigningCapability;
+use mevdetector::compat::to_checksum;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SafeServiceError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Transaction service rejected request ({status}): {body}")]
+    Rejected { status: u16, body: String },
+
+    #[error("No Safe transaction service known for chain {0}")]
+    UnsupportedChain(u64),
+
+    #[error(transparent)]
+    Multisig(#[from] MultisigError),
+}
+
+/// Адреса официального Safe Transaction Service по сетям
+pub fn service_url(chain_id: u64) -> Option<&'static str> {
+    match chain_id {
+        1 => Some("https://safe-transaction-mainnet.safe.global"),
+        10 => Some("https://safe-transaction-optimism.safe.global"),
+        137 => Some("https://safe-transaction-polygon.safe.global"),
+        8453 => Some("https://safe-transaction-base.safe.global"),
+        17000 => Some("https://safe-transaction-holesky.safe.global"),
+        42161 => Some("https://safe-transaction-arbitrum.safe.global"),
+        11155111 => Some("https://safe-transaction-sepolia.safe.global"),
+        _ => None,
+    }
+}
+
+/// Тело предложения в формате transaction service
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProposeBody<'a> {
+    to: Address,
+    value: String,
+    data: &'a Bytes,
+    operation: u8,
+    safe_tx_gas: String,
+    base_gas: String,
+    gas_price: String,
+    gas_token: Address,
+    refund_receiver: Address,
+    nonce: String,
+    contract_transaction_hash: &'a str,
+    sender: Address,
+    signature: String,
+    origin: &'static str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceConfirmation {
+    pub owner: Address,
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    results: Vec<T>,
+}
+
+/// Клиент Safe Transaction Service
+pub struct SafeServiceClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl SafeServiceClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn for_chain(chain_id: u64) -> Result<Self, SafeServiceError> {
+        service_url(chain_id)
+            .map(Self::new)
+            .ok_or(SafeServiceError::UnsupportedChain(chain_id))
+    }
+
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, SafeServiceError> {
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            Err(SafeServiceError::Rejected {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            })
+        }
+    }
+
+    /// Публикует SafeTx с подписью предлагающего владельца
+    pub async fn propose(
+        &self,
+        safe: Address,
+        tx: &SafeTx,
+        safe_tx_hash: &str,
+        sender: Address,
+        signature: &Signature,
+    ) -> Result<(), SafeServiceError> {
+        let body = ProposeBody {
+            to: tx.to,
+            value: tx.value.to_string(),
+            data: &tx.data,
+            operation: tx.operation,
+            safe_tx_gas: tx.safe_tx_gas.to_string(),
+            base_gas: tx.base_gas.to_string(),
+            gas_price: tx.gas_price.to_string(),
+            gas_token: tx.gas_token,
+            refund_receiver: tx.refund_receiver,
+            nonce: tx.nonce.to_string(),
+            contract_transaction_hash: safe_tx_hash,
+            sender,
+            signature: format!("0x{}", signature),
+            origin: "definetly",
+        };
+        let url = format!("{}/api/v1/safes/{}/multisig-transactions/", self.base_url, to_checksum(&safe));
+        Self::check(self.http.post(url).json(&body).send().await?).await?;
+        Ok(())
+    }
+
+    pub async fn confirmations(&self, safe_tx_hash: &str) -> Result<Vec<ServiceConfirmation>, SafeServiceError> {
+        let url = format!("{}/api/v1/multisig-transactions/{}/confirmations/", self.base_url, safe_tx_hash);
+        let page: Page<ServiceConfirmation> = Self::check(self.http.get(url).send().await?).await?.json().await?;
+        Ok(page.results)
+    }
+
+    pub async fn confirm(&self, safe_tx_hash: &str, signature: &Signature) -> Result<(), SafeServiceError> {
+        let url = format!("{}/api/v1/multisig-transactions/{}/confirmations/", self.base_url, safe_tx_hash);
+        let body = serde_json::json!({ "signature": format!("0x{}", signature) });
+        Self::check(self.http.post(url).json(&body).send().await?).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Execution {
+    Executed { tx_hash: H256 },
+    Waiting { signatures: usize, threshold: usize },
+}
+
+/// Предложение, подтверждения и исполнение через Safe поверх `MultisigWorkflow`:
+/// подписи собираются в transaction service, а не через собственное API
+pub struct SafeSubmitter<M> {
+    workflow: Arc<MultisigWorkflow<M>>,
+    service: SafeServiceClient,
+    signing: SigningCapability,
+}
+
+impl<M: Middleware + 'static> SafeSubmitter<M> {
+    /// Подписывает предложения и исполняет их, поэтому собирается только с правом подписи узла
+    pub fn new(workflow: Arc<MultisigWorkflow<M>>, service: SafeServiceClient, signing: SigningCapability) -> Self {
+        Self { workflow, service, signing }
+    }
+
+    /// Готовит SafeTx, подписывает её ключом владельца и публикует в сервисе
+    pub async fn propose(
+        &self,
+        safe: Address,
+        operation: MultisigOperation,
+        proposer: &LocalWallet,
+    ) -> Result<Proposal, SafeServiceError> {
+        let proposal = self.workflow.prepare(safe, operation).await?;
+        let hash = proposal.safe_tx.hash(proposal.chain_id, proposal.safe);
+        let signature = proposer
+            .sign_hash(hash)
+            .map_err(|e| MultisigError::InvalidSignature(e.to_string()))?;
+
+        let proposal = self.workflow.add_signature(&proposal.id, &signature.to_string())?;
+        self.service
+            .propose(safe, &proposal.safe_tx, &proposal.id, proposer.address(), &signature)
+            .await?;
+        Ok(proposal)
+    }
+
+    /// Подтягивает подтверждения владельцев из сервиса; каждая подпись проверяется заново
+    pub async fn sync_confirmations(&self, id: &str) -> Result<Proposal, SafeServiceError> {
+        let mut proposal = self.workflow.load(id)?;
+        for confirmation in self.service.confirmations(id).await? {
+            if proposal.signatures.contains_key(&confirmation.owner) {
+                continue;
+            }
+            match self.workflow.add_signature(id, &confirmation.signature) {
+                Ok(updated) => proposal = updated,
+                // Чужие или подписи eth_sign (v > 30) не принимаем, но и не прерываемся
+                Err(MultisigError::NotAnOwner(_) | MultisigError::InvalidSignature(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(proposal)
+    }
+
+    /// Исполняет, если порог достигнут; иначе сообщает, сколько подписей собрано
+    pub async fn execute_when_ready(&self, id: &str, executor: LocalWallet) -> Result<Execution, SafeServiceError> {
+        let proposal = self.sync_confirmations(id).await?;
+        if !proposal.is_ready() {
+            return Ok(Execution::Waiting {
+                signatures: proposal.signatures.len(),
+                threshold: proposal.threshold,
+            });
+        }
+        Ok(Execution::Executed { tx_hash: self.workflow.execute(id, executor, &self.signing).await? })
+    }
+}
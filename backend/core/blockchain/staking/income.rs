@@ -0,0 +1,339 @@
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockId, U256};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+use thiserror::Error;
+
+const SECONDS_PER_SLOT: u64 = 12;
+const SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(Debug, Error)]
+pub enum IncomeError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Beacon API returned unexpected data: {0}")]
+    InvalidResponse(String),
+
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Invalid time range {0}..{1}")]
+    InvalidRange(u64, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Proposed,
+    Missed,
+}
+
+/// Один слот, назначенный валидатору
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposalRecord {
+    pub slot: u64,
+    /// UNIX-время начала слота
+    pub timestamp: u64,
+    pub validator_index: u64,
+    pub status: ProposalStatus,
+    pub block_number: Option<u64>,
+    /// Награда consensus layer за блок, gwei
+    pub consensus_reward_gwei: u64,
+    /// Приоритетные комиссии, полученные fee recipient валидатора напрямую (блок без MEV-Boost)
+    pub priority_fees_wei: U256,
+    /// Платёж билдера последней транзакцией блока (MEV-Boost)
+    pub mev_payment_wei: U256,
+}
+
+impl ProposalRecord {
+    /// Всё, что валидатор получил за блок, в wei
+    pub fn total_wei(&self) -> U256 {
+        U256::from(self.consensus_reward_gwei) * U256::exp10(9) + self.priority_fees_wei + self.mev_payment_wei
+    }
+}
+
+/// Итоги по одному валидатору
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidatorIncome {
+    pub validator_index: u64,
+    pub proposed: usize,
+    pub missed: usize,
+    pub consensus_rewards_gwei: u64,
+    pub priority_fees_wei: U256,
+    pub mev_payments_wei: U256,
+    /// Пропущенные слоты, оценённые медианным доходом блока за период
+    pub opportunity_cost_wei: U256,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomeReport {
+    pub from_timestamp: u64,
+    pub to_timestamp: u64,
+    pub from_slot: u64,
+    pub to_slot: u64,
+    pub validators: Vec<ValidatorIncome>,
+    pub proposals: Vec<ProposalRecord>,
+}
+
+fn median(values: &mut [U256]) -> U256 {
+    if values.is_empty() {
+        return U256::zero();
+    }
+    values.sort();
+    values[values.len() / 2]
+}
+
+impl IncomeReport {
+    /// Сводит записи слотов в итоги по валидаторам
+    pub fn build(from_timestamp: u64, to_timestamp: u64, from_slot: u64, to_slot: u64, proposals: Vec<ProposalRecord>) -> Self {
+        let mut totals: Vec<U256> = proposals
+            .iter()
+            .filter(|p| p.status == ProposalStatus::Proposed)
+            .map(ProposalRecord::total_wei)
+            .collect();
+        let typical = median(&mut totals);
+
+        let mut by_validator: BTreeMap<u64, ValidatorIncome> = BTreeMap::new();
+        for p in &proposals {
+            let income = by_validator.entry(p.validator_index).or_insert_with(|| ValidatorIncome {
+                validator_index: p.validator_index,
+                ..ValidatorIncome::default()
+            });
+            match p.status {
+                ProposalStatus::Proposed => {
+                    income.proposed += 1;
+                    income.consensus_rewards_gwei += p.consensus_reward_gwei;
+                    income.priority_fees_wei += p.priority_fees_wei;
+                    income.mev_payments_wei += p.mev_payment_wei;
+                }
+                ProposalStatus::Missed => {
+                    income.missed += 1;
+                    income.opportunity_cost_wei += typical;
+                }
+            }
+        }
+
+        Self {
+            from_timestamp,
+            to_timestamp,
+            from_slot,
+            to_slot,
+            validators: by_validator.into_values().collect(),
+            proposals,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Построчно по слотам — формат для бухгалтерии
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "slot,validator_index,status,block_number,consensus_reward_gwei,priority_fees_wei,mev_payment_wei,total_wei\n",
+        );
+        for p in &self.proposals {
+            let status = match p.status {
+                ProposalStatus::Proposed => "proposed",
+                ProposalStatus::Missed => "missed",
+            };
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                p.slot,
+                p.validator_index,
+                status,
+                p.block_number.map(|b| b.to_string()).unwrap_or_default(),
+                p.consensus_reward_gwei,
+                p.priority_fees_wei,
+                p.mev_payment_wei,
+                p.total_wei(),
+            );
+        }
+        out
+    }
+
+    /// Итоги по валидаторам в CSV
+    pub fn summary_csv(&self) -> String {
+        let mut out = String::from(
+            "validator_index,proposed,missed,consensus_rewards_gwei,priority_fees_wei,mev_payments_wei,opportunity_cost_wei\n",
+        );
+        for v in &self.validators {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                v.validator_index,
+                v.proposed,
+                v.missed,
+                v.consensus_rewards_gwei,
+                v.priority_fees_wei,
+                v.mev_payments_wei,
+                v.opportunity_cost_wei,
+            );
+        }
+        out
+    }
+}
+
+fn field<'a>(value: &'a Value, path: &[&str]) -> Result<&'a Value, IncomeError> {
+    path.iter()
+        .try_fold(value, |v, key| v.get(key))
+        .ok_or_else(|| IncomeError::InvalidResponse(format!("missing {}", path.join("."))))
+}
+
+fn parse_u64(value: &Value) -> Result<u64, IncomeError> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| IncomeError::InvalidResponse(format!("expected numeric string, got {}", value)))
+}
+
+/// Доход валидаторов от предложенных блоков: Beacon API для расписания и наград CL,
+/// execution RPC для комиссий и платежей билдеров
+pub struct IncomeReporter<M> {
+    http: reqwest::Client,
+    beacon_url: String,
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> IncomeReporter<M> {
+    pub fn new(beacon_url: &str, provider: Arc<M>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            beacon_url: beacon_url.trim_end_matches('/').to_string(),
+            provider,
+        }
+    }
+
+    /// `None` — 404 (пропущенный слот)
+    async fn get(&self, path: &str) -> Result<Option<Value>, IncomeError> {
+        let response = self.http.get(format!("{}{}", self.beacon_url, path)).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    async fn genesis_time(&self) -> Result<u64, IncomeError> {
+        let genesis = self
+            .get("/eth/v1/beacon/genesis")
+            .await?
+            .ok_or_else(|| IncomeError::InvalidResponse("no genesis".into()))?;
+        parse_u64(field(&genesis, &["data", "genesis_time"])?)
+    }
+
+    /// Приоритетные комиссии блока и платёж билдера в последней транзакции
+    async fn execution_income(&self, block_number: u64, fee_recipient: Address, validator_recipient: Option<Address>) -> Result<(U256, U256), IncomeError> {
+        let block = self
+            .provider
+            .get_block_with_txs(BlockId::Number(block_number.into()))
+            .await
+            .map_err(|e| IncomeError::ProviderError(e.to_string()))?
+            .ok_or_else(|| IncomeError::InvalidResponse(format!("block {} not found", block_number)))?;
+
+        // MEV-Boost: fee recipient — билдер, валидатору платит последняя транзакция блока
+        if let Some(last) = block.transactions.last() {
+            let to_validator = match validator_recipient {
+                Some(recipient) => last.to == Some(recipient),
+                None => last.to.is_some() && last.to != Some(fee_recipient),
+            };
+            if last.from == fee_recipient && to_validator && !last.value.is_zero() {
+                return Ok((U256::zero(), last.value));
+            }
+        }
+
+        let base_fee = block.base_fee_per_gas.unwrap_or_default();
+        let receipts = self
+            .provider
+            .get_block_receipts(block_number)
+            .await
+            .map_err(|e| IncomeError::ProviderError(e.to_string()))?;
+        let fees = receipts.iter().fold(U256::zero(), |acc, r| {
+            let price = r.effective_gas_price.unwrap_or_default();
+            acc + r.gas_used.unwrap_or_default() * price.saturating_sub(base_fee)
+        });
+        Ok((fees, U256::zero()))
+    }
+
+    async fn proposal(&self, genesis: u64, slot: u64, validator_index: u64, recipients: &BTreeMap<u64, Address>) -> Result<ProposalRecord, IncomeError> {
+        let missed = ProposalRecord {
+            slot,
+            timestamp: genesis + slot * SECONDS_PER_SLOT,
+            validator_index,
+            status: ProposalStatus::Missed,
+            block_number: None,
+            consensus_reward_gwei: 0,
+            priority_fees_wei: U256::zero(),
+            mev_payment_wei: U256::zero(),
+        };
+        let Some(block) = self.get(&format!("/eth/v2/beacon/blocks/{}", slot)).await? else {
+            return Ok(missed);
+        };
+
+        let payload = field(&block, &["data", "message", "body", "execution_payload"])?;
+        let block_number = parse_u64(field(payload, &["block_number"])?)?;
+        let fee_recipient: Address = field(payload, &["fee_recipient"])?
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| IncomeError::InvalidResponse("bad fee_recipient".into()))?;
+
+        let consensus_reward_gwei = match self.get(&format!("/eth/v1/beacon/rewards/blocks/{}", slot)).await? {
+            Some(rewards) => parse_u64(field(&rewards, &["data", "total"])?)?,
+            None => 0,
+        };
+        let (priority_fees_wei, mev_payment_wei) = self
+            .execution_income(block_number, fee_recipient, recipients.get(&validator_index).copied())
+            .await?;
+
+        Ok(ProposalRecord {
+            status: ProposalStatus::Proposed,
+            block_number: Some(block_number),
+            consensus_reward_gwei,
+            priority_fees_wei,
+            mev_payment_wei,
+            ..missed
+        })
+    }
+
+    /// Отчёт за `[from_timestamp, to_timestamp)` по UNIX-времени.
+    /// `recipients` — известные fee recipient валидаторов: без них платёж билдера
+    /// определяется эвристически по последней транзакции
+    pub async fn report(
+        &self,
+        validators: &[u64],
+        recipients: &BTreeMap<u64, Address>,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<IncomeReport, IncomeError> {
+        if from_timestamp >= to_timestamp {
+            return Err(IncomeError::InvalidRange(from_timestamp, to_timestamp));
+        }
+        let genesis = self.genesis_time().await?;
+        let from_slot = from_timestamp.saturating_sub(genesis) / SECONDS_PER_SLOT;
+        let to_slot = to_timestamp.saturating_sub(genesis) / SECONDS_PER_SLOT;
+        let wanted: HashSet<u64> = validators.iter().copied().collect();
+
+        let mut proposals = Vec::new();
+        for epoch in from_slot / SLOTS_PER_EPOCH..=to_slot.saturating_sub(1) / SLOTS_PER_EPOCH {
+            let Some(duties) = self.get(&format!("/eth/v1/validator/duties/proposer/{}", epoch)).await? else {
+                continue;
+            };
+            let duties = field(&duties, &["data"])?
+                .as_array()
+                .ok_or_else(|| IncomeError::InvalidResponse("duties is not an array".into()))?;
+            for duty in duties {
+                let slot = parse_u64(field(duty, &["slot"])?)?;
+                let index = parse_u64(field(duty, &["validator_index"])?)?;
+                if slot >= from_slot && slot < to_slot && wanted.contains(&index) {
+                    proposals.push(self.proposal(genesis, slot, index, recipients).await?);
+                }
+            }
+        }
+
+        Ok(IncomeReport::build(from_timestamp, to_timestamp, from_slot, to_slot, proposals))
+    }
+}
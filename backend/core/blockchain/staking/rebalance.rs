@@ -0,0 +1,257 @@
+use super::risks::{RiskAnalyzer, RiskParams, ValidatorData};
+use ethers::types::{Address, U256};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Выигрыш полезности, ниже которого перемещение не предлагается
+const MIN_UTILITY_GAIN: f64 = 1e-4;
+
+/// Что известно об операторе-кандидате: доходность и входы модели рисков
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperatorProfile {
+    pub operator: Address,
+    /// Годовая доходность делегатора за вычетом комиссии, 0.05 = 5%
+    pub apr: f64,
+    pub total_staked: U256,
+    pub restaked_assets: Vec<Address>,
+    pub slash_history: u32,
+    pub avg_uptime: f64,
+    /// AVS, которые обеспечивает оператор
+    #[serde(default)]
+    pub avs: Vec<Address>,
+}
+
+/// Текущая делегация портфеля; `amount` — в ETH-эквиваленте
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Delegation {
+    #[schema(value_type = String)]
+    pub operator: Address,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebalanceConstraints {
+    /// Доля портфеля, которую можно переместить за один план
+    pub max_churn: f64,
+    /// Сколько операторов должно остаться после плана
+    pub min_operators: usize,
+    /// Потолок доли одного оператора
+    pub max_operator_share: f64,
+    /// Цена единицы риска в единицах доходности
+    pub risk_aversion: f64,
+}
+
+impl Default for RebalanceConstraints {
+    fn default() -> Self {
+        Self { max_churn: 0.25, min_operators: 2, max_operator_share: 0.5, risk_aversion: 0.1 }
+    }
+}
+
+/// Одно предложенное переделегирование
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RebalanceMove {
+    #[schema(value_type = String)]
+    pub from: Address,
+    #[schema(value_type = String)]
+    pub to: Address,
+    pub amount: f64,
+    /// Изменение годового дохода портфеля, ETH
+    pub yield_delta: f64,
+    /// Изменение взвешенного по сумме риска портфеля
+    pub risk_delta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AllocationMetrics {
+    /// Средняя доходность, взвешенная по сумме
+    pub apr: f64,
+    /// Средний риск оператора (среднее слэшинга и концентрации), взвешенный по сумме
+    pub risk: f64,
+    pub operators: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RebalancePlan {
+    pub moves: Vec<RebalanceMove>,
+    pub before: AllocationMetrics,
+    pub after: AllocationMetrics,
+    /// Доля портфеля, перемещаемая планом
+    pub churn: f64,
+    /// Операторы портфеля без профиля: их позиции не трогаются
+    #[schema(value_type = Vec<String>)]
+    pub unscored: Vec<Address>,
+}
+
+struct Scored {
+    apr: f64,
+    risk: f64,
+    utility: f64,
+}
+
+/// Рекомендации переделегирования: доходность операторов против их риска по модели
+/// `RiskAnalyzer` с ограничениями на оборот и диверсификацию
+pub struct RebalanceEngine {
+    analyzer: Arc<RiskAnalyzer>,
+    operators: Vec<OperatorProfile>,
+    constraints: RebalanceConstraints,
+}
+
+impl RebalanceEngine {
+    pub fn new(analyzer: Arc<RiskAnalyzer>, operators: Vec<OperatorProfile>, constraints: RebalanceConstraints) -> Self {
+        Self { analyzer, operators, constraints }
+    }
+
+    /// Риск оператора для делегатора: ликвидность зависит от портфеля, а не от оператора
+    fn operator_risk(risks: &RiskParams) -> f64 {
+        (risks.slashing_risk + risks.concentration_risk) / 2.0
+    }
+
+    fn score(&self) -> HashMap<Address, Scored> {
+        self.operators
+            .iter()
+            .map(|p| {
+                let risks = self.analyzer.calculate_risks(&ValidatorData {
+                    total_staked: p.total_staked,
+                    restaked_assets: p.restaked_assets.clone(),
+                    slash_history: p.slash_history,
+                    avg_uptime: p.avg_uptime,
+                    avs: p.avs.clone(),
+                });
+                let risk = Self::operator_risk(&risks);
+                (p.operator, Scored { apr: p.apr, risk, utility: p.apr - self.constraints.risk_aversion * risk })
+            })
+            .collect()
+    }
+
+    fn metrics(allocation: &HashMap<Address, f64>, scores: &HashMap<Address, Scored>) -> AllocationMetrics {
+        let scored: Vec<(f64, &Scored)> =
+            allocation.iter().filter(|(_, a)| **a > 0.0).filter_map(|(o, a)| Some((*a, scores.get(o)?))).collect();
+        let total: f64 = scored.iter().map(|(a, _)| a).sum();
+        let weighted = |f: fn(&Scored) -> f64| {
+            if total > 0.0 {
+                scored.iter().map(|(a, s)| a * f(s)).sum::<f64>() / total
+            } else {
+                0.0
+            }
+        };
+        AllocationMetrics {
+            apr: weighted(|s| s.apr),
+            risk: weighted(|s| s.risk),
+            operators: allocation.values().filter(|a| **a > 0.0).count(),
+        }
+    }
+
+    /// Переделегирования с худших по полезности позиций в лучших операторов, пока
+    /// позволяют оборот, потолок доли и минимальное число операторов
+    pub fn rebalance_suggestions(&self, portfolio: &[Delegation]) -> RebalancePlan {
+        let scores = self.score();
+        let mut allocation: HashMap<Address, f64> = HashMap::new();
+        for delegation in portfolio {
+            *allocation.entry(delegation.operator).or_default() += delegation.amount;
+        }
+        let total: f64 = allocation.values().sum();
+        let before = Self::metrics(&allocation, &scores);
+        let mut unscored: Vec<Address> = allocation.keys().filter(|o| !scores.contains_key(o)).copied().collect();
+        unscored.sort();
+
+        let utility = |o: &Address| scores.get(o).map_or(f64::NEG_INFINITY, |s| s.utility);
+        let mut targets: Vec<Address> = scores.keys().copied().collect();
+        targets.sort_by(|a, b| utility(b).total_cmp(&utility(a)).then(a.cmp(b)));
+
+        let mut budget = total * self.constraints.max_churn;
+        let cap = total * self.constraints.max_operator_share;
+        let mut moves = Vec::new();
+        loop {
+            let mut sources: Vec<Address> =
+                allocation.iter().filter(|(o, a)| **a > 0.0 && scores.contains_key(o)).map(|(o, _)| *o).collect();
+            sources.sort_by(|a, b| utility(a).total_cmp(&utility(b)).then(a.cmp(b)));
+
+            let mut candidate = None;
+            'search: for from in &sources {
+                for to in &targets {
+                    if utility(to) - utility(from) < MIN_UTILITY_GAIN {
+                        break;
+                    }
+                    let room = cap - allocation.get(to).copied().unwrap_or(0.0);
+                    let amount = allocation[from].min(room).min(budget);
+                    if amount <= f64::EPSILON {
+                        continue;
+                    }
+                    let count = allocation.values().filter(|a| **a > 0.0).count();
+                    let empties_source = amount >= allocation[from];
+                    let fills_new = allocation.get(to).copied().unwrap_or(0.0) <= 0.0;
+                    let count_after = count - empties_source as usize + fills_new as usize;
+                    if count_after < count && count_after < self.constraints.min_operators {
+                        continue;
+                    }
+                    candidate = Some((*from, *to, amount));
+                    break 'search;
+                }
+            }
+            let Some((from, to, amount)) = candidate else {
+                break;
+            };
+
+            *allocation.get_mut(&from).unwrap() -= amount;
+            *allocation.entry(to).or_default() += amount;
+            budget -= amount;
+            let (source, target) = (&scores[&from], &scores[&to]);
+            moves.push(RebalanceMove {
+                from,
+                to,
+                amount,
+                yield_delta: amount * (target.apr - source.apr),
+                risk_delta: if total > 0.0 { amount * (target.risk - source.risk) / total } else { 0.0 },
+            });
+        }
+
+        RebalancePlan {
+            churn: if total > 0.0 { moves.iter().map(|m| m.amount).sum::<f64>() / total } else { 0.0 },
+            after: Self::metrics(&allocation, &scores),
+            before,
+            moves,
+            unscored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::risks::RiskModelConfig;
+
+    fn profile(byte: u8, apr: f64, slash_history: u32) -> OperatorProfile {
+        OperatorProfile {
+            operator: Address::repeat_byte(byte),
+            apr,
+            total_staked: U256::exp10(18),
+            restaked_assets: Vec::new(),
+            slash_history,
+            avg_uptime: 0.99,
+            avs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_moves_respect_churn_and_operator_cap() {
+        let analyzer = Arc::new(RiskAnalyzer::new(RiskModelConfig::default()));
+        // Оператор 1 со слэшингом, 2 и 3 — чистые, 3 доходнее
+        let engine = RebalanceEngine::new(
+            analyzer,
+            vec![profile(1, 0.04, 2), profile(2, 0.04, 0), profile(3, 0.05, 0)],
+            RebalanceConstraints { max_churn: 0.3, min_operators: 2, max_operator_share: 0.5, risk_aversion: 0.1 },
+        );
+        let portfolio = vec![
+            Delegation { operator: Address::repeat_byte(1), amount: 60.0 },
+            Delegation { operator: Address::repeat_byte(2), amount: 40.0 },
+        ];
+
+        let plan = engine.rebalance_suggestions(&portfolio);
+        assert!((plan.churn - 0.3).abs() < 1e-9);
+        assert!(plan.moves.iter().all(|m| m.from == Address::repeat_byte(1)));
+        assert!(plan.after.risk < plan.before.risk);
+        assert!(plan.after.operators >= 2);
+    }
+}
@@ -1,34 +1,64 @@
+use super::backtest::ValidatorHistory;
+use crate::portfolio::{lp, PortfolioSnapshot, PortfolioTracker, PositionKind};
+use async_trait::async_trait;
+use ethers::providers::Middleware;
 use ethers::types::{Address, U256};
+use mevdetector::amount::WeiAmount;
+use mevdetector::digest::{DigestRow, DigestSection, DigestSource, DigestTenant};
+use mevdetector::units::Fraction;
 use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use utoipa::{OpenApi, ToSchema};
 
 /// Параметры риска для валидатора
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, ToSchema)]
 pub struct RiskParams {
     pub slashing_risk: f64,       // 0.0-1.0
     pub liquidity_risk: f64,      // 0.0-1.0
     pub concentration_risk: f64,  // 0.0-1.0
+    pub lp_risk: f64,             // 0.0-1.0, непостоянные потери LP-позиций
 }
 
+/// Схема параметров риска для спецификации API и генерируемых клиентов
+#[derive(OpenApi)]
+#[openapi(components(schemas(RiskParams)))]
+pub struct RiskSchema;
+
 #[derive(Debug, Clone)]
 pub struct ValidatorData {
     pub total_staked: U256,
     pub restaked_assets: Vec<Address>,
     pub slash_history: u32,
     pub avg_uptime: f64,  // 0.0-1.0
+    /// AVS, которые обеспечивает стейк; их задержки вывода тоже учитываются
+    pub avs: Vec<Address>,
 }
 
+/// Задержка вывода EigenLayer по умолчанию (`minWithdrawalDelayBlocks`, ~7 дней)
+pub const DEFAULT_WITHDRAWAL_DELAY_SECS: u64 = 7 * 24 * 3600;
+
 /// Конфигурация модели рисков
 pub struct RiskModelConfig {
-    pub max_slashing_penalty: U256,
-    pub min_uptime_threshold: f64,
+    pub max_slashing_penalty: WeiAmount,
+    pub min_uptime_threshold: Fraction,
+    /// Задержка вывода в секундах по стратегии, активу или AVS
+    pub withdrawal_delays: HashMap<Address, u64>,
+    /// Для стратегий без записи в `withdrawal_delays`
+    pub default_withdrawal_delay: u64,
+    /// Задержка, при которой позиция неликвидна наполовину; риск растёт с задержкой
+    /// и к 1.0 только стремится, поэтому разные задержки дают разные оценки
+    pub liquidity_horizon: u64,
 }
 
 impl Default for RiskModelConfig {
     fn default() -> Self {
         Self {
-            max_slashing_penalty: U256::from(1_000_000_000_000_000_000u64), // 1 ETH
-            min_uptime_threshold: 0.95,
+            max_slashing_penalty: WeiAmount::from_eth(1.0),
+            min_uptime_threshold: Fraction::new(0.95).unwrap(),
+            withdrawal_delays: HashMap::new(),
+            default_withdrawal_delay: DEFAULT_WITHDRAWAL_DELAY_SECS,
+            liquidity_horizon: DEFAULT_WITHDRAWAL_DELAY_SECS,
         }
     }
 }
@@ -37,6 +67,7 @@ impl Default for RiskModelConfig {
 pub struct RiskAnalyzer {
     config: RiskModelConfig,
     asset_volatility: HashMap<Address, f64>,  // Волатильность активов
+    history: Option<Arc<dyn ValidatorHistory>>,
 }
 
 impl RiskAnalyzer {
@@ -44,15 +75,62 @@ impl RiskAnalyzer {
         Self {
             config,
             asset_volatility: Self::load_volatility_data(),
+            history: None,
         }
     }
 
+    /// Подключает источник исторических данных для бэктеста
+    pub fn with_history(mut self, history: Arc<dyn ValidatorHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    pub(crate) fn history(&self) -> Option<&dyn ValidatorHistory> {
+        self.history.as_deref()
+    }
+
     /// Основная функция оценки рисков
     pub fn calculate_risks(&self, validator: &ValidatorData) -> RiskParams {
         RiskParams {
             slashing_risk: self.calculate_slashing_risk(validator),
             liquidity_risk: self.calculate_liquidity_risk(validator),
             concentration_risk: self.calculate_concentration_risk(validator),
+            lp_risk: 0.0,
+        }
+    }
+
+    /// Оценка по фактическому портфелю адреса; суммы берутся в единицах активов без пересчёта в цену
+    pub fn calculate_portfolio_risks(&self, snapshot: &PortfolioSnapshot, slash_history: u32, avg_uptime: f64) -> RiskParams {
+        let staked = snapshot.staked_amount();
+        let validator = ValidatorData {
+            total_staked: ethers::utils::parse_units(staked, "ether").map(Into::into).unwrap_or_default(),
+            restaked_assets: snapshot.restaked_assets(),
+            slash_history,
+            avg_uptime,
+            avs: Vec::new(),
+        };
+
+        // Риск ликвидности — доля портфеля, которую нельзя быстро вывести: стейкинг целиком,
+        // рестейкинг — пропорционально задержке вывода стратегии
+        let total: f64 = snapshot.positions.iter().map(|p| p.amount).sum();
+        let illiquid: f64 = snapshot
+            .positions
+            .iter()
+            .map(|p| match &p.kind {
+                PositionKind::Staking { .. } => p.amount,
+                PositionKind::EigenShares { strategy, underlying, .. } => {
+                    p.amount * self.delay_factor(self.withdrawal_delay(&[*strategy, *underlying], &[]))
+                }
+                _ => 0.0,
+            })
+            .sum();
+        let liquidity_risk = if total > 0.0 { (illiquid / total).min(1.0) } else { 0.0 };
+
+        RiskParams {
+            slashing_risk: self.calculate_slashing_risk(&validator),
+            liquidity_risk,
+            concentration_risk: self.calculate_concentration_risk(&validator),
+            lp_risk: lp::lp_risk(&snapshot.lp_positions),
         }
     }
 
@@ -64,8 +142,9 @@ impl RiskAnalyzer {
             0.1
         };
 
-        let uptime_penalty = if validator.avg_uptime < self.config.min_uptime_threshold {
-            (self.config.min_uptime_threshold - validator.avg_uptime) * 2.0
+        let min_uptime = self.config.min_uptime_threshold.get();
+        let uptime_penalty = if validator.avg_uptime < min_uptime {
+            (min_uptime - validator.avg_uptime) * 2.0
         } else {
             0.0
         };
@@ -73,6 +152,26 @@ impl RiskAnalyzer {
         (base_risk + uptime_penalty).min(1.0)
     }
 
+    /// Задержка вывода позиции: самая длинная из задержек её стратегий/активов и AVS.
+    /// Без записи для стратегии берётся задержка EigenLayer по умолчанию
+    pub fn withdrawal_delay(&self, assets: &[Address], avs: &[Address]) -> u64 {
+        let delays = &self.config.withdrawal_delays;
+        let asset_delay = match assets.iter().filter_map(|a| delays.get(a)).max() {
+            Some(delay) => *delay,
+            None if assets.is_empty() => 0,
+            None => self.config.default_withdrawal_delay,
+        };
+        avs.iter().filter_map(|a| delays.get(a)).copied().fold(asset_delay, u64::max)
+    }
+
+    /// 0.0 — вывод мгновенный, 0.5 — задержка равна горизонту ликвидности
+    fn delay_factor(&self, delay: u64) -> f64 {
+        if self.config.liquidity_horizon == 0 {
+            return if delay > 0 { 1.0 } else { 0.0 };
+        }
+        delay as f64 / (delay as f64 + self.config.liquidity_horizon as f64)
+    }
+
     /// Риск ликвидности (0.0-1.0)
     fn calculate_liquidity_risk(&self, validator: &ValidatorData) -> f64 {
         if validator.restaked_assets.is_empty() {
@@ -87,7 +186,12 @@ impl RiskAnalyzer {
         }
 
         // Чем выше доля ETH, тем ниже риск
-        1.0 - (eth_value / total_value).min(1.0)
+        let composition = 1.0 - (eth_value / total_value).min(1.0);
+
+        // Задержка вывода добавляет риск сверх состава: позиция за 7-дневной очередью
+        // хуже мгновенно выводимой при том же составе
+        let delay = self.delay_factor(self.withdrawal_delay(&validator.restaked_assets, &validator.avs));
+        (composition + (1.0 - composition) * delay).min(1.0)
     }
 
     /// Риск концентрации (0.0-1.0)
@@ -123,6 +227,59 @@ impl RiskAnalyzer {
     }
 }
 
+/// Изменение любой составляющей риска больше этого попадает в дайджест
+const RISK_DIGEST_THRESHOLD: f64 = 0.05;
+
+/// Раздел дайджеста с изменениями рисков отслеживаемых адресов с прошлого дайджеста
+pub struct RiskDigest<M> {
+    analyzer: Arc<RiskAnalyzer>,
+    tracker: Arc<PortfolioTracker<M>>,
+    previous: Mutex<HashMap<(String, Address), RiskParams>>,
+}
+
+impl<M: Middleware + 'static> RiskDigest<M> {
+    pub fn new(analyzer: Arc<RiskAnalyzer>, tracker: Arc<PortfolioTracker<M>>) -> Self {
+        Self { analyzer, tracker, previous: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> DigestSource for RiskDigest<M> {
+    async fn section(&self, tenant: &DigestTenant, _since: u64, _until: u64) -> Option<DigestSection> {
+        let mut previous = self.previous.lock().unwrap();
+        let mut addresses: Vec<Address> =
+            self.tracker.tracked().into_iter().filter(|a| tenant.covers(&format!("{:?}", a))).collect();
+        addresses.sort();
+
+        let mut rows = Vec::new();
+        for address in addresses {
+            let Some(snapshot) = self.tracker.snapshot(address) else {
+                continue;
+            };
+            // История слэшинга по адресу портфеля неизвестна — оценка без неё
+            let risks = self.analyzer.calculate_portfolio_risks(&snapshot, 0, 1.0);
+            if let Some(before) = previous.insert((tenant.name.clone(), address), risks.clone()) {
+                let components = [
+                    ("slashing", before.slashing_risk, risks.slashing_risk),
+                    ("liquidity", before.liquidity_risk, risks.liquidity_risk),
+                    ("concentration", before.concentration_risk, risks.concentration_risk),
+                    ("lp", before.lp_risk, risks.lp_risk),
+                ];
+                for (name, was, now) in components {
+                    if (now - was).abs() >= RISK_DIGEST_THRESHOLD {
+                        rows.push(DigestRow {
+                            label: format!("{:?} {} risk", address, name),
+                            value: format!("{:.2} -> {:.2}", was, now),
+                        });
+                    }
+                }
+            }
+        }
+
+        (!rows.is_empty()).then(|| DigestSection { title: "Risk changes".into(), rows })
+    }
+}
+
 /// Тесты модуля
 #[cfg(test)]
 mod tests {
@@ -136,9 +293,35 @@ mod tests {
             restaked_assets: vec![],
             slash_history: 0,
             avg_uptime: 0.99,
+            avs: vec![],
         };
 
         let risks = analyzer.calculate_risks(&validator);
         assert!(risks.slashing_risk < 0.2);
     }
+
+    #[test]
+    fn test_withdrawal_delay_raises_liquidity_risk() {
+        let (instant, delayed, longer) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let mut config = RiskModelConfig::default();
+        config.withdrawal_delays.insert(instant, 0);
+        config.withdrawal_delays.insert(delayed, DEFAULT_WITHDRAWAL_DELAY_SECS);
+        config.withdrawal_delays.insert(longer, 2 * DEFAULT_WITHDRAWAL_DELAY_SECS);
+        let analyzer = RiskAnalyzer::new(config);
+        let validator = |asset| ValidatorData {
+            total_staked: U256::from(10u64.pow(18)),
+            restaked_assets: vec![asset],
+            slash_history: 0,
+            avg_uptime: 0.99,
+            avs: vec![],
+        };
+
+        let instant_risk = analyzer.calculate_risks(&validator(instant)).liquidity_risk;
+        let delayed_risk = analyzer.calculate_risks(&validator(delayed)).liquidity_risk;
+        let longer_risk = analyzer.calculate_risks(&validator(longer)).liquidity_risk;
+        // Задержка по умолчанию равна горизонту, но не насыщает оценку
+        assert!(instant_risk < delayed_risk);
+        assert!(delayed_risk < longer_risk);
+        assert!(longer_risk < 1.0);
+    }
 }
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use mevdetector::backfill::{BackfillJob, BatchOutcome};
+use mevdetector::store::{SharedStore, Store, StoreError, StoreExt};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Пространство имён хранилища: индекс валидатора -> его слэшинги
+pub const SLASHINGS_NS: &str = "slashings";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashingKind {
+    /// Два разных блока на один слот
+    Proposer,
+    /// Двойное или окружающее голосование
+    Attester,
+}
+
+/// Слэшинг, включённый в блок слота `slot`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SlashingRecord {
+    pub slot: u64,
+    pub kind: SlashingKind,
+}
+
+/// Сколько раз валидатор был наказан — `ValidatorData::slash_history`
+pub fn slash_history(store: &dyn Store, index: &str) -> Result<u32, StoreError> {
+    let records: Option<BTreeSet<SlashingRecord>> = store.get_json(SLASHINGS_NS, index)?;
+    Ok(records.map_or(0, |records| records.len() as u32))
+}
+
+/// Заполнение `slash_history`: слэшинги из блоков маяковой сети за `[from_slot, to_slot)`.
+/// Записи — множества по слоту, поэтому повтор прерванной порции ничего не задваивает
+pub struct SlashingHistoryJob {
+    http: reqwest::Client,
+    beacon_url: String,
+    store: SharedStore,
+    from_slot: u64,
+    to_slot: u64,
+    slots_per_batch: u64,
+}
+
+impl SlashingHistoryJob {
+    pub fn new(beacon_url: &str, store: SharedStore, from_slot: u64, to_slot: u64, slots_per_batch: u64) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            beacon_url: beacon_url.trim_end_matches('/').to_string(),
+            store,
+            from_slot,
+            to_slot,
+            slots_per_batch: slots_per_batch.max(1),
+        }
+    }
+
+    /// Тело блока слота; `None` — слот пропущен
+    async fn block_body(&self, slot: u64) -> Result<Option<Value>, String> {
+        let response = self
+            .http
+            .get(format!("{}/eth/v2/beacon/blocks/{}", self.beacon_url, slot))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let mut block: Value = response.error_for_status().map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
+        Ok(Some(block.pointer_mut("/data/message/body").map(Value::take).unwrap_or_default()))
+    }
+
+    fn record(&self, index: &str, record: SlashingRecord) -> Result<(), String> {
+        let mut records: BTreeSet<SlashingRecord> = self.store.get_json(SLASHINGS_NS, index).map_err(|e| e.to_string())?.unwrap_or_default();
+        if records.insert(record) {
+            self.store.put_json(SLASHINGS_NS, index, &records).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Наказанные в теле блока: предлагающий двух заголовков и голосовавшие в обеих аттестациях
+fn slashed(body: &Value) -> Vec<(String, SlashingKind)> {
+    let index = |value: &Value| value.as_str().map(str::to_string);
+    let mut slashed = Vec::new();
+    for slashing in body["proposer_slashings"].as_array().into_iter().flatten() {
+        slashed.extend(index(&slashing["signed_header_1"]["message"]["proposer_index"]).map(|i| (i, SlashingKind::Proposer)));
+    }
+    for slashing in body["attester_slashings"].as_array().into_iter().flatten() {
+        let indices = |attestation: &str| -> BTreeSet<String> {
+            slashing[attestation]["attesting_indices"].as_array().into_iter().flatten().filter_map(index).collect()
+        };
+        let second = indices("attestation_2");
+        slashed.extend(indices("attestation_1").into_iter().filter(|i| second.contains(i)).map(|i| (i, SlashingKind::Attester)));
+    }
+    slashed
+}
+
+#[async_trait]
+impl BackfillJob for SlashingHistoryJob {
+    fn name(&self) -> &str {
+        "slash_history"
+    }
+
+    async fn total(&self) -> Option<u64> {
+        Some(self.to_slot.saturating_sub(self.from_slot))
+    }
+
+    async fn run_batch(&self, cursor: Option<Value>) -> Result<BatchOutcome, String> {
+        let start = match cursor {
+            None => self.from_slot,
+            Some(cursor) => cursor.as_u64().ok_or_else(|| format!("cursor {} is not a slot", cursor))?,
+        };
+        let end = start.saturating_add(self.slots_per_batch).min(self.to_slot);
+        for slot in start..end {
+            let Some(body) = self.block_body(slot).await? else { continue };
+            for (index, kind) in slashed(&body) {
+                self.record(&index, SlashingRecord { slot, kind })?;
+            }
+        }
+        Ok(BatchOutcome { processed: end.saturating_sub(start), next: (end < self.to_slot).then(|| Value::from(end)) })
+    }
+}
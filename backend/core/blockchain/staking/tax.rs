@@ -0,0 +1,375 @@
+use super::income::{IncomeReport, ProposalStatus};
+use async_trait::async_trait;
+use ethers::types::{H256, U256};
+use ethers::utils::format_units;
+use serde::{Serialize, Deserialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use thiserror::Error;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Error)]
+pub enum TaxError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("No ETH/USD price for {0}")]
+    PriceUnavailable(String),
+
+    #[error("Invalid price table: {0}")]
+    InvalidPriceTable(String),
+}
+
+/// Вид дохода — определяет метку в учётной системе
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomeKind {
+    ConsensusReward,
+    ExecutionFees,
+    MevPayment,
+    RestakingReward,
+}
+
+impl IncomeKind {
+    fn label(self) -> &'static str {
+        match self {
+            IncomeKind::ConsensusReward => "consensus_reward",
+            IncomeKind::ExecutionFees => "execution_fees",
+            IncomeKind::MevPayment => "mev_payment",
+            IncomeKind::RestakingReward => "restaking_reward",
+        }
+    }
+}
+
+/// Одно поступление ETH
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomeRecord {
+    pub timestamp: u64,
+    pub kind: IncomeKind,
+    pub amount_wei: U256,
+    pub validator_index: Option<u64>,
+    pub tx_hash: Option<H256>,
+    /// Для рестейкинга — оператор или AVS, выплативший награду
+    pub description: String,
+}
+
+impl IncomeRecord {
+    pub fn restaking(timestamp: u64, amount_wei: U256, tx_hash: Option<H256>, description: &str) -> Self {
+        Self {
+            timestamp,
+            kind: IncomeKind::RestakingReward,
+            amount_wei,
+            validator_index: None,
+            tx_hash,
+            description: description.to_string(),
+        }
+    }
+
+    /// Раскладывает предложенные блоки отчёта на отдельные поступления.
+    /// Пропущенные слоты — упущенная выгода, а не доход, и в экспорт не попадают
+    pub fn from_report(report: &IncomeReport) -> Vec<Self> {
+        let mut records = Vec::new();
+        for p in report.proposals.iter().filter(|p| p.status == ProposalStatus::Proposed) {
+            let block = p.block_number.map(|b| format!("block {}", b)).unwrap_or_default();
+            let parts = [
+                (IncomeKind::ConsensusReward, U256::from(p.consensus_reward_gwei) * U256::exp10(9)),
+                (IncomeKind::ExecutionFees, p.priority_fees_wei),
+                (IncomeKind::MevPayment, p.mev_payment_wei),
+            ];
+            for (kind, amount_wei) in parts {
+                if amount_wei.is_zero() {
+                    continue;
+                }
+                records.push(Self {
+                    timestamp: p.timestamp,
+                    kind,
+                    amount_wei,
+                    validator_index: Some(p.validator_index),
+                    tx_hash: None,
+                    description: format!("slot {} {}", p.slot, block).trim_end().to_string(),
+                });
+            }
+        }
+        records
+    }
+
+    pub fn amount_eth(&self) -> String {
+        format_units(self.amount_wei, "ether").unwrap_or_default()
+    }
+}
+
+/// Исторический курс ETH/USD на момент поступления
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    async fn eth_usd(&self, timestamp: u64) -> Result<f64, TaxError>;
+}
+
+/// Курсы из заранее выгруженной таблицы `YYYY-MM-DD,price` (дневное закрытие)
+pub struct DailyPriceTable {
+    prices: BTreeMap<u64, f64>,
+}
+
+impl DailyPriceTable {
+    pub fn from_csv(text: &str) -> Result<Self, TaxError> {
+        let mut prices = BTreeMap::new();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with("date")) {
+            let (date, price) = line
+                .split_once(',')
+                .ok_or_else(|| TaxError::InvalidPriceTable(line.to_string()))?;
+            let day = parse_date(date).ok_or_else(|| TaxError::InvalidPriceTable(date.to_string()))?;
+            let price: f64 = price
+                .trim()
+                .parse()
+                .map_err(|_| TaxError::InvalidPriceTable(line.to_string()))?;
+            prices.insert(day, price);
+        }
+        Ok(Self { prices })
+    }
+}
+
+#[async_trait]
+impl PriceSource for DailyPriceTable {
+    async fn eth_usd(&self, timestamp: u64) -> Result<f64, TaxError> {
+        let day = timestamp / SECONDS_PER_DAY;
+        self.prices
+            .get(&day)
+            .copied()
+            .ok_or_else(|| TaxError::PriceUnavailable(format_date(timestamp)))
+    }
+}
+
+/// CoinGecko `/coins/ethereum/history`: одна цена на день, ответы кэшируются
+pub struct CoinGeckoPrices {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    cache: Mutex<BTreeMap<u64, f64>>,
+}
+
+impl CoinGeckoPrices {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            cache: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl PriceSource for CoinGeckoPrices {
+    async fn eth_usd(&self, timestamp: u64) -> Result<f64, TaxError> {
+        let day = timestamp / SECONDS_PER_DAY;
+        if let Some(price) = self.cache.lock().unwrap().get(&day) {
+            return Ok(*price);
+        }
+
+        let (y, m, d, ..) = civil(timestamp);
+        let mut request = self
+            .http
+            .get(format!("{}/coins/ethereum/history", self.base_url))
+            .query(&[("date", format!("{:02}-{:02}-{}", d, m, y)), ("localization", "false".into())]);
+        if let Some(key) = &self.api_key {
+            request = request.header("x-cg-demo-api-key", key);
+        }
+        let body: serde_json::Value = request.send().await?.error_for_status()?.json().await?;
+        let price = body["market_data"]["current_price"]["usd"]
+            .as_f64()
+            .ok_or_else(|| TaxError::PriceUnavailable(format_date(timestamp)))?;
+
+        self.cache.lock().unwrap().insert(day, price);
+        Ok(price)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxFormat {
+    /// Собственная таблица со стоимостью на момент получения как базой
+    Csv,
+    /// Koinly Universal CSV
+    Koinly,
+    /// CoinTracker CSV import
+    CoinTracker,
+}
+
+/// Поступление с курсом на момент получения
+#[derive(Debug, Clone, Serialize)]
+pub struct PricedIncome {
+    #[serde(flatten)]
+    pub record: IncomeRecord,
+    pub eth_usd: f64,
+    /// Доход признаётся по рыночной стоимости в момент получения — она же база для продажи
+    pub fair_value_usd: f64,
+}
+
+pub async fn price_records(records: &[IncomeRecord], prices: &dyn PriceSource) -> Result<Vec<PricedIncome>, TaxError> {
+    let mut priced = Vec::with_capacity(records.len());
+    for record in records {
+        let eth_usd = prices.eth_usd(record.timestamp).await?;
+        let amount: f64 = record.amount_eth().parse().unwrap_or(0.0);
+        priced.push(PricedIncome {
+            record: record.clone(),
+            eth_usd,
+            fair_value_usd: amount * eth_usd,
+        });
+    }
+    priced.sort_by_key(|p| p.record.timestamp);
+    Ok(priced)
+}
+
+/// Дни с 1970-01-01 -> (год, месяц, день), алгоритм Хиннанта
+fn civil(timestamp: u64) -> (i64, u32, u32, u64) {
+    let days = (timestamp / SECONDS_PER_DAY) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d, timestamp % SECONDS_PER_DAY)
+}
+
+fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    // Обратное преобразование к `civil`
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    u64::try_from(era * 146_097 + doe - 719_468).ok()
+}
+
+fn format_date(timestamp: u64) -> String {
+    let (y, m, d, _) = civil(timestamp);
+    format!("{}-{:02}-{:02}", y, m, d)
+}
+
+fn format_time(timestamp: u64) -> (u64, u64, u64) {
+    let secs = timestamp % SECONDS_PER_DAY;
+    (secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+fn tx_hash(record: &IncomeRecord) -> String {
+    record.tx_hash.map(|h| format!("{:?}", h)).unwrap_or_default()
+}
+
+/// Поле CSV по RFC 4180: с запятой, кавычкой или переводом строки — в кавычках, кавычки удваиваются
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(&[',', '"', '\r', '\n'][..]) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+pub fn export(priced: &[PricedIncome], format: TaxFormat) -> String {
+    let mut out = String::new();
+    match format {
+        TaxFormat::Csv => {
+            out.push_str("date,time_utc,kind,amount_eth,eth_usd,fair_value_usd,cost_basis_usd,validator_index,tx_hash,description\n");
+            for p in priced {
+                let (h, min, s) = format_time(p.record.timestamp);
+                let _ = writeln!(
+                    out,
+                    "{},{:02}:{:02}:{:02},{},{},{:.2},{:.2},{:.2},{},{},{}",
+                    format_date(p.record.timestamp),
+                    h,
+                    min,
+                    s,
+                    p.record.kind.label(),
+                    p.record.amount_eth(),
+                    p.eth_usd,
+                    p.fair_value_usd,
+                    p.fair_value_usd,
+                    p.record.validator_index.map(|i| i.to_string()).unwrap_or_default(),
+                    tx_hash(&p.record),
+                    csv_field(&p.record.description),
+                );
+            }
+        }
+        TaxFormat::Koinly => {
+            out.push_str("Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash\n");
+            for p in priced {
+                let (h, min, _) = format_time(p.record.timestamp);
+                let label = match p.record.kind {
+                    IncomeKind::ConsensusReward | IncomeKind::RestakingReward => "staking",
+                    IncomeKind::ExecutionFees | IncomeKind::MevPayment => "income",
+                };
+                let _ = writeln!(
+                    out,
+                    "{} {:02}:{:02} UTC,,,{},ETH,,,{:.2},USD,{},{},{}",
+                    format_date(p.record.timestamp),
+                    h,
+                    min,
+                    p.record.amount_eth(),
+                    p.fair_value_usd,
+                    label,
+                    csv_field(&format!("{} {}", p.record.kind.label(), p.record.description)),
+                    tx_hash(&p.record),
+                );
+            }
+        }
+        TaxFormat::CoinTracker => {
+            out.push_str("Date,Received Quantity,Received Currency,Sent Quantity,Sent Currency,Fee Amount,Fee Currency,Tag\n");
+            for p in priced {
+                let (y, m, d, _) = civil(p.record.timestamp);
+                let (h, min, s) = format_time(p.record.timestamp);
+                let tag = match p.record.kind {
+                    IncomeKind::ConsensusReward | IncomeKind::RestakingReward => "staked",
+                    IncomeKind::ExecutionFees | IncomeKind::MevPayment => "income",
+                };
+                let _ = writeln!(
+                    out,
+                    "{:02}/{:02}/{} {:02}:{:02}:{:02},{},ETH,,,,,{}",
+                    m,
+                    d,
+                    y,
+                    h,
+                    min,
+                    s,
+                    p.record.amount_eth(),
+                    tag,
+                );
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates_and_koinly_row() {
+        // 2024-03-01 12:30:00 UTC
+        let ts = 1_709_296_200;
+        assert_eq!(format_date(ts), "2024-03-01");
+        assert_eq!(parse_date("2024-03-01"), Some(ts / SECONDS_PER_DAY));
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+
+        let record = IncomeRecord::restaking(ts, U256::exp10(17), None, "operator rewards");
+        let priced = vec![PricedIncome { record, eth_usd: 3400.0, fair_value_usd: 340.0 }];
+        let koinly = export(&priced, TaxFormat::Koinly);
+        assert_eq!(
+            koinly.lines().nth(1),
+            Some("2024-03-01 12:30 UTC,,,0.100000000000000000,ETH,,,340.00,USD,staking,restaking_reward operator rewards,")
+        );
+
+        let record = IncomeRecord::restaking(ts, U256::exp10(17), None, "rewards, \"AVS\"\nQ1");
+        let priced = vec![PricedIncome { record, eth_usd: 3400.0, fair_value_usd: 340.0 }];
+        let csv = export(&priced, TaxFormat::Csv);
+        assert!(csv.ends_with(",\"rewards, \"\"AVS\"\"\nQ1\"\n"));
+    }
+}
@@ -1,42 +1,214 @@
+#[cfg(feature = "ffi-c")]
 use cxx::UniquePtr;
+#[cfg(feature = "cpp-simulator")]
+use simulator::SimState;
+#[cfg(feature = "ffi-c")]
 use serde::{Serialize, Deserialize};
+#[cfg(feature = "ffi-c")]
 use std::collections::HashMap;
+#[cfg(feature = "ffi-c")]
+use std::ffi::{c_char, CStr, CString};
+#[cfg(feature = "ffi-c")]
+use std::ptr;
+
+#[cfg(feature = "mev")]
+pub mod abi;
+#[cfg(feature = "mev")]
+pub mod access_list;
+pub mod address;
+#[cfg(feature = "server")]
+pub mod admin;
+pub mod amount;
+#[cfg(feature = "mev")]
+pub mod approvals;
+#[cfg(feature = "server")]
+pub mod assess;
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod backfill;
+#[cfg(feature = "mev")]
+pub mod bridges;
+#[cfg(feature = "mev")]
+pub mod bundle;
+pub mod bus;
+pub mod capability;
+#[cfg(feature = "mev")]
+pub mod cancel;
+pub mod chain;
+/// Переход с ethers-rs на alloy: модули берут `Address`, `U256`, `H256` отсюда, а не из
+/// `ethers::types`, и считают через `alloy_primitives`, переводя значения на границе
+/// через `ToAlloy` / `ToEthers`. Замена реэкспорта на alloy — одна строка
+pub mod compat;
+#[cfg(feature = "mev")]
+pub mod congestion;
+pub mod config;
+pub mod correlation;
+#[cfg(feature = "mev")]
+pub mod create2;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+#[cfg(feature = "mev")]
+pub mod dedup;
+#[cfg(feature = "mev")]
+pub mod detector;
+pub mod digest;
+#[cfg(feature = "email")]
+pub mod email;
+#[cfg(feature = "secrets")]
+pub mod encryption;
+#[cfg(feature = "mev")]
+pub mod engine;
+#[cfg(feature = "mev")]
+pub mod enrichment;
+#[cfg(feature = "mev")]
+pub mod fingerprint;
+#[cfg(feature = "mev")]
+pub mod forensics;
+#[cfg(feature = "mev")]
+pub mod funding;
+pub mod gas_oracle;
+pub mod i18n;
+pub mod indexer;
+#[cfg(feature = "mev")]
+pub mod intents;
+#[cfg(feature = "mev")]
+pub mod ingest;
+#[cfg(feature = "mev")]
+pub mod labels;
+pub mod leader;
+pub mod multicall;
+#[cfg(feature = "server")]
+pub mod node;
+#[cfg(feature = "server")]
+pub mod openapi;
+#[cfg(feature = "mev")]
+pub mod permit2;
+#[cfg(feature = "mev")]
+pub mod pipeline;
+#[cfg(feature = "staking")]
+pub mod policy;
+#[cfg(feature = "push")]
+pub mod push;
+#[cfg(feature = "ffi-python")]
+// pyo3 0.20 раскрывает `#[pymethods]` в impl внутри `const _` уровня модуля; исправлено в 0.21
+#[allow(non_local_definitions)]
+mod python;
+#[cfg(feature = "mev")]
+pub mod registry;
+#[cfg(feature = "staking")]
+pub mod remote_signer;
+pub mod retention;
+pub mod routing;
+pub mod rpc;
+#[cfg(feature = "mev")]
+pub mod rules;
+pub mod schema;
+#[cfg(feature = "secrets")]
+pub mod secrets;
+#[cfg(feature = "mev")]
+pub mod shared;
+#[cfg(feature = "mev")]
+pub mod severity;
+#[cfg(feature = "mev")]
+pub mod simulator;
+pub mod shutdown;
+pub mod sink;
+pub mod state;
+#[cfg(feature = "mev")]
+pub mod state_diff;
+pub mod store;
+pub mod task_errors;
+/// Фикстуры для детерминированных тестов детектора без RPC и C++ симулятора.
+/// Доступны снаружи с фичей `test-util`
+#[cfg(all(feature = "mev", any(test, feature = "test-util")))]
+pub mod testing;
+#[cfg(feature = "mev")]
+pub mod trap;
+#[cfg(feature = "mev")]
+pub mod tx;
+#[cfg(feature = "mev")]
+pub mod typed_data;
+pub mod units;
+#[cfg(feature = "mev")]
+pub mod victims;
+#[cfg(feature = "mev")]
+pub mod warm;
 
 /// C++ FFI мост
+#[cfg(feature = "cpp-simulator")]
 #[cxx::bridge]
 mod ffi {
-    // Экспортируемые в C++ типы
-    #[derive(Debug, Serialize, Deserialize)]
-    pub struct Tx {
+    /// Транзакция для симулятора: value в ETH, gas_price в wei.
+    /// Точные суммы остаются в `crate::tx::Tx`
+    #[derive(Debug)]
+    pub struct SimTx {
+        pub from: String,
         pub to: String,
         pub value: f64,
         pub gas_price: f64,
         pub input: Vec<u8>,
     }
 
+    /// Аккаунт для симулятора; баланс в wei, 32 байта big-endian
+    #[derive(Debug)]
+    pub struct SimAccount {
+        pub balance: Vec<u8>,
+        pub nonce: u64,
+    }
+
+    extern "Rust" {
+        /// Состояние цепи на последнем блоке для C++ симулятора. Ошибка чтения приходит
+        /// в C++ исключением `rust::Error`; симулятор считает такую симуляцию неудачной
+        type SimState;
+
+        fn account(self: &SimState, address: &str) -> Result<SimAccount>;
+        /// Значение слота, 32 байта big-endian
+        fn storage(self: &SimState, address: &str, slot: &[u8]) -> Result<Vec<u8>>;
+        fn code(self: &SimState, address: &str) -> Result<Vec<u8>>;
+    }
+
     extern "C++" {
         include!("mev-detector/cpp/simulator.h");
         
         type CppSimulator;
 
+        /// Симулятор со своим клиентом узла; для встраиваний без `StateProvider` (Python, C ABI)
+        #[allow(dead_code)]
         fn new_simulator() -> UniquePtr<CppSimulator>;
-        fn simulate_profit(sim: &CppSimulator, victim: &Tx, attacker: &Tx) -> f64;
+        /// Симулятор, читающий состояние через `state`, а не собственным клиентом узла
+        fn new_simulator_with_state(state: Box<SimState>) -> UniquePtr<CppSimulator>;
+        fn simulate_profit(sim: &CppSimulator, victim: &SimTx, attacker: &SimTx) -> f64;
+        fn simulate_sandwich(sim: &CppSimulator, front: &SimTx, victim: &SimTx, back: &SimTx) -> f64;
+        /// Выход жертвы в ETH, если её транзакция исполнится без атакующего
+        fn simulate_victim_output(sim: &CppSimulator, victim: &SimTx) -> f64;
+        /// Выход жертвы в ETH между `front` и `back`
+        fn simulate_sandwiched_victim_output(sim: &CppSimulator, front: &SimTx, victim: &SimTx, back: &SimTx) -> f64;
     }
 }
 
 /// Результат детекции MEV
+#[cfg(feature = "ffi-c")]
 #[derive(Serialize, Deserialize)]
 pub struct MevAlert {
     pub alert_type: String,
     pub profit_eth: f64,
-    pub risk_score: f8,
+    pub risk_score: f32,
 }
 
+#[cfg(feature = "ffi-c")]
 pub struct MevDetector {
     simulator: UniquePtr<ffi::CppSimulator>,
-    pending_pool: HashMap<String, Vec<ffi::Tx>>, // Адрес -> Ожидающие транзы
+    pending_pool: HashMap<address::ChecksummedAddress, Vec<tx::Tx>>, // Адрес -> Ожидающие транзы
 }
 
+#[cfg(feature = "ffi-c")]
+impl Default for MevDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "ffi-c")]
 impl MevDetector {
     pub fn new() -> Self {
         Self {
@@ -45,8 +217,8 @@ impl MevDetector {
         }
     }
 
-    pub fn analyze(&mut self, tx: ffi::Tx) -> Option<MevAlert> {
-        let target = tx.to.clone();
+    pub fn analyze(&mut self, tx: tx::Tx) -> Option<MevAlert> {
+        let target = tx.to;
 
         if let Some(alert) = self.check_frontrun(&target, &tx) {
             return Some(alert);
@@ -57,14 +229,14 @@ impl MevDetector {
     }
 
     /// Детекция фронтраннинга
-    fn check_frontrun(&self, target: &str, new_tx: &ffi::Tx) -> Option<MevAlert> {
+    fn check_frontrun(&self, target: &address::ChecksummedAddress, new_tx: &tx::Tx) -> Option<MevAlert> {
         self.pending_pool.get(target).and_then(|pending| {
             pending.iter().find_map(|existing| {
                 if self.is_frontrun_candidate(existing, new_tx) {
                     let profit = ffi::simulate_profit(
                         &self.simulator,
-                        existing,
-                        new_tx
+                        &existing.to_sim(),
+                        &new_tx.to_sim()
                     );
                     
                     if profit > 0.0 {
@@ -83,34 +255,50 @@ impl MevDetector {
         })
     }
 
-    fn is_frontrun_candidate(&self, existing: &ffi::Tx, new: &ffi::Tx) -> bool {
+    fn is_frontrun_candidate(&self, existing: &tx::Tx, new: &tx::Tx) -> bool {
         // 1. Тот же целевой контракт
         existing.to == new.to &&
         // 2. Похожий input (вызов той же функции)
         existing.input == new.input &&
         // 3. Более высокий gas price (минимум +10%)
-        new.gas_price > existing.gas_price * 1.1
+        new.outbids(existing, 10)
     }
 
     /// Расчет риска (0.0 - 1.0)
-    fn calculate_risk(&self, profit: f64) -> f8 {
-        (profit / 10.0).min(1.0) // Нормализуем к 10 ETH
+    fn calculate_risk(&self, profit: f64) -> f32 {
+        (profit / 10.0).min(1.0) as f32 // Нормализуем к 10 ETH
     }
 }
 
+#[cfg(feature = "ffi-c")]
 #[no_mangle]
 pub extern "C" fn mev_detector_new() -> *mut MevDetector {
     Box::into_raw(Box::new(MevDetector::new()))
 }
 
+/// Алерт в JSON или null, если алерта нет или `tx_json` не разбирается
+///
+/// # Safety
+///
+/// `detector` — указатель из `mev_detector_new`, которым в этот момент не пользуется
+/// другой поток; `tx_json` — нуль-терминированная строка. Null в любом из них — null в ответе
+#[cfg(feature = "ffi-c")]
 #[no_mangle]
-pub extern "C" fn mev_detector_analyze(
+pub unsafe extern "C" fn mev_detector_analyze(
     detector: *mut MevDetector,
     tx_json: *const c_char,
 ) -> *mut c_char {
+    if detector.is_null() || tx_json.is_null() {
+        return ptr::null_mut();
+    }
     let detector = unsafe { &mut *detector };
-    let tx_str = unsafe { CStr::from_ptr(tx_json).to_str().unwrap() };
-    let tx: ffi::Tx = serde_json::from_str(tx_str).unwrap();
+    let Some(tx) = unsafe { CStr::from_ptr(tx_json) }
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str::<tx::Tx>(s).ok())
+    else {
+        return ptr::null_mut();
+    };
 
     if let Some(alert) = detector.analyze(tx) {
         let alert_json = serde_json::to_string(&alert).unwrap();
@@ -0,0 +1,127 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Eip1559TransactionRequest, Transaction, U256};
+use ethers::utils::rlp::Rlp;
+use mevdetector::ingest::{tx_from_raw, tx_from_rpc};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+fn fixture() -> (Vec<u8>, String) {
+    let wallet: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        .parse()
+        .unwrap();
+    let typed: TypedTransaction = Eip1559TransactionRequest::new()
+        .to(Address::repeat_byte(0x11))
+        .value(U256::exp10(18))
+        .nonce(7)
+        .gas(210_000)
+        .max_fee_per_gas(U256::from(40_000_000_000u64))
+        .max_priority_fee_per_gas(U256::from(2_000_000_000u64))
+        .chain_id(1)
+        .data([0x38, 0xed, 0x17, 0x39].repeat(50))
+        .into();
+    let from = wallet.address();
+    let signature = wallet.with_chain_id(1u64).sign_transaction_sync(&typed).unwrap();
+    let raw = typed.rlp_signed(&signature).to_vec();
+
+    let mut tx: Transaction = Rlp::new(&raw).as_val().unwrap();
+    tx.from = from;
+    let json = serde_json::to_string(&tx).unwrap();
+    (raw, json)
+}
+
+/// Узел на loopback, отвечающий на каждый запрос `eth_getTransactionByHash` одной и той же транзакцией
+fn serve_transaction(tx_json: String) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let body = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{}}}"#, tx_json);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            while let Some(length) = read_request(&mut reader) {
+                let mut request = vec![0; length];
+                if reader.read_exact(&mut request).is_err() {
+                    break;
+                }
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                if writer.write_all(response.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    port
+}
+
+/// Заголовки HTTP-сообщения; возвращает длину тела
+fn read_request(reader: &mut impl BufRead) -> Option<usize> {
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            return Some(length);
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok()?;
+            }
+        }
+    }
+}
+
+/// Путь JSON-RPC при подписке на хэши: запрос транзакции по хэшу и её разбор
+fn fetch_by_hash(reader: &mut BufReader<TcpStream>, writer: &mut TcpStream) -> Transaction {
+    let request = r#"{"jsonrpc":"2.0","id":1,"method":"eth_getTransactionByHash","params":["0x00"]}"#;
+    write!(writer, "POST / HTTP/1.1\r\nHost: node\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", request.len(), request).unwrap();
+    let length = read_request(reader).unwrap();
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    let mut response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    serde_json::from_value(response["result"].take()).unwrap()
+}
+
+/// Стоимость разбора одной транзакции мемпула: JSON-RPC против RLP из txpool gRPC
+fn bench_decode(c: &mut Criterion) {
+    let (raw, json) = fixture();
+
+    c.bench_function("json_rpc_transaction", |b| {
+        b.iter(|| {
+            let tx: Transaction = serde_json::from_str(black_box(&json)).unwrap();
+            tx_from_rpc(&tx)
+        })
+    });
+
+    c.bench_function("erigon_rlp_with_sender_recovery", |b| {
+        b.iter(|| tx_from_raw(black_box(&raw)).unwrap())
+    });
+}
+
+/// Задержка от анонса транзакции до готового `Tx`. JSON-RPC-подписка на хэши добавляет
+/// `eth_getTransactionByHash` на каждую транзакцию; здесь это один круг по loopback, то есть
+/// нижняя граница: до удалённого узла разница больше на сетевую задержку. txpool gRPC
+/// присылает RLP сразу, и задержка — это разбор с восстановлением отправителя
+fn bench_latency(c: &mut Criterion) {
+    let (raw, json) = fixture();
+    let port = serve_transaction(json);
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_nodelay(true).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    let mut group = c.benchmark_group("announce_to_tx");
+    group.bench_function("json_rpc_hash_then_fetch", |b| {
+        b.iter(|| tx_from_rpc(&fetch_by_hash(&mut reader, &mut writer)))
+    });
+    group.bench_function("erigon_txpool_stream", |b| b.iter(|| tx_from_raw(black_box(&raw)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode, bench_latency);
+criterion_main!(benches);
@@ -0,0 +1,171 @@
+use crate::address::ChecksummedAddress;
+use ethers::abi::{Abi, Function, HumanReadableParser, Token};
+use ethers::utils::hex;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AbiError {
+    #[error("Cannot parse function signature '{0}': {1}")]
+    InvalidSignature(String, String),
+}
+
+/// Функции, которые разбираются без конфигурации: ERC-20/721, WETH и роутеры Uniswap
+const WELL_KNOWN: &[&str] = &[
+    "function transfer(address to, uint256 amount)",
+    "function transferFrom(address from, address to, uint256 amount)",
+    "function approve(address spender, uint256 amount)",
+    "function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)",
+    "function setApprovalForAll(address operator, bool approved)",
+    "function deposit()",
+    "function withdraw(uint256 amount)",
+    "function swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "function swapExactTokensForETH(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline)",
+    "function swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline)",
+    // Парсер сигнатур не принимает имена полей кортежа, они теряются в любом случае
+    "function exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160) params)",
+    "function exactInput((bytes,address,uint256,uint256,uint256) params)",
+    "function multicall(bytes[] data)",
+    "function multicall(uint256 deadline, bytes[] data)",
+    "function execute(bytes commands, bytes[] inputs, uint256 deadline)",
+];
+
+/// Аргумент разобранного вызова
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DecodedArg {
+    pub name: String,
+    /// Тип Solidity: `address`, `uint256`, `address[]`, ...
+    pub kind: String,
+    /// Адреса — с контрольной суммой, числа — десятичные, байты — hex
+    pub value: String,
+}
+
+/// Calldata, разобранная по известной сигнатуре
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DecodedCall {
+    /// `0x` + 4 байта
+    pub selector: String,
+    /// Каноническая сигнатура, например `transfer(address,uint256)`
+    pub signature: String,
+    pub name: String,
+    pub args: Vec<DecodedArg>,
+}
+
+/// Реестр ABI по селекторам для разбора calldata
+pub struct AbiRegistry {
+    functions: RwLock<HashMap<[u8; 4], Function>>,
+}
+
+impl Default for AbiRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AbiRegistry {
+    /// Пустой реестр
+    pub fn empty() -> Self {
+        Self { functions: RwLock::new(HashMap::new()) }
+    }
+
+    /// Реестр с известными функциями
+    pub fn new() -> Self {
+        let registry = Self::empty();
+        for signature in WELL_KNOWN {
+            registry.register_signature(signature).expect("well-known signature");
+        }
+        registry
+    }
+
+    /// Человекочитаемая сигнатура: `function name(type arg, ...)`
+    pub fn register_signature(&self, signature: &str) -> Result<(), AbiError> {
+        let function = HumanReadableParser::parse_function(signature)
+            .map_err(|e| AbiError::InvalidSignature(signature.to_string(), e.to_string()))?;
+        self.register(function);
+        Ok(())
+    }
+
+    /// Все функции ABI контракта
+    pub fn register_abi(&self, abi: &Abi) {
+        for function in abi.functions() {
+            self.register(function.clone());
+        }
+    }
+
+    pub fn register(&self, function: Function) {
+        self.functions.write().unwrap().insert(function.short_signature(), function);
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `None` — селектор неизвестен или аргументы не подходят под сигнатуру
+    pub fn decode(&self, input: &[u8]) -> Option<DecodedCall> {
+        let selector: [u8; 4] = input.get(..4)?.try_into().ok()?;
+        let functions = self.functions.read().unwrap();
+        let function = functions.get(&selector)?;
+        let tokens = function.decode_input(&input[4..]).ok()?;
+
+        let args = function
+            .inputs
+            .iter()
+            .zip(&tokens)
+            .map(|(param, token)| DecodedArg {
+                name: param.name.clone(),
+                kind: param.kind.to_string(),
+                value: format_token(token),
+            })
+            .collect();
+        Some(DecodedCall {
+            selector: format!("0x{}", hex::encode(selector)),
+            signature: function.signature(),
+            name: function.name.clone(),
+            args,
+        })
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(a) => ChecksummedAddress::from(*a).to_string(),
+        Token::Uint(v) | Token::Int(v) => v.to_string(),
+        Token::Bool(b) => b.to_string(),
+        Token::String(s) => s.clone(),
+        Token::Bytes(b) | Token::FixedBytes(b) => format!("0x{}", hex::encode(b)),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(format_token).collect();
+            format!("[{}]", items.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::addr;
+    use ethers::abi::encode;
+    use crate::compat::U256;
+
+    #[test]
+    fn test_decodes_well_known_transfer() {
+        let registry = AbiRegistry::new();
+        let mut input = ethers::utils::id("transfer(address,uint256)")[..4].to_vec();
+        input.extend(encode(&[Token::Address(addr(0x22).address()), Token::Uint(U256::from(1500))]));
+
+        let call = registry.decode(&input).unwrap();
+        assert_eq!(call.signature, "transfer(address,uint256)");
+        assert_eq!(call.args[0].value, addr(0x22).to_string());
+        assert_eq!(call.args[1], DecodedArg { name: "amount".into(), kind: "uint256".into(), value: "1500".into() });
+        assert!(registry.decode(&[0xde, 0xad, 0xbe, 0xef]).is_none());
+    }
+}
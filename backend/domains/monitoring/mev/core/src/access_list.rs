@@ -0,0 +1,121 @@
+use crate::compat::Address;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::{AccessList, Eip2930TransactionRequest};
+use ethers::types::BlockId;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AccessListError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+/// Экономия на одно предобъявленное обращение по EIP-2929/2930:
+/// аккаунт 2600 холодный против 2400 в списке + 100 тёплый, слот 2100 против 1900 + 100
+pub const SAVING_PER_ACCOUNT: u64 = 100;
+pub const SAVING_PER_SLOT: u64 = 100;
+
+/// Список доступа EIP-2930 для транзакции и его выгода
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AccessListPlan {
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub access_list: AccessList,
+    pub accounts: usize,
+    pub slots: usize,
+    /// Оценка газа узлом без списка и со списком
+    pub gas_without_list: u64,
+    pub gas_with_list: u64,
+    /// Экономия по оценке узла; 0, если список не окупается
+    pub gas_saved: u64,
+    /// Экономия по числу записей, без симуляции
+    pub estimated_savings: u64,
+}
+
+impl AccessListPlan {
+    pub fn worthwhile(&self) -> bool {
+        self.gas_saved > 0
+    }
+}
+
+/// Экономия по записям списка; отправитель и получатель тёплые и так, их записи не окупаются
+pub fn estimate_savings(list: &AccessList, from: Option<Address>, to: Option<Address>) -> u64 {
+    list.0
+        .iter()
+        .map(|item| {
+            let account = if Some(item.address) == from || Some(item.address) == to { 0 } else { SAVING_PER_ACCOUNT };
+            account + SAVING_PER_SLOT * item.storage_keys.len() as u64
+        })
+        .sum()
+}
+
+/// Legacy-транзакция не несёт списка доступа: становится типом 1 с той же ценой газа
+pub fn with_access_list(tx: &TypedTransaction, list: AccessList) -> TypedTransaction {
+    match tx {
+        TypedTransaction::Legacy(request) => {
+            TypedTransaction::Eip2930(Eip2930TransactionRequest::new(request.clone(), list))
+        }
+        other => {
+            let mut tx = other.clone();
+            tx.set_access_list(list);
+            tx
+        }
+    }
+}
+
+/// Строит список доступа симуляцией транзакции на узле (`eth_createAccessList`)
+pub struct AccessListPlanner<M> {
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> AccessListPlanner<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider }
+    }
+
+    pub async fn plan(&self, tx: &TypedTransaction, block: Option<BlockId>) -> Result<AccessListPlan, AccessListError> {
+        let provider_err = |e: M::Error| AccessListError::ProviderError(e.to_string());
+
+        let mut plain = tx.clone();
+        plain.set_access_list(AccessList::default());
+        let gas_without_list = self.provider.estimate_gas(&plain, block).await.map_err(provider_err)?.as_u64();
+
+        let created = self.provider.create_access_list(&plain, block).await.map_err(provider_err)?;
+        let with_list = with_access_list(&plain, created.access_list.clone());
+        let gas_with_list = self.provider.estimate_gas(&with_list, block).await.map_err(provider_err)?.as_u64();
+
+        let list = created.access_list;
+        Ok(AccessListPlan {
+            accounts: list.0.len(),
+            slots: list.0.iter().map(|item| item.storage_keys.len()).sum(),
+            estimated_savings: estimate_savings(&list, tx.from().copied(), tx.to_addr().copied()),
+            gas_saved: gas_without_list.saturating_sub(gas_with_list),
+            gas_without_list,
+            gas_with_list,
+            access_list: list,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip2930::AccessListItem;
+    use crate::compat::H256;
+
+    #[test]
+    fn test_sender_and_target_entries_only_save_on_slots() {
+        let (from, to, pool) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let list = AccessList(vec![
+            AccessListItem { address: to, storage_keys: vec![H256::zero(), H256::repeat_byte(1)] },
+            AccessListItem { address: pool, storage_keys: vec![H256::zero()] },
+        ]);
+        assert_eq!(estimate_savings(&list, Some(from), Some(to)), 2 * SAVING_PER_SLOT + SAVING_PER_ACCOUNT + SAVING_PER_SLOT);
+
+        let legacy = TypedTransaction::Legacy(Default::default());
+        assert!(matches!(with_access_list(&legacy, list), TypedTransaction::Eip2930(_)));
+    }
+}
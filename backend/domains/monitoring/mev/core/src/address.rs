@@ -0,0 +1,81 @@
+use crate::compat::{to_checksum, Address};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Invalid address '{0}'")]
+pub struct AddressError(pub String);
+
+/// Адрес, нормализованный при разборе: сравнивается как 20 байт, печатается в EIP-55.
+/// Разбор принимает любой регистр, поэтому `0xabc…` и `0xABC…` — один ключ пула.
+/// Нулевой адрес — транзакция создания контракта (нет получателя)
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChecksummedAddress(Address);
+
+impl ChecksummedAddress {
+    pub const ZERO: Self = Self(Address::zero());
+
+    pub fn address(&self) -> Address {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl From<Address> for ChecksummedAddress {
+    fn from(address: Address) -> Self {
+        Self(address)
+    }
+}
+
+impl From<ChecksummedAddress> for Address {
+    fn from(address: ChecksummedAddress) -> Self {
+        address.0
+    }
+}
+
+/// Любой регистр, с `0x` или без; пустая строка — нулевой адрес
+impl FromStr for ChecksummedAddress {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Ok(Self::ZERO);
+        }
+        let hex = trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")).unwrap_or(trimmed);
+        if hex.len() != 40 {
+            return Err(AddressError(s.to_string()));
+        }
+        hex.parse::<Address>().map(Self).map_err(|_| AddressError(s.to_string()))
+    }
+}
+
+impl fmt::Display for ChecksummedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_checksum(&self.0))
+    }
+}
+
+impl fmt::Debug for ChecksummedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Serialize for ChecksummedAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChecksummedAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
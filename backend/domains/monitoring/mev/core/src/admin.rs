@@ -0,0 +1,492 @@
+use crate::audit::{AuditError, AuditLog, AuditQuery};
+use crate::backfill::{BackfillError, BackfillRunner};
+use crate::bus::ALERT_BUFFER_NS;
+use crate::encryption::{EncryptedStore, ReadScope};
+use crate::openapi;
+use crate::registry::{DetectorPatch, DetectorRegistry, RegistryError};
+use crate::routing::AlertRouter;
+use crate::rpc::RpcQuota;
+use crate::secrets::SecretString;
+use crate::shutdown::ShutdownSignal;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Состояние админ-API
+#[derive(Clone)]
+pub struct AdminState {
+    pub registry: Arc<DetectorRegistry>,
+    /// Bearer-токен; без него API доступно только с localhost (см. `serve`)
+    pub token: Option<Arc<SecretString>>,
+    /// Журнал подписанных действий; без него `/admin/audit` отвечает 404
+    pub audit: Option<Arc<AuditLog>>,
+    /// Токен, которому зашифрованные поля отдаются расшифрованными; остальным — конверты
+    pub sensitive_token: Option<Arc<SecretString>>,
+    /// Хранилище с шифрованием полей; без него `/admin/alerts/buffered` отвечает 404
+    pub store: Option<Arc<EncryptedStore>>,
+    /// Учёт квот RPC; без него `/admin/rpc/usage` отвечает 404
+    pub rpc: Option<Arc<RpcQuota>>,
+    /// Маршрутизатор алертов; без него `/admin/alerts/{id}/ack` отвечает 404
+    pub routing: Option<Arc<AlertRouter>>,
+    /// Фоновые заполнения; без них `/admin/backfill` отвечает 404
+    pub backfill: Option<Arc<BackfillRunner>>,
+}
+
+impl IntoResponse for RegistryError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            RegistryError::UnknownDetector(_) => StatusCode::NOT_FOUND,
+            RegistryError::InvalidValue { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+impl IntoResponse for BackfillError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            BackfillError::UnknownJob(_) => StatusCode::NOT_FOUND,
+            BackfillError::InvalidState(..) => StatusCode::CONFLICT,
+            BackfillError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+/// Сравнение без раннего выхода, чтобы не раскрывать токен по времени ответа
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn bearer(headers: &HeaderMap) -> &str {
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default()
+}
+
+/// Расшифровка только по отдельному токену; основной токен её не даёт
+fn read_scope(state: &AdminState, headers: &HeaderMap) -> ReadScope {
+    match &state.sensitive_token {
+        Some(token) if constant_time_eq(bearer(headers).as_bytes(), token.expose().as_bytes()) => ReadScope::Decrypted,
+        _ => ReadScope::Sealed,
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn authorize(state: &AdminState, headers: &HeaderMap) -> Result<(), Response> {
+    let Some(token) = &state.token else {
+        return Ok(());
+    };
+    let provided = bearer(headers);
+    let sensitive = state.sensitive_token.as_ref().is_some_and(|t| constant_time_eq(provided.as_bytes(), t.expose().as_bytes()));
+
+    if sensitive || constant_time_eq(provided.as_bytes(), token.expose().as_bytes()) {
+        Ok(())
+    } else {
+        Err((StatusCode::UNAUTHORIZED, Json(json!({ "error": "unauthorized" }))).into_response())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/detectors",
+    tag = "detectors",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Settings and hit statistics of every detector", body = [DetectorStatus]),
+        (status = 401, description = "Missing or invalid bearer token"),
+    )
+)]
+async fn list_detectors(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    Json(state.registry.statuses()).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/detectors/{name}",
+    tag = "detectors",
+    security(("bearer" = [])),
+    params(("name" = String, Path, description = "Detector name, e.g. `sandwich`")),
+    responses(
+        (status = 200, body = DetectorStatus),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Unknown detector"),
+    )
+)]
+async fn get_detector(State(state): State<AdminState>, headers: HeaderMap, Path(name): Path<String>) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match state.registry.status(&name) {
+        Some(status) => Json(status).into_response(),
+        None => RegistryError::UnknownDetector(name).into_response(),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin/detectors/{name}",
+    tag = "detectors",
+    security(("bearer" = [])),
+    params(("name" = String, Path, description = "Detector name, e.g. `sandwich`")),
+    request_body = DetectorPatch,
+    responses(
+        (status = 200, description = "Settings after the change", body = DetectorStatus),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Unknown detector"),
+        (status = 422, description = "Value out of range"),
+    )
+)]
+async fn patch_detector(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(patch): Json<DetectorPatch>,
+) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match state.registry.patch(&name, patch) {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+fn audit_error(e: AuditError) -> Response {
+    let status = match &e {
+        AuditError::ChainBroken { .. } => StatusCode::CONFLICT,
+        AuditError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(json!({ "error": e.to_string() }))).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "audit",
+    security(("bearer" = [])),
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Entries from newest to oldest", body = [AuditEntry]),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Audit log is not configured"),
+    )
+)]
+async fn query_audit(State(state): State<AdminState>, headers: HeaderMap, Query(query): Query<AuditQuery>) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    let Some(audit) = &state.audit else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match audit.query(&query) {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => audit_error(e),
+    }
+}
+
+/// Целостная хэш-цепочка журнала
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditVerification {
+    pub valid: bool,
+    /// Проверено записей
+    pub entries: u64,
+    /// Записей, ещё ожидающих записи
+    pub deferred: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit/verify",
+    tag = "audit",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Hash chain is intact", body = AuditVerification),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Audit log is not configured"),
+        (status = 409, description = "Hash chain is broken"),
+    )
+)]
+async fn verify_audit(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    let Some(audit) = &state.audit else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match audit.verify() {
+        Ok(entries) => Json(AuditVerification { valid: true, entries, deferred: audit.deferred() }).into_response(),
+        Err(e) => audit_error(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/alerts/buffered",
+    tag = "alerts",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Undelivered alerts saved at shutdown; sensitive fields are decrypted only for the sensitive token", body = [crate::bus::BusAlert]),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Encrypted store is not configured"),
+    )
+)]
+async fn buffered_alerts(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    let Some(store) = &state.store else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let result = store
+        .get_scoped(ALERT_BUFFER_NS, "pending", read_scope(&state, &headers))
+        .and_then(|bytes| bytes.map(|b| serde_json::from_slice::<serde_json::Value>(&b)).transpose().map_err(Into::into));
+    match result {
+        Ok(alerts) => Json(alerts.unwrap_or_else(|| json!([]))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/rpc/usage",
+    tag = "rpc",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "RPC calls, compute units, retries and quota waits per subsystem", body = [SubsystemUsage]),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "RPC quotas are not configured"),
+    )
+)]
+async fn rpc_usage(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match &state.rpc {
+        Some(quota) => Json(quota.usage()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/alerts/{id}/ack",
+    tag = "alerts",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "Alert id from the delivered message, see `routing::alert_id`")),
+    responses(
+        (status = 204, description = "Escalation stopped"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Routing is not configured or nothing is pending for the alert"),
+    )
+)]
+async fn acknowledge_alert(State(state): State<AdminState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match &state.routing {
+        Some(router) if router.acknowledge(&id).await => StatusCode::NO_CONTENT.into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/alerts/{id}/resolve",
+    tag = "alerts",
+    security(("bearer" = [])),
+    params(("id" = String, Path, description = "Alert id from the delivered message, see `routing::alert_id`")),
+    responses(
+        (status = 204, description = "Escalation stopped and incidents closed on every platform that received the alert"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Routing is not configured or the alert is unknown"),
+    )
+)]
+async fn resolve_alert(State(state): State<AdminState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match &state.routing {
+        Some(router) if router.resolve(&id).await => StatusCode::NO_CONTENT.into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/incidents/{sink}",
+    tag = "alerts",
+    security(("bearer" = [])),
+    params(("sink" = String, Path, description = "Name of the `pagerduty` (v3 webhook) or `opsgenie` (outgoing webhook) sink in `[routing]`")),
+    responses(
+        (status = 204, description = "Acknowledgement or resolution applied, or event ignored"),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Routing is not configured or unknown sink"),
+    )
+)]
+async fn incident_webhook(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(sink): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    let Some(router) = &state.routing else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    // Платформа шлёт и события, которые нас не касаются: на них тоже 2xx, иначе она повторяет
+    match router.incident_webhook(&sink, &body).await {
+        Some(()) => StatusCode::NO_CONTENT.into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/backfill",
+    tag = "backfill",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Progress of every backfill job", body = [JobStatus]),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Backfills are not configured"),
+    )
+)]
+async fn list_backfills(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match &state.backfill {
+        Some(runner) => Json(runner.statuses()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/backfill/{name}",
+    tag = "backfill",
+    security(("bearer" = [])),
+    params(("name" = String, Path, description = "Backfill job name")),
+    responses(
+        (status = 200, description = "Job progress", body = JobStatus),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Unknown job or backfills are not configured"),
+    )
+)]
+async fn get_backfill(State(state): State<AdminState>, headers: HeaderMap, Path(name): Path<String>) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    match state.backfill.as_ref().and_then(|runner| runner.status(&name)) {
+        Some(status) => Json(status).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/backfill/{name}/{action}",
+    tag = "backfill",
+    security(("bearer" = [])),
+    params(
+        ("name" = String, Path, description = "Backfill job name"),
+        ("action" = String, Path, description = "`pause`, `resume` or `restart` (drops saved progress)"),
+    ),
+    responses(
+        (status = 200, description = "Job progress after the change", body = JobStatus),
+        (status = 401, description = "Missing or invalid bearer token"),
+        (status = 404, description = "Unknown job or action, or backfills are not configured"),
+        (status = 409, description = "Job is not in a state the action applies to"),
+    )
+)]
+async fn control_backfill(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path((name, action)): Path<(String, String)>,
+) -> Response {
+    if let Err(denied) = authorize(&state, &headers) {
+        return denied;
+    }
+    let Some(runner) = &state.backfill else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let result = match action.as_str() {
+        "pause" => runner.pause(&name),
+        "resume" => runner.resume(&name),
+        "restart" => runner.restart(&name),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    match result {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub fn router(state: AdminState) -> Router {
+    Router::new()
+        .route("/admin/detectors", get(list_detectors))
+        .route("/admin/detectors/:name", get(get_detector).patch(patch_detector))
+        .route("/admin/audit", get(query_audit))
+        .route("/admin/audit/verify", get(verify_audit))
+        .route("/admin/alerts/buffered", get(buffered_alerts))
+        .route("/admin/alerts/:id/ack", post(acknowledge_alert))
+        .route("/admin/alerts/:id/resolve", post(resolve_alert))
+        .route("/admin/incidents/:sink", post(incident_webhook))
+        .route("/admin/rpc/usage", get(rpc_usage))
+        .route("/admin/backfill", get(list_backfills))
+        .route("/admin/backfill/:name", get(get_backfill))
+        .route("/admin/backfill/:name/:action", post(control_backfill))
+        .with_state(state)
+}
+
+async fn require_token(State(state): State<AdminState>, request: Request, next: Next) -> Response {
+    if let Err(denied) = authorize(&state, request.headers()) {
+        return denied;
+    }
+    next.run(request).await
+}
+
+/// Закрывает роутер другой подсистемы (согласование Safe, оценка транзакций) той же
+/// bearer-проверкой, что и админ-API
+pub fn protect(routes: Router, state: &AdminState) -> Router {
+    routes.layer(middleware::from_fn_with_state(state.clone(), require_token))
+}
+
+/// Поднимает админ-API и закрытые `protect` роутеры `routes` до сигнала остановки;
+/// `doc` отдаётся на `/openapi.json`. Без токена слушать можно только loopback
+pub async fn serve(
+    addr: SocketAddr,
+    state: AdminState,
+    routes: Router,
+    doc: utoipa::openapi::OpenApi,
+    mut shutdown: ShutdownSignal,
+) -> std::io::Result<()> {
+    if state.token.is_none() && !addr.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "admin API without a token must listen on a loopback address",
+        ));
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let app = router(state).merge(routes).merge(openapi::router(doc));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await
+}
@@ -0,0 +1,258 @@
+use crate::compat::U256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+use thiserror::Error;
+
+const ETH_DECIMALS: usize = 18;
+const GWEI_DECIMALS: usize = 9;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AmountError {
+    #[error("Invalid amount '{0}'")]
+    Invalid(String),
+
+    #[error("Amount '{0}' has more than {1} decimal places")]
+    TooPrecise(String, usize),
+}
+
+/// Сумма в wei без потери точности.
+/// В JSON — `{"wei": "...", "eth": "..."}`; читаются также десятичная строка и число в ETH.
+/// В бинарных форматах (снимки движка) — только wei
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WeiAmount(U256);
+
+impl WeiAmount {
+    pub const ZERO: Self = Self(U256([0; 4]));
+
+    pub fn from_wei(wei: impl Into<U256>) -> Self {
+        Self(wei.into())
+    }
+
+    pub fn wei(&self) -> U256 {
+        self.0
+    }
+
+    /// Из ETH в f64 (конфиг, ответ симулятора); отрицательные и нечисловые значения дают ноль
+    pub fn from_eth(eth: f64) -> Self {
+        Self::from_f64(eth, ETH_DECIMALS)
+    }
+
+    pub fn from_gwei(gwei: f64) -> Self {
+        Self::from_f64(gwei, GWEI_DECIMALS)
+    }
+
+    fn from_f64(value: f64, decimals: usize) -> Self {
+        if !value.is_finite() || value <= 0.0 {
+            return Self::ZERO;
+        }
+        Self::parse_units(&format!("{:.*}", decimals, value), decimals).unwrap_or(Self::ZERO)
+    }
+
+    /// Десятичная строка в единицах с `decimals` знаками: `parse_units("1.5", 18)` — 1.5 ETH
+    pub fn parse_units(s: &str, decimals: usize) -> Result<Self, AmountError> {
+        let invalid = || AmountError::Invalid(s.to_string());
+        let (int, frac) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid());
+        }
+        if !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let frac = frac.trim_end_matches('0');
+        if frac.len() > decimals {
+            return Err(AmountError::TooPrecise(s.to_string(), decimals));
+        }
+
+        let scale = U256::exp10(decimals);
+        let int = if int.is_empty() { U256::zero() } else { U256::from_dec_str(int).map_err(|_| invalid())? };
+        let frac = if frac.is_empty() {
+            U256::zero()
+        } else {
+            U256::from_dec_str(frac).map_err(|_| invalid())? * U256::exp10(decimals - frac.len())
+        };
+        int.checked_mul(scale)
+            .and_then(|v| v.checked_add(frac))
+            .map(Self)
+            .ok_or_else(invalid)
+    }
+
+    /// Приближённое значение для эвристик (risk score, пороги в процентах)
+    pub fn to_eth_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::MAX)
+    }
+
+    /// ETH с фиксированным числом знаков, с усечением: `format_eth(4)` → `"0.0500"`
+    pub fn format_eth(&self, decimals: usize) -> String {
+        let (int, frac) = self.split(ETH_DECIMALS);
+        match decimals.min(ETH_DECIMALS) {
+            0 => int,
+            d => format!("{}.{}", int, &frac[..d]),
+        }
+    }
+
+    /// Целая часть и дробная, дополненная нулями до `decimals` знаков
+    fn split(&self, decimals: usize) -> (String, String) {
+        let scale = U256::exp10(decimals);
+        let (int, frac) = self.0.div_mod(scale);
+        (int.to_string(), format!("{:0>width$}", frac.to_string(), width = decimals))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    /// Для очередей по цене газа: значения больше `u64::MAX` упираются в него
+    pub fn saturating_u64(&self) -> u64 {
+        if self.0 > U256::from(u64::MAX) {
+            u64::MAX
+        } else {
+            self.0.as_u64()
+        }
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.0.to_big_endian(&mut bytes);
+        bytes
+    }
+}
+
+impl Add for WeiAmount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl From<U256> for WeiAmount {
+    fn from(wei: U256) -> Self {
+        Self(wei)
+    }
+}
+
+/// Десятичная запись в ETH без лишних нулей
+impl fmt::Display for WeiAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (int, frac) = self.split(ETH_DECIMALS);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            write!(f, "{}", int)
+        } else {
+            write!(f, "{}.{}", int, frac)
+        }
+    }
+}
+
+/// Десятичная строка в ETH
+impl FromStr for WeiAmount {
+    type Err = AmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_units(s, ETH_DECIMALS)
+    }
+}
+
+#[derive(Serialize)]
+struct WeiRepr {
+    wei: String,
+    eth: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WeiInput {
+    Wei { wei: String },
+    Eth(String),
+    Number(f64),
+}
+
+impl Serialize for WeiAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            WeiRepr { wei: self.0.to_string(), eth: self.to_string() }.serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WeiAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return U256::deserialize(deserializer).map(Self);
+        }
+        match WeiInput::deserialize(deserializer)? {
+            WeiInput::Wei { wei } => U256::from_dec_str(&wei)
+                .map(Self)
+                .map_err(|_| serde::de::Error::custom(AmountError::Invalid(wei))),
+            WeiInput::Eth(eth) => eth.parse().map_err(serde::de::Error::custom),
+            WeiInput::Number(eth) if eth >= 0.0 => Ok(Self::from_eth(eth)),
+            WeiInput::Number(eth) => Err(serde::de::Error::custom(AmountError::Invalid(eth.to_string()))),
+        }
+    }
+}
+
+/// Для полей, которые внешние системы всегда передавали в wei (`gas_price`): в JSON —
+/// десятичная строка wei; читаются также целое число и `{"wei": "..."}`, но не ETH
+pub mod wei {
+    use super::{AmountError, WeiAmount};
+    use crate::compat::U256;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum WeiField {
+        Object { wei: String },
+        Decimal(String),
+        Integer(u64),
+    }
+
+    pub fn serialize<S: Serializer>(amount: &WeiAmount, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            amount.wei().to_string().serialize(serializer)
+        } else {
+            amount.wei().serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<WeiAmount, D::Error> {
+        if !deserializer.is_human_readable() {
+            return U256::deserialize(deserializer).map(WeiAmount::from_wei);
+        }
+        match WeiField::deserialize(deserializer)? {
+            WeiField::Integer(wei) => Ok(WeiAmount::from_wei(wei)),
+            WeiField::Object { wei } | WeiField::Decimal(wei) => U256::from_dec_str(&wei)
+                .map(WeiAmount::from_wei)
+                .map_err(|_| serde::de::Error::custom(AmountError::Invalid(wei))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_decimal_roundtrip() {
+        let amount: WeiAmount = "1.000000000000000001".parse().unwrap();
+        assert_eq!(amount.wei(), U256::exp10(18) + U256::one());
+        assert_eq!(amount.to_string(), "1.000000000000000001");
+        assert_eq!(WeiAmount::from_eth(0.05).format_eth(4), "0.0500");
+        assert_eq!(WeiAmount::from_gwei(30.0).wei(), U256::from(30_000_000_000u64));
+        assert!(matches!("0.1234567890123456789".parse::<WeiAmount>(), Err(AmountError::TooPrecise(..))));
+
+        let json = serde_json::to_value(amount).unwrap();
+        assert_eq!(json, serde_json::json!({ "wei": "1000000000000000001", "eth": "1.000000000000000001" }));
+        assert_eq!(serde_json::from_value::<WeiAmount>(json).unwrap(), amount);
+        assert_eq!(serde_json::from_str::<WeiAmount>("1.5").unwrap(), WeiAmount::from_eth(1.5));
+
+        #[cfg(feature = "mev")]
+        {
+            let bytes = bincode::serialize(&amount).unwrap();
+            assert_eq!(bincode::deserialize::<WeiAmount>(&bytes).unwrap(), amount);
+        }
+    }
+}
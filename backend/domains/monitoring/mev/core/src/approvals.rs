@@ -0,0 +1,342 @@
+use crate::address::ChecksummedAddress;
+use crate::bus::AlertLevel;
+use crate::compat::{keccak256, Address, H256, U256};
+use crate::permit2::{self, Permit2Exposure};
+use crate::typed_data::UNLIMITED_BITS;
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::contract::EthCall;
+use ethers::providers::call_raw::spoof;
+use ethers::providers::{JsonRpcClient, Middleware, Provider, RawCall};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, Bytes, TransactionRequest};
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Сколько первых слотов перебирается в поисках маппинга `allowance`
+const MAX_SLOT_PROBE: u64 = 64;
+
+/// Слот адреса реализации EIP-1967: `keccak256("eip1967.proxy.implementation") - 1`
+const EIP1967_IMPLEMENTATION_SLOT: &str = "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// Получатель при симуляции: адрес без кода и без баланса, чтобы хук токена не мешал
+const SINK: Address = Address::repeat_byte(0xde);
+
+/// Баланс, подставляемый владельцу с нулевым балансом под безлимитным разрешением:
+/// 10^30 минимальных единиц, с запасом для токенов с 18 знаками и без риска переполнения
+const PROBE_BALANCE: u128 = 1_000_000_000_000_000_000_000_000_000_000;
+
+#[derive(Debug, Error)]
+pub enum ApprovalSimError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Token {0} does not answer balanceOf/allowance as ERC-20")]
+    NotErc20(ChecksummedAddress),
+}
+
+#[derive(Clone, Debug, EthCall)]
+#[ethcall(name = "balanceOf", abi = "balanceOf(address)")]
+struct BalanceOfCall {
+    owner: Address,
+}
+
+#[derive(Clone, Debug, EthCall)]
+#[ethcall(name = "allowance", abi = "allowance(address,address)")]
+struct AllowanceCall {
+    owner: Address,
+    spender: Address,
+}
+
+#[derive(Clone, Debug, EthCall)]
+#[ethcall(name = "transferFrom", abi = "transferFrom(address,address,uint256)")]
+struct TransferFromCall {
+    from: Address,
+    to: Address,
+    amount: U256,
+}
+
+#[derive(Clone, Debug, EthCall)]
+#[ethcall(name = "owner", abi = "owner()")]
+struct OwnerCall;
+
+/// Проверяемое разрешение: `owner` дал `spender` право на `amount` токена; `None` — максимум
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ApprovalQuery {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub token: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub owner: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub spender: ChecksummedAddress,
+    #[serde(default)]
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+    pub amount: Option<U256>,
+    #[serde(default)]
+    pub block: Option<u64>,
+}
+
+/// Кто и как может вывести средства по разрешению
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ExtractionPath {
+    /// spender — EOA: владелец ключа вызывает `transferFrom` напрямую
+    Key,
+    /// spender — контракт, и симуляция `transferFrom` от его имени прошла
+    Contract {
+        /// `owner()` контракта, если он его объявляет
+        #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+        admin: Option<ChecksummedAddress>,
+    },
+    /// spender — прокси EIP-1967: реализацию можно заменить на любую логику вывода
+    Upgrade {
+        #[cfg_attr(feature = "server", schema(value_type = String))]
+        implementation: ChecksummedAddress,
+    },
+}
+
+/// Итог симуляции: сколько реально забирается и какими путями
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ApprovalImpact {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub token: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub spender: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub balance: U256,
+    /// Разрешение, под которым шла симуляция
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub allowance: U256,
+    /// `false` — слот разрешения не найден, симуляция шла на текущем разрешении из сети
+    pub allowance_spoofed: bool,
+    /// Баланс, подставленный владельцу на время симуляции, если собственный нулевой
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+    pub simulated_balance: Option<U256>,
+    /// Сумма, которую `transferFrom` перевёл в симуляции; 0 — вызов отклонён. При подставленном
+    /// балансе показывает, что вывод работает, а не сумму, которую можно забрать сейчас
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub extractable: U256,
+    /// Безлимитное разрешение открывает и будущие поступления
+    pub covers_future_balance: bool,
+    /// Причина отказа `transferFrom`, если он откатился
+    pub revert: Option<String>,
+    pub paths: Vec<ExtractionPath>,
+    pub level: AlertLevel,
+    /// Действующее разрешение той же пары в Permit2: его не видно в `allowance` токена
+    #[serde(default)]
+    pub permit2: Option<Permit2Exposure>,
+}
+
+fn erc20_call(token: Address, from: Address, data: impl AbiEncode) -> TypedTransaction {
+    TransactionRequest::new().from(from).to(token).data(Bytes::from(data.encode())).into()
+}
+
+/// Ключ `mapping[a][b]` в слоте `slot`: раскладка Solidity и Vyper
+fn mapping_keys(a: Address, b: Address, slot: u64) -> [H256; 2] {
+    let word = |address: Address| H256::from(address).to_fixed_bytes();
+    let slot = H256::from_low_u64_be(slot).to_fixed_bytes();
+
+    let solidity_inner = keccak256([word(a), slot].concat());
+    let solidity = keccak256([word(b), solidity_inner.0].concat());
+    let vyper_inner = keccak256([slot, word(a)].concat());
+    let vyper = keccak256([vyper_inner.0, word(b)].concat());
+    [solidity, vyper]
+}
+
+/// Ключ `mapping[a]` в слоте `slot`: раскладка Solidity и Vyper
+fn balance_keys(a: Address, slot: u64) -> [H256; 2] {
+    let word = H256::from(a).to_fixed_bytes();
+    let slot = H256::from_low_u64_be(slot).to_fixed_bytes();
+    [keccak256([word, slot].concat()), keccak256([slot, word].concat())]
+}
+
+fn word(value: U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    H256::from(bytes)
+}
+
+/// Оценка разрешений симуляцией на форке: `eth_call` с подменой состояния ставит
+/// разрешение на проверяемую сумму и исполняет `transferFrom` от имени spender
+pub struct ApprovalSimulator<P> {
+    provider: Arc<Provider<P>>,
+}
+
+impl<P: JsonRpcClient + 'static> ApprovalSimulator<P> {
+    pub fn new(provider: Arc<Provider<P>>) -> Self {
+        Self { provider }
+    }
+
+    async fn call(&self, tx: &TypedTransaction, block: Option<BlockId>, state: Option<&spoof::State>) -> Result<Bytes, String> {
+        let mut call = self.provider.call_raw(tx);
+        if let Some(block) = block {
+            call = call.block(block);
+        }
+        if let Some(state) = state {
+            call = call.state(state);
+        }
+        call.await.map_err(|e| e.to_string())
+    }
+
+    async fn read_u256(&self, tx: &TypedTransaction, block: Option<BlockId>, state: Option<&spoof::State>) -> Option<U256> {
+        let bytes = self.call(tx, block, state).await.ok()?;
+        U256::decode(bytes.as_ref()).ok()
+    }
+
+    /// Подмена, при которой `allowance(owner, spender)` возвращает `amount`; `None` — слот не найден
+    async fn allowance_override(&self, query: &ApprovalQuery, amount: U256, block: Option<BlockId>) -> Option<spoof::State> {
+        let (token, owner, spender): (Address, Address, Address) = (query.token.into(), query.owner.into(), query.spender.into());
+        let probe = erc20_call(token, owner, AllowanceCall { owner, spender });
+        for slot in 0..MAX_SLOT_PROBE {
+            for key in mapping_keys(owner, spender, slot) {
+                let mut state = spoof::state();
+                state.account(token).store(key, word(amount));
+                if self.read_u256(&probe, block, Some(&state)).await == Some(amount) {
+                    return Some(state);
+                }
+            }
+        }
+        None
+    }
+
+    /// Добавляет к `base` подмену, при которой `balanceOf(owner)` возвращает `amount`
+    async fn balance_override(
+        &self,
+        query: &ApprovalQuery,
+        amount: U256,
+        block: Option<BlockId>,
+        base: &spoof::State,
+    ) -> Option<spoof::State> {
+        let (token, owner): (Address, Address) = (query.token.into(), query.owner.into());
+        let probe = erc20_call(token, owner, BalanceOfCall { owner });
+        for slot in 0..MAX_SLOT_PROBE {
+            for key in balance_keys(owner, slot) {
+                let mut state = base.clone();
+                state.account(token).store(key, word(amount));
+                if self.read_u256(&probe, block, Some(&state)).await == Some(amount) {
+                    return Some(state);
+                }
+            }
+        }
+        None
+    }
+
+    async fn paths(&self, spender: Address, block: Option<BlockId>) -> Result<Vec<ExtractionPath>, ApprovalSimError> {
+        let provider_err = |e: ethers::providers::ProviderError| ApprovalSimError::ProviderError(e.to_string());
+        let code = self.provider.get_code(spender, block).await.map_err(provider_err)?;
+        if code.is_empty() {
+            return Ok(vec![ExtractionPath::Key]);
+        }
+
+        let owner_call = erc20_call(spender, Address::zero(), OwnerCall);
+        let admin = match self.call(&owner_call, block, None).await {
+            Ok(bytes) => Address::decode(bytes.as_ref()).ok().filter(|a| !a.is_zero()).map(ChecksummedAddress::from),
+            Err(_) => None,
+        };
+        let mut paths = vec![ExtractionPath::Contract { admin }];
+
+        let slot: H256 = EIP1967_IMPLEMENTATION_SLOT.parse().unwrap();
+        let implementation = self.provider.get_storage_at(spender, slot, block).await.map_err(provider_err)?;
+        if !implementation.is_zero() {
+            paths.push(ExtractionPath::Upgrade { implementation: Address::from(implementation).into() });
+        }
+        Ok(paths)
+    }
+
+    pub async fn simulate(&self, query: &ApprovalQuery) -> Result<ApprovalImpact, ApprovalSimError> {
+        let block = query.block.map(BlockId::from);
+        let (token, owner, spender): (Address, Address, Address) = (query.token.into(), query.owner.into(), query.spender.into());
+        let requested = query.amount.unwrap_or(U256::MAX);
+
+        let balance = self
+            .read_u256(&erc20_call(token, owner, BalanceOfCall { owner }), block, None)
+            .await
+            .ok_or(ApprovalSimError::NotErc20(query.token))?;
+        let mut state = self.allowance_override(query, requested, block).await;
+        let allowance_spoofed = state.is_some();
+        let allowance = match allowance_spoofed {
+            true => requested,
+            // Без найденного слота симулируем на текущем разрешении и так и сообщаем
+            false => self
+                .read_u256(&erc20_call(token, owner, AllowanceCall { owner, spender }), block, None)
+                .await
+                .ok_or(ApprovalSimError::NotErc20(query.token))?,
+        };
+
+        // При нулевом балансе перевод ничего не показывает: владельцу подставляется баланс
+        let mut simulated_balance = None;
+        if balance.is_zero() && !allowance.is_zero() {
+            let probe = allowance.min(U256::from(PROBE_BALANCE));
+            let base = state.clone().unwrap_or_else(spoof::state);
+            if let Some(with_balance) = self.balance_override(query, probe, block, &base).await {
+                state = Some(with_balance);
+                simulated_balance = Some(probe);
+            }
+        }
+
+        let attempt = simulated_balance.unwrap_or(balance).min(allowance);
+        let transfer = erc20_call(token, spender, TransferFromCall { from: owner, to: SINK, amount: attempt });
+        let (extractable, revert) = match attempt.is_zero() {
+            true => (U256::zero(), Some("nothing to transfer: zero balance or allowance".to_string())),
+            false => match self.call(&transfer, block, state.as_ref()).await {
+                // Токены без возвращаемого значения тоже считаются успешными
+                Ok(bytes) if bytes.is_empty() || bool::decode(bytes.as_ref()).unwrap_or(false) => (attempt, None),
+                Ok(_) => (U256::zero(), Some("transferFrom returned false".to_string())),
+                Err(e) => (U256::zero(), Some(e)),
+            },
+        };
+
+        let paths = if extractable.is_zero() { Vec::new() } else { self.paths(spender, block).await? };
+        let covers_future_balance = allowance.bits() > UNLIMITED_BITS && !extractable.is_zero();
+        let level = match (extractable.is_zero(), covers_future_balance || paths.iter().any(|p| matches!(p, ExtractionPath::Upgrade { .. }))) {
+            (true, _) => AlertLevel::Info,
+            (false, true) => AlertLevel::High,
+            (false, false) => AlertLevel::Medium,
+        };
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        let permit2 = permit2::read_allowance(self.provider.as_ref(), (token, owner, spender), block)
+            .await
+            .ok()
+            .filter(|allowance| allowance.is_active(now))
+            .map(Permit2Exposure::assess);
+
+        Ok(ApprovalImpact {
+            token: query.token,
+            spender: query.spender,
+            balance,
+            allowance,
+            allowance_spoofed,
+            simulated_balance,
+            extractable,
+            covers_future_balance,
+            revert,
+            paths,
+            level,
+            permit2,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_keys_match_solidity_layout() {
+        // USDC: balanceOf в слоте 9, allowance в слоте 10 (FiatTokenV2)
+        let owner: Address = "0x000000000000000000000000000000000000dEaD".parse().unwrap();
+        let spender = Address::repeat_byte(0x11);
+        let expected = keccak256([H256::from(owner).to_fixed_bytes(), H256::from_low_u64_be(9).to_fixed_bytes()].concat());
+        assert_eq!(balance_keys(owner, 9)[0], expected);
+        assert_ne!(balance_keys(owner, 9)[0], balance_keys(owner, 9)[1]);
+
+        let inner = keccak256([H256::from(owner).to_fixed_bytes(), H256::from_low_u64_be(10).to_fixed_bytes()].concat());
+        let outer = keccak256([H256::from(spender).to_fixed_bytes(), inner.0].concat());
+        assert_eq!(mapping_keys(owner, spender, 10)[0], outer);
+        assert_eq!(word(U256::from(258)), H256::from_low_u64_be(258));
+    }
+}
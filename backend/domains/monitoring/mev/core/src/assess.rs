@@ -0,0 +1,225 @@
+use crate::abi::{AbiRegistry, DecodedCall};
+use crate::access_list::{AccessListError, AccessListPlan, AccessListPlanner};
+use crate::amount::WeiAmount;
+use crate::approvals::{ApprovalQuery, ApprovalSimError, ApprovalSimulator};
+use crate::enrichment::{self, Enricher};
+use crate::ingest::{self, IngestError};
+use crate::labels::{AddressLabel, SharedLabelResolver};
+use crate::rpc::{QuotaClient, QuotaProvider};
+use crate::typed_data::{TypedDataAssessor, TypedDataDomain, TypedDataVerdict};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use ethers::providers::Http;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, BlockNumber, Bytes};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Состояние API оценки транзакций для кошельков
+#[derive(Clone)]
+pub struct AssessState {
+    pub access_lists: Arc<AccessListPlanner<QuotaProvider>>,
+    pub approvals: Arc<ApprovalSimulator<QuotaClient<Http>>>,
+    pub typed_data: Arc<TypedDataAssessor>,
+    pub abi: Arc<AbiRegistry>,
+    pub labels: Option<SharedLabelResolver>,
+    /// Те же источники обогащения, что и у детекторов конвейера
+    pub enrichers: Arc<Vec<Box<dyn Enricher>>>,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct AssessTxRequest {
+    /// Транзакция в формате JSON-RPC (`eth_sendTransaction`)
+    #[schema(value_type = Object)]
+    pub tx: TypedTransaction,
+    /// Блок симуляции; по умолчанию `latest`
+    #[serde(default)]
+    pub block: Option<u64>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct AssessTxResponse {
+    pub access_list: AccessListPlan,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct AssessTypedDataRequest {
+    pub domain: TypedDataDomain,
+    /// `message` из запроса `eth_signTypedData_v4`
+    #[schema(value_type = Object)]
+    pub message: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct DecodeRawTxRequest {
+    /// Подписанная транзакция, как для `eth_sendRawTransaction`
+    #[schema(value_type = String, example = "0x02f8...")]
+    pub raw: Bytes,
+}
+
+/// Разбор подписанной транзакции в том виде, в каком её видят детекторы
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RawTxView {
+    #[schema(value_type = String)]
+    pub hash: ethers::types::H256,
+    pub tx_type: u8,
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    #[schema(value_type = Object)]
+    pub gas_price: WeiAmount,
+    #[schema(value_type = Option<Object>)]
+    pub max_priority_fee_per_gas: Option<WeiAmount>,
+    #[schema(value_type = Option<Object>)]
+    pub max_fee_per_blob_gas: Option<WeiAmount>,
+    #[schema(value_type = Vec<String>)]
+    pub blob_versioned_hashes: Vec<ethers::types::H256>,
+    pub access_list_entries: usize,
+    #[schema(value_type = Object)]
+    pub from: AddressLabel,
+    /// `None` — создание контракта
+    #[schema(value_type = Option<Object>)]
+    pub to: Option<AddressLabel>,
+    #[schema(value_type = Object)]
+    pub value: WeiAmount,
+    /// `None` — пустая calldata или неизвестный селектор
+    pub call: Option<DecodedCall>,
+    #[schema(value_type = Object)]
+    pub enrichment: serde_json::Value,
+}
+
+impl IntoResponse for AccessListError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_GATEWAY, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/assess/tx",
+    tag = "assess",
+    request_body = AssessTxRequest,
+    responses(
+        (status = 200, description = "Simulation results for the transaction", body = AssessTxResponse),
+        (status = 502, description = "Node failed to simulate the transaction"),
+    )
+)]
+async fn assess_tx(State(state): State<AssessState>, Json(request): Json<AssessTxRequest>) -> Response {
+    let block = request.block.map(|b| BlockId::Number(BlockNumber::Number(b.into())));
+    match state.access_lists.plan(&request.tx, block).await {
+        Ok(access_list) => Json(AssessTxResponse { access_list }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/assess/typed-data",
+    tag = "assess",
+    request_body = AssessTypedDataRequest,
+    responses(
+        (status = 200, description = "Decoded permissions and risk of the signature", body = TypedDataVerdict),
+    )
+)]
+async fn assess_typed_data(
+    State(state): State<AssessState>,
+    Json(request): Json<AssessTypedDataRequest>,
+) -> Json<TypedDataVerdict> {
+    Json(state.typed_data.assess_typed_data(&request.domain, &request.message))
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+fn label(state: &AssessState, address: &crate::address::ChecksummedAddress) -> AddressLabel {
+    match &state.labels {
+        Some(labels) => labels.resolve(&address.to_string()),
+        None => AddressLabel {
+            address: address.to_string(),
+            label: None,
+            category: None,
+            ens: None,
+            display: address.to_string(),
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/assess/raw-tx",
+    tag = "assess",
+    request_body = DecodeRawTxRequest,
+    responses(
+        (status = 200, description = "Decoded transaction with sender, calldata and enrichment", body = RawTxView),
+        (status = 422, description = "Not a valid signed transaction"),
+    )
+)]
+async fn decode_raw_tx(State(state): State<AssessState>, Json(request): Json<DecodeRawTxRequest>) -> Response {
+    let decoded = match ingest::decode_raw(&request.raw) {
+        Ok(decoded) => decoded,
+        Err(e) => return e.into_response(),
+    };
+    let tx = &decoded.tx;
+    let view = RawTxView {
+        hash: decoded.hash,
+        tx_type: decoded.tx_type,
+        chain_id: decoded.chain_id,
+        nonce: decoded.nonce.low_u64(),
+        gas_limit: decoded.gas_limit.low_u64(),
+        gas_price: WeiAmount::from_wei(decoded.gas_price),
+        max_priority_fee_per_gas: decoded.max_priority_fee_per_gas.map(WeiAmount::from_wei),
+        max_fee_per_blob_gas: decoded.max_fee_per_blob_gas.map(WeiAmount::from_wei),
+        blob_versioned_hashes: decoded.blob_versioned_hashes.clone(),
+        access_list_entries: decoded.access_list_entries,
+        from: label(&state, &tx.from),
+        to: (!tx.to.is_zero()).then(|| label(&state, &tx.to)),
+        value: tx.value,
+        call: state.abi.decode(&tx.input),
+        enrichment: enrichment::enrich(&state.enrichers, tx).to_value(),
+    };
+    Json(view).into_response()
+}
+
+impl IntoResponse for ApprovalSimError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApprovalSimError::ProviderError(_) => StatusCode::BAD_GATEWAY,
+            ApprovalSimError::NotErc20(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/assess/approval",
+    tag = "assess",
+    request_body = ApprovalQuery,
+    responses(
+        (status = 200, description = "Amount the spender can actually move and through which paths", body = ApprovalImpact),
+        (status = 422, description = "Token is not an ERC-20"),
+        (status = 502, description = "Node failed to simulate the transfer"),
+    )
+)]
+async fn assess_approval(State(state): State<AssessState>, Json(query): Json<ApprovalQuery>) -> Response {
+    match state.approvals.simulate(&query).await {
+        Ok(impact) => Json(impact).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub fn router(state: AssessState) -> Router {
+    Router::new()
+        .route("/assess/tx", post(assess_tx))
+        .route("/assess/typed-data", post(assess_typed_data))
+        .route("/assess/raw-tx", post(decode_raw_tx))
+        .route("/assess/approval", post(assess_approval))
+        .with_state(state)
+}
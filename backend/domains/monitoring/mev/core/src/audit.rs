@@ -0,0 +1,336 @@
+use crate::store::{SharedStore, StoreError, StoreExt};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Записи журнала; ключ — порядковый номер с ведущими нулями, чтобы `list` шёл по порядку
+const AUDIT_NS: &str = "audit";
+const AUDIT_META_NS: &str = "audit_meta";
+/// `prev_hash` первой записи
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Audit chain broken at entry {seq}: {reason}")]
+    ChainBroken { seq: u64, reason: String },
+}
+
+/// Итог подписанного действия
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditResult {
+    Success { tx_hash: Option<String> },
+    Failure { error: String },
+}
+
+/// Запись журнала; `hash` покрывает все остальные поля, включая `prev_hash`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: u64,
+    /// Подсистема: `restaking`, `protection`, `bundles`
+    pub component: String,
+    pub action: String,
+    /// Адрес ключа, которым подписано действие
+    pub signer: String,
+    pub payload: serde_json::Value,
+    pub result: AuditResult,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let body = serde_json::json!({
+            "seq": self.seq,
+            "timestamp": self.timestamp,
+            "component": self.component,
+            "action": self.action,
+            "signer": self.signer,
+            "payload": self.payload,
+            "result": self.result,
+            "prev_hash": self.prev_hash,
+        });
+        ethers::utils::hex::encode(Sha256::digest(body.to_string().as_bytes()))
+    }
+}
+
+/// Фильтр выборки; записи возвращаются от новых к старым
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "server", into_params(parameter_in = Query))]
+pub struct AuditQuery {
+    pub component: Option<String>,
+    pub signer: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.component.as_ref().is_none_or(|c| *c == entry.component)
+            && self.signer.as_ref().is_none_or(|s| s.eq_ignore_ascii_case(&entry.signer))
+            && self.since.is_none_or(|t| entry.timestamp >= t)
+            && self.until.is_none_or(|t| entry.timestamp <= t)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Head {
+    seq: u64,
+    hash: String,
+}
+
+fn entry_key(seq: u64) -> String {
+    format!("{:020}", seq)
+}
+
+/// Запись, которую не удалось сохранить; время — момент самого действия
+#[derive(Clone)]
+struct Deferred {
+    timestamp: u64,
+    component: String,
+    action: String,
+    signer: String,
+    payload: serde_json::Value,
+    result: AuditResult,
+}
+
+/// Журнал только на дописывание с цепочкой хэшей: изменение или удаление
+/// любой записи ломает `verify` начиная с неё
+pub struct AuditLog {
+    store: SharedStore,
+    /// Последняя запись; `None` — журнал пуст
+    head: Mutex<Option<Head>>,
+    /// Очередь `append_or_defer`, дописывается перед следующей записью
+    deferred: Mutex<Vec<Deferred>>,
+}
+
+impl AuditLog {
+    /// Открывает журнал и догоняет голову, если запись успела сохраниться, а голова нет
+    pub fn open(store: SharedStore) -> Result<Self, AuditError> {
+        let mut head: Option<Head> = store.get_json(AUDIT_META_NS, "head")?;
+        loop {
+            let next = head.as_ref().map_or(0, |h| h.seq + 1);
+            match store.get_json::<AuditEntry>(AUDIT_NS, &entry_key(next))? {
+                Some(entry) => head = Some(Head { seq: entry.seq, hash: entry.hash }),
+                None => break,
+            }
+        }
+        Ok(Self { store, head: Mutex::new(head), deferred: Mutex::new(Vec::new()) })
+    }
+
+    pub fn append(
+        &self,
+        component: &str,
+        action: &str,
+        signer: &str,
+        payload: serde_json::Value,
+        result: AuditResult,
+    ) -> Result<AuditEntry, AuditError> {
+        self.flush_deferred();
+        self.write(Deferred {
+            timestamp: now(),
+            component: component.to_string(),
+            action: action.to_string(),
+            signer: signer.to_string(),
+            payload,
+            result,
+        })
+    }
+
+    /// Для действий, уже ушедших в сеть: сбой хранилища не должен терять хэш транзакции
+    /// у вызывающего, поэтому несохранённая запись остаётся в очереди и дописывается
+    /// перед следующей. `false` — запись пока в очереди
+    pub fn append_or_defer(
+        &self,
+        component: &str,
+        action: &str,
+        signer: &str,
+        payload: serde_json::Value,
+        result: AuditResult,
+    ) -> bool {
+        self.deferred.lock().unwrap().push(Deferred {
+            timestamp: now(),
+            component: component.to_string(),
+            action: action.to_string(),
+            signer: signer.to_string(),
+            payload,
+            result,
+        });
+        self.flush_deferred()
+    }
+
+    /// Записи, ожидающие сохранения
+    pub fn deferred(&self) -> usize {
+        self.deferred.lock().unwrap().len()
+    }
+
+    /// Дописывает очередь по порядку до первой ошибки; `true` — очередь пуста
+    fn flush_deferred(&self) -> bool {
+        let mut deferred = self.deferred.lock().unwrap();
+        while !deferred.is_empty() {
+            if self.write(deferred[0].clone()).is_err() {
+                return false;
+            }
+            deferred.remove(0);
+        }
+        true
+    }
+
+    fn write(&self, record: Deferred) -> Result<AuditEntry, AuditError> {
+        let mut head = self.head.lock().unwrap();
+
+        let mut entry = AuditEntry {
+            seq: head.as_ref().map_or(0, |h| h.seq + 1),
+            timestamp: record.timestamp,
+            component: record.component,
+            action: record.action,
+            signer: record.signer,
+            payload: record.payload,
+            result: record.result,
+            prev_hash: head.as_ref().map_or(GENESIS_HASH.to_string(), |h| h.hash.clone()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        self.store.put_json(AUDIT_NS, &entry_key(entry.seq), &entry)?;
+        let next = Head { seq: entry.seq, hash: entry.hash.clone() };
+        self.store.put_json(AUDIT_META_NS, "head", &next)?;
+        *head = Some(next);
+
+        Ok(entry)
+    }
+
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, AuditError> {
+        let entries: Vec<(String, AuditEntry)> = self.store.list_json(AUDIT_NS)?;
+        Ok(entries
+            .into_iter()
+            .rev()
+            .map(|(_, e)| e)
+            .filter(|e| query.matches(e))
+            .take(query.limit.unwrap_or(100))
+            .collect())
+    }
+
+    /// Проверяет всю цепочку; возвращает число записей
+    pub fn verify(&self) -> Result<u64, AuditError> {
+        let entries: Vec<(String, AuditEntry)> = self.store.list_json(AUDIT_NS)?;
+        let mut prev_hash = GENESIS_HASH.to_string();
+
+        for (expected_seq, (_, entry)) in entries.iter().enumerate() {
+            let broken = |reason: &str| AuditError::ChainBroken { seq: expected_seq as u64, reason: reason.to_string() };
+            if entry.seq != expected_seq as u64 {
+                return Err(broken("missing entry"));
+            }
+            if entry.prev_hash != prev_hash {
+                return Err(broken("prev_hash does not match previous entry"));
+            }
+            if entry.compute_hash() != entry.hash {
+                return Err(broken("entry hash mismatch"));
+            }
+            prev_hash = entry.hash.clone();
+        }
+
+        // Отрезанный хвост виден по голове
+        if let Some(head) = self.head.lock().unwrap().as_ref() {
+            if head.hash != prev_hash {
+                return Err(AuditError::ChainBroken { seq: head.seq, reason: "log truncated".into() });
+            }
+        }
+
+        Ok(entries.len() as u64)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_tampering_breaks_chain() {
+        let store: SharedStore = Arc::new(MemoryStore::new());
+        let log = AuditLog::open(store.clone()).unwrap();
+        for amount in [1, 2, 3] {
+            log.append(
+                "restaking",
+                "restake_eth",
+                "0xabc",
+                serde_json::json!({ "amount_eth": amount }),
+                AuditResult::Success { tx_hash: None },
+            )
+            .unwrap();
+        }
+        assert_eq!(log.verify().unwrap(), 3);
+
+        // После переоткрытия цепочка продолжается
+        let log = AuditLog::open(store.clone()).unwrap();
+        let entry = log.append("bundles", "submit", "0xdef", serde_json::Value::Null, AuditResult::Failure { error: "rejected".into() }).unwrap();
+        assert_eq!(entry.seq, 3);
+        assert_eq!(log.query(&AuditQuery { component: Some("restaking".into()), ..Default::default() }).unwrap().len(), 3);
+
+        let mut forged: AuditEntry = store.get_json(AUDIT_NS, &entry_key(1)).unwrap().unwrap();
+        forged.payload = serde_json::json!({ "amount_eth": 200 });
+        store.put_json(AUDIT_NS, &entry_key(1), &forged).unwrap();
+        assert!(matches!(log.verify(), Err(AuditError::ChainBroken { seq: 1, .. })));
+
+        store.delete(AUDIT_NS, &entry_key(1)).unwrap();
+        assert!(matches!(log.verify(), Err(AuditError::ChainBroken { seq: 1, .. })));
+    }
+
+    /// Хранилище, которое отказывает в записи, пока не разрешат
+    struct FlakyStore {
+        inner: MemoryStore,
+        failing: std::sync::atomic::AtomicBool,
+    }
+
+    impl crate::store::Store for FlakyStore {
+        fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+            self.inner.get(namespace, key)
+        }
+        fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StoreError> {
+            if self.failing.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(StoreError::IoError(std::io::Error::other("disk full")));
+            }
+            self.inner.put(namespace, key, value)
+        }
+        fn delete(&self, namespace: &str, key: &str) -> Result<(), StoreError> {
+            self.inner.delete(namespace, key)
+        }
+        fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>, StoreError> {
+            self.inner.list(namespace)
+        }
+    }
+
+    #[test]
+    fn test_deferred_entries_written_in_order() {
+        let store = Arc::new(FlakyStore { inner: MemoryStore::new(), failing: true.into() });
+        let log = AuditLog::open(store.clone()).unwrap();
+        let sent = AuditResult::Success { tx_hash: Some("0x01".into()) };
+        assert!(!log.append_or_defer("restaking", "restake_eth", "0xabc", serde_json::Value::Null, sent));
+        assert_eq!(log.deferred(), 1);
+
+        store.failing.store(false, std::sync::atomic::Ordering::Relaxed);
+        let entry = log.append("bundles", "submit", "0xdef", serde_json::Value::Null, AuditResult::Success { tx_hash: None }).unwrap();
+        assert_eq!((entry.seq, log.deferred()), (1, 0));
+        assert_eq!(log.query(&AuditQuery::default()).unwrap()[1].component, "restaking");
+        assert_eq!(log.verify().unwrap(), 2);
+    }
+}
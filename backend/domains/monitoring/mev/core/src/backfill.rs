@@ -0,0 +1,361 @@
+use crate::rpc::RetryPolicy;
+use crate::shutdown::ShutdownSignal;
+use crate::store::{Store, StoreError, StoreExt};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use async_trait::async_trait;
+use ethers::core::rand::{thread_rng, Rng};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+/// Пространство имён хранилища: прогресс фоновых заполнений
+pub const BACKFILL_NS: &str = "backfill";
+
+#[derive(Debug, Error)]
+pub enum BackfillError {
+    #[error("Unknown backfill job: {0}")]
+    UnknownJob(String),
+
+    #[error("Job {0} is {1:?}")]
+    InvalidState(String, JobState),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+}
+
+/// Результат одной порции работы
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutcome {
+    pub processed: u64,
+    /// Курсор следующей порции; `None` — заполнение закончено
+    pub next: Option<Value>,
+}
+
+/// Долгое заполнение порциями: метки адресов, история слэшингов, возраст контрактов.
+/// Курсор сохраняется после каждой успешной порции, поэтому порция должна быть идемпотентной:
+/// прерванную остановкой узла повторят после перезапуска
+#[async_trait]
+pub trait BackfillJob: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Сколько всего единиц работы, если известно заранее
+    async fn total(&self) -> Option<u64> {
+        None
+    }
+
+    /// `cursor` — `None` для первой порции
+    async fn run_batch(&self, cursor: Option<Value>) -> Result<BatchOutcome, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Paused,
+    Completed,
+    /// Исчерпаны повторы; продолжить можно через `resume`
+    Failed,
+}
+
+/// Прогресс заполнения; переживает перезапуск узла
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct JobStatus {
+    pub name: String,
+    pub state: JobState,
+    pub processed: u64,
+    pub total: Option<u64>,
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub cursor: Option<Value>,
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub last_error: Option<String>,
+    /// Неудачи подряд с последней успешной порции
+    pub failures: u32,
+}
+
+impl JobStatus {
+    fn new(name: &str, now: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            state: JobState::Running,
+            processed: 0,
+            total: None,
+            cursor: None,
+            started_at: now,
+            updated_at: now,
+            last_error: None,
+            failures: 0,
+        }
+    }
+}
+
+struct Registered {
+    job: Arc<dyn BackfillJob>,
+    /// Пауза между порциями, чтобы не занимать всю квоту провайдера
+    interval: Duration,
+    wake: Notify,
+}
+
+/// Выполняет заполнения в фоне: прогресс в хранилище, пауза между порциями,
+/// повторы с отступом, остановка и продолжение по запросу
+pub struct BackfillRunner {
+    store: Arc<dyn Store>,
+    retry: RetryPolicy,
+    jobs: BTreeMap<String, Registered>,
+    statuses: Mutex<BTreeMap<String, JobStatus>>,
+    /// Упавшие порции и несохранённый прогресс
+    errors: TaskErrors,
+}
+
+impl BackfillRunner {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self {
+            store,
+            retry: RetryPolicy::default(),
+            jobs: BTreeMap::new(),
+            statuses: Mutex::new(BTreeMap::new()),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Подключает задачу; сохранённый прогресс подхватывается, новая начинается с начала
+    pub fn with_job(mut self, job: Arc<dyn BackfillJob>, interval: Duration) -> Result<Self, BackfillError> {
+        let name = job.name().to_string();
+        let status = self.store.get_json(BACKFILL_NS, &name)?.unwrap_or_else(|| JobStatus::new(&name, now()));
+        self.statuses.lock().unwrap().insert(name.clone(), status);
+        self.jobs.insert(name, Registered { job, interval, wake: Notify::new() });
+        Ok(self)
+    }
+
+    pub fn status(&self, name: &str) -> Option<JobStatus> {
+        self.statuses.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.statuses.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Меняет статус под блокировкой и сохраняет его
+    fn update<T>(&self, name: &str, change: impl FnOnce(&mut JobStatus) -> T) -> Result<T, BackfillError> {
+        let mut statuses = self.statuses.lock().unwrap();
+        let status = statuses.get_mut(name).ok_or_else(|| BackfillError::UnknownJob(name.to_string()))?;
+        let result = change(status);
+        status.updated_at = now();
+        self.store.put_json(BACKFILL_NS, name, status)?;
+        Ok(result)
+    }
+
+    /// Текущая порция доработает, следующая не начнётся
+    pub fn pause(&self, name: &str) -> Result<JobStatus, BackfillError> {
+        self.update(name, |status| match status.state {
+            JobState::Running => {
+                status.state = JobState::Paused;
+                Ok(status.clone())
+            }
+            state => Err(BackfillError::InvalidState(name.to_string(), state)),
+        })?
+    }
+
+    /// Продолжает остановленную или упавшую задачу с сохранённого курсора
+    pub fn resume(&self, name: &str) -> Result<JobStatus, BackfillError> {
+        let status = self.update(name, |status| match status.state {
+            JobState::Paused | JobState::Failed => {
+                status.state = JobState::Running;
+                status.failures = 0;
+                Ok(status.clone())
+            }
+            state => Err(BackfillError::InvalidState(name.to_string(), state)),
+        })??;
+        self.wake(name);
+        Ok(status)
+    }
+
+    /// Сбрасывает прогресс: задача начнёт с первой порции
+    pub fn restart(&self, name: &str) -> Result<JobStatus, BackfillError> {
+        let status = self.update(name, |status| {
+            *status = JobStatus { total: status.total, ..JobStatus::new(name, now()) };
+            status.clone()
+        })?;
+        self.wake(name);
+        Ok(status)
+    }
+
+    fn wake(&self, name: &str) {
+        if let Some(registered) = self.jobs.get(name) {
+            registered.wake.notify_one();
+        }
+    }
+
+    async fn drive(&self, name: &str, mut shutdown: ShutdownSignal) {
+        let registered = &self.jobs[name];
+        if let Some(total) = registered.job.total().await {
+            self.errors.check(self.update(name, |status| status.total = Some(total)));
+        }
+        loop {
+            let Some(status) = self.status(name) else { return };
+            if status.state != JobState::Running {
+                tokio::select! {
+                    _ = registered.wake.notified() => continue,
+                    _ = shutdown.wait() => return,
+                }
+            }
+
+            let result = tokio::select! {
+                result = registered.job.run_batch(status.cursor.clone()) => result,
+                _ = shutdown.wait() => return,
+            };
+            // Перезапуск во время порции: её результат относится к прежнему проходу
+            let same_run = |current: &JobStatus| current.started_at == status.started_at && current.cursor == status.cursor;
+            let delay = match result {
+                Ok(outcome) => {
+                    let saved = self.update(name, |status| {
+                        if !same_run(status) {
+                            return;
+                        }
+                        status.processed += outcome.processed;
+                        status.failures = 0;
+                        status.last_error = None;
+                        match outcome.next {
+                            Some(next) => status.cursor = Some(next),
+                            // Пауза, пришедшая во время последней порции, уже не нужна
+                            None => status.state = JobState::Completed,
+                        }
+                    });
+                    // Курсор не сохранён: порцию повторят после перезапуска, она идемпотентна
+                    self.errors.check(saved);
+                    registered.interval
+                }
+                Err(e) => {
+                    self.errors.record(format!("backfill {} batch failed: {}", name, e));
+                    let saved = self.update(name, |status| {
+                        if !same_run(status) {
+                            return 1;
+                        }
+                        status.failures += 1;
+                        status.last_error = Some(e);
+                        if status.failures > self.retry.max_retries && status.state == JobState::Running {
+                            status.state = JobState::Failed;
+                        }
+                        status.failures
+                    });
+                    let failures = self.errors.check(saved).unwrap_or(1);
+                    self.retry.delay(failures - 1, thread_rng().gen()).max(registered.interval)
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+
+    /// Ведёт все задачи до сигнала остановки
+    pub async fn run(self: Arc<Self>, shutdown: ShutdownSignal) {
+        let mut tasks = JoinSet::new();
+        for name in self.jobs.keys() {
+            let (name, runner, shutdown) = (name.clone(), self.clone(), shutdown.clone());
+            tasks.spawn(async move { runner.drive(&name, shutdown).await });
+        }
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// Порция списка для заданий по списку адресов: курсор — индекс первого необработанного
+/// элемента. Возвращает порцию и курсор следующей, `None` — список пройден
+pub fn list_batch<'a, T>(items: &'a [T], cursor: Option<&Value>, size: usize) -> Result<(&'a [T], Option<Value>), String> {
+    let start = match cursor {
+        None => 0,
+        Some(cursor) => cursor.as_u64().ok_or_else(|| format!("cursor {} is not an index", cursor))? as usize,
+    };
+    let end = start.saturating_add(size.max(1)).min(items.len());
+    let batch = items.get(start.min(end)..end).unwrap_or_default();
+    Ok((batch, (end < items.len()).then(|| Value::from(end as u64))))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shutdown::ShutdownCoordinator;
+    use crate::store::MemoryStore;
+
+    /// Пять элементов порциями по два; первая попытка третьей порции падает
+    struct Flaky {
+        failed: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl BackfillJob for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn total(&self) -> Option<u64> {
+            Some(5)
+        }
+
+        async fn run_batch(&self, cursor: Option<Value>) -> Result<BatchOutcome, String> {
+            let (batch, next) = list_batch(&[1, 2, 3, 4, 5], cursor.as_ref(), 2)?;
+            if batch == [5] && !std::mem::replace(&mut *self.failed.lock().unwrap(), true) {
+                return Err("provider unavailable".into());
+            }
+            Ok(BatchOutcome { processed: batch.len() as u64, next })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runs_to_completion_through_failures_and_persists_progress() {
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let retry = RetryPolicy { max_retries: 3, initial_backoff_ms: 1, max_backoff_ms: 1 };
+        let runner = Arc::new(
+            BackfillRunner::new(store.clone())
+                .with_retry(retry)
+                .with_job(Arc::new(Flaky { failed: Mutex::new(false) }), Duration::ZERO)
+                .unwrap(),
+        );
+        assert!(matches!(runner.resume("flaky"), Err(BackfillError::InvalidState(_, JobState::Running))));
+
+        let coordinator = ShutdownCoordinator::new(Duration::from_secs(1));
+        let task = tokio::spawn(runner.clone().run(coordinator.signal()));
+        for _ in 0..200 {
+            if runner.status("flaky").unwrap().state == JobState::Completed {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        coordinator.run().await;
+        task.await.unwrap();
+
+        let status = runner.status("flaky").unwrap();
+        assert_eq!((status.state, status.processed, status.total, status.failures), (JobState::Completed, 5, Some(5), 0));
+        assert_eq!(runner.errors().errors, 1);
+        // После перезапуска узла задача не начинается заново
+        let saved: JobStatus = store.get_json(BACKFILL_NS, "flaky").unwrap().unwrap();
+        assert_eq!(saved, status);
+        let restarted = BackfillRunner::new(store).with_job(Arc::new(Flaky { failed: Mutex::new(true) }), Duration::ZERO).unwrap();
+        assert_eq!(restarted.status("flaky").unwrap().state, JobState::Completed);
+        assert_eq!(restarted.restart("flaky").unwrap().processed, 0);
+    }
+}
@@ -0,0 +1,300 @@
+#[cfg(feature = "mev")]
+use mevdetector::bundle::{export_state, stage_import, NodeState, StateBundle};
+use mevdetector::capability::DeploymentMode;
+use mevdetector::config::{chain_name, DefinetlyConfig};
+#[cfg(feature = "server")]
+use mevdetector::node::Node;
+#[cfg(feature = "server")]
+use mevdetector::openapi;
+use std::path::PathBuf;
+use std::process::ExitCode;
+#[cfg(feature = "staking")]
+use mevdetector::remote_signer::Web3Signer;
+#[cfg(feature = "mev")]
+use mevdetector::forensics::BlockAnalyzer;
+#[cfg(feature = "mev")]
+use mevdetector::rpc::RpcQuota;
+#[cfg(feature = "mev")]
+use mevdetector::rules::RuleEngine;
+#[cfg(feature = "mev")]
+use mevdetector::store::FileStore;
+#[cfg(all(feature = "server", feature = "push"))]
+use utoipa::OpenApi;
+
+const USAGE: &str = "usage: definetly <command> [args]
+
+commands:
+  check-config [path]    validate configuration (default: definetly.toml)
+  doctor [path]          validate configuration and check external dependencies (remote signer)
+  export-state [path]    print watch-list, rules and subscriptions as a YAML bundle
+  import-state <bundle> [path]
+                         validate a bundle, import its subscriptions and stage the rest for the next node start
+  openapi                print the OpenAPI document of the HTTP API (feature `server`)
+  replay <from> [to] [path]
+                         print MEV, coinbase tips and builder payment of mined blocks as JSON lines
+  run [path]             start the node: admin API and subsystems configured in the file (feature `server`)";
+
+fn check_config(path: PathBuf) -> ExitCode {
+    match DefinetlyConfig::load(&path) {
+        Ok(config) => {
+            let mode = match config.capabilities().mode() {
+                DeploymentMode::ReadOnly => "read-only",
+                DeploymentMode::Signing => "signing",
+            };
+            println!(
+                "{}: OK (chain {} / {}, {})",
+                path.display(),
+                config.rpc.chain_id,
+                chain_name(config.rpc.chain_id).unwrap_or("unknown"),
+                mode
+            );
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            eprint!("{}: {}", path.display(), errors);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Доступность удалённого подписанта и наличие ожидаемых ключей
+#[cfg(feature = "staking")]
+fn check_signer(config: &DefinetlyConfig) -> bool {
+    let Some(section) = &config.web3signer else {
+        return true;
+    };
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("doctor: {}", e);
+            return false;
+        }
+    };
+    let signer = Web3Signer::with_timeout(&section.url, std::time::Duration::from_secs(section.timeout_seconds));
+    let health = runtime.block_on(signer.health(&section.public_keys));
+    if health.is_healthy() {
+        println!("web3signer {}: OK ({} keys, {} ms)", health.url, health.keys_loaded, health.latency_ms);
+        return true;
+    }
+    let reason = match (&health.error, health.missing_keys.is_empty()) {
+        (Some(e), _) => e.clone(),
+        (None, false) => format!("missing keys: {}", health.missing_keys.join(", ")),
+        (None, true) => format!("status {}", health.status.as_deref().unwrap_or("unknown")),
+    };
+    eprintln!("web3signer {}: FAILED ({})", health.url, reason);
+    false
+}
+
+/// Диагностика узла: конфиг и внешние зависимости, которые нельзя проверить статически
+fn doctor(path: PathBuf) -> ExitCode {
+    let code = check_config(path.clone());
+    let Ok(config) = DefinetlyConfig::load(&path) else {
+        return code;
+    };
+    #[cfg(feature = "staking")]
+    let healthy = check_signer(&config);
+    #[cfg(not(feature = "staking"))]
+    let healthy = {
+        let _ = config;
+        true
+    };
+
+    if healthy {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Бандл из конфига и хранилища мониторинга: без работающего узла источник состояния — файл конфигурации
+#[cfg(feature = "mev")]
+fn export_bundle(path: PathBuf) -> Result<StateBundle, String> {
+    let config = DefinetlyConfig::load(&path).map_err(|e| e.to_string())?;
+    let store = match &config.monitor {
+        Some(monitor) => Some(FileStore::open(&monitor.store_path).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let mut state = NodeState::new();
+    state.store = store.as_ref().map(|s| s as &dyn mevdetector::store::Store);
+    let mut bundle = export_state(&state).map_err(|e| e.to_string())?;
+
+    if let Some(monitor) = &config.monitor {
+        bundle.watchlist = monitor.watched_contracts.iter().filter_map(|a| a.parse().ok()).collect();
+        bundle.watchlist.sort();
+    }
+    bundle.rules = config.detector.rules.clone();
+    if let Some(routing) = &config.routing {
+        bundle.suppressions = routing.suppressions.clone();
+    }
+    Ok(bundle)
+}
+
+#[cfg(feature = "mev")]
+fn export_command(path: PathBuf) -> ExitCode {
+    match export_bundle(path).and_then(|b| b.to_yaml().map_err(|e| e.to_string())) {
+        Ok(yaml) => {
+            print!("{}", yaml);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("export-state: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+fn import_bundle(bundle_path: PathBuf, path: PathBuf) -> Result<String, String> {
+    let text = std::fs::read_to_string(&bundle_path).map_err(|e| format!("{}: {}", bundle_path.display(), e))?;
+    let bundle = StateBundle::from_yaml(&text).map_err(|e| e.to_string())?;
+    RuleEngine::new(bundle.rules.clone()).map_err(|e| e.to_string())?;
+
+    let config = DefinetlyConfig::load(&path).map_err(|e| e.to_string())?;
+    let monitor = config.monitor.ok_or("no [monitor] section: nowhere to import subscriptions")?;
+    let store = FileStore::open(&monitor.store_path).map_err(|e| e.to_string())?;
+    let report = stage_import(&store, &bundle).map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        "imported {} store records; {} watched addresses, {} labels, {} detector settings, {} rules and {} suppressions are applied on the next node start",
+        report.records,
+        bundle.watchlist.len(),
+        bundle.labels.len(),
+        bundle.detectors.len(),
+        bundle.rules.len(),
+        bundle.suppressions.len()
+    ))
+}
+
+/// Отчёты `BlockReport` по добытым блокам `[from, to]`, по строке JSON на блок
+#[cfg(feature = "mev")]
+fn replay(from: u64, to: u64, path: PathBuf) -> Result<(), String> {
+    let config = DefinetlyConfig::load(&path).map_err(|e| e.to_string())?;
+    let rpc = std::sync::Arc::new(RpcQuota::new(config.rpc.quota.clone().unwrap_or_default()));
+    let provider = rpc.http_provider("replay", &config.rpc.http_url).map_err(|e| format!("rpc.http_url: {}", e))?;
+    let analyzer = BlockAnalyzer::new(std::sync::Arc::new(provider));
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        for block in from..=to {
+            let report = analyzer.report(block).await.map_err(|e| format!("block {}: {}", block, e))?;
+            println!("{}", serde_json::to_string(&report).map_err(|e| e.to_string())?);
+        }
+        Ok(())
+    })
+}
+
+/// Узел до SIGTERM; отказы участников остановки печатаются, но не меняют код выхода
+#[cfg(feature = "server")]
+fn run(path: PathBuf) -> ExitCode {
+    let config = match DefinetlyConfig::load(&path) {
+        Ok(config) => config,
+        Err(errors) => {
+            eprint!("{}: {}", path.display(), errors);
+            return ExitCode::FAILURE;
+        }
+    };
+    let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("run: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = runtime.block_on(async move { Node::build(config).await?.run().await });
+    match result {
+        Ok(outcomes) => {
+            for outcome in outcomes.iter().filter(|o| o.error.is_some()) {
+                eprintln!("shutdown {:?} {}: {}", outcome.phase, outcome.name, outcome.error.as_deref().unwrap_or_default());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("run: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Спецификации подсистем этого крейта рядом с `ApiDoc`; роутеры других крейтов
+/// добавляются в узел через `Node::with_routes`
+#[cfg(feature = "server")]
+fn api_docs() -> Vec<utoipa::openapi::OpenApi> {
+    #[cfg(feature = "push")]
+    return vec![mevdetector::push::WalletApi::openapi()];
+    #[cfg(not(feature = "push"))]
+    Vec::new()
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        Some("check-config") => {
+            check_config(args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into()))
+        }
+        Some("doctor") => doctor(args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into())),
+        #[cfg(feature = "mev")]
+        Some("export-state") => {
+            export_command(args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into()))
+        }
+        #[cfg(feature = "mev")]
+        Some("import-state") => {
+            let Some(bundle) = args.next().map(PathBuf::from) else {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            };
+            let path = args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into());
+            match import_bundle(bundle, path) {
+                Ok(summary) => {
+                    println!("{}", summary);
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    eprintln!("import-state: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        #[cfg(feature = "mev")]
+        Some("replay") => {
+            let Some(from) = args.next().and_then(|a| a.parse::<u64>().ok()) else {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            };
+            let to = match args.next() {
+                Some(arg) => match arg.parse::<u64>() {
+                    Ok(to) => to,
+                    Err(_) => {
+                        eprintln!("{}", USAGE);
+                        return ExitCode::from(2);
+                    }
+                },
+                None => from,
+            };
+            let path = args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into());
+            match replay(from, to, path) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("replay: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        #[cfg(feature = "server")]
+        Some("run") => run(args.next().map(PathBuf::from).unwrap_or_else(|| "definetly.toml".into())),
+        #[cfg(feature = "server")]
+        Some("openapi") => match openapi::merged(api_docs()).to_pretty_json() {
+            Ok(json) => {
+                println!("{}", json);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("openapi: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
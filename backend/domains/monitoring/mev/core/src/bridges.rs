@@ -0,0 +1,649 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::compat::{keccak256, Address, H256, U256};
+use crate::indexer::{IndexHandler, IndexedEvent, LogIndexer};
+use crate::shutdown::ShutdownSignal;
+use crate::store::{Store, StoreError, StoreExt};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use ethers::types::Log;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Пространство имён хранилища: переводы через мосты, ожидающие доставки
+pub const BRIDGE_MESSAGES_NS: &str = "bridge_messages";
+
+/// Пространство имён хранилища: доставки, пришедшие раньше своих отправок
+pub const BRIDGE_DELIVERIES_NS: &str = "bridge_deliveries";
+
+/// Сколько хранить доставку: дольше любого отставания индекса и любой реорганизации
+const DELIVERY_RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+#[derive(Debug, Error)]
+pub enum BridgeError {
+    #[error("No provider for chain {0}")]
+    NoProvider(u64),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+}
+
+/// Отправка через мост, разобранная адаптером из лога исходной сети
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deposit {
+    /// Идентификатор сообщения, однозначный в пределах моста; пригоден как ключ хранилища
+    pub message_id: String,
+    pub sender: Address,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: U256,
+}
+
+/// Перевод, ожидающий выпуска на стороне назначения
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeMessage {
+    pub bridge: String,
+    pub message_id: String,
+    pub source_chain: u64,
+    pub dest_chain: u64,
+    pub sender: Address,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: U256,
+    pub deposit_tx: H256,
+    pub deposit_block: u64,
+    pub deposited_at: u64,
+    /// Позже этого момента доставка считается просроченной
+    pub deadline: u64,
+    #[serde(default)]
+    pub alerted: bool,
+    /// Сообщение закрыто; запись держится, пока доставку может отменить реорганизация
+    #[serde(default)]
+    pub delivered: Option<Delivery>,
+}
+
+/// Как у конкретного моста сопоставить отправку и доставку одного сообщения
+pub trait BridgeAdapter: Send + Sync {
+    /// Имя экземпляра, например `cctp-mainnet-base`; ключ курсоров и сообщений
+    fn name(&self) -> &str;
+
+    fn source_chain(&self) -> u64;
+
+    fn dest_chain(&self) -> u64;
+
+    /// Обычное время доставки с запасом
+    fn expected_window(&self) -> Duration;
+
+    /// Контракты, чьи логи в исходной сети содержат отправки
+    fn source_contracts(&self) -> Vec<Address>;
+
+    /// Контракты, чьи логи в сети назначения содержат доставки
+    fn dest_contracts(&self) -> Vec<Address>;
+
+    /// `topic0` лога отправки
+    fn deposit_topic(&self) -> H256;
+
+    /// `topic0` лога доставки
+    fn delivery_topic(&self) -> H256;
+
+    fn decode_deposit(&self, log: &Log) -> Option<Deposit>;
+
+    /// Идентификатор доставленного сообщения, в том же виде, что у `Deposit::message_id`
+    fn decode_delivery(&self, log: &Log) -> Option<String>;
+}
+
+fn word(data: &[u8], offset: usize) -> Option<&[u8]> {
+    data.get(offset..offset + 32)
+}
+
+fn uint(data: &[u8], offset: usize, len: usize) -> Option<u64> {
+    Some(data.get(offset..offset + len)?.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn address_word(data: &[u8], offset: usize) -> Option<Address> {
+    Some(Address::from_slice(&word(data, offset)?[12..]))
+}
+
+/// `bytes` в данных лога по смещению из слова `head`
+fn dynamic_bytes(data: &[u8], head: usize) -> Option<&[u8]> {
+    let start = U256::from_big_endian(word(data, head)?).try_into().ok().filter(|s: &usize| *s <= data.len())?;
+    let len: usize = U256::from_big_endian(word(data, start)?).try_into().ok()?;
+    data.get(start + 32..start.checked_add(32)?.checked_add(len)?)
+}
+
+/// Circle CCTP v1: `MessageSent(bytes)` в исходной сети, `MessageReceived` в сети назначения.
+/// Сообщение однозначно задаётся доменом источника и nonce
+pub struct Cctp {
+    name: String,
+    source_chain: u64,
+    source_domain: u32,
+    source_transmitter: Address,
+    dest_chain: u64,
+    dest_domain: u32,
+    dest_transmitter: Address,
+    window: Duration,
+}
+
+impl Cctp {
+    pub fn new(
+        name: &str,
+        (source_chain, source_domain, source_transmitter): (u64, u32, Address),
+        (dest_chain, dest_domain, dest_transmitter): (u64, u32, Address),
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            source_chain,
+            source_domain,
+            source_transmitter,
+            dest_chain,
+            dest_domain,
+            dest_transmitter,
+            // Аттестация Circle ждёт финальности источника: для Ethereum это около 20 минут
+            window: Duration::from_secs(45 * 60),
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    fn message_id(source_domain: u32, nonce: u64) -> String {
+        format!("{}-{}", source_domain, nonce)
+    }
+}
+
+impl BridgeAdapter for Cctp {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn source_chain(&self) -> u64 {
+        self.source_chain
+    }
+
+    fn dest_chain(&self) -> u64 {
+        self.dest_chain
+    }
+
+    fn expected_window(&self) -> Duration {
+        self.window
+    }
+
+    fn source_contracts(&self) -> Vec<Address> {
+        vec![self.source_transmitter]
+    }
+
+    fn dest_contracts(&self) -> Vec<Address> {
+        vec![self.dest_transmitter]
+    }
+
+    fn deposit_topic(&self) -> H256 {
+        keccak256(b"MessageSent(bytes)")
+    }
+
+    /// `MessageReceived(address indexed caller, uint32 sourceDomain, uint64 indexed nonce, bytes32 sender, bytes messageBody)`
+    fn delivery_topic(&self) -> H256 {
+        keccak256(b"MessageReceived(address,uint32,uint64,bytes32,bytes)")
+    }
+
+    /// Заголовок: версия (4), домены источника и назначения (4 + 4), nonce (8), отправитель,
+    /// получатель и вызывающий (по 32). Тело `BurnMessage`: версия (4), токен, получатель,
+    /// сумма и отправитель сжигания (по 32)
+    fn decode_deposit(&self, log: &Log) -> Option<Deposit> {
+        if log.address != self.source_transmitter || log.topics.first() != Some(&self.deposit_topic()) {
+            return None;
+        }
+        let message = dynamic_bytes(&log.data, 0)?;
+        let source_domain = uint(message, 4, 4)? as u32;
+        if source_domain != self.source_domain || uint(message, 8, 4)? as u32 != self.dest_domain {
+            return None;
+        }
+        let nonce = uint(message, 12, 8)?;
+        let body = message.get(116..)?;
+        Some(Deposit {
+            message_id: Self::message_id(source_domain, nonce),
+            token: address_word(body, 4)?,
+            recipient: address_word(body, 36)?,
+            amount: U256::from_big_endian(word(body, 68)?),
+            sender: address_word(body, 100)?,
+        })
+    }
+
+    fn decode_delivery(&self, log: &Log) -> Option<String> {
+        if log.address != self.dest_transmitter || log.topics.first() != Some(&self.delivery_topic()) {
+            return None;
+        }
+        let source_domain = uint(word(&log.data, 0)?, 28, 4)? as u32;
+        let nonce = uint(log.topics.get(2)?.as_bytes(), 24, 8)?;
+        (source_domain == self.source_domain).then(|| Self::message_id(source_domain, nonce))
+    }
+}
+
+/// Доставка сообщения в сети назначения
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub block: u64,
+    /// Когда доставка замечена
+    pub at: u64,
+}
+
+/// Учёт сообщений в хранилище. Доставка может прийти раньше отправки, если индекс
+/// источника отстаёт: тогда она ждёт свою отправку в `BRIDGE_DELIVERIES_NS`
+struct Ledger {
+    store: Arc<dyn Store>,
+    watched: HashSet<Address>,
+}
+
+impl Ledger {
+    fn key(bridge: &str, message_id: &str) -> String {
+        format!("{}.{}", bridge, message_id)
+    }
+
+    fn messages(&self) -> Result<Vec<BridgeMessage>, BridgeError> {
+        Ok(self.store.list_json(BRIDGE_MESSAGES_NS)?.into_iter().map(|(_, m)| m).collect())
+    }
+
+    fn put(&self, message: &BridgeMessage) -> Result<(), BridgeError> {
+        Ok(self.store.put_json(BRIDGE_MESSAGES_NS, &Self::key(&message.bridge, &message.message_id), message)?)
+    }
+
+    /// Ранние доставки моста `bridge`
+    fn early_deliveries(&self, bridge: &str) -> Result<Vec<(String, Delivery)>, BridgeError> {
+        let prefix = format!("{}.", bridge);
+        Ok(self
+            .store
+            .list_json::<Delivery>(BRIDGE_DELIVERIES_NS)?
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .collect())
+    }
+
+    fn pending(&self) -> Result<Vec<BridgeMessage>, BridgeError> {
+        Ok(self.messages()?.into_iter().filter(|m| m.delivered.is_none()).collect())
+    }
+
+    /// Запоминает отправки отслеживаемых адресов; возвращает новые
+    fn record_deposits(
+        &self,
+        adapter: &dyn BridgeAdapter,
+        events: Vec<IndexedEvent<Deposit>>,
+        now: u64,
+    ) -> Result<Vec<BridgeMessage>, BridgeError> {
+        let mut recorded = Vec::new();
+        for IndexedEvent { block, tx_hash, event: deposit, .. } in events {
+            if !self.watched.contains(&deposit.sender) {
+                continue;
+            }
+            let key = Self::key(adapter.name(), &deposit.message_id);
+            let delivered = self.store.get_json::<Delivery>(BRIDGE_DELIVERIES_NS, &key)?;
+            let message = BridgeMessage {
+                bridge: adapter.name().to_string(),
+                message_id: deposit.message_id,
+                source_chain: adapter.source_chain(),
+                dest_chain: adapter.dest_chain(),
+                sender: deposit.sender,
+                recipient: deposit.recipient,
+                token: deposit.token,
+                amount: deposit.amount,
+                deposit_tx: tx_hash,
+                deposit_block: block,
+                deposited_at: now,
+                deadline: now + adapter.expected_window().as_secs(),
+                alerted: false,
+                delivered,
+            };
+            self.put(&message)?;
+            if delivered.is_some() {
+                self.store.delete(BRIDGE_DELIVERIES_NS, &key)?;
+            }
+            recorded.push(message);
+        }
+        Ok(recorded)
+    }
+
+    /// Отмечает доставленные сообщения; возвращает те, что ждали доставки
+    fn record_deliveries(
+        &self,
+        adapter: &dyn BridgeAdapter,
+        events: Vec<IndexedEvent<String>>,
+        now: u64,
+    ) -> Result<Vec<BridgeMessage>, BridgeError> {
+        let mut delivered = Vec::new();
+        for event in events {
+            let key = Self::key(adapter.name(), &event.event);
+            let delivery = Delivery { block: event.block, at: now };
+            match self.store.get_json::<BridgeMessage>(BRIDGE_MESSAGES_NS, &key)? {
+                Some(mut message) if message.delivered.is_none() => {
+                    message.delivered = Some(delivery);
+                    self.put(&message)?;
+                    delivered.push(message);
+                }
+                Some(_) => {}
+                None => self.store.put_json(BRIDGE_DELIVERIES_NS, &key, &delivery)?,
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Реорганизация источника: отправки из блоков `from_block` и выше отменены
+    fn revert_deposits(&self, bridge: &str, from_block: u64) -> Result<(), BridgeError> {
+        for message in self.messages()? {
+            if message.bridge != bridge || message.deposit_block < from_block {
+                continue;
+            }
+            let key = Self::key(bridge, &message.message_id);
+            if let Some(delivery) = message.delivered {
+                self.store.put_json(BRIDGE_DELIVERIES_NS, &key, &delivery)?;
+            }
+            self.store.delete(BRIDGE_MESSAGES_NS, &key)?;
+        }
+        Ok(())
+    }
+
+    /// Реорганизация назначения: доставки из блоков `from_block` и выше отменены
+    fn revert_deliveries(&self, bridge: &str, from_block: u64) -> Result<(), BridgeError> {
+        for mut message in self.messages()? {
+            if message.bridge == bridge && message.delivered.is_some_and(|d| d.block >= from_block) {
+                message.delivered = None;
+                self.put(&message)?;
+            }
+        }
+        for (key, delivery) in self.early_deliveries(bridge)? {
+            if delivery.block >= from_block {
+                self.store.delete(BRIDGE_DELIVERIES_NS, &key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Доставленные сообщения и ранние доставки старше `DELIVERY_RETENTION` больше не нужны:
+    /// реорганизация их уже не отменит
+    fn prune(&self, now: u64) -> Result<(), BridgeError> {
+        let expired = |delivery: &Delivery| delivery.at + DELIVERY_RETENTION.as_secs() < now;
+        for message in self.messages()? {
+            if message.delivered.as_ref().is_some_and(expired) {
+                self.store.delete(BRIDGE_MESSAGES_NS, &Self::key(&message.bridge, &message.message_id))?;
+            }
+        }
+        for (key, delivery) in self.store.list_json::<Delivery>(BRIDGE_DELIVERIES_NS)? {
+            if expired(&delivery) {
+                self.store.delete(BRIDGE_DELIVERIES_NS, &key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Алерты по сообщениям, не доставленным к сроку; каждое сообщение алертит один раз
+    fn overdue(&self, now: u64) -> Result<Vec<BusAlert>, BridgeError> {
+        let mut alerts = Vec::new();
+        for mut message in self.pending()? {
+            if message.alerted || now <= message.deadline {
+                continue;
+            }
+            message.alerted = true;
+            self.put(&message)?;
+            let title = format!(
+                "Bridge message {} via {} not delivered to chain {} after {} min",
+                message.message_id,
+                message.bridge,
+                message.dest_chain,
+                (now - message.deposited_at) / 60
+            );
+            alerts.push(
+                BusAlert::new("bridges", "undelivered", AlertLevel::High, format!("{:?}", message.sender), title)
+                    .with_payload(json!({ "message": message, "wallet": format!("{:?}", message.sender) })),
+            );
+        }
+        Ok(alerts)
+    }
+}
+
+/// Сторона источника: отправки
+struct Deposits<'a> {
+    ledger: &'a Ledger,
+    adapter: &'a dyn BridgeAdapter,
+}
+
+#[async_trait]
+impl IndexHandler<Deposit> for Deposits<'_> {
+    async fn apply(&self, events: Vec<IndexedEvent<Deposit>>) -> Result<(), String> {
+        self.ledger.record_deposits(self.adapter, events, now()).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    async fn revert(&self, from_block: u64) -> Result<(), String> {
+        self.ledger.revert_deposits(self.adapter.name(), from_block).map_err(|e| e.to_string())
+    }
+}
+
+/// Сторона назначения: доставки
+struct Deliveries<'a> {
+    ledger: &'a Ledger,
+    adapter: &'a dyn BridgeAdapter,
+}
+
+#[async_trait]
+impl IndexHandler<String> for Deliveries<'_> {
+    async fn apply(&self, events: Vec<IndexedEvent<String>>) -> Result<(), String> {
+        self.ledger.record_deliveries(self.adapter, events, now()).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    async fn revert(&self, from_block: u64) -> Result<(), String> {
+        self.ledger.revert_deliveries(self.adapter.name(), from_block).map_err(|e| e.to_string())
+    }
+}
+
+/// Мост с индексами логов обеих сторон
+struct Route<M> {
+    adapter: Arc<dyn BridgeAdapter>,
+    source: LogIndexer<M, Deposit>,
+    dest: LogIndexer<M, String>,
+}
+
+/// Переводы отслеживаемых адресов через мосты: отправка запоминается в хранилище,
+/// доставка в сети назначения её закрывает, просроченная доставка — алерт `bridges.undelivered`.
+/// Логи обеих сторон читает `LogIndexer`, поэтому реорганизации откатывают и отправки, и доставки
+pub struct BridgeTracker<M> {
+    ledger: Ledger,
+    providers: HashMap<u64, Arc<M>>,
+    routes: Vec<Route<M>>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> BridgeTracker<M> {
+    /// `providers` — по сети; у каждой сети каждого моста должен быть провайдер
+    pub fn new(store: Arc<dyn Store>, watched: impl IntoIterator<Item = Address>, providers: HashMap<u64, Arc<M>>) -> Self {
+        Self {
+            ledger: Ledger { store, watched: watched.into_iter().collect() },
+            providers,
+            routes: Vec::new(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn with_adapter(mut self, adapter: Arc<dyn BridgeAdapter>) -> Result<Self, BridgeError> {
+        let provider = |chain: u64| self.providers.get(&chain).cloned().ok_or(BridgeError::NoProvider(chain));
+        let store = self.ledger.store.clone();
+
+        let decoder = adapter.clone();
+        let mut source = LogIndexer::new(&format!("bridges.{}.source", adapter.name()), provider(adapter.source_chain())?, store.clone(), 0)
+            .from_head()
+            .with_decoder(adapter.deposit_topic(), move |log| decoder.decode_deposit(&log));
+        for contract in adapter.source_contracts() {
+            source = source.with_address(contract);
+        }
+
+        let decoder = adapter.clone();
+        let mut dest = LogIndexer::new(&format!("bridges.{}.dest", adapter.name()), provider(adapter.dest_chain())?, store, 0)
+            .from_head()
+            .with_decoder(adapter.delivery_topic(), move |log| decoder.decode_delivery(&log));
+        for contract in adapter.dest_contracts() {
+            dest = dest.with_address(contract);
+        }
+
+        self.routes.push(Route { adapter, source, dest });
+        Ok(self)
+    }
+
+    /// Сообщения, ещё не доставленные в сеть назначения
+    pub fn pending(&self) -> Result<Vec<BridgeMessage>, BridgeError> {
+        self.ledger.pending()
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Один проход по всем мостам: отправки, доставки, просрочки. Сбой одной стороны
+    /// учитывается и не останавливает остальные мосты
+    pub async fn step(&self, shutdown: &ShutdownSignal) -> Vec<BusAlert> {
+        for route in &self.routes {
+            let (ledger, adapter) = (&self.ledger, route.adapter.as_ref());
+            if let Err(e) = route.source.catch_up(&Deposits { ledger, adapter }, shutdown).await {
+                self.errors.record(format!("bridge {} source: {}", adapter.name(), e));
+            }
+            if let Err(e) = route.dest.catch_up(&Deliveries { ledger, adapter }, shutdown).await {
+                self.errors.record(format!("bridge {} destination: {}", adapter.name(), e));
+            }
+        }
+        let now = now();
+        self.errors.check(self.ledger.prune(now));
+        self.errors.check(self.ledger.overdue(now)).unwrap_or_default()
+    }
+
+    pub async fn run(&self, bus: AlertBus, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            for alert in self.step(&shutdown).await {
+                bus.publish(alert);
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    fn log(address: Address, topics: Vec<H256>, data: Vec<u8>) -> Log {
+        Log { address, topics, data: data.into(), transaction_hash: Some(H256::repeat_byte(7)), ..Default::default() }
+    }
+
+    fn padded(bytes: &[u8]) -> Vec<u8> {
+        let mut word = vec![0u8; 32 - bytes.len()];
+        word.extend_from_slice(bytes);
+        word
+    }
+
+    fn indexed<E>(block: u64, event: E) -> IndexedEvent<E> {
+        IndexedEvent {
+            block,
+            block_hash: H256::repeat_byte(block as u8),
+            tx_hash: H256::repeat_byte(7),
+            log_index: 0,
+            address: Address::zero(),
+            event,
+        }
+    }
+
+    const SENDER: Address = Address::repeat_byte(0xaa);
+
+    fn cctp() -> Cctp {
+        Cctp::new("cctp-mainnet-base", (1, 0, Address::repeat_byte(0x51)), (8453, 6, Address::repeat_byte(0xd5)))
+    }
+
+    /// `MessageSent` с nonce 42 от `SENDER`
+    fn sent() -> Log {
+        let mut message = [0u32.to_be_bytes(), 0u32.to_be_bytes(), 6u32.to_be_bytes()].concat();
+        message.extend(42u64.to_be_bytes());
+        message.extend([0u8; 96]);
+        message.extend(0u32.to_be_bytes());
+        let (token, recipient) = (Address::repeat_byte(0xc0), Address::repeat_byte(0xbb));
+        for field in [token.as_bytes(), recipient.as_bytes(), &[0x0f, 0x42, 0x40], SENDER.as_bytes()] {
+            message.extend(padded(field));
+        }
+        let mut data = padded(&[32]);
+        data.extend(padded(&[message.len() as u8]));
+        data.extend(&message);
+        data.resize(data.len().div_ceil(32) * 32, 0);
+        log(Address::repeat_byte(0x51), vec![keccak256(b"MessageSent(bytes)")], data)
+    }
+
+    fn received() -> Log {
+        log(
+            Address::repeat_byte(0xd5),
+            vec![
+                keccak256(b"MessageReceived(address,uint32,uint64,bytes32,bytes)"),
+                H256::zero(),
+                H256::from_low_u64_be(42),
+            ],
+            padded(&[0]),
+        )
+    }
+
+    fn ledger() -> Ledger {
+        Ledger { store: Arc::new(MemoryStore::new()), watched: HashSet::from([SENDER]) }
+    }
+
+    #[test]
+    fn test_cctp_deposit_is_tracked_until_delivered_or_overdue() {
+        let (cctp, ledger) = (cctp(), ledger());
+        let deposit = cctp.decode_deposit(&sent()).unwrap();
+        let recorded = ledger.record_deposits(&cctp, vec![indexed(10, deposit)], 1_000).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].message_id, "0-42");
+        assert_eq!(recorded[0].amount, U256::from(1_000_000));
+        assert_eq!(recorded[0].recipient, Address::repeat_byte(0xbb));
+
+        assert!(ledger.overdue(1_000 + 30 * 60).unwrap().is_empty());
+        assert_eq!(ledger.overdue(1_000 + 50 * 60).unwrap().len(), 1);
+        assert!(ledger.overdue(1_000 + 60 * 60).unwrap().is_empty());
+
+        let id = cctp.decode_delivery(&received()).unwrap();
+        assert_eq!(ledger.record_deliveries(&cctp, vec![indexed(20, id)], 4_000).unwrap().len(), 1);
+        assert!(ledger.pending().unwrap().is_empty());
+
+        // Доставку отменила реорганизация назначения: сообщение снова ждёт
+        ledger.revert_deliveries("cctp-mainnet-base", 20).unwrap();
+        assert_eq!(ledger.pending().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_delivery_seen_before_its_deposit_closes_the_message() {
+        let (cctp, ledger) = (cctp(), ledger());
+        let id = cctp.decode_delivery(&received()).unwrap();
+        assert!(ledger.record_deliveries(&cctp, vec![indexed(20, id)], 1_000).unwrap().is_empty());
+
+        let deposit = cctp.decode_deposit(&sent()).unwrap();
+        ledger.record_deposits(&cctp, vec![indexed(10, deposit)], 1_100).unwrap();
+        assert!(ledger.pending().unwrap().is_empty());
+        assert!(ledger.overdue(1_100 + 60 * 60).unwrap().is_empty());
+
+        // Отправку отменила реорганизация источника: доставка снова ждёт её
+        ledger.revert_deposits("cctp-mainnet-base", 10).unwrap();
+        assert!(ledger.messages().unwrap().is_empty());
+        assert_eq!(ledger.early_deliveries("cctp-mainnet-base").unwrap().len(), 1);
+
+        ledger.prune(1_000 + DELIVERY_RETENTION.as_secs() + 1).unwrap();
+        assert!(ledger.early_deliveries("cctp-mainnet-base").unwrap().is_empty());
+    }
+}
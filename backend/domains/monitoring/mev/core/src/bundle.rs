@@ -0,0 +1,314 @@
+use crate::address::ChecksummedAddress;
+use crate::engine::Engine;
+use crate::labels::{LabelCategory, LabelResolver};
+use crate::registry::{DetectorPatch, DetectorRegistry, DetectorSettings, RegistryError};
+use crate::retention::PROTECTED_NAMESPACES;
+use crate::routing::{AlertRouter, Suppression};
+use crate::rules::{RuleEngine, RuleError, RuleSpec};
+use crate::store::{Store, StoreError, StoreExt};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Текущая версия формата бандла; при изменении `StateBundle` добавляется миграция в `from_yaml`
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Пространства имён хранилища, которые переносятся по умолчанию
+pub const DEFAULT_NAMESPACES: &[&str] = &["monitor_subscriptions"];
+
+/// Бандл, принятый без работающего узла; узел применяет его при старте
+const STAGED_NAMESPACE: &str = "state_import";
+const STAGED_KEY: &str = "pending";
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("YAML error: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+
+    #[error("Unsupported bundle version {0} (this build reads up to {BUNDLE_VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Detector settings rejected: {0}")]
+    RegistryError(#[from] RegistryError),
+
+    #[error("Rules rejected: {0}")]
+    RuleError(#[from] RuleError),
+
+    #[error("Namespace '{0}' is reserved and cannot be imported")]
+    ReservedNamespace(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelEntry {
+    pub address: String,
+    pub label: String,
+    pub category: LabelCategory,
+}
+
+/// Переносимое состояние узла: список наблюдения, метки, настройки детекторов,
+/// правила, подавления алертов и записи хранилища (подписки мониторинга)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateBundle {
+    pub version: u32,
+    pub exported_at: u64,
+    #[serde(default)]
+    pub watchlist: Vec<ChecksummedAddress>,
+    #[serde(default)]
+    pub labels: Vec<LabelEntry>,
+    #[serde(default)]
+    pub detectors: BTreeMap<String, DetectorSettings>,
+    #[serde(default)]
+    pub rules: Vec<RuleSpec>,
+    #[serde(default)]
+    pub suppressions: Vec<Suppression>,
+    /// Пространство имён -> ключ -> JSON-запись
+    #[serde(default)]
+    pub store: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl StateBundle {
+    pub fn to_yaml(&self) -> Result<String, BundleError> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, BundleError> {
+        let bundle: StateBundle = serde_yaml::from_str(text)?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(bundle.version));
+        }
+        Ok(bundle)
+    }
+}
+
+/// Части узла, участвующие в переносе; отсутствующие пропускаются
+#[derive(Default)]
+pub struct NodeState<'a> {
+    pub engine: Option<&'a mut Engine>,
+    pub labels: Option<&'a LabelResolver>,
+    pub registry: Option<&'a DetectorRegistry>,
+    pub router: Option<&'a AlertRouter>,
+    pub store: Option<&'a dyn Store>,
+    pub namespaces: Vec<String>,
+}
+
+impl NodeState<'_> {
+    pub fn new() -> Self {
+        Self {
+            namespaces: DEFAULT_NAMESPACES.iter().map(|ns| ns.to_string()).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Что изменил импорт
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub watched: usize,
+    pub labels: usize,
+    pub detectors: usize,
+    pub rules: usize,
+    pub suppressions: usize,
+    pub records: usize,
+}
+
+pub fn export_state(state: &NodeState<'_>) -> Result<StateBundle, BundleError> {
+    let mut bundle = StateBundle {
+        version: BUNDLE_VERSION,
+        exported_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        watchlist: Vec::new(),
+        labels: Vec::new(),
+        detectors: BTreeMap::new(),
+        rules: Vec::new(),
+        suppressions: Vec::new(),
+        store: BTreeMap::new(),
+    };
+
+    if let Some(engine) = state.engine.as_deref() {
+        bundle.watchlist = engine.watchlist().iter().copied().collect();
+        bundle.watchlist.sort();
+        bundle.rules = engine.detector().rules().map(RuleEngine::specs).unwrap_or_default();
+    }
+    if let Some(labels) = state.labels {
+        bundle.labels = labels
+            .custom_labels()
+            .into_iter()
+            .map(|(address, label, category)| LabelEntry { address, label, category })
+            .collect();
+    }
+    if let Some(registry) = state.registry {
+        bundle.detectors = registry.names().map(|name| (name.to_string(), (*registry.settings(name)).clone())).collect();
+    }
+    if let Some(router) = state.router {
+        bundle.suppressions = router.suppressions();
+    }
+    if let Some(store) = state.store {
+        for ns in &state.namespaces {
+            let mut records = BTreeMap::new();
+            for (key, bytes) in store.list(ns)? {
+                records.insert(key, serde_json::from_slice(&bytes).map_err(StoreError::from)?);
+            }
+            if !records.is_empty() {
+                bundle.store.insert(ns.clone(), records);
+            }
+        }
+    }
+    Ok(bundle)
+}
+
+fn is_reserved(namespace: &str) -> bool {
+    PROTECTED_NAMESPACES.contains(&namespace) || namespace == STAGED_NAMESPACE
+}
+
+/// Применяет бандл поверх текущего состояния: список наблюдения и метки дополняются,
+/// правила и подавления заменяются целиком. Правила, имена детекторов и пространства имён
+/// проверяются до любых изменений; журнал аудита бандлом не перезаписать
+pub fn import_state(state: &mut NodeState<'_>, bundle: &StateBundle) -> Result<ImportReport, BundleError> {
+    if bundle.version > BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(bundle.version));
+    }
+    if let Some(ns) = bundle.store.keys().find(|ns| is_reserved(ns)) {
+        return Err(BundleError::ReservedNamespace(ns.clone()));
+    }
+    let rules = match (&state.engine, bundle.rules.is_empty()) {
+        (Some(_), false) => Some(RuleEngine::new(bundle.rules.clone())?),
+        _ => None,
+    };
+    if let Some(registry) = state.registry {
+        if let Some(unknown) = bundle.detectors.keys().find(|name| registry.names().all(|n| n != name.as_str())) {
+            return Err(RegistryError::UnknownDetector(unknown.clone()).into());
+        }
+    }
+
+    let mut report = ImportReport::default();
+    if let Some(engine) = state.engine.as_deref_mut() {
+        for address in &bundle.watchlist {
+            if !engine.is_watched(address) {
+                engine.watch(*address);
+                report.watched += 1;
+            }
+        }
+        if let Some(rules) = rules {
+            report.rules = bundle.rules.len();
+            engine.detector_mut().set_rules(Some(rules));
+        }
+    }
+    if let Some(labels) = state.labels {
+        for entry in &bundle.labels {
+            labels.add_label(&entry.address, &entry.label, entry.category);
+        }
+        report.labels = bundle.labels.len();
+    }
+    if let Some(registry) = state.registry {
+        for (name, settings) in &bundle.detectors {
+            let current = registry.settings(name);
+            if (current.enabled, current.min_profit_eth, current.max_gas_price_gwei)
+                == (settings.enabled, settings.min_profit_eth, settings.max_gas_price_gwei)
+            {
+                continue;
+            }
+            registry.patch(
+                name,
+                DetectorPatch {
+                    enabled: Some(settings.enabled),
+                    min_profit_eth: settings.min_profit_eth,
+                    max_gas_price_gwei: settings.max_gas_price_gwei,
+                },
+            )?;
+            report.detectors += 1;
+        }
+    }
+    if let (Some(router), false) = (state.router, bundle.suppressions.is_empty()) {
+        router.set_suppressions(bundle.suppressions.clone());
+        report.suppressions = bundle.suppressions.len();
+    }
+    if let Some(store) = state.store {
+        for (ns, records) in &bundle.store {
+            for (key, value) in records {
+                store.put(ns, key, &serde_json::to_vec(value).map_err(StoreError::from)?)?;
+                report.records += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Импорт без работающего узла: записи хранилища переносятся сразу, остальное откладывается
+/// до старта узла (`apply_staged`). Повторный импорт заменяет отложенный
+pub fn stage_import(store: &dyn Store, bundle: &StateBundle) -> Result<ImportReport, BundleError> {
+    let mut state = NodeState::new();
+    state.store = Some(store);
+    let report = import_state(&mut state, bundle)?;
+    store.put_json(STAGED_NAMESPACE, STAGED_KEY, &StateBundle { store: BTreeMap::new(), ..bundle.clone() })?;
+    Ok(report)
+}
+
+/// Применяет отложенный `stage_import` бандл к запущенному узлу и удаляет его
+pub fn apply_staged(state: &mut NodeState<'_>) -> Result<Option<ImportReport>, BundleError> {
+    let Some(store) = state.store else {
+        return Ok(None);
+    };
+    let Some(bundle) = store.get_json::<StateBundle>(STAGED_NAMESPACE, STAGED_KEY)? else {
+        return Ok(None);
+    };
+    let report = import_state(state, &bundle)?;
+    store.delete(STAGED_NAMESPACE, STAGED_KEY)?;
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use serde_json::json;
+
+    fn bundle() -> StateBundle {
+        StateBundle {
+            version: BUNDLE_VERSION,
+            exported_at: 0,
+            watchlist: Vec::new(),
+            labels: Vec::new(),
+            detectors: BTreeMap::new(),
+            rules: Vec::new(),
+            suppressions: vec![Suppression {
+                name: "maintenance".into(),
+                matcher: Default::default(),
+                subjects: Vec::new(),
+                until: None,
+                reason: "upgrade".into(),
+            }],
+            store: [("monitor_subscriptions".to_string(), [("a".to_string(), json!({ "x": 1 }))].into())].into(),
+        }
+    }
+
+    #[test]
+    fn test_rejects_reserved_namespaces() {
+        let store = MemoryStore::new();
+        let mut forged = bundle();
+        forged.store.insert("audit".into(), [("0".to_string(), json!({}))].into());
+        let mut state = NodeState::new();
+        state.store = Some(&store);
+        assert!(matches!(import_state(&mut state, &forged), Err(BundleError::ReservedNamespace(ns)) if ns == "audit"));
+        assert!(store.list("monitor_subscriptions").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_staged_import_applied_on_start() {
+        let store = MemoryStore::new();
+        assert_eq!(stage_import(&store, &bundle()).unwrap().records, 1);
+
+        let router = AlertRouter::new(Vec::new(), BTreeMap::new());
+        let mut state = NodeState::new();
+        state.store = Some(&store);
+        state.router = Some(&router);
+        assert_eq!(apply_staged(&mut state).unwrap().unwrap().suppressions, 1);
+        assert_eq!(router.suppressions(), bundle().suppressions);
+        assert!(apply_staged(&mut state).unwrap().is_none());
+        assert_eq!(export_state(&state).unwrap().suppressions.len(), 1);
+    }
+}
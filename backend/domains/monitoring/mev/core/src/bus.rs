@@ -0,0 +1,275 @@
+#[cfg(feature = "mev")]
+use crate::detector::{MevAlert, MevType};
+use crate::leader::LeaderGate;
+use crate::schema::BUS_ALERT_VERSION;
+use crate::shutdown::{ShutdownHook, ShutdownSignal};
+use crate::store::{SharedStore, Store, StoreError, StoreExt};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Ёмкость канала по умолчанию; отстающие подписчики теряют старые алерты
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Уровень алерта, общий для всех подсистем
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AlertLevel {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl AlertLevel {
+    /// Уровень по risk_score детектора (0.0 - 1.0)
+    pub fn from_risk(risk: f64) -> Self {
+        match risk {
+            r if r >= 0.9 => AlertLevel::Critical,
+            r if r >= 0.7 => AlertLevel::High,
+            r if r >= 0.4 => AlertLevel::Medium,
+            r if r > 0.0 => AlertLevel::Low,
+            _ => AlertLevel::Info,
+        }
+    }
+}
+
+/// Алерт в шине: MEV, мониторинг контрактов, аудит
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct BusAlert {
+    /// Версия формата, см. `schema::SCHEMAS`
+    #[serde(default = "crate::schema::unversioned")]
+    pub schema_version: u32,
+    /// Подсистема-источник, например `mev` или `monitor`
+    pub source: String,
+    pub kind: String,
+    pub level: AlertLevel,
+    /// Адрес или другой идентификатор объекта алерта
+    pub subject: String,
+    pub title: String,
+    pub timestamp: u64,
+    pub payload: serde_json::Value,
+}
+
+impl BusAlert {
+    pub fn new(source: &str, kind: &str, level: AlertLevel, subject: String, title: String) -> Self {
+        Self {
+            schema_version: BUS_ALERT_VERSION,
+            source: source.to_string(),
+            kind: kind.to_string(),
+            level,
+            subject,
+            title,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            payload: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Кошелёк пользователя, которого касается алерт: жертва MEV или владелец разрешения.
+    /// Прочие подсистемы могут указать его в `payload.wallet`
+    pub fn wallet(&self) -> Option<String> {
+        let wallet = match self.source.as_str() {
+            "mev" => ["/metadata/victim_tx/from", "/metadata/target/from", "/metadata/victim"]
+                .iter()
+                .find_map(|p| self.payload.pointer(p)?.as_str()),
+            _ => self.payload.get("wallet").and_then(|w| w.as_str()),
+        };
+        wallet.filter(|w| !w.is_empty()).map(str::to_string)
+    }
+}
+
+#[cfg(feature = "mev")]
+impl From<&MevAlert> for BusAlert {
+    fn from(alert: &MevAlert) -> Self {
+        let kind = match &alert.mev_type {
+            MevType::Custom(name) => name.clone(),
+            MevType::RepeatedTargeting => "repeated_targeting".into(),
+            other => format!("{:?}", other).to_lowercase(),
+        };
+        // Целевой контракт: у фронтрана в victim_tx, у сэндвича в target; у повторных атак — сама жертва
+        let subject = ["victim_tx", "target"]
+            .iter()
+            .find_map(|k| alert.metadata[*k]["to"].as_str())
+            .or_else(|| alert.metadata["victim"].as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        Self {
+            schema_version: BUS_ALERT_VERSION,
+            source: "mev".into(),
+            title: format!("{} ({} ETH)", kind, alert.profit.format_eth(4)),
+            kind,
+            level: AlertLevel::from_risk(alert.risk_score as f64),
+            subject,
+            timestamp: alert.timestamp,
+            payload: serde_json::to_value(alert).unwrap_or_default(),
+        }
+    }
+}
+
+/// Дополняет алерт перед публикацией, например отметками скрининга адресов
+pub trait AlertAnnotator: Send + Sync {
+    fn annotate(&self, alert: BusAlert) -> BusAlert;
+}
+
+/// Шина алертов поверх broadcast-канала; клонируется дёшево
+#[derive(Clone)]
+pub struct AlertBus {
+    tx: broadcast::Sender<Arc<BusAlert>>,
+    leader: Option<LeaderGate>,
+    annotators: Vec<Arc<dyn AlertAnnotator>>,
+}
+
+impl Default for AlertBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl AlertBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx, leader: None, annotators: Vec::new() }
+    }
+
+    /// Алерты публикуются только пока экземпляр лидер; резерв анализирует, но молчит
+    pub fn with_leader_gate(mut self, gate: LeaderGate) -> Self {
+        self.leader = Some(gate);
+        self
+    }
+
+    /// Аннотатор применяется ко всем алертам шины до подписчиков
+    pub fn with_annotator(mut self, annotator: Arc<dyn AlertAnnotator>) -> Self {
+        self.annotators.push(annotator);
+        self
+    }
+
+    /// Публикует алерт; возвращает число получателей (0, если подписчиков нет или экземпляр не лидер)
+    pub fn publish(&self, alert: BusAlert) -> usize {
+        if self.leader.as_ref().is_some_and(|gate| !gate.is_leader()) {
+            return 0;
+        }
+        let alert = self.annotators.iter().fold(alert, |alert, annotator| annotator.annotate(alert));
+        self.tx.send(Arc::new(alert)).unwrap_or(0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<BusAlert>> {
+        self.tx.subscribe()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Пространство имён хранилища для недоставленных алертов
+pub const ALERT_BUFFER_NS: &str = "alert_buffer";
+
+/// Буфер алертов между шиной и синками; переживает рестарт через хранилище
+pub struct AlertBuffer {
+    alerts: Mutex<VecDeque<BusAlert>>,
+    capacity: usize,
+}
+
+impl AlertBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            alerts: Mutex::new(VecDeque::new()),
+            capacity,
+        }
+    }
+
+    /// При переполнении вытесняются самые старые алерты уровня ниже High
+    pub fn push(&self, alert: BusAlert) {
+        let mut alerts = self.alerts.lock().unwrap();
+        if alerts.len() >= self.capacity {
+            match alerts.iter().position(|a| a.level < AlertLevel::High) {
+                Some(i) => {
+                    alerts.remove(i);
+                }
+                None => {
+                    alerts.pop_front();
+                }
+            }
+        }
+        alerts.push_back(alert);
+    }
+
+    pub fn drain(&self, max: usize) -> Vec<BusAlert> {
+        let mut alerts = self.alerts.lock().unwrap();
+        let n = max.min(alerts.len());
+        alerts.drain(..n).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.alerts.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Переносит буфер в хранилище (при остановке)
+    pub fn flush_to(&self, store: &dyn Store) -> Result<usize, StoreError> {
+        let alerts: Vec<BusAlert> = self.alerts.lock().unwrap().drain(..).collect();
+        store.put_json(ALERT_BUFFER_NS, "pending", &alerts)?;
+        Ok(alerts.len())
+    }
+
+    /// Возвращает сохранённые алерты в буфер (при старте)
+    pub fn restore_from(&self, store: &dyn Store) -> Result<usize, StoreError> {
+        let saved: Vec<BusAlert> = store.get_json(ALERT_BUFFER_NS, "pending")?.unwrap_or_default();
+        let count = saved.len();
+        for alert in saved {
+            self.push(alert);
+        }
+        store.delete(ALERT_BUFFER_NS, "pending")?;
+        Ok(count)
+    }
+
+    /// Складывает алерты из шины в буфер до сигнала остановки
+    pub async fn collect(&self, bus: &AlertBus, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => self.push((*alert).clone()),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+/// Фаза FlushState: сохраняет недоставленные алерты
+pub struct AlertBufferFlush {
+    pub buffer: Arc<AlertBuffer>,
+    pub store: SharedStore,
+}
+
+#[async_trait]
+impl ShutdownHook for AlertBufferFlush {
+    fn name(&self) -> &str {
+        "alert-buffer"
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        self.buffer.flush_to(self.store.as_ref()).map(|_| ()).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Номер последнего блока; двигает подписка на новые блоки (`advance`)
+#[derive(Debug)]
+pub struct ChainHead {
+    number: AtomicU64,
+    relevance_blocks: u64,
+}
+
+impl ChainHead {
+    /// `relevance_blocks` — сколько блоков после постановки симуляция ещё имеет смысл;
+    /// 0 — до первого нового блока
+    pub fn new(number: u64, relevance_blocks: u64) -> Arc<Self> {
+        Arc::new(Self { number: AtomicU64::new(number), relevance_blocks })
+    }
+
+    /// Голова не откатывается: блок старше текущего игнорируется
+    pub fn advance(&self, number: u64) {
+        self.number.fetch_max(number, Ordering::AcqRel);
+    }
+
+    pub fn current(&self) -> u64 {
+        self.number.load(Ordering::Acquire)
+    }
+
+    /// Двигает голову за подпиской на новые блоки, пока та не закроется
+    pub async fn follow(self: Arc<Self>, mut heads: watch::Receiver<u64>) {
+        loop {
+            self.advance(*heads.borrow_and_update());
+            if heads.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Задание, помеченное текущим блоком
+    pub fn job(self: &Arc<Self>) -> SimulationJob {
+        SimulationJob { created_at_block: self.current(), head: self.clone() }
+    }
+}
+
+/// Метка задания симуляции. Отмена кооперативная: детектор проверяет метку между
+/// вызовами симулятора, а устаревшая транзакция всё равно попадает в пул ожидающих
+#[derive(Debug, Clone)]
+pub struct SimulationJob {
+    pub created_at_block: u64,
+    head: Arc<ChainHead>,
+}
+
+impl SimulationJob {
+    pub fn is_cancelled(&self) -> bool {
+        self.head.current() > self.created_at_block + self.head.relevance_blocks
+    }
+}
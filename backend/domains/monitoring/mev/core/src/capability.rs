@@ -0,0 +1,64 @@
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum CapabilityError {
+    #[error("Signing is disabled: binary was built without the `signing` feature")]
+    CompiledOut,
+
+    #[error("Signing is disabled: node runs in read-only mode")]
+    ReadOnly,
+}
+
+/// Режим развёртывания, который узел сообщает наружу (`/health`, журнал старта)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentMode {
+    ReadOnly,
+    Signing,
+}
+
+/// Право подписывать транзакции. Выдаётся только конфигом узла
+/// (`DefinetlyConfig::capabilities`) и только в сборке с `signing`; модули, способные двигать средства, требуют его в конструкторе,
+/// так что узел без токена не может их собрать
+#[derive(Debug, Clone)]
+pub struct SigningCapability {
+    _sealed: (),
+}
+
+/// Права узла по сборке и конфигу
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    signing: Result<SigningCapability, CapabilityError>,
+}
+
+impl Capabilities {
+    /// Мониторинг без подписи независимо от сборки
+    pub fn read_only() -> Self {
+        Self { signing: Err(CapabilityError::ReadOnly) }
+    }
+
+    /// `read_only` из конфига; без фичи `signing` подпись недоступна в любом случае.
+    /// Снаружи крейта права получают только из разобранного конфига
+    pub(crate) fn new(read_only: bool) -> Self {
+        if cfg!(not(feature = "signing")) {
+            return Self { signing: Err(CapabilityError::CompiledOut) };
+        }
+        if read_only {
+            return Self::read_only();
+        }
+        Self { signing: Ok(SigningCapability { _sealed: () }) }
+    }
+
+    pub fn signing(&self) -> Result<SigningCapability, CapabilityError> {
+        self.signing.clone()
+    }
+
+    pub fn mode(&self) -> DeploymentMode {
+        match self.signing {
+            Ok(_) => DeploymentMode::Signing,
+            Err(_) => DeploymentMode::ReadOnly,
+        }
+    }
+}
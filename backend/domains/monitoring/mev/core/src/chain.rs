@@ -0,0 +1,285 @@
+use crate::compat::U256;
+use ethers::types::Transaction;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Тип EIP-2718 депозитной транзакции OP-stack (приходит из L1, без подписи)
+pub const OP_DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+/// Внутренние типы транзакций ArbOS: retryable, депозиты, служебные
+const ARBITRUM_INTERNAL_TX_TYPES: std::ops::RangeInclusive<u8> = 0x64..=0x6a;
+
+/// Параметры комиссии транзакции в том виде, в каком они приходят из RPC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeFields {
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+impl From<&Transaction> for FeeFields {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            gas_price: tx.gas_price,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// Правила конкретной сети: форматы транзакций, комиссия и порядок в блоке, финальность.
+/// Детекторы, модели риска и портфель спрашивают сеть через адаптер, а не по `chain_id`
+pub trait ChainAdapter: Send + Sync {
+    fn chain_id(&self) -> u64;
+
+    fn name(&self) -> &'static str;
+
+    /// Среднее время блока
+    fn block_time(&self) -> Duration;
+
+    fn supports_tx_type(&self, tx_type: u8) -> bool;
+
+    /// Есть ли публичный мемпул, где порядок решает цена газа.
+    /// Без него фронтран и сэндвич через мемпул невозможны
+    fn fee_ordered_mempool(&self) -> bool;
+
+    /// Цена газа, которую транзакция заплатит и с которой конкурирует за место в блоке.
+    /// `base_fee` — `None`, если блок неизвестен: тогда берётся потолок
+    fn effective_gas_price(&self, fees: FeeFields, base_fee: Option<U256>) -> U256 {
+        eip1559_price(fees, base_fee)
+    }
+
+    /// Блоков до финальности
+    fn finality_depth(&self) -> u64;
+
+    fn is_final(&self, block: u64, head: u64) -> bool {
+        head >= block.saturating_add(self.finality_depth())
+    }
+
+    /// Последний финальный блок при голове `head`
+    fn finalized_block(&self, head: u64) -> u64 {
+        head.saturating_sub(self.finality_depth())
+    }
+}
+
+pub type SharedChainAdapter = Arc<dyn ChainAdapter>;
+
+/// EIP-1559: `min(max_fee, base_fee + priority)`; legacy — `gas_price`
+fn eip1559_price(fees: FeeFields, base_fee: Option<U256>) -> U256 {
+    match (fees.max_fee_per_gas, base_fee) {
+        (Some(max_fee), Some(base_fee)) => {
+            let priority = fees.max_priority_fee_per_gas.unwrap_or_default();
+            max_fee.min(base_fee.saturating_add(priority))
+        }
+        (Some(max_fee), None) => fees.gas_price.unwrap_or(max_fee),
+        (None, _) => fees.gas_price.unwrap_or_default(),
+    }
+}
+
+/// Ethereum и сети с тем же консенсусом: публичный мемпул, блобы, финальность через две эпохи
+#[derive(Debug, Clone)]
+pub struct Ethereum {
+    chain_id: u64,
+    name: &'static str,
+    block_time: Duration,
+    finality_depth: u64,
+}
+
+impl Ethereum {
+    pub fn new(chain_id: u64, name: &'static str, block_time: Duration, finality_depth: u64) -> Self {
+        Self { chain_id, name, block_time, finality_depth }
+    }
+
+    pub fn mainnet() -> Self {
+        Self::new(1, "mainnet", Duration::from_secs(12), 64)
+    }
+
+    pub fn holesky() -> Self {
+        Self::new(17000, "holesky", Duration::from_secs(12), 64)
+    }
+
+    pub fn sepolia() -> Self {
+        Self::new(11155111, "sepolia", Duration::from_secs(12), 64)
+    }
+}
+
+impl ChainAdapter for Ethereum {
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn block_time(&self) -> Duration {
+        self.block_time
+    }
+
+    fn supports_tx_type(&self, tx_type: u8) -> bool {
+        tx_type <= 3
+    }
+
+    fn fee_ordered_mempool(&self) -> bool {
+        true
+    }
+
+    fn finality_depth(&self) -> u64 {
+        self.finality_depth
+    }
+}
+
+/// OP-stack (Optimism, Base): приватный секвенсер, депозиты из L1, финальность — после финальности батча в L1
+#[derive(Debug, Clone)]
+pub struct OpStack {
+    chain_id: u64,
+    name: &'static str,
+}
+
+impl OpStack {
+    pub fn new(chain_id: u64, name: &'static str) -> Self {
+        Self { chain_id, name }
+    }
+
+    pub fn optimism() -> Self {
+        Self::new(10, "optimism")
+    }
+
+    pub fn base() -> Self {
+        Self::new(8453, "base")
+    }
+}
+
+impl ChainAdapter for OpStack {
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn block_time(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn supports_tx_type(&self, tx_type: u8) -> bool {
+        tx_type <= 2 || tx_type == OP_DEPOSIT_TX_TYPE
+    }
+
+    fn fee_ordered_mempool(&self) -> bool {
+        false
+    }
+
+    /// ~15 минут: публикация батча плюс финальность L1
+    fn finality_depth(&self) -> u64 {
+        450
+    }
+}
+
+/// Arbitrum One: секвенсер FCFS, приоритетная комиссия не влияет на порядок и не взимается
+#[derive(Debug, Clone, Default)]
+pub struct Arbitrum;
+
+impl ChainAdapter for Arbitrum {
+    fn chain_id(&self) -> u64 {
+        42161
+    }
+
+    fn name(&self) -> &'static str {
+        "arbitrum"
+    }
+
+    fn block_time(&self) -> Duration {
+        Duration::from_millis(250)
+    }
+
+    fn supports_tx_type(&self, tx_type: u8) -> bool {
+        tx_type <= 2 || ARBITRUM_INTERNAL_TX_TYPES.contains(&tx_type)
+    }
+
+    fn fee_ordered_mempool(&self) -> bool {
+        false
+    }
+
+    fn effective_gas_price(&self, fees: FeeFields, base_fee: Option<U256>) -> U256 {
+        eip1559_price(FeeFields { max_priority_fee_per_gas: None, ..fees }, base_fee)
+    }
+
+    /// ~20 минут: батч в L1 и его финальность
+    fn finality_depth(&self) -> u64 {
+        4800
+    }
+}
+
+/// BNB Smart Chain: публичный мемпул, base fee равен нулю, быстрая финальность (BEP-126)
+#[derive(Debug, Clone, Default)]
+pub struct Bsc;
+
+impl ChainAdapter for Bsc {
+    fn chain_id(&self) -> u64 {
+        56
+    }
+
+    fn name(&self) -> &'static str {
+        "bsc"
+    }
+
+    fn block_time(&self) -> Duration {
+        Duration::from_millis(750)
+    }
+
+    fn supports_tx_type(&self, tx_type: u8) -> bool {
+        tx_type <= 2
+    }
+
+    fn fee_ordered_mempool(&self) -> bool {
+        true
+    }
+
+    fn effective_gas_price(&self, fees: FeeFields, base_fee: Option<U256>) -> U256 {
+        eip1559_price(fees, base_fee.or(Some(U256::zero())))
+    }
+
+    fn finality_depth(&self) -> u64 {
+        2
+    }
+}
+
+/// Адаптер для сети из `KNOWN_CHAINS`; `None` для неизвестной сети
+pub fn adapter_for(chain_id: u64) -> Option<SharedChainAdapter> {
+    let adapter: SharedChainAdapter = match chain_id {
+        1 => Arc::new(Ethereum::mainnet()),
+        17000 => Arc::new(Ethereum::holesky()),
+        11155111 => Arc::new(Ethereum::sepolia()),
+        137 => Arc::new(Ethereum::new(137, "polygon", Duration::from_secs(2), 16)),
+        10 => Arc::new(OpStack::optimism()),
+        8453 => Arc::new(OpStack::base()),
+        42161 => Arc::new(Arbitrum),
+        56 => Arc::new(Bsc),
+        _ => return None,
+    };
+    Some(adapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KNOWN_CHAINS;
+
+    #[test]
+    fn test_fee_rules_differ_by_chain() {
+        let gwei = |g: u64| U256::from(g) * U256::exp10(9);
+        let fees = FeeFields { gas_price: None, max_fee_per_gas: Some(gwei(50)), max_priority_fee_per_gas: Some(gwei(2)) };
+
+        assert_eq!(Ethereum::mainnet().effective_gas_price(fees, Some(gwei(30))), gwei(32));
+        assert_eq!(Ethereum::mainnet().effective_gas_price(fees, None), gwei(50));
+        assert_eq!(Arbitrum.effective_gas_price(fees, Some(gwei(30))), gwei(30));
+        assert_eq!(Bsc.effective_gas_price(fees, None), gwei(2));
+
+        for (id, name) in KNOWN_CHAINS {
+            let adapter = adapter_for(*id).unwrap();
+            assert_eq!((adapter.chain_id(), adapter.name()), (*id, *name));
+        }
+    }
+}
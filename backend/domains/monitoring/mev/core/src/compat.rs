@@ -0,0 +1,99 @@
+pub use ethers::types::{Address, Bytes, H256, U256};
+
+/// ethers → alloy
+pub trait ToAlloy {
+    type Alloy;
+    fn to_alloy(&self) -> Self::Alloy;
+}
+
+/// alloy → ethers
+pub trait ToEthers {
+    type Ethers;
+    fn to_ethers(&self) -> Self::Ethers;
+}
+
+impl ToAlloy for Address {
+    type Alloy = alloy_primitives::Address;
+    fn to_alloy(&self) -> Self::Alloy {
+        alloy_primitives::Address::from(self.0)
+    }
+}
+
+impl ToEthers for alloy_primitives::Address {
+    type Ethers = Address;
+    fn to_ethers(&self) -> Self::Ethers {
+        Address::from(self.into_array())
+    }
+}
+
+impl ToAlloy for H256 {
+    type Alloy = alloy_primitives::B256;
+    fn to_alloy(&self) -> Self::Alloy {
+        alloy_primitives::B256::from(self.0)
+    }
+}
+
+impl ToEthers for alloy_primitives::B256 {
+    type Ethers = H256;
+    fn to_ethers(&self) -> Self::Ethers {
+        H256(self.0)
+    }
+}
+
+impl ToAlloy for U256 {
+    type Alloy = alloy_primitives::U256;
+    fn to_alloy(&self) -> Self::Alloy {
+        let mut bytes = [0u8; 32];
+        self.to_big_endian(&mut bytes);
+        alloy_primitives::U256::from_be_bytes(bytes)
+    }
+}
+
+impl ToEthers for alloy_primitives::U256 {
+    type Ethers = U256;
+    fn to_ethers(&self) -> Self::Ethers {
+        U256::from_big_endian(&self.to_be_bytes::<32>())
+    }
+}
+
+impl ToAlloy for Bytes {
+    type Alloy = alloy_primitives::Bytes;
+    fn to_alloy(&self) -> Self::Alloy {
+        alloy_primitives::Bytes::copy_from_slice(self.as_ref())
+    }
+}
+
+impl ToEthers for alloy_primitives::Bytes {
+    type Ethers = Bytes;
+    fn to_ethers(&self) -> Self::Ethers {
+        Bytes::from(self.to_vec())
+    }
+}
+
+/// keccak-256 без зависимости от `ethers::utils`
+pub fn keccak256(bytes: impl AsRef<[u8]>) -> H256 {
+    alloy_primitives::keccak256(bytes).to_ethers()
+}
+
+/// EIP-55
+pub fn to_checksum(address: &Address) -> String {
+    address.to_alloy().to_checksum(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_checksum() {
+        let address: Address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".parse().unwrap();
+        assert_eq!(address.to_alloy().to_ethers(), address);
+        assert_eq!(to_checksum(&address), "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+
+        let value = U256::from(10).pow(U256::from(24)) + U256::from(7);
+        assert_eq!(value.to_alloy().to_ethers(), value);
+        assert_eq!(value.to_alloy().to_string(), "1000000000000000000000007");
+
+        assert_eq!(keccak256(b""), H256(ethers::utils::keccak256(b"")));
+    }
+}
@@ -0,0 +1,1942 @@
+#[cfg(feature = "mev")]
+use crate::amount::WeiAmount;
+#[cfg(feature = "mev")]
+use crate::bridges::{BridgeError, BridgeTracker, Cctp};
+#[cfg(feature = "mev")]
+use crate::bus::AlertBus;
+use crate::capability::Capabilities;
+use crate::compat::{to_checksum, Address};
+#[cfg(feature = "mev")]
+use crate::congestion::{parse_selector, CongestionError, CongestionMonitor, CongestionWatch, Regime};
+use crate::correlation::{Correlator, DEFAULT_WINDOW_SECONDS};
+#[cfg(feature = "mev")]
+use crate::detector::MevThresholds;
+#[cfg(all(feature = "mev", feature = "audit"))]
+use crate::enrichment::screening::{ListSource, ListSpec, ScreeningEnricher};
+use crate::digest::DigestTenant;
+#[cfg(feature = "email")]
+use crate::email::SmtpSettings;
+#[cfg(feature = "mev")]
+use crate::fingerprint::BotFingerprinter;
+#[cfg(feature = "mev")]
+use crate::funding::FundingGraph;
+use crate::i18n::{fill, Locale, LocaleSelector, MessageCatalog};
+#[cfg(feature = "mev")]
+use crate::ingest::ws::ReconnectPolicy;
+#[cfg(feature = "mev")]
+use crate::intents::IntentConfig;
+#[cfg(feature = "mev")]
+use crate::state_diff::StorageLayouts;
+#[cfg(feature = "staking")]
+use crate::policy::WalletPolicy;
+use crate::retention::{RetentionRule, RetentionTarget, PROTECTED_NAMESPACES};
+use crate::routing::{AlertRouter, Route, Suppression};
+use crate::rpc::QuotaConfig;
+#[cfg(feature = "mev")]
+use crate::rpc::RetryPolicy;
+use crate::sink::Sink;
+use crate::store::{SharedStore, StoreError};
+#[cfg(feature = "staking")]
+use crate::remote_signer::is_bls_public_key;
+#[cfg(feature = "mev")]
+use crate::rules::{RuleEngine, RuleSpec};
+#[cfg(feature = "secrets")]
+use crate::secrets::SecretRef;
+#[cfg(feature = "mev")]
+use crate::severity::SeverityConfig;
+use crate::chain::{adapter_for, SharedChainAdapter};
+#[cfg(feature = "mev")]
+use crate::units::Bps;
+#[cfg(any(feature = "mev", feature = "staking"))]
+use crate::units::Gwei;
+#[cfg(feature = "mev")]
+use crate::victims::VictimTracker;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Сети, с которыми работает система: chain_id -> имя
+pub const KNOWN_CHAINS: &[(u64, &str)] = &[
+    (1, "mainnet"),
+    (10, "optimism"),
+    (56, "bsc"),
+    (137, "polygon"),
+    (8453, "base"),
+    (17000, "holesky"),
+    (42161, "arbitrum"),
+    (11155111, "sepolia"),
+];
+
+pub fn chain_name(chain_id: u64) -> Option<&'static str> {
+    KNOWN_CHAINS.iter().find(|(id, _)| *id == chain_id).map(|(_, name)| *name)
+}
+
+/// Одна проблема конфигурации с путём до поля
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+}
+
+/// Все найденные проблемы сразу, а не первая попавшаяся
+#[derive(Debug, Clone, Default)]
+pub struct ConfigErrors(pub Vec<ConfigIssue>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} configuration problem(s):", self.0.len())?;
+        for issue in &self.0 {
+            writeln!(f, "  - {}: {}", issue.path, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Сборщик проблем; проверки не прерываются на первой ошибке
+#[derive(Default)]
+pub struct ConfigValidator {
+    issues: Vec<ConfigIssue>,
+}
+
+impl ConfigValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.issues.push(ConfigIssue {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Адрес: 20 байт hex; при смешанном регистре — корректная контрольная сумма EIP-55
+    pub fn address(&mut self, path: &str, value: &str) -> Option<Address> {
+        let Ok(address) = value.parse::<Address>() else {
+            self.error(path, format!("'{}' is not a 20-byte hex address", value));
+            return None;
+        };
+
+        let hex = value.trim_start_matches("0x");
+        let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+        let checksummed = to_checksum(&address);
+        if mixed_case && checksummed != value {
+            self.error(path, format!("bad EIP-55 checksum, did you mean {}?", checksummed));
+        }
+        Some(address)
+    }
+
+    pub fn url(&mut self, path: &str, value: &str, schemes: &[&str]) {
+        match value.split_once("://") {
+            Some((scheme, rest)) if schemes.contains(&scheme) && !rest.is_empty() => {}
+            Some((scheme, _)) if !schemes.contains(&scheme) => self.error(
+                path,
+                format!("unsupported scheme '{}', expected one of {}", scheme, schemes.join(", ")),
+            ),
+            _ => self.error(path, format!("'{}' is not a valid URL", value)),
+        }
+    }
+
+    pub fn range(&mut self, path: &str, value: f64, min: f64, max: f64) {
+        if !(min..=max).contains(&value) || value.is_nan() {
+            self.error(path, format!("{} is out of range [{}, {}]", value, min, max));
+        }
+    }
+
+    pub fn positive(&mut self, path: &str, value: u64) {
+        if value == 0 {
+            self.error(path, "must be greater than zero");
+        }
+    }
+
+    pub fn chain_id(&mut self, path: &str, chain_id: u64) {
+        if chain_name(chain_id).is_none() {
+            let known: Vec<String> = KNOWN_CHAINS.iter().map(|(id, n)| format!("{} ({})", id, n)).collect();
+            self.error(path, format!("unknown chain_id {}; known: {}", chain_id, known.join(", ")));
+        }
+    }
+
+    pub fn finish(self) -> Result<(), ConfigErrors> {
+        if self.issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(self.issues))
+        }
+    }
+}
+
+/// Конфигурационная секция, умеющая проверить себя
+pub trait Validate {
+    fn validate(&self, v: &mut ConfigValidator);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcConfig {
+    pub http_url: String,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    pub chain_id: u64,
+    /// Квоты плана провайдера; без секции вызовы не ограничиваются
+    #[serde(default)]
+    pub quota: Option<QuotaConfig>,
+}
+
+impl RpcConfig {
+    /// Правила сети `chain_id`; `None` для неизвестной сети (её отсекает валидация)
+    pub fn chain(&self) -> Option<SharedChainAdapter> {
+        adapter_for(self.chain_id)
+    }
+}
+
+impl Validate for RpcConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.url("rpc.http_url", &self.http_url, &["http", "https"]);
+        if let Some(ws) = &self.ws_url {
+            v.url("rpc.ws_url", ws, &["ws", "wss"]);
+        }
+        v.chain_id("rpc.chain_id", self.chain_id);
+        if let Some(quota) = &self.quota {
+            let total: f64 = quota.budgets.values().map(|share| share.get()).sum();
+            if total > 1.0 {
+                v.error("rpc.quota.budgets", format!("subsystem shares sum to {}, must not exceed 1", total));
+            }
+            if !quota.budgets.is_empty() && quota.compute_units_per_second == 0 {
+                v.error("rpc.quota.compute_units_per_second", "must be set when subsystem budgets are configured");
+            }
+            if quota.retry.initial_backoff_ms > quota.retry.max_backoff_ms {
+                v.error("rpc.quota.retry.initial_backoff_ms", "must not exceed max_backoff_ms");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorConfig {
+    pub pending_ttl_seconds: u64,
+    pub min_profit_eth: f64,
+    pub max_gas_price_gwei: Gwei,
+    #[serde(default)]
+    pub rules: Vec<RuleSpec>,
+    /// Окно дедупликации повторных доставок (`MevDetector::with_dedup`); 0 — выключено
+    #[serde(default = "default_dedup_window")]
+    pub dedup_window_seconds: u64,
+    /// Веса severity сэндвичей (`SeverityModel`)
+    #[serde(default)]
+    pub severity: SeverityConfig,
+    /// Повторные сэндвичи одной жертвы (`[detector.victims]`); без секции не отслеживаются
+    #[serde(default)]
+    pub victims: Option<VictimTrackingConfig>,
+    /// Отпечатки ботов-копировщиков (`[detector.fingerprints]`); без секции не снимаются
+    #[serde(default)]
+    pub fingerprints: Option<FingerprintConfig>,
+    /// Кластеры атакующих по графу пополнений (`[detector.funding]`); без секции не строятся
+    #[serde(default)]
+    pub funding: Option<FundingConfig>,
+}
+
+/// Окно истории атак на жертву и взвешенное число атак, с которого выпускается `RepeatedTargeting`
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VictimTrackingConfig {
+    pub window_seconds: u64,
+    pub threshold: f64,
+}
+
+/// Бот получает отпечаток после `min_copies` копий чужой calldata на `min_targets` разных контрактах
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FingerprintConfig {
+    /// Сколько оригиналов помнить для сравнения
+    pub capacity: usize,
+    pub min_copies: u64,
+    pub min_targets: usize,
+}
+
+#[cfg(feature = "mev")]
+fn default_funding_fanout() -> usize {
+    crate::funding::DEFAULT_MAX_FANOUT
+}
+
+#[cfg(feature = "mev")]
+fn default_funding_capacity() -> usize {
+    crate::funding::DEFAULT_CAPACITY
+}
+
+/// Граф пополнений по переводам ETH из мемпула
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FundingConfig {
+    /// Источник с большим числом пополнений считается хабом и кластеры не объединяет
+    #[serde(default = "default_funding_fanout")]
+    pub max_fanout: usize,
+    /// Сколько адресов помнить
+    #[serde(default = "default_funding_capacity")]
+    pub capacity: usize,
+    /// Биржи, мосты и роутеры, которые никогда не объединяют кластеры
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[cfg(feature = "mev")]
+impl FundingConfig {
+    /// Адреса уже проверены `validate`; непарсящиеся пропускаются
+    pub fn graph(&self) -> FundingGraph {
+        let graph = FundingGraph::new(self.max_fanout, self.capacity);
+        for address in self.exclude.iter().filter_map(|a| a.parse().ok()) {
+            graph.exclude(address);
+        }
+        graph
+    }
+}
+
+#[cfg(feature = "mev")]
+impl FingerprintConfig {
+    pub fn fingerprinter(&self) -> BotFingerprinter {
+        BotFingerprinter::new(self.capacity, self.min_copies, self.min_targets)
+    }
+}
+
+#[cfg(feature = "mev")]
+impl VictimTrackingConfig {
+    pub fn tracker(&self) -> VictimTracker {
+        VictimTracker::new(self.window_seconds, self.threshold)
+    }
+}
+
+#[cfg(feature = "mev")]
+fn default_dedup_window() -> u64 {
+    crate::dedup::DEFAULT_WINDOW.as_secs()
+}
+
+#[cfg(feature = "mev")]
+impl DetectorConfig {
+    pub fn thresholds(&self) -> MevThresholds {
+        MevThresholds {
+            min_profit: WeiAmount::from_eth(self.min_profit_eth),
+            max_gas_price: self.max_gas_price_gwei,
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+impl Validate for DetectorConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("detector.pending_ttl_seconds", self.pending_ttl_seconds);
+        v.range("detector.min_profit_eth", self.min_profit_eth, 0.0, 1_000.0);
+        v.range("detector.max_gas_price_gwei", self.max_gas_price_gwei.to_f64(), 0.1, 100_000.0);
+        for (i, rule) in self.rules.iter().enumerate() {
+            v.range(&format!("detector.rules[{}].risk_score", i), rule.risk_score, 0.0, 1.0);
+        }
+        v.range("detector.severity.loss_share_weight", self.severity.loss_share_weight, 0.0, 1.0);
+        v.range("detector.severity.pool_share_weight", self.severity.pool_share_weight, 0.0, 1.0);
+        let weights = &self.severity.tier_weights;
+        let tiers = [
+            ("major", weights.major),
+            ("stable", weights.stable),
+            ("standard", weights.standard),
+            ("long_tail", weights.long_tail),
+        ];
+        for (tier, weight) in tiers {
+            v.range(&format!("detector.severity.tier_weights.{}", tier), weight, 0.0, 5.0);
+        }
+        if let Err(e) = RuleEngine::new(self.rules.clone()) {
+            v.error("detector.rules", e.to_string());
+        }
+        if let Some(victims) = &self.victims {
+            v.positive("detector.victims.window_seconds", victims.window_seconds);
+            v.range("detector.victims.threshold", victims.threshold, 1.0, 1_000.0);
+        }
+        if let Some(fingerprints) = &self.fingerprints {
+            v.positive("detector.fingerprints.capacity", fingerprints.capacity as u64);
+            v.positive("detector.fingerprints.min_copies", fingerprints.min_copies);
+            v.positive("detector.fingerprints.min_targets", fingerprints.min_targets as u64);
+        }
+        if let Some(funding) = &self.funding {
+            v.positive("detector.funding.max_fanout", funding.max_fanout as u64);
+            v.positive("detector.funding.capacity", funding.capacity as u64);
+            for (i, address) in funding.exclude.iter().enumerate() {
+                v.address(&format!("detector.funding.exclude[{}]", i), address);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+    pub store_path: String,
+    #[serde(default)]
+    pub watched_contracts: Vec<String>,
+    #[serde(default = "default_interval_blocks")]
+    pub interval_blocks: u64,
+    /// Симуляция апгрейдов наблюдаемых прокси на форке
+    #[serde(default)]
+    pub simulation: Option<UpgradeSimulationConfig>,
+    /// Авторизации EIP-7702 наблюдаемых EOA
+    #[serde(default)]
+    pub delegation: Option<DelegationConfig>,
+}
+
+fn default_interval_blocks() -> u64 {
+    5
+}
+
+/// Форк Anvil и сценарии, прогоняемые на прежней и новой реализации
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpgradeSimulationConfig {
+    pub fork_url: String,
+    /// RPC сети для сброса форка на блок апгрейда; без него форк сбрасывается через свой upstream
+    #[serde(default)]
+    pub upstream_url: Option<String>,
+    #[serde(default)]
+    pub vaults: Vec<VaultScenario>,
+}
+
+/// EOA, чьи делегирования проверяются, и реализации, которым оператор доверяет
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DelegationConfig {
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub trusted: Vec<String>,
+}
+
+/// Круг депозит-вывод ERC-4626 от имени держателя актива
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VaultScenario {
+    pub proxy: String,
+    pub asset: String,
+    pub holder: String,
+    /// Сумма депозита в минимальных единицах актива, десятичной строкой
+    pub assets: String,
+}
+
+impl Validate for MonitorConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if self.store_path.trim().is_empty() {
+            v.error("monitor.store_path", "must not be empty");
+        }
+        for (i, contract) in self.watched_contracts.iter().enumerate() {
+            v.address(&format!("monitor.watched_contracts[{}]", i), contract);
+        }
+        v.positive("monitor.interval_blocks", self.interval_blocks);
+        if let Some(simulation) = &self.simulation {
+            simulation.validate(v);
+        }
+        if let Some(delegation) = &self.delegation {
+            delegation.validate(v);
+        }
+    }
+}
+
+impl Validate for DelegationConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if self.accounts.is_empty() {
+            v.error("monitor.delegation.accounts", "must not be empty");
+        }
+        for (i, account) in self.accounts.iter().enumerate() {
+            v.address(&format!("monitor.delegation.accounts[{}]", i), account);
+        }
+        for (i, trusted) in self.trusted.iter().enumerate() {
+            v.address(&format!("monitor.delegation.trusted[{}]", i), trusted);
+        }
+    }
+}
+
+impl Validate for UpgradeSimulationConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.url("monitor.simulation.fork_url", &self.fork_url, &["http", "https"]);
+        if let Some(upstream) = &self.upstream_url {
+            v.url("monitor.simulation.upstream_url", upstream, &["http", "https"]);
+        }
+        for (i, vault) in self.vaults.iter().enumerate() {
+            let path = format!("monitor.simulation.vaults[{}]", i);
+            v.address(&format!("{}.proxy", path), &vault.proxy);
+            v.address(&format!("{}.asset", path), &vault.asset);
+            v.address(&format!("{}.holder", path), &vault.holder);
+            if !vault.assets.parse::<u128>().is_ok_and(|assets| assets > 0) {
+                v.error(&format!("{}.assets", path), format!("'{}' is not a positive integer amount", vault.assets));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "staking")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestakingSection {
+    pub eigen_contract: String,
+    pub gas_limit: u64,
+    pub max_priority_fee_gwei: Gwei,
+    pub max_fee_gwei: Gwei,
+    /// Ссылка на ключ подписи (`env:`, `keystore:`, `vault:`); сырые ключи не принимаются
+    pub signer: String,
+}
+
+#[cfg(feature = "staking")]
+impl Validate for RestakingSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.address("restaking.eigen_contract", &self.eigen_contract);
+        v.positive("restaking.gas_limit", self.gas_limit);
+        v.range("restaking.max_fee_gwei", self.max_fee_gwei.to_f64(), 0.0, 10_000.0);
+        if self.max_priority_fee_gwei > self.max_fee_gwei {
+            v.error("restaking.max_priority_fee_gwei", "must not exceed max_fee_gwei");
+        }
+        if self.signer.parse::<SecretRef>().is_err() {
+            v.error("restaking.signer", "must be a secret reference (env:, keystore:, vault:), not a raw key");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderBackend {
+    Postgres,
+    Etcd,
+}
+
+/// Выборы лидера между экземплярами; без секции экземпляр всегда лидер
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeaderSection {
+    pub backend: LeaderBackend,
+    /// DSN Postgres или список эндпоинтов etcd
+    pub endpoints: Vec<String>,
+    /// Имя кластера: экземпляры с одинаковым именем конкурируют за лидерство
+    pub cluster: String,
+    #[serde(default = "default_lease_ttl")]
+    pub ttl_seconds: u64,
+    #[serde(default = "default_renew_interval")]
+    pub renew_interval_seconds: u64,
+}
+
+fn default_lease_ttl() -> u64 {
+    15
+}
+
+fn default_renew_interval() -> u64 {
+    5
+}
+
+impl Validate for LeaderSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        let schemes: &[&str] = match self.backend {
+            LeaderBackend::Postgres => &["postgres", "postgresql"],
+            LeaderBackend::Etcd => &["http", "https"],
+        };
+        if self.endpoints.is_empty() {
+            v.error("leader.endpoints", "must not be empty");
+        }
+        for (i, endpoint) in self.endpoints.iter().enumerate() {
+            v.url(&format!("leader.endpoints[{}]", i), endpoint, schemes);
+        }
+        if self.cluster.trim().is_empty() {
+            v.error("leader.cluster", "must not be empty");
+        }
+        v.positive("leader.ttl_seconds", self.ttl_seconds);
+        if self.renew_interval_seconds == 0 || self.renew_interval_seconds * 2 > self.ttl_seconds {
+            v.error("leader.renew_interval_seconds", "must be positive and at most half of ttl_seconds");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IngestionMode {
+    /// Подписка на ожидающие транзакции (только ws/wss)
+    #[default]
+    Subscribe,
+    /// Опрос txpool для провайдеров без подписок
+    Poll,
+    /// txpool gRPC собственного узла Erigon, минуя JSON-RPC
+    #[serde(rename = "erigon_grpc")]
+    ErigonGrpc,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxpoolMethod {
+    /// Полные транзакции с calldata
+    #[default]
+    Content,
+    /// Только сводки (получатель, value, газ) — дешевле, но без calldata
+    Inspect,
+}
+
+#[cfg(feature = "mev")]
+/// Один провайдер мемпула
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IngestionProvider {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub mode: IngestionMode,
+    #[serde(default)]
+    pub txpool_method: TxpoolMethod,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_ms: u64,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+}
+
+#[cfg(feature = "mev")]
+fn default_poll_interval() -> u64 {
+    1_000
+}
+
+#[cfg(feature = "mev")]
+/// Источники мемпула; без секции используется `rpc.ws_url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IngestionSection {
+    pub providers: Vec<IngestionProvider>,
+}
+
+#[cfg(feature = "mev")]
+impl Validate for IngestionSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if self.providers.is_empty() {
+            v.error("ingestion.providers", "must not be empty");
+        }
+        for (i, provider) in self.providers.iter().enumerate() {
+            let path = format!("ingestion.providers[{}]", i);
+            if self.providers[..i].iter().any(|p| p.name == provider.name) {
+                v.error(&format!("{}.name", path), format!("duplicate provider name '{}'", provider.name));
+            }
+            match provider.mode {
+                IngestionMode::Subscribe => v.url(&format!("{}.url", path), &provider.url, &["ws", "wss"]),
+                IngestionMode::Poll => {
+                    v.url(&format!("{}.url", path), &provider.url, &["http", "https", "ws", "wss"]);
+                    v.positive(&format!("{}.poll_interval_ms", path), provider.poll_interval_ms);
+                }
+                IngestionMode::ErigonGrpc => v.url(&format!("{}.url", path), &provider.url, &["http", "https"]),
+            }
+            v.range(&format!("{}.reconnect.jitter", path), provider.reconnect.jitter, 0.0, 1.0);
+        }
+    }
+}
+
+/// Язык сообщений по синкам и тенантам, перекрытия встроенных шаблонов
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct I18nSection {
+    #[serde(default)]
+    pub default_locale: Locale,
+    #[serde(default)]
+    pub sinks: BTreeMap<String, Locale>,
+    #[serde(default)]
+    pub tenants: BTreeMap<String, Locale>,
+    /// Код языка -> ключ (`lending.health_factor`) -> шаблон
+    #[serde(default)]
+    pub templates: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl I18nSection {
+    pub fn selector(&self) -> LocaleSelector {
+        LocaleSelector {
+            default_locale: self.default_locale,
+            sinks: self.sinks.clone(),
+            tenants: self.tenants.clone(),
+        }
+    }
+
+    /// Встроенные наборы с перекрытиями; неизвестные языки отсекает валидация
+    pub fn catalog(&self) -> MessageCatalog {
+        self.templates
+            .iter()
+            .filter_map(|(code, templates)| Some((code.parse::<Locale>().ok()?, templates)))
+            .fold(MessageCatalog::new(), |catalog, (locale, templates)| catalog.with_templates(locale, templates))
+    }
+}
+
+impl Validate for I18nSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        for (code, templates) in &self.templates {
+            if let Err(e) = code.parse::<Locale>() {
+                v.error(&format!("i18n.templates.{}", code), e);
+            }
+            for (key, template) in templates {
+                if fill(template, |_| Some(serde_json::Value::String(String::new()))).is_none() {
+                    v.error(&format!("i18n.templates.{}.{}", code, key), "unbalanced or malformed placeholder");
+                }
+            }
+        }
+    }
+}
+
+/// Встроенный дашборд (feature `dashboard`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DashboardSection {
+    /// Только loopback: у дашборда нет авторизации
+    pub listen: String,
+    /// Origin обратного прокси, которому можно открыть живую ленту алертов
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl Validate for DashboardSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        match self.listen.parse::<std::net::SocketAddr>() {
+            Ok(addr) if !addr.ip().is_loopback() => {
+                v.error("dashboard.listen", "dashboard has no authentication and must listen on loopback");
+            }
+            Ok(_) => {}
+            Err(_) => v.error("dashboard.listen", format!("'{}' is not a socket address", self.listen)),
+        }
+        for (i, origin) in self.allowed_origins.iter().enumerate() {
+            v.url(&format!("dashboard.allowed_origins[{}]", i), origin, &["http", "https"]);
+        }
+        if cfg!(not(feature = "dashboard")) {
+            v.error("dashboard", "binary was built without the `dashboard` feature");
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+/// Админ-API управления детекторами
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminSection {
+    pub listen: String,
+    /// Ссылка на bearer-токен; без токена допускается только loopback
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl Validate for AdminSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        match self.listen.parse::<std::net::SocketAddr>() {
+            Ok(addr) if self.token.is_none() && !addr.ip().is_loopback() => {
+                v.error("admin.listen", "non-loopback address requires admin.token");
+            }
+            Ok(_) => {}
+            Err(_) => v.error("admin.listen", format!("'{}' is not a socket address", self.listen)),
+        }
+        if let Some(token) = &self.token {
+            if token.parse::<SecretRef>().is_err() {
+                v.error("admin.token", "must be a secret reference (env:, keystore:, vault:)");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "secrets")]
+fn default_encrypted_fields() -> Vec<String> {
+    crate::encryption::DEFAULT_SENSITIVE_FIELDS.iter().map(|f| f.to_string()).collect()
+}
+
+#[cfg(feature = "secrets")]
+fn default_encrypted_namespaces() -> Vec<String> {
+    vec![crate::bus::ALERT_BUFFER_NS.to_string()]
+}
+
+/// Шифрование полей метаданных алертов в хранилище
+#[cfg(feature = "secrets")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionSection {
+    /// Ссылка на ключ AES-256 (32 байта hex)
+    pub key: String,
+    /// Пути полей через точку, например `payload.metadata.victim_tx`
+    #[serde(default = "default_encrypted_fields")]
+    pub fields: Vec<String>,
+    #[serde(default = "default_encrypted_namespaces")]
+    pub namespaces: Vec<String>,
+    /// Ссылка на токен админ-API, которому поля отдаются расшифрованными
+    #[serde(default)]
+    pub sensitive_token: Option<String>,
+}
+
+#[cfg(feature = "secrets")]
+impl Validate for EncryptionSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if self.key.parse::<SecretRef>().is_err() {
+            v.error("encryption.key", "must be a secret reference (env:, keystore:, vault:), not a raw key");
+        }
+        if self.fields.is_empty() {
+            v.error("encryption.fields", "must not be empty");
+        }
+        if self.namespaces.is_empty() {
+            v.error("encryption.namespaces", "must not be empty");
+        }
+        if self.sensitive_token.as_ref().is_some_and(|token| token.parse::<SecretRef>().is_err()) {
+            v.error("encryption.sensitive_token", "must be a secret reference (env:, keystore:, vault:)");
+        }
+    }
+}
+
+#[cfg(feature = "staking")]
+impl Validate for WalletPolicy {
+    fn validate(&self, v: &mut ConfigValidator) {
+        let path = format!("policies[{:?}]", self.wallet);
+        if self.allowed_destinations.is_empty() {
+            v.error(&format!("{}.allowed_destinations", path), "empty allowlist blocks every transaction");
+        }
+        v.range(&format!("{}.daily_cap_eth", path), self.daily_cap_eth, 0.0, 1_000_000.0);
+        if let Some(threshold) = self.confirm_above_eth {
+            v.range(&format!("{}.confirm_above_eth", path), threshold, 0.0, self.daily_cap_eth);
+            if self.required_confirmations == 0 {
+                v.error(&format!("{}.required_confirmations", path), "must be at least 1 when confirm_above_eth is set");
+            }
+            if self.required_confirmations > self.approvers.len() {
+                v.error(&format!("{}.approvers", path), "fewer approvers than required_confirmations");
+            }
+        }
+    }
+}
+
+fn default_retention_interval() -> u64 {
+    3600
+}
+
+/// Хранение алертов, сегментов записи и выгрузок; без секции ничего не удаляется
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionSection {
+    #[serde(default = "default_retention_interval")]
+    pub interval_seconds: u64,
+    pub rules: Vec<RetentionRule>,
+}
+
+impl Validate for RetentionSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("retention.interval_seconds", self.interval_seconds);
+        for (i, rule) in self.rules.iter().enumerate() {
+            let path = format!("retention.rules[{}]", i);
+            if rule.max_age_seconds.is_none() && rule.max_bytes.is_none() {
+                v.error(&path, "needs max_age_seconds or max_bytes");
+            }
+            if let RetentionTarget::Namespace(ns) = &rule.target {
+                if PROTECTED_NAMESPACES.contains(&ns.as_str()) {
+                    v.error(&format!("{}.target", path), format!("namespace '{}' cannot be pruned", ns));
+                }
+            }
+        }
+    }
+}
+
+/// Маршруты алертов шины по цепочкам синков с эскалацией
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingSection {
+    /// Проверяются по порядку, см. `Route::continue_matching`
+    pub routes: Vec<Route>,
+    /// Тенант -> его адреса
+    #[serde(default)]
+    pub tenants: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub suppressions: Vec<Suppression>,
+    /// Синки, на которые ссылаются шаги `chain`
+    #[serde(default)]
+    pub sinks: Vec<SinkSpec>,
+}
+
+/// Синк маршрутизации. Ключи платформ — ссылки на секреты (env:, keystore:, vault:)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SinkSpec {
+    Webhook { name: String, url: String },
+    /// Вебхук v3 сервиса шлётся на `/admin/incidents/{name}`
+    #[serde(rename = "pagerduty")]
+    PagerDuty {
+        name: String,
+        routing_key: String,
+        #[serde(default)]
+        url: Option<String>,
+    },
+    /// Исходящий вебхук интеграции шлётся на `/admin/incidents/{name}`
+    Opsgenie {
+        name: String,
+        api_key: String,
+        /// `https://api.eu.opsgenie.com` для аккаунтов в EU
+        #[serde(default)]
+        base_url: Option<String>,
+    },
+    /// Почта; `password` — ссылка на секрет
+    #[cfg(feature = "email")]
+    Smtp {
+        name: String,
+        #[serde(default)]
+        password: Option<String>,
+        smtp: SmtpSettings,
+    },
+    /// Почта в сборке без фичи `email`: разбирается, но узел такой синк не поднимает
+    #[cfg(not(feature = "email"))]
+    Smtp {
+        name: String,
+        #[serde(default)]
+        password: Option<String>,
+        smtp: toml::Value,
+    },
+    /// Алерты владельцам кошельков; подписки хранятся в `[monitor]` и принимаются
+    /// публичным `/wallets/subscriptions`
+    #[cfg(feature = "push")]
+    Wallet {
+        name: String,
+        #[serde(default)]
+        xmtp: Option<XmtpSettings>,
+        #[serde(default)]
+        web_push: Option<WebPushSettings>,
+    },
+    /// Кошельки в сборке без фичи `push`: разбирается, но узел такой синк не поднимает
+    #[cfg(not(feature = "push"))]
+    Wallet {
+        name: String,
+        #[serde(default)]
+        xmtp: Option<toml::Value>,
+        #[serde(default)]
+        web_push: Option<toml::Value>,
+    },
+}
+
+/// HTTP-шлюз XMTP; `token` — ссылка на секрет
+#[cfg(feature = "push")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct XmtpSettings {
+    pub gateway_url: String,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Ключ VAPID (ссылка на секрет) и контакт владельца для push-сервисов
+#[cfg(feature = "push")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebPushSettings {
+    pub vapid_private_key: String,
+    pub subject: String,
+}
+
+impl SinkSpec {
+    pub fn name(&self) -> &str {
+        match self {
+            SinkSpec::Webhook { name, .. }
+            | SinkSpec::PagerDuty { name, .. }
+            | SinkSpec::Opsgenie { name, .. }
+            | SinkSpec::Smtp { name, .. }
+            | SinkSpec::Wallet { name, .. } => name,
+        }
+    }
+}
+
+impl RoutingSection {
+    /// Маршрутизатор с синками из `sinks` (см. `SinkSpec::sink`) и эскалациями,
+    /// пережившими перезапуск, из `store`
+    pub fn router(&self, sinks: Vec<std::sync::Arc<dyn Sink>>, store: Option<SharedStore>) -> Result<AlertRouter, StoreError> {
+        let router = sinks.into_iter().fold(
+            AlertRouter::new(self.routes.clone(), self.tenants.clone()).with_suppressions(self.suppressions.clone()),
+            AlertRouter::with_sink,
+        );
+        match store {
+            Some(store) => router.with_store(store),
+            None => Ok(router),
+        }
+    }
+}
+
+/// Ключ платформы в конфиге — только ссылка на секрет
+#[cfg_attr(not(feature = "secrets"), allow(unused_variables))]
+fn secret_reference(v: &mut ConfigValidator, path: &str, value: &str) {
+    #[cfg(feature = "secrets")]
+    if value.parse::<SecretRef>().is_err() {
+        v.error(path, "must be a secret reference (env:, keystore:, vault:), not a raw key");
+    }
+}
+
+impl Validate for RoutingSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        let mut sinks = std::collections::HashSet::new();
+        for (i, spec) in self.sinks.iter().enumerate() {
+            let path = format!("routing.sinks[{}]", i);
+            if spec.name().trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !sinks.insert(spec.name()) {
+                v.error(&format!("{}.name", path), format!("duplicate sink '{}'", spec.name()));
+            }
+            match spec {
+                SinkSpec::Webhook { url, .. } => v.url(&format!("{}.url", path), url, &["http", "https"]),
+                SinkSpec::PagerDuty { routing_key, url, .. } => {
+                    secret_reference(v, &format!("{}.routing_key", path), routing_key);
+                    if let Some(url) = url {
+                        v.url(&format!("{}.url", path), url, &["https"]);
+                    }
+                }
+                SinkSpec::Opsgenie { api_key, base_url, .. } => {
+                    secret_reference(v, &format!("{}.api_key", path), api_key);
+                    if let Some(base_url) = base_url {
+                        v.url(&format!("{}.base_url", path), base_url, &["https"]);
+                    }
+                }
+                #[cfg(feature = "email")]
+                SinkSpec::Smtp { password, smtp, .. } => {
+                    if let Some(password) = password {
+                        secret_reference(v, &format!("{}.password", path), password);
+                    }
+                    if smtp.username.is_some() != password.is_some() {
+                        v.error(&format!("{}.password", path), "username and password must be set together");
+                    }
+                    if smtp.max_per_window == 0 {
+                        v.error(&format!("{}.smtp.max_per_window", path), "must be positive, alerts would never be emailed");
+                    }
+                    v.positive(&format!("{}.smtp.window_seconds", path), smtp.window_seconds);
+                }
+                #[cfg(not(feature = "email"))]
+                SinkSpec::Smtp { .. } => {}
+                #[cfg(not(feature = "push"))]
+                SinkSpec::Wallet { .. } => {}
+                #[cfg(feature = "push")]
+                SinkSpec::Wallet { xmtp, web_push, .. } => {
+                    if xmtp.is_none() && web_push.is_none() {
+                        v.error(&path, "needs xmtp or web_push");
+                    }
+                    if let Some(xmtp) = xmtp {
+                        v.url(&format!("{}.xmtp.gateway_url", path), &xmtp.gateway_url, &["http", "https"]);
+                        if let Some(token) = &xmtp.token {
+                            secret_reference(v, &format!("{}.xmtp.token", path), token);
+                        }
+                    }
+                    if let Some(web_push) = web_push {
+                        secret_reference(v, &format!("{}.web_push.vapid_private_key", path), &web_push.vapid_private_key);
+                        if !web_push.subject.starts_with("mailto:") && !web_push.subject.starts_with("https://") {
+                            v.error(&format!("{}.web_push.subject", path), "must be a mailto: or https:// contact");
+                        }
+                    }
+                }
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        for (i, route) in self.routes.iter().enumerate() {
+            let path = format!("routing.routes[{}]", i);
+            if route.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(route.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate route '{}'", route.name));
+            }
+            if route.chain.is_empty() {
+                v.error(&format!("{}.chain", path), "must have at least one step");
+            }
+            for tenant in &route.matcher.tenants {
+                if !self.tenants.contains_key(tenant) {
+                    v.error(&format!("{}.match.tenants", path), format!("unknown tenant '{}'", tenant));
+                }
+            }
+            for (j, step) in route.chain.iter().enumerate() {
+                let step_path = format!("{}.chain[{}]", path, j);
+                if step.sinks.is_empty() {
+                    v.error(&format!("{}.sinks", step_path), "must not be empty");
+                }
+                for sink in &step.sinks {
+                    if !sinks.contains(sink.as_str()) {
+                        v.error(&format!("{}.sinks", step_path), format!("unknown sink '{}'", sink));
+                    }
+                }
+                if j > 0 && step.after_seconds < route.chain[j - 1].after_seconds {
+                    v.error(&format!("{}.after_seconds", step_path), "must not be earlier than the previous step");
+                }
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        for (i, suppression) in self.suppressions.iter().enumerate() {
+            let path = format!("routing.suppressions[{}]", i);
+            if suppression.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(suppression.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate suppression '{}'", suppression.name));
+            }
+            for tenant in &suppression.matcher.tenants {
+                if !self.tenants.contains_key(tenant) {
+                    v.error(&format!("{}.match.tenants", path), format!("unknown tenant '{}'", tenant));
+                }
+            }
+        }
+    }
+}
+
+/// Плановые сводки тенантам; синки — из `[routing]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DigestsSection {
+    pub tenants: Vec<DigestTenant>,
+}
+
+impl Validate for DigestsSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        let mut names = std::collections::HashSet::new();
+        for (i, tenant) in self.tenants.iter().enumerate() {
+            let path = format!("digests.tenants[{}]", i);
+            if tenant.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(tenant.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate tenant '{}'", tenant.name));
+            }
+            if tenant.sinks.is_empty() {
+                v.error(&format!("{}.sinks", path), "must not be empty");
+            }
+        }
+    }
+}
+
+fn default_correlation_window() -> u64 {
+    DEFAULT_WINDOW_SECONDS
+}
+
+fn default_correlation_classes() -> usize {
+    2
+}
+
+/// Составные инциденты: алерты разных классов по одному адресу в пределах окна
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorrelationSection {
+    #[serde(default = "default_correlation_window")]
+    pub window_seconds: u64,
+    /// Сколько разных классов сигналов (изменение, уязвимость, атака, потери) нужно для инцидента
+    #[serde(default = "default_correlation_classes")]
+    pub min_classes: usize,
+}
+
+impl CorrelationSection {
+    pub fn correlator(&self) -> Correlator {
+        Correlator::new(self.window_seconds).with_min_classes(self.min_classes)
+    }
+}
+
+impl Validate for CorrelationSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("correlation.window_seconds", self.window_seconds);
+        if !(1..=4).contains(&self.min_classes) {
+            v.error("correlation.min_classes", format!("{} is not between 1 and 4", self.min_classes));
+        }
+    }
+}
+
+#[cfg(all(feature = "mev", feature = "audit"))]
+fn default_screening_interval() -> u64 {
+    6 * 3600
+}
+
+/// Блоклисты для скрининга адресов алертов (снимки OFAC SDN, внутренние списки)
+#[cfg(all(feature = "mev", feature = "audit"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScreeningSection {
+    #[serde(default = "default_screening_interval")]
+    pub refresh_interval_seconds: u64,
+    pub lists: Vec<ListSpec>,
+}
+
+#[cfg(all(feature = "mev", feature = "audit"))]
+impl ScreeningSection {
+    pub fn enricher(&self, audit: Option<std::sync::Arc<crate::audit::AuditLog>>) -> ScreeningEnricher {
+        match audit {
+            Some(audit) => ScreeningEnricher::new().with_audit(audit),
+            None => ScreeningEnricher::new(),
+        }
+    }
+}
+
+#[cfg(all(feature = "mev", feature = "audit"))]
+impl Validate for ScreeningSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("screening.refresh_interval_seconds", self.refresh_interval_seconds);
+        let mut names = std::collections::HashSet::new();
+        for (i, list) in self.lists.iter().enumerate() {
+            let path = format!("screening.lists[{}]", i);
+            if list.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(list.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate list '{}'", list.name));
+            }
+            if let ListSource::Url(url) = &list.source {
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    v.error(&format!("{}.source", path), "url must be http(s)");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "staking")]
+fn default_signer_timeout() -> u64 {
+    10
+}
+
+/// Удалённый подписант BLS-ключей валидаторов (Web3Signer)
+#[cfg(feature = "staking")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Web3SignerSection {
+    pub url: String,
+    #[serde(default = "default_signer_timeout")]
+    pub timeout_seconds: u64,
+    /// Ключи, которые должны быть загружены в подписант; проверяются диагностикой
+    #[serde(default)]
+    pub public_keys: Vec<String>,
+}
+
+#[cfg(feature = "staking")]
+impl Validate for Web3SignerSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if !self.url.starts_with("https://") && !self.url.starts_with("http://") {
+            v.error("web3signer.url", "must be an http(s) URL");
+        }
+        v.positive("web3signer.timeout_seconds", self.timeout_seconds);
+        for (i, key) in self.public_keys.iter().enumerate() {
+            if !is_bls_public_key(key) {
+                v.error(&format!("web3signer.public_keys[{}]", i), "must be a 0x-prefixed 48-byte BLS public key");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+fn default_congestion_interval() -> u64 {
+    12
+}
+
+/// Режимы загрузки сети и операции, которые в них нельзя оставлять в пуле
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CongestionSection {
+    #[serde(default = "default_congestion_interval")]
+    pub interval_seconds: u64,
+    /// От лёгкого к тяжёлому
+    pub regimes: Vec<Regime>,
+    #[serde(default)]
+    pub watches: Vec<CongestionWatch>,
+}
+
+#[cfg(feature = "mev")]
+impl CongestionSection {
+    pub fn monitor(&self, bus: std::sync::Arc<AlertBus>) -> Result<CongestionMonitor, CongestionError> {
+        CongestionMonitor::new(self.regimes.clone(), self.watches.clone(), bus)
+    }
+}
+
+#[cfg(feature = "mev")]
+impl Validate for CongestionSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("congestion.interval_seconds", self.interval_seconds);
+        let mut names = std::collections::HashSet::new();
+        for (i, regime) in self.regimes.iter().enumerate() {
+            let path = format!("congestion.regimes[{}]", i);
+            if regime.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(regime.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate regime '{}'", regime.name));
+            }
+            if regime.min_base_fee_gwei.is_none() && regime.min_blob_fee_gwei.is_none() && regime.min_pending.is_none() {
+                v.error(&path, "needs min_base_fee_gwei, min_blob_fee_gwei or min_pending");
+            }
+        }
+        for (i, watch) in self.watches.iter().enumerate() {
+            let path = format!("congestion.watches[{}]", i);
+            if watch.to.is_empty() {
+                v.error(&format!("{}.to", path), "must not be empty");
+            }
+            for (j, to) in watch.to.iter().enumerate() {
+                v.address(&format!("{}.to[{}]", path, j), to);
+            }
+            for (j, selector) in watch.selectors.iter().enumerate() {
+                if parse_selector(selector).is_err() {
+                    v.error(&format!("{}.selectors[{}]", path, j), "must be a 4-byte hex selector");
+                }
+            }
+            if !names.contains(watch.regime.as_str()) {
+                v.error(&format!("{}.regime", path), format!("unknown regime '{}'", watch.regime));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+fn default_forensics_blocks() -> u64 {
+    3
+}
+
+/// Известная переменная хранилища для state diff: `mapping = true` — `mapping(address => ...)`
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageSlot {
+    pub contract: String,
+    pub slot: u64,
+    pub name: String,
+    #[serde(default)]
+    pub mapping: bool,
+}
+
+/// Сверка алертов мемпула с добытыми блоками и state diff подтверждённых атак
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForensicsSection {
+    #[serde(default = "default_congestion_interval")]
+    pub interval_seconds: u64,
+    /// Сколько блоков алерт ждёт включения жертвы
+    #[serde(default = "default_forensics_blocks")]
+    pub blocks: u64,
+    /// Снимать state diff атакующих транзакций через `debug_traceTransaction`
+    #[serde(default)]
+    pub state_diff: bool,
+    #[serde(default)]
+    pub layouts: Vec<StorageSlot>,
+}
+
+#[cfg(feature = "mev")]
+impl ForensicsSection {
+    pub fn layouts(&self) -> StorageLayouts {
+        self.layouts.iter().fold(StorageLayouts::new(), |layouts, slot| {
+            let contract = slot.contract.parse().unwrap_or_default();
+            match slot.mapping {
+                true => layouts.with_address_mapping(contract, slot.slot, &slot.name),
+                false => layouts.with_value(contract, slot.slot, &slot.name),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "mev")]
+impl Validate for ForensicsSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("forensics.interval_seconds", self.interval_seconds);
+        v.positive("forensics.blocks", self.blocks);
+        for (i, slot) in self.layouts.iter().enumerate() {
+            v.address(&format!("forensics.layouts[{}].contract", i), &slot.contract);
+            if slot.name.trim().is_empty() {
+                v.error(&format!("forensics.layouts[{}].name", i), "must not be empty");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mev")]
+fn default_bridges_interval() -> u64 {
+    60
+}
+
+/// HTTP RPC сети моста
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BridgeChain {
+    pub chain_id: u64,
+    pub http_url: String,
+}
+
+/// Маршрут Circle CCTP: домены и `MessageTransmitter` обеих сторон
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CctpRoute {
+    pub name: String,
+    pub source_chain: u64,
+    pub source_domain: u32,
+    pub source_transmitter: String,
+    pub dest_chain: u64,
+    pub dest_domain: u32,
+    pub dest_transmitter: String,
+    /// Срок доставки; по умолчанию 45 минут
+    #[serde(default)]
+    pub window_minutes: Option<u64>,
+}
+
+#[cfg(feature = "mev")]
+fn default_backfill_interval_ms() -> u64 {
+    1000
+}
+
+#[cfg(feature = "mev")]
+fn default_backfill_batch_size() -> usize {
+    20
+}
+
+#[cfg(feature = "mev")]
+fn default_young_contract_hours() -> f64 {
+    24.0
+}
+
+#[cfg(feature = "mev")]
+fn default_permit2_reminder_hours() -> u64 {
+    72
+}
+
+/// Разрешения Permit2 кошельков; курсор индекса событий хранится в `[monitor]`
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Permit2Section {
+    pub wallets: Vec<String>,
+    /// За сколько часов до истечения напоминать о разрешении
+    #[serde(default = "default_permit2_reminder_hours")]
+    pub reminder_hours: u64,
+}
+
+#[cfg(feature = "mev")]
+impl Validate for Permit2Section {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if self.wallets.is_empty() {
+            v.error("permit2.wallets", "must not be empty");
+        }
+        for (i, wallet) in self.wallets.iter().enumerate() {
+            v.address(&format!("permit2.wallets[{}]", i), wallet);
+        }
+        v.positive("permit2.reminder_hours", self.reminder_hours);
+    }
+}
+
+/// Фоновые заполнения по `monitor.watched_contracts`; прогресс хранится в `[monitor]`
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackfillSection {
+    /// Пауза между порциями одной задачи
+    #[serde(default = "default_backfill_interval_ms")]
+    pub interval_ms: u64,
+    /// Адресов в порции
+    #[serde(default = "default_backfill_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Обратные ENS-имена
+    #[serde(default)]
+    pub labels: bool,
+    /// Блок создания и деплоер; нужен архивный узел с trace API
+    #[serde(default)]
+    pub contract_age: bool,
+    /// Контракт моложе считается рискованным при обогащении
+    #[serde(default = "default_young_contract_hours")]
+    pub young_contract_hours: f64,
+}
+
+#[cfg(feature = "mev")]
+impl Validate for BackfillSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("backfill.interval_ms", self.interval_ms);
+        v.positive("backfill.batch_size", self.batch_size as u64);
+        v.range("backfill.young_contract_hours", self.young_contract_hours, 0.0, 24.0 * 365.0);
+        if !self.labels && !self.contract_age {
+            v.error("backfill", "enable labels or contract_age");
+        }
+    }
+}
+
+/// Переводы отслеживаемых адресов через мосты (`[bridges]`)
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BridgesSection {
+    #[serde(default = "default_bridges_interval")]
+    pub interval_seconds: u64,
+    /// Отправители, чьи переводы отслеживаются
+    pub watched: Vec<String>,
+    /// RPC сетей маршрутов; сеть узла (`rpc`) указывать не нужно
+    #[serde(default)]
+    pub chains: Vec<BridgeChain>,
+    #[serde(default)]
+    pub cctp: Vec<CctpRoute>,
+}
+
+#[cfg(feature = "mev")]
+impl BridgesSection {
+    /// `providers` — по сети, вместе с сетью узла
+    pub fn tracker<M: ethers::providers::Middleware + 'static>(
+        &self,
+        store: std::sync::Arc<dyn crate::store::Store>,
+        providers: std::collections::HashMap<u64, std::sync::Arc<M>>,
+    ) -> Result<BridgeTracker<M>, BridgeError> {
+        let address = |value: &str| value.parse::<Address>().map_err(|_| BridgeError::InvalidAddress(value.to_string()));
+        let watched = self.watched.iter().map(|a| address(a)).collect::<Result<Vec<_>, _>>()?;
+        let mut tracker = BridgeTracker::new(store, watched, providers);
+        for route in &self.cctp {
+            let mut cctp = Cctp::new(
+                &route.name,
+                (route.source_chain, route.source_domain, address(&route.source_transmitter)?),
+                (route.dest_chain, route.dest_domain, address(&route.dest_transmitter)?),
+            );
+            if let Some(minutes) = route.window_minutes {
+                cctp = cctp.with_window(std::time::Duration::from_secs(minutes * 60));
+            }
+            tracker = tracker.with_adapter(std::sync::Arc::new(cctp))?;
+        }
+        Ok(tracker)
+    }
+
+    /// Сети, для которых есть RPC: из `chains` и сеть узла
+    fn has_chain(&self, rpc: &RpcConfig, chain_id: u64) -> bool {
+        chain_id == rpc.chain_id || self.chains.iter().any(|c| c.chain_id == chain_id)
+    }
+}
+
+#[cfg(feature = "mev")]
+impl Validate for BridgesSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        v.positive("bridges.interval_seconds", self.interval_seconds);
+        if self.watched.is_empty() {
+            v.error("bridges.watched", "must not be empty");
+        }
+        for (i, address) in self.watched.iter().enumerate() {
+            v.address(&format!("bridges.watched[{}]", i), address);
+        }
+        for (i, chain) in self.chains.iter().enumerate() {
+            v.chain_id(&format!("bridges.chains[{}].chain_id", i), chain.chain_id);
+            v.url(&format!("bridges.chains[{}].http_url", i), &chain.http_url, &["http", "https"]);
+        }
+        let mut names = std::collections::HashSet::new();
+        for (i, route) in self.cctp.iter().enumerate() {
+            let path = format!("bridges.cctp[{}]", i);
+            if route.name.trim().is_empty() {
+                v.error(&format!("{}.name", path), "must not be empty");
+            } else if !names.insert(route.name.as_str()) {
+                v.error(&format!("{}.name", path), format!("duplicate route '{}'", route.name));
+            }
+            v.address(&format!("{}.source_transmitter", path), &route.source_transmitter);
+            v.address(&format!("{}.dest_transmitter", path), &route.dest_transmitter);
+            if route.window_minutes == Some(0) {
+                v.error(&format!("{}.window_minutes", path), "must be positive");
+            }
+        }
+    }
+}
+
+/// Солвер и его адреса: расчёт ордера на них — сделка солвера с самим собой
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SolverAffiliates {
+    pub solver: String,
+    pub addresses: Vec<String>,
+}
+
+/// Исполнение ордеров CoW Protocol и UniswapX (`[intents]`); без адресов и API — mainnet
+#[cfg(feature = "mev")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntentsSection {
+    #[serde(default)]
+    pub cow_settlement: Option<String>,
+    #[serde(default)]
+    pub uniswapx_reactors: Option<Vec<String>>,
+    #[serde(default)]
+    pub cow_api_url: Option<String>,
+    #[serde(default)]
+    pub uniswapx_api_url: Option<String>,
+    /// Исполнение хуже котировки больше чем на столько б.п. — алерт
+    #[serde(default)]
+    pub max_shortfall_bps: Option<Bps>,
+    #[serde(default)]
+    pub solvers: Vec<SolverAffiliates>,
+}
+
+#[cfg(feature = "mev")]
+impl IntentsSection {
+    /// Адреса уже проверены `validate`; непарсящиеся пропускаются
+    pub fn intent_config(&self) -> IntentConfig {
+        let defaults = IntentConfig::default();
+        IntentConfig {
+            cow_settlement: self.cow_settlement.as_deref().and_then(|a| a.parse().ok()).unwrap_or(defaults.cow_settlement),
+            uniswapx_reactors: match &self.uniswapx_reactors {
+                Some(reactors) => reactors.iter().filter_map(|a| a.parse().ok()).collect(),
+                None => defaults.uniswapx_reactors,
+            },
+            cow_api_url: self.cow_api_url.clone().unwrap_or(defaults.cow_api_url),
+            uniswapx_api_url: self.uniswapx_api_url.clone().unwrap_or(defaults.uniswapx_api_url),
+            max_shortfall_bps: self.max_shortfall_bps.unwrap_or(defaults.max_shortfall_bps),
+        }
+    }
+
+    /// Пары солвер -> связанный адрес для `IntentMonitor::with_affiliate`
+    pub fn affiliates(&self) -> Vec<(Address, Address)> {
+        self.solvers
+            .iter()
+            .filter_map(|s| Some((s.solver.parse().ok()?, &s.addresses)))
+            .flat_map(|(solver, addresses)| addresses.iter().filter_map(move |a| Some((solver, a.parse().ok()?))))
+            .collect()
+    }
+
+    /// Без явных адресов и API секция годится только для mainnet
+    fn uses_mainnet_defaults(&self) -> bool {
+        self.cow_settlement.is_none() || self.uniswapx_reactors.is_none() || self.cow_api_url.is_none() || self.uniswapx_api_url.is_none()
+    }
+}
+
+#[cfg(feature = "mev")]
+impl Validate for IntentsSection {
+    fn validate(&self, v: &mut ConfigValidator) {
+        if let Some(settlement) = &self.cow_settlement {
+            v.address("intents.cow_settlement", settlement);
+        }
+        for (i, reactor) in self.uniswapx_reactors.iter().flatten().enumerate() {
+            v.address(&format!("intents.uniswapx_reactors[{}]", i), reactor);
+        }
+        if let Some(url) = &self.cow_api_url {
+            v.url("intents.cow_api_url", url, &["http", "https"]);
+        }
+        if let Some(url) = &self.uniswapx_api_url {
+            v.url("intents.uniswapx_api_url", url, &["http", "https"]);
+        }
+        for (i, solver) in self.solvers.iter().enumerate() {
+            v.address(&format!("intents.solvers[{}].solver", i), &solver.solver);
+            if solver.addresses.is_empty() {
+                v.error(&format!("intents.solvers[{}].addresses", i), "must not be empty");
+            }
+            for (j, address) in solver.addresses.iter().enumerate() {
+                v.address(&format!("intents.solvers[{}].addresses[{}]", i, j), address);
+            }
+        }
+    }
+}
+
+/// Секция фичи, без которой собран крейт: конфиг другой сборки разбирается, секция не используется
+#[allow(dead_code)]
+type DisabledSection = Option<toml::Value>;
+
+/// Корневой конфиг `definetly.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefinetlyConfig {
+    /// Только мониторинг: узел не получает права подписи, даже если собран с ним
+    #[serde(default)]
+    pub read_only: bool,
+    pub rpc: RpcConfig,
+    #[cfg(feature = "mev")]
+    pub detector: DetectorConfig,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    detector: DisabledSection,
+    #[serde(default)]
+    pub monitor: Option<MonitorConfig>,
+    #[cfg(feature = "staking")]
+    #[serde(default)]
+    pub restaking: Option<RestakingSection>,
+    #[cfg(not(feature = "staking"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    restaking: DisabledSection,
+    #[serde(default)]
+    pub leader: Option<LeaderSection>,
+    #[cfg(feature = "server")]
+    #[serde(default)]
+    pub admin: Option<AdminSection>,
+    #[cfg(not(feature = "server"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    admin: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub ingestion: Option<IngestionSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    ingestion: DisabledSection,
+    #[serde(default)]
+    pub i18n: Option<I18nSection>,
+    #[serde(default)]
+    pub dashboard: Option<DashboardSection>,
+    #[cfg(feature = "secrets")]
+    #[serde(default)]
+    pub encryption: Option<EncryptionSection>,
+    #[cfg(not(feature = "secrets"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    encryption: DisabledSection,
+    #[serde(default)]
+    pub retention: Option<RetentionSection>,
+    #[serde(default)]
+    pub routing: Option<RoutingSection>,
+    #[serde(default)]
+    pub digests: Option<DigestsSection>,
+    #[serde(default)]
+    pub correlation: Option<CorrelationSection>,
+    #[cfg(all(feature = "mev", feature = "audit"))]
+    #[serde(default)]
+    pub screening: Option<ScreeningSection>,
+    #[cfg(not(all(feature = "mev", feature = "audit")))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    screening: DisabledSection,
+    #[cfg(feature = "staking")]
+    #[serde(default)]
+    pub web3signer: Option<Web3SignerSection>,
+    #[cfg(not(feature = "staking"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    web3signer: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub congestion: Option<CongestionSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    congestion: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub forensics: Option<ForensicsSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    forensics: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub permit2: Option<Permit2Section>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    permit2: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub bridges: Option<BridgesSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    bridges: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub backfill: Option<BackfillSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    backfill: DisabledSection,
+    #[cfg(feature = "mev")]
+    #[serde(default)]
+    pub intents: Option<IntentsSection>,
+    #[cfg(not(feature = "mev"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    intents: DisabledSection,
+    /// Политики подписи по кошелькам; кошелёк без политики подписывать нельзя
+    #[cfg(feature = "staking")]
+    #[serde(default)]
+    pub policies: Vec<WalletPolicy>,
+    #[cfg(not(feature = "staking"))]
+    #[serde(default, skip_serializing)]
+    #[allow(dead_code)]
+    policies: DisabledSection,
+}
+
+impl Validate for DefinetlyConfig {
+    fn validate(&self, v: &mut ConfigValidator) {
+        self.rpc.validate(v);
+        #[cfg(feature = "mev")]
+        self.detector.validate(v);
+        if let Some(monitor) = &self.monitor {
+            monitor.validate(v);
+        }
+        #[cfg(feature = "staking")]
+        if let Some(restaking) = &self.restaking {
+            restaking.validate(v);
+            if self.read_only {
+                v.error("restaking", "read-only node cannot sign restaking transactions");
+            }
+        }
+        if let Some(leader) = &self.leader {
+            leader.validate(v);
+        }
+        #[cfg(feature = "server")]
+        if let Some(admin) = &self.admin {
+            admin.validate(v);
+        }
+        #[cfg(feature = "mev")]
+        if let Some(ingestion) = &self.ingestion {
+            ingestion.validate(v);
+        }
+        if let Some(i18n) = &self.i18n {
+            i18n.validate(v);
+        }
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.validate(v);
+        }
+        #[cfg(feature = "secrets")]
+        if let Some(encryption) = &self.encryption {
+            encryption.validate(v);
+            if self.monitor.is_none() {
+                v.error("encryption", "needs [monitor] store_path to encrypt stored alerts");
+            }
+        }
+        if let Some(retention) = &self.retention {
+            retention.validate(v);
+            if self.monitor.is_none() && retention.rules.iter().any(|rule| matches!(rule.target, RetentionTarget::Namespace(_))) {
+                v.error("retention.rules", "namespace targets need [monitor] store_path");
+            }
+        }
+        if let Some(routing) = &self.routing {
+            routing.validate(v);
+            #[cfg(feature = "push")]
+            if self.monitor.is_none() && routing.sinks.iter().any(|s| matches!(s, SinkSpec::Wallet { .. })) {
+                v.error("routing.sinks", "wallet sinks need [monitor] store_path to keep subscriptions");
+            }
+        }
+        if let Some(digests) = &self.digests {
+            digests.validate(v);
+            let sinks: Vec<&str> = self.routing.iter().flat_map(|r| r.sinks.iter().map(SinkSpec::name)).collect();
+            for (i, tenant) in digests.tenants.iter().enumerate() {
+                for sink in tenant.sinks.iter().filter(|s| !sinks.contains(&s.as_str())) {
+                    v.error(&format!("digests.tenants[{}].sinks", i), format!("unknown sink '{}' in routing.sinks", sink));
+                }
+            }
+        }
+        if let Some(correlation) = &self.correlation {
+            correlation.validate(v);
+        }
+        #[cfg(all(feature = "mev", feature = "audit"))]
+        if let Some(screening) = &self.screening {
+            screening.validate(v);
+        }
+        #[cfg(feature = "staking")]
+        if let Some(web3signer) = &self.web3signer {
+            web3signer.validate(v);
+        }
+        #[cfg(feature = "mev")]
+        if let Some(congestion) = &self.congestion {
+            congestion.validate(v);
+        }
+        #[cfg(feature = "mev")]
+        if let Some(forensics) = &self.forensics {
+            forensics.validate(v);
+        }
+        #[cfg(feature = "mev")]
+        if let Some(permit2) = &self.permit2 {
+            permit2.validate(v);
+            if self.monitor.is_none() {
+                v.error("permit2", "needs [monitor] store_path to keep the event cursor");
+            }
+        }
+        #[cfg(feature = "mev")]
+        if let Some(bridges) = &self.bridges {
+            bridges.validate(v);
+            for (i, route) in bridges.cctp.iter().enumerate() {
+                for (field, chain_id) in [("source_chain", route.source_chain), ("dest_chain", route.dest_chain)] {
+                    if !bridges.has_chain(&self.rpc, chain_id) {
+                        v.error(&format!("bridges.cctp[{}].{}", i, field), format!("no RPC for chain {} in bridges.chains", chain_id));
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "mev")]
+        if let Some(backfill) = &self.backfill {
+            backfill.validate(v);
+            if self.monitor.is_none() {
+                v.error("backfill", "needs [monitor] store_path to keep progress");
+            }
+        }
+        #[cfg(feature = "mev")]
+        if let Some(intents) = &self.intents {
+            intents.validate(v);
+            if self.rpc.chain_id != 1 && intents.uses_mainnet_defaults() {
+                v.error("intents", format!("mainnet contracts and APIs by default; set them for chain {}", self.rpc.chain_id));
+            }
+        }
+        #[cfg(feature = "staking")]
+        for policy in &self.policies {
+            policy.validate(v);
+        }
+    }
+}
+
+impl DefinetlyConfig {
+    /// Разбирает TOML и проверяет все секции; ошибки разбора тоже попадают в список
+    pub fn parse(source: &str) -> Result<Self, ConfigErrors> {
+        let config: Self = toml::from_str(source).map_err(|e| {
+            ConfigErrors(vec![ConfigIssue {
+                path: "<parse>".into(),
+                message: e.to_string().trim().to_string(),
+            }])
+        })?;
+
+        let mut v = ConfigValidator::new();
+        config.validate(&mut v);
+        v.finish()?;
+        Ok(config)
+    }
+
+    /// Права узла: подпись только в сборке с `signing` и без `read_only`
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.read_only)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigErrors> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            ConfigErrors(vec![ConfigIssue {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            }])
+        })?;
+        Self::parse(&source)
+    }
+}
+
+#[cfg(all(test, feature = "mev"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_all_issues() {
+        let source = r#"
+            [rpc]
+            http_url = "ftp://node"
+            chain_id = 999
+
+            [detector]
+            pending_ttl_seconds = 0
+            min_profit_eth = 0.01
+            max_gas_price_gwei = 500.0
+
+            [monitor]
+            store_path = "/var/lib/definetly"
+            watched_contracts = ["0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "0xc02AAa39b223FE8D0A0e5C4F27eAD9083C756Cc2"]
+        "#;
+
+        let errors = DefinetlyConfig::parse(source).unwrap_err();
+        let paths: Vec<&str> = errors.0.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["rpc.http_url", "rpc.chain_id", "detector.pending_ttl_seconds", "monitor.watched_contracts[1]"]
+        );
+    }
+
+    #[cfg(all(not(feature = "staking"), not(feature = "email")))]
+    #[test]
+    fn test_accepts_sections_of_other_builds() {
+        let source = r#"
+            [rpc]
+            http_url = "https://node"
+            chain_id = 1
+
+            [detector]
+            pending_ttl_seconds = 60
+            min_profit_eth = 0.01
+            max_gas_price_gwei = 500.0
+
+            [restaking]
+            operator = "0x00"
+
+            [routing]
+            routes = []
+
+            [[routing.sinks]]
+            type = "smtp"
+            name = "ops"
+            smtp = { host = "smtp.example.com" }
+        "#;
+
+        let config = DefinetlyConfig::parse(source).unwrap();
+        assert_eq!(config.routing.unwrap().sinks[0].name(), "ops");
+    }
+}
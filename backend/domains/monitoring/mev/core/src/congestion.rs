@@ -0,0 +1,413 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::compat::{H256, U256};
+use crate::enrichment::{Enricher, Enrichment};
+use crate::ingest::TxSink;
+use crate::shutdown::ShutdownSignal;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use crate::units::Gwei;
+use ethers::providers::Middleware;
+use ethers::types::BlockNumber;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Сколько отслеживаемых транзакций из пула помнить для проверки при смене режима
+const MAX_QUEUED: usize = 1_024;
+
+/// Дольше транзакция без хэша считается ушедшей из пула
+const QUEUED_TTL_SECS: u64 = 1_800;
+
+#[derive(Debug, Error)]
+pub enum CongestionError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Unknown regime '{0}'")]
+    UnknownRegime(String),
+
+    #[error("Invalid selector '{0}'")]
+    InvalidSelector(String),
+}
+
+fn default_regime_level() -> AlertLevel {
+    AlertLevel::Medium
+}
+
+fn default_watch_level() -> AlertLevel {
+    AlertLevel::High
+}
+
+/// Режим загрузки сети; наступает, когда выполнено любое из заданных условий
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Regime {
+    pub name: String,
+    #[serde(default)]
+    pub min_base_fee_gwei: Option<Gwei>,
+    #[serde(default)]
+    pub min_blob_fee_gwei: Option<Gwei>,
+    /// Ожидающих транзакций в пуле узла
+    #[serde(default)]
+    pub min_pending: Option<u64>,
+    /// Уровень алерта о входе в режим
+    #[serde(default = "default_regime_level")]
+    pub level: AlertLevel,
+}
+
+impl Regime {
+    fn matches(&self, snapshot: &CongestionSnapshot) -> bool {
+        let base_fee = self.min_base_fee_gwei.is_some_and(|min| snapshot.base_fee_gwei >= min);
+        let blob_fee = matches!((self.min_blob_fee_gwei, snapshot.blob_fee_gwei), (Some(min), Some(fee)) if fee >= min);
+        let pending = matches!((self.min_pending, snapshot.pending), (Some(min), Some(depth)) if depth >= min);
+        base_fee || blob_fee || pending
+    }
+}
+
+/// Операция, которую дорого отправлять в загруженную сеть, например рестейкинг
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CongestionWatch {
+    pub name: String,
+    /// Контракты операции
+    pub to: Vec<String>,
+    /// Селекторы `0x12345678`; пусто — любой вызов
+    #[serde(default)]
+    pub selectors: Vec<String>,
+    /// Алерт, если транзакция ждёт в этом режиме или тяжелее
+    pub regime: String,
+    #[serde(default = "default_watch_level")]
+    pub level: AlertLevel,
+}
+
+/// Загрузка сети на последнем блоке
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CongestionSnapshot {
+    pub block: u64,
+    pub base_fee_gwei: Gwei,
+    /// `None` до Dencun и у сетей без блобов
+    pub blob_fee_gwei: Option<Gwei>,
+    /// `None`, если провайдер закрыл `txpool_status`
+    pub pending: Option<u64>,
+    pub regime: Option<String>,
+}
+
+/// Тяжелейший подходящий режим; режимы перечислены от лёгкого к тяжёлому
+pub fn classify(regimes: &[Regime], snapshot: &CongestionSnapshot) -> Option<usize> {
+    regimes.iter().rposition(|r| r.matches(snapshot))
+}
+
+pub(crate) fn parse_selector(s: &str) -> Result<[u8; 4], CongestionError> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    if digits.len() != 8 {
+        return Err(CongestionError::InvalidSelector(s.to_string()));
+    }
+    let mut out = [0u8; 4];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16)
+            .map_err(|_| CongestionError::InvalidSelector(s.to_string()))?;
+    }
+    Ok(out)
+}
+
+struct Watch {
+    config: CongestionWatch,
+    to: HashSet<String>,
+    selectors: Vec<[u8; 4]>,
+    regime: usize,
+}
+
+impl Watch {
+    fn matches(&self, tx: &Tx) -> bool {
+        self.to.contains(&tx.to.to_string().to_lowercase())
+            && (self.selectors.is_empty() || tx.input.get(..4).is_some_and(|s| self.selectors.iter().any(|w| w == s)))
+    }
+}
+
+struct Current {
+    snapshot: CongestionSnapshot,
+    regime: Option<usize>,
+}
+
+/// Отслеживаемая транзакция, замеченная в пуле
+struct Queued {
+    hash: Option<H256>,
+    tx: Tx,
+    seen_at: u64,
+}
+
+/// Следит за base fee, ценой блобов и глубиной пула. Алертит на входе в более тяжёлый
+/// режим и на отслеживаемые операции, ожидающие в нём; режим попадает в обогащение
+/// всех алертов детектора как `enrichment.network.*`
+pub struct CongestionMonitor {
+    regimes: Vec<Regime>,
+    watches: Vec<Watch>,
+    bus: Arc<AlertBus>,
+    current: RwLock<Option<Current>>,
+    /// `(операция, отправитель)`, о которых уже сообщили в текущем режиме
+    notified: Mutex<HashSet<(usize, String)>>,
+    /// Отслеживаемые транзакции, которые могут ещё ждать в пуле, когда режим сменится
+    queued: Mutex<VecDeque<Queued>>,
+    /// Неудачные замеры и проверки включения в `run`
+    errors: TaskErrors,
+}
+
+impl CongestionMonitor {
+    pub fn new(regimes: Vec<Regime>, watches: Vec<CongestionWatch>, bus: Arc<AlertBus>) -> Result<Self, CongestionError> {
+        let watches = watches
+            .into_iter()
+            .map(|config| {
+                let regime = regimes
+                    .iter()
+                    .position(|r| r.name == config.regime)
+                    .ok_or_else(|| CongestionError::UnknownRegime(config.regime.clone()))?;
+                let selectors = config.selectors.iter().map(|s| parse_selector(s)).collect::<Result<_, _>>()?;
+                let to = config.to.iter().map(|a| a.to_lowercase()).collect();
+                Ok(Watch { config, to, selectors, regime })
+            })
+            .collect::<Result<_, CongestionError>>()?;
+        Ok(Self {
+            regimes,
+            watches,
+            bus,
+            current: RwLock::new(None),
+            notified: Mutex::new(HashSet::new()),
+            queued: Mutex::new(VecDeque::new()),
+            errors: TaskErrors::default(),
+        })
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn snapshot(&self) -> Option<CongestionSnapshot> {
+        self.current.read().unwrap().as_ref().map(|c| c.snapshot.clone())
+    }
+
+    /// Запоминает замер и возвращает алерт, если сеть перешла в более тяжёлый режим
+    pub fn observe(&self, mut snapshot: CongestionSnapshot) -> Option<BusAlert> {
+        let regime = classify(&self.regimes, &snapshot);
+        snapshot.regime = regime.map(|i| self.regimes[i].name.clone());
+        let replaced = self.current.write().unwrap().replace(Current { snapshot: snapshot.clone(), regime });
+        let previous = replaced.and_then(|c| c.regime);
+        if previous == regime {
+            return None;
+        }
+        // Новый режим — о зависших операциях сообщаем заново
+        self.notified.lock().unwrap().clear();
+        let entered = regime.filter(|r| previous.is_none_or(|p| *r > p))?;
+        let config = &self.regimes[entered];
+        let title = format!("Network entered {} regime: base fee {} gwei", config.name, snapshot.base_fee_gwei);
+        Some(
+            BusAlert::new("congestion", "regime", config.level, config.name.clone(), title)
+                .with_payload(json!({ "regime": config.name, "snapshot": snapshot })),
+        )
+    }
+
+    /// Алерты по отслеживаемым операциям, которые ждут в пуле в тяжёлом режиме.
+    /// Транзакция запоминается, чтобы `recheck` сообщил о ней, если режим наступит позже
+    pub fn check(&self, hash: Option<H256>, tx: &Tx) -> Vec<BusAlert> {
+        self.check_at(hash, tx, now())
+    }
+
+    fn check_at(&self, hash: Option<H256>, tx: &Tx, now: u64) -> Vec<BusAlert> {
+        if self.watches.iter().any(|watch| watch.matches(tx)) {
+            let mut queued = self.queued.lock().unwrap();
+            if queued.len() >= MAX_QUEUED {
+                queued.pop_front();
+            }
+            queued.push_back(Queued { hash, tx: tx.clone(), seen_at: now });
+        }
+        self.alerts(hash, tx)
+    }
+
+    /// Алерты по запомненным транзакциям после смены режима; добытые и устаревшие забываются.
+    /// `mined` — попала ли транзакция с хэшем в блок
+    pub fn recheck(&self, now: u64, mined: impl Fn(H256) -> bool) -> Vec<BusAlert> {
+        let waiting: Vec<(Option<H256>, Tx)> = {
+            let mut queued = self.queued.lock().unwrap();
+            queued.retain(|q| match q.hash {
+                Some(hash) => !mined(hash),
+                None => now.saturating_sub(q.seen_at) <= QUEUED_TTL_SECS,
+            });
+            queued.iter().map(|q| (q.hash, q.tx.clone())).collect()
+        };
+        waiting.iter().flat_map(|(hash, tx)| self.alerts(*hash, tx)).collect()
+    }
+
+    fn alerts(&self, hash: Option<H256>, tx: &Tx) -> Vec<BusAlert> {
+        let current = self.current.read().unwrap();
+        let Some(Current { snapshot, regime: Some(regime) }) = current.as_ref() else {
+            return Vec::new();
+        };
+        let mut notified = self.notified.lock().unwrap();
+        self.watches
+            .iter()
+            .enumerate()
+            .filter(|(_, watch)| *regime >= watch.regime && watch.matches(tx))
+            .filter(|(i, _)| notified.insert((*i, tx.from.to_string())))
+            .map(|(_, watch)| {
+                let regime = &self.regimes[*regime].name;
+                let title =
+                    format!("{} tx queued during {} regime ({} gwei base fee)", watch.config.name, regime, snapshot.base_fee_gwei);
+                BusAlert::new("congestion", "watched_operation", watch.config.level, tx.from.to_string(), title)
+                    .with_payload(json!({ "watch": watch.config.name, "regime": regime, "tx": tx, "hash": hash, "snapshot": snapshot }))
+            })
+            .collect()
+    }
+
+    /// Транзакции сначала проверяются по отслеживаемым операциям, затем уходят в `inner`
+    pub fn watching<S: TxSink>(self: &Arc<Self>, inner: S) -> CongestionWatcher<S> {
+        CongestionWatcher { monitor: self.clone(), inner }
+    }
+
+    /// Замер по последнему блоку; цена блобов и пул необязательны
+    pub async fn sample<M: Middleware>(provider: &M) -> Result<CongestionSnapshot, CongestionError> {
+        let block = provider
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(|e| CongestionError::ProviderError(e.to_string()))?
+            .ok_or_else(|| CongestionError::ProviderError("latest block not found".into()))?;
+        let blob_fee = provider.provider().request::<_, U256>("eth_blobBaseFee", ()).await.ok();
+        let pending = provider.txpool_status().await.ok().map(|status| status.pending.as_u64());
+        Ok(CongestionSnapshot {
+            block: block.number.map(|n| n.as_u64()).unwrap_or_default(),
+            base_fee_gwei: Gwei::from_wei(block.base_fee_per_gas.unwrap_or_default()),
+            blob_fee_gwei: blob_fee.map(Gwei::from_wei),
+            pending,
+            regime: None,
+        })
+    }
+
+    pub async fn run<M: Middleware>(self: Arc<Self>, provider: Arc<M>, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            let Some(snapshot) = self.errors.check(Self::sample(provider.as_ref()).await) else {
+                continue;
+            };
+            let before = self.snapshot().and_then(|s| s.regime);
+            if let Some(alert) = self.observe(snapshot) {
+                self.bus.publish(alert);
+            }
+            if self.snapshot().and_then(|s| s.regime) != before {
+                for alert in self.recheck_pool(provider.as_ref()).await {
+                    self.bus.publish(alert);
+                }
+            }
+        }
+    }
+
+    /// `recheck` с проверкой включения по квитанциям; ошибка провайдера оставляет транзакцию
+    async fn recheck_pool<M: Middleware>(&self, provider: &M) -> Vec<BusAlert> {
+        let hashes: Vec<H256> = self.queued.lock().unwrap().iter().filter_map(|q| q.hash).collect();
+        let mut mined = HashSet::new();
+        for hash in hashes {
+            match provider.get_transaction_receipt(hash).await {
+                Ok(Some(_)) => {
+                    mined.insert(hash);
+                }
+                Ok(None) => {}
+                Err(e) => self.errors.record(format!("receipt of {:?}: {}", hash, e)),
+            }
+        }
+        self.recheck(now(), |hash| mined.contains(&hash))
+    }
+}
+
+impl Enricher for Arc<CongestionMonitor> {
+    fn enrich(&self, _tx: &Tx, out: &mut Enrichment) {
+        let Some(snapshot) = self.snapshot() else {
+            return;
+        };
+        out.set("network", "regime", json!(snapshot.regime));
+        out.set("network", "base_fee_gwei", json!(snapshot.base_fee_gwei));
+        out.set("network", "blob_fee_gwei", json!(snapshot.blob_fee_gwei));
+        out.set("network", "pending", json!(snapshot.pending));
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// `TxSink` с проверкой отслеживаемых операций, см. `CongestionMonitor::watching`
+pub struct CongestionWatcher<S> {
+    monitor: Arc<CongestionMonitor>,
+    inner: S,
+}
+
+impl<S: TxSink> TxSink for CongestionWatcher<S> {
+    fn deliver(&self, source: &str, hash: Option<H256>, tx: Tx) -> bool {
+        for alert in self.monitor.check(hash, &tx) {
+            self.monitor.bus.publish(alert);
+        }
+        self.inner.deliver(source, hash, tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regime(name: &str, gwei: f64) -> Regime {
+        Regime {
+            name: name.into(),
+            min_base_fee_gwei: Some(Gwei::new(gwei).unwrap()),
+            min_blob_fee_gwei: None,
+            min_pending: None,
+            level: AlertLevel::Medium,
+        }
+    }
+
+    fn snapshot(block: u64, gwei: f64) -> CongestionSnapshot {
+        CongestionSnapshot { block, base_fee_gwei: Gwei::new(gwei).unwrap(), blob_fee_gwei: None, pending: Some(5_000), regime: None }
+    }
+
+    #[test]
+    fn test_alerts_on_escalation_and_watched_operation_once() {
+        let eigen = "0x858646372cc42e1a627fce94aa7a7033e7cf075a";
+        let watch: CongestionWatch = toml::from_str(&format!(
+            "name = \"restaking\"\nto = [\"{}\"]\nselectors = [\"0xe7a050aa\"]\nregime = \"extreme\"",
+            eigen
+        ))
+        .unwrap();
+        let monitor =
+            CongestionMonitor::new(vec![regime("busy", 50.0), regime("extreme", 200.0)], vec![watch], Arc::new(AlertBus::new(8)))
+                .unwrap();
+
+        assert!(monitor.observe(snapshot(1, 20.0)).is_none());
+        assert_eq!(monitor.observe(snapshot(2, 60.0)).unwrap().subject, "busy");
+        let tx = Tx {
+            from: "0x00000000000000000000000000000000000000aa".parse().unwrap(),
+            to: eigen.parse().unwrap(),
+            value: Default::default(),
+            gas_price: Default::default(),
+            input: vec![0xe7, 0xa0, 0x50, 0xaa, 0],
+        };
+        let hash = H256::repeat_byte(1);
+        assert!(monitor.check_at(Some(hash), &tx, 10).is_empty());
+
+        // Транзакция ждала в пуле до наступления режима
+        assert_eq!(monitor.observe(snapshot(3, 250.0)).unwrap().subject, "extreme");
+        assert_eq!(monitor.recheck(20, |_| false).len(), 1);
+        assert!(monitor.check_at(None, &tx, 20).is_empty());
+        // Спад в более лёгкий режим не алертит
+        assert!(monitor.observe(snapshot(4, 70.0)).is_none());
+        assert_eq!(monitor.snapshot().unwrap().regime.as_deref(), Some("busy"));
+
+        // Добытая транзакция и транзакция без хэша после TTL забываются
+        monitor.observe(snapshot(5, 250.0));
+        assert!(monitor.recheck(21 + QUEUED_TTL_SECS, |h| h == hash).is_empty());
+    }
+}
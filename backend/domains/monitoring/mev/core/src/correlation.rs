@@ -0,0 +1,246 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::shutdown::ShutdownSignal;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Окно по умолчанию: звенья одной атаки укладываются в час
+pub const DEFAULT_WINDOW_SECONDS: u64 = 3600;
+
+/// Источник составных инцидентов; свои алерты коррелятор не разбирает
+pub const SOURCE: &str = "correlation";
+
+/// Звено цепочки атаки, к которому относится алерт
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SignalClass {
+    /// Деплой, апгрейд, смена слота
+    Change,
+    /// Находка аудита
+    Vulnerability,
+    /// MEV-атака, злоупотребление солвера
+    Attack,
+    /// Отток средств
+    Loss,
+}
+
+/// Классы по ключу `source.kind` или `source`; остальные алерты не коррелируются
+const DEFAULT_CLASSES: &[(&str, SignalClass)] = &[
+    ("monitor.upgrade", SignalClass::Change),
+    ("monitor.storage_slot", SignalClass::Change),
+    ("monitor.new_finding", SignalClass::Vulnerability),
+    ("mev", SignalClass::Attack),
+    ("intents", SignalClass::Attack),
+    ("monitor.outflow", SignalClass::Loss),
+    ("anomaly", SignalClass::Loss),
+];
+
+/// Алерт-звено в составе инцидента
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Signal {
+    pub class: SignalClass,
+    pub source: String,
+    pub kind: String,
+    pub level: AlertLevel,
+    pub title: String,
+    pub timestamp: u64,
+}
+
+/// Составной инцидент: алерты разных классов по одному адресу в пределах окна
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Incident {
+    pub id: String,
+    pub subject: String,
+    pub level: AlertLevel,
+    pub classes: Vec<SignalClass>,
+    pub signals: Vec<Signal>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+impl Incident {
+    pub fn to_alert(&self) -> BusAlert {
+        let chain: Vec<String> = self.signals.iter().map(|s| format!("{}.{}", s.source, s.kind)).collect();
+        let mut alert = BusAlert::new(SOURCE, "incident", self.level, self.subject.clone(), chain.join(" -> "))
+            .with_payload(json!(self));
+        alert.timestamp = self.last_seen;
+        alert
+    }
+}
+
+/// Уровень инцидента: максимум звеньев, поднятый на ступень за каждый класс сверх первого
+fn escalate(level: AlertLevel, classes: usize) -> AlertLevel {
+    const LEVELS: [AlertLevel; 5] =
+        [AlertLevel::Info, AlertLevel::Low, AlertLevel::Medium, AlertLevel::High, AlertLevel::Critical];
+    let index = LEVELS.iter().position(|l| *l == level).unwrap_or(0);
+    LEVELS[(index + classes.saturating_sub(1)).min(LEVELS.len() - 1)]
+}
+
+#[derive(Default)]
+struct Subject {
+    signals: VecDeque<Signal>,
+    /// Число классов в последнем выпущенном инциденте
+    reported: usize,
+}
+
+/// Связывает алерты подсистем по адресу: апгрейд, находка аудита и отток на одном
+/// контракте за окно — одна цепочка атаки, которую отдельные детекторы не видят
+pub struct Correlator {
+    window_seconds: u64,
+    min_classes: usize,
+    classes: HashMap<String, SignalClass>,
+    subjects: Mutex<HashMap<String, Subject>>,
+    /// Алерты, пропущенные из-за отставания от шины
+    skipped: AtomicU64,
+}
+
+impl Default for Correlator {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SECONDS)
+    }
+}
+
+impl Correlator {
+    pub fn new(window_seconds: u64) -> Self {
+        Self {
+            window_seconds: window_seconds.max(1),
+            min_classes: 2,
+            classes: DEFAULT_CLASSES.iter().map(|(k, c)| (k.to_string(), *c)).collect(),
+            subjects: Mutex::new(HashMap::new()),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    /// Сколько алертов шины пропущено из-за отставания
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Сколько разных классов нужно для инцидента
+    pub fn with_min_classes(mut self, min_classes: usize) -> Self {
+        self.min_classes = min_classes.max(1);
+        self
+    }
+
+    /// Класс для алертов `key` (`source.kind` или `source`)
+    pub fn with_class(mut self, key: &str, class: SignalClass) -> Self {
+        self.classes.insert(key.to_string(), class);
+        self
+    }
+
+    pub fn classify(&self, alert: &BusAlert) -> Option<SignalClass> {
+        self.classes
+            .get(&format!("{}.{}", alert.source, alert.kind))
+            .or_else(|| self.classes.get(&alert.source))
+            .copied()
+    }
+
+    /// Учитывает алерт; возвращает инцидент, если по адресу набралось больше классов,
+    /// чем в последнем выпущенном
+    pub fn observe(&self, alert: &BusAlert) -> Option<Incident> {
+        if alert.source == SOURCE || alert.subject.is_empty() {
+            return None;
+        }
+        let class = self.classify(alert)?;
+        // Подсистемы пишут адрес по-разному: checksum и нижний регистр
+        let key = alert.subject.to_lowercase();
+
+        let mut subjects = self.subjects.lock().unwrap();
+        let subject = subjects.entry(key.clone()).or_default();
+        let now = alert.timestamp;
+        while subject.signals.front().is_some_and(|s| now.saturating_sub(s.timestamp) >= self.window_seconds) {
+            subject.signals.pop_front();
+        }
+        if subject.signals.is_empty() {
+            subject.reported = 0;
+        }
+        subject.signals.push_back(Signal {
+            class,
+            source: alert.source.clone(),
+            kind: alert.kind.clone(),
+            level: alert.level,
+            title: alert.title.clone(),
+            timestamp: now,
+        });
+
+        let classes: BTreeSet<SignalClass> = subject.signals.iter().map(|s| s.class).collect();
+        if classes.len() < self.min_classes || classes.len() <= subject.reported {
+            return None;
+        }
+        subject.reported = classes.len();
+
+        let signals: Vec<Signal> = subject.signals.iter().cloned().collect();
+        let first_seen = signals.first().map_or(now, |s| s.timestamp);
+        let level = signals.iter().map(|s| s.level).max().unwrap_or(AlertLevel::Info);
+        Some(Incident {
+            id: format!("incident-{}-{}", key, first_seen),
+            subject: alert.subject.clone(),
+            level: escalate(level, classes.len()),
+            classes: classes.into_iter().collect(),
+            signals,
+            first_seen,
+            last_seen: now,
+        })
+    }
+
+    /// Разбирает алерты шины и публикует в неё инциденты до сигнала остановки
+    pub async fn run(&self, bus: &AlertBus, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => {
+                        if let Some(incident) = self.observe(&alert) {
+                            bus.publish(incident.to_alert());
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        self.skipped.fetch_add(n, Ordering::Relaxed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(source: &str, kind: &str, level: AlertLevel, subject: &str, at: u64) -> BusAlert {
+        let mut alert = BusAlert::new(source, kind, level, subject.into(), kind.into());
+        alert.timestamp = at;
+        alert
+    }
+
+    #[test]
+    fn test_chain_on_one_contract_escalates() {
+        let correlator = Correlator::new(600);
+        let contract = "0xAbCd000000000000000000000000000000000001";
+
+        assert!(correlator.observe(&alert("monitor", "upgrade", AlertLevel::Medium, contract, 0)).is_none());
+        assert!(correlator.observe(&alert("monitor", "storage_slot", AlertLevel::Low, contract, 10)).is_none());
+
+        let incident = correlator
+            .observe(&alert("monitor", "new_finding", AlertLevel::High, &contract.to_lowercase(), 20))
+            .unwrap();
+        assert_eq!(incident.classes, vec![SignalClass::Change, SignalClass::Vulnerability]);
+        assert_eq!(incident.level, AlertLevel::Critical);
+
+        // Тот же набор классов повторно не выпускается; новый класс — выпускается
+        assert!(correlator.observe(&alert("monitor", "new_finding", AlertLevel::High, contract, 30)).is_none());
+        assert_eq!(correlator.observe(&alert("monitor", "outflow", AlertLevel::High, contract, 40)).unwrap().classes.len(), 3);
+
+        // Вне окна звенья не связываются
+        assert!(correlator.observe(&alert("mev", "sandwich", AlertLevel::Low, "0x02", 0)).is_none());
+        assert!(correlator.observe(&alert("anomaly", "tvl_outflow", AlertLevel::High, "0x02", 900)).is_none());
+    }
+}
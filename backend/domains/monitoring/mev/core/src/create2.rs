@@ -0,0 +1,330 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::compat::{keccak256, Address, H256};
+use crate::detector::{build_alert, MevAlert, MevType};
+use crate::shutdown::ShutdownSignal;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use ethers::abi::{decode, ParamType, Token};
+use ethers::providers::Middleware;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Детерминированный деплоер (Arachnid), есть почти во всех EVM-сетях
+pub const DETERMINISTIC_DEPLOYER: &str = "0x4e59b44847b379578588920ca78fbf26c0b4956c";
+
+/// Singleton Factory из EIP-2470
+pub const EIP2470_FACTORY: &str = "0xce0042B868300000d44A59004Da54A005ffdcf9f";
+
+/// Функции, которыми новый контракт получает владельца или настройки. Вызов будущего
+/// контракта с другим селектором — обычная работа с ним, а не гонка
+const INITIALIZERS: [&str; 8] = [
+    "initialize()",
+    "initialize(address)",
+    "initialize(address,address)",
+    "initialize(address,address,address)",
+    "initialize(address,uint256)",
+    "initialize(string,string)",
+    "initialize(bytes)",
+    // Safe
+    "setup(address[],uint256,address,bytes,address,address,uint256,address)",
+];
+
+/// Перехват деплоя не приносит профита, который можно посчитать, но грозит захватом контракта
+const DEPLOYMENT_RISK: f32 = 0.9;
+
+/// Адрес контракта, созданного через CREATE2:
+/// `keccak256(0xff ‖ deployer ‖ salt ‖ keccak256(init_code))[12..]`
+pub fn create2_address(deployer: Address, salt: H256, init_code_hash: H256) -> Address {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(deployer.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(init_code_hash.as_bytes());
+    Address::from_slice(&keccak256(preimage).as_bytes()[12..])
+}
+
+/// То же по самому init code
+pub fn create2_address_from_code(deployer: Address, salt: H256, init_code: &[u8]) -> Address {
+    create2_address(deployer, salt, keccak256(init_code))
+}
+
+/// Как фабрика принимает соль и init code. Фабрики, примешивающие к соли отправителя
+/// (CreateX с защищённой солью), не подходят: скопировать их деплой нельзя
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryKind {
+    /// Calldata — соль и сразу init code, без селектора
+    SaltPrefixed,
+    /// `deploy(bytes initCode, bytes32 salt)`
+    Eip2470,
+    /// Функция `(bytes32 salt, bytes initCode)` с заданным селектором
+    SaltThenCode([u8; 4]),
+}
+
+impl FactoryKind {
+    /// Соль и init code из calldata вызова фабрики
+    pub fn decode(&self, input: &[u8]) -> Option<(H256, Vec<u8>)> {
+        match self {
+            FactoryKind::SaltPrefixed => {
+                (input.len() > 32).then(|| (H256::from_slice(&input[..32]), input[32..].to_vec()))
+            }
+            FactoryKind::Eip2470 => {
+                let args = strip_selector(input, selector("deploy(bytes,bytes32)"))?;
+                match decode(&[ParamType::Bytes, ParamType::FixedBytes(32)], args).ok()?.as_slice() {
+                    [Token::Bytes(code), Token::FixedBytes(salt)] => Some((H256::from_slice(salt), code.clone())),
+                    _ => None,
+                }
+            }
+            FactoryKind::SaltThenCode(expected) => {
+                let args = strip_selector(input, *expected)?;
+                match decode(&[ParamType::FixedBytes(32), ParamType::Bytes], args).ok()?.as_slice() {
+                    [Token::FixedBytes(salt), Token::Bytes(code)] => Some((H256::from_slice(salt), code.clone())),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Первые четыре байта keccak256 сигнатуры
+pub fn selector(signature: &str) -> [u8; 4] {
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&keccak256(signature.as_bytes()).as_bytes()[..4]);
+    selector
+}
+
+fn strip_selector(input: &[u8], expected: [u8; 4]) -> Option<&[u8]> {
+    input.strip_prefix(expected.as_slice())
+}
+
+/// Деплой через фабрику, ожидающий включения в блок
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingDeployment {
+    pub factory: ChecksummedAddress,
+    pub salt: H256,
+    pub init_code_hash: H256,
+    /// Адрес будущего контракта
+    pub address: ChecksummedAddress,
+    pub tx: Tx,
+}
+
+/// Как перехватывают деплой
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentPattern {
+    /// Другой отправитель деплоит на тот же адрес: копия calldata или тот же init code с той же солью
+    AddressSquatting,
+    /// Другой отправитель вызывает будущий контракт — обычно `initialize`, чтобы стать владельцем
+    InitializationRace,
+}
+
+/// Следит за деплоями через известные фабрики CREATE2 и ловит их перехват в мемпуле.
+/// Первый замеченный деплой адреса считается настоящим; транзакции самого деплоера
+/// к будущему контракту — его собственная инициализация. Деплой ждёт, пока его код
+/// не появится в сети (`prune_deployed`) или не истечёт TTL
+pub struct DeploymentWatch {
+    factories: HashMap<ChecksummedAddress, FactoryKind>,
+    initializers: HashSet<[u8; 4]>,
+    ttl_seconds: u64,
+    pending: Mutex<HashMap<ChecksummedAddress, (PendingDeployment, u64)>>,
+    /// Неудачные проверки кода в `run`
+    errors: TaskErrors,
+}
+
+impl DeploymentWatch {
+    /// Детерминированный деплоер и фабрика EIP-2470 подключены сразу
+    pub fn new(ttl_seconds: u64) -> Self {
+        let factories = [(DETERMINISTIC_DEPLOYER, FactoryKind::SaltPrefixed), (EIP2470_FACTORY, FactoryKind::Eip2470)]
+            .into_iter()
+            .filter_map(|(address, kind)| Some((address.parse().ok()?, kind)))
+            .collect();
+        Self {
+            factories,
+            initializers: INITIALIZERS.iter().map(|signature| selector(signature)).collect(),
+            ttl_seconds,
+            pending: Mutex::new(HashMap::new()),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn with_factory(mut self, factory: ChecksummedAddress, kind: FactoryKind) -> Self {
+        self.factories.insert(factory, kind);
+        self
+    }
+
+    /// Ещё одна функция инициализации, вызов которой чужим адресом считается гонкой
+    pub fn with_initializer(mut self, signature: &str) -> Self {
+        self.initializers.insert(selector(signature));
+        self
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Деплой, если транзакция — вызов известной фабрики
+    pub fn deployment(&self, tx: &Tx) -> Option<PendingDeployment> {
+        let kind = self.factories.get(&tx.to)?;
+        let (salt, init_code) = kind.decode(&tx.input)?;
+        let init_code_hash = keccak256(&init_code);
+        Some(PendingDeployment {
+            factory: tx.to,
+            salt,
+            init_code_hash,
+            address: create2_address(tx.to.into(), salt, init_code_hash).into(),
+            tx: tx.clone(),
+        })
+    }
+
+    /// Ожидающий деплой будущего контракта `address`
+    pub fn pending(&self, address: &ChecksummedAddress) -> Option<PendingDeployment> {
+        self.pending.lock().unwrap().get(address).map(|(deployment, _)| deployment.clone())
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Деплой добыт: дальнейшие вызовы контракта — работа с ним, а не перехват
+    pub fn included(&self, address: &ChecksummedAddress) -> bool {
+        self.pending.lock().unwrap().remove(address).is_some()
+    }
+
+    /// Снимает ожидающие деплои, код которых уже есть в сети; возвращает их число
+    pub async fn prune_deployed<M: Middleware>(&self, provider: &M) -> Result<usize, M::Error> {
+        let addresses: Vec<ChecksummedAddress> = self.pending.lock().unwrap().keys().copied().collect();
+        let mut included = 0;
+        for address in addresses {
+            if !provider.get_code(Address::from(address), None).await?.is_empty() && self.included(&address) {
+                included += 1;
+            }
+        }
+        Ok(included)
+    }
+
+    /// Проверка включения деплоев раз в `interval` до сигнала остановки
+    pub async fn run<M: Middleware>(&self, provider: &M, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            if self.pending_len() > 0 {
+                self.errors.check(self.prune_deployed(provider).await);
+            }
+        }
+    }
+
+    /// Запоминает деплой из транзакции и сверяет её с ожидающими деплоями
+    pub fn observe(&self, tx: &Tx) -> Vec<MevAlert> {
+        self.observe_at(tx, now())
+    }
+
+    fn observe_at(&self, tx: &Tx, now: u64) -> Vec<MevAlert> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, (_, seen_at)| now.saturating_sub(*seen_at) <= self.ttl_seconds);
+
+        let mut alerts = Vec::new();
+        if let Some(deployment) = self.deployment(tx) {
+            match pending.get(&deployment.address) {
+                Some((existing, _)) if existing.tx.from != tx.from => {
+                    alerts.push(deployment_alert(existing, tx, DeploymentPattern::AddressSquatting));
+                }
+                Some(_) => {}
+                None => {
+                    pending.insert(deployment.address, (deployment, now));
+                }
+            }
+        }
+        if let Some((existing, _)) = pending.get(&tx.to) {
+            let initializer = tx.input.get(..4).is_some_and(|s| self.initializers.contains(s));
+            if existing.tx.from != tx.from && initializer {
+                alerts.push(deployment_alert(existing, tx, DeploymentPattern::InitializationRace));
+            }
+        }
+        alerts
+    }
+}
+
+/// Ключи `victim_tx` и `attacker_tx` — как у обычного фронтрана, чтобы их понимали форензика и правила
+fn deployment_alert(deployment: &PendingDeployment, attacker: &Tx, pattern: DeploymentPattern) -> MevAlert {
+    let mut alert = build_alert(
+        MevType::Frontrun,
+        WeiAmount::ZERO,
+        json!({
+            "victim_tx": deployment.tx,
+            "attacker_tx": attacker,
+            "deployment": {
+                "pattern": pattern,
+                "factory": deployment.factory,
+                "salt": deployment.salt,
+                "init_code_hash": deployment.init_code_hash,
+                "address": deployment.address,
+                "deployer": deployment.tx.from,
+                "outbids": attacker.gas_price > deployment.tx.gas_price,
+            }
+        }),
+    );
+    alert.risk_score = DEPLOYMENT_RISK;
+    alert
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squatting_and_initialization_race() {
+        // Пример 0 из EIP-1014
+        let address = create2_address_from_code(Address::zero(), H256::zero(), &[0x00]);
+        assert_eq!(address, "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38".parse::<Address>().unwrap());
+
+        let factory: ChecksummedAddress = DETERMINISTIC_DEPLOYER.parse().unwrap();
+        let deployer: ChecksummedAddress = "0x1111111111111111111111111111111111111111".parse().unwrap();
+        let attacker: ChecksummedAddress = "0x2222222222222222222222222222222222222222".parse().unwrap();
+        let tx = |from, to, input: Vec<u8>, gwei: f64| Tx {
+            from,
+            to,
+            value: WeiAmount::ZERO,
+            gas_price: WeiAmount::from_gwei(gwei),
+            input,
+        };
+        let mut calldata = vec![0x42; 32];
+        calldata.extend_from_slice(&[0x60, 0x00, 0x60, 0x00, 0xf3]);
+
+        let watch = DeploymentWatch::new(60);
+        let deploy = tx(deployer, factory, calldata.clone(), 10.0);
+        assert!(watch.observe_at(&deploy, 100).is_empty());
+        let target = watch.deployment(&deploy).unwrap().address;
+        assert!(watch.observe_at(&tx(deployer, target, vec![0x81, 0x29, 0xfc, 0x1c], 10.0), 101).is_empty());
+
+        let squat = watch.observe_at(&tx(attacker, factory, calldata, 20.0), 102);
+        assert_eq!(squat.len(), 1);
+        assert_eq!(squat[0].metadata["deployment"]["pattern"], "address_squatting");
+        assert_eq!(squat[0].metadata["deployment"]["outbids"], true);
+
+        let race = watch.observe_at(&tx(attacker, target, vec![0x81, 0x29, 0xfc, 0x1c], 20.0), 103);
+        assert_eq!(race[0].metadata["deployment"]["pattern"], "initialization_race");
+        // Не инициализатор: обычный вызов, например `transfer`
+        assert!(watch.observe_at(&tx(attacker, target, vec![0xa9, 0x05, 0x9c, 0xbb], 20.0), 104).is_empty());
+
+        assert!(watch.observe_at(&tx(attacker, target, vec![0x81, 0x29, 0xfc, 0x1c], 20.0), 200).is_empty());
+
+        // После включения деплоя инициализация с другого адреса (Safe) — не гонка
+        assert!(watch.observe_at(&deploy, 300).is_empty());
+        assert!(watch.included(&target));
+        assert!(watch.observe_at(&tx(attacker, target, vec![0x81, 0x29, 0xfc, 0x1c], 20.0), 301).is_empty());
+    }
+}
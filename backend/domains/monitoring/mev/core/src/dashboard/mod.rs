@@ -0,0 +1,237 @@
+use crate::bus::{AlertBus, BusAlert};
+use crate::ingest::multi::MultiSource;
+use crate::openapi::{self, ApiDoc};
+use crate::pipeline::Pipeline;
+use crate::shutdown::ShutdownSignal;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use utoipa::OpenApi;
+
+const INDEX_HTML: &str = include_str!("index.html");
+const DEFAULT_HISTORY: usize = 1_000;
+
+/// Последние алерты шины для начальной загрузки страницы и выборок по адресу
+pub struct AlertHistory {
+    alerts: Mutex<VecDeque<Arc<BusAlert>>>,
+    capacity: usize,
+}
+
+impl AlertHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            alerts: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    fn push(&self, alert: Arc<BusAlert>) {
+        let mut alerts = self.alerts.lock().unwrap();
+        if alerts.len() >= self.capacity {
+            alerts.pop_front();
+        }
+        alerts.push_back(alert);
+    }
+
+    /// От новых к старым
+    pub fn recent(&self, limit: usize, filter: impl Fn(&BusAlert) -> bool) -> Vec<BusAlert> {
+        self.alerts
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|a| filter(a))
+            .take(limit)
+            .map(|a| a.as_ref().clone())
+            .collect()
+    }
+
+    /// Копит алерты шины до сигнала остановки
+    pub async fn collect(&self, bus: &AlertBus, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => self.push(alert),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+/// Алерт относится к адресу, если он субъект алерта или встречается в нагрузке
+fn mentions(alert: &BusAlert, address: &str) -> bool {
+    alert.subject.eq_ignore_ascii_case(address)
+        || alert.payload.to_string().to_lowercase().contains(address)
+}
+
+#[derive(Clone)]
+pub struct DashboardState {
+    pub bus: AlertBus,
+    pub history: Arc<AlertHistory>,
+    pub pipeline: Arc<Pipeline>,
+    /// Метрики источников мемпула, если их несколько
+    pub sources: Option<Arc<MultiSource>>,
+    /// Origin страниц, которым можно открыть живую ленту, кроме loopback: адрес обратного прокси
+    pub allowed_origins: Vec<String>,
+}
+
+impl DashboardState {
+    pub fn new(bus: AlertBus, pipeline: Arc<Pipeline>) -> Self {
+        Self {
+            bus,
+            history: Arc::new(AlertHistory::new(DEFAULT_HISTORY)),
+            pipeline,
+            sources: None,
+            allowed_origins: Vec::new(),
+        }
+    }
+
+    pub fn with_sources(mut self, sources: Arc<MultiSource>) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertsQuery {
+    limit: Option<usize>,
+    source: Option<String>,
+}
+
+async fn index() -> Response {
+    ([(header::CACHE_CONTROL, "no-cache")], Html(INDEX_HTML)).into_response()
+}
+
+async fn list_alerts(State(state): State<DashboardState>, Query(query): Query<AlertsQuery>) -> Response {
+    let alerts = state.history.recent(query.limit.unwrap_or(100), |a| {
+        query.source.as_ref().is_none_or(|s| *s == a.source)
+    });
+    Json(alerts).into_response()
+}
+
+async fn pool_stats(State(state): State<DashboardState>) -> Response {
+    let sources = state.sources.as_ref().map(|s| s.metrics()).unwrap_or_default();
+    Json(json!({
+        "pipeline": state.pipeline.metrics(),
+        "sources": sources,
+        "subscribers": state.bus.subscriber_count(),
+    }))
+    .into_response()
+}
+
+async fn address_view(State(state): State<DashboardState>, Path(address): Path<String>) -> Response {
+    let address = address.to_lowercase();
+    let alerts = state.history.recent(DEFAULT_HISTORY, |a| mentions(a, &address));
+    Json(json!({ "address": address, "alerts": alerts })).into_response()
+}
+
+/// Браузер даёт любой странице открыть сокет к loopback, и cookie прокси уйдут вместе с ним,
+/// поэтому ленту получают только страницы с loopback-хоста и явно разрешённые
+fn origin_allowed(origin: &str, allowed: &[String]) -> bool {
+    if allowed.iter().any(|a| a.trim_end_matches('/').eq_ignore_ascii_case(origin)) {
+        return true;
+    }
+    let Some(host) = origin.parse::<Uri>().ok().and_then(|uri| uri.host().map(str::to_string)) else {
+        return false;
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Без `Origin` подключаются не браузеры: им подделывать чужую страницу незачем
+async fn live(State(state): State<DashboardState>, headers: HeaderMap, ws: WebSocketUpgrade) -> Response {
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        if !origin.to_str().is_ok_and(|origin| origin_allowed(origin, &state.allowed_origins)) {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+    }
+    ws.on_upgrade(move |socket| stream_alerts(socket, state.bus.subscribe()))
+}
+
+/// Пересылает алерты шины в сокет, пока клиент не отключится
+async fn stream_alerts(mut socket: WebSocket, mut rx: broadcast::Receiver<Arc<BusAlert>>) {
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Ok(alert) => {
+                    let Ok(text) = serde_json::to_string(alert.as_ref()) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+pub fn router(state: DashboardState) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/alerts", get(list_alerts))
+        .route("/api/alerts/live", get(live))
+        .route("/api/pool", get(pool_stats))
+        .route("/api/address/:address", get(address_view))
+        .with_state(state)
+        .merge(openapi::router(ApiDoc::openapi()))
+}
+
+/// Встроенный дашборд: без токена, поэтому слушает только loopback.
+/// Снаружи — через обратный прокси с авторизацией
+pub async fn serve(addr: SocketAddr, state: DashboardState, mut shutdown: ShutdownSignal) -> std::io::Result<()> {
+    if !addr.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "dashboard must listen on a loopback address",
+        ));
+    }
+
+    let history = state.history.clone();
+    let bus = state.bus.clone();
+    let collector_shutdown = shutdown.clone();
+    tokio::spawn(async move { history.collect(&bus, collector_shutdown).await });
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_feed_only_for_loopback_and_allowed_origins() {
+        let allowed = vec!["https://dash.example.com/".to_string()];
+        assert!(origin_allowed("http://localhost:8090", &allowed));
+        assert!(origin_allowed("http://127.0.0.1:8090", &allowed));
+        assert!(origin_allowed("http://[::1]:8090", &allowed));
+        assert!(origin_allowed("https://dash.example.com", &allowed));
+        assert!(!origin_allowed("https://evil.example", &allowed));
+        assert!(!origin_allowed("null", &allowed));
+    }
+}
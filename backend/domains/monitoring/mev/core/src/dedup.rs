@@ -0,0 +1,108 @@
+use crate::compat::{keccak256, H256};
+use crate::tx::Tx;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Окно по умолчанию: копия от другого пира приходит в пределах секунд
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+pub const DEFAULT_CAPACITY: usize = 200_000;
+
+/// Отпечаток содержимого для транзакций без хэша (`Pipeline::ingest` без источника).
+/// Одна и та же подписанная транзакция от разных пиров даёт один отпечаток
+pub fn tx_fingerprint(tx: &Tx) -> H256 {
+    let mut bytes = Vec::with_capacity(40 + 64 + tx.input.len());
+    bytes.extend_from_slice(tx.from.address().as_bytes());
+    bytes.extend_from_slice(tx.to.address().as_bytes());
+    bytes.extend_from_slice(&tx.value.to_be_bytes());
+    bytes.extend_from_slice(&tx.gas_price.to_be_bytes());
+    bytes.extend_from_slice(&tx.input);
+    keccak256(bytes)
+}
+
+/// Уже проанализированные транзакции за скользящее окно.
+/// Точный набор, а не bloom-фильтр: ложное срабатывание означало бы пропущенную атаку,
+/// а размер ограничен окном и `capacity`
+pub struct SeenSet {
+    window: Duration,
+    capacity: usize,
+    seen: HashMap<H256, Instant>,
+    order: VecDeque<(H256, Instant)>,
+    skipped: u64,
+}
+
+impl Default for SeenSet {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW, DEFAULT_CAPACITY)
+    }
+}
+
+impl SeenSet {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity: capacity.max(1),
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            skipped: 0,
+        }
+    }
+
+    /// `true`, если хэш не встречался в окне; повторы считаются в `skipped`
+    pub fn insert(&mut self, hash: H256, now: Instant) -> bool {
+        while let Some((old, at)) = self.order.front().copied() {
+            if now.duration_since(at) <= self.window && self.order.len() < self.capacity {
+                break;
+            }
+            self.order.pop_front();
+            // Хэш мог быть вставлен заново после вытеснения — удаляем только свою запись
+            if self.seen.get(&old) == Some(&at) {
+                self.seen.remove(&old);
+            }
+        }
+
+        if self.seen.contains_key(&hash) {
+            self.skipped += 1;
+            return false;
+        }
+        self.seen.insert(hash, now);
+        self.order.push_back((hash, now));
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Сколько повторных анализов пропущено
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_skipped_within_window_only() {
+        let mut seen = SeenSet::new(Duration::from_secs(10), 100);
+        let start = Instant::now();
+        let hash = H256::repeat_byte(7);
+
+        assert!(seen.insert(hash, start));
+        assert!(!seen.insert(hash, start + Duration::from_secs(3)));
+        assert!(seen.insert(hash, start + Duration::from_secs(11)));
+        assert_eq!(seen.skipped(), 1);
+
+        let mut small = SeenSet::new(Duration::from_secs(10), 2);
+        for i in 0..3 {
+            small.insert(H256::repeat_byte(i), start);
+        }
+        assert_eq!(small.len(), 2);
+        assert!(small.insert(H256::repeat_byte(0), start));
+    }
+}
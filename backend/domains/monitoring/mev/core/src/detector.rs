@@ -1,28 +1,68 @@
-use crate::ffi::{Tx, CppSimulator};
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::cancel::SimulationJob;
+use crate::chain::{ChainAdapter, Ethereum, SharedChainAdapter};
+use crate::compat::H256;
+use crate::create2::DeploymentWatch;
+use crate::dedup::{tx_fingerprint, SeenSet};
+use crate::enrichment::{self, Enricher, Enrichment};
+use crate::fingerprint::BotFingerprinter;
+use crate::funding::FundingGraph;
+use crate::gas_oracle::{FeeUrgency, GasOracle};
+use crate::labels::SharedLabelResolver;
+use crate::pipeline::LatencyBudget;
+use crate::registry::DetectorRegistry;
+use crate::rules::RuleEngine;
+use crate::severity::SeverityModel;
+use crate::simulator::Simulator;
+use crate::store::{Store, StoreError, StoreExt};
+use crate::trap;
+use crate::tx::Tx;
+use crate::units::Gwei;
+use crate::victims::VictimTracker;
 use serde::{Serialize, Deserialize};
+use serde_json::json;
 use std::collections::{HashMap, VecDeque};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub enum MevType {
     Frontrun,
     Sandwich,
     Arbitrage,
     Liquidation,
+    /// Один и тот же адрес снова и снова попадает в сэндвичи, см. `VictimTracker`
+    RepeatedTargeting,
+    /// Срабатывание пользовательского правила (имя правила)
+    Custom(String),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct MevAlert {
+    /// Версия формата, см. `schema::SCHEMAS`
+    #[serde(default = "crate::schema::unversioned")]
+    pub schema_version: u32,
     pub mev_type: MevType,
-    pub profit_eth: f64,
-    pub risk_score: f8,
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub profit: WeiAmount,
+    /// 0.0 - 1.0
+    pub risk_score: f32,
     pub timestamp: u64,
     pub metadata: serde_json::Value,
+    /// Отметки стадий конвейера; заполняется при прохождении через `Pipeline`
+    #[serde(default)]
+    pub latency: Option<LatencyBudget>,
+    /// Результат сверки с добытым блоком; `None` — ещё не проверялся
+    #[serde(default)]
+    pub confirmed_onchain: Option<bool>,
 }
 
 /// Пул ожидающих транзакций с TTL
 struct PendingPool {
-    txs: HashMap<String, VecDeque<(Tx, u64)>>, // address -> (tx, timestamp)
+    txs: HashMap<ChecksummedAddress, VecDeque<(Tx, u64)>>, // address -> (tx, timestamp)
     ttl_seconds: u64,
 }
 
@@ -42,7 +82,7 @@ impl PendingPool {
             .as_secs();
         
         self.txs
-            .entry(tx.to.clone())
+            .entry(tx.to)
             .or_default()
             .push_back((tx, timestamp));
         
@@ -69,98 +109,442 @@ impl PendingPool {
 
 /// Основной детектор MEV
 pub struct MevDetector {
-    simulator: UniquePtr<CppSimulator>,
+    simulator: Box<dyn Simulator>,
     pending_pool: PendingPool,
     thresholds: MevThresholds,
+    rules: Option<RuleEngine>,
+    enrichers: Vec<Box<dyn Enricher>>,
+    labels: Option<SharedLabelResolver>,
+    severity: Option<Arc<SeverityModel>>,
+    registry: Option<Arc<DetectorRegistry>>,
+    seen: Option<SeenSet>,
+    chain: SharedChainAdapter,
+    gas_oracle: Option<Arc<GasOracle>>,
+    victims: Option<VictimTracker>,
+    fingerprints: Option<Arc<BotFingerprinter>>,
+    funding: Option<Arc<FundingGraph>>,
+    deployments: Option<Arc<DeploymentWatch>>,
 }
 
-#[derive(Debug)]
-struct MevThresholds {
-    min_profit_eth: f64,
-    max_gas_price_gwei: f64,
+/// Пространство имён хранилища для состояния детектора
+const DETECTOR_NS: &str = "detector";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MevThresholds {
+    pub min_profit: WeiAmount,
+    pub max_gas_price: Gwei,
 }
 
 impl MevDetector {
+    /// `simulator` — обычно `simulator::state_simulator`
     pub fn new(
-        simulator: UniquePtr<CppSimulator>,
+        simulator: impl Simulator + 'static,
         ttl_seconds: u64,
         thresholds: MevThresholds,
     ) -> Self {
         Self {
-            simulator,
+            simulator: Box::new(simulator),
             pending_pool: PendingPool::new(ttl_seconds),
             thresholds,
+            rules: None,
+            enrichers: Vec::new(),
+            labels: None,
+            severity: None,
+            registry: None,
+            seen: None,
+            chain: Arc::new(Ethereum::mainnet()),
+            gas_oracle: None,
+            victims: None,
+            fingerprints: None,
+            funding: None,
+            deployments: None,
+        }
+    }
+
+    /// Повторная доставка той же транзакции в пределах окна не анализируется заново
+    pub fn with_dedup(mut self, window: Duration, capacity: usize) -> Self {
+        self.seen = Some(SeenSet::new(window, capacity));
+        self
+    }
+
+    /// Сколько повторных доставок пропущено дедупликацией
+    pub fn deduplicated(&self) -> u64 {
+        self.seen.as_ref().map(SeenSet::skipped).unwrap_or(0)
+    }
+
+    /// Включает разметку адресов участников (метки и ENS) в алертах
+    pub fn with_labels(mut self, labels: SharedLabelResolver) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Сеть, в которой работает детектор; по умолчанию mainnet
+    pub fn with_chain(mut self, chain: SharedChainAdapter) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Потолок цены газа растёт вместе с прогнозом комиссий, см. `adaptive_thresholds`
+    pub fn with_gas_oracle(mut self, oracle: Arc<GasOracle>) -> Self {
+        self.gas_oracle = Some(oracle);
+        self
+    }
+
+    /// Повторные сэндвичи одной жертвы поднимают severity и дают `RepeatedTargeting`
+    pub fn with_victim_tracking(mut self, tracker: VictimTracker) -> Self {
+        self.victims = Some(tracker);
+        self
+    }
+
+    /// Профит относится на кластер атакующих по графу пополнений; в алертах — `attacker_cluster`.
+    /// Тот же граф стоит подключить источником обогащения, чтобы он видел переводы
+    pub fn with_funding(mut self, funding: Arc<FundingGraph>) -> Self {
+        self.funding = Some(funding);
+        self
+    }
+
+    /// Каждая транзакция сверяется с недавними на копирование calldata;
+    /// алерты с атакующим-ботом получают `attacker_fingerprint`
+    pub fn with_fingerprints(mut self, fingerprints: Arc<BotFingerprinter>) -> Self {
+        self.fingerprints = Some(fingerprints);
+        self
+    }
+
+    /// Деплои через фабрики CREATE2 сверяются с мемпулом на захват адреса и гонку за `initialize`
+    pub fn with_deployments(mut self, deployments: Arc<DeploymentWatch>) -> Self {
+        self.deployments = Some(deployments);
+        self
+    }
+
+    /// Severity сэндвичей с учётом размера сделки жертвы и класса токена
+    pub fn with_severity(mut self, severity: Arc<SeverityModel>) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Добавляет источник обогащения; источники вызываются в порядке добавления
+    pub fn with_enricher(mut self, enricher: Box<dyn Enricher>) -> Self {
+        self.enrichers.push(enricher);
+        self
+    }
+
+    /// Подключает пользовательские правила, проверяемые после встроенных детекторов
+    pub fn with_rules(mut self, rules: RuleEngine) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Подключает реестр: детекторы можно выключать и перенастраивать на лету
+    pub fn with_registry(mut self, registry: Arc<DetectorRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn rules(&self) -> Option<&RuleEngine> {
+        self.rules.as_ref()
+    }
+
+    /// Заменяет набор пользовательских правил целиком; `None` — правила выключены
+    pub fn set_rules(&mut self, rules: Option<RuleEngine>) {
+        self.rules = rules;
+    }
+
+    pub fn thresholds(&self) -> &MevThresholds {
+        &self.thresholds
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: MevThresholds) {
+        self.thresholds = thresholds;
+    }
+
+    /// Ожидающие транзакции с временем поступления
+    pub fn pending_entries(&self) -> Vec<&(Tx, u64)> {
+        self.pending_pool.txs.values().flatten().collect()
+    }
+
+    /// Заменяет пул ожидающих транзакций; просроченные по TTL отбрасываются
+    pub fn restore_pending(&mut self, entries: Vec<(Tx, u64)>) -> usize {
+        self.pending_pool.txs.clear();
+        for (tx, timestamp) in entries {
+            self.pending_pool
+                .txs
+                .entry(tx.to)
+                .or_default()
+                .push_back((tx, timestamp));
         }
+        self.pending_pool.cleanup();
+        self.pending_pool.txs.values().map(|q| q.len()).sum()
+    }
+
+    /// Сохраняет пул ожидающих транзакций, чтобы рестарт не терял кандидатов в жертвы.
+    /// Вызывается владельцем детектора по сигналу остановки.
+    pub fn save_pending(&self, store: &dyn Store) -> Result<usize, StoreError> {
+        let entries = self.pending_entries();
+        store.put_json(DETECTOR_NS, "pending_pool", &entries)?;
+        Ok(entries.len())
+    }
+
+    /// Загружает сохранённый пул из хранилища
+    pub fn load_pending(&mut self, store: &dyn Store) -> Result<usize, StoreError> {
+        let entries: Vec<(Tx, u64)> = store.get_json(DETECTOR_NS, "pending_pool")?.unwrap_or_default();
+        Ok(self.restore_pending(entries))
     }
 
     /// Анализирует транзакцию на все типы MEV
     pub fn analyze(&mut self, tx: Tx) -> Vec<MevAlert> {
+        self.analyze_with(tx, None, None, None)
+    }
+
+    /// То же, но с обогащением, посчитанным заранее (стадией конвейера)
+    pub fn analyze_enriched(&mut self, tx: Tx, enrichment: Enrichment) -> Vec<MevAlert> {
+        self.analyze_with(tx, None, Some(enrichment), None)
+    }
+
+    /// Анализ с известным хэшем транзакции; без хэша дедупликация идёт по отпечатку содержимого
+    pub fn analyze_hashed(&mut self, tx: Tx, hash: Option<H256>, enrichment: Option<Enrichment>) -> Vec<MevAlert> {
+        self.analyze_with(tx, hash, enrichment, None)
+    }
+
+    /// Анализ как задание симуляции: после продвижения головы цепи за `job`
+    /// оставшиеся симуляции пропускаются, транзакция всё равно попадает в пул
+    pub fn analyze_job(
+        &mut self,
+        tx: Tx,
+        hash: Option<H256>,
+        enrichment: Option<Enrichment>,
+        job: &SimulationJob,
+    ) -> Vec<MevAlert> {
+        self.analyze_with(tx, hash, enrichment, Some(job))
+    }
+
+    fn analyze_with(
+        &mut self,
+        tx: Tx,
+        hash: Option<H256>,
+        precomputed: Option<Enrichment>,
+        job: Option<&SimulationJob>,
+    ) -> Vec<MevAlert> {
+        if let Some(seen) = &mut self.seen {
+            let key = hash.unwrap_or_else(|| tx_fingerprint(&tx));
+            if !seen.insert(key, Instant::now()) {
+                return Vec::new();
+            }
+        }
+
         let mut alerts = Vec::new();
+        let registry = self.registry.as_ref();
+        let heuristics = Heuristics {
+            simulator: self.simulator.as_ref(),
+            labels: self.labels.as_ref(),
+            severity: self.severity.as_deref(),
+            chain: self.chain.as_ref(),
+            job,
+        };
+        let pending = self.pending_pool.txs.get(&tx.to);
+        let base = adaptive_thresholds(&self.thresholds, self.gas_oracle.as_deref());
 
-        if let Some(alert) = self.detect_frontrun(&tx) {
-            alerts.push(alert);
+        if let Some(thresholds) = effective_thresholds(registry, &base, "frontrun") {
+            let alert = heuristics.frontrun(pending, &tx, &thresholds);
+            record_hit(registry, "frontrun", alert.as_ref().is_some_and(|a| !trap::is_trap(a)));
+            alerts.extend(alert);
         }
 
-        alerts.extend(self.detect_sandwich(&tx));
+        if let Some(thresholds) = effective_thresholds(registry, &base, "sandwich") {
+            let found = heuristics.sandwich(pending, &tx, &thresholds);
+            record_hit(registry, "sandwich", found.iter().any(|a| !trap::is_trap(a)));
+            alerts.extend(found);
+        }
+
+        if let Some(deployments) = self
+            .deployments
+            .as_ref()
+            .filter(|_| effective_thresholds(registry, &base, "deployment_frontrun").is_some())
+        {
+            let found = deployments.observe(&tx);
+            record_hit(registry, "deployment_frontrun", !found.is_empty());
+            alerts.extend(found);
+        }
+
+        if let Some(victims) = &mut self.victims {
+            let repeated = victims.observe(&mut alerts);
+            alerts.extend(repeated);
+        }
+        if let Some(fingerprints) = &self.fingerprints {
+            fingerprints.observe(&tx);
+            fingerprints.annotate(&mut alerts);
+        }
+        if let Some(funding) = &self.funding {
+            funding.attribute(&mut alerts);
+        }
+
+        let rules = self
+            .rules
+            .as_ref()
+            .filter(|_| effective_thresholds(registry, &self.thresholds, "rules").is_some());
+        finish_alerts(&mut alerts, &tx, rules, registry, precomputed, &self.enrichers);
 
         self.pending_pool.push(tx);
 
         alerts
     }
+}
 
-    fn detect_frontrun(&self, new_tx: &Tx) -> Option<MevAlert> {
-        self.pending_pool.txs.get(&new_tx.to).and_then(|pending| {
-            pending.iter().find_map(|(existing, _)| {
-                if self.is_frontrun_candidate(existing, new_tx) {
-                    let profit = unsafe {
-                        ffi::simulate_profit(&self.simulator, existing, new_tx)
-                    };
-
-                    if profit >= self.thresholds.min_profit_eth {
-                        Some(self.build_alert(
-                            MevType::Frontrun,
-                            profit,
-                            json!({
-                                "victim_tx": existing,
-                                "attacker_tx": new_tx
-                            }),
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+/// Во сколько раз быстрая комиссия по прогнозу может превышать потолок `max_gas_price`
+const ADAPTIVE_GAS_HEADROOM: u64 = 3;
+
+/// В перегруженной сети честные транзакции дороже статического потолка:
+/// потолок поднимается до `ADAPTIVE_GAS_HEADROOM` × быстрая комиссия следующего блока
+pub(crate) fn adaptive_thresholds(base: &MevThresholds, oracle: Option<&GasOracle>) -> MevThresholds {
+    let mut thresholds = base.clone();
+    if let Some(forecast) = oracle.and_then(|o| o.forecast(1)) {
+        let (fast, _) = forecast.fees(FeeUrgency::Fast);
+        let adaptive = Gwei::from_wei(fast.saturating_mul(ADAPTIVE_GAS_HEADROOM.into()));
+        thresholds.max_gas_price = thresholds.max_gas_price.max(adaptive);
+    }
+    thresholds
+}
+
+/// Пороги детектора с учётом реестра; `None`, если детектор выключен
+pub(crate) fn effective_thresholds(
+    registry: Option<&Arc<DetectorRegistry>>,
+    base: &MevThresholds,
+    name: &str,
+) -> Option<MevThresholds> {
+    match registry {
+        Some(registry) => {
+            let settings = registry.settings(name);
+            settings.enabled.then(|| settings.thresholds(base))
+        }
+        None => Some(base.clone()),
+    }
+}
+
+pub(crate) fn record_hit(registry: Option<&Arc<DetectorRegistry>>, name: &str, hit: bool) {
+    if let Some(registry) = registry {
+        registry.record(name, hit);
+    }
+}
+
+/// Обогащение и пользовательские правила поверх встроенных детекторов.
+/// Обогащаем только то, что может дать алерт: найденные срабатывания или правила
+pub(crate) fn finish_alerts(
+    alerts: &mut Vec<MevAlert>,
+    tx: &Tx,
+    rules: Option<&RuleEngine>,
+    registry: Option<&Arc<DetectorRegistry>>,
+    precomputed: Option<Enrichment>,
+    enrichers: &[Box<dyn Enricher>],
+) {
+    if alerts.is_empty() && rules.is_none() {
+        return;
+    }
+
+    let enrichment = precomputed.unwrap_or_else(|| enrichment::enrich(enrichers, tx));
+    apply_enrichment(alerts, &enrichment);
+
+    if let Some(rules) = rules {
+        let custom = rules.evaluate(tx, alerts, &enrichment.to_value());
+        record_hit(registry, "rules", !custom.is_empty());
+        alerts.extend(custom);
+    }
+}
+
+/// Прикладывает данные обогащения к алертам и корректирует risk score
+fn apply_enrichment(alerts: &mut [MevAlert], enrichment: &Enrichment) {
+    if enrichment.is_empty() {
+        return;
+    }
+
+    for alert in alerts.iter_mut() {
+        alert.risk_score = (alert.risk_score as f64 + enrichment.risk_adjustment()).clamp(0.0, 1.0) as f32;
+        if let Some(meta) = alert.metadata.as_object_mut() {
+            meta.insert("enrichment".into(), enrichment.to_value());
+        }
+    }
+}
+
+/// Встроенные эвристики над очередью ожидающих транзакций одного контракта.
+/// Общие для `MevDetector` и `SharedDetector`
+pub(crate) struct Heuristics<'a> {
+    pub simulator: &'a dyn Simulator,
+    pub labels: Option<&'a SharedLabelResolver>,
+    pub severity: Option<&'a SeverityModel>,
+    pub chain: &'a dyn ChainAdapter,
+    pub job: Option<&'a SimulationJob>,
+}
+
+impl Heuristics<'_> {
+    /// Голова цепи ушла дальше задания: новые симуляции не запускаем
+    fn cancelled(&self) -> bool {
+        self.job.is_some_and(SimulationJob::is_cancelled)
+    }
+
+    pub fn frontrun(&self, pending: Option<&VecDeque<(Tx, u64)>>, new_tx: &Tx, thresholds: &MevThresholds) -> Option<MevAlert> {
+        // Без аукциона по цене газа перебить транзакцию в мемпуле нельзя
+        if !self.chain.fee_ordered_mempool() {
+            return None;
+        }
+        pending?.iter().take_while(|_| !self.cancelled()).find_map(|(existing, _)| {
+            if !is_frontrun_candidate(existing, new_tx, thresholds) {
+                return None;
+            }
+            let profit_eth = self.simulator.simulate_profit(existing, new_tx);
+            let profit = WeiAmount::from_eth(profit_eth);
+
+            (profit >= thresholds.min_profit).then(|| {
+                let mut alert = build_alert(
+                    MevType::Frontrun,
+                    profit,
+                    json!({
+                        "victim_tx": existing,
+                        "attacker_tx": new_tx,
+                        "labels": self.participant_labels(existing, new_tx)
+                    }),
+                );
+                let control = trap::as_sender(new_tx, &trap::control_sender());
+                if let Some(verdict) = trap::compare(profit_eth, self.simulator.simulate_profit(existing, &control)) {
+                    trap::mark(&mut alert, verdict);
                 }
+                alert
             })
         })
     }
 
-    fn detect_sandwich(&self, new_tx: &Tx) -> Vec<MevAlert> {
+    pub fn sandwich(&self, pending: Option<&VecDeque<(Tx, u64)>>, new_tx: &Tx, thresholds: &MevThresholds) -> Vec<MevAlert> {
         let mut alerts = Vec::new();
 
-        if let Some(pending) = self.pending_pool.txs.get(&new_tx.to) {
+        if let Some(pending) = pending.filter(|_| self.chain.fee_ordered_mempool()) {
             for (i, (tx1, _)) in pending.iter().enumerate() {
                 for (tx2, _) in pending.iter().skip(i + 1) {
-                    if self.is_sandwich_candidate(tx1, new_tx, tx2) {
-                        let profit = unsafe {
-                            ffi::simulate_sandwich(
-                                &self.simulator,
-                                tx1,
-                                new_tx,
-                                tx2
-                            )
-                        };
-
-                        if profit >= self.thresholds.min_profit_eth {
-                            alerts.push(self.build_alert(
+                    if is_sandwich_candidate(tx1, new_tx, tx2) {
+                        if self.cancelled() {
+                            return alerts;
+                        }
+                        let profit_eth = self.simulator.simulate_sandwich(tx1, new_tx, tx2);
+                        let profit = WeiAmount::from_eth(profit_eth);
+
+                        if profit >= thresholds.min_profit {
+                            let (trade, protection) = self.victim_outputs(tx1, new_tx, tx2);
+                            let mut alert = build_alert(
                                 MevType::Sandwich,
                                 profit,
                                 json!({
                                     "tx1": tx1,
                                     "tx2": tx2,
-                                    "target": new_tx
+                                    "target": new_tx,
+                                    "protection_value_eth": protection.to_eth_f64(),
+                                    "labels": self.participant_labels(new_tx, tx1)
                                 }),
-                            ));
+                            );
+                            if let Some(severity) = self.severity {
+                                let (score, breakdown) = severity.score(alert.risk_score, new_tx, trade, protection);
+                                alert.risk_score = score;
+                                alert.metadata["severity"] = breakdown;
+                            }
+                            if let Some(verdict) = self.trap_check(tx1, new_tx, tx2, profit_eth) {
+                                trap::mark(&mut alert, verdict);
+                            }
+                            alerts.push(alert);
                         }
                     }
                 }
@@ -170,35 +554,66 @@ impl MevDetector {
         alerts
     }
 
-    fn is_frontrun_candidate(&self, existing: &Tx, new: &Tx) -> bool {
-        existing.input == new.input &&
-        new.gas_price > existing.gas_price * 1.1 &&
-        new.gas_price <= self.thresholds.max_gas_price_gwei * 1e9
+    /// Повтор сэндвича от контрольного отправителя: токен, который отличает атакующего
+    /// (salmonella), даёт другую прибыль, и «выгодная» атака на деле ловушка
+    fn trap_check(&self, front: &Tx, victim: &Tx, back: &Tx, profit_eth: f64) -> Option<trap::TrapVerdict> {
+        let control = trap::control_sender();
+        let (front, back) = (trap::as_sender(front, &control), trap::as_sender(back, &control));
+        trap::compare(profit_eth, self.simulator.simulate_sandwich(&front, victim, &back))
     }
 
-    fn is_sandwich_candidate(&self, tx1: &Tx, tx2: &Tx, tx3: &Tx) -> bool {
-        tx1.input == tx3.input &&
-        tx2.input.len() >= 4 && 
-        tx1.gas_price < tx2.gas_price &&
-        tx3.gas_price > tx2.gas_price
+    /// Выход жертвы в блоке без `front`/`back` и сколько она сохранила бы без ног
+    /// атакующего (выход без них минус выход внутри сэндвича). Столько даёт приватный релей
+    fn victim_outputs(&self, front: &Tx, victim: &Tx, back: &Tx) -> (WeiAmount, WeiAmount) {
+        let clean = WeiAmount::from_eth(self.simulator.simulate_victim_output(victim));
+        let sandwiched = self.simulator.simulate_sandwiched_victim_output(front, victim, back);
+        (clean, clean.saturating_sub(WeiAmount::from_eth(sandwiched)))
     }
 
-    fn build_alert(&self, mev_type: MevType, profit: f64, metadata: serde_json::Value) -> MevAlert {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        MevAlert {
-            mev_type,
-            profit_eth: profit,
-            risk_score: self.calculate_risk(profit),
-            timestamp,
-            metadata,
+    /// Метки жертвы, атакующего и целевого контракта; `null`, если резолвер не подключён
+    fn participant_labels(&self, victim: &Tx, attacker: &Tx) -> serde_json::Value {
+        match self.labels {
+            Some(labels) => json!({
+                "victim": labels.resolve(&victim.from.to_string()),
+                "attacker": labels.resolve(&attacker.from.to_string()),
+                "target": labels.resolve(&victim.to.to_string()),
+            }),
+            None => serde_json::Value::Null,
         }
     }
+}
 
-    fn calculate_risk(&self, profit: f64) -> f8 {
-        (profit.log10() / 2.0).clamp(0.0, 1.0) 
+fn is_frontrun_candidate(existing: &Tx, new: &Tx, thresholds: &MevThresholds) -> bool {
+    existing.input == new.input &&
+    new.outbids(existing, 10) &&
+    new.gas_price <= thresholds.max_gas_price.amount()
+}
+
+fn is_sandwich_candidate(tx1: &Tx, tx2: &Tx, tx3: &Tx) -> bool {
+    tx1.input == tx3.input &&
+    tx2.input.len() >= 4 && 
+    tx1.gas_price < tx2.gas_price &&
+    tx3.gas_price > tx2.gas_price
+}
+
+pub(crate) fn build_alert(mev_type: MevType, profit: WeiAmount, metadata: serde_json::Value) -> MevAlert {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    MevAlert {
+        schema_version: crate::schema::MEV_ALERT_VERSION,
+        mev_type,
+        profit,
+        risk_score: calculate_risk(profit),
+        timestamp,
+        metadata,
+        latency: None,
+        confirmed_onchain: None,
     }
 }
+
+fn calculate_risk(profit: WeiAmount) -> f32 {
+    (profit.to_eth_f64().log10() / 2.0).clamp(0.0, 1.0) as f32
+}
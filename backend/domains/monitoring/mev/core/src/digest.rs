@@ -0,0 +1,386 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::i18n::{Locale, LocaleSelector, MessageCatalog};
+use crate::schema::DIGEST_VERSION;
+use crate::shutdown::ShutdownSignal;
+use crate::sink::{Message, MessageKind, Sink};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+const DAY: u64 = 86_400;
+
+/// Сколько самых атакуемых адресов показывать
+const TOP_SUBJECTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum DigestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    pub fn seconds(self) -> u64 {
+        match self {
+            DigestPeriod::Daily => DAY,
+            DigestPeriod::Weekly => 7 * DAY,
+        }
+    }
+
+    /// Ближайшая граница периода после `now`: полночь UTC, для недели — понедельник
+    pub fn next_boundary(self, now: u64) -> u64 {
+        // 1970-01-01 — четверг, до понедельника 4 дня
+        let offset = match self {
+            DigestPeriod::Daily => 0,
+            DigestPeriod::Weekly => 4 * DAY,
+        };
+        let period = self.seconds();
+        ((now.saturating_sub(offset)) / period + 1) * period + offset
+    }
+}
+
+/// Получатель дайджеста
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestTenant {
+    pub name: String,
+    pub period: DigestPeriod,
+    /// Адреса тенанта; пусто — все алерты
+    #[serde(default)]
+    pub subjects: Vec<String>,
+    /// Имена синков для доставки
+    pub sinks: Vec<String>,
+}
+
+impl DigestTenant {
+    pub fn covers(&self, subject: &str) -> bool {
+        self.subjects.is_empty() || self.subjects.iter().any(|s| s.eq_ignore_ascii_case(subject))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DigestRow {
+    pub label: String,
+    pub value: String,
+}
+
+/// Раздел, который дайджесту отдают другие подсистемы: риски валидаторов, портфели
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DigestSection {
+    pub title: String,
+    pub rows: Vec<DigestRow>,
+}
+
+#[async_trait]
+pub trait DigestSource: Send + Sync {
+    /// Изменения за `[since, until)`; `None` — нечего сообщить
+    async fn section(&self, tenant: &DigestTenant, since: u64, until: u64) -> Option<DigestSection>;
+}
+
+/// Алерты шины за период хранения — материал для дайджестов
+pub struct AlertHistory {
+    retention_seconds: u64,
+    alerts: Mutex<VecDeque<BusAlert>>,
+}
+
+impl Default for AlertHistory {
+    fn default() -> Self {
+        Self::new(DigestPeriod::Weekly.seconds())
+    }
+}
+
+impl AlertHistory {
+    pub fn new(retention_seconds: u64) -> Self {
+        Self { retention_seconds, alerts: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn push(&self, alert: BusAlert) {
+        let mut alerts = self.alerts.lock().unwrap();
+        let horizon = alert.timestamp.saturating_sub(self.retention_seconds);
+        while alerts.front().is_some_and(|a| a.timestamp < horizon) {
+            alerts.pop_front();
+        }
+        alerts.push_back(alert);
+    }
+
+    pub fn between(&self, since: u64, until: u64) -> Vec<BusAlert> {
+        self.alerts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|a| a.timestamp >= since && a.timestamp < until)
+            .cloned()
+            .collect()
+    }
+
+    /// Складывает алерты из шины до сигнала остановки
+    pub async fn collect(&self, bus: &AlertBus, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => self.push((*alert).clone()),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+/// Сводка тенанта за период
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Digest {
+    /// Версия формата, см. `schema::SCHEMAS`
+    #[serde(default = "crate::schema::unversioned")]
+    pub schema_version: u32,
+    pub tenant: String,
+    pub period: DigestPeriod,
+    pub since: u64,
+    pub until: u64,
+    pub by_level: BTreeMap<AlertLevel, usize>,
+    /// Адреса с наибольшим числом алертов
+    #[cfg_attr(feature = "server", schema(value_type = Vec<Vec<Object>>))]
+    pub top_subjects: Vec<(String, usize)>,
+    pub sections: Vec<DigestSection>,
+}
+
+impl Digest {
+    /// Самый тяжёлый уровень алертов периода
+    pub fn level(&self) -> AlertLevel {
+        self.by_level.keys().max().copied().unwrap_or(AlertLevel::Info)
+    }
+
+    fn text(catalog: &MessageCatalog, locale: Locale, key: &str, fallback: &str) -> String {
+        catalog.render_key(locale, key, &json!({})).unwrap_or_else(|| fallback.to_string())
+    }
+
+    pub fn title(&self, catalog: &MessageCatalog, locale: Locale) -> String {
+        let args = json!({
+            "tenant": self.tenant,
+            "since": format_date(self.since),
+            "until": format_date(self.until.saturating_sub(1)),
+        });
+        let key = match self.period {
+            DigestPeriod::Daily => "digest.daily",
+            DigestPeriod::Weekly => "digest.weekly",
+        };
+        catalog
+            .render_key(locale, key, &args)
+            .unwrap_or_else(|| format!("{} digest, {}", self.tenant, format_date(self.since)))
+    }
+
+    fn blocks(&self, catalog: &MessageCatalog, locale: Locale) -> Vec<DigestSection> {
+        let level_names = |level: AlertLevel| {
+            let key = format!("level.{}", json!(level).as_str().unwrap_or_default());
+            Self::text(catalog, locale, &key, &key)
+        };
+        let mut blocks = vec![DigestSection {
+            title: Self::text(catalog, locale, "digest.alerts_by_level", "New alerts"),
+            rows: self
+                .by_level
+                .iter()
+                .rev()
+                .map(|(level, n)| DigestRow { label: level_names(*level), value: n.to_string() })
+                .collect(),
+        }];
+        if !self.top_subjects.is_empty() {
+            blocks.push(DigestSection {
+                title: Self::text(catalog, locale, "digest.top_subjects", "Most targeted contracts"),
+                rows: self
+                    .top_subjects
+                    .iter()
+                    .map(|(subject, n)| DigestRow { label: subject.clone(), value: n.to_string() })
+                    .collect(),
+            });
+        }
+        blocks.extend(self.sections.iter().cloned());
+        blocks
+    }
+
+    pub fn to_markdown(&self, catalog: &MessageCatalog, locale: Locale) -> String {
+        let mut out = format!("# {}\n", self.title(catalog, locale));
+        for block in self.blocks(catalog, locale) {
+            out.push_str(&format!("\n## {}\n\n", block.title));
+            if block.rows.is_empty() {
+                out.push_str("—\n");
+            }
+            for row in &block.rows {
+                out.push_str(&format!("- {}: **{}**\n", row.label, row.value));
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self, catalog: &MessageCatalog, locale: Locale) -> String {
+        let mut out = format!("<h1>{}</h1>\n", escape(&self.title(catalog, locale)));
+        for block in self.blocks(catalog, locale) {
+            out.push_str(&format!("<h2>{}</h2>\n<table>\n", escape(&block.title)));
+            for row in &block.rows {
+                out.push_str(&format!("<tr><td>{}</td><td><b>{}</b></td></tr>\n", escape(&row.label), escape(&row.value)));
+            }
+            out.push_str("</table>\n");
+        }
+        out
+    }
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `YYYY-MM-DD` по UTC
+fn format_date(timestamp: u64) -> String {
+    // Обратное преобразование days_from_civil (Howard Hinnant)
+    let z = (timestamp / DAY) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Собирает дайджесты тенантов на границах периодов и отправляет их в синки
+pub struct DigestScheduler {
+    history: Arc<AlertHistory>,
+    tenants: Vec<DigestTenant>,
+    sources: Vec<Arc<dyn DigestSource>>,
+    sinks: HashMap<String, Arc<dyn Sink>>,
+    catalog: MessageCatalog,
+    locales: LocaleSelector,
+    errors: TaskErrors,
+}
+
+impl DigestScheduler {
+    pub fn new(history: Arc<AlertHistory>, tenants: Vec<DigestTenant>) -> Self {
+        Self {
+            history,
+            tenants,
+            sources: Vec::new(),
+            sinks: HashMap::new(),
+            catalog: MessageCatalog::new(),
+            locales: LocaleSelector::default(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Неудачные доставки дайджестов
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn with_source(mut self, source: Arc<dyn DigestSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn Sink>) -> Self {
+        self.sinks.insert(sink.name().to_string(), sink);
+        self
+    }
+
+    pub fn with_messages(mut self, catalog: MessageCatalog, locales: LocaleSelector) -> Self {
+        self.catalog = catalog;
+        self.locales = locales;
+        self
+    }
+
+    /// Дайджест тенанта за период, заканчивающийся в `until`
+    pub async fn build(&self, tenant: &DigestTenant, until: u64) -> Digest {
+        let since = until.saturating_sub(tenant.period.seconds());
+        let alerts: Vec<BusAlert> =
+            self.history.between(since, until).into_iter().filter(|a| tenant.covers(&a.subject)).collect();
+
+        let mut by_level = BTreeMap::new();
+        let mut by_subject: HashMap<String, usize> = HashMap::new();
+        for alert in &alerts {
+            *by_level.entry(alert.level).or_insert(0) += 1;
+            if !alert.subject.is_empty() {
+                *by_subject.entry(alert.subject.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+        let mut top_subjects: Vec<(String, usize)> = by_subject.into_iter().collect();
+        top_subjects.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_subjects.truncate(TOP_SUBJECTS);
+
+        let mut sections = Vec::new();
+        for source in &self.sources {
+            sections.extend(source.section(tenant, since, until).await);
+        }
+        Digest { schema_version: DIGEST_VERSION, tenant: tenant.name.clone(), period: tenant.period, since, until, by_level, top_subjects, sections }
+    }
+
+    /// Собирает и отправляет дайджест; возвращает число успешных доставок
+    pub async fn deliver(&self, tenant: &DigestTenant, until: u64) -> usize {
+        let digest = self.build(tenant, until).await;
+        let mut delivered = 0;
+        for name in &tenant.sinks {
+            let Some(sink) = self.sinks.get(name) else {
+                self.errors.record(format!("digest for tenant {} refers to unknown sink {}", tenant.name, name));
+                continue;
+            };
+            let locale = self.locales.resolve(name, Some(&tenant.name));
+            let message = Message::new(digest.title(&self.catalog, locale), digest.level(), digest.to_markdown(&self.catalog, locale))
+                .with_kind(MessageKind::Digest)
+                .with_html(digest.to_html(&self.catalog, locale))
+                .with_tenant(&tenant.name);
+            match sink.send(&message).await {
+                Ok(()) => delivered += 1,
+                Err(e) => self.errors.record(format!("digest delivery to {} for tenant {} failed: {}", name, tenant.name, e)),
+            }
+        }
+        delivered
+    }
+
+    /// Ждёт ближайшую границу периода и рассылает дайджесты, чьи периоды на ней закончились
+    pub async fn run(&self, mut shutdown: ShutdownSignal) {
+        loop {
+            let now = now();
+            let Some(next) = self.tenants.iter().map(|t| t.period.next_boundary(now)).min() else {
+                return;
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(next - now)) => {}
+                _ = shutdown.wait() => return,
+            }
+            for tenant in self.tenants.iter().filter(|t| t.period.next_boundary(now) == next) {
+                self.deliver(tenant, next).await;
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_boundaries_and_dates() {
+        // 2024-01-03, среда, 12:00 UTC
+        let now = 1_704_283_200;
+        assert_eq!(format_date(now), "2024-01-03");
+        assert_eq!(format_date(DigestPeriod::Daily.next_boundary(now)), "2024-01-04");
+        assert_eq!(format_date(DigestPeriod::Weekly.next_boundary(now)), "2024-01-08");
+        assert_eq!(DigestPeriod::Daily.next_boundary(1_704_240_000), 1_704_240_000 + DAY);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::bus::AlertLevel;
+use crate::digest::escape;
+use crate::i18n::fill;
+use crate::secrets::SecretString;
+use crate::sink::{Message, MessageKind, Sink, SinkError};
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Обёртка письма. Плейсхолдеры `{subject}`, `{level}`, `{color}`, `{body}`, `{suppressed}`;
+/// стили только в атрибутах: фигурные скобки CSS заняты шаблоном
+pub const DEFAULT_TEMPLATE: &str = r#"<html><body style="font-family: sans-serif; color: #222">
+<div style="border-left: 4px solid {color}; padding-left: 12px">
+<p style="color: {color}; font-weight: bold">{level}</p>
+{body}
+</div>
+<p style="color: #888; font-size: small">{suppressed}DeFinetly</p>
+</body></html>"#;
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("Invalid mailbox '{0}'")]
+    InvalidMailbox(String),
+
+    #[error("SMTP transport error: {0}")]
+    TransportError(String),
+
+    #[error("Template has malformed placeholders")]
+    InvalidTemplate,
+}
+
+/// Защищённость соединения с сервером
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// STARTTLS, обычно порт 587
+    #[default]
+    Starttls,
+    /// TLS с первого байта, обычно порт 465
+    Implicit,
+}
+
+/// Настройки почтового сервера и адресатов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    #[serde(default)]
+    pub username: Option<String>,
+    pub from: String,
+    /// Адресаты сообщений без тенанта и тенантов без своего списка
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// Тенант -> адресаты
+    #[serde(default)]
+    pub tenants: BTreeMap<String, Vec<String>>,
+    /// Отдельные алерты ниже уровня не отправляются; дайджесты уходят всегда
+    #[serde(default = "default_min_level")]
+    pub min_level: AlertLevel,
+    /// Не больше `max_per_window` писем с алертами на один список адресатов за окно
+    #[serde(default = "default_max_per_window")]
+    pub max_per_window: u32,
+    #[serde(default = "default_window_seconds")]
+    pub window_seconds: u64,
+}
+
+fn default_min_level() -> AlertLevel {
+    AlertLevel::Critical
+}
+
+fn default_max_per_window() -> u32 {
+    10
+}
+
+fn default_window_seconds() -> u64 {
+    3600
+}
+
+/// Окно ограничения для одного списка адресатов
+#[derive(Default)]
+struct Window {
+    started_at: u64,
+    sent: u32,
+    /// Алерты, не отправленные с начала окна; упоминаются в первом письме следующего
+    suppressed: u32,
+}
+
+/// Письма SMTP: отдельные алерты от `min_level` и дайджесты. На шторм алертов приходится
+/// не больше `max_per_window` писем, остальные только подсчитываются
+pub struct SmtpSink {
+    name: String,
+    settings: SmtpSettings,
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    template: String,
+    /// Тенанты без своего списка делят окно с сообщениями без тенанта
+    windows: Mutex<HashMap<Vec<String>, Window>>,
+}
+
+impl SmtpSink {
+    pub fn new(name: &str, settings: SmtpSettings, password: Option<&SecretString>) -> Result<Self, EmailError> {
+        let from = settings.from.parse().map_err(|_| EmailError::InvalidMailbox(settings.from.clone()))?;
+        for address in settings.recipients.iter().chain(settings.tenants.values().flatten()) {
+            address.parse::<Mailbox>().map_err(|_| EmailError::InvalidMailbox(address.clone()))?;
+        }
+        let mut builder = match settings.tls {
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host),
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host),
+        }
+        .map_err(|e| EmailError::TransportError(e.to_string()))?;
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&settings.username, password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.expose().to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            transport: builder.build(),
+            from,
+            settings,
+            template: DEFAULT_TEMPLATE.to_string(),
+            windows: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn with_template(mut self, template: &str) -> Result<Self, EmailError> {
+        if fill(template, |_| Some(Value::String(String::new()))).is_none() {
+            return Err(EmailError::InvalidTemplate);
+        }
+        self.template = template.to_string();
+        Ok(self)
+    }
+
+    fn recipients(&self, tenant: Option<&str>) -> &[String] {
+        tenant.and_then(|t| self.settings.tenants.get(t)).unwrap_or(&self.settings.recipients)
+    }
+
+    /// Можно ли отправить письмо с алертом; `Some(n)` — да, и `n` алертов до него было подавлено
+    fn admit(&self, recipients: &[String], now: u64) -> Option<u32> {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(recipients.to_vec()).or_default();
+        if now.saturating_sub(window.started_at) >= self.settings.window_seconds {
+            window.started_at = now;
+            window.sent = 0;
+        }
+        if window.sent >= self.settings.max_per_window {
+            window.suppressed += 1;
+            return None;
+        }
+        window.sent += 1;
+        Some(std::mem::take(&mut window.suppressed))
+    }
+
+    fn render(&self, message: &Message, suppressed: u32) -> String {
+        let color = match message.level {
+            AlertLevel::Critical => "#b00020",
+            AlertLevel::High => "#e65100",
+            AlertLevel::Medium => "#f9a825",
+            AlertLevel::Low | AlertLevel::Info => "#1565c0",
+        };
+        let body = message.html.clone().unwrap_or_else(|| {
+            message.markdown.split("\n\n").map(|p| format!("<p>{}</p>", escape(p).replace('\n', "<br>"))).collect()
+        });
+        let suppressed = match suppressed {
+            0 => String::new(),
+            n => format!("{} more alerts were not emailed because of the rate limit. ", n),
+        };
+        let level = serde_json::to_value(message.level).unwrap_or_default();
+        fill(&self.template, |name| match name {
+            "subject" => Some(Value::String(escape(&message.subject))),
+            "level" => Some(level.clone()),
+            "color" => Some(Value::String(color.into())),
+            "body" => Some(Value::String(body.clone())),
+            "suppressed" => Some(Value::String(suppressed.clone())),
+            _ => None,
+        })
+        .unwrap_or(body)
+    }
+}
+
+#[async_trait]
+impl Sink for SmtpSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError> {
+        let tenant = message.tenant.as_deref();
+        let recipients = self.recipients(tenant);
+        if recipients.is_empty() {
+            return Err(SinkError::Rejected(format!("no email recipients for tenant {}", tenant.unwrap_or("-"))));
+        }
+        // Дайджесты плановые и не ограничиваются
+        let suppressed = match message.kind {
+            MessageKind::Digest => 0,
+            MessageKind::Alert if message.level < self.settings.min_level => return Ok(()),
+            MessageKind::Alert => match self.admit(recipients, now()) {
+                Some(suppressed) => suppressed,
+                None => return Ok(()),
+            },
+        };
+
+        let mut email = lettre::Message::builder().from(self.from.clone()).subject(&message.subject);
+        for address in recipients {
+            // Адреса проверены в `new`
+            email = email.to(address.parse().map_err(|_| SinkError::Rejected(format!("invalid mailbox {}", address)))?);
+        }
+        let email = email
+            .multipart(MultiPart::alternative_plain_html(message.markdown.clone(), self.render(message, suppressed)))
+            .map_err(|e| SinkError::Rejected(e.to_string()))?;
+        self.transport.send(email).await.map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_counts_suppressed_alerts() {
+        let settings: SmtpSettings = toml::from_str(
+            r#"
+            host = "smtp.example.com"
+            from = "DeFinetly <alerts@example.com>"
+            recipients = ["oncall@example.com"]
+            max_per_window = 2
+            window_seconds = 60
+
+            [tenants]
+            acme = ["security@acme.example"]
+            "#,
+        )
+        .unwrap();
+        let sink = SmtpSink::new("email", settings, None).unwrap();
+        let acme = sink.recipients(Some("acme")).to_vec();
+
+        assert_eq!(sink.admit(&acme, 1_000), Some(0));
+        assert_eq!(sink.admit(&acme, 1_010), Some(0));
+        assert_eq!(sink.admit(&acme, 1_020), None);
+        assert_eq!(sink.admit(&acme, 1_030), None);
+        // Тенант без своего списка пишет тем же адресатам, что и сообщения без тенанта
+        let shared = sink.recipients(Some("globex")).to_vec();
+        assert_eq!(sink.admit(&shared, 1_030), Some(0));
+        assert_eq!(sink.admit(sink.recipients(None), 1_031), Some(0));
+        assert_eq!(sink.admit(sink.recipients(None), 1_032), None);
+        assert_eq!(sink.admit(&acme, 1_060), Some(2));
+    }
+}
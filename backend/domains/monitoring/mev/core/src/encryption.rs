@@ -0,0 +1,258 @@
+use crate::secrets::SecretString;
+use crate::store::{SharedStore, Store, StoreError};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use ethers::utils::hex;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Ключ конверта зашифрованного поля: `{"$encrypted": "v1:<hex nonce || ciphertext>"}`
+pub const ENVELOPE_KEY: &str = "$encrypted";
+
+const VERSION_PREFIX: &str = "v1:";
+const NONCE_LEN: usize = 12;
+
+/// Поля алертов с calldata и адресами жертв
+pub const DEFAULT_SENSITIVE_FIELDS: &[&str] = &[
+    "payload.metadata.victim_tx",
+    "payload.metadata.target",
+    "payload.metadata.victim",
+];
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+}
+
+impl From<EncryptionError> for StoreError {
+    fn from(e: EncryptionError) -> Self {
+        StoreError::EncryptionError(e.to_string())
+    }
+}
+
+/// Что видит читатель: расшифрованные поля или конверты
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ReadScope {
+    Sealed,
+    Decrypted,
+}
+
+/// Шифрование отдельных полей JSON (AES-256-GCM). Поле задаётся путём через точку;
+/// массивы на пути обходятся поэлементно, так что путь работает и для списка алертов
+pub struct FieldCipher {
+    cipher: Aes256Gcm,
+    fields: Vec<Vec<String>>,
+}
+
+impl FieldCipher {
+    /// `key` — 32 байта в hex
+    pub fn new(key: &SecretString, fields: &[String]) -> Result<Self, EncryptionError> {
+        let bytes = hex::decode(key.expose().trim().trim_start_matches("0x"))
+            .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?;
+        if bytes.len() != 32 {
+            return Err(EncryptionError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len())));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)),
+            fields: fields.iter().map(|f| f.split('.').map(str::to_string).collect()).collect(),
+        })
+    }
+
+    /// Заменяет назначенные поля конвертами; возвращает число зашифрованных полей
+    pub fn seal(&self, value: &mut Value) -> Result<usize, EncryptionError> {
+        let mut sealed = 0;
+        for path in &self.fields {
+            sealed += self.seal_path(value, path)?;
+        }
+        Ok(sealed)
+    }
+
+    fn seal_path(&self, value: &mut Value, path: &[String]) -> Result<usize, EncryptionError> {
+        if let Value::Array(items) = value {
+            let mut sealed = 0;
+            for item in items {
+                sealed += self.seal_path(item, path)?;
+            }
+            return Ok(sealed);
+        }
+        let Some((head, rest)) = path.split_first() else {
+            if value.is_null() || is_envelope(value) {
+                return Ok(0);
+            }
+            *value = self.envelope(value)?;
+            return Ok(1);
+        };
+        match value.get_mut(head.as_str()) {
+            Some(child) => self.seal_path(child, rest),
+            None => Ok(0),
+        }
+    }
+
+    fn envelope(&self, value: &Value) -> Result<Value, EncryptionError> {
+        let plaintext = serde_json::to_vec(value).map_err(|_| EncryptionError::EncryptionFailed)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext.as_ref()).map_err(|_| EncryptionError::EncryptionFailed)?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(serde_json::json!({ ENVELOPE_KEY: format!("{}{}", VERSION_PREFIX, hex::encode(sealed)) }))
+    }
+
+    /// Расшифровывает все конверты в значении, где бы они ни были
+    pub fn open(&self, value: &mut Value) -> Result<usize, EncryptionError> {
+        if let Some(sealed) = is_envelope(value).then(|| value[ENVELOPE_KEY].as_str().unwrap_or_default().to_string()) {
+            *value = self.decrypt(&sealed)?;
+            return Ok(1);
+        }
+        let mut opened = 0;
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    opened += self.open(item)?;
+                }
+            }
+            Value::Object(fields) => {
+                for field in fields.values_mut() {
+                    opened += self.open(field)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(opened)
+    }
+
+    fn decrypt(&self, sealed: &str) -> Result<Value, EncryptionError> {
+        let raw = sealed
+            .strip_prefix(VERSION_PREFIX)
+            .ok_or_else(|| EncryptionError::DecryptionFailed("unknown envelope version".into()))?;
+        let bytes = hex::decode(raw).map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+        if bytes.len() < NONCE_LEN {
+            return Err(EncryptionError::DecryptionFailed("envelope too short".into()));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| EncryptionError::DecryptionFailed("wrong key or corrupted data".into()))?;
+        serde_json::from_slice(&plaintext).map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))
+    }
+}
+
+fn is_envelope(value: &Value) -> bool {
+    value.as_object().is_some_and(|o| o.len() == 1 && o.get(ENVELOPE_KEY).is_some_and(Value::is_string))
+}
+
+/// Хранилище, шифрующее назначенные поля при записи в выбранные пространства имён.
+/// Чтение через `Store` расшифровывает: процесс узла авторизован; API выбирает
+/// представление через `get_scoped`
+pub struct EncryptedStore {
+    inner: SharedStore,
+    cipher: Arc<FieldCipher>,
+    namespaces: HashSet<String>,
+}
+
+impl EncryptedStore {
+    pub fn new(inner: SharedStore, cipher: Arc<FieldCipher>) -> Self {
+        Self { inner, cipher, namespaces: HashSet::new() }
+    }
+
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespaces.insert(namespace.to_string());
+        self
+    }
+
+    fn transform(&self, namespace: &str, bytes: Vec<u8>, scope: ReadScope) -> Result<Vec<u8>, StoreError> {
+        if scope == ReadScope::Sealed || !self.namespaces.contains(namespace) {
+            return Ok(bytes);
+        }
+        let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+            return Ok(bytes);
+        };
+        if self.cipher.open(&mut value)? == 0 {
+            return Ok(bytes);
+        }
+        Ok(serde_json::to_vec(&value)?)
+    }
+
+    pub fn get_scoped(&self, namespace: &str, key: &str, scope: ReadScope) -> Result<Option<Vec<u8>>, StoreError> {
+        self.inner.get(namespace, key)?.map(|bytes| self.transform(namespace, bytes, scope)).transpose()
+    }
+
+    pub fn list_scoped(&self, namespace: &str, scope: ReadScope) -> Result<Vec<(String, Vec<u8>)>, StoreError> {
+        self.inner
+            .list(namespace)?
+            .into_iter()
+            .map(|(key, bytes)| Ok((key, self.transform(namespace, bytes, scope)?)))
+            .collect()
+    }
+}
+
+impl Store for EncryptedStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        self.get_scoped(namespace, key, ReadScope::Decrypted)
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StoreError> {
+        if !self.namespaces.contains(namespace) {
+            return self.inner.put(namespace, key, value);
+        }
+        let Ok(mut json) = serde_json::from_slice::<Value>(value) else {
+            return self.inner.put(namespace, key, value);
+        };
+        match self.cipher.seal(&mut json)? {
+            0 => self.inner.put(namespace, key, value),
+            _ => self.inner.put(namespace, key, &serde_json::to_vec(&json)?),
+        }
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), StoreError> {
+        self.inner.delete(namespace, key)
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>, StoreError> {
+        self.list_scoped(namespace, ReadScope::Decrypted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryStore, StoreExt};
+    use serde_json::json;
+
+    #[test]
+    fn test_fields_sealed_at_rest_and_opened_on_read() {
+        let key = SecretString::new("11".repeat(32));
+        let cipher = Arc::new(FieldCipher::new(&key, &["payload.metadata.victim_tx".to_string()]).unwrap());
+        let inner: SharedStore = Arc::new(MemoryStore::new());
+        let store = EncryptedStore::new(inner.clone(), cipher).with_namespace("alert_buffer");
+
+        let alerts = json!([
+            { "kind": "frontrun", "payload": { "metadata": { "victim_tx": { "input": "0xdeadbeef" } } } },
+            { "kind": "sandwich", "payload": { "metadata": {} } },
+        ]);
+        store.put_json("alert_buffer", "pending", &alerts).unwrap();
+
+        let raw: Value = inner.get_json("alert_buffer", "pending").unwrap().unwrap();
+        assert!(is_envelope(&raw[0]["payload"]["metadata"]["victim_tx"]));
+        assert!(!raw.to_string().contains("deadbeef"));
+
+        let sealed: Value = serde_json::from_slice(&store.get_scoped("alert_buffer", "pending", ReadScope::Sealed).unwrap().unwrap()).unwrap();
+        assert_eq!(sealed, raw);
+        assert_eq!(store.get_json::<Value>("alert_buffer", "pending").unwrap().unwrap(), alerts);
+
+        let other = FieldCipher::new(&SecretString::new("22".repeat(32)), &[]).unwrap();
+        assert!(other.open(&mut raw.clone()).is_err());
+    }
+}
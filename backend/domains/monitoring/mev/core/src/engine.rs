@@ -0,0 +1,282 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::compat::U256;
+use crate::detector::{MevAlert, MevDetector, MevThresholds};
+use crate::tx::Tx;
+use crate::units::Gwei;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Сигнатура файла снимка
+const SNAPSHOT_MAGIC: &[u8; 4] = b"DFNS";
+/// Текущая версия формата; при изменении `EngineSnapshot` добавляется миграция в `decode_snapshot`
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Not an engine snapshot")]
+    BadMagic,
+
+    #[error("Unsupported snapshot version {0} (this build reads up to {SNAPSHOT_VERSION})")]
+    UnsupportedVersion(u16),
+
+    #[error("Snapshot checksum mismatch")]
+    ChecksumMismatch,
+
+    #[error("Snapshot is truncated")]
+    Truncated,
+
+    #[error("Encoding error: {0}")]
+    EncodingError(#[from] bincode::Error),
+}
+
+/// Полное состояние движка для передачи тёплому резерву
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub taken_at: u64,
+    pub pending: Vec<(Tx, u64)>,
+    pub thresholds: MevThresholds,
+    pub watchlist: Vec<ChecksummedAddress>,
+    /// Отпечаток алерта -> время последней отправки
+    pub alert_dedup: Vec<(String, u64)>,
+}
+
+/// Версия 1: суммы в f64 (value в ETH, gas_price в wei)
+#[derive(Deserialize)]
+struct SnapshotV1 {
+    taken_at: u64,
+    pending: Vec<(TxV1, u64)>,
+    thresholds: ThresholdsV1,
+    watchlist: Vec<String>,
+    alert_dedup: Vec<(String, u64)>,
+}
+
+#[derive(Deserialize)]
+struct TxV1 {
+    from: String,
+    to: String,
+    value: f64,
+    gas_price: f64,
+    input: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct ThresholdsV1 {
+    min_profit_eth: f64,
+    max_gas_price_gwei: f64,
+}
+
+impl From<SnapshotV1> for EngineSnapshot {
+    fn from(v1: SnapshotV1) -> Self {
+        let pending = v1
+            .pending
+            .into_iter()
+            .map(|(tx, seen)| {
+                let tx = Tx {
+                    from: tx.from.parse().unwrap_or_default(),
+                    to: tx.to.parse().unwrap_or_default(),
+                    value: WeiAmount::from_eth(tx.value),
+                    gas_price: WeiAmount::from_wei(U256::from(tx.gas_price.max(0.0) as u128)),
+                    input: tx.input,
+                };
+                (tx, seen)
+            })
+            .collect();
+
+        Self {
+            taken_at: v1.taken_at,
+            pending,
+            thresholds: MevThresholds {
+                min_profit: WeiAmount::from_eth(v1.thresholds.min_profit_eth),
+                max_gas_price: Gwei::new(v1.thresholds.max_gas_price_gwei).unwrap_or_default(),
+            },
+            watchlist: v1.watchlist.iter().filter_map(|a| a.parse().ok()).collect(),
+            alert_dedup: v1.alert_dedup,
+        }
+    }
+}
+
+/// Кодирует снимок: `magic | version u16 BE | sha256(payload) | payload (bincode)`
+pub fn encode_snapshot(snapshot: &EngineSnapshot) -> Result<Vec<u8>, SnapshotError> {
+    let payload = bincode::serialize(snapshot)?;
+    let mut out = Vec::with_capacity(4 + 2 + 32 + payload.len());
+    out.extend_from_slice(SNAPSHOT_MAGIC);
+    out.extend_from_slice(&SNAPSHOT_VERSION.to_be_bytes());
+    out.extend_from_slice(&Sha256::digest(&payload));
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+pub fn decode_snapshot(bytes: &[u8]) -> Result<EngineSnapshot, SnapshotError> {
+    if bytes.len() < 38 {
+        return Err(SnapshotError::Truncated);
+    }
+    if &bytes[..4] != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_be_bytes([bytes[4], bytes[5]]);
+    let (checksum, payload) = bytes[6..].split_at(32);
+    if Sha256::digest(payload).as_slice() != checksum {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+
+    match version {
+        1 => Ok(bincode::deserialize::<SnapshotV1>(payload)?.into()),
+        SNAPSHOT_VERSION => Ok(bincode::deserialize(payload)?),
+        other => Err(SnapshotError::UnsupportedVersion(other)),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Отпечаток алерта для подавления повторов
+fn alert_fingerprint(alert: &MevAlert) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{:?}", alert.mev_type));
+    hasher.update(alert.metadata.to_string());
+    ethers::utils::hex::encode(&hasher.finalize()[..16])
+}
+
+/// Движок детекции: детектор, список наблюдения и кэш подавления повторных алертов
+pub struct Engine {
+    detector: MevDetector,
+    watchlist: HashSet<ChecksummedAddress>,
+    alert_dedup: HashMap<String, u64>,
+    dedup_window_secs: u64,
+}
+
+impl Engine {
+    pub fn new(detector: MevDetector, dedup_window_secs: u64) -> Self {
+        Self {
+            detector,
+            watchlist: HashSet::new(),
+            alert_dedup: HashMap::new(),
+            dedup_window_secs,
+        }
+    }
+
+    pub fn detector(&self) -> &MevDetector {
+        &self.detector
+    }
+
+    pub fn detector_mut(&mut self) -> &mut MevDetector {
+        &mut self.detector
+    }
+
+    pub fn watch(&mut self, address: ChecksummedAddress) {
+        self.watchlist.insert(address);
+    }
+
+    pub fn unwatch(&mut self, address: &ChecksummedAddress) {
+        self.watchlist.remove(address);
+    }
+
+    pub fn watchlist(&self) -> &HashSet<ChecksummedAddress> {
+        &self.watchlist
+    }
+
+    pub fn is_watched(&self, address: &ChecksummedAddress) -> bool {
+        self.watchlist.contains(address)
+    }
+
+    /// Анализирует транзакцию; алерты, уже отправленные в пределах окна, подавляются
+    pub fn process(&mut self, tx: Tx) -> Vec<MevAlert> {
+        let now = now();
+        let window = self.dedup_window_secs;
+        self.alert_dedup.retain(|_, seen| now.saturating_sub(*seen) <= window);
+
+        let mut alerts = self.detector.analyze(tx);
+        alerts.retain(|alert| {
+            let fingerprint = alert_fingerprint(alert);
+            match self.alert_dedup.get(&fingerprint) {
+                Some(_) => false,
+                None => {
+                    self.alert_dedup.insert(fingerprint, now);
+                    true
+                }
+            }
+        });
+        alerts
+    }
+
+    /// Сериализует состояние в версионированный бинарный формат
+    pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut watchlist: Vec<ChecksummedAddress> = self.watchlist.iter().copied().collect();
+        watchlist.sort();
+
+        let pending: Vec<(Tx, u64)> = self.detector.pending_entries().into_iter().cloned().collect();
+
+        encode_snapshot(&EngineSnapshot {
+            taken_at: now(),
+            pending,
+            thresholds: self.detector.thresholds().clone(),
+            watchlist,
+            alert_dedup: self.alert_dedup.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        })
+    }
+
+    /// Заменяет состояние снимком с другого экземпляра
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<EngineSnapshot, SnapshotError> {
+        let mut snapshot = decode_snapshot(bytes)?;
+
+        self.detector.restore_pending(std::mem::take(&mut snapshot.pending));
+        self.detector.set_thresholds(snapshot.thresholds.clone());
+        self.watchlist = snapshot.watchlist.iter().copied().collect();
+        self.alert_dedup = snapshot.alert_dedup.iter().cloned().collect();
+
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::Address;
+
+    fn addr(byte: u8) -> ChecksummedAddress {
+        Address::repeat_byte(byte).into()
+    }
+
+    fn snapshot() -> EngineSnapshot {
+        EngineSnapshot {
+            taken_at: 1_700_000_000,
+            pending: vec![(
+                Tx {
+                    from: addr(0xaa),
+                    to: addr(0xbb),
+                    value: WeiAmount::from_eth(1.5),
+                    gas_price: WeiAmount::from_gwei(30.0),
+                    input: vec![0xa9, 0x05, 0x9c, 0xbb],
+                },
+                1_700_000_000,
+            )],
+            thresholds: MevThresholds { min_profit: WeiAmount::from_eth(0.05), max_gas_price: Gwei::new(500.0).unwrap() },
+            watchlist: vec![addr(0xbb)],
+            alert_dedup: vec![("abcd".into(), 1_700_000_000)],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_and_integrity() {
+        let bytes = encode_snapshot(&snapshot()).unwrap();
+        let decoded = decode_snapshot(&bytes).unwrap();
+        assert_eq!(decoded.pending[0].0.input, vec![0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(decoded.watchlist, vec![addr(0xbb)]);
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 0xff;
+        assert!(matches!(decode_snapshot(&corrupted), Err(SnapshotError::ChecksumMismatch)));
+
+        let mut future = bytes;
+        future[5] = 9;
+        assert!(matches!(decode_snapshot(&future), Err(SnapshotError::UnsupportedVersion(9))));
+    }
+}
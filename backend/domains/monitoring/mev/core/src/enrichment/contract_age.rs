@@ -0,0 +1,364 @@
+use super::{Enricher, Enrichment, EnrichmentError};
+use crate::backfill::{list_batch, BackfillJob, BatchOutcome};
+use crate::compat::{Address, H256};
+use crate::store::{SharedStore, Store, StoreError, StoreExt};
+use crate::tx::Tx;
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use ethers::types::{Action, BlockId, BlockNumber, Res};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Пространство имён хранилища: создания контрактов, найденные `ContractAgeBackfill`
+pub const CONTRACT_AGE_NS: &str = "contract_age";
+
+/// Сведения о создании контракта
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractCreation {
+    pub address: String,
+    pub deployer: String,
+    pub creation_block: u64,
+    pub creation_timestamp: u64,
+    pub creation_tx: Option<String>,
+}
+
+/// Источник данных о создании контракта для фонового дозаполнения
+#[async_trait]
+pub trait CreationSource: Send + Sync {
+    /// `Ok(None)` — по адресу нет контракта (EOA или код удалён)
+    async fn lookup(&self, address: Address) -> Result<Option<ContractCreation>, EnrichmentError>;
+}
+
+fn provider_err(e: impl std::fmt::Display) -> EnrichmentError {
+    EnrichmentError::ProviderError(e.to_string())
+}
+
+/// Поиск через узел: бинарный поиск блока появления кода и `trace_block`.
+/// Требует архивный узел с trace API (Erigon, Reth, Nethermind).
+pub struct TraceCreationSource<M> {
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> TraceCreationSource<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider }
+    }
+
+    async fn has_code_at(&self, address: Address, block: u64) -> Result<bool, EnrichmentError> {
+        let code = self
+            .provider
+            .get_code(address, Some(BlockId::Number(BlockNumber::Number(block.into()))))
+            .await
+            .map_err(provider_err)?;
+        Ok(!code.is_empty())
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> CreationSource for TraceCreationSource<M> {
+    async fn lookup(&self, address: Address) -> Result<Option<ContractCreation>, EnrichmentError> {
+        let head = self.provider.get_block_number().await.map_err(provider_err)?.as_u64();
+        if !self.has_code_at(address, head).await? {
+            return Ok(None);
+        }
+
+        // Первый блок, в котором по адресу есть код
+        let (mut lo, mut hi) = (0u64, head);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.has_code_at(address, mid).await? {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        let traces = self
+            .provider
+            .trace_block(BlockNumber::Number(lo.into()))
+            .await
+            .map_err(provider_err)?;
+
+        let trace = traces.iter().find(|t| {
+            matches!(&t.result, Some(Res::Create(created)) if created.address == address)
+        });
+        let Some(trace) = trace else {
+            return Ok(None);
+        };
+        let deployer = match &trace.action {
+            Action::Create(create) => create.from,
+            _ => return Ok(None),
+        };
+
+        let block = self
+            .provider
+            .get_block(lo)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| EnrichmentError::InvalidResponse(format!("block {} not found", lo)))?;
+
+        Ok(Some(ContractCreation {
+            address: format!("{:?}", address),
+            deployer: format!("{:?}", deployer),
+            creation_block: lo,
+            creation_timestamp: block.timestamp.as_u64(),
+            creation_tx: trace.transaction_hash.map(|h| format!("{:?}", h)),
+        }))
+    }
+}
+
+/// Поиск через Etherscan `getcontractcreation`; блок и время берутся у провайдера
+pub struct EtherscanCreationSource<M> {
+    client: reqwest::Client,
+    api_url: String,
+    api_key: String,
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> EtherscanCreationSource<M> {
+    pub fn new(api_url: String, api_key: String, provider: Arc<M>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url,
+            api_key,
+            provider,
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> CreationSource for EtherscanCreationSource<M> {
+    async fn lookup(&self, address: Address) -> Result<Option<ContractCreation>, EnrichmentError> {
+        let response: Value = self
+            .client
+            .get(&self.api_url)
+            .query(&[
+                ("module", "contract"),
+                ("action", "getcontractcreation"),
+                ("contractaddresses", &format!("{:?}", address)),
+                ("apikey", &self.api_key),
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // Etherscan отвечает status "0" и для EOA, и для ошибок — различаем по result
+        let Some(entry) = response["result"].as_array().and_then(|r| r.first()) else {
+            return match response["status"].as_str() {
+                Some("0") if response["message"].as_str() == Some("No data found") => Ok(None),
+                _ => Err(EnrichmentError::InvalidResponse(response.to_string())),
+            };
+        };
+
+        let field = |name: &str| {
+            entry[name]
+                .as_str()
+                .map(str::to_lowercase)
+                .ok_or_else(|| EnrichmentError::InvalidResponse(format!("missing {}", name)))
+        };
+        let deployer = field("contractCreator")?;
+        let tx_hash: H256 = field("txHash")?
+            .parse()
+            .map_err(|_| EnrichmentError::InvalidResponse("invalid txHash".into()))?;
+
+        let receipt = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| EnrichmentError::InvalidResponse(format!("receipt {:?} not found", tx_hash)))?;
+        let creation_block = receipt.block_number.unwrap_or_default().as_u64();
+
+        let block = self
+            .provider
+            .get_block(creation_block)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| EnrichmentError::InvalidResponse(format!("block {} not found", creation_block)))?;
+
+        Ok(Some(ContractCreation {
+            address: format!("{:?}", address),
+            deployer,
+            creation_block,
+            creation_timestamp: block.timestamp.as_u64(),
+            creation_tx: Some(format!("{:?}", tx_hash)),
+        }))
+    }
+}
+
+/// Обогащение возрастом целевого контракта и репутацией деплоера.
+/// Неизвестные адреса ставятся в очередь и дозаполняются через `backfill`.
+pub struct ContractAgeEnricher {
+    known: RwLock<HashMap<String, ContractCreation>>,
+    not_contracts: RwLock<HashSet<String>>,
+    pending: Mutex<HashSet<String>>,
+    flagged_deployers: RwLock<HashSet<String>>,
+    young_contract_hours: f64,
+    max_pending: usize,
+}
+
+impl ContractAgeEnricher {
+    pub fn new(young_contract_hours: f64, max_pending: usize) -> Self {
+        Self {
+            known: RwLock::new(HashMap::new()),
+            not_contracts: RwLock::new(HashSet::new()),
+            pending: Mutex::new(HashSet::new()),
+            flagged_deployers: RwLock::new(HashSet::new()),
+            young_contract_hours,
+            max_pending,
+        }
+    }
+
+    /// Помечает деплоера как скомпрометированного/вредоносного
+    pub fn flag_deployer(&self, deployer: &str) {
+        self.flagged_deployers.write().unwrap().insert(deployer.to_lowercase());
+    }
+
+    pub fn is_flagged(&self, deployer: &str) -> bool {
+        self.flagged_deployers.read().unwrap().contains(&deployer.to_lowercase())
+    }
+
+    /// Загружает заранее известные данные (например, из хранилища)
+    pub fn insert(&self, creation: ContractCreation) {
+        let key = creation.address.to_lowercase();
+        self.pending.lock().unwrap().remove(&key);
+        self.known.write().unwrap().insert(key, creation);
+    }
+
+    /// Создания, сохранённые `ContractAgeBackfill` до перезапуска; возвращает их число
+    pub fn load(&self, store: &dyn Store) -> Result<usize, StoreError> {
+        let creations: Vec<(String, ContractCreation)> = store.list_json(CONTRACT_AGE_NS)?;
+        let count = creations.len();
+        for (_, creation) in creations {
+            self.insert(creation);
+        }
+        Ok(count)
+    }
+
+    pub fn get(&self, address: &str) -> Option<ContractCreation> {
+        self.known.read().unwrap().get(&address.to_lowercase()).cloned()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Дозаполняет очередь неизвестных адресов из источника.
+    /// При ошибке необработанные адреса возвращаются в очередь.
+    pub async fn backfill(&self, source: &dyn CreationSource) -> Result<usize, EnrichmentError> {
+        let queue: Vec<String> = self.pending.lock().unwrap().drain().collect();
+        let mut processed = 0;
+
+        for (i, key) in queue.iter().enumerate() {
+            let Ok(address) = key.parse::<Address>() else {
+                self.not_contracts.write().unwrap().insert(key.clone());
+                continue;
+            };
+
+            match source.lookup(address).await {
+                Ok(Some(creation)) => self.insert(creation),
+                Ok(None) => {
+                    self.not_contracts.write().unwrap().insert(key.clone());
+                }
+                Err(e) => {
+                    self.pending.lock().unwrap().extend(queue[i..].iter().cloned());
+                    return Err(e);
+                }
+            }
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    fn queue(&self, key: String) {
+        if self.not_contracts.read().unwrap().contains(&key) {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() < self.max_pending {
+            pending.insert(key);
+        }
+    }
+}
+
+impl Enricher for ContractAgeEnricher {
+    fn enrich(&self, tx: &Tx, out: &mut Enrichment) {
+        let key = tx.to.to_string().to_lowercase();
+        let Some(creation) = self.get(&key) else {
+            self.queue(key);
+            return;
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let age_hours = now.saturating_sub(creation.creation_timestamp) as f64 / 3600.0;
+        let flagged = self.is_flagged(&creation.deployer);
+
+        out.set("target", "deployer", json!(creation.deployer));
+        out.set("target", "creation_block", json!(creation.creation_block));
+        out.set("target", "age_hours", json!(age_hours));
+        out.set("target", "deployer_flagged", json!(flagged));
+
+        if age_hours < self.young_contract_hours {
+            out.add_risk(0.1);
+        }
+        if flagged {
+            out.add_risk(0.2);
+        }
+    }
+}
+
+/// Заполнение `contract_age` для списка адресов: в отличие от `ContractAgeEnricher::backfill`,
+/// не ждёт, пока адрес встретится в мемпуле. Найденное сохраняется и попадает в обогащение
+pub struct ContractAgeBackfill {
+    enricher: Arc<ContractAgeEnricher>,
+    source: Arc<dyn CreationSource>,
+    store: SharedStore,
+    addresses: Vec<Address>,
+    batch_size: usize,
+}
+
+impl ContractAgeBackfill {
+    pub fn new(
+        enricher: Arc<ContractAgeEnricher>,
+        source: Arc<dyn CreationSource>,
+        store: SharedStore,
+        addresses: Vec<Address>,
+        batch_size: usize,
+    ) -> Self {
+        Self { enricher, source, store, addresses, batch_size }
+    }
+}
+
+#[async_trait]
+impl BackfillJob for ContractAgeBackfill {
+    fn name(&self) -> &str {
+        "contract_age"
+    }
+
+    async fn total(&self) -> Option<u64> {
+        Some(self.addresses.len() as u64)
+    }
+
+    async fn run_batch(&self, cursor: Option<Value>) -> Result<BatchOutcome, String> {
+        let (batch, next) = list_batch(&self.addresses, cursor.as_ref(), self.batch_size)?;
+        for address in batch {
+            let key = format!("{:?}", address);
+            if self.enricher.get(&key).is_some() {
+                continue;
+            }
+            if let Some(creation) = self.source.lookup(*address).await.map_err(|e| e.to_string())? {
+                self.store.put_json(CONTRACT_AGE_NS, &key, &creation).map_err(|e| e.to_string())?;
+                self.enricher.insert(creation);
+            }
+        }
+        Ok(BatchOutcome { processed: batch.len() as u64, next })
+    }
+}
@@ -0,0 +1,86 @@
+pub mod contract_age;
+#[cfg(feature = "audit")]
+pub mod screening;
+
+use crate::tx::Tx;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Ошибки источников обогащения
+#[derive(Debug, Error)]
+pub enum EnrichmentError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Unexpected response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Данные обогащения одной транзакции.
+/// Поля группируются по сущностям (`target`, `victim`, `attacker`) и доступны
+/// правилам как `enrichment.<entity>.<field>`.
+#[derive(Debug, Default, Clone)]
+pub struct Enrichment {
+    fields: Map<String, Value>,
+    risk_adjustment: f64,
+}
+
+impl Enrichment {
+    pub fn set(&mut self, entity: &str, field: &str, value: Value) {
+        let slot = self
+            .fields
+            .entry(entity)
+            .or_insert_with(|| Value::Object(Map::new()));
+
+        if let Value::Object(obj) = slot {
+            obj.insert(field.to_string(), value);
+        }
+    }
+
+    pub fn get(&self, entity: &str, field: &str) -> Option<&Value> {
+        self.fields.get(entity).and_then(|e| e.get(field))
+    }
+
+    /// Поправка к risk score, которую источник считает нужной (может быть отрицательной)
+    pub fn add_risk(&mut self, delta: f64) {
+        self.risk_adjustment += delta;
+    }
+
+    pub fn risk_adjustment(&self) -> f64 {
+        self.risk_adjustment
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.fields.clone())
+    }
+}
+
+/// Источник обогащения. Вызывается синхронно на горячем пути детектора,
+/// поэтому реализации должны отвечать из кэша, а медленные запросы
+/// выполнять фоном.
+pub trait Enricher: Send + Sync {
+    fn enrich(&self, tx: &Tx, out: &mut Enrichment);
+}
+
+/// Источник, который заполняется фоном и одновременно читается конвейером
+impl<E: Enricher + ?Sized> Enricher for std::sync::Arc<E> {
+    fn enrich(&self, tx: &Tx, out: &mut Enrichment) {
+        (**self).enrich(tx, out)
+    }
+}
+
+/// Прогоняет транзакцию через все источники по порядку
+pub fn enrich(enrichers: &[Box<dyn Enricher>], tx: &Tx) -> Enrichment {
+    let mut out = Enrichment::default();
+    for enricher in enrichers {
+        enricher.enrich(tx, &mut out);
+    }
+    out
+}
@@ -0,0 +1,314 @@
+use super::{Enricher, Enrichment};
+use crate::audit::{AuditError, AuditLog, AuditResult};
+use crate::bus::{AlertAnnotator, BusAlert};
+use crate::shutdown::ShutdownSignal;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use ethers::utils::hex;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Поправка к risk score за участие адреса из списка
+const SCREENING_RISK: f64 = 0.3;
+
+#[derive(Debug, Error)]
+pub enum ScreeningError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("List '{0}' contains no addresses")]
+    EmptyList(String),
+
+    #[error("Audit error: {0}")]
+    AuditError(#[from] AuditError),
+}
+
+/// Откуда берётся снимок списка
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl std::fmt::Display for ListSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListSource::File(path) => write!(f, "{}", path.display()),
+            ListSource::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Список из конфига: `ofac-sdn`, внутренние списки
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListSpec {
+    pub name: String,
+    pub source: ListSource,
+}
+
+/// Загруженный снимок списка
+#[derive(Debug, Clone)]
+pub struct Blocklist {
+    pub name: String,
+    /// SHA-256 снимка: по нему видно, какой версией помечен алерт
+    pub version: String,
+    pub updated_at: u64,
+    addresses: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn contains(&self, address: &str) -> bool {
+        self.addresses.contains(&address.to_lowercase())
+    }
+}
+
+/// Итог обновления списка (он же и пишется в журнал)
+#[derive(Debug, Clone, Serialize)]
+pub struct ListUpdate {
+    pub name: String,
+    pub source: String,
+    pub version: String,
+    pub previous_version: Option<String>,
+    pub entries: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Совпадение адреса со списком
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComplianceFlag {
+    pub address: String,
+    pub list: String,
+    pub version: String,
+}
+
+/// Все EVM-адреса в тексте. Формат снимка не важен: SDN XML/CSV OFAC держит адреса
+/// в полях «Digital Currency Address», внутренние списки — по одному в строке
+pub fn extract_addresses(text: &str) -> HashSet<String> {
+    let bytes = text.as_bytes();
+    let mut found = HashSet::new();
+    let mut i = 0;
+    while i + 42 <= bytes.len() {
+        let boundary = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        if boundary && bytes[i] == b'0' && (bytes[i + 1] | 0x20) == b'x' {
+            let body = &bytes[i + 2..i + 42];
+            let end_ok = bytes.get(i + 42).is_none_or(|c| !c.is_ascii_alphanumeric());
+            if end_ok && body.iter().all(u8::is_ascii_hexdigit) {
+                found.insert(text[i..i + 42].to_lowercase());
+                i += 42;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Скрининг адресов по блоклистам. Обновления списков пишутся в журнал аудита,
+/// совпадения — в обогащение транзакций и в payload алертов
+pub struct ScreeningEnricher {
+    lists: RwLock<BTreeMap<String, Blocklist>>,
+    audit: Option<Arc<AuditLog>>,
+    client: reqwest::Client,
+    /// Неудачные обновления списков
+    errors: TaskErrors,
+}
+
+impl Default for ScreeningEnricher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreeningEnricher {
+    pub fn new() -> Self {
+        Self { lists: RwLock::new(BTreeMap::new()), audit: None, client: reqwest::Client::new(), errors: TaskErrors::default() }
+    }
+
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Заменяет список снимком; пустой снимок отклоняется, чтобы сбой выгрузки не снял блокировки
+    pub fn update(&self, name: &str, source: &str, text: &str) -> Result<ListUpdate, ScreeningError> {
+        let addresses = extract_addresses(text);
+        if addresses.is_empty() {
+            return Err(ScreeningError::EmptyList(name.to_string()));
+        }
+        let version = hex::encode(Sha256::digest(text.as_bytes()));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        let mut lists = self.lists.write().unwrap();
+        let previous = lists.get(name);
+        if previous.is_some_and(|p| p.version == version) {
+            return Ok(ListUpdate {
+                name: name.to_string(),
+                source: source.to_string(),
+                previous_version: Some(version.clone()),
+                version,
+                entries: addresses.len(),
+                added: 0,
+                removed: 0,
+            });
+        }
+        let update = ListUpdate {
+            name: name.to_string(),
+            source: source.to_string(),
+            version: version.clone(),
+            previous_version: previous.map(|p| p.version.clone()),
+            entries: addresses.len(),
+            added: previous.map_or(addresses.len(), |p| addresses.difference(&p.addresses).count()),
+            removed: previous.map_or(0, |p| p.addresses.difference(&addresses).count()),
+        };
+        if let Some(audit) = &self.audit {
+            let payload = serde_json::to_value(&update).unwrap_or_default();
+            audit.append("screening", "list_update", source, payload, AuditResult::Success { tx_hash: None })?;
+        }
+        lists.insert(name.to_string(), Blocklist { name: name.to_string(), version, updated_at: now, addresses });
+        Ok(update)
+    }
+
+    pub async fn refresh(&self, spec: &ListSpec) -> Result<ListUpdate, ScreeningError> {
+        let text = match &spec.source {
+            ListSource::File(path) => std::fs::read_to_string(path)?,
+            ListSource::Url(url) => self.client.get(url).send().await?.error_for_status()?.text().await?,
+        };
+        self.update(&spec.name, &spec.source.to_string(), &text)
+    }
+
+    /// Обновляет списки с интервалом; при ошибке остаётся прежний снимок
+    pub async fn run(&self, specs: Vec<ListSpec>, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    for spec in &specs {
+                        if let Err(e) = self.refresh(spec).await {
+                            self.errors.record(format!("screening list {} refresh from {}: {}", spec.name, spec.source, e));
+                        }
+                    }
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+
+    pub fn lists(&self) -> Vec<(String, String, usize)> {
+        self.lists.read().unwrap().values().map(|l| (l.name.clone(), l.version.clone(), l.len())).collect()
+    }
+
+    pub fn screen(&self, address: &str) -> Vec<ComplianceFlag> {
+        self.lists
+            .read()
+            .unwrap()
+            .values()
+            .filter(|l| l.contains(address))
+            .map(|l| ComplianceFlag { address: address.to_lowercase(), list: l.name.clone(), version: l.version.clone() })
+            .collect()
+    }
+
+    /// Помечает алерт: проверяются субъект и все адреса в payload
+    pub fn annotate(&self, mut alert: BusAlert) -> BusAlert {
+        let mut addresses = extract_addresses(&alert.subject);
+        addresses.extend(extract_addresses(&alert.payload.to_string()));
+        let mut addresses: Vec<String> = addresses.into_iter().collect();
+        addresses.sort();
+
+        let flags: Vec<ComplianceFlag> = addresses.iter().flat_map(|a| self.screen(a)).collect();
+        if alert.payload.is_null() {
+            alert.payload = json!({});
+        }
+        if let Value::Object(payload) = &mut alert.payload {
+            payload.insert("compliance".into(), json!({ "flagged": !flags.is_empty(), "flags": flags }));
+        }
+        alert
+    }
+}
+
+impl AlertAnnotator for ScreeningEnricher {
+    fn annotate(&self, alert: BusAlert) -> BusAlert {
+        ScreeningEnricher::annotate(self, alert)
+    }
+}
+
+impl Enricher for ScreeningEnricher {
+    fn enrich(&self, tx: &Tx, out: &mut Enrichment) {
+        let mut flagged = false;
+        for (entity, address) in [("sender", tx.from.to_string()), ("target", tx.to.to_string())] {
+            let flags = self.screen(&address);
+            if flags.is_empty() {
+                continue;
+            }
+            flagged = true;
+            out.set(entity, "compliance_lists", json!(flags.iter().map(|f| &f.list).collect::<Vec<_>>()));
+        }
+        if flagged {
+            out.set("compliance", "flagged", json!(true));
+            out.add_risk(SCREENING_RISK);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::AlertLevel;
+
+    #[test]
+    fn test_extracts_addresses_only_on_word_boundaries() {
+        let sanctioned = "0x8589427373D6D84E98730D7795D8f6f8731FDA16";
+        let text = format!(
+            "Digital Currency Address - ETH {}; hash 0x{}; tail {}ff, x{}",
+            sanctioned,
+            "ab".repeat(32),
+            sanctioned,
+            sanctioned
+        );
+        let found = extract_addresses(&text);
+        assert_eq!(found, HashSet::from([sanctioned.to_lowercase()]));
+    }
+
+    #[test]
+    fn test_update_counts_changes_and_keeps_list_on_empty_snapshot() {
+        let (a, b, c) = ("0x".to_string() + &"a".repeat(40), "0x".to_string() + &"b".repeat(40), "0x".to_string() + &"c".repeat(40));
+        let screening = ScreeningEnricher::new();
+        let first = screening.update("internal", "file", &format!("{}\n{}", a, b)).unwrap();
+        assert_eq!((first.entries, first.added, first.removed, first.previous_version), (2, 2, 0, None));
+
+        let second = screening.update("internal", "file", &format!("{}\n{}", b, c.to_uppercase().replace("0X", "0x"))).unwrap();
+        assert_eq!((second.added, second.removed), (1, 1));
+        assert_eq!(second.previous_version.as_deref(), Some(first.version.as_str()));
+
+        assert!(matches!(screening.update("internal", "file", "no addresses"), Err(ScreeningError::EmptyList(_))));
+        assert_eq!(screening.screen(&c).len(), 1);
+        assert!(screening.screen(&a).is_empty());
+
+        let alert = BusAlert::new("mev", "sandwich", AlertLevel::High, b.to_uppercase().replace("0X", "0x"), "t".into());
+        let annotated = screening.annotate(alert);
+        assert_eq!(annotated.payload["compliance"]["flagged"], json!(true));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::address::ChecksummedAddress;
+use crate::compat::{keccak256, H256};
+use crate::detector::MevAlert;
+use crate::tx::Tx;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Минимальное превышение цены газа оригинала, чтобы копия считалась перебивающей
+const MIN_BUMP_PERCENT: u64 = 1;
+
+/// Сколько последних надбавок хранить в профиле
+const BUMP_SAMPLES: usize = 32;
+
+/// Сколько адресов-копировщиков помнить; сверх этого вытесняются давно не копировавшие
+const MAX_PROFILES: usize = 10_000;
+
+/// Сколько целей помнить в профиле; `distinct_targets` дальше не растёт
+const MAX_TARGETS: usize = 256;
+
+/// Отпечаток обобщённого фронтраннера: бот, который копирует чужую calldata
+/// с более высокой ценой газа, а не атакует конкретные контракты
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotFingerprint {
+    pub address: ChecksummedAddress,
+    pub copies: u64,
+    pub distinct_targets: usize,
+    /// Медианная надбавка к цене газа оригинала, %
+    pub median_bump_percent: f64,
+    /// Доля копий, где в calldata адрес жертвы заменён своим
+    pub substitution_share: f64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+#[derive(Default)]
+struct Profile {
+    copies: u64,
+    substituted: u64,
+    targets: HashSet<ChecksummedAddress>,
+    bumps: VecDeque<f64>,
+    first_seen: u64,
+    last_seen: u64,
+}
+
+struct Original {
+    from: ChecksummedAddress,
+    tx: Tx,
+}
+
+#[derive(Default)]
+struct State {
+    /// Нормализованная calldata -> первая увиденная транзакция с ней
+    originals: HashMap<H256, Original>,
+    order: VecDeque<H256>,
+    profiles: HashMap<ChecksummedAddress, Profile>,
+}
+
+/// Ищет ботов по поведению: одна и та же calldata (с точностью до адреса отправителя)
+/// от другого адреса и дороже. Бот получает отпечаток, когда копирует достаточно
+/// часто и на достаточно разных контрактах
+pub struct BotFingerprinter {
+    capacity: usize,
+    min_copies: u64,
+    min_targets: usize,
+    state: Mutex<State>,
+}
+
+impl BotFingerprinter {
+    /// `capacity` — сколько оригиналов помнить для сравнения
+    pub fn new(capacity: usize, min_copies: u64, min_targets: usize) -> Self {
+        Self { capacity: capacity.max(1), min_copies, min_targets, state: Mutex::new(State::default()) }
+    }
+
+    /// Сравнивает транзакцию с недавними; возвращает `true`, если это копия чужой
+    pub fn observe(&self, tx: &Tx) -> bool {
+        if tx.input.len() < 4 {
+            return false;
+        }
+        let key = normalized_input(&tx.input, &tx.from);
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+
+        let copied = match state.originals.get(&key) {
+            Some(original) if original.from != tx.from && tx.outbids(&original.tx, MIN_BUMP_PERCENT) => {
+                let original_price = original.tx.gas_price.to_eth_f64();
+                let bump = if original_price > 0.0 { (tx.gas_price.to_eth_f64() / original_price - 1.0) * 100.0 } else { 0.0 };
+                let substituted = original.tx.input != tx.input;
+                Some((bump, substituted))
+            }
+            Some(_) => None,
+            None => {
+                state.order.push_back(key);
+                state.originals.insert(key, Original { from: tx.from, tx: tx.clone() });
+                while state.order.len() > self.capacity {
+                    if let Some(old) = state.order.pop_front() {
+                        state.originals.remove(&old);
+                    }
+                }
+                None
+            }
+        };
+
+        let Some((bump, substituted)) = copied else {
+            return false;
+        };
+        let now = now();
+        if !state.profiles.contains_key(&tx.from) && state.profiles.len() >= MAX_PROFILES {
+            self.evict(&mut state.profiles);
+        }
+        let profile = state.profiles.entry(tx.from).or_insert_with(|| Profile { first_seen: now, ..Profile::default() });
+        profile.copies += 1;
+        profile.substituted += substituted as u64;
+        if profile.targets.len() < MAX_TARGETS {
+            profile.targets.insert(tx.to);
+        }
+        profile.bumps.push_back(bump);
+        if profile.bumps.len() > BUMP_SAMPLES {
+            profile.bumps.pop_front();
+        }
+        profile.last_seen = now;
+        true
+    }
+
+    /// Отпечаток адреса, если его поведение набрало порог
+    pub fn fingerprint(&self, address: &ChecksummedAddress) -> Option<BotFingerprint> {
+        let state = self.state.lock().unwrap();
+        let profile = state.profiles.get(address)?;
+        self.fingerprint_of(address, profile)
+    }
+
+    pub fn fingerprints(&self) -> Vec<BotFingerprint> {
+        let state = self.state.lock().unwrap();
+        let mut all: Vec<BotFingerprint> =
+            state.profiles.iter().filter_map(|(address, profile)| self.fingerprint_of(address, profile)).collect();
+        all.sort_by_key(|f| std::cmp::Reverse(f.copies));
+        all
+    }
+
+    /// Помечает алерты, где атакующий совпадает с отпечатком: `attacker_fingerprint` в metadata
+    pub fn annotate(&self, alerts: &mut [MevAlert]) {
+        for alert in alerts {
+            let attacker = ["attacker_tx", "tx1"]
+                .iter()
+                .find_map(|k| alert.metadata[*k]["from"].as_str())
+                .and_then(|from| from.parse::<ChecksummedAddress>().ok());
+            if let Some(fingerprint) = attacker.and_then(|a| self.fingerprint(&a)) {
+                alert.metadata["attacker_fingerprint"] = json!(fingerprint);
+            }
+        }
+    }
+
+    /// Вытесняет самый давний профиль, не набравший порога; отпечатки — только если других нет,
+    /// чтобы поток разовых копий не стёр известных ботов
+    fn evict(&self, profiles: &mut HashMap<ChecksummedAddress, Profile>) {
+        let stalest = profiles
+            .iter()
+            .min_by_key(|(_, p)| (p.copies >= self.min_copies && p.targets.len() >= self.min_targets, p.last_seen))
+            .map(|(address, _)| *address);
+        if let Some(address) = stalest {
+            profiles.remove(&address);
+        }
+    }
+
+    fn fingerprint_of(&self, address: &ChecksummedAddress, profile: &Profile) -> Option<BotFingerprint> {
+        if profile.copies < self.min_copies || profile.targets.len() < self.min_targets {
+            return None;
+        }
+        let mut bumps: Vec<f64> = profile.bumps.iter().copied().collect();
+        bumps.sort_by(f64::total_cmp);
+        Some(BotFingerprint {
+            address: *address,
+            copies: profile.copies,
+            distinct_targets: profile.targets.len(),
+            median_bump_percent: bumps[bumps.len() / 2],
+            substitution_share: profile.substituted as f64 / profile.copies as f64,
+            first_seen: profile.first_seen,
+            last_seen: profile.last_seen,
+        })
+    }
+}
+
+/// Хэш calldata, где адрес отправителя обнулён: копия с подменённым получателем выплаты
+/// совпадает с оригиналом
+fn normalized_input(input: &[u8], from: &ChecksummedAddress) -> H256 {
+    let needle = from.address();
+    let needle = needle.as_bytes();
+    let mut normalized = input.to_vec();
+    let mut i = 0;
+    while i + needle.len() <= normalized.len() {
+        if &normalized[i..i + needle.len()] == needle {
+            normalized[i..i + needle.len()].fill(0);
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    keccak256(normalized)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::WeiAmount;
+    use crate::compat::{Address, U256};
+    use crate::detector::MevType;
+
+    fn tx(from: u8, to: u8, gas_price_gwei: u64, recipient: u8) -> Tx {
+        // swap(..., address to): получатель выплаты в последнем слове calldata
+        let mut input = vec![0x38, 0xed, 0x17, 0x39];
+        input.extend([0u8; 12]);
+        input.extend(Address::repeat_byte(recipient).as_bytes());
+        Tx {
+            from: Address::repeat_byte(from).into(),
+            to: Address::repeat_byte(to).into(),
+            value: WeiAmount::default(),
+            gas_price: WeiAmount::from_wei(U256::from(gas_price_gwei * 1_000_000_000)),
+            input,
+        }
+    }
+
+    #[test]
+    fn test_fingerprints_copier_across_targets() {
+        let fingerprinter = BotFingerprinter::new(16, 2, 2);
+        for target in [0x10, 0x20] {
+            assert!(!fingerprinter.observe(&tx(0xaa, target, 10, 0xaa)));
+            // Та же calldata с адресом бота вместо жертвы и дороже
+            assert!(fingerprinter.observe(&tx(0xbb, target, 12, 0xbb)));
+        }
+        // Копия не дороже оригинала — не перебивание
+        assert!(!fingerprinter.observe(&tx(0xcc, 0x20, 10, 0xcc)));
+
+        let bot: ChecksummedAddress = Address::repeat_byte(0xbb).into();
+        let fingerprint = fingerprinter.fingerprint(&bot).unwrap();
+        assert_eq!((fingerprint.copies, fingerprint.distinct_targets), (2, 2));
+        assert!((fingerprint.median_bump_percent - 20.0).abs() < 1e-9);
+        assert_eq!(fingerprint.substitution_share, 1.0);
+
+        let mut alerts = vec![MevAlert {
+            schema_version: 1,
+            mev_type: MevType::Sandwich,
+            profit: WeiAmount::default(),
+            risk_score: 0.5,
+            timestamp: 0,
+            metadata: json!({ "attacker_tx": { "from": bot.to_string() } }),
+            latency: None,
+            confirmed_onchain: None,
+        }];
+        fingerprinter.annotate(&mut alerts);
+        assert_eq!(alerts[0].metadata["attacker_fingerprint"]["copies"], 2);
+    }
+
+    #[test]
+    fn test_profiles_are_bounded_and_keep_fingerprints() {
+        let fingerprinter = BotFingerprinter::new(1, 1, 1);
+        let bot: ChecksummedAddress = Address::repeat_byte(0xbb).into();
+        fingerprinter.observe(&tx(0xaa, 0x10, 10, 0xaa));
+        fingerprinter.observe(&tx(0xbb, 0x10, 12, 0xbb));
+        {
+            let mut state = fingerprinter.state.lock().unwrap();
+            for i in 1..MAX_PROFILES as u64 {
+                let address: ChecksummedAddress = Address::from_low_u64_be(i).into();
+                state.profiles.insert(address, Profile::default());
+            }
+        }
+        fingerprinter.observe(&tx(0xaa, 0x30, 10, 0xaa));
+        fingerprinter.observe(&tx(0xcc, 0x30, 12, 0xcc));
+
+        let state = fingerprinter.state.lock().unwrap();
+        assert_eq!(state.profiles.len(), MAX_PROFILES);
+        assert!(state.profiles.contains_key(&bot));
+        assert!(state.profiles.contains_key(&Address::repeat_byte(0xcc).into()));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::amount::WeiAmount;
+use crate::bus::{AlertBus, BusAlert};
+use crate::compat::{Address, H256, U256};
+use crate::detector::{MevAlert, MevType};
+use crate::shutdown::ShutdownSignal;
+use crate::state_diff::StateDiffer;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use ethers::providers::Middleware;
+use ethers::types::{BlockId, Transaction};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Алертов мемпула, ждущих блока с жертвой
+const MAX_AWAITING: usize = 1024;
+
+#[derive(Debug, Error)]
+pub enum ForensicsError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Block {0} not found")]
+    BlockNotFound(u64),
+}
+
+/// Транзакция блока в объёме, нужном для разбора порядка
+#[derive(Debug, Clone)]
+pub struct BlockTx {
+    pub hash: H256,
+    pub index: usize,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Vec<u8>,
+}
+
+impl From<&Transaction> for BlockTx {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            hash: tx.hash,
+            index: tx.transaction_index.map(|i| i.as_usize()).unwrap_or_default(),
+            from: tx.from,
+            to: tx.to,
+            value: tx.value,
+            input: tx.input.to_vec(),
+        }
+    }
+}
+
+/// Атака, найденная в добытом блоке
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct InBlockMev {
+    pub mev_type: MevType,
+    pub block: u64,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub attacker: Address,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub victim: H256,
+    #[cfg_attr(feature = "server", schema(value_type = Vec<String>))]
+    pub attacker_txs: Vec<H256>,
+    /// Прямые переводы атакующего на coinbase в этом блоке
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub coinbase_payment: U256,
+    /// 0.0 - 1.0: соседство в блоке, плата билдеру, обе ноги сэндвича от одного адреса
+    pub confidence: f64,
+}
+
+/// Порог, с которого находка считается реальной атакой, а не совпадением порядка
+pub const CONFIRMATION_THRESHOLD: f64 = 0.7;
+
+impl InBlockMev {
+    pub fn is_confirmed(&self) -> bool {
+        self.confidence >= CONFIRMATION_THRESHOLD
+    }
+}
+
+/// Прямой перевод ETH на coinbase — чаевые билдеру от поисковика
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct CoinbaseTransfer {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub tx: H256,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub from: Address,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub value: U256,
+    /// Индекс атаки в `BlockReport::mev`, которой принадлежит перевод
+    pub bundle: Option<usize>,
+}
+
+/// Последняя транзакция блока от coinbase: билдер передаёт выручку предлагающему валидатору
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ProposerPayment {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub tx: H256,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub proposer: Address,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub value: U256,
+}
+
+/// Чаевые на coinbase по отправителям. Видны только переводы верхнего уровня:
+/// `block.coinbase.transfer` внутри контракта без трейсов не различить
+pub fn coinbase_transfers(txs: &[BlockTx], coinbase: Address) -> Vec<CoinbaseTransfer> {
+    txs.iter()
+        .filter(|t| t.to == Some(coinbase) && t.from != coinbase && !t.value.is_zero())
+        .map(|t| CoinbaseTransfer { tx: t.hash, from: t.from, value: t.value, bundle: None })
+        .collect()
+}
+
+/// Плата билдера валидатору, если блок собран внешним билдером
+pub fn proposer_payment(txs: &[BlockTx], coinbase: Address) -> Option<ProposerPayment> {
+    let last = txs.last()?;
+    let proposer = last.to?;
+    (last.from == coinbase && proposer != coinbase && !last.value.is_zero())
+        .then_some(ProposerPayment { tx: last.hash, proposer, value: last.value })
+}
+
+/// Выручка билдера и MEV одного блока
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct BlockReport {
+    pub block: u64,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub coinbase: Address,
+    pub mev: Vec<InBlockMev>,
+    pub coinbase_transfers: Vec<CoinbaseTransfer>,
+    pub proposer_payment: Option<ProposerPayment>,
+    /// Сумма чаевых на coinbase
+    pub coinbase_transfers_eth: f64,
+    /// Сколько билдер заплатил валидатору последней транзакцией; 0 для локально собранного блока
+    pub builder_payment_eth: f64,
+}
+
+/// Разбор блока с выплатами: чаевые привязываются к атакам от того же отправителя
+pub fn block_report(block: u64, coinbase: Address, txs: &[BlockTx]) -> BlockReport {
+    let mev = classify_block(block, coinbase, txs);
+    let mut transfers = coinbase_transfers(txs, coinbase);
+    for transfer in &mut transfers {
+        transfer.bundle = mev.iter().position(|m| m.attacker == transfer.from);
+    }
+    let proposer_payment = proposer_payment(txs, coinbase);
+    let eth = |wei: U256| WeiAmount::from_wei(wei).to_eth_f64();
+    BlockReport {
+        block,
+        coinbase,
+        mev,
+        coinbase_transfers_eth: eth(transfers.iter().fold(U256::zero(), |acc, t| acc + t.value)),
+        builder_payment_eth: proposer_payment.as_ref().map(|p| eth(p.value)).unwrap_or_default(),
+        coinbase_transfers: transfers,
+        proposer_payment,
+    }
+}
+
+fn coinbase_payment(txs: &[BlockTx], coinbase: Address, payer: Address) -> U256 {
+    txs.iter()
+        .filter(|t| t.from == payer && t.to == Some(coinbase))
+        .fold(U256::zero(), |acc, t| acc + t.value)
+}
+
+fn selector(input: &[u8]) -> Option<&[u8]> {
+    input.get(..4)
+}
+
+/// Разбор порядка транзакций блока. Сэндвич — атакующий непосредственно до и после
+/// жертвы к тому же контракту; фронтран — тот же calldata сразу перед жертвой.
+/// Плата на coinbase отличает бандл от случайного соседства
+pub fn classify_block(block: u64, coinbase: Address, txs: &[BlockTx]) -> Vec<InBlockMev> {
+    let mut found = Vec::new();
+
+    for window in txs.windows(3) {
+        let (front, victim, back) = (&window[0], &window[1], &window[2]);
+        let same_target = front.to.is_some() && front.to == victim.to && back.to == victim.to;
+        if !same_target || front.from == victim.from || back.from != front.from {
+            continue;
+        }
+        let payment = coinbase_payment(txs, coinbase, front.from);
+        // Соседство и обе ноги от одного адреса
+        let mut confidence: f64 = 0.6;
+        if !payment.is_zero() {
+            confidence += 0.3;
+        }
+        if selector(&front.input) == selector(&back.input) {
+            confidence += 0.1;
+        }
+        found.push(InBlockMev {
+            mev_type: MevType::Sandwich,
+            block,
+            attacker: front.from,
+            victim: victim.hash,
+            attacker_txs: vec![front.hash, back.hash],
+            coinbase_payment: payment,
+            confidence: confidence.min(1.0),
+        });
+    }
+
+    for pair in txs.windows(2) {
+        let (attacker, victim) = (&pair[0], &pair[1]);
+        if attacker.from == victim.from || attacker.to != victim.to || attacker.input != victim.input {
+            continue;
+        }
+        // Уже учтено как передняя нога сэндвича
+        if found.iter().any(|f| f.attacker_txs.contains(&attacker.hash)) {
+            continue;
+        }
+        let payment = coinbase_payment(txs, coinbase, attacker.from);
+        let confidence = if payment.is_zero() { 0.5 } else { 0.9 };
+        found.push(InBlockMev {
+            mev_type: MevType::Frontrun,
+            block,
+            attacker: attacker.from,
+            victim: victim.hash,
+            attacker_txs: vec![attacker.hash],
+            coinbase_payment: payment,
+            confidence,
+        });
+    }
+
+    found
+}
+
+fn same_tx(mined: &BlockTx, tx: &Tx) -> bool {
+    mined.from == tx.from.address() && mined.to.unwrap_or_default() == tx.to.address()
+        && mined.input == tx.input
+}
+
+fn alert_tx(alert: &MevAlert, key: &str) -> Option<Tx> {
+    serde_json::from_value(alert.metadata.get(key)?.clone()).ok()
+}
+
+/// Разбор добытых блоков для проверки алертов мемпула
+pub struct BlockAnalyzer<M> {
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> BlockAnalyzer<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider }
+    }
+
+    pub async fn fetch(&self, block: u64) -> Result<(Address, Vec<BlockTx>), ForensicsError> {
+        let full = self
+            .provider
+            .get_block_with_txs(BlockId::Number(block.into()))
+            .await
+            .map_err(|e| ForensicsError::ProviderError(e.to_string()))?
+            .ok_or(ForensicsError::BlockNotFound(block))?;
+        let coinbase = full.author.unwrap_or_default();
+        Ok((coinbase, full.transactions.iter().map(BlockTx::from).collect()))
+    }
+
+    pub async fn analyze(&self, block: u64) -> Result<Vec<InBlockMev>, ForensicsError> {
+        let (coinbase, txs) = self.fetch(block).await?;
+        Ok(classify_block(block, coinbase, &txs))
+    }
+
+    pub async fn report(&self, block: u64) -> Result<BlockReport, ForensicsError> {
+        let (coinbase, txs) = self.fetch(block).await?;
+        Ok(block_report(block, coinbase, &txs))
+    }
+
+    /// Отмечает алерты, чьи жертва и атакующий попали в блок в подтверждённой атаке.
+    /// Алерты, транзакции которых в блок не попали, не трогаются
+    pub async fn confirm(&self, block: u64, alerts: &mut [MevAlert]) -> Result<usize, ForensicsError> {
+        let (coinbase, txs) = self.fetch(block).await?;
+        Ok(confirm_alerts(block, coinbase, &txs, alerts))
+    }
+}
+
+/// `BlockAnalyzer::confirm` по уже полученному блоку
+pub fn confirm_alerts(block: u64, coinbase: Address, txs: &[BlockTx], alerts: &mut [MevAlert]) -> usize {
+    let report = block_report(block, coinbase, txs);
+    let found = &report.mev;
+
+    let mut confirmed = 0;
+    for alert in alerts.iter_mut() {
+        let (victim_key, attacker_key) = match alert.mev_type {
+            MevType::Frontrun => ("victim_tx", "attacker_tx"),
+            MevType::Sandwich => ("target", "tx1"),
+            _ => continue,
+        };
+        let (Some(victim), Some(attacker)) = (alert_tx(alert, victim_key), alert_tx(alert, attacker_key)) else {
+            continue;
+        };
+        let Some(mined_victim) = txs.iter().find(|t| same_tx(t, &victim)) else {
+            continue;
+        };
+
+        let landed = found.iter().find(|f| {
+            f.mev_type == alert.mev_type
+                && f.victim == mined_victim.hash
+                && txs
+                    .iter()
+                    .filter(|t| f.attacker_txs.contains(&t.hash))
+                    .any(|t| same_tx(t, &attacker))
+        });
+        let is_confirmed = landed.map(InBlockMev::is_confirmed).unwrap_or(false);
+        alert.confirmed_onchain = Some(is_confirmed);
+        if let Some(metadata) = alert.metadata.as_object_mut() {
+            metadata.insert(
+                "onchain".into(),
+                json!({ "block": block, "match": landed, "builder_payment_eth": report.builder_payment_eth }),
+            );
+        }
+        if is_confirmed {
+            confirmed += 1;
+        }
+    }
+    confirmed
+}
+
+/// Алерт шины, ждущий блока с жертвой до `deadline`
+struct Awaiting {
+    alert: MevAlert,
+    deadline: u64,
+}
+
+/// Сверяет фронтраны и сэндвичи шины с новыми блоками. Подтверждённая атака публикуется ещё раз
+/// как `<kind>_confirmed` с совпадением из блока и, если задан `StateDiffer`, state diff атакующего
+pub struct AlertConfirmer<M> {
+    analyzer: BlockAnalyzer<M>,
+    differ: Option<StateDiffer<M>>,
+    /// Сколько блоков алерт ждёт включения жертвы
+    blocks: u64,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware> AlertConfirmer<M> {
+    pub fn new(provider: Arc<M>, blocks: u64) -> Self {
+        Self { analyzer: BlockAnalyzer::new(provider), differ: None, blocks: blocks.max(1), errors: TaskErrors::default() }
+    }
+
+    pub fn with_state_diff(mut self, differ: StateDiffer<M>) -> Self {
+        self.differ = Some(differ);
+        self
+    }
+
+    /// Ошибки провайдера при разборе блоков
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Ошибки снятия state diff
+    pub fn state_diff_errors(&self) -> Option<TaskErrorsSnapshot> {
+        self.differ.as_ref().map(StateDiffer::errors)
+    }
+
+    /// Раз в `interval` разбирает блоки, добытые с прошлой проверки
+    pub async fn run(&self, bus: &AlertBus, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        let mut ticker = tokio::time::interval(interval);
+        let mut awaiting: Vec<Awaiting> = Vec::new();
+        let mut incoming: Vec<MevAlert> = Vec::new();
+        let mut checked: Option<u64> = None;
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => {
+                        incoming.extend(confirmable(&alert));
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+            let head = match self.analyzer.provider.get_block_number().await {
+                Ok(head) => head.as_u64(),
+                Err(e) => {
+                    self.errors.record(ForensicsError::ProviderError(e.to_string()));
+                    continue;
+                }
+            };
+            awaiting.extend(incoming.drain(..).map(|alert| Awaiting { alert, deadline: head + self.blocks }));
+            let overflow = awaiting.len().saturating_sub(MAX_AWAITING);
+            awaiting.drain(..overflow);
+
+            // Отстав больше чем на `blocks`, старые блоки не разбираются: их алерты уже истекли
+            let mut next = checked.map_or(head, |checked| (checked + 1).max(head.saturating_sub(self.blocks)));
+            while next <= head && !awaiting.is_empty() {
+                let Some((coinbase, txs)) = self.errors.check(self.analyzer.fetch(next).await) else {
+                    break;
+                };
+                awaiting = self.settle(bus, next, coinbase, &txs, awaiting).await;
+                next += 1;
+            }
+            if awaiting.is_empty() {
+                next = head + 1;
+            }
+            checked = next.checked_sub(1);
+            awaiting.retain(|a| a.deadline >= next);
+        }
+    }
+
+    /// Публикует подтверждённые в блоке атаки; жертва без атаки снимается, остальные ждут дальше
+    async fn settle(&self, bus: &AlertBus, block: u64, coinbase: Address, txs: &[BlockTx], awaiting: Vec<Awaiting>) -> Vec<Awaiting> {
+        let mut still = Vec::new();
+        for mut entry in awaiting {
+            confirm_alerts(block, coinbase, txs, std::slice::from_mut(&mut entry.alert));
+            match entry.alert.confirmed_onchain {
+                Some(true) => {
+                    if let Some(differ) = &self.differ {
+                        differ.attach_confirmed(std::slice::from_mut(&mut entry.alert)).await;
+                    }
+                    bus.publish(confirmed_alert(&entry.alert, block));
+                }
+                Some(false) => {}
+                None => still.push(entry),
+            }
+        }
+        still
+    }
+}
+
+/// Алерт мемпула, который можно сверить с блоком: фронтран или сэндвич детектора
+fn confirmable(alert: &BusAlert) -> Option<MevAlert> {
+    if alert.source != "mev" || !matches!(alert.kind.as_str(), "frontrun" | "sandwich") {
+        return None;
+    }
+    serde_json::from_value(alert.payload.clone()).ok()
+}
+
+fn confirmed_alert(alert: &MevAlert, block: u64) -> BusAlert {
+    let mut confirmed = BusAlert::from(alert);
+    confirmed.kind = format!("{}_confirmed", confirmed.kind);
+    confirmed.title = format!("{} confirmed in block {}", confirmed.title, block);
+    confirmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(index: usize, from: u8, to: u8, input: &[u8], value: u64) -> BlockTx {
+        BlockTx {
+            hash: H256::repeat_byte(index as u8 + 1),
+            index,
+            from: Address::repeat_byte(from),
+            to: Some(Address::repeat_byte(to)),
+            value: U256::from(value),
+            input: input.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_paid_sandwich_is_confirmed_and_coincidence_is_not() {
+        let coinbase = Address::repeat_byte(0xcb);
+        let swap = [0x38, 0xed, 0x17, 0x39, 1];
+        let txs = vec![
+            tx(0, 0xaa, 0x10, &swap, 0),
+            tx(1, 0x01, 0x10, &[0x38, 0xed, 0x17, 0x39, 2], 0),
+            tx(2, 0xaa, 0x10, &swap, 0),
+            tx(3, 0xaa, 0xcb, &[], 1_000),
+        ];
+        let found = classify_block(1, coinbase, &txs);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].mev_type, MevType::Sandwich);
+        assert!(found[0].is_confirmed());
+
+        // Без платы билдеру и с разными функциями — похоже на совпадение
+        let unpaid = vec![
+            tx(0, 0xaa, 0x10, &[1, 2, 3, 4], 0),
+            tx(1, 0x01, 0x10, &[5, 6, 7, 8], 0),
+            tx(2, 0xaa, 0x10, &[9, 9, 9, 9], 0),
+        ];
+        assert!(!classify_block(1, coinbase, &unpaid)[0].is_confirmed());
+    }
+
+    #[test]
+    fn test_block_report_attributes_tips_and_reads_proposer_payment() {
+        let coinbase = Address::repeat_byte(0xcb);
+        let swap = [0x38, 0xed, 0x17, 0x39, 1];
+        let txs = vec![
+            tx(0, 0xaa, 0x10, &swap, 0),
+            tx(1, 0x01, 0x10, &[0x38, 0xed, 0x17, 0x39, 2], 0),
+            tx(2, 0xaa, 0x10, &swap, 0),
+            tx(3, 0xaa, 0xcb, &[], 3_000_000_000_000_000),
+            tx(4, 0x02, 0xcb, &[1], 1_000_000_000_000_000),
+            tx(5, 0xcb, 0x77, &[], 2_000_000_000_000_000),
+        ];
+        let report = block_report(1, coinbase, &txs);
+        assert_eq!(report.coinbase_transfers.len(), 2);
+        assert_eq!(report.coinbase_transfers[0].bundle, Some(0));
+        assert_eq!(report.coinbase_transfers[1].bundle, None);
+        assert!((report.coinbase_transfers_eth - 0.004).abs() < 1e-9);
+        assert_eq!(report.proposer_payment.as_ref().unwrap().proposer, Address::repeat_byte(0x77));
+        assert!((report.builder_payment_eth - 0.002).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::detector::MevAlert;
+use crate::enrichment::{Enricher, Enrichment};
+use crate::tx::Tx;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// Адрес, пополнивший больше стольких новых адресов, — биржа или кран, а не владелец кластера
+pub const DEFAULT_MAX_FANOUT: usize = 50;
+/// Сколько адресов граф помнит; старые вытесняются вместе со своими рёбрами
+pub const DEFAULT_CAPACITY: usize = 1_000_000;
+
+/// Сводка по кластеру атакующих: профит без дробления по EOA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ClusterStats {
+    pub cluster_id: String,
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub profit: WeiAmount,
+    pub attacks: u64,
+    /// Адреса кластера, замеченные в атаках
+    #[cfg_attr(feature = "server", schema(value_type = Vec<String>))]
+    pub attackers: Vec<ChecksummedAddress>,
+}
+
+#[derive(Default)]
+struct Graph {
+    /// Кто первым пополнил адрес
+    funded_by: HashMap<ChecksummedAddress, ChecksummedAddress>,
+    /// Явные связи; объединяют кластеры и через хабы
+    linked: HashMap<ChecksummedAddress, ChecksummedAddress>,
+    /// Кого адрес пополнил первым переводом; больше `max_fanout + 1` не хранится
+    funded: HashMap<ChecksummedAddress, HashSet<ChecksummedAddress>>,
+    /// Адреса, уже получившие первое пополнение или отправлявшие транзакции
+    known: HashSet<ChecksummedAddress>,
+    /// Порядок появления в `known`: старые адреса вытесняются сверх `capacity`
+    order: VecDeque<ChecksummedAddress>,
+    excluded: HashSet<ChecksummedAddress>,
+    /// Атаки по адресу первой ноги; на кластеры сводятся при чтении
+    stats: HashMap<ChecksummedAddress, (WeiAmount, u64, HashSet<ChecksummedAddress>)>,
+}
+
+impl Graph {
+    /// `true`, если адрес раньше не встречался
+    fn remember(&mut self, address: ChecksummedAddress, capacity: usize) -> bool {
+        if !self.known.insert(address) {
+            return false;
+        }
+        self.order.push_back(address);
+        while self.order.len() > capacity {
+            let Some(old) = self.order.pop_front() else { break };
+            self.forget(old);
+        }
+        true
+    }
+
+    /// Вытесненный адрес больше не связывает пополненных им
+    fn forget(&mut self, address: ChecksummedAddress) {
+        self.known.remove(&address);
+        self.funded_by.remove(&address);
+        for child in self.funded.remove(&address).into_iter().flatten() {
+            if self.funded_by.get(&child) == Some(&address) {
+                self.funded_by.remove(&child);
+            }
+        }
+    }
+
+    /// Источник, через который кластер не продолжается: исключённый адрес или хаб
+    fn stops_at(&self, funder: &ChecksummedAddress, max_fanout: usize) -> bool {
+        self.excluded.contains(funder) || self.funded.get(funder).is_some_and(|children| children.len() > max_fanout)
+    }
+
+    /// Самый ранний источник средств. Хаб определяется на момент запроса, поэтому
+    /// пополненные им до превышения `max_fanout` тоже перестают считаться одним кластером
+    fn root(&self, address: ChecksummedAddress, max_fanout: usize) -> ChecksummedAddress {
+        let mut node = address;
+        let mut visited = HashSet::new();
+        while visited.insert(node) {
+            let next = self
+                .linked
+                .get(&node)
+                .or_else(|| self.funded_by.get(&node).filter(|funder| !self.stops_at(funder, max_fanout)));
+            match next {
+                Some(next) => node = *next,
+                None => break,
+            }
+        }
+        node
+    }
+}
+
+/// Граф пополнений: EOA, пополненные из одного источника, — один кластер.
+/// Строится по наблюдаемым переводам ETH; хабы с большим числом пополнений не объединяют
+pub struct FundingGraph {
+    max_fanout: usize,
+    capacity: usize,
+    graph: Mutex<Graph>,
+}
+
+impl Default for FundingGraph {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FANOUT, DEFAULT_CAPACITY)
+    }
+}
+
+impl FundingGraph {
+    pub fn new(max_fanout: usize, capacity: usize) -> Self {
+        Self { max_fanout, capacity: capacity.max(1), graph: Mutex::new(Graph::default()) }
+    }
+
+    /// Адрес никогда не объединяет кластеры как источник (биржа, мост, роутер)
+    pub fn exclude(&self, address: ChecksummedAddress) {
+        self.graph.lock().unwrap().excluded.insert(address);
+    }
+
+    /// Перевод `value` с `from` на `to`. Первый перевод на новый адрес — ребро пополнения
+    pub fn record_transfer(&self, from: ChecksummedAddress, to: ChecksummedAddress, value: WeiAmount) {
+        if from == to || value == WeiAmount::default() || from.is_zero() || to.is_zero() {
+            return;
+        }
+        let mut graph = self.graph.lock().unwrap();
+        graph.remember(from, self.capacity);
+        if !graph.remember(to, self.capacity) || graph.excluded.contains(&from) {
+            return;
+        }
+
+        let children = graph.funded.entry(from).or_default();
+        if children.len() <= self.max_fanout {
+            children.insert(to);
+        }
+        graph.funded_by.insert(to, from);
+    }
+
+    /// Явная связь, найденная вне мемпула (трассировка, ручное расследование)
+    pub fn link(&self, funder: ChecksummedAddress, funded: ChecksummedAddress) {
+        let mut graph = self.graph.lock().unwrap();
+        let (funder, funded) = (graph.root(funder, self.max_fanout), graph.root(funded, self.max_fanout));
+        if funder != funded {
+            graph.linked.insert(funded, funder);
+        }
+    }
+
+    pub fn cluster_id(&self, address: &ChecksummedAddress) -> String {
+        let root = self.graph.lock().unwrap().root(*address, self.max_fanout);
+        format!("cluster-{}", root)
+    }
+
+    /// Добавляет `attacker_cluster` в алерты и относит профит на кластер, а не на EOA.
+    /// Сэндвич, ноги которого отправлены разными адресами одного кластера, — одна атака кластера
+    pub fn attribute(&self, alerts: &mut [MevAlert]) {
+        let mut graph = self.graph.lock().unwrap();
+        for alert in alerts {
+            let legs: Vec<ChecksummedAddress> = ["attacker_tx", "tx1", "tx2"]
+                .iter()
+                .filter_map(|k| alert.metadata[*k]["from"].as_str())
+                .filter_map(|from| from.parse().ok())
+                .collect();
+            let Some(first) = legs.first().copied() else {
+                continue;
+            };
+            let root = graph.root(first, self.max_fanout);
+            let split = legs.iter().any(|leg| *leg != first);
+
+            let entry = graph.stats.entry(first).or_default();
+            entry.0 = entry.0 + alert.profit;
+            entry.1 += 1;
+            entry.2.extend(legs.iter().copied());
+
+            alert.metadata["attacker_cluster"] = json!({
+                "cluster_id": format!("cluster-{}", root),
+                // Ноги от разных EOA одного кластера: профит считается один раз
+                "split_accounts": split,
+            });
+        }
+    }
+
+    /// Кластеры по убыванию суммарного профита
+    pub fn clusters(&self) -> Vec<ClusterStats> {
+        let graph = self.graph.lock().unwrap();
+        let mut by_root: HashMap<ChecksummedAddress, (WeiAmount, u64, HashSet<ChecksummedAddress>)> = HashMap::new();
+        for (first, (profit, attacks, attackers)) in &graph.stats {
+            let entry = by_root.entry(graph.root(*first, self.max_fanout)).or_default();
+            entry.0 = entry.0 + *profit;
+            entry.1 += attacks;
+            entry.2.extend(attackers.iter().copied());
+        }
+        let mut all: Vec<ClusterStats> = by_root
+            .into_iter()
+            .map(|(root, (profit, attacks, attackers))| {
+                let mut attackers: Vec<ChecksummedAddress> = attackers.into_iter().collect();
+                attackers.sort();
+                ClusterStats { cluster_id: format!("cluster-{}", root), profit, attacks, attackers }
+            })
+            .collect();
+        all.sort_by_key(|cluster| Reverse(cluster.profit));
+        all
+    }
+}
+
+/// Подключается как источник обогащения: запоминает простые переводы ETH из мемпула
+impl Enricher for FundingGraph {
+    fn enrich(&self, tx: &Tx, _out: &mut Enrichment) {
+        if tx.input.is_empty() {
+            self.record_transfer(tx.from, tx.to, tx.value);
+        } else {
+            self.graph.lock().unwrap().remember(tx.from, self.capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::Address;
+
+    fn addr(n: u16) -> ChecksummedAddress {
+        let mut bytes = [0u8; 20];
+        bytes[18..].copy_from_slice(&n.to_be_bytes());
+        ChecksummedAddress::from(Address::from(bytes))
+    }
+
+    #[test]
+    fn test_hub_children_split_once_fanout_is_exceeded() {
+        let graph = FundingGraph::new(2, 100);
+        let one_eth = WeiAmount::from_eth(1.0);
+        graph.record_transfer(addr(1), addr(10), one_eth);
+        graph.record_transfer(addr(1), addr(11), one_eth);
+        assert_eq!(graph.cluster_id(&addr(10)), graph.cluster_id(&addr(11)));
+
+        // Третий пополненный делает источник хабом — и для пополненных раньше
+        graph.record_transfer(addr(1), addr(12), one_eth);
+        assert_ne!(graph.cluster_id(&addr(10)), graph.cluster_id(&addr(11)));
+        assert_eq!(graph.cluster_id(&addr(12)), format!("cluster-{}", addr(12)));
+
+        graph.link(addr(10), addr(11));
+        assert_eq!(graph.cluster_id(&addr(11)), format!("cluster-{}", addr(10)));
+    }
+
+    #[test]
+    fn test_oldest_addresses_are_evicted_over_capacity() {
+        let graph = FundingGraph::new(DEFAULT_MAX_FANOUT, 4);
+        let one_eth = WeiAmount::from_eth(1.0);
+        graph.record_transfer(addr(1), addr(2), one_eth);
+        for n in 3..6 {
+            graph.record_transfer(addr(n), addr(n + 100), one_eth);
+        }
+        let known = graph.graph.lock().unwrap().known.len();
+        assert_eq!(known, 4);
+        // Источник вытеснен вместе с ребром: адрес снова сам по себе
+        assert_eq!(graph.cluster_id(&addr(2)), format!("cluster-{}", addr(2)));
+    }
+}
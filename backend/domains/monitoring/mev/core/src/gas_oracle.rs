@@ -0,0 +1,204 @@
+use crate::compat::U256;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use ethers::providers::Middleware;
+use ethers::types::BlockNumber;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::watch;
+
+#[derive(Debug, Error)]
+pub enum GasOracleError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+/// Перцентили приоритетной комиссии, которые запрашиваются в `eth_feeHistory`
+pub const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Знаменатель изменения base fee по EIP-1559: не больше 12.5% за блок
+const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Вес последнего блока в сглаженной загрузке
+const DEMAND_SMOOTHING: f64 = 0.3;
+
+/// Один блок истории комиссий
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeSample {
+    pub block: u64,
+    pub base_fee: U256,
+    /// `gas_used / gas_limit`, 0..1; цель EIP-1559 — 0.5
+    pub gas_used_ratio: f64,
+    /// Приоритетная комиссия по `REWARD_PERCENTILES`
+    pub rewards: [U256; 3],
+}
+
+/// Как быстро транзакция должна попасть в блок
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeUrgency {
+    Slow,
+    Normal,
+    Fast,
+}
+
+/// Прогноз комиссии на `blocks_ahead` блоков вперёд, в wei
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GasForecast {
+    pub blocks_ahead: u64,
+    /// Ожидаемый base fee при текущем спросе
+    pub base_fee: U256,
+    /// Худший случай: полные блоки всё это время
+    pub base_fee_max: U256,
+    /// Приоритетная комиссия: медиана по истории для p10 / p50 / p90
+    pub priority_fee: [U256; 3],
+}
+
+impl GasForecast {
+    pub fn priority_fee(&self, urgency: FeeUrgency) -> U256 {
+        match urgency {
+            FeeUrgency::Slow => self.priority_fee[0],
+            FeeUrgency::Normal => self.priority_fee[1],
+            FeeUrgency::Fast => self.priority_fee[2],
+        }
+    }
+
+    /// `(max_fee_per_gas, max_priority_fee_per_gas)`: потолок покрывает худший base fee,
+    /// так что транзакция не выпадет из блока, если спрос вырастет
+    pub fn fees(&self, urgency: FeeUrgency) -> (U256, U256) {
+        let priority = self.priority_fee(urgency);
+        (self.base_fee_max.saturating_add(priority), priority)
+    }
+}
+
+/// Base fee следующего блока по EIP-1559 при загрузке `gas_used_ratio`
+pub fn next_base_fee(base_fee: U256, gas_used_ratio: f64) -> U256 {
+    // Допуск к цели: (used - target) / target в тысячных
+    let delta = ((gas_used_ratio.clamp(0.0, 1.0) * 2.0 - 1.0) * 1000.0).round() as i64;
+    let change = base_fee * U256::from(delta.unsigned_abs()) / U256::from(1000 * BASE_FEE_CHANGE_DENOMINATOR);
+    if delta >= 0 {
+        base_fee.saturating_add(change)
+    } else {
+        base_fee.saturating_sub(change)
+    }
+}
+
+/// Прогноз комиссий по окну последних блоков: динамика base fee по EIP-1559
+/// со сглаженным спросом и перцентили приоритетной комиссии
+pub struct GasOracle {
+    window: usize,
+    history: Mutex<VecDeque<FeeSample>>,
+    errors: TaskErrors,
+}
+
+impl GasOracle {
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), history: Mutex::new(VecDeque::new()), errors: TaskErrors::default() }
+    }
+
+    /// Неудачные обновления истории
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Добавляет блок; повтор или блок старше последнего игнорируется
+    pub fn observe(&self, sample: FeeSample) {
+        let mut history = self.history.lock().unwrap();
+        if history.back().is_some_and(|last| last.block >= sample.block) {
+            return;
+        }
+        history.push_back(sample);
+        while history.len() > self.window {
+            history.pop_front();
+        }
+    }
+
+    /// Подтягивает последние блоки через `eth_feeHistory`
+    pub async fn refresh<M: Middleware>(&self, provider: &M) -> Result<usize, GasOracleError> {
+        let history = provider
+            .fee_history(self.window as u64, BlockNumber::Latest, &REWARD_PERCENTILES)
+            .await
+            .map_err(|e| GasOracleError::ProviderError(e.to_string()))?;
+
+        let oldest = history.oldest_block.as_u64();
+        let blocks = history.gas_used_ratio.len();
+        for i in 0..blocks {
+            let reward = history.reward.get(i);
+            let at = |p: usize| reward.and_then(|r| r.get(p)).copied().unwrap_or_default();
+            self.observe(FeeSample {
+                block: oldest + i as u64,
+                base_fee: history.base_fee_per_gas.get(i).copied().unwrap_or_default(),
+                gas_used_ratio: history.gas_used_ratio[i],
+                rewards: [at(0), at(1), at(2)],
+            });
+        }
+        Ok(blocks)
+    }
+
+    /// Обновление на каждый новый блок из `heads`; завершается, когда отправитель закрыт
+    pub async fn run<M: Middleware>(&self, provider: &M, mut heads: watch::Receiver<u64>) {
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            if let Err(e) = self.refresh(provider).await {
+                self.errors.record(format!("fee history at block {}: {}", block, e));
+            }
+        }
+    }
+
+    /// `None`, пока история пуста
+    pub fn forecast(&self, blocks_ahead: u64) -> Option<GasForecast> {
+        let history = self.history.lock().unwrap();
+        let last = history.back()?;
+
+        let demand = history
+            .iter()
+            .fold(None, |acc: Option<f64>, s| {
+                Some(acc.map_or(s.gas_used_ratio, |d| d + DEMAND_SMOOTHING * (s.gas_used_ratio - d)))
+            })
+            .unwrap_or(0.5);
+
+        // Последний блок уже известен: первый шаг — по его фактической загрузке
+        let mut base_fee = next_base_fee(last.base_fee, last.gas_used_ratio);
+        let mut base_fee_max = base_fee;
+        for _ in 1..blocks_ahead.max(1) {
+            base_fee = next_base_fee(base_fee, demand);
+            base_fee_max = next_base_fee(base_fee_max, 1.0);
+        }
+
+        let priority_fee = [0, 1, 2].map(|p| {
+            let mut values: Vec<U256> = history.iter().map(|s| s.rewards[p]).collect();
+            values.sort();
+            values[values.len() / 2]
+        });
+
+        Some(GasForecast { blocks_ahead: blocks_ahead.max(1), base_fee, base_fee_max, priority_fee })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(g: u64) -> U256 {
+        U256::from(g) * U256::exp10(9)
+    }
+
+    #[test]
+    fn test_forecast_follows_eip1559_dynamics() {
+        assert_eq!(next_base_fee(gwei(80), 1.0), gwei(90));
+        assert_eq!(next_base_fee(gwei(80), 0.0), gwei(70));
+        assert_eq!(next_base_fee(gwei(80), 0.5), gwei(80));
+
+        let oracle = GasOracle::new(4);
+        for (block, tip) in [(1, 1), (2, 3), (3, 2)] {
+            oracle.observe(FeeSample { block, base_fee: gwei(80), gas_used_ratio: 0.5, rewards: [gwei(1), gwei(tip), gwei(9)] });
+        }
+        oracle.observe(FeeSample { block: 2, base_fee: gwei(1), gas_used_ratio: 1.0, rewards: [U256::zero(); 3] });
+
+        let forecast = oracle.forecast(3).unwrap();
+        assert_eq!(forecast.base_fee, gwei(80));
+        assert!(forecast.base_fee_max > gwei(100));
+        assert_eq!(forecast.fees(FeeUrgency::Normal), (forecast.base_fee_max + gwei(2), gwei(2)));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::bus::{AlertLevel, BusAlert};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Языки встроенных наборов сообщений
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ru,
+    Zh,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::Ru, Locale::Zh];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ru => "ru",
+            Locale::Zh => "zh",
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Принимает и региональные коды: `ru-RU`, `zh_CN`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        Locale::ALL
+            .into_iter()
+            .find(|l| l.code() == language)
+            .ok_or_else(|| format!("unsupported locale '{}'", s))
+    }
+}
+
+/// Шаблоны по ключу `source.kind`; `source.default` и `default` — запасные.
+/// Подстановки: `{subject}`, `{title}`, `{kind}`, `{source}`, `{level}` и поля
+/// полезной нагрузки `{payload.reading.health_factor:.3}` с необязательной точностью
+const EN: &[(&str, &str)] = &[
+    ("default", "[{level}] {title}"),
+    ("level.info", "info"),
+    ("level.low", "low"),
+    ("level.medium", "medium"),
+    ("level.high", "high"),
+    ("level.critical", "critical"),
+    ("mev.frontrun", "[{level}] Frontrun targeting {subject}, attacker profit {payload.profit.eth:.4} ETH"),
+    ("mev.sandwich", "[{level}] Sandwich on {subject}, attacker profit {payload.profit.eth:.4} ETH"),
+    ("mev.repeated_targeting", "[{level}] {subject} sandwiched {payload.metadata.attacks} times within {payload.metadata.window_seconds}s ({payload.metadata.pattern})"),
+    ("mev.default", "[{level}] MEV ({kind}) on {subject}, {payload.profit.eth:.4} ETH"),
+    ("lending.health_factor", "[{level}] Health factor of {subject} dropped to {payload.reading.health_factor:.3}"),
+    ("governance.proposal", "[{level}] New governance proposal {payload.proposal.proposal_id} on {subject}"),
+    ("monitor.upgrade", "[{level}] Proxy {subject} upgraded to {payload.new_implementation} at block {payload.block}"),
+    ("monitor.storage_slot", "[{level}] Storage slot {payload.slot} of {subject} changed at block {payload.block}"),
+    ("monitor.new_finding", "[{level}] New finding in {subject}: {payload.title}"),
+    ("monitor.outflow", "[{level}] Large outflow of {payload.asset} from {subject}"),
+    ("monitor.delegation", "[{level}] EOA {subject} delegated its code to {payload.delegation.delegate} (EIP-7702)"),
+    ("monitor.upgrade_simulation", "[{level}] Upgrade of {subject} to {payload.new_implementation} changes behavior in simulation"),
+    ("anomaly.tvl_outflow", "[{level}] Anomalous TVL outflow of {payload.asset} from {subject} ({payload.z_score:.1}σ)"),
+    ("intents.settlement_shortfall", "[{level}] Solver {subject} settled order {payload.settlement.uid} {payload.shortfall_bps} bps below quote"),
+    ("intents.solver_self_dealing", "[{level}] Solver {subject} settled order {payload.settlement.uid} against its own account"),
+    ("permit2.allowance_granted", "[{level}] {subject} granted {payload.allowance.spender} a Permit2 allowance for {payload.allowance.token}"),
+    ("permit2.allowance_expiring", "[{level}] Permit2 allowance of {subject} to {payload.allowance.spender} for {payload.allowance.token} expires at {payload.allowance.expiration}"),
+    ("dvt.redundancy_degraded", "[{level}] DVT cluster {subject} has {payload.spare} spare operators above its signing threshold"),
+    ("dvt.low_participation", "[{level}] Low attestation participation in DVT cluster {subject}: {payload.participation:.2}"),
+    ("dvt.operator_fee_changed", "[{level}] SSV operator {payload.operator} fee changed from {payload.before} to {payload.after}"),
+    ("restaking.dilution", "[{level}] Share price of EigenLayer strategy {payload.event.strategy} held by {subject} fell {payload.event.drop_bps} bps"),
+    ("staking.parameter_change", "[{level}] Operator {payload.operator} of {subject}: {title}, effective {payload.change.effective_at}"),
+    ("routing.escalated", "Escalated: not acknowledged within {minutes} min (route {route}, alert {id})"),
+    ("congestion.regime", "[{level}] Network entered {payload.regime} regime: base fee {payload.snapshot.base_fee_gwei} gwei"),
+    ("congestion.watched_operation", "[{level}] {payload.watch} tx from {subject} queued during {payload.regime} regime ({payload.snapshot.base_fee_gwei} gwei base fee)"),
+    ("bridges.undelivered", "[{level}] Bridge message {payload.message.message_id} from {subject} via {payload.message.bridge} not delivered to chain {payload.message.dest_chain}"),
+    ("correlation.incident", "[{level}] Correlated incident on {subject}: {title}"),
+    ("digest.daily", "Daily digest for {tenant}, {since}"),
+    ("digest.weekly", "Weekly digest for {tenant}, {since} - {until}"),
+    ("digest.alerts_by_level", "New alerts by severity"),
+    ("digest.top_subjects", "Most targeted contracts"),
+];
+
+const RU: &[(&str, &str)] = &[
+    ("default", "[{level}] {title}"),
+    ("level.info", "инфо"),
+    ("level.low", "низкий"),
+    ("level.medium", "средний"),
+    ("level.high", "высокий"),
+    ("level.critical", "критический"),
+    ("mev.frontrun", "[{level}] Фронтран на {subject}, прибыль атакующего {payload.profit.eth:.4} ETH"),
+    ("mev.sandwich", "[{level}] Сэндвич на {subject}, прибыль атакующего {payload.profit.eth:.4} ETH"),
+    ("mev.repeated_targeting", "[{level}] {subject} попал в сэндвич {payload.metadata.attacks} раз за {payload.metadata.window_seconds} с ({payload.metadata.pattern})"),
+    ("mev.default", "[{level}] MEV ({kind}) на {subject}, {payload.profit.eth:.4} ETH"),
+    ("lending.health_factor", "[{level}] Health factor {subject} упал до {payload.reading.health_factor:.3}"),
+    ("governance.proposal", "[{level}] Новое предложение {payload.proposal.proposal_id} в {subject}"),
+    ("monitor.upgrade", "[{level}] Прокси {subject} обновлён до {payload.new_implementation} в блоке {payload.block}"),
+    ("monitor.storage_slot", "[{level}] Слот {payload.slot} контракта {subject} изменён в блоке {payload.block}"),
+    ("monitor.new_finding", "[{level}] Новая находка в {subject}: {payload.title}"),
+    ("monitor.outflow", "[{level}] Крупный отток {payload.asset} из {subject}"),
+    ("monitor.delegation", "[{level}] EOA {subject} делегировал код на {payload.delegation.delegate} (EIP-7702)"),
+    ("monitor.upgrade_simulation", "[{level}] Апгрейд {subject} до {payload.new_implementation} меняет поведение в симуляции"),
+    ("anomaly.tvl_outflow", "[{level}] Аномальный отток TVL {payload.asset} из {subject} ({payload.z_score:.1}σ)"),
+    ("intents.settlement_shortfall", "[{level}] Солвер {subject} исполнил ордер {payload.settlement.uid} на {payload.shortfall_bps} б.п. хуже котировки"),
+    ("intents.solver_self_dealing", "[{level}] Солвер {subject} исполнил ордер {payload.settlement.uid} против собственного адреса"),
+    ("permit2.allowance_granted", "[{level}] {subject} выдал {payload.allowance.spender} разрешение Permit2 на {payload.allowance.token}"),
+    ("permit2.allowance_expiring", "[{level}] Разрешение Permit2 от {subject} для {payload.allowance.spender} на {payload.allowance.token} истекает в {payload.allowance.expiration}"),
+    ("dvt.redundancy_degraded", "[{level}] У DVT-кластера {subject} запас {payload.spare} операторов над порогом подписи"),
+    ("dvt.low_participation", "[{level}] Низкое участие в аттестациях DVT-кластера {subject}: {payload.participation:.2}"),
+    ("dvt.operator_fee_changed", "[{level}] Комиссия оператора SSV {payload.operator} изменилась с {payload.before} на {payload.after}"),
+    ("restaking.dilution", "[{level}] Цена доли стратегии EigenLayer {payload.event.strategy} у {subject} упала на {payload.event.drop_bps} б.п."),
+    ("staking.parameter_change", "[{level}] Оператор {payload.operator} стейкера {subject}: {title}, вступает в силу {payload.change.effective_at}"),
+    ("routing.escalated", "Эскалация: не подтверждено за {minutes} мин (маршрут {route}, алерт {id})"),
+    ("congestion.regime", "[{level}] Сеть перешла в режим {payload.regime}: base fee {payload.snapshot.base_fee_gwei} gwei"),
+    ("congestion.watched_operation", "[{level}] Транзакция {payload.watch} от {subject} ждёт в режиме {payload.regime} (base fee {payload.snapshot.base_fee_gwei} gwei)"),
+    ("bridges.undelivered", "[{level}] Сообщение моста {payload.message.message_id} от {subject} через {payload.message.bridge} не доставлено в сеть {payload.message.dest_chain}"),
+    ("correlation.incident", "[{level}] Составной инцидент на {subject}: {title}"),
+    ("digest.daily", "Дневная сводка для {tenant}, {since}"),
+    ("digest.weekly", "Недельная сводка для {tenant}, {since} - {until}"),
+    ("digest.alerts_by_level", "Новые алерты по уровням"),
+    ("digest.top_subjects", "Самые атакуемые контракты"),
+];
+
+const ZH: &[(&str, &str)] = &[
+    ("default", "[{level}] {title}"),
+    ("level.info", "信息"),
+    ("level.low", "低"),
+    ("level.medium", "中"),
+    ("level.high", "高"),
+    ("level.critical", "严重"),
+    ("mev.frontrun", "[{level}] 针对 {subject} 的抢跑交易，攻击者利润 {payload.profit.eth:.4} ETH"),
+    ("mev.sandwich", "[{level}] {subject} 上的三明治攻击，攻击者利润 {payload.profit.eth:.4} ETH"),
+    ("mev.repeated_targeting", "[{level}] {subject} 在 {payload.metadata.window_seconds} 秒内被三明治攻击 {payload.metadata.attacks} 次（{payload.metadata.pattern}）"),
+    ("mev.default", "[{level}] {subject} 上的 MEV（{kind}），{payload.profit.eth:.4} ETH"),
+    ("lending.health_factor", "[{level}] {subject} 的健康因子降至 {payload.reading.health_factor:.3}"),
+    ("governance.proposal", "[{level}] {subject} 上的新治理提案 {payload.proposal.proposal_id}"),
+    ("monitor.upgrade", "[{level}] 代理合约 {subject} 在区块 {payload.block} 升级为 {payload.new_implementation}"),
+    ("monitor.storage_slot", "[{level}] {subject} 的存储槽 {payload.slot} 在区块 {payload.block} 发生变化"),
+    ("monitor.new_finding", "[{level}] {subject} 中的新发现：{payload.title}"),
+    ("monitor.outflow", "[{level}] {payload.asset} 从 {subject} 大额流出"),
+    ("monitor.delegation", "[{level}] EOA {subject} 将代码委托给 {payload.delegation.delegate}（EIP-7702）"),
+    ("monitor.upgrade_simulation", "[{level}] {subject} 升级到 {payload.new_implementation} 后在模拟中行为发生变化"),
+    ("anomaly.tvl_outflow", "[{level}] {payload.asset} 从 {subject} 异常流出 TVL（{payload.z_score:.1}σ）"),
+    ("intents.settlement_shortfall", "[{level}] 求解器 {subject} 执行订单 {payload.settlement.uid} 的结果比报价差 {payload.shortfall_bps} 个基点"),
+    ("intents.solver_self_dealing", "[{level}] 求解器 {subject} 以自有账户成交订单 {payload.settlement.uid}"),
+    ("permit2.allowance_granted", "[{level}] {subject} 向 {payload.allowance.spender} 授予了 {payload.allowance.token} 的 Permit2 授权"),
+    ("permit2.allowance_expiring", "[{level}] {subject} 授予 {payload.allowance.spender} 的 {payload.allowance.token} Permit2 授权将于 {payload.allowance.expiration} 到期"),
+    ("dvt.redundancy_degraded", "[{level}] DVT 集群 {subject} 在签名阈值之上仅剩 {payload.spare} 个冗余运营者"),
+    ("dvt.low_participation", "[{level}] DVT 集群 {subject} 的见证参与率偏低：{payload.participation:.2}"),
+    ("dvt.operator_fee_changed", "[{level}] SSV 运营者 {payload.operator} 的费用从 {payload.before} 变为 {payload.after}"),
+    ("restaking.dilution", "[{level}] {subject} 持有的 EigenLayer 策略 {payload.event.strategy} 份额价格下跌 {payload.event.drop_bps} 个基点"),
+    ("staking.parameter_change", "[{level}] {subject} 的运营者 {payload.operator}：{title}，生效时间 {payload.change.effective_at}"),
+    ("routing.escalated", "已升级：{minutes} 分钟内未确认（路由 {route}，告警 {id}）"),
+    ("congestion.regime", "[{level}] 网络进入 {payload.regime} 状态：基础费用 {payload.snapshot.base_fee_gwei} gwei"),
+    ("congestion.watched_operation", "[{level}] 来自 {subject} 的 {payload.watch} 交易在 {payload.regime} 状态下排队（基础费用 {payload.snapshot.base_fee_gwei} gwei）"),
+    ("bridges.undelivered", "[{level}] 来自 {subject} 经 {payload.message.bridge} 的跨链消息 {payload.message.message_id} 未送达链 {payload.message.dest_chain}"),
+    ("correlation.incident", "[{level}] {subject} 上的关联事件：{title}"),
+    ("digest.daily", "{tenant} 的每日摘要，{since}"),
+    ("digest.weekly", "{tenant} 的每周摘要，{since} - {until}"),
+    ("digest.alerts_by_level", "按级别统计的新告警"),
+    ("digest.top_subjects", "受攻击最多的合约"),
+];
+
+fn level_key(level: AlertLevel) -> &'static str {
+    match level {
+        AlertLevel::Info => "level.info",
+        AlertLevel::Low => "level.low",
+        AlertLevel::Medium => "level.medium",
+        AlertLevel::High => "level.high",
+        AlertLevel::Critical => "level.critical",
+    }
+}
+
+/// Значение подстановки; числа с точностью `:.N` форматируются как f64
+fn format_value(value: &Value, precision: Option<usize>) -> Option<String> {
+    match (value, precision) {
+        (Value::Null, _) => None,
+        (Value::String(s), Some(p)) => Some(s.parse::<f64>().map(|f| format!("{:.*}", p, f)).unwrap_or_else(|_| s.clone())),
+        (Value::String(s), None) => Some(s.clone()),
+        (Value::Number(n), Some(p)) => n.as_f64().map(|f| format!("{:.*}", p, f)),
+        (other, _) => Some(other.to_string()),
+    }
+}
+
+/// Подставляет аргументы в шаблон; `None`, если какого-то поля нет —
+/// тогда вызывающий переходит к более общему шаблону
+pub fn fill(template: &str, lookup: impl Fn(&str) -> Option<Value>) -> Option<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}')?;
+        let spec = &rest[start + 1..end];
+        let (name, precision) = match spec.split_once(":.") {
+            Some((name, p)) => (name, Some(p.parse().ok()?)),
+            None => (spec, None),
+        };
+        out.push_str(&format_value(&lookup(name)?, precision)?);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Наборы сообщений по языкам. Встроенные шаблоны можно перекрыть из конфига
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    bundles: HashMap<Locale, HashMap<String, String>>,
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        let mut bundles = HashMap::new();
+        for (locale, entries) in [(Locale::En, EN), (Locale::Ru, RU), (Locale::Zh, ZH)] {
+            bundles.insert(
+                locale,
+                entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            );
+        }
+        Self { bundles }
+    }
+}
+
+impl MessageCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_templates(mut self, locale: Locale, templates: &BTreeMap<String, String>) -> Self {
+        self.bundles
+            .entry(locale)
+            .or_default()
+            .extend(templates.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
+    /// Шаблон на языке, иначе английский
+    pub fn template(&self, locale: Locale, key: &str) -> Option<&str> {
+        [locale, Locale::En]
+            .iter()
+            .find_map(|l| self.bundles.get(l)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// Текст по ключу для отчётов и прочих сообщений вне шины
+    pub fn render_key(&self, locale: Locale, key: &str, args: &Value) -> Option<String> {
+        fill(self.template(locale, key)?, |name| lookup_path(args, name))
+    }
+
+    /// Человекочитаемая сводка алерта. Шаблоны перебираются от точного `source.kind`
+    /// к `default`; если полезная нагрузка не подходит ни к одному, остаётся `title`
+    pub fn render(&self, alert: &BusAlert, locale: Locale) -> String {
+        let level = self.template(locale, level_key(alert.level)).unwrap_or_default().to_string();
+        let lookup = |name: &str| -> Option<Value> {
+            match name {
+                "subject" => Some(Value::String(alert.subject.clone())),
+                "title" => Some(Value::String(alert.title.clone())),
+                "kind" => Some(Value::String(alert.kind.clone())),
+                "source" => Some(Value::String(alert.source.clone())),
+                "level" => Some(Value::String(level.clone())),
+                _ => lookup_path(&alert.payload, name.strip_prefix("payload.")?),
+            }
+        };
+
+        let keys = [
+            format!("{}.{}", alert.source, alert.kind),
+            format!("{}.default", alert.source),
+            "default".to_string(),
+        ];
+        keys.iter()
+            .filter_map(|key| self.template(locale, key))
+            .find_map(|template| fill(template, lookup))
+            .unwrap_or_else(|| alert.title.clone())
+    }
+}
+
+fn lookup_path(value: &Value, path: &str) -> Option<Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key)).cloned()
+}
+
+/// Язык получателя: сначала синк, затем тенант, затем язык по умолчанию
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocaleSelector {
+    #[serde(default)]
+    pub default_locale: Locale,
+    #[serde(default)]
+    pub sinks: BTreeMap<String, Locale>,
+    #[serde(default)]
+    pub tenants: BTreeMap<String, Locale>,
+}
+
+impl LocaleSelector {
+    pub fn resolve(&self, sink: &str, tenant: Option<&str>) -> Locale {
+        self.sinks
+            .get(sink)
+            .or_else(|| tenant.and_then(|t| self.tenants.get(t)))
+            .copied()
+            .unwrap_or(self.default_locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn health_alert() -> BusAlert {
+        BusAlert::new("lending", "health_factor", AlertLevel::High, "0xabc".into(), "Health factor 1.080".into())
+            .with_payload(json!({ "reading": { "health_factor": 1.0812 } }))
+    }
+
+    #[test]
+    fn test_render_localized_and_fallbacks() {
+        let catalog = MessageCatalog::new();
+        let alert = health_alert();
+        assert_eq!(catalog.render(&alert, Locale::En), "[high] Health factor of 0xabc dropped to 1.081");
+        assert_eq!(catalog.render(&alert, Locale::Ru), "[высокий] Health factor 0xabc упал до 1.081");
+
+        // Нет нужного поля в нагрузке — общий шаблон с исходным заголовком
+        let bare = BusAlert::new("lending", "health_factor", AlertLevel::Low, "0xabc".into(), "HF".into());
+        assert_eq!(catalog.render(&bare, Locale::Zh), "[低] HF");
+
+        let selector = LocaleSelector {
+            default_locale: Locale::En,
+            sinks: BTreeMap::from([("telegram-cn".to_string(), Locale::Zh)]),
+            tenants: BTreeMap::from([("acme".to_string(), Locale::Ru)]),
+        };
+        assert_eq!(selector.resolve("telegram-cn", Some("acme")), Locale::Zh);
+        assert_eq!(selector.resolve("slack", Some("acme")), Locale::Ru);
+        assert_eq!("ru-RU".parse::<Locale>(), Ok(Locale::Ru));
+    }
+}
@@ -0,0 +1,415 @@
+use crate::compat::{Address, H256};
+use crate::shutdown::ShutdownSignal;
+use crate::store::{Store, StoreError, StoreExt};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use async_trait::async_trait;
+use ethers::contract::{parse_log, EthEvent};
+use ethers::providers::Middleware;
+use ethers::types::{Filter, Log};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::watch;
+
+/// Пространство имён хранилища для курсоров индексов
+pub const INDEXER_NS: &str = "indexer";
+
+/// Диапазон `eth_getLogs` по умолчанию; сужается при отказах провайдера
+pub const DEFAULT_MAX_RANGE: u64 = 2_000;
+
+/// Сколько последних проиндексированных блоков помнить для обнаружения реорганизаций
+const REORG_WINDOW: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Handler failed: {0}")]
+    HandlerError(String),
+
+    #[error("Reorg deeper than {0} indexed blocks")]
+    ReorgTooDeep(usize),
+
+    #[error("Block {0} changed while its logs were fetched")]
+    ChangedDuringFetch(u64),
+}
+
+fn provider_err(e: impl std::fmt::Display) -> IndexerError {
+    IndexerError::ProviderError(e.to_string())
+}
+
+/// Позиция индекса: следующий блок, текущий диапазон запроса и хэши последних блоков
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    pub next_block: u64,
+    pub range: u64,
+    /// `(номер, хэш)` концов проиндексированных отрезков, по возрастанию
+    pub recent: Vec<(u64, H256)>,
+}
+
+/// Декодированное событие с положением в цепи
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedEvent<E> {
+    pub block: u64,
+    pub block_hash: H256,
+    pub tx_hash: H256,
+    pub log_index: u64,
+    pub address: Address,
+    pub event: E,
+}
+
+/// Применение событий индекса; курсор сдвигается только после успешного `apply`,
+/// поэтому обработчик должен быть идемпотентным
+#[async_trait]
+pub trait IndexHandler<E>: Send + Sync {
+    async fn apply(&self, events: Vec<IndexedEvent<E>>) -> Result<(), String>;
+
+    /// События из блоков `from_block` и выше отменены реорганизацией
+    async fn revert(&self, from_block: u64) -> Result<(), String>;
+}
+
+/// Итог одного шага индексации
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Indexed { from: u64, to: u64, events: usize },
+    /// Реорганизация: индекс откатился к блоку
+    Reverted { from_block: u64 },
+    /// Провайдер отказал на диапазоне; следующий шаг будет с диапазоном `range`
+    Narrowed { range: u64 },
+    CaughtUp,
+}
+
+type Decoder<E> = Box<dyn Fn(Log) -> Option<E> + Send + Sync>;
+
+/// Индекс логов: курсор в хранилище, `eth_getLogs` кусками с адаптивным диапазоном,
+/// откат при реорганизации и декодеры по `topic0`
+pub struct LogIndexer<M, E> {
+    name: String,
+    provider: Arc<M>,
+    store: Arc<dyn Store>,
+    start_block: u64,
+    addresses: Vec<Address>,
+    decoders: HashMap<H256, Decoder<E>>,
+    confirmations: u64,
+    max_range: u64,
+    /// Без курсора начинать с головы, а не со `start_block`
+    from_head: bool,
+    /// Неудачные шаги `run`
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static, E: Send + 'static> LogIndexer<M, E> {
+    /// `name` — ключ курсора; у разных индексов должен различаться
+    pub fn new(name: &str, provider: Arc<M>, store: Arc<dyn Store>, start_block: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            provider,
+            store,
+            start_block,
+            addresses: Vec::new(),
+            decoders: HashMap::new(),
+            confirmations: 0,
+            max_range: DEFAULT_MAX_RANGE,
+            from_head: false,
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Ограничивает индекс логами контракта; без адресов — логи любых контрактов
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.addresses.push(address);
+        self
+    }
+
+    /// Регистрирует событие `T`; `map` приводит его к общему типу индекса
+    pub fn with_event<T: EthEvent + 'static>(mut self, map: fn(T) -> E) -> Self {
+        self.decoders.insert(T::signature(), Box::new(move |log| parse_log::<T>(log).ok().map(map)));
+        self
+    }
+
+    /// Декодер логов с `topic0`, если событие не описано типом `EthEvent`
+    pub fn with_decoder(mut self, topic0: H256, decode: impl Fn(Log) -> Option<E> + Send + Sync + 'static) -> Self {
+        self.decoders.insert(topic0, Box::new(decode));
+        self
+    }
+
+    /// Первый запуск начинает с головы: история до него не нужна
+    pub fn from_head(mut self) -> Self {
+        self.from_head = true;
+        self
+    }
+
+    /// Индексировать только блоки глубже `confirmations` от головы
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    pub fn with_max_range(mut self, max_range: u64) -> Self {
+        self.max_range = max_range.max(1);
+        self
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn cursor(&self) -> Result<Cursor, IndexerError> {
+        Ok(self.stored_cursor()?.unwrap_or(Cursor {
+            next_block: self.start_block,
+            range: self.max_range,
+            recent: Vec::new(),
+        }))
+    }
+
+    fn stored_cursor(&self) -> Result<Option<Cursor>, IndexerError> {
+        Ok(self.store.get_json(INDEXER_NS, &self.name)?)
+    }
+
+    fn save(&self, cursor: &Cursor) -> Result<(), IndexerError> {
+        Ok(self.store.put_json(INDEXER_NS, &self.name, cursor)?)
+    }
+
+    /// Переиндексировать с `block`: следующий шаг начнёт оттуда
+    pub fn reset(&self, block: u64) -> Result<(), IndexerError> {
+        self.save(&Cursor { next_block: block, range: self.max_range, recent: Vec::new() })
+    }
+
+    async fn block_hash(&self, block: u64) -> Result<Option<H256>, IndexerError> {
+        Ok(self.provider.get_block(block).await.map_err(provider_err)?.and_then(|b| b.hash))
+    }
+
+    /// Первый блок, отличающийся от запомненного; `None` — цепь совпадает
+    async fn fork_point(&self, cursor: &Cursor) -> Result<Option<usize>, IndexerError> {
+        let Some((block, hash)) = cursor.recent.last() else {
+            return Ok(None);
+        };
+        if self.block_hash(*block).await? == Some(*hash) {
+            return Ok(None);
+        }
+        for (i, (block, hash)) in cursor.recent.iter().enumerate().rev().skip(1) {
+            if self.block_hash(*block).await? == Some(*hash) {
+                return Ok(Some(i + 1));
+            }
+        }
+        Err(IndexerError::ReorgTooDeep(cursor.recent.len()))
+    }
+
+    fn decode(&self, logs: Vec<Log>) -> Vec<IndexedEvent<E>> {
+        let mut events: Vec<IndexedEvent<E>> = logs
+            .into_iter()
+            .filter(|log| log.removed != Some(true))
+            .filter_map(|log| {
+                let decoder = self.decoders.get(log.topics.first()?)?;
+                let (block, block_hash, tx_hash) = (log.block_number?.as_u64(), log.block_hash?, log.transaction_hash?);
+                let (log_index, address) = (log.log_index.map_or(0, |i| i.as_u64()), log.address);
+                Some(IndexedEvent { block, block_hash, tx_hash, log_index, address, event: decoder(log)? })
+            })
+            .collect();
+        events.sort_by_key(|e| (e.block, e.log_index));
+        events
+    }
+
+    /// Один шаг: проверка реорганизации либо один диапазон логов
+    pub async fn step(&self, handler: &dyn IndexHandler<E>) -> Result<Step, IndexerError> {
+        let mut cursor = self.cursor()?;
+
+        if let Some(keep) = self.fork_point(&cursor).await? {
+            let from_block = cursor.recent[keep - 1].0 + 1;
+            handler.revert(from_block).await.map_err(IndexerError::HandlerError)?;
+            cursor.recent.truncate(keep);
+            cursor.next_block = from_block;
+            self.save(&cursor)?;
+            return Ok(Step::Reverted { from_block });
+        }
+
+        let head = self.provider.get_block_number().await.map_err(provider_err)?.as_u64();
+        let target = head.saturating_sub(self.confirmations);
+        if self.from_head && self.stored_cursor()?.is_none() {
+            cursor.next_block = target;
+        }
+        if cursor.next_block > target {
+            return Ok(Step::CaughtUp);
+        }
+        let from = cursor.next_block;
+        let to = target.min(from + cursor.range.max(1) - 1);
+        // Хэш конца отрезка берётся до логов: если блок заменят после, откат увидит это на следующем
+        // шаге, а логи новой цепи под старым хэшем отсекает сверка ниже
+        let end_hash = self.block_hash(to).await?.ok_or_else(|| IndexerError::ProviderError(format!("block {} not found", to)))?;
+
+        let mut filter = Filter::new().from_block(from).to_block(to).topic0(self.decoders.keys().copied().collect::<Vec<_>>());
+        if !self.addresses.is_empty() {
+            filter = filter.address(self.addresses.clone());
+        }
+        let logs = match self.provider.get_logs(&filter).await {
+            Ok(logs) => logs,
+            // Слишком широкий диапазон или слишком много логов: провайдеры называют это по-разному,
+            // поэтому сужается на любой ошибке, пока не останется один блок
+            Err(_) if cursor.range > 1 => {
+                cursor.range = (cursor.range / 2).max(1);
+                self.save(&cursor)?;
+                return Ok(Step::Narrowed { range: cursor.range });
+            }
+            Err(e) => return Err(provider_err(e)),
+        };
+
+        let events = self.decode(logs);
+        if events.iter().any(|e| e.block == to && e.block_hash != end_hash) {
+            return Err(IndexerError::ChangedDuringFetch(to));
+        }
+        let count = events.len();
+        handler.apply(events).await.map_err(IndexerError::HandlerError)?;
+
+        cursor.recent.push((to, end_hash));
+        if cursor.recent.len() > REORG_WINDOW {
+            cursor.recent.drain(..cursor.recent.len() - REORG_WINDOW);
+        }
+        cursor.next_block = to + 1;
+        cursor.range = (cursor.range * 2).min(self.max_range);
+        self.save(&cursor)?;
+        Ok(Step::Indexed { from, to, events: count })
+    }
+
+    /// Шаги до головы; останавливается на первой ошибке или по сигналу остановки
+    pub async fn catch_up(&self, handler: &dyn IndexHandler<E>, shutdown: &ShutdownSignal) -> Result<(), IndexerError> {
+        loop {
+            match self.step(handler).await? {
+                Step::CaughtUp => return Ok(()),
+                _ if shutdown.is_triggered() => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Догоняет голову и дальше идёт за ней до сигнала остановки
+    pub async fn run(&self, handler: Arc<dyn IndexHandler<E>>, mut heads: watch::Receiver<u64>, mut shutdown: ShutdownSignal) {
+        loop {
+            if let Err(e) = self.catch_up(handler.as_ref(), &shutdown).await {
+                self.errors.record(format!("log index {}: {}", self.name, e));
+            }
+            if shutdown.is_triggered() {
+                return;
+            }
+            tokio::select! {
+                changed = heads.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use ethers::providers::{MockProvider, Provider};
+    use crate::compat::U256;
+    use ethers::types::{Block, U64};
+    use std::sync::Mutex;
+
+    #[derive(Clone, Debug, EthEvent)]
+    #[ethevent(name = "Ping", abi = "Ping(uint256)")]
+    struct Ping {
+        value: U256,
+    }
+
+    #[derive(Default)]
+    struct Collect {
+        applied: Mutex<Vec<(u64, U256)>>,
+        reverted: Mutex<Vec<u64>>,
+    }
+
+    #[async_trait]
+    impl IndexHandler<Ping> for Collect {
+        async fn apply(&self, events: Vec<IndexedEvent<Ping>>) -> Result<(), String> {
+            self.applied.lock().unwrap().extend(events.into_iter().map(|e| (e.block, e.event.value)));
+            Ok(())
+        }
+
+        async fn revert(&self, from_block: u64) -> Result<(), String> {
+            self.reverted.lock().unwrap().push(from_block);
+            Ok(())
+        }
+    }
+
+    fn respond<T: Serialize + Send + Sync>(mock: &MockProvider, value: T) {
+        mock.push::<T, T>(value).unwrap();
+    }
+
+    fn block(number: u64, hash: H256) -> Block<H256> {
+        Block { number: Some(U64::from(number)), hash: Some(hash), ..Default::default() }
+    }
+
+    fn ping(block: u64, block_hash: H256, value: u64) -> Log {
+        let mut data = [0u8; 32];
+        U256::from(value).to_big_endian(&mut data);
+        Log {
+            topics: vec![Ping::signature()],
+            data: data.to_vec().into(),
+            block_number: Some(U64::from(block)),
+            block_hash: Some(block_hash),
+            transaction_hash: Some(H256::repeat_byte(0xee)),
+            log_index: Some(U256::zero()),
+            ..Default::default()
+        }
+    }
+
+    fn indexer() -> (MockProvider, LogIndexer<Provider<MockProvider>, Ping>) {
+        let (provider, mock) = Provider::mocked();
+        let indexer = LogIndexer::new("test", Arc::new(provider), Arc::new(MemoryStore::default()), 0)
+            .with_event::<Ping>(|ping| ping)
+            .with_max_range(10);
+        (mock, indexer)
+    }
+
+    #[tokio::test]
+    async fn test_indexes_ranges_and_reverts_on_reorg() {
+        let (mock, indexer) = indexer();
+        let handler = Collect::default();
+        let (h9, h19) = (H256::repeat_byte(9), H256::repeat_byte(19));
+
+        // Ответы снимаются с конца: последним кладётся первый вызов шага
+        respond(&mock, vec![ping(9, h9, 1)]);
+        respond(&mock, block(9, h9));
+        respond(&mock, U64::from(25));
+        assert_eq!(indexer.step(&handler).await.unwrap(), Step::Indexed { from: 0, to: 9, events: 1 });
+
+        // Проверка реорганизации по концу прошлого отрезка, затем следующий отрезок
+        respond(&mock, Vec::<Log>::new());
+        respond(&mock, block(19, h19));
+        respond(&mock, U64::from(25));
+        respond(&mock, block(9, h9));
+        assert_eq!(indexer.step(&handler).await.unwrap(), Step::Indexed { from: 10, to: 19, events: 0 });
+
+        // Блок 19 заменён, 9 совпадает: откат к 10
+        respond(&mock, block(9, h9));
+        respond(&mock, block(19, H256::repeat_byte(0x19)));
+        assert_eq!(indexer.step(&handler).await.unwrap(), Step::Reverted { from_block: 10 });
+        assert_eq!(*handler.reverted.lock().unwrap(), [10]);
+        assert_eq!(indexer.cursor().unwrap().next_block, 10);
+        assert_eq!(*handler.applied.lock().unwrap(), [(9, U256::from(1))]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_logs_from_a_replaced_end_block() {
+        let (mock, indexer) = indexer();
+        let handler = Collect::default();
+
+        // Логи пришли уже с другой цепи, чем хэш конца отрезка
+        respond(&mock, vec![ping(9, H256::repeat_byte(0xaa), 1)]);
+        respond(&mock, block(9, H256::repeat_byte(9)));
+        respond(&mock, U64::from(25));
+        assert!(matches!(indexer.step(&handler).await, Err(IndexerError::ChangedDuringFetch(9))));
+        assert!(handler.applied.lock().unwrap().is_empty());
+        assert_eq!(indexer.cursor().unwrap().next_block, 0);
+    }
+}
@@ -0,0 +1,199 @@
+use super::ws::{backoff_delay, ReconnectPolicy};
+use super::{tx_from_raw, IngestError, IngestMetrics, RecentHashes, TxSink};
+use crate::shutdown::ShutdownSignal;
+use ethers::core::rand::{thread_rng, Rng};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+
+const RECENT_CAPACITY: usize = 100_000;
+
+/// Сообщения `txpool.proto` Erigon (erigon-lib/gointerfaces), нужные источнику
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OnAddRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct OnAddReply {
+    /// Подписанные транзакции в RLP
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub rpl_txs: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AllRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AllReplyTx {
+    /// 0 — pending, 1 — queued, 2 — base fee
+    #[prost(int32, tag = "1")]
+    pub txn_type: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub sender: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub rlp_tx: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AllReply {
+    #[prost(message, repeated, tag = "1")]
+    pub txs: Vec<AllReplyTx>,
+}
+
+const PENDING: i32 = 0;
+
+fn grpc_error(e: impl std::fmt::Display) -> IngestError {
+    IngestError::ProviderError(e.to_string())
+}
+
+/// Поток новых транзакций из txpool gRPC Erigon: без JSON-RPC и без повторного
+/// запроса тела транзакции по хэшу. Отправитель восстанавливается из подписи локально
+pub struct ErigonSource {
+    name: String,
+    url: String,
+    policy: ReconnectPolicy,
+    seen: RecentHashes,
+    metrics: Arc<IngestMetrics>,
+}
+
+impl ErigonSource {
+    pub fn new(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            policy: ReconnectPolicy::default(),
+            seen: RecentHashes::new(RECENT_CAPACITY),
+            metrics: Arc::new(IngestMetrics::default()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<IngestMetrics> {
+        self.metrics.clone()
+    }
+
+    fn accept(&mut self, raw: &[u8], sink: &dyn TxSink) -> bool {
+        let (hash, tx) = match tx_from_raw(raw) {
+            Ok(decoded) => decoded,
+            Err(_) => {
+                self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        };
+        if !self.seen.insert(hash) {
+            self.metrics.duplicates.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.metrics.received.fetch_add(1, Ordering::Relaxed);
+        sink.deliver(&self.name, Some(hash), tx);
+        true
+    }
+
+    async fn connect(&self) -> Result<tonic::client::Grpc<Channel>, IngestError> {
+        let channel = Endpoint::from_shared(self.url.clone())
+            .map_err(grpc_error)?
+            .connect()
+            .await
+            .map_err(grpc_error)?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await.map_err(grpc_error)?;
+        Ok(grpc)
+    }
+
+    /// Снимок ожидающих транзакций пула — после обрыва
+    async fn heal(&mut self, grpc: &mut tonic::client::Grpc<Channel>, sink: &dyn TxSink) -> Result<usize, IngestError> {
+        let codec: ProstCodec<AllRequest, AllReply> = ProstCodec::default();
+        let reply = grpc
+            .unary(tonic::Request::new(AllRequest {}), PathAndQuery::from_static("/txpool.Txpool/All"), codec)
+            .await
+            .map_err(grpc_error)?
+            .into_inner();
+
+        let mut healed = 0;
+        for tx in reply.txs.iter().filter(|t| t.txn_type == PENDING) {
+            if self.accept(&tx.rlp_tx, sink) {
+                healed += 1;
+            }
+        }
+        self.metrics.healed.fetch_add(healed as u64, Ordering::Relaxed);
+        Ok(healed)
+    }
+
+    /// Читает `OnAdd` до обрыва; `Ok(true)` — остановка по сигналу
+    async fn stream(
+        &mut self,
+        grpc: &mut tonic::client::Grpc<Channel>,
+        sink: &dyn TxSink,
+        shutdown: &mut ShutdownSignal,
+    ) -> Result<bool, IngestError> {
+        let codec: ProstCodec<OnAddRequest, OnAddReply> = ProstCodec::default();
+        grpc.ready().await.map_err(grpc_error)?;
+        let mut stream = grpc
+            .server_streaming(tonic::Request::new(OnAddRequest {}), PathAndQuery::from_static("/txpool.Txpool/OnAdd"), codec)
+            .await
+            .map_err(grpc_error)?
+            .into_inner();
+
+        loop {
+            tokio::select! {
+                reply = stream.message() => match reply.map_err(grpc_error)? {
+                    Some(reply) => {
+                        for raw in &reply.rpl_txs {
+                            self.accept(raw, sink);
+                        }
+                    }
+                    None => return Ok(false),
+                },
+                _ = shutdown.wait() => return Ok(true),
+            }
+        }
+    }
+
+    /// Как у WebSocket-источника: переподключение с задержкой и догрузка пула после обрыва
+    pub async fn run(&mut self, sink: &dyn TxSink, mut shutdown: ShutdownSignal) -> Result<(), IngestError> {
+        let mut attempt = 0u32;
+        let mut connected_before = false;
+
+        loop {
+            if shutdown.is_triggered() {
+                return Ok(());
+            }
+
+            match self.connect().await {
+                Ok(mut grpc) => {
+                    if connected_before {
+                        self.metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+                        if self.policy.heal_with_txpool && self.heal(&mut grpc, sink).await.is_err() {
+                            self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    connected_before = true;
+                    attempt = 0;
+
+                    match self.stream(&mut grpc, sink, &mut shutdown).await {
+                        Ok(true) => return Ok(()),
+                        Ok(false) => {}
+                        Err(_) => {
+                            self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                Err(_) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let delay = backoff_delay(&self.policy, attempt, thread_rng().gen());
+            attempt = attempt.saturating_add(1);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.wait() => return Ok(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,354 @@
+#[cfg(feature = "grpc-erigon")]
+pub mod erigon;
+pub mod multi;
+pub mod txpool;
+pub mod ws;
+
+use crate::amount::WeiAmount;
+use crate::chain::{ChainAdapter, Ethereum, FeeFields};
+use crate::compat::{keccak256, Address, H256, U256};
+use crate::config::{IngestionMode, IngestionProvider};
+use crate::pipeline::Pipeline;
+use crate::shutdown::ShutdownSignal;
+use crate::tx::Tx;
+use ethers::providers::{Http, Provider, Ws};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Signature, Transaction};
+use ethers::utils::rlp::{Rlp, RlpStream};
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Ошибки источников мемпула
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Method {0} is not supported by provider")]
+    Unsupported(&'static str),
+
+    #[error("Cannot decode transaction: {0}")]
+    DecodeError(String),
+}
+
+/// Ошибка вызова; «метод не найден» у провайдера — отдельный случай, а не сбой
+pub(crate) fn rpc_error(e: impl std::fmt::Display, method: &'static str) -> IngestError {
+    let message = e.to_string();
+    let lower = message.to_lowercase();
+    if lower.contains("not found") || lower.contains("not supported") || lower.contains("does not exist") {
+        IngestError::Unsupported(method)
+    } else {
+        IngestError::ProviderError(message)
+    }
+}
+
+/// Транзакция RPC в формате детектора; суммы в wei без округления
+pub fn tx_from_rpc(tx: &Transaction) -> Tx {
+    tx_from_rpc_on(&Ethereum::mainnet(), tx, None)
+}
+
+/// То же по правилам комиссии сети `chain`; `base_fee` — текущего блока, если известен
+pub fn tx_from_rpc_on(chain: &dyn ChainAdapter, tx: &Transaction, base_fee: Option<U256>) -> Tx {
+    let gas_price = chain.effective_gas_price(FeeFields::from(tx), base_fee);
+    Tx {
+        from: tx.from.into(),
+        to: tx.to.map(Into::into).unwrap_or_default(),
+        value: WeiAmount::from_wei(tx.value),
+        gas_price: WeiAmount::from_wei(gas_price),
+        input: tx.input.to_vec(),
+    }
+}
+
+/// Приёмник транзакций источников: конвейер напрямую или объединитель нескольких источников
+pub trait TxSink: Send + Sync {
+    /// `hash` — `None`, если источник его не знает (сводки `txpool_inspect`).
+    /// Возвращает `false`, если транзакция не принята
+    fn deliver(&self, source: &str, hash: Option<H256>, tx: Tx) -> bool;
+}
+
+impl<S: TxSink + ?Sized> TxSink for Arc<S> {
+    fn deliver(&self, source: &str, hash: Option<H256>, tx: Tx) -> bool {
+        self.as_ref().deliver(source, hash, tx)
+    }
+}
+
+impl TxSink for Pipeline {
+    fn deliver(&self, source: &str, hash: Option<H256>, tx: Tx) -> bool {
+        self.ingest_hashed(tx, hash, Some(source))
+    }
+}
+
+/// Разобранная подписанная транзакция: `Tx` детектора и поля конверта, которых в нём нет
+#[derive(Debug, Clone)]
+pub struct RawTx {
+    pub hash: H256,
+    /// Тип EIP-2718: 0 — legacy, 1 — 2930, 2 — 1559, 3 — 4844
+    pub tx_type: u8,
+    pub chain_id: Option<u64>,
+    pub nonce: U256,
+    pub gas_limit: U256,
+    /// Для 1559 и 4844 — `max_fee_per_gas`
+    pub gas_price: U256,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_versioned_hashes: Vec<H256>,
+    pub access_list_entries: usize,
+    pub tx: Tx,
+}
+
+fn decode_error(e: impl std::fmt::Display) -> IngestError {
+    IngestError::DecodeError(e.to_string())
+}
+
+/// Подписанная транзакция в RLP (legacy или EIP-2718): хэш и транзакция с восстановленным отправителем
+pub fn tx_from_raw(raw: &[u8]) -> Result<(H256, Tx), IngestError> {
+    decode_raw(raw).map(|decoded| (decoded.hash, decoded.tx))
+}
+
+/// Полный разбор подписанной транзакции: legacy, 2930, 1559 и 4844
+pub fn decode_raw(raw: &[u8]) -> Result<RawTx, IngestError> {
+    if raw.first() == Some(&BLOB_TX_TYPE) {
+        return decode_blob(&raw[1..]);
+    }
+
+    let rlp = Rlp::new(raw);
+    let (typed, signature) = TypedTransaction::decode_signed(&rlp).map_err(decode_error)?;
+    let from = signature.recover(typed.sighash()).map_err(decode_error)?;
+    let (tx_type, max_priority_fee_per_gas) = match &typed {
+        TypedTransaction::Legacy(_) => (0, None),
+        TypedTransaction::Eip2930(_) => (1, None),
+        TypedTransaction::Eip1559(request) => (2, request.max_priority_fee_per_gas),
+    };
+    let gas_price = match &typed {
+        TypedTransaction::Eip1559(request) => request.max_fee_per_gas.unwrap_or_default(),
+        other => other.gas_price().unwrap_or_default(),
+    };
+    let tx = Tx {
+        from: from.into(),
+        to: typed.to_addr().copied().map(Into::into).unwrap_or_default(),
+        value: WeiAmount::from_wei(typed.value().copied().unwrap_or_default()),
+        gas_price: WeiAmount::from_wei(gas_price),
+        input: typed.data().map(|d| d.to_vec()).unwrap_or_default(),
+    };
+    Ok(RawTx {
+        hash: keccak256(raw),
+        tx_type,
+        chain_id: typed.chain_id().map(|id| id.as_u64()),
+        nonce: typed.nonce().copied().unwrap_or_default(),
+        gas_limit: typed.gas().copied().unwrap_or_default(),
+        gas_price,
+        max_priority_fee_per_gas,
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: Vec::new(),
+        access_list_entries: typed.access_list().map_or(0, |list| list.0.len()),
+        tx,
+    })
+}
+
+const BLOB_TX_TYPE: u8 = 0x03;
+
+/// Поля EIP-4844 до подписи: `chain_id .. blob_versioned_hashes`
+const BLOB_UNSIGNED_FIELDS: usize = 11;
+
+/// ethers 2 не знает тип 3: разбираем RLP по EIP-4844 вручную.
+/// Сетевая форма `[tx, blobs, commitments, proofs]` из `eth_sendRawTransaction` тоже принимается
+fn decode_blob(payload: &[u8]) -> Result<RawTx, IngestError> {
+    let outer = Rlp::new(payload);
+    let first = outer.at(0).map_err(decode_error)?;
+    let body = if first.is_list() { first } else { outer };
+    if body.item_count().map_err(decode_error)? != BLOB_UNSIGNED_FIELDS + 3 {
+        return Err(IngestError::DecodeError("blob transaction must have 14 fields".into()));
+    }
+
+    let mut unsigned = RlpStream::new_list(BLOB_UNSIGNED_FIELDS);
+    for i in 0..BLOB_UNSIGNED_FIELDS {
+        unsigned.append_raw(body.at(i).map_err(decode_error)?.as_raw(), 1);
+    }
+    let mut preimage = vec![BLOB_TX_TYPE];
+    preimage.extend_from_slice(&unsigned.out());
+    let signature = Signature {
+        v: body.val_at::<u64>(11).map_err(decode_error)?,
+        r: body.val_at(12).map_err(decode_error)?,
+        s: body.val_at(13).map_err(decode_error)?,
+    };
+    let from = signature.recover(keccak256(&preimage)).map_err(decode_error)?;
+
+    let mut envelope = vec![BLOB_TX_TYPE];
+    envelope.extend_from_slice(body.as_raw());
+    let gas_price: U256 = body.val_at(3).map_err(decode_error)?;
+    let to: Address = body.val_at(5).map_err(decode_error)?;
+    Ok(RawTx {
+        hash: keccak256(&envelope),
+        tx_type: BLOB_TX_TYPE,
+        chain_id: Some(body.val_at(0).map_err(decode_error)?),
+        nonce: body.val_at(1).map_err(decode_error)?,
+        gas_limit: body.val_at(4).map_err(decode_error)?,
+        gas_price,
+        max_priority_fee_per_gas: Some(body.val_at(2).map_err(decode_error)?),
+        max_fee_per_blob_gas: Some(body.val_at(9).map_err(decode_error)?),
+        blob_versioned_hashes: body.list_at(10).map_err(decode_error)?,
+        access_list_entries: body.at(8).and_then(|list| list.item_count()).map_err(decode_error)?,
+        tx: Tx {
+            from: from.into(),
+            to: to.into(),
+            value: WeiAmount::from_wei(body.val_at::<U256>(6).map_err(decode_error)?),
+            gas_price: WeiAmount::from_wei(gas_price),
+            input: body.val_at(7).map_err(decode_error)?,
+        },
+    })
+}
+
+/// Последние принятые хэши: повторная доставка после переподключения не попадает в конвейер
+pub struct RecentHashes {
+    capacity: usize,
+    order: VecDeque<H256>,
+    set: HashSet<H256>,
+}
+
+impl RecentHashes {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            set: HashSet::new(),
+        }
+    }
+
+    /// `true`, если хэш новый
+    pub fn insert(&mut self, hash: H256) -> bool {
+        if !self.set.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        while self.order.len() > self.capacity {
+            if let Some(old) = self.order.pop_front() {
+                self.set.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+/// Счётчики источника
+#[derive(Default)]
+pub struct IngestMetrics {
+    pub received: AtomicU64,
+    pub duplicates: AtomicU64,
+    pub reconnects: AtomicU64,
+    /// Транзакции, восстановленные из `txpool_content` после обрыва
+    pub healed: AtomicU64,
+    /// Сбои подключения, подписки, опроса и разбора транзакций
+    pub errors: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestMetricsSnapshot {
+    pub received: u64,
+    pub duplicates: u64,
+    pub reconnects: u64,
+    pub healed: u64,
+    pub errors: u64,
+}
+
+impl IngestMetrics {
+    pub fn snapshot(&self) -> IngestMetricsSnapshot {
+        IngestMetricsSnapshot {
+            received: self.received.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            healed: self.healed.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Запускает источник провайдера в режиме из конфига и работает до сигнала остановки
+pub async fn run_provider(
+    config: &IngestionProvider,
+    sink: &dyn TxSink,
+    shutdown: ShutdownSignal,
+) -> Result<(), IngestError> {
+    let interval = Duration::from_millis(config.poll_interval_ms);
+    match config.mode {
+        IngestionMode::Subscribe => {
+            ws::WsSource::new(&config.name, &config.url, config.reconnect.clone()).run(sink, shutdown).await;
+            Ok(())
+        }
+        #[cfg(feature = "grpc-erigon")]
+        IngestionMode::ErigonGrpc => {
+            erigon::ErigonSource::new(&config.name, &config.url)
+                .with_policy(config.reconnect.clone())
+                .run(sink, shutdown)
+                .await
+        }
+        #[cfg(not(feature = "grpc-erigon"))]
+        IngestionMode::ErigonGrpc => Err(IngestError::Unsupported("erigon txpool gRPC (build with grpc-erigon)")),
+        IngestionMode::Poll if config.url.starts_with("ws") => {
+            let provider = Provider::<Ws>::connect(&config.url)
+                .await
+                .map_err(|e| IngestError::ProviderError(e.to_string()))?;
+            txpool::TxpoolSource::new(&config.name, Arc::new(provider), config.txpool_method, interval)
+                .run(sink, shutdown)
+                .await
+        }
+        IngestionMode::Poll => {
+            let provider = Provider::<Http>::try_from(config.url.as_str())
+                .map_err(|e| IngestError::ProviderError(e.to_string()))?;
+            txpool::TxpoolSource::new(&config.name, Arc::new(provider), config.txpool_method, interval)
+                .run(sink, shutdown)
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Тип 3, подписанный ключом из примеров EIP-155; хэш — keccak256 всего конверта
+    const BLOB_TX: &str = "03f897010584773594008509502f900082520894111111111111111111111111111111111111111187038d7ea4c6800082deadc003e1a001b0761f87b081d5cf10757ccc89f12be355c70e2e29df288b65b30710dcbcd180a012172edaa97ab707aad34a937e42647287df098efe2f4d34f649834d10f2cb1fa076b2ec1ea8631e1a354f5fea4b2c7c195c9cdebe73efd1c508732a0c79b87861";
+
+    #[test]
+    fn test_decodes_blob_transaction_in_both_forms() {
+        let raw = ethers::utils::hex::decode(BLOB_TX).unwrap();
+        let decoded = decode_raw(&raw).unwrap();
+        let hash: H256 = "0xba7c9a24cfb0c86ea61017e33d6554b22695636d6d5e5e1fda343e5a5384fb4f".parse().unwrap();
+        assert_eq!(decoded.hash, hash);
+        assert_eq!(decoded.tx_type, 3);
+        assert_eq!(decoded.chain_id, Some(1));
+        assert_eq!(decoded.nonce, U256::from(5));
+        assert_eq!(decoded.gas_limit, U256::from(21_000));
+        assert_eq!(decoded.gas_price, U256::from(40_000_000_000u64));
+        assert_eq!(decoded.max_priority_fee_per_gas, Some(U256::from(2_000_000_000u64)));
+        assert_eq!(decoded.max_fee_per_blob_gas, Some(U256::from(3)));
+        assert_eq!(
+            decoded.blob_versioned_hashes,
+            vec!["0x01b0761f87b081d5cf10757ccc89f12be355c70e2e29df288b65b30710dcbcd1".parse::<H256>().unwrap()]
+        );
+        assert_eq!(decoded.access_list_entries, 0);
+        assert_eq!(decoded.tx.from.to_string(), "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+        assert_eq!(decoded.tx.to.to_string(), "0x1111111111111111111111111111111111111111");
+        assert_eq!(decoded.tx.value, WeiAmount::from_eth(0.001));
+        assert_eq!(decoded.tx.input, vec![0xde, 0xad]);
+
+        // Сетевая форма: те же поля плюс блобы, коммитменты и доказательства; хэш не меняется
+        let mut network = RlpStream::new_list(4);
+        network.append_raw(&raw[1..], 1);
+        for sidecar in [[0x01u8; 4], [0x02; 4], [0x03; 4]] {
+            network.begin_list(1).append(&sidecar.to_vec());
+        }
+        let mut wrapped = vec![BLOB_TX_TYPE];
+        wrapped.extend_from_slice(&network.out());
+        let unwrapped = decode_raw(&wrapped).unwrap();
+        assert_eq!(unwrapped.hash, hash);
+        assert_eq!(unwrapped.tx, decoded.tx);
+
+        let mut truncated = raw.clone();
+        truncated.truncate(raw.len() - 33);
+        assert!(decode_raw(&truncated).is_err());
+    }
+}
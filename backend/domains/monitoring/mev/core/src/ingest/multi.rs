@@ -0,0 +1,204 @@
+use super::{run_provider, TxSink};
+use crate::compat::H256;
+use crate::config::IngestionProvider;
+use crate::shutdown::ShutdownSignal;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Сколько помнить первую доставку: поздние копии за пределами окна считаются новыми
+const DEFAULT_WINDOW: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Default, Clone)]
+struct SourceStats {
+    delivered: u64,
+    first: u64,
+    duplicates: u64,
+    /// Без хэша: не участвуют в дедупликации и покрытии
+    unhashed: u64,
+    lag_total_ms: u64,
+    lag_max_ms: u64,
+}
+
+/// Метрики источника: насколько часто он первый и насколько отстаёт от самого быстрого
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceMetrics {
+    pub name: String,
+    pub delivered: u64,
+    pub first: u64,
+    pub duplicates: u64,
+    pub unhashed: u64,
+    /// Доля уникальных транзакций окна, которые источник доставил хотя бы раз
+    pub coverage: f64,
+    /// Доля доставленных, где источник был первым
+    pub first_ratio: f64,
+    /// Среднее отставание от первого источника по не первым доставкам
+    pub mean_lag_ms: f64,
+    pub max_lag_ms: u64,
+}
+
+struct FirstSeen {
+    source: String,
+    at: Instant,
+}
+
+struct MergeState {
+    seen: HashMap<H256, FirstSeen>,
+    order: VecDeque<(H256, Instant)>,
+    stats: BTreeMap<String, SourceStats>,
+}
+
+/// Объединение нескольких провайдеров мемпула: дедупликация по хэшу,
+/// учёт первого источника и отставания остальных
+pub struct MultiSource {
+    /// Куда уходит первая доставка транзакции: конвейер, возможно через обёртки
+    sink: Arc<dyn TxSink>,
+    window: Duration,
+    state: Mutex<MergeState>,
+    /// Источники, завершившиеся с ошибкой
+    errors: TaskErrors,
+}
+
+impl MultiSource {
+    pub fn new(sink: Arc<dyn TxSink>) -> Self {
+        Self::with_window(sink, DEFAULT_WINDOW)
+    }
+
+    pub fn with_window(sink: Arc<dyn TxSink>, window: Duration) -> Self {
+        Self {
+            sink,
+            window,
+            state: Mutex::new(MergeState {
+                seen: HashMap::new(),
+                order: VecDeque::new(),
+                stats: BTreeMap::new(),
+            }),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Какой источник доставил транзакцию первым (пока она в окне)
+    pub fn first_source(&self, hash: H256) -> Option<String> {
+        self.state.lock().unwrap().seen.get(&hash).map(|f| f.source.clone())
+    }
+
+    /// Возвращает `true`, если транзакция впервые увидена в окне
+    fn record(&self, source: &str, hash: Option<H256>, now: Instant) -> bool {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        while let Some((old, at)) = state.order.front().copied() {
+            if now.duration_since(at) <= self.window {
+                break;
+            }
+            state.order.pop_front();
+            state.seen.remove(&old);
+        }
+
+        let Some(hash) = hash else {
+            let stats = state.stats.entry(source.to_string()).or_default();
+            stats.delivered += 1;
+            stats.unhashed += 1;
+            return true;
+        };
+
+        let lag = state.seen.get(&hash).map(|f| now.duration_since(f.at).as_millis() as u64);
+        let stats = state.stats.entry(source.to_string()).or_default();
+        stats.delivered += 1;
+        match lag {
+            Some(lag) => {
+                stats.duplicates += 1;
+                stats.lag_total_ms += lag;
+                stats.lag_max_ms = stats.lag_max_ms.max(lag);
+                false
+            }
+            None => {
+                stats.first += 1;
+                state.seen.insert(hash, FirstSeen { source: source.to_string(), at: now });
+                state.order.push_back((hash, now));
+                true
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> Vec<SourceMetrics> {
+        let state = self.state.lock().unwrap();
+        let unique: u64 = state.stats.values().map(|s| s.first).sum();
+        state
+            .stats
+            .iter()
+            .map(|(name, s)| {
+                let hashed = s.delivered - s.unhashed;
+                let late = hashed - s.first;
+                SourceMetrics {
+                    name: name.clone(),
+                    delivered: s.delivered,
+                    first: s.first,
+                    duplicates: s.duplicates,
+                    unhashed: s.unhashed,
+                    coverage: if unique == 0 { 0.0 } else { (hashed as f64 / unique as f64).min(1.0) },
+                    first_ratio: if hashed == 0 { 0.0 } else { s.first as f64 / hashed as f64 },
+                    mean_lag_ms: if late == 0 { 0.0 } else { s.lag_total_ms as f64 / late as f64 },
+                    max_lag_ms: s.lag_max_ms,
+                }
+            })
+            .collect()
+    }
+
+    /// Запускает все провайдеры параллельно; каждый работает до сигнала остановки
+    pub async fn run(self: Arc<Self>, providers: Vec<IngestionProvider>, shutdown: ShutdownSignal) {
+        let mut tasks = JoinSet::new();
+        for provider in providers {
+            let sink = self.clone();
+            let shutdown = shutdown.clone();
+            tasks.spawn(async move {
+                if let Err(e) = run_provider(&provider, sink.as_ref(), shutdown).await {
+                    sink.errors.record(format!("mempool source {} stopped: {}", provider.name, e));
+                }
+            });
+        }
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+impl TxSink for MultiSource {
+    fn deliver(&self, source: &str, hash: Option<H256>, tx: Tx) -> bool {
+        if !self.record(source, hash, Instant::now()) {
+            return false;
+        }
+        self.sink.deliver(source, hash, tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{Pipeline, PipelineConfig};
+
+    #[test]
+    fn test_first_delivery_wins_and_lag_is_tracked() {
+        let multi = MultiSource::new(Arc::new(Pipeline::new(PipelineConfig::default())));
+        let start = Instant::now();
+        let (a, b) = (H256::repeat_byte(1), H256::repeat_byte(2));
+
+        assert!(multi.record("local", Some(a), start));
+        assert!(!multi.record("alchemy", Some(a), start + Duration::from_millis(40)));
+        assert!(multi.record("alchemy", Some(b), start + Duration::from_millis(50)));
+        assert_eq!(multi.first_source(a).as_deref(), Some("local"));
+
+        let metrics = multi.metrics();
+        let alchemy = metrics.iter().find(|m| m.name == "alchemy").unwrap();
+        assert_eq!(alchemy.coverage, 1.0);
+        assert_eq!(alchemy.first_ratio, 0.5);
+        assert_eq!(alchemy.mean_lag_ms, 40.0);
+        let local = metrics.iter().find(|m| m.name == "local").unwrap();
+        assert_eq!(local.coverage, 0.5);
+    }
+}
@@ -0,0 +1,143 @@
+use super::{rpc_error, tx_from_rpc, IngestError, IngestMetrics, TxSink};
+use crate::amount::WeiAmount;
+use crate::compat::{Address, H256, U256};
+use crate::config::TxpoolMethod;
+use crate::shutdown::ShutdownSignal;
+use crate::tx::Tx;
+use ethers::providers::Middleware;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Ключ транзакции в снимке пула. Сводки `txpool_inspect` не содержат хэша,
+/// поэтому замена (тот же nonce, другая цена) считается новой транзакцией
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PoolKey {
+    Hash(H256),
+    Summary { from: Address, nonce: String, gas_price: U256 },
+}
+
+/// Сравнивает снимок с предыдущим: возвращает новый набор ключей и транзакции, которых раньше не было
+pub fn diff_snapshot<K: Eq + Hash + Clone>(last: &HashSet<K>, current: Vec<(K, Tx)>) -> (HashSet<K>, Vec<(K, Tx)>) {
+    let mut keys = HashSet::with_capacity(current.len());
+    let mut fresh = Vec::new();
+    for (key, tx) in current {
+        if !last.contains(&key) {
+            fresh.push((key.clone(), tx));
+        }
+        keys.insert(key);
+    }
+    (keys, fresh)
+}
+
+/// Опрос пула для провайдеров без подписок на ожидающие транзакции
+pub struct TxpoolSource<M> {
+    name: String,
+    provider: Arc<M>,
+    method: TxpoolMethod,
+    interval: Duration,
+    last: HashSet<PoolKey>,
+    metrics: Arc<IngestMetrics>,
+}
+
+impl<M: Middleware> TxpoolSource<M> {
+    pub fn new(name: &str, provider: Arc<M>, method: TxpoolMethod, interval: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            provider,
+            method,
+            interval,
+            last: HashSet::new(),
+            metrics: Arc::new(IngestMetrics::default()),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<IngestMetrics> {
+        self.metrics.clone()
+    }
+
+    async fn snapshot(&self) -> Result<Vec<(PoolKey, Tx)>, IngestError> {
+        match self.method {
+            TxpoolMethod::Content => {
+                let content = self
+                    .provider
+                    .txpool_content()
+                    .await
+                    .map_err(|e| rpc_error(e, "txpool_content"))?;
+                Ok(content
+                    .pending
+                    .values()
+                    .flat_map(|by_nonce| by_nonce.values())
+                    .map(|tx| (PoolKey::Hash(tx.hash), tx_from_rpc(tx)))
+                    .collect())
+            }
+            TxpoolMethod::Inspect => {
+                let inspect = self
+                    .provider
+                    .txpool_inspect()
+                    .await
+                    .map_err(|e| rpc_error(e, "txpool_inspect"))?;
+                Ok(inspect
+                    .pending
+                    .iter()
+                    .flat_map(|(from, by_nonce)| by_nonce.iter().map(move |(nonce, s)| (from, nonce, s)))
+                    .map(|(from, nonce, summary)| {
+                        let key = PoolKey::Summary {
+                            from: *from,
+                            nonce: nonce.clone(),
+                            gas_price: summary.gas_price,
+                        };
+                        let tx = Tx {
+                            from: (*from).into(),
+                            to: summary.to.map(Into::into).unwrap_or_default(),
+                            value: WeiAmount::from_wei(summary.value),
+                            gas_price: WeiAmount::from_wei(summary.gas_price),
+                            input: Vec::new(),
+                        };
+                        (key, tx)
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Один опрос: в конвейер уходят только транзакции, появившиеся с прошлого снимка
+    pub async fn poll_once(&mut self, sink: &dyn TxSink) -> Result<usize, IngestError> {
+        let current = self.snapshot().await?;
+        let total = current.len();
+        let (keys, fresh) = diff_snapshot(&self.last, current);
+        self.last = keys;
+
+        self.metrics.received.fetch_add(fresh.len() as u64, Ordering::Relaxed);
+        self.metrics.duplicates.fetch_add((total - fresh.len()) as u64, Ordering::Relaxed);
+        let count = fresh.len();
+        for (key, tx) in fresh {
+            let hash = match key {
+                PoolKey::Hash(hash) => Some(hash),
+                PoolKey::Summary { .. } => None,
+            };
+            sink.deliver(&self.name, hash, tx);
+        }
+        Ok(count)
+    }
+
+    /// Опрашивает пул до сигнала остановки; если метод не поддерживается, завершается с ошибкой
+    pub async fn run(&mut self, sink: &dyn TxSink, mut shutdown: ShutdownSignal) -> Result<(), IngestError> {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return Ok(()),
+            }
+            match self.poll_once(sink).await {
+                Ok(_) => {}
+                Err(e @ IngestError::Unsupported(_)) => return Err(e),
+                Err(_) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
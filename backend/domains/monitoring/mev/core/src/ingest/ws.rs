@@ -0,0 +1,201 @@
+use super::{rpc_error, tx_from_rpc, IngestError, IngestMetrics, RecentHashes, TxSink};
+use crate::shutdown::ShutdownSignal;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
+use ethers::types::Transaction;
+use serde::{Serialize, Deserialize};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Сколько последних хэшей помнить для отсева повторов после переподключения
+const RECENT_CAPACITY: usize = 100_000;
+
+/// Переподключение к WebSocket-провайдеру
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Разброс задержки как доля от неё (0.0 - 1.0), чтобы экземпляры не переподключались синхронно
+    pub jitter: f64,
+    /// После обрыва догружать пул через `txpool_content`
+    pub heal_with_txpool: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            jitter: 0.3,
+            heal_with_txpool: true,
+        }
+    }
+}
+
+/// Задержка перед попыткой `attempt` (с нуля); `random` — равномерно из [0, 1)
+pub fn backoff_delay(policy: &ReconnectPolicy, attempt: u32, random: f64) -> Duration {
+    let base = policy
+        .initial_backoff_ms
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(policy.max_backoff_ms) as f64;
+    let spread = base * policy.jitter.clamp(0.0, 1.0);
+    Duration::from_millis((base - spread + 2.0 * spread * random) as u64)
+}
+
+/// Подписка на ожидающие транзакции через WebSocket, переживающая обрывы провайдера
+pub struct WsSource {
+    name: String,
+    url: String,
+    policy: ReconnectPolicy,
+    seen: RecentHashes,
+    metrics: Arc<IngestMetrics>,
+}
+
+impl WsSource {
+    pub fn new(name: &str, url: &str, policy: ReconnectPolicy) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            policy,
+            seen: RecentHashes::new(RECENT_CAPACITY),
+            metrics: Arc::new(IngestMetrics::default()),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<IngestMetrics> {
+        self.metrics.clone()
+    }
+
+    fn accept(&mut self, tx: &Transaction, sink: &dyn TxSink) -> bool {
+        if !self.seen.insert(tx.hash) {
+            self.metrics.duplicates.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.metrics.received.fetch_add(1, Ordering::Relaxed);
+        sink.deliver(&self.name, Some(tx.hash), tx_from_rpc(tx));
+        true
+    }
+
+    /// Догружает ожидающие транзакции, пропущенные за время обрыва
+    async fn heal(&mut self, provider: &Provider<Ws>, sink: &dyn TxSink) -> Result<usize, IngestError> {
+        let content = provider
+            .txpool_content()
+            .await
+            .map_err(|e| rpc_error(e, "txpool_content"))?;
+
+        let mut healed = 0;
+        for tx in content.pending.values().flat_map(|by_nonce| by_nonce.values()) {
+            if self.accept(tx, sink) {
+                healed += 1;
+            }
+        }
+        self.metrics.healed.fetch_add(healed as u64, Ordering::Relaxed);
+        Ok(healed)
+    }
+
+    /// Читает подписку до обрыва; `Ok(true)` — остановка по сигналу.
+    /// Полные транзакции в подписке поддерживают не все узлы, иначе берём хэши и дочитываем
+    async fn stream(
+        &mut self,
+        provider: &Provider<Ws>,
+        sink: &dyn TxSink,
+        shutdown: &mut ShutdownSignal,
+    ) -> Result<bool, IngestError> {
+        if let Ok(mut stream) = provider.subscribe_full_pending_txs().await {
+            loop {
+                tokio::select! {
+                    tx = stream.next() => match tx {
+                        Some(tx) => {
+                            self.accept(&tx, sink);
+                        }
+                        None => return Ok(false),
+                    },
+                    _ = shutdown.wait() => return Ok(true),
+                }
+            }
+        }
+
+        let mut stream = provider
+            .subscribe_pending_txs()
+            .await
+            .map_err(|e| IngestError::ProviderError(e.to_string()))?;
+        loop {
+            tokio::select! {
+                hash = stream.next() => match hash {
+                    Some(hash) => {
+                        if let Ok(Some(tx)) = provider.get_transaction(hash).await {
+                            self.accept(&tx, sink);
+                        }
+                    }
+                    None => return Ok(false),
+                },
+                _ = shutdown.wait() => return Ok(true),
+            }
+        }
+    }
+
+    /// Подключается, подписывается и после каждого обрыва переподключается с нарастающей задержкой
+    pub async fn run(&mut self, sink: &dyn TxSink, mut shutdown: ShutdownSignal) {
+        let mut attempt = 0u32;
+        let mut connected_before = false;
+
+        loop {
+            if shutdown.is_triggered() {
+                return;
+            }
+
+            match Provider::<Ws>::connect(&self.url).await {
+                Ok(provider) => {
+                    if connected_before {
+                        self.metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+                        if self.policy.heal_with_txpool {
+                            match self.heal(&provider, sink).await {
+                                Ok(_) | Err(IngestError::Unsupported(_)) => {}
+                                Err(_) => {
+                                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                        }
+                    }
+                    connected_before = true;
+                    attempt = 0;
+
+                    match self.stream(&provider, sink, &mut shutdown).await {
+                        Ok(true) => return,
+                        Ok(false) => {}
+                        Err(_) => {
+                            self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                Err(_) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let delay = backoff_delay(&self.policy, attempt, thread_rng().gen());
+            attempt = attempt.saturating_add(1);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_to_cap_with_jitter() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(backoff_delay(&policy, 0, 0.5), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&policy, 3, 0.5), Duration::from_millis(4_000));
+        assert_eq!(backoff_delay(&policy, 30, 0.5), Duration::from_millis(30_000));
+        assert_eq!(backoff_delay(&policy, 0, 0.0), Duration::from_millis(350));
+        assert!(backoff_delay(&policy, 0, 0.999) < Duration::from_millis(650));
+    }
+}
@@ -0,0 +1,543 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::compat::{Address, H256, U256};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::units::Bps;
+use async_trait::async_trait;
+use ethers::contract::{parse_log, EthEvent};
+use ethers::providers::Middleware;
+use ethers::types::{Bytes, Filter, Log};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::watch;
+
+#[derive(Debug, Error)]
+pub enum IntentError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Unexpected response: {0}")]
+    InvalidResponse(String),
+}
+
+fn provider_err(e: impl std::fmt::Display) -> IntentError {
+    IntentError::ProviderError(e.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentProtocol {
+    Cow,
+    UniswapX,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Trade", abi = "Trade(address,address,address,uint256,uint256,uint256,bytes)")]
+struct CowTrade {
+    #[ethevent(indexed)]
+    owner: Address,
+    sell_token: Address,
+    buy_token: Address,
+    sell_amount: U256,
+    buy_amount: U256,
+    fee_amount: U256,
+    order_uid: Bytes,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Settlement", abi = "Settlement(address)")]
+struct CowSettlement {
+    #[ethevent(indexed)]
+    solver: Address,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Fill", abi = "Fill(bytes32,address,address,uint256)")]
+struct UniswapXFill {
+    #[ethevent(indexed)]
+    order_hash: [u8; 32],
+    #[ethevent(indexed)]
+    filler: Address,
+    #[ethevent(indexed)]
+    swapper: Address,
+    nonce: U256,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Transfer", abi = "Transfer(address,address,uint256)")]
+struct Erc20Transfer {
+    #[ethevent(indexed)]
+    from: Address,
+    #[ethevent(indexed)]
+    to: Address,
+    value: U256,
+}
+
+/// Адреса контрактов и API; по умолчанию mainnet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentConfig {
+    pub cow_settlement: Address,
+    pub uniswapx_reactors: Vec<Address>,
+    pub cow_api_url: String,
+    pub uniswapx_api_url: String,
+    /// Исполнение хуже котировки больше чем на столько б.п. — алерт
+    pub max_shortfall_bps: Bps,
+}
+
+impl Default for IntentConfig {
+    fn default() -> Self {
+        Self {
+            cow_settlement: "0x9008D19f58AAbD9eD0D60971565AA8510560ab41".parse().unwrap(),
+            uniswapx_reactors: vec![
+                "0x6000da47483062A0D734Ba3dc7576Ce6A0B645C4".parse().unwrap(),
+                "0x00000011F84B9aa48e5f8aA8B9897600006289Be".parse().unwrap(),
+            ],
+            cow_api_url: "https://api.cow.fi/mainnet".into(),
+            uniswapx_api_url: "https://api.uniswap.org".into(),
+            max_shortfall_bps: Bps::new(50).unwrap(),
+        }
+    }
+}
+
+/// Нулевой адрес в выходах UniswapX — нативный ETH
+pub const NATIVE_TOKEN: Address = Address::zero();
+
+/// Снижение выхода голландского аукциона UniswapX от `quoted_buy_amount` до `end_amount`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DutchDecay {
+    pub end_amount: U256,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// Ордер, как его видел пользователь до исполнения
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotedOrder {
+    pub owner: Address,
+    pub buy_token: Address,
+    /// Котировка на момент подписи; для UniswapX — стартовая сумма голландского аукциона
+    pub quoted_buy_amount: U256,
+    pub decay: Option<DutchDecay>,
+}
+
+impl QuotedOrder {
+    /// Сколько должен был получить владелец при исполнении в момент `timestamp`
+    pub fn expected_at(&self, timestamp: u64) -> U256 {
+        let Some(decay) = &self.decay else {
+            return self.quoted_buy_amount;
+        };
+        if timestamp <= decay.start_time || decay.end_time <= decay.start_time {
+            return self.quoted_buy_amount;
+        }
+        if timestamp >= decay.end_time || decay.end_amount >= self.quoted_buy_amount {
+            return decay.end_amount.min(self.quoted_buy_amount);
+        }
+        let elapsed = U256::from(timestamp - decay.start_time);
+        let duration = U256::from(decay.end_time - decay.start_time);
+        self.quoted_buy_amount - (self.quoted_buy_amount - decay.end_amount) * elapsed / duration
+    }
+}
+
+/// Off-chain книга ордеров протоколов намерений
+#[async_trait]
+pub trait OrderBook: Send + Sync {
+    /// `Ok(None)` — API не знает ордер
+    async fn quoted(&self, protocol: IntentProtocol, uid: &str) -> Result<Option<QuotedOrder>, IntentError>;
+}
+
+/// API CoW Protocol и UniswapX по HTTP
+pub struct HttpOrderBook {
+    client: reqwest::Client,
+    cow_api_url: String,
+    uniswapx_api_url: String,
+    chain_id: u64,
+}
+
+impl HttpOrderBook {
+    pub fn new(config: &IntentConfig, chain_id: u64) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            cow_api_url: config.cow_api_url.trim_end_matches('/').to_string(),
+            uniswapx_api_url: config.uniswapx_api_url.trim_end_matches('/').to_string(),
+            chain_id,
+        }
+    }
+
+    async fn get(&self, url: String) -> Result<Option<Value>, IntentError> {
+        let response = self.client.get(url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+}
+
+fn field<T: std::str::FromStr>(value: &Value, key: &str) -> Result<T, IntentError> {
+    value[key]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| IntentError::InvalidResponse(format!("missing or invalid {}", key)))
+}
+
+fn amount(value: &Value, key: &str) -> Result<U256, IntentError> {
+    let raw: String = field(value, key)?;
+    U256::from_dec_str(&raw).map_err(|e| IntentError::InvalidResponse(format!("{}: {}", key, e)))
+}
+
+/// Момент времени: API отдаёт его и числом, и строкой
+fn timestamp(value: &Value, key: &str) -> Option<u64> {
+    value[key].as_u64().or_else(|| value[key].as_str()?.parse().ok())
+}
+
+#[async_trait]
+impl OrderBook for HttpOrderBook {
+    async fn quoted(&self, protocol: IntentProtocol, uid: &str) -> Result<Option<QuotedOrder>, IntentError> {
+        match protocol {
+            IntentProtocol::Cow => {
+                let Some(order) = self.get(format!("{}/api/v1/orders/{}", self.cow_api_url, uid)).await? else {
+                    return Ok(None);
+                };
+                // Котировка есть не у всех ордеров; лимит — нижняя граница исполнения
+                let quoted_buy_amount = match order["quote"].is_object() {
+                    true => amount(&order["quote"], "buyAmount")?,
+                    false => amount(&order, "buyAmount")?,
+                };
+                Ok(Some(QuotedOrder { owner: field(&order, "owner")?, buy_token: field(&order, "buyToken")?, quoted_buy_amount, decay: None }))
+            }
+            IntentProtocol::UniswapX => {
+                let url = format!("{}/v2/orders?orderHash={}&chainId={}", self.uniswapx_api_url, uid, self.chain_id);
+                let Some(response) = self.get(url).await? else {
+                    return Ok(None);
+                };
+                let Some(order) = response["orders"].get(0) else {
+                    return Ok(None);
+                };
+                let owner: Address = field(order, "swapper")?;
+                // Остальные выходы — комиссии интерфейса другим получателям
+                let outputs = order["outputs"].as_array().map(Vec::as_slice).unwrap_or_default();
+                let output = outputs
+                    .iter()
+                    .find(|o| field::<Address>(o, "recipient").is_ok_and(|r| r == owner))
+                    .or(outputs.first())
+                    .ok_or_else(|| IntentError::InvalidResponse("order has no outputs".into()))?;
+                let decay = match (timestamp(order, "decayStartTime"), timestamp(order, "decayEndTime")) {
+                    (Some(start_time), Some(end_time)) => {
+                        Some(DutchDecay { end_amount: amount(output, "endAmount")?, start_time, end_time })
+                    }
+                    _ => None,
+                };
+                Ok(Some(QuotedOrder { owner, buy_token: field(output, "token")?, quoted_buy_amount: amount(output, "startAmount")?, decay }))
+            }
+        }
+    }
+}
+
+/// Исполнение одного ордера в блоке
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settlement {
+    pub protocol: IntentProtocol,
+    /// `orderUid` CoW или `orderHash` UniswapX, hex
+    pub uid: String,
+    pub tx_hash: H256,
+    pub block: u64,
+    pub timestamp: u64,
+    /// Солвер CoW или филлер UniswapX
+    pub solver: Address,
+    pub owner: Address,
+    /// Другие участники того же расчёта (владельцы встречных ордеров CoW)
+    pub counterparties: Vec<Address>,
+    /// Для CoW — из события `Trade`; для UniswapX считается по переводам токена владельцу
+    pub executed_buy_amount: Option<U256>,
+}
+
+/// Мониторинг расчётов CoW Protocol и UniswapX: исполнение хуже котировки и сделки
+/// солвера с самим собой. Алерты уходят в шину с источником `intents`
+pub struct IntentMonitor<M> {
+    provider: Arc<M>,
+    orders: Arc<dyn OrderBook>,
+    config: IntentConfig,
+    /// Известные адреса солверов: солвер -> связанные с ним адреса
+    affiliates: HashMap<Address, HashSet<Address>>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> IntentMonitor<M> {
+    pub fn new(provider: Arc<M>, orders: Arc<dyn OrderBook>, config: IntentConfig) -> Self {
+        Self { provider, orders, config, affiliates: HashMap::new(), errors: TaskErrors::default() }
+    }
+
+    /// Блоки, которые не удалось проверить
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Адрес, принадлежащий солверу (его маркет-мейкер, казна)
+    pub fn with_affiliate(mut self, solver: Address, affiliate: Address) -> Self {
+        self.affiliates.entry(solver).or_default().insert(affiliate);
+        self
+    }
+
+    /// Расчёты ордеров в блоке по событиям контрактов
+    pub async fn settlements(&self, block: u64) -> Result<Vec<Settlement>, IntentError> {
+        let mut addresses = vec![self.config.cow_settlement];
+        addresses.extend(&self.config.uniswapx_reactors);
+        let filter = Filter::new().address(addresses).from_block(block).to_block(block);
+        let logs = self.provider.get_logs(&filter).await.map_err(provider_err)?;
+        if logs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let timestamp = self
+            .provider
+            .get_block(block)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| IntentError::InvalidResponse(format!("no block {}", block)))?
+            .timestamp
+            .low_u64();
+
+        let mut by_tx: BTreeMap<(u64, H256), Vec<Log>> = BTreeMap::new();
+        for log in logs {
+            let (Some(index), Some(hash)) = (log.transaction_index, log.transaction_hash) else {
+                continue;
+            };
+            by_tx.entry((index.as_u64(), hash)).or_default().push(log);
+        }
+
+        let mut settlements = Vec::new();
+        for ((_, tx_hash), logs) in by_tx {
+            let solver = logs.iter().find_map(|l| parse_log::<CowSettlement>(l.clone()).ok()).map(|s| s.solver);
+            let trades: Vec<CowTrade> = logs.iter().filter_map(|l| parse_log::<CowTrade>(l.clone()).ok()).collect();
+            if let Some(solver) = solver {
+                for trade in &trades {
+                    settlements.push(Settlement {
+                        protocol: IntentProtocol::Cow,
+                        uid: format!("0x{}", ethers::utils::hex::encode(&trade.order_uid)),
+                        tx_hash,
+                        block,
+                        timestamp,
+                        solver,
+                        owner: trade.owner,
+                        counterparties: trades.iter().map(|t| t.owner).filter(|o| *o != trade.owner).collect(),
+                        executed_buy_amount: Some(trade.buy_amount),
+                    });
+                }
+            }
+
+            for fill in logs.iter().filter_map(|l| parse_log::<UniswapXFill>(l.clone()).ok()) {
+                settlements.push(Settlement {
+                    protocol: IntentProtocol::UniswapX,
+                    uid: format!("{:?}", H256::from(fill.order_hash)),
+                    tx_hash,
+                    block,
+                    timestamp,
+                    solver: fill.filler,
+                    owner: fill.swapper,
+                    counterparties: Vec::new(),
+                    executed_buy_amount: None,
+                });
+            }
+        }
+        Ok(settlements)
+    }
+
+    /// Сколько токена `token` получил владелец ордера; `None` — измерить нельзя
+    async fn received(&self, settlement: &Settlement, token: Address) -> Result<Option<U256>, IntentError> {
+        match token == NATIVE_TOKEN {
+            true => self.received_native(settlement.owner, settlement.block).await,
+            false => self.received_erc20(settlement.tx_hash, token, settlement.owner).await.map(Some),
+        }
+    }
+
+    /// Перевод ETH не оставляет событий: прирост баланса владельца за блок. Прочие поступления
+    /// в том же блоке только завышают его, а свои транзакции владельца делают прирост
+    /// неизмеримым
+    async fn received_native(&self, owner: Address, block: u64) -> Result<Option<U256>, IntentError> {
+        let (before, after) = (block.saturating_sub(1).into(), block.into());
+        let nonce_before = self.provider.get_transaction_count(owner, Some(before)).await.map_err(provider_err)?;
+        let nonce_after = self.provider.get_transaction_count(owner, Some(after)).await.map_err(provider_err)?;
+        if nonce_before != nonce_after {
+            return Ok(None);
+        }
+        let balance_before = self.provider.get_balance(owner, Some(before)).await.map_err(provider_err)?;
+        let balance_after = self.provider.get_balance(owner, Some(after)).await.map_err(provider_err)?;
+        Ok(Some(balance_after.saturating_sub(balance_before)))
+    }
+
+    /// Сколько токена `token` получил `owner` в транзакции
+    async fn received_erc20(&self, tx_hash: H256, token: Address, owner: Address) -> Result<U256, IntentError> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| IntentError::InvalidResponse(format!("no receipt for {:?}", tx_hash)))?;
+        Ok(receipt
+            .logs
+            .into_iter()
+            .filter(|l| l.address == token)
+            .filter_map(|l| parse_log::<Erc20Transfer>(l).ok())
+            .filter(|t| t.to == owner)
+            .fold(U256::zero(), |acc, t| acc.saturating_add(t.value)))
+    }
+
+    fn is_self_dealing(&self, settlement: &Settlement) -> bool {
+        let affiliates = self.affiliates.get(&settlement.solver);
+        let owned = |a: &Address| *a == settlement.solver || affiliates.is_some_and(|set| set.contains(a));
+        owned(&settlement.owner) || settlement.counterparties.iter().any(owned)
+    }
+
+    /// Проверяет расчёты блока и возвращает алерты. Расчёт, который не удалось проверить,
+    /// учитывается в `errors` и не мешает остальным
+    pub async fn scan_block(&self, block: u64) -> Result<Vec<BusAlert>, IntentError> {
+        let mut alerts = Vec::new();
+        for settlement in self.settlements(block).await? {
+            if self.is_self_dealing(&settlement) {
+                alerts.push(alert(&settlement, "solver_self_dealing", AlertLevel::High, json!({})));
+            }
+            let shortfall = self.shortfall(&settlement).await;
+            if let Some(alert) = self.errors.check(shortfall.map_err(|e| format!("order {}: {}", settlement.uid, e))).flatten() {
+                alerts.push(alert);
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// Исполнение хуже котировки, приведённой к моменту блока
+    async fn shortfall(&self, settlement: &Settlement) -> Result<Option<BusAlert>, IntentError> {
+        let Some(quote) = self.orders.quoted(settlement.protocol, &settlement.uid).await? else {
+            return Ok(None);
+        };
+        let executed = match settlement.executed_buy_amount {
+            Some(executed) => executed,
+            None => match self.received(settlement, quote.buy_token).await? {
+                Some(received) => received,
+                None => return Ok(None),
+            },
+        };
+        let expected = quote.expected_at(settlement.timestamp);
+        if expected.is_zero() || executed >= expected {
+            return Ok(None);
+        }
+        let shortfall_bps = ((expected - executed) * 10_000u64 / expected).low_u64();
+        let max_shortfall = self.config.max_shortfall_bps.get() as u64;
+        if shortfall_bps <= max_shortfall {
+            return Ok(None);
+        }
+        let level = if shortfall_bps > max_shortfall * 5 { AlertLevel::High } else { AlertLevel::Medium };
+        let details = json!({
+            "quoted_buy_amount": expected.to_string(),
+            "executed_buy_amount": executed.to_string(),
+            "shortfall_bps": shortfall_bps,
+        });
+        Ok(Some(alert(settlement, "settlement_shortfall", level, details)))
+    }
+
+    /// Проверка на каждый новый блок из `heads` с публикацией в шину
+    pub async fn run(&self, mut heads: watch::Receiver<u64>, bus: AlertBus) {
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            match self.scan_block(block).await {
+                Ok(alerts) => {
+                    for alert in alerts {
+                        bus.publish(alert);
+                    }
+                }
+                Err(e) => self.errors.record(format!("intent settlement scan for block {} failed: {}", block, e)),
+            }
+        }
+    }
+}
+
+fn alert(settlement: &Settlement, kind: &str, level: AlertLevel, details: Value) -> BusAlert {
+    let title = match kind {
+        "solver_self_dealing" => format!("Solver {:?} settled against its own account", settlement.solver),
+        _ => format!("Order {} settled below quote", settlement.uid),
+    };
+    let mut payload = json!({ "settlement": settlement });
+    if let (Value::Object(payload), Value::Object(details)) = (&mut payload, details) {
+        payload.extend(details);
+    }
+    BusAlert::new("intents", kind, level, format!("{:?}", settlement.solver), title).with_payload(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::providers::{MockProvider, Provider};
+    use ethers::types::{Block, U64};
+
+    /// Ордер `quotes` по uid; uid без котировки — ошибка API
+    struct Quotes(HashMap<String, QuotedOrder>);
+
+    #[async_trait]
+    impl OrderBook for Quotes {
+        async fn quoted(&self, _: IntentProtocol, uid: &str) -> Result<Option<QuotedOrder>, IntentError> {
+            match self.0.get(uid) {
+                Some(order) => Ok(Some(order.clone())),
+                None => Err(IntentError::InvalidResponse("503".into())),
+            }
+        }
+    }
+
+    fn respond<T: Serialize + Send + Sync>(mock: &MockProvider, value: T) {
+        mock.push::<T, T>(value).unwrap();
+    }
+
+    fn fill(order_hash: H256, swapper: Address, tx_index: u64) -> Log {
+        Log {
+            address: IntentConfig::default().uniswapx_reactors[0],
+            topics: vec![UniswapXFill::signature(), order_hash, H256::from(Address::repeat_byte(0x5e)), H256::from(swapper)],
+            data: encode(&[Token::Uint(U256::one())]).into(),
+            transaction_index: Some(U64::from(tx_index)),
+            transaction_hash: Some(H256::repeat_byte(tx_index as u8)),
+            ..Default::default()
+        }
+    }
+
+    fn dutch(owner: Address, start: u64, end: u64) -> QuotedOrder {
+        QuotedOrder {
+            owner,
+            buy_token: NATIVE_TOKEN,
+            quoted_buy_amount: U256::from(start),
+            decay: Some(DutchDecay { end_amount: U256::from(end), start_time: 1_000, end_time: 1_100 }),
+        }
+    }
+
+    #[test]
+    fn test_dutch_output_decays_linearly() {
+        let order = dutch(Address::zero(), 1_000, 900);
+        assert_eq!(order.expected_at(900), U256::from(1_000));
+        assert_eq!(order.expected_at(1_050), U256::from(950));
+        assert_eq!(order.expected_at(2_000), U256::from(900));
+    }
+
+    #[tokio::test]
+    async fn test_scan_measures_native_output_against_decayed_amount_and_survives_api_errors() {
+        let (provider, mock) = Provider::mocked();
+        let (failing, native) = (H256::repeat_byte(0xa1), H256::repeat_byte(0xa2));
+        let swapper = Address::repeat_byte(0x77);
+        let quotes = HashMap::from([(format!("{:?}", native), dutch(swapper, 1_000, 900))]);
+        let monitor = IntentMonitor::new(Arc::new(provider), Arc::new(Quotes(quotes)), IntentConfig::default());
+
+        // Ответы снимаются с конца: балансы и nonce владельца до и после блока, блок, логи
+        respond(&mock, U256::from(10_900));
+        respond(&mock, U256::from(10_000));
+        respond(&mock, U256::from(3));
+        respond(&mock, U256::from(3));
+        respond(&mock, Block::<H256> { timestamp: U256::from(1_050), ..Default::default() });
+        respond(&mock, vec![fill(failing, swapper, 1), fill(native, swapper, 2)]);
+
+        let alerts = monitor.scan_block(100).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, "settlement_shortfall");
+        // 900 против 950 после снижения, а не против стартовых 1000
+        assert_eq!(alerts[0].payload["shortfall_bps"], 526);
+        assert_eq!(monitor.errors().errors, 1);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::backfill::{list_batch, BackfillJob, BatchOutcome};
+use crate::compat::Address;
+use crate::store::{SharedStore, Store, StoreError, StoreExt};
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Пространство имён хранилища: обратные ENS-имена, найденные `EnsBackfill`
+pub const ENS_NS: &str = "ens";
+
+/// Категория известного адреса
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LabelCategory {
+    Exchange,
+    Router,
+    Bridge,
+    Builder,
+    Other,
+}
+
+/// Человекочитаемое представление адреса в алерте
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AddressLabel {
+    pub address: String,
+    pub label: Option<String>,
+    pub category: Option<LabelCategory>,
+    pub ens: Option<String>,
+    /// То, что показывают людям: метка, затем ENS, затем hex
+    pub display: String,
+}
+
+/// Известные адреса mainnet, доступные без конфигурации
+const WELL_KNOWN: &[(&str, &str, LabelCategory)] = &[
+    ("0x3fc91a3afd70395cd496c647d5a6cc9d4b2b7fad", "uniswap-universal-router", LabelCategory::Router),
+    ("0x7a250d5630b4cf539739df2c5dacb4c659f2488d", "uniswap-v2-router", LabelCategory::Router),
+    ("0xe592427a0aece92de3edee1f18e0157c05861564", "uniswap-v3-router", LabelCategory::Router),
+    ("0x68b3465833fb72a70ecdf485e0e4c7bd8665fc45", "uniswap-v3-router-02", LabelCategory::Router),
+    ("0x1111111254eeb25477b68fb85ed929f73a960582", "1inch-v5-router", LabelCategory::Router),
+    ("0xdef1c0ded9bec7f1a1670819833240f027b25eff", "0x-exchange-proxy", LabelCategory::Router),
+    ("0x28c6c06298d514db089934071355e5743bf21d60", "binance-14", LabelCategory::Exchange),
+    ("0x71660c4005ba85c37ccec55d0c4493e66fe775d3", "coinbase-1", LabelCategory::Exchange),
+    ("0x2910543af39aba0cd09dbb2d50200b3e800a63d2", "kraken", LabelCategory::Exchange),
+    ("0x4dbd4fc535ac27206064b68ffcf827b0a60bab3f", "arbitrum-inbox", LabelCategory::Bridge),
+    ("0x99c9fc46f92e8a1c0dec1b1747d010903e884be1", "optimism-l1-standard-bridge", LabelCategory::Bridge),
+    ("0x3ee18b2214aff97000d974cf647e7c347e8fa585", "wormhole-token-bridge", LabelCategory::Bridge),
+    ("0x5c7bcd6e7de5423a257d81b442095a1a6ced35c5", "across-spoke-pool", LabelCategory::Bridge),
+];
+
+/// Обратное ENS-разрешение
+#[async_trait]
+pub trait EnsLookup: Send + Sync {
+    async fn reverse(&self, address: Address) -> Option<String>;
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> EnsLookup for M {
+    async fn reverse(&self, address: Address) -> Option<String> {
+        self.lookup_address(address).await.ok()
+    }
+}
+
+struct EnsEntry {
+    name: Option<String>,
+    fetched_at: Instant,
+}
+
+/// Кэширующий резолвер меток и ENS-имён.
+/// `resolve` работает только с кэшем и не блокирует построение алерта;
+/// промахи по ENS складываются в очередь и разрешаются в `refresh_ens`.
+pub struct LabelResolver {
+    labels: RwLock<HashMap<String, (String, LabelCategory)>>,
+    ens_cache: RwLock<HashMap<String, EnsEntry>>,
+    ens_pending: Mutex<HashSet<String>>,
+    ens_ttl: Duration,
+}
+
+impl LabelResolver {
+    pub fn new(ens_ttl: Duration) -> Self {
+        let labels = WELL_KNOWN
+            .iter()
+            .map(|(addr, label, cat)| (addr.to_string(), (label.to_string(), *cat)))
+            .collect();
+
+        Self {
+            labels: RwLock::new(labels),
+            ens_cache: RwLock::new(HashMap::new()),
+            ens_pending: Mutex::new(HashSet::new()),
+            ens_ttl,
+        }
+    }
+
+    /// Добавляет или переопределяет метку (например, из конфигурации оператора)
+    pub fn add_label(&self, address: &str, label: &str, category: LabelCategory) {
+        self.labels
+            .write()
+            .unwrap()
+            .insert(address.to_lowercase(), (label.to_string(), category));
+    }
+
+    /// Метки оператора: всё, что добавлено или переопределено поверх `WELL_KNOWN`
+    pub fn custom_labels(&self) -> Vec<(String, String, LabelCategory)> {
+        let mut custom: Vec<_> = self
+            .labels
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(addr, (label, cat))| {
+                !WELL_KNOWN.iter().any(|(a, l, c)| a == addr && l == label && c == cat)
+            })
+            .map(|(addr, (label, cat))| (addr.clone(), label.clone(), *cat))
+            .collect();
+        custom.sort_by(|a, b| a.0.cmp(&b.0));
+        custom
+    }
+
+    pub fn resolve(&self, address: &str) -> AddressLabel {
+        let key = address.to_lowercase();
+        let known = self.labels.read().unwrap().get(&key).cloned();
+        let ens = self.cached_ens(&key);
+
+        let display = known
+            .as_ref()
+            .map(|(label, _)| label.clone())
+            .or_else(|| ens.clone())
+            .unwrap_or_else(|| address.to_string());
+
+        AddressLabel {
+            address: address.to_string(),
+            label: known.as_ref().map(|(label, _)| label.clone()),
+            category: known.map(|(_, cat)| cat),
+            ens,
+            display,
+        }
+    }
+
+    fn cached_ens(&self, key: &str) -> Option<String> {
+        if let Some(entry) = self.ens_cache.read().unwrap().get(key) {
+            if entry.fetched_at.elapsed() < self.ens_ttl {
+                return entry.name.clone();
+            }
+        }
+        self.ens_pending.lock().unwrap().insert(key.to_string());
+        None
+    }
+
+    /// Кладёт имя в кэш, как если бы его разрешил `refresh_ens`
+    pub fn set_ens(&self, address: &str, name: Option<String>) {
+        let key = address.to_lowercase();
+        self.ens_pending.lock().unwrap().remove(&key);
+        self.ens_cache.write().unwrap().insert(key, EnsEntry { name, fetched_at: Instant::now() });
+    }
+
+    /// Имена, сохранённые `EnsBackfill` до перезапуска; возвращает их число
+    pub fn load_ens(&self, store: &dyn Store) -> Result<usize, StoreError> {
+        let names: Vec<(String, String)> = store.list_json(ENS_NS)?;
+        let count = names.len();
+        for (address, name) in names {
+            self.set_ens(&address, Some(name));
+        }
+        Ok(count)
+    }
+
+    /// Разрешает накопившиеся ENS-промахи; возвращает число обработанных адресов
+    pub async fn refresh_ens(&self, lookup: &dyn EnsLookup) -> usize {
+        let queue: Vec<String> = self.ens_pending.lock().unwrap().drain().collect();
+
+        for key in &queue {
+            let name = match key.parse::<Address>() {
+                Ok(address) => lookup.reverse(address).await,
+                Err(_) => None,
+            };
+            self.ens_cache.write().unwrap().insert(
+                key.clone(),
+                EnsEntry { name, fetched_at: Instant::now() },
+            );
+        }
+
+        queue.len()
+    }
+}
+
+/// Разделяемый резолвер для детектора и фоновой задачи обновления
+pub type SharedLabelResolver = Arc<LabelResolver>;
+
+/// Заполнение `labels`: обратные ENS-имена адресов списка. Найденные имена сохраняются,
+/// чтобы после перезапуска они были в кэше сразу, без новых запросов
+pub struct EnsBackfill {
+    labels: SharedLabelResolver,
+    lookup: Arc<dyn EnsLookup>,
+    store: SharedStore,
+    addresses: Vec<Address>,
+    batch_size: usize,
+}
+
+impl EnsBackfill {
+    pub fn new(labels: SharedLabelResolver, lookup: Arc<dyn EnsLookup>, store: SharedStore, addresses: Vec<Address>, batch_size: usize) -> Self {
+        Self { labels, lookup, store, addresses, batch_size }
+    }
+}
+
+#[async_trait]
+impl BackfillJob for EnsBackfill {
+    fn name(&self) -> &str {
+        "labels"
+    }
+
+    async fn total(&self) -> Option<u64> {
+        Some(self.addresses.len() as u64)
+    }
+
+    async fn run_batch(&self, cursor: Option<Value>) -> Result<BatchOutcome, String> {
+        let (batch, next) = list_batch(&self.addresses, cursor.as_ref(), self.batch_size)?;
+        for address in batch {
+            let key = format!("{:?}", address);
+            let name = self.lookup.reverse(*address).await;
+            if let Some(name) = &name {
+                self.store.put_json(ENS_NS, &key, name).map_err(|e| e.to_string())?;
+            }
+            self.labels.set_ens(&key, name);
+        }
+        Ok(BatchOutcome { processed: batch.len() as u64, next })
+    }
+}
@@ -0,0 +1,487 @@
+#[cfg(any(feature = "leader-postgres", feature = "leader-etcd"))]
+use crate::config::LeaderBackend;
+use crate::config::LeaderSection;
+use crate::shutdown::ShutdownSignal;
+use crate::task_errors::TaskErrors;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+#[derive(Debug, Error)]
+pub enum LeaderError {
+    #[error("Not the leader")]
+    NotLeader,
+
+    #[error("Lease backend error: {0}")]
+    BackendError(String),
+}
+
+/// Распределённая блокировка лидера; ровно один экземпляр держит её в каждый момент
+#[async_trait]
+pub trait LeaseBackend: Send + Sync {
+    fn name(&self) -> &str;
+    /// Пытается захватить блокировку; `false`, если её держит другой экземпляр
+    async fn try_acquire(&self) -> Result<bool, LeaderError>;
+    /// Продлевает уже захваченную блокировку; `false`, если она потеряна
+    async fn renew(&self) -> Result<bool, LeaderError>;
+    async fn release(&self) -> Result<(), LeaderError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Leadership {
+    /// Одиночный экземпляр без выборов
+    Always,
+    /// Лидер, пока не истёк срок последнего подтверждения блокировки
+    Until(Instant),
+    Standby,
+}
+
+impl Leadership {
+    fn is_leader(&self) -> bool {
+        match self {
+            Leadership::Always => true,
+            Leadership::Until(deadline) => Instant::now() < *deadline,
+            Leadership::Standby => false,
+        }
+    }
+}
+
+/// Признак лидерства для тех, кто отправляет алерты и защитные транзакции
+#[derive(Clone)]
+pub struct LeaderGate {
+    rx: watch::Receiver<Leadership>,
+}
+
+impl LeaderGate {
+    /// Гейт для одиночного экземпляра без выборов: всегда лидер
+    pub fn always() -> Self {
+        let (tx, rx) = watch::channel(Leadership::Always);
+        // Отправитель не нужен: значение больше не меняется
+        drop(tx);
+        Self { rx }
+    }
+
+    /// Лидерство истекает само, если блокировку не удалось подтвердить за TTL, даже когда
+    /// цикл выборов завис и не успел снять роль
+    pub fn is_leader(&self) -> bool {
+        self.rx.borrow().is_leader()
+    }
+
+    /// Вызывается перед подписью и отправкой транзакции
+    pub fn ensure_leader(&self) -> Result<(), LeaderError> {
+        if self.is_leader() {
+            Ok(())
+        } else {
+            Err(LeaderError::NotLeader)
+        }
+    }
+
+    /// Завершается, когда цикл выборов сменил роль; возвращает новую
+    pub async fn changed(&mut self) -> bool {
+        let _ = self.rx.changed().await;
+        self.is_leader()
+    }
+}
+
+/// Цикл выборов: лидер продлевает блокировку, резерв периодически пытается её захватить
+pub struct LeaderElector {
+    backend: Arc<dyn LeaseBackend>,
+    interval: Duration,
+    ttl: Duration,
+    tx: watch::Sender<Leadership>,
+    errors: Arc<TaskErrors>,
+}
+
+impl LeaderElector {
+    /// `interval` должен быть заметно меньше `ttl`, иначе лидер потеряет блокировку между продлениями
+    pub fn new(backend: Arc<dyn LeaseBackend>, interval: Duration, ttl: Duration) -> Self {
+        let (tx, _) = watch::channel(Leadership::Standby);
+        Self { backend, interval, ttl, tx, errors: Arc::new(TaskErrors::default()) }
+    }
+
+    /// Выборы по секции `[leader]`
+    pub fn from_config(section: &LeaderSection, instance_id: &str) -> Result<Self, LeaderError> {
+        Ok(Self::new(
+            backend_from_config(section, instance_id)?,
+            Duration::from_secs(section.renew_interval_seconds),
+            Duration::from_secs(section.ttl_seconds),
+        ))
+    }
+
+    pub fn with_errors(mut self, errors: Arc<TaskErrors>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn gate(&self) -> LeaderGate {
+        LeaderGate { rx: self.tx.subscribe() }
+    }
+
+    /// Один шаг выборов. Срок лидерства отсчитывается от начала шага: блокировка бэкенда
+    /// живёт не меньше, так что резерв не захватит её, пока этот экземпляр ещё считает себя
+    /// лидером. Ошибка или зависший вызов означают потерю лидерства; блокировка при этом
+    /// отпускается, чтобы резерв не ждал её истечения
+    pub async fn step(&self) -> bool {
+        let started = Instant::now();
+        let holding = *self.tx.borrow() != Leadership::Standby;
+        let call = async {
+            if holding {
+                self.backend.renew().await
+            } else {
+                self.backend.try_acquire().await
+            }
+        };
+        let (leader, failed) = match tokio::time::timeout(self.interval, call).await {
+            Ok(Ok(leader)) => (leader, false),
+            Ok(Err(e)) => {
+                self.errors.record(format!("leader election via {}: {}", self.backend.name(), e));
+                (false, true)
+            }
+            Err(_) => {
+                self.errors.record(format!("leader election via {} timed out", self.backend.name()));
+                (false, true)
+            }
+        };
+        // Ответ мог потеряться уже после захвата, поэтому после сбоя блокировка отпускается и у резерва
+        if !leader && (holding || failed) {
+            self.release().await;
+        }
+        self.tx.send_if_modified(|current| {
+            let changed = (*current != Leadership::Standby) != leader;
+            *current = if leader { Leadership::Until(started + self.ttl) } else { Leadership::Standby };
+            changed
+        });
+        leader
+    }
+
+    async fn release(&self) {
+        match tokio::time::timeout(self.interval, self.backend.release()).await {
+            Ok(result) => {
+                self.errors.check(result);
+            }
+            Err(_) => self.errors.record(format!("releasing the leader lock via {} timed out", self.backend.name())),
+        }
+    }
+
+    /// Работает до сигнала остановки, затем снимает лидерство и отпускает блокировку
+    pub async fn run(&self, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    self.step().await;
+                }
+                _ = shutdown.wait() => break,
+            }
+        }
+
+        if self.tx.send_replace(Leadership::Standby) != Leadership::Standby {
+            self.release().await;
+        }
+    }
+}
+
+/// Бэкенд по секции `[leader]`; нужный feature должен быть включён при сборке
+pub fn backend_from_config(section: &LeaderSection, instance_id: &str) -> Result<Arc<dyn LeaseBackend>, LeaderError> {
+    let _ = instance_id;
+    match section.backend {
+        #[cfg(feature = "leader-postgres")]
+        LeaderBackend::Postgres => Ok(Arc::new(PostgresLease::new(&section.endpoints[0], &section.cluster))),
+        #[cfg(feature = "leader-etcd")]
+        LeaderBackend::Etcd => Ok(Arc::new(EtcdLease::new(
+            section.endpoints.clone(),
+            &section.cluster,
+            instance_id,
+            section.ttl_seconds as i64,
+        ))),
+        #[allow(unreachable_patterns)]
+        other => Err(LeaderError::BackendError(format!(
+            "{:?} support is not compiled in (enable the leader-{} feature)",
+            other,
+            format!("{:?}", other).to_lowercase()
+        ))),
+    }
+}
+
+/// Ключ advisory-блокировки из имени кластера
+#[cfg(feature = "leader-postgres")]
+fn advisory_key(name: &str) -> i64 {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(name.as_bytes());
+    i64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Держит ли текущая сессия advisory-блокировку с ключом `$1`; ключ bigint хранится в
+/// `pg_locks` старшей половиной в `classid` и младшей в `objid`
+#[cfg(feature = "leader-postgres")]
+const HELD_ADVISORY_LOCK: &str = "SELECT EXISTS (SELECT 1 FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid() \
+     AND granted AND objsubid = 1 AND ((classid::bigint << 32) | objid::bigint) = $1)";
+
+/// Блокировка на `pg_try_advisory_lock`: держится, пока живо соединение
+#[cfg(feature = "leader-postgres")]
+pub struct PostgresLease {
+    url: String,
+    key: i64,
+    client: tokio::sync::Mutex<Option<tokio_postgres::Client>>,
+}
+
+#[cfg(feature = "leader-postgres")]
+impl PostgresLease {
+    pub fn new(url: &str, name: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            key: advisory_key(name),
+            client: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    async fn connect(&self) -> Result<tokio_postgres::Client, LeaderError> {
+        let (client, connection) = tokio_postgres::connect(&self.url, tokio_postgres::NoTls)
+            .await
+            .map_err(|e| LeaderError::BackendError(e.to_string()))?;
+        // Обрыв соединения виден по `is_closed` при следующем продлении
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        Ok(client)
+    }
+}
+
+#[cfg(feature = "leader-postgres")]
+#[async_trait]
+impl LeaseBackend for PostgresLease {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn try_acquire(&self) -> Result<bool, LeaderError> {
+        let mut guard = self.client.lock().await;
+        if guard.as_ref().is_none_or(|c| c.is_closed()) {
+            *guard = Some(self.connect().await?);
+        }
+        let client = guard.as_ref().unwrap();
+        let row = client
+            .query_one("SELECT pg_try_advisory_lock($1)", &[&self.key])
+            .await
+            .map_err(|e| LeaderError::BackendError(e.to_string()))?;
+        Ok(row.get::<_, bool>(0))
+    }
+
+    async fn renew(&self) -> Result<bool, LeaderError> {
+        let mut guard = self.client.lock().await;
+        let Some(client) = guard.as_ref() else {
+            return Ok(false);
+        };
+        // Блокировка сессионная; проверяется, что сессия жива и всё ещё держит её
+        let held = match client.query_one(HELD_ADVISORY_LOCK, &[&self.key]).await {
+            Ok(row) => row.get::<_, bool>(0),
+            Err(_) => false,
+        };
+        if !held {
+            *guard = None;
+        }
+        Ok(held)
+    }
+
+    /// Закрытие сессии снимает все её advisory-блокировки, в том числе захваченные повторно
+    async fn release(&self) -> Result<(), LeaderError> {
+        if let Some(client) = self.client.lock().await.take() {
+            client
+                .execute("SELECT pg_advisory_unlock_all()", &[])
+                .await
+                .map_err(|e| LeaderError::BackendError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Блокировка в etcd: ключ, привязанный к lease с TTL
+#[cfg(feature = "leader-etcd")]
+pub struct EtcdLease {
+    endpoints: Vec<String>,
+    key: String,
+    instance_id: String,
+    ttl_seconds: i64,
+    state: tokio::sync::Mutex<Option<(etcd_client::Client, i64)>>,
+}
+
+#[cfg(feature = "leader-etcd")]
+impl EtcdLease {
+    pub fn new(endpoints: Vec<String>, name: &str, instance_id: &str, ttl_seconds: i64) -> Self {
+        Self {
+            endpoints,
+            key: format!("/definetly/leader/{}", name),
+            instance_id: instance_id.to_string(),
+            ttl_seconds,
+            state: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(feature = "leader-etcd")]
+fn etcd_error(e: etcd_client::Error) -> LeaderError {
+    LeaderError::BackendError(e.to_string())
+}
+
+#[cfg(feature = "leader-etcd")]
+#[async_trait]
+impl LeaseBackend for EtcdLease {
+    fn name(&self) -> &str {
+        "etcd"
+    }
+
+    async fn try_acquire(&self) -> Result<bool, LeaderError> {
+        use etcd_client::{Compare, CompareOp, PutOptions, Txn, TxnOp};
+
+        let mut client = etcd_client::Client::connect(&self.endpoints, None).await.map_err(etcd_error)?;
+        let lease = client.lease_grant(self.ttl_seconds, None).await.map_err(etcd_error)?.id();
+
+        // Ключ создаётся, только если его ещё нет
+        let txn = Txn::new()
+            .when([Compare::create_revision(self.key.clone(), CompareOp::Equal, 0)])
+            .and_then([TxnOp::put(
+                self.key.clone(),
+                self.instance_id.clone(),
+                Some(PutOptions::new().with_lease(lease)),
+            )]);
+        let acquired = match client.txn(txn).await {
+            Ok(response) => response.succeeded(),
+            Err(e) => {
+                let _ = client.lease_revoke(lease).await;
+                return Err(etcd_error(e));
+            }
+        };
+
+        if acquired {
+            *self.state.lock().await = Some((client, lease));
+        } else {
+            let _ = client.lease_revoke(lease).await;
+        }
+        Ok(acquired)
+    }
+
+    async fn renew(&self) -> Result<bool, LeaderError> {
+        let mut state = self.state.lock().await;
+        let Some((client, lease)) = state.as_mut() else {
+            return Ok(false);
+        };
+
+        let (mut keeper, mut stream) = client.lease_keep_alive(*lease).await.map_err(etcd_error)?;
+        keeper.keep_alive().await.map_err(etcd_error)?;
+        let alive = matches!(stream.message().await.map_err(etcd_error)?, Some(resp) if resp.ttl() > 0);
+        // Ключ могли удалить или перезаписать вручную, тогда живой lease ничего не держит
+        let owned = alive
+            && client.get(self.key.clone(), None).await.map_err(etcd_error)?.kvs().first().is_some_and(|kv| kv.lease() == *lease);
+        if !owned {
+            let _ = client.lease_revoke(*lease).await;
+            *state = None;
+        }
+        Ok(owned)
+    }
+
+    async fn release(&self) -> Result<(), LeaderError> {
+        if let Some((mut client, lease)) = self.state.lock().await.take() {
+            client.lease_revoke(lease).await.map_err(etcd_error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Общая блокировка в памяти для двух экземпляров
+    struct SharedLock {
+        name: String,
+        held: Arc<AtomicBool>,
+        mine: AtomicBool,
+    }
+
+    #[async_trait]
+    impl LeaseBackend for SharedLock {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn try_acquire(&self) -> Result<bool, LeaderError> {
+            let acquired = self.held.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_ok();
+            self.mine.store(acquired, Ordering::Release);
+            Ok(acquired)
+        }
+
+        async fn renew(&self) -> Result<bool, LeaderError> {
+            Ok(self.mine.load(Ordering::Acquire))
+        }
+
+        async fn release(&self) -> Result<(), LeaderError> {
+            if self.mine.swap(false, Ordering::AcqRel) {
+                self.held.store(false, Ordering::Release);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_leader_and_failover() {
+        let held = Arc::new(AtomicBool::new(false));
+        let lock = |name: &str| {
+            Arc::new(SharedLock { name: name.into(), held: held.clone(), mine: AtomicBool::new(false) })
+        };
+        let (a_lock, b_lock) = (lock("a"), lock("b"));
+        let a = LeaderElector::new(a_lock.clone(), Duration::from_millis(10), Duration::from_secs(1));
+        let b = LeaderElector::new(b_lock, Duration::from_millis(10), Duration::from_secs(1));
+
+        assert!(a.step().await);
+        assert!(!b.step().await);
+        assert!(a.gate().ensure_leader().is_ok());
+        assert!(matches!(b.gate().ensure_leader(), Err(LeaderError::NotLeader)));
+
+        a_lock.release().await.unwrap();
+        assert!(!a.step().await);
+        assert!(b.step().await);
+    }
+
+    /// Бэкенд, который не отвечает после захвата
+    struct Hung(AtomicBool);
+
+    #[async_trait]
+    impl LeaseBackend for Hung {
+        fn name(&self) -> &str {
+            "hung"
+        }
+
+        async fn try_acquire(&self) -> Result<bool, LeaderError> {
+            Ok(true)
+        }
+
+        async fn renew(&self) -> Result<bool, LeaderError> {
+            std::future::pending().await
+        }
+
+        async fn release(&self) -> Result<(), LeaderError> {
+            self.0.store(true, Ordering::Release);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leadership_lapses_without_renewal() {
+        let backend = Arc::new(Hung(AtomicBool::new(false)));
+        let elector = LeaderElector::new(backend.clone(), Duration::from_millis(50), Duration::from_millis(20));
+        let gate = elector.gate();
+
+        assert!(elector.step().await);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!gate.is_leader());
+
+        assert!(!elector.step().await);
+        assert!(backend.0.load(Ordering::Acquire));
+        assert_eq!(elector.errors.count(), 1);
+    }
+}
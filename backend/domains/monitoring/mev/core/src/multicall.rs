@@ -0,0 +1,234 @@
+use crate::compat::{Address, Bytes};
+use ethers::abi::{Detokenize, Function};
+use ethers::contract::{abigen, decode_function_data, ContractCall};
+use ethers::providers::{Middleware, MiddlewareError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, TransactionRequest};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+abigen!(
+    Multicall3,
+    r#"[
+        struct Call3 { address target; bool allowFailure; bytes callData; }
+        struct CallResult { bool success; bytes returnData; }
+        function aggregate3(Call3[] calls) external payable returns (CallResult[] returnData)
+    ]"#
+);
+
+/// Адрес Multicall3, одинаковый во всех сетях, где он развёрнут
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Вызовов в одном `aggregate3`; больше — риск упереться в лимит газа `eth_call` провайдера
+pub const DEFAULT_CHUNK_SIZE: usize = 200;
+
+#[derive(Debug, Error)]
+pub enum MulticallError {
+    #[error("Multicall error: {0}")]
+    ContractError(String),
+
+    #[error("Call {0} reverted")]
+    Reverted(usize),
+
+    #[error("Failed to decode result of call {0}: {1}")]
+    DecodeError(usize, String),
+
+    #[error("Call has no target address")]
+    MissingTarget,
+}
+
+/// Типизированная ссылка на вызов в пакете
+pub struct CallHandle<D> {
+    index: usize,
+    function: Function,
+    _output: PhantomData<D>,
+}
+
+/// Набор чтений для одного прохода через Multicall3
+#[derive(Default)]
+pub struct Batch {
+    calls: Vec<Call3>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавляет вызов; откат одного вызова не роняет остальные
+    pub fn add<M, D: Detokenize>(&mut self, call: &ContractCall<M, D>) -> Result<CallHandle<D>, MulticallError> {
+        let target = call.tx.to_addr().copied().ok_or(MulticallError::MissingTarget)?;
+        self.calls.push(Call3 {
+            target,
+            allow_failure: true,
+            call_data: call.tx.data().cloned().unwrap_or_default(),
+        });
+        Ok(CallHandle { index: self.calls.len() - 1, function: call.function.clone(), _output: PhantomData })
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+}
+
+/// Сырые ответы пакета; `None` — вызов откатился
+pub struct BatchResults(Vec<Option<Bytes>>);
+
+impl BatchResults {
+    pub fn get<D: Detokenize>(&self, handle: &CallHandle<D>) -> Result<D, MulticallError> {
+        let data = self.0.get(handle.index).cloned().flatten().ok_or(MulticallError::Reverted(handle.index))?;
+        decode_function_data(&handle.function, data, false).map_err(|e| MulticallError::DecodeError(handle.index, e.to_string()))
+    }
+
+    /// Результат или `None`, если вызов откатился или не декодируется
+    pub fn ok<D: Detokenize>(&self, handle: &CallHandle<D>) -> Option<D> {
+        self.get(handle).ok()
+    }
+}
+
+/// Выполняет пакеты чтений через Multicall3 кусками по `chunk_size` вызовов.
+/// В сети без Multicall3 вызовы пакета уходят по одному `eth_call`
+pub struct BatchReader<M> {
+    multicall: Multicall3<M>,
+    chunk_size: usize,
+    /// Есть ли код по адресу Multicall3; проверяется один раз по последнему блоку
+    deployed: OnceCell<bool>,
+}
+
+impl<M: Middleware + 'static> BatchReader<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self::with_address(provider, MULTICALL3_ADDRESS.parse().unwrap())
+    }
+
+    /// Для сетей, где Multicall3 развёрнут по другому адресу
+    pub fn with_address(provider: Arc<M>, address: Address) -> Self {
+        Self { multicall: Multicall3::new(address, provider), chunk_size: DEFAULT_CHUNK_SIZE, deployed: OnceCell::new() }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub async fn execute(&self, batch: &Batch, block: Option<BlockId>) -> Result<BatchResults, MulticallError> {
+        if batch.is_empty() {
+            return Ok(BatchResults(Vec::new()));
+        }
+        if !self.deployed().await? {
+            return self.execute_each(batch, block).await;
+        }
+        let mut results = Vec::with_capacity(batch.len());
+        for chunk in batch.calls.chunks(self.chunk_size) {
+            let mut call = self.multicall.aggregate_3(chunk.to_vec());
+            if let Some(block) = block {
+                call = call.block(block);
+            }
+            let returned = call.call().await.map_err(|e| MulticallError::ContractError(e.to_string()))?;
+            if returned.len() != chunk.len() {
+                return Err(MulticallError::ContractError(format!("expected {} results, got {}", chunk.len(), returned.len())));
+            }
+            results.extend(returned.into_iter().map(|(success, data)| success.then_some(data)));
+        }
+        Ok(BatchResults(results))
+    }
+
+    async fn deployed(&self) -> Result<bool, MulticallError> {
+        let deployed = self.deployed.get_or_try_init(|| async {
+            let code = self
+                .multicall
+                .client()
+                .get_code(self.multicall.address(), None)
+                .await
+                .map_err(|e| MulticallError::ContractError(e.to_string()))?;
+            Ok::<_, MulticallError>(!code.is_empty())
+        });
+        deployed.await.copied()
+    }
+
+    /// Вызовы пакета по одному; ошибка в ответе узла — откат вызова, сбой транспорта — ошибка пакета
+    async fn execute_each(&self, batch: &Batch, block: Option<BlockId>) -> Result<BatchResults, MulticallError> {
+        let provider = self.multicall.client();
+        let mut results = Vec::with_capacity(batch.len());
+        for call in &batch.calls {
+            let tx: TypedTransaction = TransactionRequest::new().to(call.target).data(call.call_data.clone()).into();
+            match provider.call(&tx, block).await {
+                Ok(data) => results.push(Some(data)),
+                Err(e) if e.as_error_response().is_some() => results.push(None),
+                Err(e) => return Err(MulticallError::ContractError(e.to_string())),
+            }
+        }
+        Ok(BatchResults(results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::providers::{JsonRpcError, MockProvider, MockResponse, Provider};
+    use crate::compat::U256;
+
+    abigen!(Balances, r#"[function balanceOf(address owner) external view returns (uint256)]"#);
+
+    fn batch_of_two(provider: &Arc<Provider<MockProvider>>) -> (Batch, CallHandle<U256>, CallHandle<U256>) {
+        let token = Balances::new(Address::repeat_byte(0x11), provider.clone());
+        let mut batch = Batch::new();
+        let first = batch.add(&token.balance_of(Address::repeat_byte(0x01))).unwrap();
+        let second = batch.add(&token.balance_of(Address::repeat_byte(0x02))).unwrap();
+        (batch, first, second)
+    }
+
+    fn word(value: u64) -> Bytes {
+        encode(&[Token::Uint(U256::from(value))]).into()
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_individual_calls_without_multicall3() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let (batch, first, second) = batch_of_two(&provider);
+        let reader = BatchReader::new(provider.clone());
+
+        mock.push_response(MockResponse::Error(JsonRpcError { code: 3, message: "execution reverted".into(), data: None }));
+        mock.push::<Bytes, _>(word(7)).unwrap();
+        mock.push::<Bytes, _>(Bytes::new()).unwrap();
+
+        let results = reader.execute(&batch, None).await.unwrap();
+        assert_eq!(results.get(&first).unwrap(), U256::from(7));
+        assert!(matches!(results.get(&second), Err(MulticallError::Reverted(1))));
+    }
+
+    #[tokio::test]
+    async fn test_batches_through_multicall3_when_deployed() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let (batch, first, second) = batch_of_two(&provider);
+        let reader = BatchReader::new(provider.clone());
+
+        let returned = |values: [u64; 2]| -> Bytes {
+            let results = values
+                .iter()
+                .map(|v| Token::Tuple(vec![Token::Bool(*v > 0), Token::Bytes(word(*v).to_vec())]))
+                .collect();
+            encode(&[Token::Array(results)]).into()
+        };
+        mock.push::<Bytes, _>(returned([0, 9])).unwrap();
+        mock.push::<Bytes, _>(returned([5, 0])).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(vec![0x60, 0x80])).unwrap();
+
+        let results = reader.execute(&batch, None).await.unwrap();
+        assert_eq!(results.ok(&first), Some(U256::from(5)));
+        assert!(results.ok(&second).is_none());
+
+        // Развёрнутость уже известна: второй пакет — один `aggregate3` без `eth_getCode`
+        let results = reader.execute(&batch, None).await.unwrap();
+        assert!(results.ok(&first).is_none());
+        assert_eq!(results.ok(&second), Some(U256::from(9)));
+    }
+}
@@ -0,0 +1,810 @@
+use crate::abi::AbiRegistry;
+use crate::access_list::AccessListPlanner;
+use crate::admin::{self, AdminState};
+use crate::approvals::ApprovalSimulator;
+use crate::assess::{self, AssessState};
+use crate::audit::{AuditError, AuditLog};
+use crate::backfill::{BackfillError, BackfillJob, BackfillRunner};
+use crate::bundle::{self, BundleError, NodeState};
+use crate::bus::AlertBus;
+use crate::cancel::ChainHead;
+use crate::compat::Address;
+use crate::config::{BackfillSection, DefinetlyConfig, SinkSpec};
+use crate::congestion::CongestionMonitor;
+use crate::create2::DeploymentWatch;
+#[cfg(feature = "dashboard")]
+use crate::dashboard::{self, DashboardState};
+use crate::detector::MevDetector;
+use crate::digest::{AlertHistory, DigestScheduler};
+#[cfg(feature = "email")]
+use crate::email::{EmailError, SmtpSink};
+use crate::encryption::{EncryptedStore, FieldCipher};
+use crate::engine::Engine;
+use crate::enrichment::contract_age::{ContractAgeBackfill, ContractAgeEnricher, TraceCreationSource};
+use crate::enrichment::Enricher;
+use crate::forensics::AlertConfirmer;
+use crate::funding::FundingGraph;
+use crate::gas_oracle::GasOracle;
+use crate::ingest::multi::MultiSource;
+use crate::ingest::TxSink;
+use crate::intents::{HttpOrderBook, IntentMonitor};
+use crate::openapi;
+use crate::permit2::Permit2Monitor;
+#[cfg(feature = "push")]
+use crate::push::{self, WalletSink, WalletSubscriptions};
+use crate::i18n::{LocaleSelector, MessageCatalog};
+use crate::labels::{EnsBackfill, LabelResolver, SharedLabelResolver};
+use crate::leader::LeaderElector;
+use crate::pipeline::{IngestionStop, Pipeline, PipelineConfig, SheddingPolicy};
+use crate::registry::DetectorRegistry;
+use crate::retention::{RetentionJob, RetentionMetrics, RetentionSnapshot};
+use crate::rpc::{QuotaProvider, RpcQuota};
+use crate::rules::RuleEngine;
+use crate::secrets::{SecretError, SecretManager};
+use crate::severity::SeverityModel;
+use crate::shutdown::{HookOutcome, ShutdownCoordinator, ShutdownPhase, ShutdownSignal};
+use crate::simulator::{self, Simulator};
+use crate::sink::{OpsgenieSink, PagerDutySink, Sink, WebhookSink};
+use crate::state::{CachedStateProvider, RpcStateProvider};
+use crate::state_diff::StateDiffer;
+use crate::store::{FileStore, SharedStore, StoreError};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::typed_data::TypedDataAssessor;
+use crate::warm::{HotTargets, StateWarmer};
+use axum::Router;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use ethers::providers::Middleware;
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+/// Сколько каждый участник фазы остановки может задержать выход
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Сколько живёт разрешённое ENS-имя
+const ENS_TTL: Duration = Duration::from_secs(3600);
+
+/// Адресов из мемпула, ждущих поиска создания контракта
+const CONTRACT_AGE_QUEUE: usize = 10_000;
+
+/// Как часто ожидающие деплои CREATE2 сверяются с кодом в сети — примерно раз в блок
+const DEPLOYMENT_CHECK: Duration = Duration::from_secs(12);
+
+/// Как часто узел спрашивает номер последнего блока для подсистем, идущих за головой
+const HEAD_POLL: Duration = Duration::from_secs(4);
+
+/// Сколько самых частых целей мемпула прогревается в кэше симулятора каждый блок
+const WARM_TARGETS: usize = 32;
+
+/// Сколько последних блоков в истории комиссий оракула
+const GAS_HISTORY_BLOCKS: usize = 20;
+
+/// Симуляции транзакции имеют смысл только до следующего блока
+const SIMULATION_RELEVANCE_BLOCKS: u64 = 0;
+
+#[derive(Debug, Error)]
+pub enum NodeError {
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Secret error: {0}")]
+    SecretError(#[from] SecretError),
+
+    #[error("Audit error: {0}")]
+    AuditError(#[from] AuditError),
+
+    #[error("Bundle error: {0}")]
+    BundleError(#[from] BundleError),
+
+    #[error("Backfill error: {0}")]
+    BackfillError(#[from] BackfillError),
+
+    #[cfg(feature = "email")]
+    #[error("Email error: {0}")]
+    EmailError(#[from] EmailError),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Admin API failed: {0}")]
+    ServerError(#[from] std::io::Error),
+}
+
+/// Узел мониторинга из `definetly.toml`: хранилище, шина алертов, админ-API и роутеры подсистем
+/// за его bearer-проверкой. Подсистема строится, только если в конфиге есть её секция
+pub struct Node {
+    config: DefinetlyConfig,
+    store: Option<SharedStore>,
+    bus: AlertBus,
+    labels: SharedLabelResolver,
+    /// Забирается задачей детекции в `run`
+    engine: Option<Engine>,
+    /// Общие для конвейера и `/assess`
+    enrichers: Arc<Vec<Box<dyn Enricher>>>,
+    /// `[congestion]`: проверяет транзакции мемпула до конвейера
+    congestion: Option<Arc<CongestionMonitor>>,
+    /// Запускается в `run`, до этого подсистемы добавляют задачи через `with_backfill_job`
+    backfill: Option<BackfillRunner>,
+    errors: Arc<TaskErrors>,
+    /// `[retention]`: удалённые записи и освобождённое место
+    retention: Option<Arc<RetentionMetrics>>,
+    /// Квоты `[rpc.quota]`; без секции вызовы только учитываются
+    rpc: Arc<RpcQuota>,
+    /// Номер последнего блока; опрос запускается при сборке узла
+    heads: watch::Receiver<u64>,
+    /// Прогноз комиссий для адаптивных порогов детектора и подсистем, отправляющих транзакции
+    gas_oracle: Arc<GasOracle>,
+    admin: AdminState,
+    routes: Router,
+    /// Спецификации роутеров `with_routes`; объединяются с `ApiDoc` в `/openapi.json`
+    docs: Vec<utoipa::openapi::OpenApi>,
+    coordinator: ShutdownCoordinator,
+    tasks: JoinSet<()>,
+}
+
+impl Node {
+    pub async fn build(config: DefinetlyConfig) -> Result<Self, NodeError> {
+        let secrets = SecretManager::from_env();
+        let store: Option<SharedStore> = match &config.monitor {
+            Some(monitor) => Some(Arc::new(FileStore::open(&monitor.store_path)?)),
+            None => None,
+        };
+        let (sealed, sensitive_token) = match (&config.encryption, &store) {
+            (Some(section), Some(store)) => {
+                let key = secrets.resolve(&section.key).await?;
+                let cipher = FieldCipher::new(&key, &section.fields).map_err(|e| NodeError::Config(format!("encryption.key: {}", e)))?;
+                let sealed = section
+                    .namespaces
+                    .iter()
+                    .fold(EncryptedStore::new(store.clone(), Arc::new(cipher)), |sealed, namespace| sealed.with_namespace(namespace));
+                let token = match &section.sensitive_token {
+                    Some(reference) => Some(Arc::new(secrets.resolve(reference).await?)),
+                    None => None,
+                };
+                (Some(Arc::new(sealed)), token)
+            }
+            (Some(_), None) => return Err(NodeError::Config("encryption: needs [monitor] store_path".into())),
+            (None, _) => (None, None),
+        };
+        // Все подсистемы пишут через шифрующее хранилище, чтобы поля не попадали на диск открытыми
+        let store = match &sealed {
+            Some(sealed) => Some(sealed.clone() as SharedStore),
+            None => store,
+        };
+        let token = match config.admin.as_ref().and_then(|admin| admin.token.as_deref()) {
+            Some(reference) => Some(Arc::new(secrets.resolve(reference).await?)),
+            None => None,
+        };
+        let audit = match &store {
+            Some(store) => Some(Arc::new(AuditLog::open(store.clone())?)),
+            None => None,
+        };
+        let mut sinks = Vec::new();
+        for spec in config.routing.iter().flat_map(|routing| &routing.sinks) {
+            sinks.push(sink(spec, &secrets, store.as_ref()).await?);
+        }
+        let routing = match &config.routing {
+            Some(section) => {
+                let (catalog, locales) = messages(&config);
+                Some(Arc::new(section.router(sinks.clone(), store.clone())?.with_messages(catalog, locales)))
+            }
+            None => None,
+        };
+        let registry = Arc::new(DetectorRegistry::default());
+        let labels: SharedLabelResolver = Arc::new(LabelResolver::new(ENS_TTL));
+        let deployments = Arc::new(DeploymentWatch::new(config.detector.pending_ttl_seconds));
+        let rpc = Arc::new(RpcQuota::new(config.rpc.quota.clone().unwrap_or_default()));
+        let errors = Arc::new(TaskErrors::default());
+        let (head_tx, heads) = watch::channel(0);
+        // Симулятор читает `rpc.http_url` через кэш; горячие цели в нём прогревает `StateWarmer`
+        let state = Arc::new(CachedStateProvider::new(RpcStateProvider::new(Arc::new(provider(&rpc, &config, "simulator")?))));
+        let simulator = simulator::state_simulator(state.clone(), heads.clone(), errors.clone());
+        let gas_oracle = Arc::new(GasOracle::new(GAS_HISTORY_BLOCKS));
+        let severity = Arc::new(SeverityModel::new(config.detector.severity.clone()));
+        let funding = config.detector.funding.as_ref().map(|section| Arc::new(section.graph()));
+        let detector = detector(&config, simulator, &registry, &labels, funding.clone())?
+            .with_severity(severity.clone())
+            .with_deployments(deployments.clone())
+            .with_gas_oracle(gas_oracle.clone());
+        let mut engine = Engine::new(detector, config.detector.dedup_window_seconds);
+        if let Some(store) = &store {
+            engine.detector_mut().load_pending(store.as_ref())?;
+            // Бандл, импортированный командой `import` без работающего узла
+            bundle::apply_staged(&mut NodeState {
+                engine: Some(&mut engine),
+                labels: Some(&labels),
+                registry: Some(&registry),
+                router: routing.as_deref(),
+                store: Some(store.as_ref()),
+                ..NodeState::new()
+            })?;
+        }
+
+        let provider = Arc::new(provider(&rpc, &config, "assess")?);
+        let mut bus = AlertBus::default();
+        // Резерв анализирует всё наравне с лидером, но алерты публикует только лидер
+        let elector = match &config.leader {
+            Some(section) => {
+                let elector = LeaderElector::from_config(section, &instance_id()).map_err(|e| NodeError::Config(format!("leader: {}", e)))?;
+                Some(elector.with_errors(errors.clone()))
+            }
+            None => None,
+        };
+        if let Some(elector) = &elector {
+            bus = bus.with_leader_gate(elector.gate());
+        }
+        let hot = Arc::new(HotTargets::new());
+        let mut enrichers: Vec<Box<dyn Enricher>> = vec![Box::new(hot.clone())];
+        // Граф видит переводы мемпула только как источник обогащения
+        if let Some(funding) = funding {
+            enrichers.push(Box::new(funding));
+        }
+        let screening = config.screening.as_ref().map(|section| Arc::new(section.enricher(audit.clone())));
+        if let Some(screening) = &screening {
+            bus = bus.with_annotator(screening.clone());
+            enrichers.push(Box::new(screening.clone()));
+        }
+        let congestion = match &config.congestion {
+            Some(section) => {
+                let monitor = section.monitor(Arc::new(bus.clone())).map_err(|e| NodeError::Config(format!("congestion: {}", e)))?;
+                Some(Arc::new(monitor))
+            }
+            None => None,
+        };
+        if let Some(monitor) = &congestion {
+            enrichers.push(Box::new(monitor.clone()));
+        }
+        let backfill = match (&config.backfill, &store) {
+            (Some(section), Some(store)) => Some(backfill(section, &config, store, &labels, &rpc, &mut enrichers)?),
+            (Some(_), None) => return Err(NodeError::Config("backfill: needs [monitor] store_path".into())),
+            (None, _) => None,
+        };
+        let enrichers = Arc::new(enrichers);
+
+        let admin = AdminState {
+            registry,
+            token,
+            audit,
+            sensitive_token,
+            store: sealed,
+            rpc: Some(rpc.clone()),
+            routing: routing.clone(),
+            backfill: None,
+        };
+
+        let assess = AssessState {
+            access_lists: Arc::new(AccessListPlanner::new(provider.clone())),
+            approvals: Arc::new(ApprovalSimulator::new(provider)),
+            typed_data: Arc::new(TypedDataAssessor::new(Some(labels.clone()))),
+            abi: Arc::new(AbiRegistry::new()),
+            labels: Some(labels.clone()),
+            enrichers: enrichers.clone(),
+        };
+        let routes = admin::protect(assess::router(assess), &admin);
+
+        let mut node = Self {
+            config,
+            store,
+            bus,
+            labels,
+            engine: Some(engine),
+            enrichers,
+            congestion,
+            backfill,
+            errors,
+            retention: None,
+            rpc,
+            heads,
+            gas_oracle,
+            admin,
+            routes,
+            docs: Vec::new(),
+            coordinator: ShutdownCoordinator::new(SHUTDOWN_TIMEOUT),
+            tasks: JoinSet::new(),
+        };
+        #[cfg(feature = "push")]
+        if let Some(store) = node.store.clone().filter(|_| node.wallet_sinks()) {
+            use utoipa::OpenApi;
+            node.routes = node.routes.merge(push::router(Arc::new(WalletSubscriptions::new(store))));
+            node.docs.push(push::WalletApi::openapi());
+        }
+        if let Some(elector) = elector {
+            let shutdown = node.shutdown_signal();
+            node.tasks.spawn(async move { elector.run(shutdown).await });
+        }
+        let (provider, errors, shutdown) = (node.provider("heads")?, node.errors.clone(), node.shutdown_signal());
+        node.tasks.spawn(follow_heads(provider, head_tx, errors, shutdown));
+        let (warmer, heads) = (StateWarmer::new(state, hot, WARM_TARGETS), node.heads());
+        node.tasks.spawn(async move { warmer.run(heads).await });
+        let (oracle, provider, heads) = (node.gas_oracle.clone(), node.provider("gas_oracle")?, node.heads());
+        node.tasks.spawn(async move { oracle.run(provider.as_ref(), heads).await });
+        if severity.has_pools() {
+            let (provider, heads) = (node.provider("severity")?, node.heads());
+            node.tasks.spawn(async move { severity.run(provider, heads).await });
+        }
+        if let Some(router) = routing {
+            let (bus, shutdown) = (node.bus.clone(), node.shutdown_signal());
+            node.tasks.spawn(async move { router.run(&bus, shutdown).await });
+        }
+        let (provider, shutdown) = (node.provider("deployments")?, node.shutdown_signal());
+        node.tasks.spawn(async move { deployments.run(provider.as_ref(), DEPLOYMENT_CHECK, shutdown).await });
+        node.start_congestion()?;
+        if let (Some(screening), Some(section)) = (screening, &node.config.screening) {
+            let (specs, interval, shutdown) = (section.lists.clone(), Duration::from_secs(section.refresh_interval_seconds), node.shutdown_signal());
+            node.tasks.spawn(async move { screening.run(specs, interval, shutdown).await });
+        }
+        node.start_forensics()?;
+        node.start_permit2()?;
+        node.start_retention();
+        node.start_digests(sinks);
+        node.start_correlation();
+        node.start_bridges()?;
+        node.start_intents()?;
+        Ok(node)
+    }
+
+    pub fn config(&self) -> &DefinetlyConfig {
+        &self.config
+    }
+
+    pub fn store(&self) -> Option<&SharedStore> {
+        self.store.as_ref()
+    }
+
+    pub fn bus(&self) -> &AlertBus {
+        &self.bus
+    }
+
+    pub fn labels(&self) -> &SharedLabelResolver {
+        &self.labels
+    }
+
+    /// Ошибки фоновых задач узла
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Итоги очистки `[retention]`
+    pub fn retention(&self) -> Option<RetentionSnapshot> {
+        self.retention.as_ref().map(|metrics| metrics.snapshot())
+    }
+
+    pub fn admin(&self) -> &AdminState {
+        &self.admin
+    }
+
+    /// Провайдер `rpc.http_url`, чьи вызовы учитываются в квоте на `subsystem`
+    pub fn provider(&self, subsystem: &str) -> Result<Arc<QuotaProvider>, NodeError> {
+        Ok(Arc::new(provider(&self.rpc, &self.config, subsystem)?))
+    }
+
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.coordinator.signal()
+    }
+
+    /// Номера новых блоков для подсистем, идущих за головой; опрос общий для всех подписчиков
+    pub fn heads(&self) -> watch::Receiver<u64> {
+        self.heads.clone()
+    }
+
+    /// Общий оракул комиссий, например для `RestakingClient::with_gas_oracle`
+    pub fn gas_oracle(&self) -> Arc<GasOracle> {
+        self.gas_oracle.clone()
+    }
+
+    /// Роутер крейта подсистемы (портфель, мультисиг) рядом с админ-API и за той же проверкой токена;
+    /// `doc` — его спецификация, например `PortfolioApi::openapi()`
+    pub fn with_routes(mut self, routes: Router, doc: utoipa::openapi::OpenApi) -> Self {
+        self.routes = self.routes.merge(admin::protect(routes, &self.admin));
+        self.docs.push(doc);
+        self
+    }
+
+    /// Есть ли в `[routing]` синки владельцам кошельков: им нужен публичный приём подписок
+    #[cfg(feature = "push")]
+    fn wallet_sinks(&self) -> bool {
+        self.config.routing.iter().flat_map(|r| &r.sinks).any(|s| matches!(s, SinkSpec::Wallet { .. }))
+    }
+
+    /// Заполнение подсистемы (история слэшингов стейкинга) рядом с задачами `[backfill]`
+    pub fn with_backfill_job(mut self, job: Arc<dyn BackfillJob>, interval: Duration) -> Result<Self, NodeError> {
+        let runner = match self.backfill.take() {
+            Some(runner) => runner,
+            None => {
+                let store = self.store.clone().ok_or_else(|| NodeError::Config("backfill: needs [monitor] store_path".into()))?;
+                BackfillRunner::new(store)
+            }
+        };
+        self.backfill = Some(runner.with_job(job, interval)?);
+        Ok(self)
+    }
+
+    /// Фоновая задача узла; должна завершаться по `shutdown_signal`
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        self.tasks.spawn(task);
+    }
+
+    /// Работает до SIGTERM или Ctrl-C и проходит фазы остановки. Падение админ-API
+    /// тоже останавливает узел: без него не работают ни подтверждения, ни оценка транзакций
+    pub async fn run(mut self) -> Result<Vec<HookOutcome>, NodeError> {
+        if let Some(runner) = self.backfill.take() {
+            let runner = Arc::new(runner);
+            self.admin.backfill = Some(runner.clone());
+            self.tasks.spawn(runner.run(self.coordinator.signal()));
+        }
+        self.start_detection()?;
+        let server = match &self.config.admin {
+            Some(section) => {
+                let addr: SocketAddr = section
+                    .listen
+                    .parse()
+                    .map_err(|_| NodeError::Config(format!("admin.listen: '{}' is not a socket address", section.listen)))?;
+                let doc = openapi::merged(self.docs.clone());
+                Some(tokio::spawn(admin::serve(addr, self.admin.clone(), self.routes.clone(), doc, self.coordinator.signal())))
+            }
+            None => None,
+        };
+
+        let failure = match server {
+            Some(mut server) => tokio::select! {
+                _ = ShutdownCoordinator::wait_for_signal() => None,
+                result = &mut server => Some(match result {
+                    Ok(Ok(())) => std::io::Error::other("admin API stopped unexpectedly"),
+                    Ok(Err(e)) => e,
+                    Err(e) => std::io::Error::other(e),
+                }),
+            },
+            None => {
+                ShutdownCoordinator::wait_for_signal().await;
+                None
+            }
+        };
+
+        let outcomes = self.coordinator.run().await;
+        while self.tasks.join_next().await.is_some() {}
+        match failure {
+            Some(e) => Err(NodeError::ServerError(e)),
+            None => Ok(outcomes),
+        }
+    }
+
+    /// Мемпул из `[ingestion]` -> обогащение -> детекция -> шина. Остановка закрывает вход
+    /// конвейера, детекция дорабатывает очередь и сохраняет пул ожидающих транзакций
+    fn start_detection(&mut self) -> Result<(), NodeError> {
+        let Some(mut engine) = self.engine.take() else {
+            return Ok(());
+        };
+        let policy = SheddingPolicy { watched: engine.watchlist().clone(), ..SheddingPolicy::default() };
+        let head = ChainHead::new(*self.heads.borrow(), SIMULATION_RELEVANCE_BLOCKS);
+        self.tasks.spawn(head.clone().follow(self.heads()));
+        let pipeline = Arc::new(Pipeline::new(PipelineConfig { policy, ..PipelineConfig::default() }).with_chain_head(head));
+        self.coordinator
+            .register(ShutdownPhase::StopIngestion, Arc::new(IngestionStop { pipeline: pipeline.clone() }));
+
+        let sources = match &self.config.ingestion {
+            Some(ingestion) => {
+                let sink: Arc<dyn TxSink> = match &self.congestion {
+                    Some(monitor) => Arc::new(monitor.watching(pipeline.clone())),
+                    None => pipeline.clone(),
+                };
+                let sources = Arc::new(MultiSource::new(sink));
+                self.tasks.spawn(sources.clone().run(ingestion.providers.clone(), self.coordinator.signal()));
+                Some(sources)
+            }
+            None => None,
+        };
+        #[cfg(feature = "dashboard")]
+        self.start_dashboard(&pipeline, sources)?;
+        #[cfg(not(feature = "dashboard"))]
+        let _ = sources;
+        let (enrich, enrichers) = (pipeline.clone(), self.enrichers.clone());
+        self.tasks.spawn(async move { enrich.run_enrich(&enrichers).await });
+
+        // Симуляция ждёт ответа C++ потока синхронно, поэтому детекция занимает свой поток, а не воркер рантайма
+        let (bus, store, errors, runtime) = (self.bus.clone(), self.store.clone(), self.errors.clone(), tokio::runtime::Handle::current());
+        self.tasks.spawn_blocking(move || {
+            runtime.block_on(pipeline.run_detect(engine.detector_mut(), &bus));
+            if let Some(store) = store {
+                errors.check(engine.detector().save_pending(store.as_ref()));
+            }
+        });
+        Ok(())
+    }
+
+    /// `[dashboard]`: лента алертов шины, метрики конвейера и источников мемпула
+    #[cfg(feature = "dashboard")]
+    fn start_dashboard(&mut self, pipeline: &Arc<Pipeline>, sources: Option<Arc<MultiSource>>) -> Result<(), NodeError> {
+        let Some(section) = &self.config.dashboard else {
+            return Ok(());
+        };
+        let addr: SocketAddr = section
+            .listen
+            .parse()
+            .map_err(|_| NodeError::Config(format!("dashboard.listen: '{}' is not a socket address", section.listen)))?;
+        let mut state = DashboardState::new(self.bus.clone(), pipeline.clone()).with_allowed_origins(section.allowed_origins.clone());
+        if let Some(sources) = sources {
+            state = state.with_sources(sources);
+        }
+        let (errors, shutdown) = (self.errors.clone(), self.shutdown_signal());
+        self.tasks.spawn(async move {
+            if let Err(e) = dashboard::serve(addr, state, shutdown).await {
+                errors.record(format!("dashboard: {}", e));
+            }
+        });
+        Ok(())
+    }
+
+    /// `[congestion]`: замеры загрузки сети раз в `interval_seconds`
+    fn start_congestion(&mut self) -> Result<(), NodeError> {
+        let (Some(monitor), Some(section)) = (self.congestion.clone(), &self.config.congestion) else {
+            return Ok(());
+        };
+        let (provider, interval, shutdown) = (self.provider("congestion")?, Duration::from_secs(section.interval_seconds), self.shutdown_signal());
+        self.tasks.spawn(monitor.run(provider, interval, shutdown));
+        Ok(())
+    }
+
+    /// `[forensics]`: сверка фронтранов и сэндвичей шины с добытыми блоками
+    fn start_forensics(&mut self) -> Result<(), NodeError> {
+        let Some(section) = &self.config.forensics else {
+            return Ok(());
+        };
+        let provider = self.provider("forensics")?;
+        let mut confirmer = AlertConfirmer::new(provider.clone(), section.blocks);
+        if section.state_diff {
+            confirmer = confirmer.with_state_diff(StateDiffer::new(provider, section.layouts()).with_labels(self.labels.clone()));
+        }
+        let (bus, interval, shutdown) = (self.bus.clone(), Duration::from_secs(section.interval_seconds), self.shutdown_signal());
+        self.tasks.spawn(async move { confirmer.run(&bus, interval, shutdown).await });
+        Ok(())
+    }
+
+    /// `[permit2]`: разрешения кошельков по событиям Permit2
+    fn start_permit2(&mut self) -> Result<(), NodeError> {
+        let Some(section) = &self.config.permit2 else {
+            return Ok(());
+        };
+        let store = self.store.clone().ok_or_else(|| NodeError::Config("permit2: needs [monitor] store_path".into()))?;
+        let monitor = Permit2Monitor::new(self.provider("permit2")?)
+            .with_reminder_window(Duration::from_secs(section.reminder_hours * 3600));
+        for wallet in &section.wallets {
+            let wallet = wallet.parse().map_err(|_| NodeError::Config(format!("permit2.wallets: '{}' is not an address", wallet)))?;
+            monitor.watch(wallet);
+        }
+        let index = monitor.indexer(store);
+        let (bus, heads, shutdown) = (self.bus.clone(), self.heads(), self.shutdown_signal());
+        self.tasks.spawn(Arc::new(monitor).run(index, bus, heads, shutdown));
+        Ok(())
+    }
+
+    /// `[digests]`: сводки по алертам шины с момента старта узла
+    fn start_retention(&mut self) {
+        let Some(section) = &self.config.retention else {
+            return;
+        };
+        let mut job = RetentionJob::new(section.rules.clone());
+        if let Some(store) = &self.store {
+            job = job.with_store(store.clone());
+        }
+        self.retention = Some(job.metrics());
+        let (interval, shutdown) = (Duration::from_secs(section.interval_seconds), self.shutdown_signal());
+        self.tasks.spawn(async move { job.run(interval, shutdown).await });
+    }
+
+    fn start_digests(&mut self, sinks: Vec<Arc<dyn Sink>>) {
+        let Some(section) = &self.config.digests else {
+            return;
+        };
+        let history = Arc::new(AlertHistory::default());
+        let (catalog, locales) = messages(&self.config);
+        let scheduler = sinks
+            .into_iter()
+            .fold(DigestScheduler::new(history.clone(), section.tenants.clone()), DigestScheduler::with_sink)
+            .with_messages(catalog, locales);
+        let (bus, shutdown) = (self.bus.clone(), self.shutdown_signal());
+        self.tasks.spawn(async move { history.collect(&bus, shutdown).await });
+        let shutdown = self.shutdown_signal();
+        self.tasks.spawn(async move { scheduler.run(shutdown).await });
+    }
+
+    /// `[correlation]`: инциденты из алертов шины публикуются в неё же
+    fn start_correlation(&mut self) {
+        let Some(section) = &self.config.correlation else {
+            return;
+        };
+        let (correlator, bus, shutdown) = (section.correlator(), self.bus.clone(), self.shutdown_signal());
+        self.tasks.spawn(async move { correlator.run(&bus, shutdown).await });
+    }
+
+    /// `[bridges]`: провайдеры сетей маршрутов идут через общую квоту
+    fn start_bridges(&mut self) -> Result<(), NodeError> {
+        let Some(section) = &self.config.bridges else {
+            return Ok(());
+        };
+        let store = self.store.clone().ok_or_else(|| NodeError::Config("bridges: needs [monitor] store_path".into()))?;
+        let mut providers = HashMap::from([(self.config.rpc.chain_id, self.provider("bridges")?)]);
+        for chain in &section.chains {
+            let provider = self.rpc.http_provider("bridges", &chain.http_url)
+                .map_err(|e| NodeError::Config(format!("bridges.chains: {}", e)))?;
+            providers.insert(chain.chain_id, Arc::new(provider));
+        }
+        let tracker = section.tracker(store, providers).map_err(|e| NodeError::Config(format!("bridges: {}", e)))?;
+        let (bus, interval, shutdown) = (self.bus.clone(), Duration::from_secs(section.interval_seconds), self.shutdown_signal());
+        self.tasks.spawn(async move { tracker.run(bus, interval, shutdown).await });
+        Ok(())
+    }
+
+    /// `[intents]`: расчёты CoW Protocol и UniswapX на каждый новый блок
+    fn start_intents(&mut self) -> Result<(), NodeError> {
+        let Some(section) = &self.config.intents else {
+            return Ok(());
+        };
+        let config = section.intent_config();
+        let orders = Arc::new(HttpOrderBook::new(&config, self.config.rpc.chain_id));
+        let monitor = section
+            .affiliates()
+            .into_iter()
+            .fold(IntentMonitor::new(self.provider("intents")?, orders, config), |monitor, (solver, affiliate)| {
+                monitor.with_affiliate(solver, affiliate)
+            });
+        // Цикл завершается вместе с `follow_heads`, когда закрывается канал блоков
+        let (bus, heads) = (self.bus.clone(), self.heads());
+        self.tasks.spawn(async move { monitor.run(heads, bus).await });
+        Ok(())
+    }
+}
+
+/// Публикует номер последнего блока, когда он меняется
+async fn follow_heads<M: Middleware>(provider: Arc<M>, heads: watch::Sender<u64>, errors: Arc<TaskErrors>, mut shutdown: ShutdownSignal) {
+    let mut ticker = tokio::time::interval(HEAD_POLL);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.wait() => return,
+        }
+        let Some(head) = errors.check(provider.get_block_number().await) else {
+            continue;
+        };
+        heads.send_if_modified(|current| std::mem::replace(current, head.as_u64()) != head.as_u64());
+    }
+}
+
+/// Имя экземпляра в блокировке лидера: имя хоста, если оно задано в окружении
+fn instance_id() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| format!("pid-{}", std::process::id()))
+}
+
+/// Задачи `[backfill]` по `monitor.watched_contracts`. Обогащение возрастом контракта
+/// подключается в `enrichers` вместе с созданиями, найденными до перезапуска
+fn backfill(
+    section: &BackfillSection,
+    config: &DefinetlyConfig,
+    store: &SharedStore,
+    labels: &SharedLabelResolver,
+    rpc: &Arc<RpcQuota>,
+    enrichers: &mut Vec<Box<dyn Enricher>>,
+) -> Result<BackfillRunner, NodeError> {
+    let addresses: Vec<Address> =
+        config.monitor.iter().flat_map(|monitor| &monitor.watched_contracts).filter_map(|a| a.parse().ok()).collect();
+    let provider = Arc::new(provider(rpc, config, "backfill")?);
+    let interval = Duration::from_millis(section.interval_ms);
+    let mut runner = BackfillRunner::new(store.clone()).with_retry(section.retry.clone());
+    if section.labels {
+        labels.load_ens(store.as_ref())?;
+        let job = EnsBackfill::new(labels.clone(), provider.clone(), store.clone(), addresses.clone(), section.batch_size);
+        runner = runner.with_job(Arc::new(job), interval)?;
+    }
+    if section.contract_age {
+        let enricher = Arc::new(ContractAgeEnricher::new(section.young_contract_hours, CONTRACT_AGE_QUEUE));
+        enricher.load(store.as_ref())?;
+        let source = Arc::new(TraceCreationSource::new(provider));
+        let job = ContractAgeBackfill::new(enricher.clone(), source, store.clone(), addresses, section.batch_size);
+        runner = runner.with_job(Arc::new(job), interval)?;
+        enrichers.push(Box::new(enricher));
+    }
+    Ok(runner)
+}
+
+/// Синк `[routing.sinks]`; ключи платформ берутся из менеджера секретов
+#[cfg_attr(not(feature = "push"), allow(unused_variables))]
+async fn sink(spec: &SinkSpec, secrets: &SecretManager, store: Option<&SharedStore>) -> Result<Arc<dyn Sink>, NodeError> {
+    Ok(match spec {
+        SinkSpec::Webhook { name, url } => Arc::new(WebhookSink::new(name, url)),
+        SinkSpec::PagerDuty { name, routing_key, url } => {
+            let sink = PagerDutySink::new(name, secrets.resolve(routing_key).await?.expose());
+            Arc::new(match url {
+                Some(url) => sink.with_url(url),
+                None => sink,
+            })
+        }
+        SinkSpec::Opsgenie { name, api_key, base_url } => {
+            let sink = OpsgenieSink::new(name, secrets.resolve(api_key).await?.expose());
+            Arc::new(match base_url {
+                Some(base_url) => sink.with_base_url(base_url),
+                None => sink,
+            })
+        }
+        #[cfg(feature = "email")]
+        SinkSpec::Smtp { name, password, smtp } => {
+            let password = match password {
+                Some(reference) => Some(secrets.resolve(reference).await?),
+                None => None,
+            };
+            Arc::new(SmtpSink::new(name, smtp.clone(), password.as_ref())?)
+        }
+        #[cfg(feature = "push")]
+        SinkSpec::Wallet { name, xmtp, web_push } => {
+            let store = store.ok_or_else(|| NodeError::Config("routing.sinks: wallet sinks need [monitor]".into()))?;
+            let mut sink = WalletSink::new(name, WalletSubscriptions::new(store.clone()));
+            if let Some(xmtp) = xmtp {
+                let token = match &xmtp.token {
+                    Some(reference) => Some(secrets.resolve(reference).await?),
+                    None => None,
+                };
+                sink = sink.with_xmtp(&xmtp.gateway_url, token);
+            }
+            if let Some(web_push) = web_push {
+                sink = sink.with_web_push(secrets.resolve(&web_push.vapid_private_key).await?, &web_push.subject);
+            }
+            Arc::new(sink)
+        }
+        #[cfg(not(feature = "email"))]
+        SinkSpec::Smtp { name, .. } => return Err(NodeError::Config(format!("routing.sinks: '{}' needs the 'email' feature", name))),
+        #[cfg(not(feature = "push"))]
+        SinkSpec::Wallet { name, .. } => return Err(NodeError::Config(format!("routing.sinks: '{}' needs the 'push' feature", name))),
+    })
+}
+
+/// Шаблоны и языки `[i18n]`; без секции — встроенные
+fn messages(config: &DefinetlyConfig) -> (MessageCatalog, LocaleSelector) {
+    match &config.i18n {
+        Some(i18n) => (i18n.catalog(), i18n.selector()),
+        None => (MessageCatalog::new(), LocaleSelector::default()),
+    }
+}
+
+fn provider(rpc: &Arc<RpcQuota>, config: &DefinetlyConfig, subsystem: &str) -> Result<QuotaProvider, NodeError> {
+    rpc.http_provider(subsystem, &config.rpc.http_url)
+        .map_err(|e| NodeError::Config(format!("rpc.http_url: {}", e)))
+}
+
+/// Детектор по секции `[detector]` для сети `rpc.chain_id`: пороги, правила, метки и выключатели реестра.
+/// `funding` — тот же граф, что подключён источником обогащения
+fn detector(
+    config: &DefinetlyConfig,
+    simulator: impl Simulator + 'static,
+    registry: &Arc<DetectorRegistry>,
+    labels: &SharedLabelResolver,
+    funding: Option<Arc<FundingGraph>>,
+) -> Result<MevDetector, NodeError> {
+    let section = &config.detector;
+    let chain = config
+        .rpc
+        .chain()
+        .ok_or_else(|| NodeError::Config(format!("rpc.chain_id: no chain rules for {}", config.rpc.chain_id)))?;
+    let mut detector = MevDetector::new(simulator, section.pending_ttl_seconds, section.thresholds())
+        .with_chain(chain)
+        .with_labels(labels.clone())
+        .with_registry(registry.clone());
+    if section.dedup_window_seconds > 0 {
+        detector = detector.with_dedup(Duration::from_secs(section.dedup_window_seconds), crate::dedup::DEFAULT_CAPACITY);
+    }
+    if !section.rules.is_empty() {
+        let rules = RuleEngine::new(section.rules.clone()).map_err(|e| NodeError::Config(format!("detector.rules: {}", e)))?;
+        detector = detector.with_rules(rules);
+    }
+    if let Some(victims) = &section.victims {
+        detector = detector.with_victim_tracking(victims.tracker());
+    }
+    if let Some(fingerprints) = &section.fingerprints {
+        detector = detector.with_fingerprints(Arc::new(fingerprints.fingerprinter()));
+    }
+    if let Some(funding) = funding {
+        detector = detector.with_funding(funding);
+    }
+    Ok(detector)
+}
@@ -0,0 +1,122 @@
+use crate::abi::{DecodedArg, DecodedCall};
+use crate::access_list::AccessListPlan;
+use crate::admin::AuditVerification;
+use crate::approvals::{ApprovalImpact, ApprovalQuery, ExtractionPath};
+use crate::permit2::{Permit2Allowance, Permit2Exposure};
+use crate::assess::{AssessTxRequest, AssessTxResponse, AssessTypedDataRequest, DecodeRawTxRequest, RawTxView};
+use crate::audit::{AuditEntry, AuditResult};
+use crate::backfill::{JobState, JobStatus};
+use crate::bus::{AlertLevel, BusAlert};
+use crate::detector::{MevAlert, MevType};
+use crate::pipeline::LatencyBudget;
+use crate::registry::{DetectorPatch, DetectorSettings, DetectorStatus};
+use crate::rpc::SubsystemUsage;
+use crate::typed_data::{Grant, SignatureScheme, TypedDataDomain, TypedDataVerdict};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::OpenApi as OpenApiDoc;
+use utoipa::{Modify, OpenApi};
+
+/// Bearer-токен админ-API (`admin.token`)
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Спецификация HTTP API этого крейта. Маршруты других подсистем (портфель, мультисиг)
+/// описываются рядом с их роутерами и добавляются через `merge`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "DeFinetly API", description = "Detectors, audit log, alerts and transaction assessment of the DeFinetly monitoring service"),
+    paths(
+        crate::admin::list_detectors,
+        crate::admin::get_detector,
+        crate::admin::patch_detector,
+        crate::admin::query_audit,
+        crate::admin::verify_audit,
+        crate::admin::buffered_alerts,
+        crate::admin::acknowledge_alert,
+        crate::admin::resolve_alert,
+        crate::admin::incident_webhook,
+        crate::admin::rpc_usage,
+        crate::admin::list_backfills,
+        crate::admin::get_backfill,
+        crate::admin::control_backfill,
+        crate::assess::assess_tx,
+        crate::assess::assess_typed_data,
+        crate::assess::decode_raw_tx,
+        crate::assess::assess_approval,
+    ),
+    components(schemas(
+        AlertLevel,
+        BusAlert,
+        MevAlert,
+        MevType,
+        LatencyBudget,
+        AuditEntry,
+        AuditResult,
+        AuditVerification,
+        DetectorPatch,
+        DetectorSettings,
+        DetectorStatus,
+        SubsystemUsage,
+        JobState,
+        JobStatus,
+        AccessListPlan,
+        ApprovalImpact,
+        ApprovalQuery,
+        ExtractionPath,
+        Permit2Allowance,
+        Permit2Exposure,
+        AssessTxRequest,
+        AssessTxResponse,
+        AssessTypedDataRequest,
+        DecodeRawTxRequest,
+        DecodedArg,
+        DecodedCall,
+        Grant,
+        RawTxView,
+        SignatureScheme,
+        TypedDataDomain,
+        TypedDataVerdict,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "detectors", description = "Runtime detector settings"),
+        (name = "audit", description = "Hash-chained log of signed actions"),
+        (name = "alerts", description = "Stored alerts with field-level encryption and escalation acknowledgements"),
+        (name = "rpc", description = "RPC provider quota consumption"),
+        (name = "backfill", description = "Long-running backfill jobs with persisted progress"),
+        (name = "assess", description = "Pre-signing transaction assessment for wallets"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Полная спецификация: эта плюс спецификации остальных роутеров сервера
+pub fn merged(others: impl IntoIterator<Item = OpenApiDoc>) -> OpenApiDoc {
+    others.into_iter().fold(ApiDoc::openapi(), |mut doc, other| {
+        doc.merge(other);
+        doc
+    })
+}
+
+/// `GET /openapi.json`; документ собирается один раз при старте
+pub fn router(doc: OpenApiDoc) -> Router {
+    let doc = Arc::new(doc);
+    Router::new().route(
+        "/openapi.json",
+        get(move || {
+            let doc = doc.clone();
+            async move { Json(doc.as_ref().clone()) }
+        }),
+    )
+}
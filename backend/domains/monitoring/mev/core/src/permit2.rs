@@ -0,0 +1,452 @@
+use crate::address::ChecksummedAddress;
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::compat::{Address, H256, U256};
+use crate::indexer::{IndexHandler, IndexedEvent, LogIndexer};
+use crate::shutdown::ShutdownSignal;
+use crate::store::Store;
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::typed_data::{self, PERMIT2};
+use async_trait::async_trait;
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::contract::{parse_log, EthCall, EthEvent};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockId, Bytes, Filter, TransactionRequest};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::watch;
+
+/// За сколько до истечения напоминать о разрешении
+pub const DEFAULT_REMINDER_WINDOW: Duration = Duration::from_secs(3 * 24 * 3600);
+
+#[derive(Debug, Error)]
+pub enum Permit2Error {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+fn provider_err(e: impl std::fmt::Display) -> Permit2Error {
+    Permit2Error::ProviderError(e.to_string())
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Approval", abi = "Approval(address,address,address,uint160,uint48)")]
+struct ApprovalEvent {
+    #[ethevent(indexed)]
+    owner: Address,
+    #[ethevent(indexed)]
+    token: Address,
+    #[ethevent(indexed)]
+    spender: Address,
+    amount: U256,
+    expiration: U256,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Permit", abi = "Permit(address,address,address,uint160,uint48,uint48)")]
+struct PermitEvent {
+    #[ethevent(indexed)]
+    owner: Address,
+    #[ethevent(indexed)]
+    token: Address,
+    #[ethevent(indexed)]
+    spender: Address,
+    amount: U256,
+    expiration: U256,
+    nonce: U256,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Lockdown", abi = "Lockdown(address,address,address)")]
+struct LockdownEvent {
+    #[ethevent(indexed)]
+    owner: Address,
+    token: Address,
+    spender: Address,
+}
+
+#[derive(Clone, Debug, EthCall)]
+#[ethcall(name = "allowance", abi = "allowance(address,address,address)")]
+struct AllowanceCall {
+    owner: Address,
+    token: Address,
+    spender: Address,
+}
+
+/// Разрешение из `Permit2.allowance(owner, token, spender)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Permit2Allowance {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub owner: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub token: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub spender: ChecksummedAddress,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub amount: U256,
+    /// UNIX-время истечения; после него Permit2 отклоняет `transferFrom`
+    pub expiration: u64,
+    pub nonce: u64,
+}
+
+impl Permit2Allowance {
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.amount.is_zero() && self.expiration >= now
+    }
+}
+
+/// Разрешение с оценкой риска по тем же правилам, что и подписи Permit2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Permit2Exposure {
+    pub allowance: Permit2Allowance,
+    pub level: AlertLevel,
+    pub reasons: Vec<String>,
+}
+
+impl Permit2Exposure {
+    pub fn assess(allowance: Permit2Allowance) -> Self {
+        let grants = vec![typed_data::grant(Some(allowance.token), Some(allowance.amount), Some(allowance.expiration))];
+        let (mut reasons, mut level) = (Vec::new(), AlertLevel::Low);
+        typed_data::unlimited_reason(&grants, &mut reasons, &mut level);
+        typed_data::expiry_reason(Some(allowance.expiration), &mut reasons, &mut level);
+        Self { allowance, level, reasons }
+    }
+}
+
+/// `(owner, token, spender)`
+pub type AllowanceKey = (Address, Address, Address);
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Читает `Permit2.allowance(owner, token, spender)` на блоке
+pub async fn read_allowance<M: Middleware>(
+    provider: &M,
+    key: AllowanceKey,
+    block: Option<BlockId>,
+) -> Result<Permit2Allowance, Permit2Error> {
+    let (owner, token, spender) = key;
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(PERMIT2.parse::<Address>().unwrap())
+        .data(Bytes::from(AllowanceCall { owner, token, spender }.encode()))
+        .into();
+    let bytes = provider.call(&tx, block).await.map_err(provider_err)?;
+    let (amount, expiration, nonce) = <(U256, U256, U256)>::decode(bytes.as_ref()).map_err(provider_err)?;
+    Ok(Permit2Allowance {
+        owner: owner.into(),
+        token: token.into(),
+        spender: spender.into(),
+        amount,
+        expiration: expiration.low_u64(),
+        nonce: nonce.low_u64(),
+    })
+}
+
+/// Разрешения Permit2 наблюдаемых кошельков: их не видно в `allowance` самих токенов,
+/// поэтому состояние читается из Permit2 по его событиям
+pub struct Permit2Monitor<M> {
+    provider: Arc<M>,
+    permit2: Address,
+    reminder_window: Duration,
+    watched: RwLock<HashSet<Address>>,
+    allowances: RwLock<HashMap<AllowanceKey, Permit2Allowance>>,
+    /// Уже напомненные пары (разрешение, срок); новое продление напоминается заново
+    reminded: Mutex<HashSet<(AllowanceKey, u64)>>,
+    /// Разрешения, которые не удалось перечитать: `true` — после чтения нужен алерт о выдаче
+    stale: Mutex<HashMap<AllowanceKey, bool>>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> Permit2Monitor<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            permit2: PERMIT2.parse().unwrap(),
+            reminder_window: DEFAULT_REMINDER_WINDOW,
+            watched: RwLock::new(HashSet::new()),
+            allowances: RwLock::new(HashMap::new()),
+            reminded: Mutex::new(HashSet::new()),
+            stale: Mutex::new(HashMap::new()),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn with_reminder_window(mut self, window: Duration) -> Self {
+        self.reminder_window = window;
+        self
+    }
+
+    pub fn watch(&self, owner: Address) {
+        self.watched.write().unwrap().insert(owner);
+    }
+
+    pub fn unwatch(&self, owner: Address) {
+        self.watched.write().unwrap().remove(&owner);
+        self.allowances.write().unwrap().retain(|key, _| key.0 != owner);
+        self.stale.lock().unwrap().retain(|key, _| key.0 != owner);
+    }
+
+    fn is_watched(&self, owner: &Address) -> bool {
+        self.watched.read().unwrap().contains(owner)
+    }
+
+    /// Ошибки чтения разрешений и индекса событий
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Индекс событий `Approval`, `Permit` и `Lockdown` контракта Permit2; новый курсор — с головы
+    pub fn indexer(&self, store: Arc<dyn Store>) -> LogIndexer<M, AllowanceKey> {
+        LogIndexer::new("permit2.allowances", self.provider.clone(), store, 0)
+            .with_address(self.permit2)
+            .with_event::<ApprovalEvent>(|e| (e.owner, e.token, e.spender))
+            .with_event::<PermitEvent>(|e| (e.owner, e.token, e.spender))
+            .with_event::<LockdownEvent>(|e| (e.owner, e.token, e.spender))
+            .from_head()
+    }
+
+    /// Известные разрешения кошелька, включая истёкшие
+    pub fn allowances(&self, owner: Address) -> Vec<Permit2Allowance> {
+        let mut list: Vec<Permit2Allowance> =
+            self.allowances.read().unwrap().iter().filter(|(key, _)| key.0 == owner).map(|(_, a)| a.clone()).collect();
+        list.sort_by_key(|a| (a.token, a.spender));
+        list
+    }
+
+    /// Действующие разрешения кошелька с оценкой риска, самые опасные первыми
+    pub fn report(&self, owner: Address, now: u64) -> Vec<Permit2Exposure> {
+        let mut report: Vec<Permit2Exposure> =
+            self.allowances(owner).into_iter().filter(|a| a.is_active(now)).map(Permit2Exposure::assess).collect();
+        report.sort_by_key(|a| std::cmp::Reverse(a.level));
+        report
+    }
+
+    /// Читает текущее состояние разрешения из контракта
+    pub async fn refresh(&self, key: AllowanceKey, block: Option<BlockId>) -> Result<Permit2Allowance, Permit2Error> {
+        let allowance = read_allowance(self.provider.as_ref(), key, block).await?;
+        self.allowances.write().unwrap().insert(key, allowance.clone());
+        Ok(allowance)
+    }
+
+    /// Перечитывает разрешение; при ошибке оно остаётся в `stale` до следующего блока
+    async fn refresh_or_defer(&self, key: AllowanceKey, block: Option<BlockId>, alert_on_grant: bool) -> Option<Permit2Allowance> {
+        match self.refresh(key, block).await {
+            Ok(allowance) => Some(allowance),
+            Err(e) => {
+                self.errors.record(format!("permit2 allowance {:?}: {}", key, e));
+                let mut stale = self.stale.lock().unwrap();
+                let pending = stale.entry(key).or_default();
+                *pending |= alert_on_grant;
+                None
+            }
+        }
+    }
+
+    /// Затронутые событиями разрешения наблюдаемых кошельков в диапазоне блоков
+    async fn touched(&self, from: u64, to: u64) -> Result<Vec<AllowanceKey>, Permit2Error> {
+        let owners: Vec<H256> = self.watched.read().unwrap().iter().map(|a| H256::from(*a)).collect();
+        if owners.is_empty() {
+            return Ok(Vec::new());
+        }
+        let filter = Filter::new().address(self.permit2).topic1(owners).from_block(from).to_block(to);
+        let logs = self.provider.get_logs(&filter).await.map_err(provider_err)?;
+
+        let mut keys = Vec::new();
+        for log in logs {
+            let key = if let Ok(e) = parse_log::<ApprovalEvent>(log.clone()) {
+                (e.owner, e.token, e.spender)
+            } else if let Ok(e) = parse_log::<PermitEvent>(log.clone()) {
+                (e.owner, e.token, e.spender)
+            } else if let Ok(e) = parse_log::<LockdownEvent>(log) {
+                (e.owner, e.token, e.spender)
+            } else {
+                continue;
+            };
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Восстанавливает разрешения по истории событий, например после добавления кошелька.
+    /// Непрочитанные разрешения перечитываются в `run`
+    pub async fn backfill(&self, from: u64, to: u64) -> Result<usize, Permit2Error> {
+        let keys = self.touched(from, to).await?;
+        let mut restored = 0;
+        for key in keys {
+            if self.refresh_or_defer(key, Some(BlockId::from(to)), false).await.is_some() {
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+
+    /// Обновляет разрешения наблюдаемых кошельков по событиям индекса; новые действующие — алерты.
+    /// Разрешение читается на блоке его последнего события
+    pub async fn apply_events(&self, events: &[IndexedEvent<AllowanceKey>]) -> Vec<BusAlert> {
+        let mut latest: BTreeMap<AllowanceKey, u64> = BTreeMap::new();
+        for event in events.iter().filter(|e| self.is_watched(&e.event.0)) {
+            latest.insert(event.event, event.block);
+        }
+        let now = now();
+        let mut alerts = Vec::new();
+        for (key, block) in latest {
+            let Some(allowance) = self.refresh_or_defer(key, Some(BlockId::from(block)), true).await else {
+                continue;
+            };
+            if allowance.is_active(now) {
+                alerts.push(alert(Permit2Exposure::assess(allowance), "allowance_granted", block));
+            }
+        }
+        alerts
+    }
+
+    /// Повторное чтение отложенных разрешений на блоке `block`
+    async fn refresh_stale(&self, block: u64) -> Vec<BusAlert> {
+        let pending: Vec<(AllowanceKey, bool)> = self.stale.lock().unwrap().drain().collect();
+        let now = now();
+        let mut alerts = Vec::new();
+        for (key, alert_on_grant) in pending {
+            let Some(allowance) = self.refresh_or_defer(key, Some(BlockId::from(block)), alert_on_grant).await else {
+                continue;
+            };
+            if alert_on_grant && allowance.is_active(now) {
+                alerts.push(alert(Permit2Exposure::assess(allowance), "allowance_granted", block));
+            }
+        }
+        alerts
+    }
+
+    /// Напоминания о разрешениях, истекающих в пределах окна; каждое — один раз на срок
+    pub fn expiry_reminders(&self, now: u64, block: u64) -> Vec<BusAlert> {
+        let horizon = now + self.reminder_window.as_secs();
+        let mut reminded = self.reminded.lock().unwrap();
+        let mut alerts = Vec::new();
+        for (key, allowance) in self.allowances.read().unwrap().iter() {
+            if !allowance.is_active(now) || allowance.expiration > horizon {
+                continue;
+            }
+            if reminded.insert((*key, allowance.expiration)) {
+                alerts.push(alert(Permit2Exposure::assess(allowance.clone()), "allowance_expiring", block));
+            }
+        }
+        alerts
+    }
+
+    /// Идёт за `heads`: события Permit2 через индекс, затем отложенные чтения и напоминания
+    pub async fn run(
+        self: Arc<Self>,
+        index: LogIndexer<M, AllowanceKey>,
+        bus: AlertBus,
+        mut heads: watch::Receiver<u64>,
+        mut shutdown: ShutdownSignal,
+    ) {
+        let handler = Permit2Events { monitor: self.clone(), bus: bus.clone() };
+        loop {
+            self.errors.check(index.catch_up(&handler, &shutdown).await);
+            let block = *heads.borrow_and_update();
+            for alert in self.refresh_stale(block).await {
+                bus.publish(alert);
+            }
+            for alert in self.expiry_reminders(now(), block) {
+                bus.publish(alert);
+            }
+            tokio::select! {
+                changed = heads.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+/// Обработчик индекса: алерты о выдаче публикуются сразу, ошибки чтения не останавливают курсор
+struct Permit2Events<M> {
+    monitor: Arc<Permit2Monitor<M>>,
+    bus: AlertBus,
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> IndexHandler<AllowanceKey> for Permit2Events<M> {
+    async fn apply(&self, events: Vec<IndexedEvent<AllowanceKey>>) -> Result<(), String> {
+        for alert in self.monitor.apply_events(&events).await {
+            self.bus.publish(alert);
+        }
+        Ok(())
+    }
+
+    /// Разрешения могли измениться в отменённых блоках: известные перечитываются без алертов
+    async fn revert(&self, _from_block: u64) -> Result<(), String> {
+        let keys: Vec<AllowanceKey> = self.monitor.allowances.read().unwrap().keys().copied().collect();
+        let mut stale = self.monitor.stale.lock().unwrap();
+        for key in keys {
+            stale.entry(key).or_default();
+        }
+        Ok(())
+    }
+}
+
+fn alert(exposure: Permit2Exposure, kind: &str, block: u64) -> BusAlert {
+    let allowance = &exposure.allowance;
+    let (level, title) = match kind {
+        "allowance_expiring" => (AlertLevel::Low, format!("Permit2 allowance of {} for {} expires soon", allowance.spender, allowance.token)),
+        _ => (exposure.level, format!("Permit2 allowance granted to {} for {}", allowance.spender, allowance.token)),
+    };
+    BusAlert::new("permit2", kind, level, allowance.owner.to_string(), title)
+        .with_payload(json!({ "wallet": allowance.owner, "allowance": allowance, "reasons": exposure.reasons, "block": block }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::{JsonRpcError, MockProvider, MockResponse, Provider};
+
+    fn event(key: AllowanceKey, block: u64) -> IndexedEvent<AllowanceKey> {
+        IndexedEvent { block, block_hash: H256::zero(), tx_hash: H256::zero(), log_index: 0, address: Address::zero(), event: key }
+    }
+
+    fn allowance_response(mock: &MockProvider, expiration: u64) {
+        mock.push::<Bytes, _>(Bytes::from((U256::MAX, U256::from(expiration), U256::zero()).encode())).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_other_alerts_and_is_retried() {
+        let (provider, mock) = Provider::mocked();
+        let monitor = Permit2Monitor::new(Arc::new(provider));
+        let (owner, stranger, spender) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb), Address::repeat_byte(0x50));
+        let (first, second) = ((owner, Address::repeat_byte(0x01), spender), (owner, Address::repeat_byte(0x02), spender));
+        monitor.watch(owner);
+
+        let expiration = now() + 10 * 24 * 3600;
+        // Ответы снимаются с конца: первое чтение падает, второе проходит
+        allowance_response(&mock, expiration);
+        mock.push_response(MockResponse::Error(JsonRpcError { code: -32000, message: "header not found".into(), data: None }));
+        let events = [event(first, 5), event(second, 6), event((stranger, Address::repeat_byte(0x01), spender), 6)];
+        let alerts = monitor.apply_events(&events).await;
+
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, "allowance_granted");
+        assert_eq!(monitor.errors().errors, 1);
+        assert_eq!(monitor.allowances(owner).len(), 1);
+
+        allowance_response(&mock, expiration);
+        let retried = monitor.refresh_stale(7).await;
+        assert_eq!(retried.len(), 1);
+        assert_eq!(monitor.report(owner, now()).len(), 2);
+
+        let soon = expiration - 3600;
+        assert_eq!(monitor.expiry_reminders(soon, 8).len(), 2);
+        assert!(monitor.expiry_reminders(soon, 9).is_empty());
+    }
+}
@@ -0,0 +1,574 @@
+use crate::address::ChecksummedAddress;
+use crate::bus::{AlertBus, BusAlert};
+use crate::cancel::{ChainHead, SimulationJob};
+use crate::compat::H256;
+use crate::detector::MevDetector;
+use crate::enrichment::{self, Enricher, Enrichment};
+use crate::shutdown::{ShutdownHook, ShutdownSignal};
+use crate::tx::Tx;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// Политика сброса при отставании детекции от мемпула
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheddingPolicy {
+    /// Транзакции к этим адресам (или от них) не сбрасываются
+    #[serde(default)]
+    pub watched: HashSet<ChecksummedAddress>,
+    /// Заполненность очереди (0.0 - 1.0), с которой включается фильтр по комиссии
+    pub pressure_ratio: f64,
+    /// Под давлением транзакции дешевле этого сбрасываются сразу
+    pub min_fee_under_pressure_gwei: f64,
+    /// Наблюдаемые транзакции могут превысить ёмкость очереди в это число раз
+    pub watched_overcommit: f64,
+}
+
+impl Default for SheddingPolicy {
+    fn default() -> Self {
+        Self {
+            watched: HashSet::new(),
+            pressure_ratio: 0.8,
+            min_fee_under_pressure_gwei: 2.0,
+            watched_overcommit: 2.0,
+        }
+    }
+}
+
+impl SheddingPolicy {
+    fn is_watched(&self, tx: &Tx) -> bool {
+        self.watched.contains(&tx.to) || self.watched.contains(&tx.from)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    pub enrich_capacity: usize,
+    pub detect_capacity: usize,
+    #[serde(default)]
+    pub policy: SheddingPolicy,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            enrich_capacity: 20_000,
+            detect_capacity: 5_000,
+            policy: SheddingPolicy::default(),
+        }
+    }
+}
+
+/// Отметки стадий для одного алерта, мс от UNIX epoch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct LatencyBudget {
+    pub first_seen_ms: u64,
+    #[serde(default)]
+    pub enriched_ms: Option<u64>,
+    pub simulated_ms: u64,
+    pub emitted_ms: u64,
+    /// От первого появления в мемпуле до публикации
+    pub time_to_alert_ms: u64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Последние значения задержки для перцентилей
+pub struct LatencyWindow {
+    capacity: usize,
+    samples: VecDeque<u64>,
+}
+
+impl LatencyWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, ms: u64) {
+        self.samples.push_back(ms);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Перцентиль по ближайшему рангу; `q` из [0, 1]
+    pub fn percentile(&self, q: f64) -> u64 {
+        if self.samples.is_empty() {
+            return 0;
+        }
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (q.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+    }
+}
+
+/// Сколько последних алертов учитывать в перцентилях time_to_alert
+const LATENCY_WINDOW: usize = 10_000;
+
+/// Транзакция в пути между стадиями
+pub struct Envelope {
+    pub tx: Tx,
+    /// Хэш от источника, если известен; ключ дедупликации в детекторе
+    pub hash: Option<H256>,
+    pub received: Instant,
+    /// Время приёма по часам, для отметок в алерте
+    pub first_seen_ms: u64,
+    pub enriched_at: Option<Instant>,
+    /// Источник мемпула, доставивший транзакцию первым
+    pub source: Option<String>,
+    pub enrichment: Option<Enrichment>,
+    /// Блок, при котором транзакция поставлена в очередь; `None` — без отмены
+    pub job: Option<SimulationJob>,
+}
+
+/// Ключ вытеснения: наблюдаемые последними, затем по комиссии, затем по возрасту
+type EvictionKey = (bool, u64, u64);
+
+struct QueueState {
+    /// Порядок поступления — стадии обрабатывают FIFO, чтобы не ломать порядок для фронтрана
+    items: BTreeMap<u64, (EvictionKey, Envelope)>,
+    by_priority: BTreeSet<EvictionKey>,
+    next_seq: u64,
+}
+
+/// Ограниченная очередь стадии, сбрасывающая самые дешёвые транзакции
+pub struct StageQueue {
+    name: &'static str,
+    capacity: usize,
+    policy: Arc<SheddingPolicy>,
+    metrics: Arc<PipelineMetrics>,
+    state: Mutex<QueueState>,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+impl StageQueue {
+    fn new(name: &'static str, capacity: usize, policy: Arc<SheddingPolicy>, metrics: Arc<PipelineMetrics>) -> Self {
+        Self {
+            name,
+            capacity: capacity.max(1),
+            policy,
+            metrics,
+            state: Mutex::new(QueueState {
+                items: BTreeMap::new(),
+                by_priority: BTreeSet::new(),
+                next_seq: 0,
+            }),
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Кладёт транзакцию, при необходимости сбрасывая её или самую дешёвую из очереди.
+    /// Возвращает `false`, если транзакция не принята.
+    pub fn push(&self, envelope: Envelope) -> bool {
+        let watched = self.policy.is_watched(&envelope.tx);
+        let fee = envelope.tx.gas_price.saturating_u64();
+        let mut state = self.state.lock().unwrap();
+        let len = state.items.len();
+
+        if watched {
+            let hard_cap = (self.capacity as f64 * self.policy.watched_overcommit) as usize;
+            if len >= hard_cap.max(self.capacity) {
+                self.metrics.shed_overflow.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        } else {
+            let pressure = len as f64 / self.capacity as f64;
+            if pressure >= self.policy.pressure_ratio
+                && (fee as f64) < self.policy.min_fee_under_pressure_gwei * 1e9
+            {
+                self.metrics.shed_low_fee.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+
+            if len >= self.capacity {
+                let cheapest = state.by_priority.iter().next().copied();
+                match cheapest {
+                    // Вытесняем, только если новая транзакция ценнее самой дешёвой в очереди
+                    Some(key) if !key.0 && key.1 < fee => {
+                        state.by_priority.remove(&key);
+                        state.items.remove(&key.2);
+                        self.metrics.shed_evicted.fetch_add(1, Ordering::Relaxed);
+                    }
+                    _ => {
+                        self.metrics.shed_overflow.fetch_add(1, Ordering::Relaxed);
+                        return false;
+                    }
+                }
+            }
+        }
+
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let key = (watched, fee, seq);
+        state.by_priority.insert(key);
+        state.items.insert(seq, (key, envelope));
+        drop(state);
+
+        self.notify.notify_one();
+        true
+    }
+
+    /// Следующая транзакция по порядку поступления; `None` после закрытия и опустошения
+    pub async fn pop(&self) -> Option<Envelope> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some((_, (key, envelope))) = state.items.pop_first() {
+                    state.by_priority.remove(&key);
+                    return Some(envelope);
+                }
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            notified.await;
+        }
+    }
+
+    /// Перестаёт ждать новых транзакций; оставшиеся дорабатываются
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+}
+
+/// Счётчики конвейера
+pub struct PipelineMetrics {
+    pub ingested: AtomicU64,
+    pub shed_low_fee: AtomicU64,
+    pub shed_evicted: AtomicU64,
+    pub shed_overflow: AtomicU64,
+    /// Транзакции, дошедшие до детекции после продвижения головы цепи: симуляции для них
+    /// не запускаются, но транзакция попадает в пул ожидающих
+    pub cancelled_stale: AtomicU64,
+    pub enriched: AtomicU64,
+    pub detected: AtomicU64,
+    /// Повторные доставки, пропущенные дедупликацией детектора
+    pub deduplicated: AtomicU64,
+    pub alerts: AtomicU64,
+    /// Задержка от приёма до детекции последней транзакции
+    pub last_lag_ms: AtomicU64,
+    pub max_lag_ms: AtomicU64,
+    pub time_to_alert: Mutex<LatencyWindow>,
+}
+
+impl Default for PipelineMetrics {
+    fn default() -> Self {
+        Self {
+            ingested: AtomicU64::new(0),
+            shed_low_fee: AtomicU64::new(0),
+            shed_evicted: AtomicU64::new(0),
+            shed_overflow: AtomicU64::new(0),
+            cancelled_stale: AtomicU64::new(0),
+            enriched: AtomicU64::new(0),
+            detected: AtomicU64::new(0),
+            deduplicated: AtomicU64::new(0),
+            alerts: AtomicU64::new(0),
+            last_lag_ms: AtomicU64::new(0),
+            max_lag_ms: AtomicU64::new(0),
+            time_to_alert: Mutex::new(LatencyWindow::new(LATENCY_WINDOW)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub ingested: u64,
+    pub shed_low_fee: u64,
+    pub shed_evicted: u64,
+    pub shed_overflow: u64,
+    pub cancelled_stale: u64,
+    pub enriched: u64,
+    pub detected: u64,
+    pub deduplicated: u64,
+    pub alerts: u64,
+    pub last_lag_ms: u64,
+    pub max_lag_ms: u64,
+    pub time_to_alert_p50_ms: u64,
+    pub time_to_alert_p90_ms: u64,
+    pub time_to_alert_p99_ms: u64,
+    pub enrich_queue: usize,
+    pub detect_queue: usize,
+}
+
+/// Конвейер ingest -> enrich -> detect с ограниченными очередями между стадиями
+pub struct Pipeline {
+    enrich_queue: StageQueue,
+    detect_queue: StageQueue,
+    metrics: Arc<PipelineMetrics>,
+    head: Option<Arc<ChainHead>>,
+}
+
+impl Pipeline {
+    pub fn new(config: PipelineConfig) -> Self {
+        let policy = Arc::new(config.policy);
+        let metrics = Arc::new(PipelineMetrics::default());
+        Self {
+            enrich_queue: StageQueue::new("enrich", config.enrich_capacity, policy.clone(), metrics.clone()),
+            detect_queue: StageQueue::new("detect", config.detect_capacity, policy, metrics.clone()),
+            metrics,
+            head: None,
+        }
+    }
+
+    /// Помечает транзакции текущим блоком: устаревшие симуляции отменяются
+    /// при продвижении `head`
+    pub fn with_chain_head(mut self, head: Arc<ChainHead>) -> Self {
+        self.head = Some(head);
+        self
+    }
+
+    /// Вход конвейера: вызывается источником мемпула на каждую транзакцию
+    pub fn ingest(&self, tx: Tx) -> bool {
+        self.ingest_from(tx, None)
+    }
+
+    /// Вход с указанием источника; имя попадает в метаданные алертов
+    pub fn ingest_from(&self, tx: Tx, source: Option<&str>) -> bool {
+        self.ingest_hashed(tx, None, source)
+    }
+
+    /// Вход с хэшем транзакции: повторы от других пиров детектор пропускает по нему
+    pub fn ingest_hashed(&self, tx: Tx, hash: Option<H256>, source: Option<&str>) -> bool {
+        self.metrics.ingested.fetch_add(1, Ordering::Relaxed);
+        self.enrich_queue.push(Envelope {
+            tx,
+            hash,
+            received: Instant::now(),
+            first_seen_ms: now_ms(),
+            enriched_at: None,
+            source: source.map(str::to_string),
+            enrichment: None,
+            job: self.head.as_ref().map(ChainHead::job),
+        })
+    }
+
+    /// Стадия обогащения; может работать в отдельной задаче
+    pub async fn run_enrich(&self, enrichers: &[Box<dyn Enricher>]) {
+        while let Some(mut envelope) = self.enrich_queue.pop().await {
+            envelope.enrichment = Some(enrichment::enrich(enrichers, &envelope.tx));
+            envelope.enriched_at = Some(Instant::now());
+            self.metrics.enriched.fetch_add(1, Ordering::Relaxed);
+            self.detect_queue.push(envelope);
+        }
+        self.detect_queue.close();
+    }
+
+    /// Стадия детекции. Анализ блокирует поток на симуляции,
+    /// поэтому стадию запускают вне воркеров рантайма (`spawn_blocking` + `Handle::block_on`)
+    pub async fn run_detect(&self, detector: &mut MevDetector, bus: &AlertBus) {
+        while let Some(envelope) = self.detect_queue.pop().await {
+            let lag = envelope.received.elapsed().as_millis() as u64;
+            self.metrics.last_lag_ms.store(lag, Ordering::Relaxed);
+            self.metrics.max_lag_ms.fetch_max(lag, Ordering::Relaxed);
+
+            let offset = |at: Instant| envelope.first_seen_ms + at.duration_since(envelope.received).as_millis() as u64;
+            let enriched_ms = envelope.enriched_at.map(offset);
+            let mut alerts = match &envelope.job {
+                Some(job) => detector.analyze_job(envelope.tx, envelope.hash, envelope.enrichment, job),
+                None => detector.analyze_hashed(envelope.tx, envelope.hash, envelope.enrichment),
+            };
+            // Симуляция выполняется внутри детектора, поэтому её отметка — конец анализа
+            let simulated_ms = offset(Instant::now());
+            self.metrics.detected.fetch_add(1, Ordering::Relaxed);
+            if envelope.job.as_ref().is_some_and(SimulationJob::is_cancelled) {
+                self.metrics.cancelled_stale.fetch_add(1, Ordering::Relaxed);
+            }
+            self.metrics.deduplicated.store(detector.deduplicated(), Ordering::Relaxed);
+            self.metrics.alerts.fetch_add(alerts.len() as u64, Ordering::Relaxed);
+
+            for alert in &mut alerts {
+                if let Some(source) = &envelope.source {
+                    if let Some(metadata) = alert.metadata.as_object_mut() {
+                        metadata.insert("ingest_source".into(), source.clone().into());
+                    }
+                }
+                let emitted_ms = offset(Instant::now());
+                let time_to_alert_ms = emitted_ms - envelope.first_seen_ms;
+                alert.latency = Some(LatencyBudget {
+                    first_seen_ms: envelope.first_seen_ms,
+                    enriched_ms,
+                    simulated_ms,
+                    emitted_ms,
+                    time_to_alert_ms,
+                });
+                self.metrics.time_to_alert.lock().unwrap().record(time_to_alert_ms);
+                bus.publish(BusAlert::from(&*alert));
+            }
+        }
+    }
+
+    /// Прекращает приём; стадии дорабатывают очереди и завершаются
+    pub fn close(&self) {
+        self.enrich_queue.close();
+    }
+
+    /// Закрывает конвейер по сигналу остановки
+    pub async fn close_on(&self, mut shutdown: ShutdownSignal) {
+        shutdown.wait().await;
+        self.close();
+    }
+
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let m = &self.metrics;
+        let time_to_alert = m.time_to_alert.lock().unwrap();
+        MetricsSnapshot {
+            ingested: m.ingested.load(Ordering::Relaxed),
+            shed_low_fee: m.shed_low_fee.load(Ordering::Relaxed),
+            shed_evicted: m.shed_evicted.load(Ordering::Relaxed),
+            shed_overflow: m.shed_overflow.load(Ordering::Relaxed),
+            cancelled_stale: m.cancelled_stale.load(Ordering::Relaxed),
+            enriched: m.enriched.load(Ordering::Relaxed),
+            detected: m.detected.load(Ordering::Relaxed),
+            deduplicated: m.deduplicated.load(Ordering::Relaxed),
+            alerts: m.alerts.load(Ordering::Relaxed),
+            last_lag_ms: m.last_lag_ms.load(Ordering::Relaxed),
+            max_lag_ms: m.max_lag_ms.load(Ordering::Relaxed),
+            time_to_alert_p50_ms: time_to_alert.percentile(0.5),
+            time_to_alert_p90_ms: time_to_alert.percentile(0.9),
+            time_to_alert_p99_ms: time_to_alert.percentile(0.99),
+            enrich_queue: self.enrich_queue.len(),
+            detect_queue: self.detect_queue.len(),
+        }
+    }
+}
+
+/// Фаза StopIngestion: закрывает вход конвейера
+pub struct IngestionStop {
+    pub pipeline: Arc<Pipeline>,
+}
+
+#[async_trait]
+impl ShutdownHook for IngestionStop {
+    fn name(&self) -> &str {
+        "ingestion"
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        self.pipeline.close();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::WeiAmount;
+
+    fn addr(byte: u8) -> ChecksummedAddress {
+        crate::compat::Address::repeat_byte(byte).into()
+    }
+
+    fn tx(to: ChecksummedAddress, gas_price_gwei: f64) -> Tx {
+        Tx {
+            from: addr(0xee),
+            to,
+            value: WeiAmount::ZERO,
+            gas_price: WeiAmount::from_gwei(gas_price_gwei),
+            input: vec![],
+        }
+    }
+
+    fn envelope(tx: Tx) -> Envelope {
+        Envelope {
+            tx,
+            hash: None,
+            received: Instant::now(),
+            first_seen_ms: 0,
+            enriched_at: None,
+            source: None,
+            enrichment: None,
+            job: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sheds_cheapest_and_keeps_watched() {
+        let policy = SheddingPolicy {
+            watched: HashSet::from([addr(0xff)]),
+            pressure_ratio: 1.0,
+            min_fee_under_pressure_gwei: 0.0,
+            ..SheddingPolicy::default()
+        };
+        let metrics = Arc::new(PipelineMetrics::default());
+        let queue = StageQueue::new("test", 2, Arc::new(policy), metrics.clone());
+
+        assert!(queue.push(envelope(tx(addr(0xa), 10.0))));
+        assert!(queue.push(envelope(tx(addr(0xb), 5.0))));
+        // Дороже самой дешёвой — вытесняет её
+        assert!(queue.push(envelope(tx(addr(0xc), 20.0))));
+        // Дешевле всех — не принимается
+        assert!(!queue.push(envelope(tx(addr(0xd), 1.0))));
+        // Наблюдаемый адрес принимается сверх ёмкости
+        assert!(queue.push(envelope(tx(addr(0xff), 0.1))));
+
+        let order: Vec<ChecksummedAddress> = [queue.pop().await, queue.pop().await, queue.pop().await]
+            .into_iter()
+            .map(|e| e.unwrap().tx.to)
+            .collect();
+        assert_eq!(order, [addr(0xa), addr(0xc), addr(0xff)]);
+        assert_eq!(metrics.shed_evicted.load(Ordering::Relaxed), 1);
+        assert_eq!(metrics.shed_overflow.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_jobs_stale_after_head_advances() {
+        let metrics = Arc::new(PipelineMetrics::default());
+        let queue = StageQueue::new("detect", 10, Arc::new(SheddingPolicy::default()), metrics.clone());
+        let head = ChainHead::new(100, 0);
+
+        let stale = Envelope { job: Some(head.job()), ..envelope(tx(addr(0xa), 10.0)) };
+        assert!(queue.push(stale));
+        head.advance(101);
+        let fresh = Envelope { job: Some(head.job()), ..envelope(tx(addr(0xb), 10.0)) };
+        assert!(queue.push(fresh));
+
+        // Устаревшая транзакция всё равно доходит до детектора, только с отменённой меткой
+        let first = queue.pop().await.unwrap();
+        assert_eq!(first.tx.to, addr(0xa));
+        assert!(first.job.unwrap().is_cancelled());
+        assert!(!queue.pop().await.unwrap().job.unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut window = LatencyWindow::new(100);
+        for ms in 1..=200 {
+            window.record(ms);
+        }
+        // Остаются последние 100 значений: 101..=200
+        assert_eq!(window.percentile(0.5), 150);
+        assert_eq!(window.percentile(0.99), 199);
+        assert_eq!(window.percentile(0.0), 101);
+        assert_eq!(LatencyWindow::new(10).percentile(0.5), 0);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::compat::{Address, U256};
+use crate::store::{SharedStore, StoreError, StoreExt};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use ethers::types::Signature;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Потраченное за сутки по кошелькам; переживает рестарт, иначе лимит обнуляется перезапуском
+const POLICY_NS: &str = "policy_spend";
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Error)]
+pub enum PolicyError {
+    #[error("No signing policy configured for wallet {0:?}")]
+    UnknownWallet(Address),
+
+    #[error("Invalid value: {0} ETH")]
+    InvalidValue(f64),
+
+    #[error("Destination {0:?} is not in the wallet allowlist")]
+    DestinationNotAllowed(Address),
+
+    #[error("Daily cap exceeded: {spent_eth} of {cap_eth} ETH spent today, {requested_eth} ETH requested")]
+    DailyCapExceeded { cap_eth: f64, spent_eth: f64, requested_eth: f64 },
+
+    #[error("{required} confirmation(s) required above {threshold_eth} ETH, got {got}")]
+    ConfirmationsRequired { threshold_eth: f64, required: usize, got: usize },
+
+    #[error("Dry run is required before signing")]
+    DryRunRequired,
+
+    #[error("Dry run failed: {0}")]
+    DryRunFailed(String),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+}
+
+/// Политика одного кошелька
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletPolicy {
+    pub wallet: Address,
+    /// Разрешённые адресаты; пустой список запрещает любые отправки
+    pub allowed_destinations: Vec<Address>,
+    pub daily_cap_eth: f64,
+    /// Сумма, начиная с которой нужны подтверждения операторов
+    #[serde(default)]
+    pub confirm_above_eth: Option<f64>,
+    /// Адреса операторов, чьи подписи засчитываются как подтверждения
+    #[serde(default)]
+    pub approvers: Vec<Address>,
+    #[serde(default = "default_confirmations")]
+    pub required_confirmations: usize,
+    #[serde(default = "default_require_dry_run")]
+    pub require_dry_run: bool,
+}
+
+fn default_confirmations() -> usize {
+    1
+}
+
+fn default_require_dry_run() -> bool {
+    true
+}
+
+/// Результат `eth_call` перед подписью
+#[derive(Debug, Clone)]
+pub enum DryRun {
+    Succeeded,
+    Reverted(String),
+}
+
+/// Подпись оператора (EIP-191) над `SigningRequest::confirmation_message`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Confirmation {
+    pub approver: Address,
+    pub signature: Signature,
+}
+
+/// Запрос на подпись, проверяемый политикой
+#[derive(Debug, Clone)]
+pub struct SigningRequest {
+    pub wallet: Address,
+    pub to: Address,
+    pub value_eth: f64,
+    /// Nonce транзакции: подтверждение одной отправки не годится для следующей
+    pub nonce: U256,
+    pub dry_run: Option<DryRun>,
+    pub confirmations: Vec<Confirmation>,
+}
+
+impl SigningRequest {
+    /// Текст, который подписывает оператор, подтверждая именно эту транзакцию
+    pub fn confirmation_message(&self) -> String {
+        format!(
+            "DeFinetly signing confirmation\nwallet: {:?}\nto: {:?}\nvalue: {} ETH\nnonce: {}",
+            self.wallet, self.to, self.value_eth, self.nonce
+        )
+    }
+
+    /// Разные операторы из списка политики с верной подписью
+    fn confirmed_by(&self, approvers: &[Address]) -> HashSet<Address> {
+        let message = self.confirmation_message();
+        self.confirmations
+            .iter()
+            .filter(|c| approvers.contains(&c.approver))
+            .filter(|c| c.signature.verify(message.as_str(), c.approver).is_ok())
+            .map(|c| c.approver)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DailySpend {
+    day: u64,
+    spent_eth: f64,
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        / SECONDS_PER_DAY
+}
+
+/// Проверка перед любой подписью; вызывающий обязан не подписывать при ошибке
+pub struct PolicyEngine {
+    policies: HashMap<Address, WalletPolicy>,
+    store: SharedStore,
+    /// Держится на время проверки и резервирования, чтобы параллельные запросы не обошли лимит
+    spend_lock: Mutex<()>,
+}
+
+impl PolicyEngine {
+    pub fn new(policies: Vec<WalletPolicy>, store: SharedStore) -> Self {
+        Self {
+            policies: policies.into_iter().map(|p| (p.wallet, p)).collect(),
+            store,
+            spend_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn policy(&self, wallet: Address) -> Option<&WalletPolicy> {
+        self.policies.get(&wallet)
+    }
+
+    fn spend_key(wallet: Address) -> String {
+        format!("{:?}", wallet)
+    }
+
+    fn spent_today(&self, wallet: Address, day: u64) -> Result<f64, PolicyError> {
+        let spend: Option<DailySpend> = self.store.get_json(POLICY_NS, &Self::spend_key(wallet))?;
+        Ok(spend.filter(|s| s.day == day).map_or(0.0, |s| s.spent_eth))
+    }
+
+    /// Проверяет запрос и резервирует сумму в дневном лимите
+    pub fn authorize(&self, request: &SigningRequest) -> Result<(), PolicyError> {
+        let policy = self
+            .policies
+            .get(&request.wallet)
+            .ok_or(PolicyError::UnknownWallet(request.wallet))?;
+        // Отрицательная сумма уменьшила бы потраченное за день
+        if !request.value_eth.is_finite() || request.value_eth < 0.0 {
+            return Err(PolicyError::InvalidValue(request.value_eth));
+        }
+
+        if !policy.allowed_destinations.contains(&request.to) {
+            return Err(PolicyError::DestinationNotAllowed(request.to));
+        }
+
+        if let Some(threshold) = policy.confirm_above_eth {
+            if request.value_eth > threshold {
+                let got = request.confirmed_by(&policy.approvers).len();
+                if got < policy.required_confirmations {
+                    return Err(PolicyError::ConfirmationsRequired {
+                        threshold_eth: threshold,
+                        required: policy.required_confirmations,
+                        got,
+                    });
+                }
+            }
+        }
+
+        if policy.require_dry_run {
+            match &request.dry_run {
+                Some(DryRun::Succeeded) => {}
+                Some(DryRun::Reverted(reason)) => return Err(PolicyError::DryRunFailed(reason.clone())),
+                None => return Err(PolicyError::DryRunRequired),
+            }
+        }
+
+        let _guard = self.spend_lock.lock().unwrap();
+        let day = today();
+        let spent = self.spent_today(request.wallet, day)?;
+        if spent + request.value_eth > policy.daily_cap_eth {
+            return Err(PolicyError::DailyCapExceeded {
+                cap_eth: policy.daily_cap_eth,
+                spent_eth: spent,
+                requested_eth: request.value_eth,
+            });
+        }
+        self.store.put_json(
+            POLICY_NS,
+            &Self::spend_key(request.wallet),
+            &DailySpend { day, spent_eth: spent + request.value_eth },
+        )?;
+        Ok(())
+    }
+
+    /// Возвращает резерв, если транзакция так и не была отправлена
+    pub fn refund(&self, request: &SigningRequest) -> Result<(), PolicyError> {
+        if !request.value_eth.is_finite() || request.value_eth < 0.0 {
+            return Err(PolicyError::InvalidValue(request.value_eth));
+        }
+        let _guard = self.spend_lock.lock().unwrap();
+        let day = today();
+        let spent = self.spent_today(request.wallet, day)?;
+        self.store.put_json(
+            POLICY_NS,
+            &Self::spend_key(request.wallet),
+            &DailySpend { day, spent_eth: (spent - request.value_eth).max(0.0) },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_policy_checks() {
+        let wallet = Address::repeat_byte(1);
+        let eigen = Address::repeat_byte(2);
+        let operator = |byte: u8| LocalWallet::from_bytes(&[byte; 32]).unwrap();
+        let (alice, bob, mallory) = (operator(1), operator(2), operator(3));
+        let engine = PolicyEngine::new(
+            vec![WalletPolicy {
+                wallet,
+                allowed_destinations: vec![eigen],
+                daily_cap_eth: 40.0,
+                confirm_above_eth: Some(10.0),
+                approvers: vec![alice.address(), bob.address()],
+                required_confirmations: 2,
+                require_dry_run: true,
+            }],
+            Arc::new(MemoryStore::new()),
+        );
+        let request = |to, value_eth, nonce: u64, approvers: &[&LocalWallet]| {
+            let mut request = SigningRequest {
+                wallet,
+                to,
+                value_eth,
+                nonce: U256::from(nonce),
+                dry_run: Some(DryRun::Succeeded),
+                confirmations: Vec::new(),
+            };
+            let digest = hash_message(request.confirmation_message());
+            request.confirmations = approvers
+                .iter()
+                .map(|a| Confirmation { approver: a.address(), signature: a.sign_hash(digest).unwrap() })
+                .collect();
+            request
+        };
+
+        assert!(matches!(
+            engine.authorize(&request(Address::repeat_byte(9), 1.0, 0, &[])),
+            Err(PolicyError::DestinationNotAllowed(_))
+        ));
+        assert!(matches!(engine.authorize(&request(eigen, -5.0, 0, &[])), Err(PolicyError::InvalidValue(_))));
+        assert!(matches!(
+            engine.authorize(&request(eigen, 20.0, 0, &[&alice, &alice, &mallory])),
+            Err(PolicyError::ConfirmationsRequired { got: 1, .. })
+        ));
+        // Подпись постороннего под именем оператора и подтверждение другой транзакции не засчитываются
+        let mut forged = request(eigen, 20.0, 0, &[&alice, &mallory]);
+        forged.confirmations[1].approver = bob.address();
+        assert!(matches!(engine.authorize(&forged), Err(PolicyError::ConfirmationsRequired { got: 1, .. })));
+        let replayed = SigningRequest { nonce: U256::from(1), ..request(eigen, 20.0, 0, &[&alice, &bob]) };
+        assert!(matches!(engine.authorize(&replayed), Err(PolicyError::ConfirmationsRequired { got: 0, .. })));
+        assert!(matches!(
+            engine.authorize(&SigningRequest { dry_run: None, ..request(eigen, 1.0, 0, &[]) }),
+            Err(PolicyError::DryRunRequired)
+        ));
+
+        engine.authorize(&request(eigen, 20.0, 0, &[&alice, &bob])).unwrap();
+        let second = request(eigen, 15.0, 1, &[&alice, &bob]);
+        engine.authorize(&second).unwrap();
+        assert!(matches!(
+            engine.authorize(&request(eigen, 6.0, 2, &[])),
+            Err(PolicyError::DailyCapExceeded { .. })
+        ));
+
+        engine.refund(&second).unwrap();
+        engine.authorize(&request(eigen, 6.0, 2, &[])).unwrap();
+    }
+}
@@ -0,0 +1,432 @@
+use crate::bus::AlertLevel;
+use crate::compat::Address;
+use crate::secrets::SecretString;
+use crate::sink::{Message, Sink, SinkError};
+use crate::store::{Store, StoreError, StoreExt};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use async_trait::async_trait;
+use ethers::types::Signature;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::sync::Arc;
+use thiserror::Error;
+use web_push::{ContentEncoding, SubscriptionInfo, VapidSignatureBuilder, WebPushMessageBuilder, URL_SAFE_NO_PAD};
+
+/// Пространство имён хранилища: подписки кошельков на уведомления
+pub const WALLET_SUBSCRIPTIONS_NS: &str = "wallet_subscriptions";
+
+/// Сколько push-сервис хранит уведомление для выключенного устройства
+const PUSH_TTL_SECONDS: u32 = 24 * 3600;
+
+/// Лимит полезной нагрузки Web Push — 4 КБ вместе с шифрованием
+const MAX_PUSH_BODY: usize = 3000;
+
+/// Куда пользователь согласился получать алерты о своём кошельке
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case", tag = "channel")]
+pub enum WalletSubscription {
+    /// Сообщение XMTP на адрес самого кошелька; у кошелька должна быть личность XMTP
+    Xmtp,
+    /// Подписка браузера из `PushManager.subscribe()`
+    WebPush { endpoint: String, p256dh: String, auth: String },
+}
+
+/// Насколько подпись владения может отставать от часов узла или опережать их
+const PROOF_WINDOW_SECONDS: u64 = 600;
+
+#[derive(Debug, Error)]
+pub enum SubscriptionError {
+    #[error("Invalid wallet address: {0}")]
+    InvalidWallet(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Signature is from {0:?}, not the subscribed wallet")]
+    NotOwner(Address),
+
+    #[error("Signature issued at {0} is outside the accepted window")]
+    Stale(u64),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+}
+
+/// Что делает владелец кошелька с подпиской
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum SubscriptionAction {
+    Subscribe,
+    Unsubscribe,
+}
+
+/// Текст, который кошелёк подписывает через `personal_sign`. Подпись привязана к действию,
+/// каналу и моменту, поэтому её нельзя переиспользовать для чужой подписки
+pub fn ownership_message(
+    wallet: &str,
+    action: SubscriptionAction,
+    subscription: &WalletSubscription,
+    issued_at: u64,
+) -> String {
+    let action = match action {
+        SubscriptionAction::Subscribe => "subscribe",
+        SubscriptionAction::Unsubscribe => "unsubscribe",
+    };
+    let channel = serde_json::to_string(subscription).unwrap_or_default();
+    format!(
+        "DeFinetly wallet alerts\nwallet: {}\naction: {}\nchannel: {}\nissued at: {}",
+        wallet.to_lowercase(),
+        action,
+        channel,
+        issued_at
+    )
+}
+
+/// Проверяет, что `signature` над `ownership_message` поставил сам `wallet`
+pub fn verify_ownership(
+    wallet: &str,
+    action: SubscriptionAction,
+    subscription: &WalletSubscription,
+    issued_at: u64,
+    signature: &str,
+    now: u64,
+) -> Result<(), SubscriptionError> {
+    if issued_at.abs_diff(now) > PROOF_WINDOW_SECONDS {
+        return Err(SubscriptionError::Stale(issued_at));
+    }
+    let owner: Address = wallet.parse().map_err(|_| SubscriptionError::InvalidWallet(wallet.to_string()))?;
+    let signature: Signature = signature.parse().map_err(|e| SubscriptionError::InvalidSignature(format!("{}", e)))?;
+    let signer = signature
+        .recover(ownership_message(wallet, action, subscription, issued_at))
+        .map_err(|e| SubscriptionError::InvalidSignature(e.to_string()))?;
+    if signer != owner {
+        return Err(SubscriptionError::NotOwner(signer));
+    }
+    Ok(())
+}
+
+/// Подписки по кошелькам в хранилище; ключ — адрес в нижнем регистре
+pub struct WalletSubscriptions {
+    store: Arc<dyn Store>,
+}
+
+impl WalletSubscriptions {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self { store }
+    }
+
+    pub fn list(&self, wallet: &str) -> Result<Vec<WalletSubscription>, StoreError> {
+        Ok(self.store.get_json(WALLET_SUBSCRIPTIONS_NS, &wallet.to_lowercase())?.unwrap_or_default())
+    }
+
+    pub fn subscribe(&self, wallet: &str, subscription: WalletSubscription) -> Result<(), StoreError> {
+        let mut subscriptions = self.list(wallet)?;
+        if !subscriptions.contains(&subscription) {
+            subscriptions.push(subscription);
+        }
+        self.store.put_json(WALLET_SUBSCRIPTIONS_NS, &wallet.to_lowercase(), &subscriptions)
+    }
+
+    pub fn unsubscribe(&self, wallet: &str, subscription: &WalletSubscription) -> Result<(), StoreError> {
+        let mut subscriptions = self.list(wallet)?;
+        subscriptions.retain(|s| s != subscription);
+        if subscriptions.is_empty() {
+            self.store.delete(WALLET_SUBSCRIPTIONS_NS, &wallet.to_lowercase())
+        } else {
+            self.store.put_json(WALLET_SUBSCRIPTIONS_NS, &wallet.to_lowercase(), &subscriptions)
+        }
+    }
+}
+
+/// Шлюз XMTP: сервис с ключом отправителя, принимающий `POST {to, text}`.
+/// Своего клиента XMTP у крейта нет
+struct XmtpGateway {
+    url: String,
+    token: Option<SecretString>,
+}
+
+struct WebPushKeys {
+    /// Закрытый ключ VAPID в base64url
+    private_key: SecretString,
+    /// `mailto:` или URL владельца, которым push-сервис свяжется при проблемах
+    subject: String,
+}
+
+/// Алерты пользователям, а не дежурным: жертвам MEV и владельцам рискованных разрешений.
+/// Адресат — `Message::wallet`; сообщения без кошелька или без подписок пропускаются
+pub struct WalletSink {
+    name: String,
+    subscriptions: WalletSubscriptions,
+    xmtp: Option<XmtpGateway>,
+    web_push: Option<WebPushKeys>,
+    client: reqwest::Client,
+    errors: TaskErrors,
+}
+
+impl WalletSink {
+    pub fn new(name: &str, subscriptions: WalletSubscriptions) -> Self {
+        Self {
+            name: name.to_string(),
+            subscriptions,
+            xmtp: None,
+            web_push: None,
+            client: reqwest::Client::new(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Отказы отдельных подписок, когда другие подписки того же кошелька получили алерт
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn with_xmtp(mut self, gateway_url: &str, token: Option<SecretString>) -> Self {
+        self.xmtp = Some(XmtpGateway { url: gateway_url.to_string(), token });
+        self
+    }
+
+    pub fn with_web_push(mut self, vapid_private_key: SecretString, subject: &str) -> Self {
+        self.web_push = Some(WebPushKeys { private_key: vapid_private_key, subject: subject.to_string() });
+        self
+    }
+
+    async fn send_xmtp(&self, gateway: &XmtpGateway, wallet: &str, message: &Message) -> Result<(), SinkError> {
+        let mut request = self.client.post(&gateway.url).json(&json!({
+            "to": wallet,
+            "text": format!("{}\n\n{}", message.subject, message.markdown),
+        }));
+        if let Some(token) = &gateway.token {
+            request = request.bearer_auth(token.expose());
+        }
+        let response = request.send().await.map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(SinkError::DeliveryFailed(format!("XMTP gateway returned {}", response.status())));
+        }
+        Ok(())
+    }
+
+    /// `Ok(false)` — подписка больше не действует (404/410), её надо удалить
+    async fn send_web_push(
+        &self,
+        keys: &WebPushKeys,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+        message: &Message,
+    ) -> Result<bool, SinkError> {
+        let push_err = |e: web_push::WebPushError| SinkError::Rejected(format!("web push: {:?}", e));
+        let info = SubscriptionInfo::new(endpoint, p256dh, auth);
+        let mut signature =
+            VapidSignatureBuilder::from_base64(keys.private_key.expose(), URL_SAFE_NO_PAD, &info).map_err(push_err)?;
+        signature.add_claim("sub", keys.subject.as_str());
+
+        let body: String = message.markdown.chars().take(MAX_PUSH_BODY).collect();
+        let payload =
+            json!({ "title": message.subject, "body": body, "level": message.level, "alert_id": message.alert_id }).to_string();
+        let mut builder = WebPushMessageBuilder::new(&info).map_err(push_err)?;
+        builder.set_payload(ContentEncoding::Aes128Gcm, payload.as_bytes());
+        builder.set_vapid_signature(signature.build().map_err(push_err)?);
+        builder.set_ttl(PUSH_TTL_SECONDS);
+        let push = builder.build().map_err(push_err)?;
+
+        let urgency = match message.level {
+            AlertLevel::Critical | AlertLevel::High => "high",
+            AlertLevel::Medium => "normal",
+            AlertLevel::Low | AlertLevel::Info => "low",
+        };
+        let mut request = self.client.post(endpoint).header("TTL", push.ttl.to_string()).header("Urgency", urgency);
+        if let Some(payload) = push.payload {
+            request = request.header("Content-Encoding", payload.content_encoding.to_str());
+            for (name, value) in payload.crypto_headers {
+                request = request.header(name, value);
+            }
+            request = request.body(payload.content);
+        }
+        let response = request.send().await.map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        match response.status().as_u16() {
+            404 | 410 => Ok(false),
+            _ if response.status().is_success() => Ok(true),
+            status => Err(SinkError::DeliveryFailed(format!("push service returned {}", status))),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for WalletSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError> {
+        let Some(wallet) = &message.wallet else {
+            return Ok(());
+        };
+        let subscriptions = self.subscriptions.list(wallet).map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        let mut errors = Vec::new();
+        for subscription in &subscriptions {
+            let result = match (subscription, &self.xmtp, &self.web_push) {
+                (WalletSubscription::Xmtp, Some(gateway), _) => self.send_xmtp(gateway, wallet, message).await,
+                (WalletSubscription::WebPush { endpoint, p256dh, auth }, _, Some(keys)) => {
+                    match self.send_web_push(keys, endpoint, p256dh, auth, message).await {
+                        Ok(true) => Ok(()),
+                        Ok(false) => self
+                            .subscriptions
+                            .unsubscribe(wallet, subscription)
+                            .map_err(|e| SinkError::DeliveryFailed(e.to_string())),
+                        Err(e) => Err(e),
+                    }
+                }
+                // Канал не настроен в этом синке
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                errors.push(e.to_string());
+            }
+        }
+        if !errors.is_empty() && errors.len() == subscriptions.len() {
+            return Err(SinkError::DeliveryFailed(errors.join("; ")));
+        }
+        for e in errors {
+            self.errors.record(format!("wallet notification for {} partially failed: {}", wallet, e));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "server")]
+pub use api::{router, SubscriptionRequest, SubscriptionsResponse, WalletApi};
+
+#[cfg(feature = "server")]
+mod api {
+    use super::{verify_ownership, SubscriptionAction, SubscriptionError, WalletSubscription, WalletSubscriptions};
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde::{Serialize, Deserialize};
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Запрос владельца кошелька; `signature` — `personal_sign` над `ownership_message`
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    #[serde(deny_unknown_fields)]
+    pub struct SubscriptionRequest {
+        pub wallet: String,
+        pub action: SubscriptionAction,
+        pub subscription: WalletSubscription,
+        pub issued_at: u64,
+        pub signature: String,
+    }
+
+    /// Подписки кошелька после изменения
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct SubscriptionsResponse {
+        /// В нижнем регистре
+        pub wallet: String,
+        pub subscriptions: Vec<WalletSubscription>,
+    }
+
+    impl IntoResponse for SubscriptionError {
+        fn into_response(self) -> Response {
+            let status = match self {
+                SubscriptionError::InvalidWallet(_) | SubscriptionError::InvalidSignature(_) => StatusCode::BAD_REQUEST,
+                SubscriptionError::NotOwner(_) | SubscriptionError::Stale(_) => StatusCode::UNAUTHORIZED,
+                SubscriptionError::StoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(json!({ "error": self.to_string() }))).into_response()
+        }
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/wallets/subscriptions",
+        tag = "wallets",
+        request_body = SubscriptionRequest,
+        responses(
+            (status = 200, description = "Current subscriptions of the wallet", body = SubscriptionsResponse),
+            (status = 400, description = "Malformed wallet or signature"),
+            (status = 401, description = "Signature is stale or not from the wallet"),
+        )
+    )]
+    async fn update_subscription(
+        State(subscriptions): State<Arc<WalletSubscriptions>>,
+        Json(request): Json<SubscriptionRequest>,
+    ) -> Response {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let result = verify_ownership(
+            &request.wallet,
+            request.action,
+            &request.subscription,
+            request.issued_at,
+            &request.signature,
+            now,
+        )
+        .and_then(|()| {
+            match request.action {
+                SubscriptionAction::Subscribe => subscriptions.subscribe(&request.wallet, request.subscription)?,
+                SubscriptionAction::Unsubscribe => subscriptions.unsubscribe(&request.wallet, &request.subscription)?,
+            }
+            Ok(subscriptions.list(&request.wallet)?)
+        });
+        match result {
+            Ok(subscriptions) => Json(SubscriptionsResponse { wallet: request.wallet.to_lowercase(), subscriptions }).into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+
+    /// Спецификация подписок кошельков; объединяется с `openapi::merged`
+    #[derive(utoipa::OpenApi)]
+    #[openapi(
+        paths(update_subscription),
+        components(schemas(SubscriptionRequest, SubscriptionsResponse, SubscriptionAction, WalletSubscription)),
+        tags((name = "wallets", description = "Alert subscriptions of wallet owners, authorized by a wallet signature"))
+    )]
+    pub struct WalletApi;
+
+    /// Публичный API: доступ даёт подпись кошелька, а не токен оператора
+    pub fn router(subscriptions: Arc<WalletSubscriptions>) -> Router {
+        Router::new().route("/wallets/subscriptions", post(update_subscription)).with_state(subscriptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+
+    #[test]
+    fn test_ownership_proof_binds_wallet_action_and_channel() {
+        let wallet: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let address = format!("{:?}", wallet.address());
+        let push = WalletSubscription::WebPush { endpoint: "https://push.example/1".into(), p256dh: "k".into(), auth: "a".into() };
+        let sign = |action, subscription: &WalletSubscription, at| {
+            let message = ownership_message(&address, action, subscription, at);
+            wallet.sign_hash(hash_message(message)).unwrap().to_string()
+        };
+
+        let signature = sign(SubscriptionAction::Subscribe, &push, 1_000);
+        let checksummed = crate::compat::to_checksum(&wallet.address());
+        assert!(verify_ownership(&checksummed, SubscriptionAction::Subscribe, &push, 1_000, &signature, 1_100).is_ok());
+        assert!(matches!(
+            verify_ownership(&address, SubscriptionAction::Subscribe, &push, 1_000, &signature, 2_000),
+            Err(SubscriptionError::Stale(1_000))
+        ));
+        assert!(verify_ownership(&address, SubscriptionAction::Unsubscribe, &push, 1_000, &signature, 1_000).is_err());
+        assert!(verify_ownership(&address, SubscriptionAction::Subscribe, &WalletSubscription::Xmtp, 1_000, &signature, 1_000).is_err());
+        let other = "0x1111111111111111111111111111111111111111";
+        assert!(verify_ownership(other, SubscriptionAction::Subscribe, &push, 1_000, &signature, 1_000).is_err());
+
+        let subscriptions = WalletSubscriptions::new(Arc::new(MemoryStore::new()));
+        subscriptions.subscribe(&checksummed, push.clone()).unwrap();
+        subscriptions.subscribe(&address, push.clone()).unwrap();
+        assert_eq!(subscriptions.list(&address).unwrap(), vec![push.clone()]);
+        subscriptions.unsubscribe(&checksummed, &push).unwrap();
+        assert!(subscriptions.list(&address).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::amount::WeiAmount;
+use crate::detector::{MevDetector, MevThresholds};
+use crate::ffi;
+use crate::simulator::CppSimulatorThread;
+use crate::tx::Tx;
+use crate::units::Gwei;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Детектор для Python: транзакции и алерты передаются как JSON
+#[pyclass(unsendable, name = "MevDetector")]
+pub struct PyMevDetector {
+    inner: MevDetector,
+}
+
+#[pymethods]
+impl PyMevDetector {
+    #[new]
+    fn new(ttl_seconds: u64, min_profit_eth: f64, max_gas_price_gwei: f64) -> PyResult<Self> {
+        let thresholds = MevThresholds {
+            min_profit: WeiAmount::from_eth(min_profit_eth),
+            max_gas_price: Gwei::new(max_gas_price_gwei).map_err(|e| PyValueError::new_err(e.to_string()))?,
+        };
+        Ok(Self {
+            inner: MevDetector::new(CppSimulatorThread::spawn(ffi::new_simulator), ttl_seconds, thresholds),
+        })
+    }
+
+    /// `tx_json` — объект `Tx`; возвращает алерты в JSON
+    fn analyze(&mut self, tx_json: &str) -> PyResult<Vec<String>> {
+        let tx: Tx = serde_json::from_str(tx_json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.inner
+            .analyze(tx)
+            .iter()
+            .map(|alert| serde_json::to_string(alert).map_err(|e| PyValueError::new_err(e.to_string())))
+            .collect()
+    }
+}
+
+#[pymodule]
+fn mevdetector(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyMevDetector>()?;
+    Ok(())
+}
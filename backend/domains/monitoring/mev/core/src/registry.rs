@@ -0,0 +1,251 @@
+use crate::amount::WeiAmount;
+use crate::detector::MevThresholds;
+use crate::units::Gwei;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Встроенные детекторы `MevDetector`; `rules` — все пользовательские правила разом
+pub const BUILTIN_DETECTORS: &[&str] = &["frontrun", "sandwich", "deployment_frontrun", "rules"];
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("Unknown detector: {0}")]
+    UnknownDetector(String),
+
+    #[error("Invalid value for {field}: {message}")]
+    InvalidValue { field: &'static str, message: String },
+}
+
+/// Настройки одного детектора; пороги `None` — берутся общие из `MevThresholds`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DetectorSettings {
+    pub enabled: bool,
+    pub min_profit_eth: Option<f64>,
+    pub max_gas_price_gwei: Option<f64>,
+    /// Номер ревизии; растёт с каждым изменением
+    pub revision: u64,
+}
+
+impl Default for DetectorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_profit_eth: None,
+            max_gas_price_gwei: None,
+            revision: 0,
+        }
+    }
+}
+
+impl DetectorSettings {
+    /// Эффективные пороги с учётом переопределений
+    pub fn thresholds(&self, base: &MevThresholds) -> MevThresholds {
+        MevThresholds {
+            min_profit: self.min_profit_eth.map(WeiAmount::from_eth).unwrap_or(base.min_profit),
+            max_gas_price: self.max_gas_price_gwei.and_then(|g| Gwei::new(g).ok()).unwrap_or(base.max_gas_price),
+        }
+    }
+}
+
+/// Частичное изменение настроек (тело `PATCH /admin/detectors/{name}`)
+#[derive(Debug, Default, Clone, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(deny_unknown_fields)]
+pub struct DetectorPatch {
+    pub enabled: Option<bool>,
+    pub min_profit_eth: Option<f64>,
+    pub max_gas_price_gwei: Option<f64>,
+}
+
+#[derive(Default)]
+struct DetectorStats {
+    evaluated: AtomicU64,
+    hits: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DetectorStatus {
+    pub name: String,
+    pub settings: DetectorSettings,
+    pub evaluated: u64,
+    pub hits: u64,
+    /// Доля проверок со срабатыванием с момента последнего изменения настроек
+    pub hit_rate: f64,
+    pub updated_at: u64,
+}
+
+struct Entry {
+    settings: RwLock<Arc<DetectorSettings>>,
+    stats: DetectorStats,
+    updated_at: AtomicU64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Реестр детекторов: настройки меняются на лету целиком (замена `Arc`),
+/// читатели всегда видят согласованную версию
+pub struct DetectorRegistry {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Default for DetectorRegistry {
+    fn default() -> Self {
+        Self::new(BUILTIN_DETECTORS.iter().copied())
+    }
+}
+
+impl DetectorRegistry {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let entries = names
+            .into_iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Entry {
+                        settings: RwLock::new(Arc::new(DetectorSettings::default())),
+                        stats: DetectorStats::default(),
+                        updated_at: AtomicU64::new(now()),
+                    },
+                )
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Текущие настройки; неизвестный детектор считается включённым без переопределений
+    pub fn settings(&self, name: &str) -> Arc<DetectorSettings> {
+        self.entries
+            .get(name)
+            .map(|e| e.settings.read().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.settings(name).enabled
+    }
+
+    /// Учитывает одну проверку детектора
+    pub fn record(&self, name: &str, hit: bool) {
+        if let Some(entry) = self.entries.get(name) {
+            entry.stats.evaluated.fetch_add(1, Ordering::Relaxed);
+            if hit {
+                entry.stats.hits.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Применяет изменение атомарно и сбрасывает статистику срабатываний
+    pub fn patch(&self, name: &str, patch: DetectorPatch) -> Result<DetectorStatus, RegistryError> {
+        let entry = self
+            .entries
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownDetector(name.to_string()))?;
+
+        if let Some(profit) = patch.min_profit_eth {
+            if !(0.0..=1_000.0).contains(&profit) {
+                return Err(RegistryError::InvalidValue {
+                    field: "min_profit_eth",
+                    message: format!("{} is out of range [0, 1000]", profit),
+                });
+            }
+        }
+        if let Some(gas) = patch.max_gas_price_gwei {
+            if !(0.1..=100_000.0).contains(&gas) {
+                return Err(RegistryError::InvalidValue {
+                    field: "max_gas_price_gwei",
+                    message: format!("{} is out of range [0.1, 100000]", gas),
+                });
+            }
+        }
+
+        {
+            let mut current = entry.settings.write().unwrap();
+            let mut next = DetectorSettings::clone(&current);
+            if let Some(enabled) = patch.enabled {
+                next.enabled = enabled;
+            }
+            if patch.min_profit_eth.is_some() {
+                next.min_profit_eth = patch.min_profit_eth;
+            }
+            if patch.max_gas_price_gwei.is_some() {
+                next.max_gas_price_gwei = patch.max_gas_price_gwei;
+            }
+            next.revision += 1;
+            *current = Arc::new(next);
+        }
+
+        entry.stats.evaluated.store(0, Ordering::Relaxed);
+        entry.stats.hits.store(0, Ordering::Relaxed);
+        entry.updated_at.store(now(), Ordering::Relaxed);
+
+        Ok(self.status(name).unwrap())
+    }
+
+    pub fn status(&self, name: &str) -> Option<DetectorStatus> {
+        let entry = self.entries.get(name)?;
+        let evaluated = entry.stats.evaluated.load(Ordering::Relaxed);
+        let hits = entry.stats.hits.load(Ordering::Relaxed);
+        Some(DetectorStatus {
+            name: name.to_string(),
+            settings: DetectorSettings::clone(&entry.settings.read().unwrap()),
+            evaluated,
+            hits,
+            hit_rate: if evaluated == 0 { 0.0 } else { hits as f64 / evaluated as f64 },
+            updated_at: entry.updated_at.load(Ordering::Relaxed),
+        })
+    }
+
+    pub fn statuses(&self) -> Vec<DetectorStatus> {
+        self.entries.keys().filter_map(|name| self.status(name)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_swaps_settings_and_resets_stats() {
+        let registry = DetectorRegistry::default();
+        registry.record("sandwich", true);
+        registry.record("sandwich", false);
+        assert_eq!(registry.status("sandwich").unwrap().hit_rate, 0.5);
+
+        let before = registry.settings("sandwich");
+        let status = registry
+            .patch("sandwich", DetectorPatch { enabled: Some(false), min_profit_eth: Some(0.2), ..Default::default() })
+            .unwrap();
+
+        // Читатель, взявший настройки до изменения, продолжает видеть старую версию
+        assert!(before.enabled);
+        assert!(!registry.is_enabled("sandwich"));
+        assert_eq!(status.settings.revision, 1);
+        assert_eq!(status.evaluated, 0);
+
+        let base = MevThresholds { min_profit: WeiAmount::from_eth(0.01), max_gas_price: Gwei::new(500.0).unwrap() };
+        let effective = registry.settings("sandwich").thresholds(&base);
+        assert_eq!(effective.min_profit, WeiAmount::from_eth(0.2));
+        assert_eq!(effective.max_gas_price, Gwei::new(500.0).unwrap());
+
+        assert!(matches!(
+            registry.patch("sandwich", DetectorPatch { max_gas_price_gwei: Some(0.0), ..Default::default() }),
+            Err(RegistryError::InvalidValue { field: "max_gas_price_gwei", .. })
+        ));
+        assert!(matches!(registry.patch("nope", DetectorPatch::default()), Err(RegistryError::UnknownDetector(_))));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::capability::SigningCapability;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Ожидание ответа подписанта по умолчанию
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum RemoteSignerError {
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Remote signer has no key {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid BLS public key '{0}': expected 0x-prefixed 48 bytes")]
+    InvalidPublicKey(String),
+
+    #[error("Remote signer returned unexpected data: {0}")]
+    InvalidResponse(String),
+}
+
+/// BLS-ключ валидатора в hex; проверяется только формат
+pub fn is_bls_public_key(key: &str) -> bool {
+    key.strip_prefix("0x").is_some_and(|hex| hex.len() == 96 && hex.bytes().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Форк для домена подписи; берётся у beacon-узла
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fork {
+    pub previous_version: String,
+    pub current_version: String,
+    pub epoch: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkInfo {
+    pub fork: Fork,
+    pub genesis_validators_root: String,
+}
+
+/// Числа — строками, как в beacon API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoluntaryExit {
+    pub epoch: String,
+    pub validator_index: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositMessage {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    /// Gwei строкой
+    pub amount: String,
+    pub genesis_fork_version: String,
+}
+
+/// Состояние подписанта для диагностики узла
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerHealth {
+    pub url: String,
+    pub reachable: bool,
+    /// `status` из `/healthcheck`; `None`, если эндпоинт недоступен
+    pub status: Option<String>,
+    pub keys_loaded: usize,
+    /// Ожидаемые конфигом ключи, которых у подписанта нет
+    pub missing_keys: Vec<String>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+impl SignerHealth {
+    /// Ошибка любой проверки, в том числе списка ключей, — подписант нездоров
+    pub fn is_healthy(&self) -> bool {
+        self.reachable
+            && self.error.is_none()
+            && self.status.as_deref().is_none_or(|s| s == "UP")
+            && self.missing_keys.is_empty()
+    }
+}
+
+/// Клиент Web3Signer (REST API remote signer): BLS-ключи валидаторов
+/// остаются у подписанта, в процесс попадают только подписи
+pub struct Web3Signer {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl Web3Signer {
+    pub fn new(url: &str) -> Self {
+        Self::with_timeout(url, DEFAULT_TIMEOUT)
+    }
+
+    pub fn with_timeout(url: &str, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder().timeout(timeout).build().unwrap_or_default();
+        Self { url: url.trim_end_matches('/').to_string(), client }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub async fn upcheck(&self) -> Result<(), RemoteSignerError> {
+        self.client.get(format!("{}/upcheck", self.url)).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Ключи eth2, загруженные в подписант, в нижнем регистре
+    pub async fn public_keys(&self) -> Result<Vec<String>, RemoteSignerError> {
+        let keys: Vec<String> = self
+            .client
+            .get(format!("{}/api/v1/eth2/publicKeys", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(keys.into_iter().map(|k| k.to_lowercase()).collect())
+    }
+
+    /// Проверка для диагностики: доступность, статус и наличие ожидаемых ключей.
+    /// Ошибки не прерывают проверку, а попадают в отчёт
+    pub async fn health(&self, expected_keys: &[String]) -> SignerHealth {
+        let started = Instant::now();
+        let mut health = SignerHealth {
+            url: self.url.clone(),
+            reachable: false,
+            status: None,
+            keys_loaded: 0,
+            missing_keys: Vec::new(),
+            latency_ms: 0,
+            error: None,
+        };
+        if let Err(e) = self.upcheck().await {
+            health.error = Some(e.to_string());
+            return health;
+        }
+        health.reachable = true;
+        health.latency_ms = started.elapsed().as_millis() as u64;
+
+        // `/healthcheck` есть не во всех версиях; его отсутствие не ошибка
+        if let Ok(response) = self.client.get(format!("{}/healthcheck", self.url)).send().await {
+            if let Ok(body) = response.json::<serde_json::Value>().await {
+                health.status = body["status"].as_str().map(str::to_string);
+            }
+        }
+        match self.public_keys().await {
+            Ok(keys) => {
+                health.keys_loaded = keys.len();
+                health.missing_keys =
+                    expected_keys.iter().filter(|k| !keys.contains(&k.to_lowercase())).cloned().collect();
+            }
+            Err(e) => health.error = Some(e.to_string()),
+        }
+        health
+    }
+
+    async fn sign(&self, public_key: &str, request: serde_json::Value) -> Result<String, RemoteSignerError> {
+        if !is_bls_public_key(public_key) {
+            return Err(RemoteSignerError::InvalidPublicKey(public_key.to_string()));
+        }
+        let response = self
+            .client
+            .post(format!("{}/api/v1/eth2/sign/{}", self.url, public_key))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .json(&request)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(RemoteSignerError::UnknownKey(public_key.to_string()));
+        }
+        let body = response.error_for_status()?.text().await?;
+        // Старые версии отвечают подписью текстом, новые — `{"signature": ...}`
+        let signature = match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(value) => value["signature"].as_str().map(str::to_string),
+            Err(_) => Some(body.trim().to_string()),
+        };
+        signature
+            .filter(|s| s.starts_with("0x") && s.len() == 2 + 192)
+            .ok_or(RemoteSignerError::InvalidResponse(body))
+    }
+
+    /// Подпись добровольного выхода; домен считает подписант по `fork_info`
+    pub async fn sign_voluntary_exit(
+        &self,
+        public_key: &str,
+        fork_info: &ForkInfo,
+        exit: &VoluntaryExit,
+        _signing: &SigningCapability,
+    ) -> Result<String, RemoteSignerError> {
+        self.sign(public_key, json!({ "type": "VOLUNTARY_EXIT", "fork_info": fork_info, "voluntary_exit": exit })).await
+    }
+
+    /// Подпись депозита; домен депозита не зависит от форка, только от `genesis_fork_version`
+    pub async fn sign_deposit(
+        &self,
+        public_key: &str,
+        deposit: &DepositMessage,
+        _signing: &SigningCapability,
+    ) -> Result<String, RemoteSignerError> {
+        self.sign(public_key, json!({ "type": "DEPOSIT", "deposit": deposit })).await
+    }
+}
@@ -0,0 +1,272 @@
+use crate::shutdown::ShutdownSignal;
+use crate::store::{SharedStore, StoreError};
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Пространства имён, которые чистить нельзя: удаление рвёт хэш-цепочку журнала
+pub const PROTECTED_NAMESPACES: &[&str] = &["audit", "audit_meta"];
+
+/// Что чистится: пространство имён хранилища или каталог с файлами
+/// (сегменты записи мемпула, выгрузки признаков)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionTarget {
+    Namespace(String),
+    Directory(PathBuf),
+}
+
+impl std::fmt::Display for RetentionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetentionTarget::Namespace(ns) => write!(f, "namespace {}", ns),
+            RetentionTarget::Directory(dir) => write!(f, "directory {}", dir.display()),
+        }
+    }
+}
+
+/// Ограничения по возрасту и размеру; при превышении размера удаляются самые старые записи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionRule {
+    pub target: RetentionTarget,
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// Итог прохода по одной цели
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PruneReport {
+    pub target: String,
+    pub removed: u64,
+    pub reclaimed_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+#[derive(Default)]
+pub struct RetentionMetrics {
+    pub runs: AtomicU64,
+    pub removed: AtomicU64,
+    pub reclaimed_bytes: AtomicU64,
+    pub errors: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionSnapshot {
+    pub runs: u64,
+    pub removed: u64,
+    pub reclaimed_bytes: u64,
+    pub errors: u64,
+}
+
+impl RetentionMetrics {
+    pub fn snapshot(&self) -> RetentionSnapshot {
+        RetentionSnapshot {
+            runs: self.runs.load(Ordering::Relaxed),
+            removed: self.removed.load(Ordering::Relaxed),
+            reclaimed_bytes: self.reclaimed_bytes.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Кандидат на удаление: порядок — от старого к новому
+struct Item {
+    key: String,
+    /// Unix-время записи, если известно
+    at: Option<u64>,
+    bytes: u64,
+}
+
+/// От старого к новому: по времени записи, записи без времени — после датированных.
+/// Числовые ключи сравниваются как числа, иначе «10» оказался бы раньше «9»
+fn oldest_first(items: &mut [Item]) {
+    let key_order = |a: &str, b: &str| match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    };
+    items.sort_by(|a, b| {
+        a.at.is_none().cmp(&b.at.is_none()).then(a.at.cmp(&b.at)).then_with(|| key_order(&a.key, &b.key))
+    });
+}
+
+/// Лишние записи по правилу: сначала все старше `max_age`, потом старейшие до `max_bytes`
+fn select(items: &[Item], rule: &RetentionRule, now: u64) -> Vec<usize> {
+    let expired = |item: &Item| match (rule.max_age_seconds, item.at) {
+        (Some(max_age), Some(at)) => now.saturating_sub(at) > max_age,
+        _ => false,
+    };
+    let mut doomed: Vec<usize> = (0..items.len()).filter(|i| expired(&items[*i])).collect();
+
+    if let Some(max_bytes) = rule.max_bytes {
+        let mut total: u64 = items.iter().enumerate().filter(|(i, _)| !doomed.contains(i)).map(|(_, item)| item.bytes).sum();
+        for (i, item) in items.iter().enumerate() {
+            if total <= max_bytes {
+                break;
+            }
+            if !doomed.contains(&i) {
+                doomed.push(i);
+                total -= item.bytes;
+            }
+        }
+    }
+    doomed
+}
+
+/// Фоновая очистка хранилища и каталогов по правилам хранения
+pub struct RetentionJob {
+    store: Option<SharedStore>,
+    rules: Vec<RetentionRule>,
+    metrics: Arc<RetentionMetrics>,
+}
+
+impl RetentionJob {
+    pub fn new(rules: Vec<RetentionRule>) -> Self {
+        Self { store: None, rules, metrics: Arc::new(RetentionMetrics::default()) }
+    }
+
+    /// Хранилище для правил по пространствам имён; без него такие правила учитываются как ошибки
+    pub fn with_store(mut self, store: SharedStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<RetentionMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Записи пространства имён: возраст из поля `timestamp` JSON, если оно есть,
+    /// иначе порядок ключей (номера, метки времени)
+    fn prune_namespace(&self, store: &SharedStore, namespace: &str, rule: &RetentionRule, now: u64) -> Result<PruneReport, StoreError> {
+        let entries = store.list(namespace)?;
+        let mut items: Vec<Item> = entries
+            .iter()
+            .map(|(key, bytes)| Item {
+                key: key.clone(),
+                at: serde_json::from_slice::<serde_json::Value>(bytes).ok().and_then(|v| v["timestamp"].as_u64()),
+                bytes: bytes.len() as u64,
+            })
+            .collect();
+        oldest_first(&mut items);
+        let doomed = select(&items, rule, now);
+        for i in &doomed {
+            store.delete(namespace, &items[*i].key)?;
+        }
+        Ok(report(&rule.target, &items, &doomed))
+    }
+
+    fn prune_directory(&self, dir: &Path, rule: &RetentionRule, now: u64) -> Result<PruneReport, StoreError> {
+        let mut items = Vec::new();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report(&rule.target, &[], &[])),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            if !meta.is_file() {
+                continue;
+            }
+            let at = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs());
+            items.push(Item { key: entry.file_name().to_string_lossy().to_string(), at, bytes: meta.len() });
+        }
+        oldest_first(&mut items);
+
+        let doomed = select(&items, rule, now);
+        for i in &doomed {
+            match std::fs::remove_file(dir.join(&items[*i].key)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(report(&rule.target, &items, &doomed))
+    }
+
+    /// Один проход по всем правилам; ошибки по отдельным целям не прерывают проход
+    pub fn prune_once(&self, now: u64) -> Vec<PruneReport> {
+        self.metrics.runs.fetch_add(1, Ordering::Relaxed);
+        let mut reports = Vec::new();
+        for rule in &self.rules {
+            let result = match &rule.target {
+                RetentionTarget::Namespace(ns) if PROTECTED_NAMESPACES.contains(&ns.as_str()) => continue,
+                RetentionTarget::Namespace(ns) => match &self.store {
+                    Some(store) => self.prune_namespace(store, ns, rule, now),
+                    None => {
+                        self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                },
+                RetentionTarget::Directory(dir) => self.prune_directory(dir, rule, now),
+            };
+            match result {
+                Ok(r) => {
+                    self.metrics.removed.fetch_add(r.removed, Ordering::Relaxed);
+                    self.metrics.reclaimed_bytes.fetch_add(r.reclaimed_bytes, Ordering::Relaxed);
+                    reports.push(r);
+                }
+                Err(_) => {
+                    self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        reports
+    }
+
+    /// Чистит с интервалом до сигнала остановки
+    pub async fn run(&self, interval: Duration, mut shutdown: ShutdownSignal) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                    self.prune_once(now);
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+}
+
+fn report(target: &RetentionTarget, items: &[Item], doomed: &[usize]) -> PruneReport {
+    let reclaimed: u64 = doomed.iter().map(|i| items[*i].bytes).sum();
+    let total: u64 = items.iter().map(|i| i.bytes).sum();
+    PruneReport {
+        target: target.to_string(),
+        removed: doomed.len() as u64,
+        reclaimed_bytes: reclaimed,
+        remaining_bytes: total - reclaimed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[test]
+    fn test_size_limit_removes_oldest_entries_first() {
+        let store: SharedStore = Arc::new(MemoryStore::default());
+        // Строкой «10» меньше «9», но записан позже
+        for (key, at) in [("9", 100), ("10", 300), ("11", 200)] {
+            store.put("alerts", key, serde_json::json!({ "timestamp": at }).to_string().as_bytes()).unwrap();
+        }
+        let entry = store.get("alerts", "9").unwrap().unwrap().len() as u64;
+        let rule = RetentionRule {
+            target: RetentionTarget::Namespace("alerts".into()),
+            max_age_seconds: None,
+            max_bytes: Some(entry * 2),
+        };
+        let job = RetentionJob::new(vec![rule]).with_store(store.clone());
+
+        let reports = job.prune_once(1_000);
+        assert_eq!(reports[0].removed, 1);
+        assert!(store.get("alerts", "9").unwrap().is_none());
+        assert!(store.get("alerts", "10").unwrap().is_some());
+        assert_eq!(job.metrics().snapshot().reclaimed_bytes, entry);
+    }
+}
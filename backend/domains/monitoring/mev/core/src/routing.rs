@@ -0,0 +1,626 @@
+use crate::bus::{AlertBus, AlertLevel, BusAlert};
+use crate::i18n::{LocaleSelector, MessageCatalog};
+use crate::shutdown::ShutdownSignal;
+use crate::correlation;
+use crate::sink::{AlertState, Message, Sink, SinkError};
+use crate::store::{SharedStore, StoreError, StoreExt};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+
+/// Пространство имён хранилища: идущие эскалации и синки, получившие алерты
+pub const ROUTING_NS: &str = "routing";
+
+const STATE_KEY: &str = "state";
+
+/// Как часто проверять наступившие эскалации
+const ESCALATION_TICK: Duration = Duration::from_secs(15);
+
+/// Попыток доставки в синк; отказ синка (`SinkError::Rejected`) не повторяется
+const SEND_ATTEMPTS: u32 = 3;
+
+/// Пауза перед второй попыткой, дальше удваивается
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Сколько помнить, какие синки получили алерт. Дальше подтвердить его через узел
+/// нельзя, поэтому инцидент закрывается на платформах
+const DELIVERED_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// Условия маршрута; пустое поле подходит к любому алерту
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteMatch {
+    /// Подсистемы-источники: `mev`, `monitor`, `lending`
+    #[serde(default)]
+    pub domains: Vec<String>,
+    #[serde(default)]
+    pub min_level: Option<AlertLevel>,
+    #[serde(default)]
+    pub tenants: Vec<String>,
+    /// Нужны все теги; теги алерта — его `kind` и строки из `payload.tags`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Шаг цепочки: синки, которые получат алерт через `after_seconds` после его прихода,
+/// если он к тому времени не подтверждён
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EscalationStep {
+    pub sinks: Vec<String>,
+    #[serde(default)]
+    pub after_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub name: String,
+    #[serde(default, rename = "match")]
+    pub matcher: RouteMatch,
+    pub chain: Vec<EscalationStep>,
+    /// Проверять следующие маршруты и после совпадения с этим
+    #[serde(default, rename = "continue")]
+    pub continue_matching: bool,
+}
+
+/// Правило подавления: подходящие алерты не уходят ни в один маршрут. Пустой `subjects`
+/// подходит к любому адресу; без `until` правило действует, пока его не снимут
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suppression {
+    pub name: String,
+    #[serde(default, rename = "match")]
+    pub matcher: RouteMatch,
+    #[serde(default)]
+    pub subjects: Vec<String>,
+    /// Unix-время окончания
+    #[serde(default)]
+    pub until: Option<u64>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Идентификатор алерта для подтверждения; одинаков на всех узлах. У составного инцидента —
+/// id корреляции, поэтому его повторные выпуски обновляют один инцидент на платформе
+pub fn alert_id(alert: &BusAlert) -> String {
+    if alert.source == correlation::SOURCE {
+        if let Some(id) = alert.payload.get("id").and_then(|id| id.as_str()) {
+            return id.to_string();
+        }
+    }
+    let digest = Sha256::digest(format!("{}|{}|{}|{}", alert.source, alert.kind, alert.subject, alert.timestamp));
+    format!("{:x}", digest)[..16].to_string()
+}
+
+fn tags(alert: &BusAlert) -> Vec<&str> {
+    let payload_tags = alert.payload.get("tags").and_then(|t| t.as_array());
+    std::iter::once(alert.kind.as_str())
+        .chain(payload_tags.into_iter().flatten().filter_map(|t| t.as_str()))
+        .collect()
+}
+
+impl RouteMatch {
+    fn matches(&self, alert: &BusAlert, tenants: &[&str]) -> bool {
+        let alert_tags = tags(alert);
+        (self.domains.is_empty() || self.domains.iter().any(|d| d == &alert.source))
+            && self.min_level.is_none_or(|min| alert.level >= min)
+            && (self.tenants.is_empty() || self.tenants.iter().any(|t| tenants.contains(&t.as_str())))
+            && self.tags.iter().all(|t| alert_tags.contains(&t.as_str()))
+    }
+}
+
+impl Suppression {
+    fn suppresses(&self, alert: &BusAlert, tenants: &[&str], now: u64) -> bool {
+        self.until.is_none_or(|until| now < until)
+            && (self.subjects.is_empty() || self.subjects.iter().any(|s| s.eq_ignore_ascii_case(&alert.subject)))
+            && self.matcher.matches(alert, tenants)
+    }
+}
+
+/// Неподтверждённый алерт с ещё не пройденными шагами цепочки
+#[derive(Clone)]
+struct Pending {
+    alert: Arc<BusAlert>,
+    route: usize,
+    tenant: Option<String>,
+    received_at: u64,
+    next_step: usize,
+}
+
+/// `Pending` в хранилище; маршрут по имени, чтобы пережить правку порядка маршрутов
+#[derive(Serialize, Deserialize)]
+struct PendingRecord {
+    alert: BusAlert,
+    route: String,
+    tenant: Option<String>,
+    received_at: u64,
+    next_step: usize,
+}
+
+/// Состояние маршрутизатора, переживающее перезапуск узла
+#[derive(Default, Serialize, Deserialize)]
+struct RoutingState {
+    pending: Vec<PendingRecord>,
+    delivered: BTreeMap<String, (u64, BTreeSet<String>)>,
+}
+
+/// Разводит алерты шины по цепочкам синков маршрутов и эскалирует неподтверждённые
+pub struct AlertRouter {
+    routes: Vec<Route>,
+    /// Тенант -> его адреса
+    tenants: BTreeMap<String, Vec<String>>,
+    sinks: HashMap<String, Arc<dyn Sink>>,
+    suppressions: Mutex<Vec<Suppression>>,
+    catalog: MessageCatalog,
+    locales: LocaleSelector,
+    pending: Mutex<HashMap<String, Pending>>,
+    /// Алерт -> время прихода и синки, принявшие его
+    delivered: Mutex<HashMap<String, (u64, BTreeSet<String>)>>,
+    store: Option<SharedStore>,
+    /// Алерты, пропущенные из-за отставания от шины
+    lagged: AtomicU64,
+    errors: TaskErrors,
+}
+
+impl AlertRouter {
+    pub fn new(routes: Vec<Route>, tenants: BTreeMap<String, Vec<String>>) -> Self {
+        Self {
+            routes,
+            tenants,
+            sinks: HashMap::new(),
+            suppressions: Mutex::new(Vec::new()),
+            catalog: MessageCatalog::new(),
+            locales: LocaleSelector::default(),
+            pending: Mutex::new(HashMap::new()),
+            delivered: Mutex::new(HashMap::new()),
+            store: None,
+            lagged: AtomicU64::new(0),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Хранит эскалации и доставки в `store`: после перезапуска цепочки продолжаются,
+    /// а подтверждения доходят до платформ, получивших алерт до него
+    pub fn with_store(mut self, store: SharedStore) -> Result<Self, StoreError> {
+        let state: RoutingState = store.get_json(ROUTING_NS, STATE_KEY)?.unwrap_or_default();
+        for record in state.pending {
+            // Маршрут убран из конфига: его эскалация больше не нужна
+            let Some(route) = self.routes.iter().position(|r| r.name == record.route) else {
+                continue;
+            };
+            let alert = Arc::new(record.alert);
+            let key = format!("{}/{}", alert_id(&alert), route);
+            let pending = Pending { alert, route, tenant: record.tenant, received_at: record.received_at, next_step: record.next_step };
+            self.pending.get_mut().unwrap().insert(key, pending);
+        }
+        self.delivered.get_mut().unwrap().extend(state.delivered);
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    fn persist(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let state = RoutingState {
+            pending: self
+                .pending
+                .lock()
+                .unwrap()
+                .values()
+                .map(|p| PendingRecord {
+                    alert: (*p.alert).clone(),
+                    route: self.routes[p.route].name.clone(),
+                    tenant: p.tenant.clone(),
+                    received_at: p.received_at,
+                    next_step: p.next_step,
+                })
+                .collect(),
+            delivered: self.delivered.lock().unwrap().iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        };
+        self.errors.check(store.put_json(ROUTING_NS, STATE_KEY, &state));
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Сколько алертов шины не дошло до маршрутизации из-за отставания
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn Sink>) -> Self {
+        self.sinks.insert(sink.name().to_string(), sink);
+        self
+    }
+
+    pub fn with_messages(mut self, catalog: MessageCatalog, locales: LocaleSelector) -> Self {
+        self.catalog = catalog;
+        self.locales = locales;
+        self
+    }
+
+    pub fn with_suppressions(self, suppressions: Vec<Suppression>) -> Self {
+        self.set_suppressions(suppressions);
+        self
+    }
+
+    pub fn suppressions(&self) -> Vec<Suppression> {
+        self.suppressions.lock().unwrap().clone()
+    }
+
+    /// Заменяет правила подавления целиком
+    pub fn set_suppressions(&self, suppressions: Vec<Suppression>) {
+        *self.suppressions.lock().unwrap() = suppressions;
+    }
+
+    /// Имя первого правила, подавляющего алерт
+    pub fn suppressed_by(&self, alert: &BusAlert, now: u64) -> Option<String> {
+        let tenants = self.tenants_of(&alert.subject);
+        self.suppressions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|s| s.suppresses(alert, &tenants, now))
+            .map(|s| s.name.clone())
+    }
+
+    fn tenants_of(&self, subject: &str) -> Vec<&str> {
+        self.tenants
+            .iter()
+            .filter(|(_, subjects)| subjects.iter().any(|s| s.eq_ignore_ascii_case(subject)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Индексы подходящих маршрутов по порядку; первый без `continue` завершает поиск
+    pub fn route(&self, alert: &BusAlert) -> Vec<usize> {
+        let tenants = self.tenants_of(&alert.subject);
+        let mut matched = Vec::new();
+        for (i, route) in self.routes.iter().enumerate() {
+            if route.matcher.matches(alert, &tenants) {
+                matched.push(i);
+                if !route.continue_matching {
+                    break;
+                }
+            }
+        }
+        matched
+    }
+
+    /// Тенант сообщения: из условий маршрута, иначе первый тенант адреса
+    fn tenant_for(&self, route: &Route, alert: &BusAlert) -> Option<String> {
+        let tenants = self.tenants_of(&alert.subject);
+        route
+            .matcher
+            .tenants
+            .iter()
+            .find(|t| tenants.contains(&t.as_str()))
+            .map(|t| t.as_str())
+            .or_else(|| tenants.first().copied())
+            .map(str::to_string)
+    }
+
+    async fn send_step(&self, pending: &Pending, id: &str) {
+        let route = &self.routes[pending.route];
+        let step = &route.chain[pending.next_step];
+        let tenant = pending.tenant.as_deref();
+        for name in &step.sinks {
+            let Some(sink) = self.sinks.get(name) else {
+                self.errors.record(format!("route {} refers to unknown sink {}", route.name, name));
+                continue;
+            };
+            let locale = self.locales.resolve(name, tenant);
+            let mut markdown = self.catalog.render(&pending.alert, locale);
+            if pending.next_step > 0 {
+                let args = json!({ "minutes": step.after_seconds / 60, "route": route.name, "id": id });
+                if let Some(note) = self.catalog.render_key(locale, "routing.escalated", &args) {
+                    markdown = format!("{}\n\n{}", markdown, note);
+                }
+            }
+            let mut message = Message::new(pending.alert.title.clone(), pending.alert.level, markdown).with_alert_id(id);
+            if let Some(tenant) = tenant {
+                message = message.with_tenant(tenant);
+            }
+            if let Some(wallet) = pending.alert.wallet() {
+                message = message.with_wallet(&wallet);
+            }
+            match send_with_retry(sink.as_ref(), &message).await {
+                Ok(()) => {
+                    let mut delivered = self.delivered.lock().unwrap();
+                    let (_, sinks) = delivered.entry(id.to_string()).or_insert_with(|| (pending.received_at, BTreeSet::new()));
+                    sinks.insert(name.clone());
+                }
+                Err(e) => self.errors.record(format!("alert {} delivery to {} via route {} failed: {}", id, name, route.name, e)),
+            }
+        }
+    }
+
+    /// Отправляет шаги без задержки и запоминает алерт, если у цепочки есть продолжение.
+    /// Повторный выпуск того же инцидента не перезапускает уже идущую эскалацию
+    pub async fn dispatch(&self, alert: Arc<BusAlert>, now: u64) {
+        if self.suppressed_by(&alert, now).is_some() {
+            return;
+        }
+        let id = alert_id(&alert);
+        for index in self.route(&alert) {
+            let route = &self.routes[index];
+            let mut pending =
+                Pending { alert: alert.clone(), route: index, tenant: self.tenant_for(route, &alert), received_at: now, next_step: 0 };
+            while pending.next_step < route.chain.len() && route.chain[pending.next_step].after_seconds == 0 {
+                self.send_step(&pending, &id).await;
+                pending.next_step += 1;
+            }
+            if pending.next_step < route.chain.len() {
+                self.pending.lock().unwrap().entry(format!("{}/{}", id, index)).or_insert(pending);
+            }
+        }
+        self.persist();
+    }
+
+    /// Отправляет шаги, чьё время пришло к `now`. Шаги отмечаются пройденными до отправки,
+    /// поэтому подтверждение во время доставки останавливает только следующие
+    pub async fn escalate(&self, now: u64) {
+        let expired: Vec<String> = self
+            .delivered
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (at, _))| now.saturating_sub(*at) >= DELIVERED_TTL_SECS)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            self.resolve(&id).await;
+        }
+        let mut due = Vec::new();
+        self.pending.lock().unwrap().retain(|_, pending| {
+            let chain = &self.routes[pending.route].chain;
+            let from = pending.next_step;
+            while pending.next_step < chain.len() && pending.received_at + chain[pending.next_step].after_seconds <= now {
+                pending.next_step += 1;
+            }
+            if pending.next_step > from {
+                due.push((Pending { next_step: from, ..pending.clone() }, pending.next_step));
+            }
+            pending.next_step < chain.len()
+        });
+        let sent = !due.is_empty();
+        for (mut pending, until) in due {
+            let id = alert_id(&pending.alert);
+            while pending.next_step < until {
+                self.send_step(&pending, &id).await;
+                pending.next_step += 1;
+            }
+        }
+        if sent {
+            self.persist();
+        }
+    }
+
+    /// Останавливает эскалацию и переводит алерт на платформах инцидентов, которые его получили.
+    /// `origin` — синк, с платформы которого пришла смена: ему она уже известна.
+    /// `false` — алерт неизвестен или срок его учёта истёк
+    pub async fn transition(&self, id: &str, state: AlertState, origin: Option<&str>) -> bool {
+        let stopped = {
+            let mut pending = self.pending.lock().unwrap();
+            let before = pending.len();
+            pending.retain(|key, _| key.split('/').next() != Some(id));
+            pending.len() < before
+        };
+        let sinks = {
+            let mut delivered = self.delivered.lock().unwrap();
+            match state {
+                AlertState::Resolved => delivered.remove(id).map(|(_, sinks)| sinks),
+                _ => delivered.get(id).map(|(_, sinks)| sinks.clone()),
+            }
+        };
+        let known = sinks.is_some();
+        if stopped || state == AlertState::Resolved {
+            self.persist();
+        }
+        for name in sinks.into_iter().flatten().filter(|name| Some(name.as_str()) != origin) {
+            let Some(sink) = self.sinks.get(&name) else { continue };
+            if let Err(e) = sink.transition(id, state).await {
+                self.errors.record(format!("alert {} transition to {:?} on {} failed: {}", id, state, name, e));
+            }
+        }
+        stopped || known
+    }
+
+    pub async fn acknowledge(&self, id: &str) -> bool {
+        self.transition(id, AlertState::Acknowledged, None).await
+    }
+
+    pub async fn resolve(&self, id: &str) -> bool {
+        self.transition(id, AlertState::Resolved, None).await
+    }
+
+    /// Смена состояния из вебхука платформы синка `sink`. `None` — такого синка нет
+    pub async fn incident_webhook(&self, sink: &str, body: &serde_json::Value) -> Option<()> {
+        let update = self.sinks.get(sink)?.incident_update(body);
+        if let Some((id, state)) = update {
+            self.transition(&id, state, Some(sink)).await;
+        }
+        Some(())
+    }
+
+    /// Число алертов, ожидающих эскалации
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Маршрутизирует алерты шины и эскалирует неподтверждённые до сигнала остановки.
+    /// Доставка идёт в отдельных задачах, чтобы медленный синк не задерживал чтение шины;
+    /// начатые доставки дорабатывают после сигнала
+    pub async fn run(self: Arc<Self>, bus: &AlertBus, mut shutdown: ShutdownSignal) {
+        let mut rx = bus.subscribe();
+        let mut tick = tokio::time::interval(ESCALATION_TICK);
+        let mut deliveries = JoinSet::new();
+        loop {
+            tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(alert) => {
+                        let router = self.clone();
+                        deliveries.spawn(async move { router.dispatch(alert, now()).await });
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        self.lagged.fetch_add(skipped, Ordering::Relaxed);
+                        self.errors.record(format!("alert bus lagged, {} alerts not routed", skipped));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = tick.tick() => {
+                    let router = self.clone();
+                    deliveries.spawn(async move { router.escalate(now()).await });
+                }
+                Some(_) = deliveries.join_next(), if !deliveries.is_empty() => {}
+                _ = shutdown.wait() => break,
+            }
+        }
+        while deliveries.join_next().await.is_some() {}
+    }
+}
+
+/// Доставка с повтором временных сбоев: пауза `RETRY_BACKOFF`, потом вдвое дольше
+async fn send_with_retry(sink: &dyn Sink, message: &Message) -> Result<(), SinkError> {
+    let mut attempt = 1;
+    loop {
+        match sink.send(message).await {
+            Err(SinkError::DeliveryFailed(_)) if attempt < SEND_ATTEMPTS => {
+                tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_by_tenant_level_and_tags() {
+        let route = |name: &str, matcher: RouteMatch, continue_matching: bool| Route {
+            name: name.to_string(),
+            matcher,
+            chain: vec![EscalationStep { sinks: vec!["slack".into()], after_seconds: 0 }],
+            continue_matching,
+        };
+        let routes = vec![
+            route(
+                "acme-critical",
+                RouteMatch { min_level: Some(AlertLevel::High), tenants: vec!["acme".into()], ..Default::default() },
+                true,
+            ),
+            route("sandwich", RouteMatch { domains: vec!["mev".into()], tags: vec!["sandwich".into()], ..Default::default() }, false),
+            route("fallback", RouteMatch::default(), false),
+        ];
+        let router = AlertRouter::new(routes, [("acme".to_string(), vec!["0xAbC".to_string()])].into());
+
+        let alert = |level, kind: &str| BusAlert::new("mev", kind, level, "0xabc".into(), "t".into());
+        assert_eq!(router.route(&alert(AlertLevel::Critical, "sandwich")), vec![0, 1]);
+        assert_eq!(router.route(&alert(AlertLevel::Low, "sandwich")), vec![1]);
+        assert_eq!(router.route(&alert(AlertLevel::Critical, "arbitrage")), vec![0, 2]);
+        let tagged = alert(AlertLevel::Low, "arbitrage").with_payload(json!({ "tags": ["sandwich"] }));
+        assert_eq!(router.route(&tagged), vec![1]);
+    }
+
+    #[test]
+    fn test_suppression_matches_subject_until_expiry() {
+        let router = AlertRouter::new(Vec::new(), BTreeMap::new()).with_suppressions(vec![Suppression {
+            name: "maintenance".into(),
+            matcher: RouteMatch { domains: vec!["mev".into()], ..Default::default() },
+            subjects: vec!["0xAbC".into()],
+            until: Some(200),
+            reason: String::new(),
+        }]);
+        let alert = |source: &str, subject: &str| BusAlert::new(source, "sandwich", AlertLevel::High, subject.into(), "t".into());
+        assert_eq!(router.suppressed_by(&alert("mev", "0xabc"), 100).as_deref(), Some("maintenance"));
+        assert_eq!(router.suppressed_by(&alert("mev", "0xabc"), 200), None);
+        assert_eq!(router.suppressed_by(&alert("mev", "0xdef"), 100), None);
+        assert_eq!(router.suppressed_by(&alert("monitor", "0xabc"), 100), None);
+    }
+
+    struct Recording {
+        name: &'static str,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Sink for Recording {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, message: &Message) -> Result<(), SinkError> {
+            self.sent.lock().unwrap().push(format!("{}: {}", self.name, message.subject));
+            Ok(())
+        }
+
+        async fn transition(&self, _alert_id: &str, state: AlertState) -> Result<(), SinkError> {
+            self.sent.lock().unwrap().push(format!("{}: {:?}", self.name, state));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_escalation_survives_restart() {
+        let store: SharedStore = Arc::new(crate::store::MemoryStore::new());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let router = || {
+            let route = Route {
+                name: "oncall".into(),
+                matcher: RouteMatch::default(),
+                chain: vec![
+                    EscalationStep { sinks: vec!["slack".into()], after_seconds: 0 },
+                    EscalationStep { sinks: vec!["pager".into()], after_seconds: 300 },
+                ],
+                continue_matching: false,
+            };
+            AlertRouter::new(vec![route], BTreeMap::new())
+                .with_sink(Arc::new(Recording { name: "slack", sent: sent.clone() }))
+                .with_sink(Arc::new(Recording { name: "pager", sent: sent.clone() }))
+                .with_store(store.clone())
+                .unwrap()
+        };
+
+        let alert = Arc::new(BusAlert::new("mev", "sandwich", AlertLevel::High, "0xabc".into(), "t".into()));
+        router().dispatch(alert.clone(), 100).await;
+        assert_eq!(sent.lock().unwrap().len(), 1);
+
+        let restarted = router();
+        assert_eq!(restarted.pending_count(), 1);
+        restarted.escalate(400).await;
+        assert_eq!(sent.lock().unwrap().len(), 2);
+        assert!(sent.lock().unwrap()[1].starts_with("pager"));
+        assert_eq!(restarted.pending_count(), 0);
+
+        // Подтверждение пришло с платформы pager: туда его не повторяем
+        let restarted = router();
+        assert!(restarted.transition(&alert_id(&alert), AlertState::Acknowledged, Some("pager")).await);
+        assert_eq!(sent.lock().unwrap()[2..], ["slack: Acknowledged".to_string()]);
+
+        // Срок учёта вышел: алерт закрывается везде, где он был
+        restarted.escalate(100 + DELIVERED_TTL_SECS).await;
+        assert_eq!(sent.lock().unwrap().len(), 5);
+        assert!(!restarted.resolve(&alert_id(&alert)).await);
+        assert_eq!(restarted.errors().errors, 0);
+    }
+}
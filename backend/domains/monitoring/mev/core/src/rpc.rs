@@ -0,0 +1,401 @@
+use crate::units::Fraction;
+use async_trait::async_trait;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError, Provider, ProviderError, RpcError};
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Стоимость метода в compute units, если его нет в таблице
+pub const DEFAULT_METHOD_COST: u32 = 20;
+
+/// Коды JSON-RPC, которыми провайдеры сообщают о превышении лимита
+const RATE_LIMIT_CODES: [i64; 3] = [429, -32005, -32029];
+
+/// Стоимость методов в CU по тарифам крупных провайдеров; переопределяется в конфиге
+fn default_method_costs() -> HashMap<String, u32> {
+    [
+        ("eth_blockNumber", 10),
+        ("eth_chainId", 0),
+        ("eth_getBalance", 19),
+        ("eth_getCode", 26),
+        ("eth_getStorageAt", 17),
+        ("eth_getTransactionCount", 26),
+        ("eth_getBlockByNumber", 16),
+        ("eth_getBlockByHash", 16),
+        ("eth_getTransactionByHash", 17),
+        ("eth_getTransactionReceipt", 15),
+        ("eth_call", 26),
+        ("eth_estimateGas", 87),
+        ("eth_getLogs", 75),
+        ("eth_feeHistory", 10),
+        ("eth_sendRawTransaction", 250),
+        ("txpool_content", 500),
+        ("debug_traceTransaction", 309),
+        ("debug_traceCall", 309),
+        ("trace_block", 75),
+        ("trace_transaction", 75),
+        ("trace_replayTransaction", 2983),
+    ]
+    .into_iter()
+    .map(|(method, cost)| (method.to_string(), cost))
+    .collect()
+}
+
+/// Повтор идемпотентных чтений
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 3, initial_backoff_ms: 250, max_backoff_ms: 10_000 }
+    }
+}
+
+impl RetryPolicy {
+    /// Экспонента с полным разбросом; `random` — равномерно из [0, 1)
+    pub(crate) fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let cap = self.initial_backoff_ms.saturating_mul(1u64 << attempt.min(16)).min(self.max_backoff_ms);
+        Duration::from_millis((cap as f64 * (0.5 + random / 2.0)) as u64)
+    }
+}
+
+/// Квоты плана провайдера (`[rpc.quota]`); по умолчанию без лимитов, только учёт
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaConfig {
+    /// Лимит запросов в секунду; 0 — без лимита
+    #[serde(default)]
+    pub requests_per_second: u32,
+    /// Лимит compute units в секунду; 0 — без лимита
+    #[serde(default)]
+    pub compute_units_per_second: u32,
+    /// Переопределения стоимости методов в CU
+    #[serde(default)]
+    pub method_costs: HashMap<String, u32>,
+    /// Потолок доли CU/с для подсистемы (`enrichment`, `detector`, `staking`, ...), чтобы
+    /// тяжёлое обогащение не выедало план; подсистемы без записи ограничены только общим лимитом
+    #[serde(default)]
+    pub budgets: BTreeMap<String, Fraction>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+/// Ведро токенов: `rate` в секунду, запас не больше секундного
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self { rate, tokens: rate, updated: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated = now;
+    }
+
+    /// Сколько ждать, пока наберётся `amount`; запрос дороже ведра ждёт до полного ведра
+    fn wait_for(&self, amount: f64) -> Duration {
+        let needed = amount.min(self.rate) - self.tokens;
+        if needed <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(needed / self.rate)
+        }
+    }
+}
+
+/// Счётчики потребления одной подсистемы
+#[derive(Default)]
+struct UsageCounters {
+    requests: AtomicU64,
+    compute_units: AtomicU64,
+    retries: AtomicU64,
+    failures: AtomicU64,
+    throttled_ms: AtomicU64,
+}
+
+/// Потребление подсистемы с момента запуска
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SubsystemUsage {
+    pub subsystem: String,
+    pub requests: u64,
+    pub compute_units: u64,
+    pub retries: u64,
+    pub failures: u64,
+    /// Суммарное ожидание квоты
+    pub throttled_ms: u64,
+    /// Потолок доли CU/с; `None` — только общий лимит
+    pub budget_share: Option<f64>,
+}
+
+struct Limits {
+    requests: Option<Bucket>,
+    compute_units: Option<Bucket>,
+    subsystems: HashMap<String, Bucket>,
+}
+
+/// HTTP-провайдер, чьи вызовы идут через `RpcQuota`
+pub type QuotaProvider = Provider<QuotaClient<Http>>;
+
+/// Общий учёт квот провайдера для всех подсистем процесса
+pub struct RpcQuota {
+    config: QuotaConfig,
+    costs: HashMap<String, u32>,
+    limits: Mutex<Limits>,
+    usage: Mutex<BTreeMap<String, Arc<UsageCounters>>>,
+}
+
+impl Debug for RpcQuota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcQuota").field("config", &self.config).finish()
+    }
+}
+
+impl RpcQuota {
+    pub fn new(config: QuotaConfig) -> Self {
+        let now = Instant::now();
+        let positive = |rate: u32| (rate > 0).then(|| Bucket::new(rate as f64, now));
+        let subsystems = config
+            .budgets
+            .iter()
+            .filter(|_| config.compute_units_per_second > 0)
+            .map(|(name, share)| (name.clone(), Bucket::new(config.compute_units_per_second as f64 * share.get(), now)))
+            .filter(|(_, bucket)| bucket.rate > 0.0)
+            .collect();
+        let mut costs = default_method_costs();
+        costs.extend(config.method_costs.clone());
+        Self {
+            limits: Mutex::new(Limits {
+                requests: positive(config.requests_per_second),
+                compute_units: positive(config.compute_units_per_second),
+                subsystems,
+            }),
+            costs,
+            usage: Mutex::new(BTreeMap::new()),
+            config,
+        }
+    }
+
+    pub fn cost(&self, method: &str) -> u32 {
+        self.costs.get(method).copied().unwrap_or(DEFAULT_METHOD_COST)
+    }
+
+    fn counters(&self, subsystem: &str) -> Arc<UsageCounters> {
+        self.usage.lock().unwrap().entry(subsystem.to_string()).or_default().clone()
+    }
+
+    /// Ждёт, пока общий лимит и бюджет подсистемы позволят вызов `method`
+    pub async fn acquire(&self, subsystem: &str, method: &str) {
+        let cost = self.cost(method) as f64;
+        let started = Instant::now();
+        loop {
+            let wait = {
+                let mut limits = self.limits.lock().unwrap();
+                let now = Instant::now();
+                let Limits { requests, compute_units, subsystems } = &mut *limits;
+                let mut buckets: Vec<(&mut Bucket, f64)> = Vec::new();
+                if let Some(bucket) = requests.as_mut() {
+                    buckets.push((bucket, 1.0));
+                }
+                if let Some(bucket) = compute_units.as_mut() {
+                    buckets.push((bucket, cost));
+                }
+                if let Some(bucket) = subsystems.get_mut(subsystem) {
+                    buckets.push((bucket, cost));
+                }
+                for (bucket, _) in buckets.iter_mut() {
+                    bucket.refill(now);
+                }
+                let wait = buckets.iter().map(|(b, amount)| b.wait_for(*amount)).max().unwrap_or(Duration::ZERO);
+                if wait.is_zero() {
+                    for (bucket, amount) in buckets {
+                        bucket.tokens -= amount;
+                    }
+                }
+                wait
+            };
+            if wait.is_zero() {
+                break;
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        let counters = self.counters(subsystem);
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        counters.compute_units.fetch_add(cost as u64, Ordering::Relaxed);
+        counters.throttled_ms.fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn usage(&self) -> Vec<SubsystemUsage> {
+        self.usage
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(subsystem, c)| SubsystemUsage {
+                subsystem: subsystem.clone(),
+                requests: c.requests.load(Ordering::Relaxed),
+                compute_units: c.compute_units.load(Ordering::Relaxed),
+                retries: c.retries.load(Ordering::Relaxed),
+                failures: c.failures.load(Ordering::Relaxed),
+                throttled_ms: c.throttled_ms.load(Ordering::Relaxed),
+                budget_share: self.config.budgets.get(subsystem).map(Fraction::get),
+            })
+            .collect()
+    }
+
+    /// Клиент, чьи вызовы учитываются на `subsystem`
+    pub fn client<P>(self: &Arc<Self>, subsystem: &str, inner: P) -> QuotaClient<P> {
+        QuotaClient { inner, quota: self.clone(), subsystem: subsystem.to_string() }
+    }
+
+    /// HTTP-провайдер подсистемы с общим учётом квот
+    pub fn http_provider(self: &Arc<Self>, subsystem: &str, url: &str) -> Result<QuotaProvider, <Http as FromStr>::Err> {
+        Ok(Provider::new(self.client(subsystem, url.parse::<Http>()?)))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QuotaClientError<E> {
+    #[error(transparent)]
+    Transport(E),
+
+    #[error("Failed to serialize request params: {0}")]
+    Serialization(serde_json::Error),
+}
+
+impl<E: RpcError> RpcError for QuotaClientError<E> {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Transport(e) => e.as_error_response(),
+            Self::Serialization(_) => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Transport(e) => e.as_serde_error(),
+            Self::Serialization(e) => Some(e),
+        }
+    }
+}
+
+impl<E: Into<ProviderError>> From<QuotaClientError<E>> for ProviderError {
+    fn from(e: QuotaClientError<E>) -> Self {
+        match e {
+            QuotaClientError::Transport(e) => e.into(),
+            QuotaClientError::Serialization(e) => ProviderError::SerdeJson(e),
+        }
+    }
+}
+
+/// Вызовы, повтор которых ничего не меняет в сети
+pub fn is_idempotent(method: &str) -> bool {
+    !["eth_send", "eth_sign", "eth_subscribe", "eth_unsubscribe", "personal_", "engine_"]
+        .iter()
+        .any(|prefix| method.starts_with(prefix))
+}
+
+/// Повторять стоит сбои транспорта и отказы по лимиту, но не ошибки исполнения и разбора
+fn is_retryable<E: RpcError>(error: &E) -> bool {
+    match error.as_error_response() {
+        Some(response) => RATE_LIMIT_CODES.contains(&response.code) || response.message.to_lowercase().contains("rate limit"),
+        None => error.as_serde_error().is_none(),
+    }
+}
+
+/// Транспорт с учётом квот: каждый вызов ждёт бюджета подсистемы,
+/// идемпотентные чтения повторяются с экспоненциальной задержкой
+#[derive(Debug)]
+pub struct QuotaClient<P> {
+    inner: P,
+    quota: Arc<RpcQuota>,
+    subsystem: String,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<P> JsonRpcClient for QuotaClient<P>
+where
+    P: JsonRpcClient + 'static,
+    P::Error: Send + Sync + 'static,
+{
+    type Error = QuotaClientError<P::Error>;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params).map_err(QuotaClientError::Serialization)?;
+        let retries = if is_idempotent(method) { self.quota.config.retry.max_retries } else { 0 };
+        let counters = self.quota.counters(&self.subsystem);
+        let mut attempt = 0;
+        loop {
+            self.quota.acquire(&self.subsystem, method).await;
+            match self.inner.request(method, &params).await {
+                Ok(result) => return Ok(result),
+                Err(e) if attempt < retries && is_retryable(&e) => {
+                    counters.retries.fetch_add(1, Ordering::Relaxed);
+                    let delay = self.quota.config.retry.delay(attempt, thread_rng().gen());
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    counters.failures.fetch_add(1, Ordering::Relaxed);
+                    return Err(QuotaClientError::Transport(e));
+                }
+            }
+        }
+    }
+}
+
+/// Ошибка HTTP-транспорта с учётом квот
+pub type QuotaHttpError = QuotaClientError<HttpClientError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subsystem_budget_throttles_only_its_own_calls() {
+        let quota = RpcQuota::new(QuotaConfig {
+            requests_per_second: 0,
+            compute_units_per_second: 1_000,
+            method_costs: HashMap::new(),
+            budgets: [("enrichment".to_string(), Fraction::new(0.1).unwrap())].into_iter().collect(),
+            retry: RetryPolicy::default(),
+        });
+
+        // Бюджет обогащения — 100 CU/с: второй eth_getLogs (75 CU) ждёт пополнения
+        for _ in 0..2 {
+            quota.acquire("enrichment", "eth_getLogs").await;
+        }
+        quota.acquire("detector", "eth_call").await;
+
+        let usage = quota.usage();
+        let enrichment = usage.iter().find(|u| u.subsystem == "enrichment").unwrap();
+        assert_eq!((enrichment.requests, enrichment.compute_units), (2, 150));
+        assert!(enrichment.throttled_ms > 0);
+        let detector = usage.iter().find(|u| u.subsystem == "detector").unwrap();
+        assert_eq!(detector.throttled_ms, 0);
+        assert!(!is_idempotent("eth_sendRawTransaction"));
+    }
+}
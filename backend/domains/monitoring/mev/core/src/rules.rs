@@ -0,0 +1,442 @@
+use crate::address::ChecksummedAddress;
+use crate::detector::{MevAlert, MevType};
+use crate::tx::Tx;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Ошибки движка правил
+#[derive(Debug, Error)]
+pub enum RuleError {
+    #[error("Parse error in rule '{rule}' at {pos}: {msg}")]
+    ParseError { rule: String, pos: usize, msg: String },
+
+    #[error("Duplicate rule name: {0}")]
+    DuplicateRule(String),
+}
+
+/// Правило оператора в том виде, как оно задаётся в конфигурации
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSpec {
+    pub name: String,
+    /// Условие на языке правил, например
+    /// `alert.mev_type == "Sandwich" && tx.value > 500 && enrichment.target.label == null`
+    pub condition: String,
+    #[serde(default = "default_risk_score")]
+    pub risk_score: f64,
+}
+
+fn default_risk_score() -> f64 {
+    0.5
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// AST выражения правила
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Path(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: [&str; 10] = ["&&", "||", "==", "!=", ">=", "<=", ">", "<", "!", "."];
+
+/// Разбивает условие на токены вместе с позициями
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, (usize, String)> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push((i, Token::LParen));
+            i += 1;
+        } else if c == ')' {
+            tokens.push((i, Token::RParen));
+            i += 1;
+        } else if c == '"' {
+            let start = i;
+            i += 1;
+            let mut s = String::new();
+            while i < chars.len() && chars[i] != '"' {
+                s.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err((start, "unterminated string".into()));
+            }
+            i += 1;
+            tokens.push((start, Token::Str(s)));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == 'e' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            let raw: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let n = raw
+                .parse::<f64>()
+                .map_err(|_| (start, format!("invalid number '{}'", raw)))?;
+            tokens.push((start, Token::Number(n)));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(chars[start..i].iter().collect())));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) => {
+                    tokens.push((i, Token::Op(op)));
+                    i += op.len();
+                }
+                None => return Err((i, format!("unexpected character '{}'", c))),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Рекурсивный парсер с приоритетами: `||` < `&&` < `!` < сравнения
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    src_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map(|(p, _)| *p).unwrap_or(self.src_len)
+    }
+
+    fn eat_op(&mut self, op: &'static str) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, (usize, String)> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_op("||") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, (usize, String)> {
+        let mut lhs = self.parse_not()?;
+        while self.eat_op("&&") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.parse_not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, (usize, String)> {
+        if self.eat_op("!") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_cmp()
+    }
+
+    fn parse_cmp(&mut self) -> Result<Expr, (usize, String)> {
+        let lhs = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CmpOp::Eq,
+            Some(Token::Op("!=")) => CmpOp::Ne,
+            Some(Token::Op(">")) => CmpOp::Gt,
+            Some(Token::Op(">=")) => CmpOp::Ge,
+            Some(Token::Op("<")) => CmpOp::Lt,
+            Some(Token::Op("<=")) => CmpOp::Le,
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs = self.parse_primary()?;
+        Ok(Expr::Cmp(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, (usize, String)> {
+        let at = self.offset();
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(_, t)| t.clone())
+            .ok_or((at, "unexpected end of condition".to_string()))?;
+        self.pos += 1;
+
+        match token {
+            Token::Number(n) => Ok(Expr::Literal(json!(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::LParen => {
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err((self.offset(), "expected ')'".into()));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Ident(id) => match id.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    let mut path = vec![id];
+                    while self.eat_op(".") {
+                        match self.tokens.get(self.pos) {
+                            Some((_, Token::Ident(seg))) => {
+                                path.push(seg.clone());
+                                self.pos += 1;
+                            }
+                            _ => return Err((self.offset(), "expected field name after '.'".into())),
+                        }
+                    }
+                    Ok(Expr::Path(path))
+                }
+            },
+            other => Err((at, format!("unexpected token {:?}", other))),
+        }
+    }
+}
+
+fn parse(name: &str, src: &str) -> Result<Expr, RuleError> {
+    let to_err = |(pos, msg): (usize, String)| RuleError::ParseError {
+        rule: name.to_string(),
+        pos,
+        msg,
+    };
+
+    let tokens = tokenize(src).map_err(to_err)?;
+    let mut parser = Parser { tokens, pos: 0, src_len: src.len() };
+    let expr = parser.parse_or().map_err(to_err)?;
+
+    if parser.pos != parser.tokens.len() {
+        return Err(to_err((parser.offset(), "unexpected trailing input".into())));
+    }
+    Ok(expr)
+}
+
+fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().map(|f| f != 0.0).unwrap_or(false),
+        Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
+/// Строка вида `0x` + 40 hex; контекст печатает адреса в EIP-55, а правила пишут как угодно
+fn as_address(s: &str) -> Option<ChecksummedAddress> {
+    (s.len() == 42 && s.starts_with("0x")).then(|| s.parse().ok()).flatten()
+}
+
+fn compare(op: CmpOp, lhs: &Value, rhs: &Value) -> bool {
+    let ordering = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => match (as_address(a), as_address(b)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => Some(a.cmp(b)),
+        },
+        _ => None,
+    };
+
+    match (op, ordering) {
+        (CmpOp::Eq, Some(o)) => o.is_eq(),
+        (CmpOp::Ne, Some(o)) => o.is_ne(),
+        (CmpOp::Eq, None) => lhs == rhs,
+        (CmpOp::Ne, None) => lhs != rhs,
+        (CmpOp::Gt, Some(o)) => o.is_gt(),
+        (CmpOp::Ge, Some(o)) => o.is_ge(),
+        (CmpOp::Lt, Some(o)) => o.is_lt(),
+        (CmpOp::Le, Some(o)) => o.is_le(),
+        // Упорядочивание несравнимых значений (например, null > 5) всегда ложно
+        _ => false,
+    }
+}
+
+impl Expr {
+    fn eval(&self, ctx: &Value) -> Value {
+        match self {
+            Expr::Literal(v) => v.clone(),
+            Expr::Path(path) => path
+                .iter()
+                .try_fold(ctx, |cur, seg| cur.get(seg))
+                .cloned()
+                .unwrap_or(Value::Null),
+            Expr::Not(e) => Value::Bool(!truthy(&e.eval(ctx))),
+            Expr::And(a, b) => Value::Bool(truthy(&a.eval(ctx)) && truthy(&b.eval(ctx))),
+            Expr::Or(a, b) => Value::Bool(truthy(&a.eval(ctx)) || truthy(&b.eval(ctx))),
+            Expr::Cmp(op, a, b) => Value::Bool(compare(*op, &a.eval(ctx), &b.eval(ctx))),
+        }
+    }
+}
+
+struct CompiledRule {
+    spec: RuleSpec,
+    expr: Expr,
+}
+
+/// Движок пользовательских правил поверх транзакций и алертов
+pub struct RuleEngine {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleEngine {
+    /// Компилирует правила; ошибка в любом правиле отклоняет весь набор
+    pub fn new(specs: Vec<RuleSpec>) -> Result<Self, RuleError> {
+        let mut names = HashSet::new();
+        let mut rules = Vec::with_capacity(specs.len());
+
+        for spec in specs {
+            if !names.insert(spec.name.clone()) {
+                return Err(RuleError::DuplicateRule(spec.name));
+            }
+            let expr = parse(&spec.name, &spec.condition)?;
+            rules.push(CompiledRule { spec, expr });
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Исходные описания правил в порядке проверки
+    pub fn specs(&self) -> Vec<RuleSpec> {
+        self.rules.iter().map(|r| r.spec.clone()).collect()
+    }
+
+    /// Проверяет правила для транзакции и уже найденных по ней алертов.
+    /// Каждое правило срабатывает не более одного раза на транзакцию.
+    pub fn evaluate(&self, tx: &Tx, alerts: &[MevAlert], enrichment: &Value) -> Vec<MevAlert> {
+        let contexts: Vec<(Option<&MevAlert>, Value)> = std::iter::once(None)
+            .chain(alerts.iter().map(Some))
+            .map(|alert| {
+                (alert, json!({ "tx": tx_context(tx), "alert": alert, "enrichment": enrichment }))
+            })
+            .collect();
+
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                contexts
+                    .iter()
+                    .find(|(_, ctx)| truthy(&rule.expr.eval(ctx)))
+                    .map(|(alert, _)| self.build_alert(rule, tx, *alert))
+            })
+            .collect()
+    }
+
+    fn build_alert(&self, rule: &CompiledRule, tx: &Tx, source: Option<&MevAlert>) -> MevAlert {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        MevAlert {
+            schema_version: crate::schema::MEV_ALERT_VERSION,
+            mev_type: MevType::Custom(rule.spec.name.clone()),
+            profit: source.map(|a| a.profit).unwrap_or_default(),
+            risk_score: rule.spec.risk_score.clamp(0.0, 1.0) as f32,
+            timestamp,
+            metadata: json!({
+                "rule": rule.spec.name,
+                "condition": rule.spec.condition,
+                "tx": tx,
+                "source_alert": source,
+            }),
+            latency: None,
+            confirmed_onchain: None,
+        }
+    }
+}
+
+/// `tx` в условиях правил: суммы числами — `value` в ETH, `gas_price` в wei
+fn tx_context(tx: &Tx) -> Value {
+    json!({
+        "from": tx.from,
+        "to": tx.to,
+        "value": tx.value.to_eth_f64(),
+        "gas_price": tx.gas_price.saturating_u64() as f64,
+        "input": tx.input,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(src: &str, ctx: Value) -> bool {
+        truthy(&parse("test", src).unwrap().eval(&ctx))
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let ctx = json!({ "a": 1, "b": 2 });
+        assert!(eval("a == 1 || b == 3 && false", ctx.clone()));
+        assert!(!eval("(a == 1 || b == 3) && false", ctx.clone()));
+        assert!(eval("!(a > b)", ctx));
+    }
+
+    #[test]
+    fn test_missing_fields_are_null() {
+        let ctx = json!({ "enrichment": { "target": { "age_hours": 2 } } });
+        assert!(eval("enrichment.target.label == null && enrichment.target.age_hours < 24", ctx.clone()));
+        assert!(!eval("enrichment.target.missing > 0", ctx));
+    }
+
+    #[test]
+    fn test_addresses_compare_in_any_case() {
+        let ctx = json!({ "tx": { "to": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2" } });
+        assert!(eval("tx.to == \"0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\"", ctx.clone()));
+        assert!(!eval("tx.to != \"0xC02AAA39B223FE8D0A0E5C4F27EAD9083C756CC2\"", ctx));
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        match parse("bad", "tx.value > ") {
+            Err(RuleError::ParseError { pos, .. }) => assert_eq!(pos, 11),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(parse("bad", "tx.value > 5 5").is_err());
+        assert!(parse("bad", "\"open").is_err());
+    }
+}
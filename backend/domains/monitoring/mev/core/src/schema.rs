@@ -0,0 +1,221 @@
+/// Версия `BusAlert`
+pub const BUS_ALERT_VERSION: u32 = 1;
+/// Версия `MevAlert`
+pub const MEV_ALERT_VERSION: u32 = 1;
+/// Версия `correlation::Incident`
+pub const INCIDENT_VERSION: u32 = 1;
+/// Версия `sink::Message`
+pub const MESSAGE_VERSION: u32 = 2;
+/// Версия `digest::Digest`
+pub const DIGEST_VERSION: u32 = 1;
+/// Версия `state_diff::StateDiffReport`
+pub const STATE_DIFF_VERSION: u32 = 1;
+/// Версия `forensics::BlockReport`
+pub const BLOCK_REPORT_VERSION: u32 = 1;
+
+/// Публичный формат, который читают внешние системы, и его текущая версия. Схема
+/// сверяется тестом со снимком `schemas/<тип>.v<версия>.json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaInfo {
+    pub name: &'static str,
+    pub version: u32,
+}
+
+pub const SCHEMAS: &[SchemaInfo] = &[
+    SchemaInfo { name: "BusAlert", version: BUS_ALERT_VERSION },
+    SchemaInfo { name: "MevAlert", version: MEV_ALERT_VERSION },
+    SchemaInfo { name: "Incident", version: INCIDENT_VERSION },
+    SchemaInfo { name: "Message", version: MESSAGE_VERSION },
+    SchemaInfo { name: "Digest", version: DIGEST_VERSION },
+    SchemaInfo { name: "StateDiffReport", version: STATE_DIFF_VERSION },
+    SchemaInfo { name: "BlockReport", version: BLOCK_REPORT_VERSION },
+];
+
+/// `(формат, версия, что изменилось и как читать прежнюю)` для каждой версии выше первой
+pub const MIGRATIONS: &[(&str, u32, &str)] = &[
+    ("Message", 2, "added `kind` (`alert` or `digest`); read a v1 message without `alert_id` as a digest"),
+];
+
+/// Версия записей, сохранённых до появления поля `schema_version`
+pub fn unversioned() -> u32 {
+    1
+}
+
+#[cfg(feature = "server")]
+pub use generate::{all, json_schema};
+
+#[cfg(feature = "server")]
+mod generate {
+    use super::SCHEMAS;
+    use crate::bus::{AlertLevel, BusAlert};
+    use crate::correlation::{Incident, Signal, SignalClass};
+    use crate::detector::{MevAlert, MevType};
+    use crate::digest::{Digest, DigestPeriod, DigestRow, DigestSection};
+    use crate::forensics::{BlockReport, CoinbaseTransfer, InBlockMev, ProposerPayment};
+    use crate::labels::{AddressLabel, LabelCategory};
+    use crate::pipeline::LatencyBudget;
+    use crate::sink::{Message, MessageKind};
+    use crate::state_diff::{AccountDiff, BalanceChange, SlotChange, StateDiffReport};
+    use serde_json::{json, Map, Value};
+    use std::collections::BTreeMap;
+    use utoipa::OpenApi;
+
+    const COMPONENTS_REF: &str = "#/components/schemas/";
+
+    /// Вложенные типы перечислены явно: utoipa не регистрирует их сам
+    #[derive(OpenApi)]
+    #[openapi(components(schemas(
+        BusAlert,
+        AlertLevel,
+        MevAlert,
+        MevType,
+        LatencyBudget,
+        Incident,
+        Signal,
+        SignalClass,
+        Message,
+        MessageKind,
+        Digest,
+        DigestPeriod,
+        DigestSection,
+        DigestRow,
+        StateDiffReport,
+        AccountDiff,
+        BalanceChange,
+        SlotChange,
+        BlockReport,
+        InBlockMev,
+        CoinbaseTransfer,
+        ProposerPayment,
+        AddressLabel,
+        LabelCategory,
+    )))]
+    struct AlertSchemas;
+
+    fn components() -> Map<String, Value> {
+        let schemas = AlertSchemas::openapi().components.map(|c| c.schemas).unwrap_or_default();
+        match serde_json::to_value(schemas) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    /// Собирает `name` и все типы, на которые он ссылается
+    fn collect(components: &Map<String, Value>, name: &str, defs: &mut BTreeMap<String, Value>) {
+        if defs.contains_key(name) {
+            return;
+        }
+        let Some(schema) = components.get(name) else {
+            return;
+        };
+        defs.insert(name.to_string(), schema.clone());
+        let mut refs = Vec::new();
+        find_refs(schema, &mut refs);
+        for referenced in refs {
+            collect(components, &referenced, defs);
+        }
+    }
+
+    fn find_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value.as_str()) {
+                        ("$ref", Some(target)) => refs.extend(target.strip_prefix(COMPONENTS_REF).map(str::to_string)),
+                        _ => find_refs(value, refs),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| find_refs(item, refs)),
+            _ => {}
+        }
+    }
+
+    fn rewrite_refs(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match value {
+                        Value::String(target) if key == "$ref" => {
+                            *target = target.replace(COMPONENTS_REF, "#/$defs/");
+                        }
+                        _ => rewrite_refs(value),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+            _ => {}
+        }
+    }
+
+    /// Самодостаточная схема формата со вложенными типами в `$defs`. Диалект — схемы
+    /// OpenAPI 3.0 (`nullable` вместо `type: [.., "null"]`)
+    pub fn json_schema(name: &str) -> Option<Value> {
+        let info = SCHEMAS.iter().find(|s| s.name == name)?;
+        let mut defs = BTreeMap::new();
+        collect(&components(), name, &mut defs);
+        let Value::Object(root) = defs.remove(name)? else {
+            return None;
+        };
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": format!("https://definetly.dev/schemas/{}.v{}.json", name, info.version),
+            "title": name,
+            "x-schema-version": info.version,
+        });
+        if let Value::Object(out) = &mut schema {
+            out.extend(root);
+            if !defs.is_empty() {
+                out.insert("$defs".into(), json!(defs));
+            }
+        }
+        rewrite_refs(&mut schema);
+        Some(schema)
+    }
+
+    /// Схемы всех публичных форматов по именам
+    pub fn all() -> BTreeMap<&'static str, Value> {
+        SCHEMAS.iter().filter_map(|s| Some((s.name, json_schema(s.name)?))).collect()
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Снимки коммитятся вместе с форматом. `UPDATE_SNAPSHOTS=1` пишет недостающие снимки
+    /// в `target/schemas`, откуда их копируют в `schemas/`; исходники тест не трогает
+    #[test]
+    fn test_schema_changes_bump_version() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let dir = root.join("schemas");
+        let update = std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1");
+        let mut missing = Vec::new();
+        for info in SCHEMAS {
+            let schema = json_schema(info.name).unwrap_or_else(|| panic!("no schema generated for {}", info.name));
+            let file = format!("{}.v{}.json", info.name, info.version);
+            match std::fs::read_to_string(dir.join(&file)) {
+                Ok(snapshot) => assert_eq!(
+                    serde_json::from_str::<serde_json::Value>(&snapshot).unwrap(),
+                    schema,
+                    "{} changed: bump its version in schema.rs, add a MIGRATIONS note and commit the new snapshot",
+                    info.name
+                ),
+                Err(_) if update => {
+                    let out = root.join("target").join("schemas");
+                    std::fs::create_dir_all(&out).unwrap();
+                    std::fs::write(out.join(&file), serde_json::to_string_pretty(&schema).unwrap() + "\n").unwrap();
+                }
+                Err(_) => missing.push(file),
+            }
+            assert!(
+                info.version == 1 || MIGRATIONS.iter().any(|(name, version, _)| *name == info.name && *version == info.version),
+                "{} v{} has no MIGRATIONS note",
+                info.name,
+                info.version
+            );
+        }
+        assert!(missing.is_empty(), "missing snapshots {:?}: run with UPDATE_SNAPSHOTS=1 and commit them from target/schemas", missing);
+    }
+}
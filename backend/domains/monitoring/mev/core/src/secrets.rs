@@ -0,0 +1,364 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use crate::capability::SigningCapability;
+use async_trait::async_trait;
+use ethers::signers::LocalWallet;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Invalid secret reference '{0}': expected env:, keystore: or vault:")]
+    InvalidReference(String),
+
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+
+    #[error("Vault error: {0}")]
+    VaultError(String),
+}
+
+/// Значение секрета: не печатается в Debug и затирается при drop
+#[derive(Clone)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Ссылка на секрет вместо его значения в конфиге или FFI-строке:
+/// `env:NAME`, `keystore:/path.json?password=env:PASS`, `vault:secret/path#field`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretRef {
+    Env(String),
+    Keystore { path: PathBuf, password: Box<SecretRef> },
+    Vault { path: String, field: String },
+}
+
+impl std::str::FromStr for SecretRef {
+    type Err = SecretError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // В сообщение попадает только начало строки: вместо ссылки могли передать сам ключ
+        let invalid = || SecretError::InvalidReference(format!("{}...", s.chars().take(6).collect::<String>()));
+        let (scheme, rest) = s.split_once(':').ok_or_else(invalid)?;
+
+        match scheme {
+            "env" if !rest.is_empty() => Ok(SecretRef::Env(rest.to_string())),
+            "keystore" => {
+                let (path, password) = rest.split_once("?password=").ok_or_else(invalid)?;
+                Ok(SecretRef::Keystore {
+                    path: PathBuf::from(path),
+                    password: Box::new(password.parse()?),
+                })
+            }
+            "vault" => {
+                let (path, field) = rest.split_once('#').ok_or_else(invalid)?;
+                Ok(SecretRef::Vault {
+                    path: path.to_string(),
+                    field: field.to_string(),
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Хранилище секретов HashiCorp Vault (KV v2)
+#[async_trait]
+pub trait VaultClient: Send + Sync {
+    async fn read_field(&self, path: &str, field: &str) -> Result<SecretString, SecretError>;
+}
+
+pub struct HttpVault {
+    addr: String,
+    mount: String,
+    token: SecretString,
+    client: reqwest::Client,
+}
+
+impl HttpVault {
+    pub fn new(addr: &str, mount: &str, token: SecretString) -> Self {
+        Self {
+            addr: addr.trim_end_matches('/').to_string(),
+            mount: mount.trim_matches('/').to_string(),
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Адрес и токен из стандартных `VAULT_ADDR` / `VAULT_TOKEN`
+    pub fn from_env() -> Option<Self> {
+        let addr = std::env::var("VAULT_ADDR").ok()?;
+        let token = std::env::var("VAULT_TOKEN").ok()?;
+        Some(Self::new(&addr, "secret", SecretString::new(token)))
+    }
+}
+
+#[async_trait]
+impl VaultClient for HttpVault {
+    async fn read_field(&self, path: &str, field: &str) -> Result<SecretString, SecretError> {
+        let url = format!("{}/v1/{}/data/{}", self.addr, self.mount, path.trim_matches('/'));
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Vault-Token", self.token.expose())
+            .send()
+            .await
+            .map_err(|e| SecretError::VaultError(e.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(SecretError::NotFound(format!("vault:{}", path)));
+        }
+        let body: serde_json::Value = response
+            .error_for_status()
+            .map_err(|e| SecretError::VaultError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| SecretError::VaultError(e.to_string()))?;
+
+        body["data"]["data"][field]
+            .as_str()
+            .map(|v| SecretString::new(v.to_string()))
+            .ok_or_else(|| SecretError::NotFound(format!("vault:{}#{}", path, field)))
+    }
+}
+
+#[derive(Deserialize)]
+struct Eip2335Module {
+    function: String,
+    params: serde_json::Value,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Eip2335Crypto {
+    kdf: Eip2335Module,
+    checksum: Eip2335Module,
+    cipher: Eip2335Module,
+}
+
+#[derive(Deserialize)]
+struct Eip2335Keystore {
+    crypto: Eip2335Crypto,
+    version: u32,
+}
+
+fn hex_param(params: &serde_json::Value, name: &str) -> Result<Vec<u8>, SecretError> {
+    let value = params[name]
+        .as_str()
+        .ok_or_else(|| SecretError::KeystoreError(format!("missing {}", name)))?;
+    ethers::utils::hex::decode(value).map_err(|e| SecretError::KeystoreError(e.to_string()))
+}
+
+fn u32_param(params: &serde_json::Value, name: &str) -> Result<u32, SecretError> {
+    params[name]
+        .as_u64()
+        .map(|v| v as u32)
+        .ok_or_else(|| SecretError::KeystoreError(format!("missing {}", name)))
+}
+
+/// Расшифровка keystore EIP-2335 (BLS-ключи валидаторов).
+/// Пароль нормализуется в NFKD и очищается от управляющих символов, как требует спецификация.
+pub fn decrypt_eip2335(json: &str, password: &str) -> Result<Vec<u8>, SecretError> {
+    let keystore: Eip2335Keystore =
+        serde_json::from_str(json).map_err(|e| SecretError::KeystoreError(e.to_string()))?;
+    if keystore.version != 4 {
+        return Err(SecretError::KeystoreError(format!("unsupported version {}", keystore.version)));
+    }
+    let crypto = keystore.crypto;
+
+    let password: Vec<u8> = password
+        .nfkd()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .into_bytes();
+
+    let params = &crypto.kdf.params;
+    let salt = hex_param(params, "salt")?;
+    let dklen = u32_param(params, "dklen")? as usize;
+    let mut key = vec![0u8; dklen];
+    match crypto.kdf.function.as_str() {
+        "scrypt" => {
+            let n = u32_param(params, "n")?;
+            let scrypt_params = scrypt::Params::new(n.trailing_zeros() as u8, u32_param(params, "r")?, u32_param(params, "p")?, dklen)
+                .map_err(|e| SecretError::KeystoreError(e.to_string()))?;
+            scrypt::scrypt(&password, &salt, &scrypt_params, &mut key)
+                .map_err(|e| SecretError::KeystoreError(e.to_string()))?;
+        }
+        "pbkdf2" => {
+            pbkdf2::pbkdf2_hmac::<Sha256>(&password, &salt, u32_param(params, "c")?, &mut key);
+        }
+        other => return Err(SecretError::KeystoreError(format!("unsupported kdf {}", other))),
+    }
+    if key.len() < 32 {
+        return Err(SecretError::KeystoreError("dklen must be at least 32".into()));
+    }
+
+    let cipher_message = ethers::utils::hex::decode(&crypto.cipher.message)
+        .map_err(|e| SecretError::KeystoreError(e.to_string()))?;
+    let mut hasher = Sha256::new();
+    hasher.update(&key[16..32]);
+    hasher.update(&cipher_message);
+    if ethers::utils::hex::encode(hasher.finalize()) != crypto.checksum.message.to_lowercase() {
+        key.zeroize();
+        return Err(SecretError::KeystoreError("invalid password (checksum mismatch)".into()));
+    }
+
+    if crypto.cipher.function != "aes-128-ctr" {
+        return Err(SecretError::KeystoreError(format!("unsupported cipher {}", crypto.cipher.function)));
+    }
+    let iv = hex_param(&crypto.cipher.params, "iv")?;
+    let mut secret = cipher_message;
+    Aes128Ctr::new_from_slices(&key[..16], &iv)
+        .map_err(|e| SecretError::KeystoreError(e.to_string()))?
+        .apply_keystream(&mut secret);
+    key.zeroize();
+
+    Ok(secret)
+}
+
+/// Расшифровывает keystore: EIP-2335 (`version: 4`) или Web3 Secret Storage v3
+pub fn decrypt_keystore(path: &Path, password: &str) -> Result<Vec<u8>, SecretError> {
+    let json = std::fs::read_to_string(path)?;
+    let version = serde_json::from_str::<serde_json::Value>(&json)
+        .map_err(|e| SecretError::KeystoreError(e.to_string()))?["version"]
+        .as_u64();
+
+    match version {
+        Some(4) => decrypt_eip2335(&json, password),
+        _ => eth_keystore::decrypt_key(path, password).map_err(|e| SecretError::KeystoreError(e.to_string())),
+    }
+}
+
+/// Разрешает ссылки на секреты для клиента рестейкинга, релеев и API-сервера
+pub struct SecretManager {
+    vault: Option<Box<dyn VaultClient>>,
+}
+
+impl SecretManager {
+    pub fn new(vault: Option<Box<dyn VaultClient>>) -> Self {
+        Self { vault }
+    }
+
+    /// Vault подключается, если заданы `VAULT_ADDR` и `VAULT_TOKEN`
+    pub fn from_env() -> Self {
+        Self::new(HttpVault::from_env().map(|v| Box::new(v) as Box<dyn VaultClient>))
+    }
+
+    /// Значение секрета; бинарные значения keystore возвращаются в hex
+    pub async fn resolve(&self, reference: &str) -> Result<SecretString, SecretError> {
+        self.resolve_ref(&reference.parse()?).await
+    }
+
+    async fn resolve_ref(&self, reference: &SecretRef) -> Result<SecretString, SecretError> {
+        match reference {
+            SecretRef::Keystore { path, password } => {
+                let password = self.resolve_plain(password).await?;
+                let mut key = decrypt_keystore(path, password.expose())?;
+                let hex = ethers::utils::hex::encode(&key);
+                key.zeroize();
+                Ok(SecretString::new(hex))
+            }
+            other => self.resolve_plain(other).await,
+        }
+    }
+
+    /// Секреты без вложенности: пароль keystore не может сам быть keystore
+    async fn resolve_plain(&self, reference: &SecretRef) -> Result<SecretString, SecretError> {
+        match reference {
+            SecretRef::Env(name) => std::env::var(name)
+                .map(SecretString::new)
+                .map_err(|_| SecretError::NotFound(format!("env:{}", name))),
+            SecretRef::Vault { path, field } => match &self.vault {
+                Some(vault) => vault.read_field(path, field).await,
+                None => Err(SecretError::VaultError("vault is not configured".into())),
+            },
+            SecretRef::Keystore { path, .. } => Err(SecretError::InvalidReference(format!(
+                "keystore password cannot be another keystore ({})",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Кошелёк из секрета с приватным ключом secp256k1 (hex); только для узла с правом подписи
+    pub async fn wallet(&self, reference: &str, _signing: &SigningCapability) -> Result<LocalWallet, SecretError> {
+        let key = self.resolve(reference).await?;
+        key.expose()
+            .trim_start_matches("0x")
+            .parse::<LocalWallet>()
+            .map_err(|_| SecretError::KeystoreError(format!("{} is not a valid private key", reference)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!("env:RELAY_KEY".parse::<SecretRef>().unwrap(), SecretRef::Env("RELAY_KEY".into()));
+        assert_eq!(
+            "keystore:/keys/op.json?password=env:KS_PASS".parse::<SecretRef>().unwrap(),
+            SecretRef::Keystore {
+                path: "/keys/op.json".into(),
+                password: Box::new(SecretRef::Env("KS_PASS".into())),
+            }
+        );
+        assert!("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".parse::<SecretRef>().is_err());
+    }
+
+    #[test]
+    fn test_eip2335_spec_vector() {
+        // Тестовый вектор pbkdf2 из EIP-2335
+        let json = r#"{
+            "crypto": {
+                "kdf": {"function": "pbkdf2", "params": {"dklen": 32, "c": 262144, "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"}, "message": ""},
+                "checksum": {"function": "sha256", "params": {},
+                    "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"},
+                "cipher": {"function": "aes-128-ctr", "params": {"iv": "264daa3f303d7259501c93d997d84fe6"},
+                    "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"}
+            },
+            "version": 4
+        }"#;
+        let password = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+
+        let secret = decrypt_eip2335(json, password).unwrap();
+        assert_eq!(
+            ethers::utils::hex::encode(secret),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert!(decrypt_eip2335(json, "wrong").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::compat::{Address, U256};
+use crate::multicall::{Batch, BatchReader, MulticallError};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use ethers::contract::abigen;
+use ethers::providers::Middleware;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::watch;
+
+abigen!(
+    UniswapV2Pair,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+    ]"#
+);
+
+/// WETH в mainnet: сторона пары, по которой глубина считается в ETH
+const MAINNET_WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+/// Класс риска токена пула
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenTier {
+    /// ETH, WBTC и подобные ликвидные активы
+    Major,
+    /// Стейблкоины: жертва обычно меняет весь баланс
+    Stable,
+    #[default]
+    Standard,
+    /// Малоликвидные токены
+    LongTail,
+}
+
+/// Множители severity по классам токенов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TierWeights {
+    pub major: f64,
+    pub stable: f64,
+    pub standard: f64,
+    pub long_tail: f64,
+}
+
+impl Default for TierWeights {
+    fn default() -> Self {
+        Self { major: 0.8, stable: 1.2, standard: 1.0, long_tail: 1.3 }
+    }
+}
+
+impl TierWeights {
+    pub fn weight(&self, tier: TokenTier) -> f64 {
+        match tier {
+            TokenTier::Major => self.major,
+            TokenTier::Stable => self.stable,
+            TokenTier::Standard => self.standard,
+            TokenTier::LongTail => self.long_tail,
+        }
+    }
+}
+
+/// Настройки severity сэндвичей (`[detector.severity]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityConfig {
+    /// Вес доли сделки, которую забрал атакующий: та же прибыль на мелкой сделке тяжелее
+    pub loss_share_weight: f64,
+    /// Вес размера сделки относительно глубины пула
+    pub pool_share_weight: f64,
+    /// Класс по адресу токена; остальные — `default_tier`
+    pub tiers: HashMap<ChecksummedAddress, TokenTier>,
+    pub default_tier: TokenTier,
+    pub tier_weights: TierWeights,
+    /// Цель транзакций жертв (пара или роутер) -> пара Uniswap V2 с WETH, глубина которой
+    /// обновляется каждый блок
+    pub pools: HashMap<ChecksummedAddress, ChecksummedAddress>,
+    /// WETH этой сети; по умолчанию mainnet
+    pub weth: ChecksummedAddress,
+}
+
+impl Default for SeverityConfig {
+    fn default() -> Self {
+        Self {
+            loss_share_weight: 0.3,
+            pool_share_weight: 0.2,
+            tiers: HashMap::new(),
+            default_tier: TokenTier::Standard,
+            tier_weights: TierWeights::default(),
+            pools: HashMap::new(),
+            weth: MAINNET_WETH.parse().unwrap(),
+        }
+    }
+}
+
+/// Глубина пула: основной токен и ликвидность в ETH-эквиваленте
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolDepth {
+    pub token: ChecksummedAddress,
+    pub depth: WeiAmount,
+}
+
+/// Оценка severity сэндвича с учётом жертвы.
+/// Глубины пулов `pools` обновляет `run` по резервам раз в блок
+pub struct SeverityModel {
+    config: SeverityConfig,
+    pools: RwLock<HashMap<ChecksummedAddress, PoolDepth>>,
+    errors: TaskErrors,
+}
+
+impl SeverityModel {
+    pub fn new(config: SeverityConfig) -> Self {
+        Self { config, pools: RwLock::new(HashMap::new()), errors: TaskErrors::default() }
+    }
+
+    /// Ошибки обновления глубин пулов
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn has_pools(&self) -> bool {
+        !self.config.pools.is_empty()
+    }
+
+    pub fn set_pool(&self, pool: ChecksummedAddress, depth: PoolDepth) {
+        self.pools.write().unwrap().insert(pool, depth);
+    }
+
+    fn tier(&self, token: &ChecksummedAddress) -> TokenTier {
+        self.config.tiers.get(token).copied().unwrap_or(self.config.default_tier)
+    }
+
+    /// Глубины пулов `pools` по резервам пар на последнем блоке; возвращает число обновлённых
+    pub async fn refresh<M: Middleware + 'static>(&self, reader: &BatchReader<M>, provider: Arc<M>) -> Result<usize, MulticallError> {
+        let mut batch = Batch::new();
+        let mut calls = Vec::new();
+        for (target, pair) in &self.config.pools {
+            let pair = UniswapV2Pair::new(Address::from(*pair), provider.clone());
+            calls.push((*target, batch.add(&pair.token_0())?, batch.add(&pair.token_1())?, batch.add(&pair.get_reserves())?));
+        }
+        let results = reader.execute(&batch, None).await?;
+
+        let weth = Address::from(self.config.weth);
+        let mut updated = 0;
+        for (target, token0, token1, reserves) in calls {
+            let (token0, token1, (reserve0, reserve1, _)) = (results.get(&token0)?, results.get(&token1)?, results.get(&reserves)?);
+            // Глубина — обе стороны пары в ETH, по резерву WETH; основной токен — другая сторона
+            let (token, weth_reserve) = if token0 == weth {
+                (token1, reserve0)
+            } else if token1 == weth {
+                (token0, reserve1)
+            } else {
+                self.errors.record(format!("severity pool for {}: pair has no WETH side", target));
+                continue;
+            };
+            let depth = WeiAmount::from_wei(U256::from(weth_reserve) * 2);
+            self.set_pool(target, PoolDepth { token: token.into(), depth });
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Обновляет глубины на каждом новом блоке из `heads`
+    pub async fn run<M: Middleware + 'static>(&self, provider: Arc<M>, mut heads: watch::Receiver<u64>) {
+        let reader = BatchReader::new(provider.clone());
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            if let Err(e) = self.refresh(&reader, provider.clone()).await {
+                self.errors.record(format!("severity pool depths at block {}: {}", block, e));
+            }
+        }
+    }
+
+    /// Risk score сэндвича и его составляющие для метаданных алерта. `trade` — выход жертвы
+    /// без атакующего, `loss` — сколько она потеряла в сэндвиче, оба в ETH-эквиваленте.
+    /// Без сведений о пуле учитываются только доля потерь и класс по умолчанию
+    pub fn score(&self, base: f32, victim: &Tx, trade: WeiAmount, loss: WeiAmount) -> (f32, serde_json::Value) {
+        let trade = trade.to_eth_f64();
+        let loss_share = if trade > 0.0 { (loss.to_eth_f64() / trade).min(1.0) } else { 0.0 };
+
+        let pool = self.pools.read().unwrap().get(&victim.to).copied();
+        let pool_share = match pool {
+            Some(pool) if pool.depth > WeiAmount::ZERO => (trade / pool.depth.to_eth_f64()).min(1.0),
+            _ => 0.0,
+        };
+        let tier = pool.map(|p| self.tier(&p.token)).unwrap_or(self.config.default_tier);
+
+        let raw = base as f64
+            + self.config.loss_share_weight * loss_share
+            + self.config.pool_share_weight * pool_share;
+        let score = (raw * self.config.tier_weights.weight(tier)).clamp(0.0, 1.0) as f32;
+
+        (score, json!({ "loss_share": loss_share, "pool_share": pool_share, "tier": tier }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::Bytes;
+    use ethers::abi::{encode, Token};
+    use ethers::providers::Provider;
+
+    fn addr(byte: u8) -> ChecksummedAddress {
+        crate::compat::Address::repeat_byte(byte).into()
+    }
+
+    /// Обмен токенов: `value` нулевой, размер сделки приходит из симуляции
+    fn victim() -> Tx {
+        Tx {
+            from: addr(1),
+            to: addr(2),
+            value: WeiAmount::ZERO,
+            gas_price: WeiAmount::from_gwei(20.0),
+            input: vec![0; 4],
+        }
+    }
+
+    #[test]
+    fn test_same_profit_weighs_more_on_retail_stable_swap() {
+        let mut config = SeverityConfig::default();
+        config.tiers.insert(addr(9), TokenTier::Stable);
+        let model = SeverityModel::new(config);
+        model.set_pool(addr(2), PoolDepth { token: addr(9), depth: WeiAmount::from_eth(100.0) });
+
+        let loss = WeiAmount::from_eth(0.1);
+        let (retail, meta) = model.score(0.2, &victim(), WeiAmount::from_eth(1.0), loss);
+        let (whale, _) = model.score(0.2, &victim(), WeiAmount::from_eth(10.0), loss);
+
+        assert!(retail > whale);
+        assert_eq!(meta["tier"], "stable");
+        assert!((meta["pool_share"].as_f64().unwrap() - 0.01).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_takes_depth_from_weth_side() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let mut config = SeverityConfig::default();
+        config.pools.insert(addr(2), addr(3));
+        let weth = Address::from(config.weth);
+        let model = SeverityModel::new(config);
+
+        // Ответы снимаются с конца: код Multicall3 (нет), token0, token1, getReserves
+        let reserves = [Token::Uint(U256::from(5_000u64)), Token::Uint(U256::exp10(18)), Token::Uint(U256::zero())];
+        mock.push::<Bytes, _>(Bytes::from(encode(&reserves))).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Address(weth)]))).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Address(addr(9).into())]))).unwrap();
+        mock.push::<Bytes, _>(Bytes::new()).unwrap();
+
+        assert_eq!(model.refresh(&BatchReader::new(provider.clone()), provider).await.unwrap(), 1);
+        let pool = model.pools.read().unwrap()[&addr(2)];
+        assert_eq!(pool, PoolDepth { token: addr(9), depth: WeiAmount::from_eth(2.0) });
+    }
+}
@@ -0,0 +1,274 @@
+use crate::address::ChecksummedAddress;
+use crate::cancel::SimulationJob;
+use crate::chain::{Ethereum, SharedChainAdapter};
+use crate::compat::H256;
+use crate::create2::DeploymentWatch;
+use crate::dedup::{tx_fingerprint, SeenSet};
+use crate::detector::{adaptive_thresholds, effective_thresholds, finish_alerts, record_hit, Heuristics, MevAlert, MevThresholds};
+use crate::enrichment::{Enricher, Enrichment};
+use crate::fingerprint::BotFingerprinter;
+use crate::funding::FundingGraph;
+use crate::gas_oracle::GasOracle;
+use crate::labels::SharedLabelResolver;
+use crate::registry::DetectorRegistry;
+use crate::rules::RuleEngine;
+use crate::severity::SeverityModel;
+use crate::simulator::Simulator;
+use crate::trap;
+use crate::tx::Tx;
+use crate::victims::VictimTracker;
+use dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Неизменяемые настройки анализа; заменяются целиком, а не правятся на месте
+#[derive(Clone)]
+pub struct DetectorSnapshot {
+    pub thresholds: MevThresholds,
+    pub rules: Option<Arc<RuleEngine>>,
+    pub labels: Option<SharedLabelResolver>,
+    pub severity: Option<Arc<SeverityModel>>,
+    pub registry: Option<Arc<DetectorRegistry>>,
+    /// Сеть детектора; по умолчанию mainnet
+    pub chain: SharedChainAdapter,
+    pub gas_oracle: Option<Arc<GasOracle>>,
+}
+
+impl DetectorSnapshot {
+    pub fn new(thresholds: MevThresholds) -> Self {
+        Self {
+            thresholds,
+            rules: None,
+            labels: None,
+            severity: None,
+            registry: None,
+            chain: Arc::new(Ethereum::mainnet()),
+            gas_oracle: None,
+        }
+    }
+}
+
+/// Детектор для нескольких задач приёма сразу: `analyze(&self, ..)`.
+/// Пул шардирован по целевому контракту (DashMap): транзакции к разным контрактам
+/// анализируются параллельно, к одному — по очереди, как в `MevDetector`.
+/// Симуляторы — пул, каждый под своим мьютексом
+pub struct SharedDetector {
+    pool: DashMap<ChecksummedAddress, VecDeque<(Tx, u64)>>,
+    ttl_seconds: u64,
+    config: RwLock<Arc<DetectorSnapshot>>,
+    enrichers: Vec<Box<dyn Enricher>>,
+    simulators: Vec<Mutex<Box<dyn Simulator>>>,
+    seen: Option<Mutex<SeenSet>>,
+    victims: Option<Mutex<VictimTracker>>,
+    fingerprints: Option<Arc<BotFingerprinter>>,
+    funding: Option<Arc<FundingGraph>>,
+    deployments: Option<Arc<DeploymentWatch>>,
+}
+
+impl SharedDetector {
+    /// `simulators` — по одному на параллельную симуляцию; не может быть пустым
+    pub fn new<S: Simulator + 'static>(simulators: Vec<S>, ttl_seconds: u64, config: DetectorSnapshot) -> Self {
+        assert!(!simulators.is_empty(), "SharedDetector needs at least one simulator");
+        Self {
+            pool: DashMap::new(),
+            ttl_seconds,
+            config: RwLock::new(Arc::new(config)),
+            enrichers: Vec::new(),
+            simulators: simulators.into_iter().map(|s| Mutex::new(Box::new(s) as Box<dyn Simulator>)).collect(),
+            seen: None,
+            victims: None,
+            fingerprints: None,
+            funding: None,
+            deployments: None,
+        }
+    }
+
+    pub fn with_dedup(mut self, window: Duration, capacity: usize) -> Self {
+        self.seen = Some(Mutex::new(SeenSet::new(window, capacity)));
+        self
+    }
+
+    /// Повторные сэндвичи одной жертвы поднимают severity и дают `RepeatedTargeting`
+    pub fn with_victim_tracking(mut self, tracker: VictimTracker) -> Self {
+        self.victims = Some(Mutex::new(tracker));
+        self
+    }
+
+    /// Профит относится на кластер атакующих по графу пополнений; в алертах — `attacker_cluster`.
+    /// Тот же граф стоит подключить источником обогащения, чтобы он видел переводы
+    pub fn with_funding(mut self, funding: Arc<FundingGraph>) -> Self {
+        self.funding = Some(funding);
+        self
+    }
+
+    /// Каждая транзакция сверяется с недавними на копирование calldata;
+    /// алерты с атакующим-ботом получают `attacker_fingerprint`
+    pub fn with_fingerprints(mut self, fingerprints: Arc<BotFingerprinter>) -> Self {
+        self.fingerprints = Some(fingerprints);
+        self
+    }
+
+    /// Деплои через фабрики CREATE2 сверяются с мемпулом на захват адреса и гонку за `initialize`
+    pub fn with_deployments(mut self, deployments: Arc<DeploymentWatch>) -> Self {
+        self.deployments = Some(deployments);
+        self
+    }
+
+    pub fn with_enricher(mut self, enricher: Box<dyn Enricher>) -> Self {
+        self.enrichers.push(enricher);
+        self
+    }
+
+    /// Текущий снимок настроек
+    pub fn config(&self) -> Arc<DetectorSnapshot> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Атомарно подменяет настройки; идущие анализы доработают со старым снимком
+    pub fn reconfigure(&self, config: DetectorSnapshot) {
+        *self.config.write().unwrap() = Arc::new(config);
+    }
+
+    pub fn set_thresholds(&self, thresholds: MevThresholds) {
+        let mut next = DetectorSnapshot::clone(&self.config());
+        next.thresholds = thresholds;
+        self.reconfigure(next);
+    }
+
+    pub fn analyze(&self, tx: Tx) -> Vec<MevAlert> {
+        self.analyze_hashed(tx, None, None)
+    }
+
+    /// То же, что `MevDetector::analyze_hashed`
+    pub fn analyze_hashed(&self, tx: Tx, hash: Option<H256>, precomputed: Option<Enrichment>) -> Vec<MevAlert> {
+        self.analyze_with(tx, hash, precomputed, None)
+    }
+
+    /// То же, что `MevDetector::analyze_job`
+    pub fn analyze_job(
+        &self,
+        tx: Tx,
+        hash: Option<H256>,
+        precomputed: Option<Enrichment>,
+        job: &SimulationJob,
+    ) -> Vec<MevAlert> {
+        self.analyze_with(tx, hash, precomputed, Some(job))
+    }
+
+    fn analyze_with(
+        &self,
+        tx: Tx,
+        hash: Option<H256>,
+        precomputed: Option<Enrichment>,
+        job: Option<&SimulationJob>,
+    ) -> Vec<MevAlert> {
+        if let Some(seen) = &self.seen {
+            let key = hash.unwrap_or_else(|| tx_fingerprint(&tx));
+            if !seen.lock().unwrap().insert(key, Instant::now()) {
+                return Vec::new();
+            }
+        }
+
+        let config = self.config();
+        let registry = config.registry.as_ref();
+        let mut alerts = Vec::new();
+
+        // Очередь контракта заблокирована до постановки транзакции: следующая к тому же
+        // контракту увидит эту, как при последовательном анализе
+        {
+            let mut pending = self.pool.entry(tx.to).or_default();
+            let simulator = self.simulator_for(&tx.to).lock().unwrap();
+            let heuristics = Heuristics {
+                simulator: simulator.as_ref(),
+                labels: config.labels.as_ref(),
+                severity: config.severity.as_deref(),
+                chain: config.chain.as_ref(),
+                job,
+            };
+
+            let base = adaptive_thresholds(&config.thresholds, config.gas_oracle.as_deref());
+            if let Some(thresholds) = effective_thresholds(registry, &base, "frontrun") {
+                let alert = heuristics.frontrun(Some(&*pending), &tx, &thresholds);
+                record_hit(registry, "frontrun", alert.as_ref().is_some_and(|a| !trap::is_trap(a)));
+                alerts.extend(alert);
+            }
+
+            if let Some(thresholds) = effective_thresholds(registry, &base, "sandwich") {
+                let found = heuristics.sandwich(Some(&*pending), &tx, &thresholds);
+                record_hit(registry, "sandwich", found.iter().any(|a| !trap::is_trap(a)));
+                alerts.extend(found);
+            }
+
+            let now = now();
+            while pending.front().is_some_and(|(_, ts)| now.saturating_sub(*ts) > self.ttl_seconds) {
+                pending.pop_front();
+            }
+            pending.push_back((tx.clone(), now));
+        }
+
+        if let Some(deployments) = self
+            .deployments
+            .as_ref()
+            .filter(|_| effective_thresholds(registry, &config.thresholds, "deployment_frontrun").is_some())
+        {
+            let found = deployments.observe(&tx);
+            record_hit(registry, "deployment_frontrun", !found.is_empty());
+            alerts.extend(found);
+        }
+
+        if let Some(victims) = &self.victims {
+            let repeated = victims.lock().unwrap().observe(&mut alerts);
+            alerts.extend(repeated);
+        }
+        if let Some(fingerprints) = &self.fingerprints {
+            fingerprints.observe(&tx);
+            fingerprints.annotate(&mut alerts);
+        }
+        if let Some(funding) = &self.funding {
+            funding.attribute(&mut alerts);
+        }
+
+        let rules = config
+            .rules
+            .as_deref()
+            .filter(|_| effective_thresholds(registry, &config.thresholds, "rules").is_some());
+        finish_alerts(&mut alerts, &tx, rules, registry, precomputed, &self.enrichers);
+
+        alerts
+    }
+
+    /// Убирает просроченные транзакции и пустые очереди; вызывается по таймеру
+    pub fn prune(&self) {
+        let now = now();
+        self.pool.retain(|_, pending| {
+            pending.retain(|(_, ts)| now.saturating_sub(*ts) <= self.ttl_seconds);
+            !pending.is_empty()
+        });
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pool.iter().map(|entry| entry.value().len()).sum()
+    }
+
+    /// Сколько повторных доставок пропущено дедупликацией
+    pub fn deduplicated(&self) -> u64 {
+        self.seen.as_ref().map(|s| s.lock().unwrap().skipped()).unwrap_or(0)
+    }
+
+    /// Один контракт — всегда один симулятор
+    fn simulator_for(&self, target: &ChecksummedAddress) -> &Mutex<Box<dyn Simulator>> {
+        let mut hasher = DefaultHasher::new();
+        target.hash(&mut hasher);
+        &self.simulators[hasher.finish() as usize % self.simulators.len()]
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
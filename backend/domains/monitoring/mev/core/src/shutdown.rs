@@ -0,0 +1,258 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify};
+
+/// Фазы остановки; выполняются строго по порядку
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownPhase {
+    /// Перестать принимать новые транзакции из мемпула
+    StopIngestion,
+    /// Дождаться завершения начатых симуляций
+    DrainSimulations,
+    /// Сохранить пулы ожидающих транзакций и буферы алертов в хранилище
+    FlushState,
+    /// Закрыть синки (вебхуки, пейджеры, почту)
+    CloseSinks,
+}
+
+impl ShutdownPhase {
+    pub const ALL: [ShutdownPhase; 4] = [
+        ShutdownPhase::StopIngestion,
+        ShutdownPhase::DrainSimulations,
+        ShutdownPhase::FlushState,
+        ShutdownPhase::CloseSinks,
+    ];
+}
+
+/// Сигнал остановки для фоновых задач; клонируется в каждую задачу
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    rx: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    pub fn is_triggered(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Завершается, когда начата остановка
+    pub async fn wait(&mut self) {
+        while !*self.rx.borrow() {
+            if self.rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Счётчик выполняющихся операций (симуляций), который можно дождаться
+#[derive(Clone, Default)]
+pub struct InFlight {
+    inner: Arc<(AtomicUsize, Notify)>,
+}
+
+pub struct InFlightGuard {
+    inner: Arc<(AtomicUsize, Notify)>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.inner.0.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.1.notify_waiters();
+        }
+    }
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Регистрирует операцию на время жизни guard
+    pub fn enter(&self) -> InFlightGuard {
+        self.inner.0.fetch_add(1, Ordering::AcqRel);
+        InFlightGuard { inner: self.inner.clone() }
+    }
+
+    pub fn count(&self) -> usize {
+        self.inner.0.load(Ordering::Acquire)
+    }
+
+    pub async fn drained(&self) {
+        loop {
+            let notified = self.inner.1.notified();
+            if self.count() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Участник остановки: подсистема, которой нужно что-то доделать перед выходом
+#[async_trait]
+pub trait ShutdownHook: Send + Sync {
+    fn name(&self) -> &str;
+    async fn shutdown(&self) -> Result<(), String>;
+}
+
+/// Итог остановки одного участника
+#[derive(Debug, Clone, Serialize)]
+pub struct HookOutcome {
+    pub phase: ShutdownPhase,
+    pub name: String,
+    pub elapsed_ms: u64,
+    /// `None` — успешно; иначе ошибка или `timeout`
+    pub error: Option<String>,
+}
+
+/// Координатор остановки: по SIGTERM проходит фазы по порядку,
+/// каждому участнику даётся свой таймаут фазы
+pub struct ShutdownCoordinator {
+    tx: watch::Sender<bool>,
+    hooks: Vec<(ShutdownPhase, Arc<dyn ShutdownHook>)>,
+    timeouts: Vec<(ShutdownPhase, Duration)>,
+    default_timeout: Duration,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(default_timeout: Duration) -> Self {
+        let (tx, _) = watch::channel(false);
+        Self {
+            tx,
+            hooks: Vec::new(),
+            timeouts: Vec::new(),
+            default_timeout,
+        }
+    }
+
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal { rx: self.tx.subscribe() }
+    }
+
+    pub fn with_timeout(mut self, phase: ShutdownPhase, timeout: Duration) -> Self {
+        self.timeouts.retain(|(p, _)| *p != phase);
+        self.timeouts.push((phase, timeout));
+        self
+    }
+
+    pub fn register(&mut self, phase: ShutdownPhase, hook: Arc<dyn ShutdownHook>) {
+        self.hooks.push((phase, hook));
+    }
+
+    fn timeout(&self, phase: ShutdownPhase) -> Duration {
+        self.timeouts
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|(_, t)| *t)
+            .unwrap_or(self.default_timeout)
+    }
+
+    /// Ждёт SIGTERM или Ctrl-C
+    pub async fn wait_for_signal() {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut term = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+            tokio::select! {
+                _ = term.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+
+    /// Оповещает задачи и выполняет все фазы; ошибки участников не прерывают остановку
+    pub async fn run(&self) -> Vec<HookOutcome> {
+        let _ = self.tx.send(true);
+        let mut outcomes = Vec::new();
+
+        for phase in ShutdownPhase::ALL {
+            let timeout = self.timeout(phase);
+            for (_, hook) in self.hooks.iter().filter(|(p, _)| *p == phase) {
+                let started = Instant::now();
+                let error = match tokio::time::timeout(timeout, hook.shutdown()).await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e),
+                    Err(_) => Some("timeout".to_string()),
+                };
+                outcomes.push(HookOutcome {
+                    phase,
+                    name: hook.name().to_string(),
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    error,
+                });
+            }
+        }
+
+        outcomes
+    }
+}
+
+/// Участник фазы DrainSimulations: ждёт завершения начатых симуляций
+pub struct DrainInFlight {
+    pub name: String,
+    pub in_flight: InFlight,
+}
+
+#[async_trait]
+impl ShutdownHook for DrainInFlight {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        self.in_flight.drained().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Slow;
+
+    #[async_trait]
+    impl ShutdownHook for Slow {
+        fn name(&self) -> &str {
+            "slow-sink"
+        }
+
+        async fn shutdown(&self) -> Result<(), String> {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phases_run_in_order_with_timeouts() {
+        let in_flight = InFlight::new();
+        let guard = in_flight.enter();
+
+        let mut coordinator = ShutdownCoordinator::new(Duration::from_millis(50));
+        let mut signal = coordinator.signal();
+        coordinator.register(ShutdownPhase::CloseSinks, Arc::new(Slow));
+        coordinator.register(
+            ShutdownPhase::DrainSimulations,
+            Arc::new(DrainInFlight { name: "simulations".into(), in_flight: in_flight.clone() }),
+        );
+
+        tokio::spawn(async move {
+            signal.wait().await;
+            drop(guard);
+        });
+
+        let outcomes = coordinator.run().await;
+        assert_eq!(outcomes[0].name, "simulations");
+        assert_eq!(outcomes[0].error, None);
+        assert_eq!(outcomes[1].error.as_deref(), Some("timeout"));
+    }
+}
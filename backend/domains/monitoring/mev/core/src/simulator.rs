@@ -0,0 +1,148 @@
+#[cfg(feature = "cpp-simulator")]
+use crate::compat::{Address, H256};
+#[cfg(feature = "cpp-simulator")]
+use crate::ffi::{self, CppSimulator, SimAccount, SimTx};
+#[cfg(feature = "cpp-simulator")]
+use crate::state::{BlockingState, SharedStateProvider, StateError};
+#[cfg(feature = "cpp-simulator")]
+use crate::task_errors::TaskErrors;
+use crate::tx::Tx;
+#[cfg(feature = "cpp-simulator")]
+use cxx::UniquePtr;
+#[cfg(feature = "cpp-simulator")]
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "cpp-simulator")]
+use tokio::sync::watch;
+
+/// Симулятор исполнения для эвристик; все суммы в ETH.
+/// Основная реализация — C++ (`state_simulator`, фича `cpp-simulator`), для тестов — `testing::MockSimulator`
+pub trait Simulator: Send {
+    /// Прибыль атакующего, вставшего перед `victim`
+    fn simulate_profit(&self, victim: &Tx, attacker: &Tx) -> f64;
+    /// Прибыль атакующего от пары `front`/`back` вокруг `victim`
+    fn simulate_sandwich(&self, front: &Tx, victim: &Tx, back: &Tx) -> f64;
+    /// Выход жертвы без атакующего
+    fn simulate_victim_output(&self, victim: &Tx) -> f64;
+    /// Выход жертвы внутри сэндвича
+    fn simulate_sandwiched_victim_output(&self, front: &Tx, victim: &Tx, back: &Tx) -> f64;
+}
+
+#[cfg(feature = "cpp-simulator")]
+type SimJob = Box<dyn FnOnce(&CppSimulator) + Send>;
+
+/// C++ симулятор в собственном потоке. Про потокобезопасность C++-объекта ничего не известно,
+/// поэтому он создаётся, вызывается и уничтожается в одном потоке, а вызовы приходят по каналу
+#[cfg(feature = "cpp-simulator")]
+pub struct CppSimulatorThread {
+    jobs: mpsc::Sender<SimJob>,
+}
+
+#[cfg(feature = "cpp-simulator")]
+impl CppSimulatorThread {
+    /// `make` выполняется уже в потоке симулятора
+    pub fn spawn(make: impl FnOnce() -> UniquePtr<CppSimulator> + Send + 'static) -> Self {
+        let (jobs, queue) = mpsc::channel::<SimJob>();
+        std::thread::Builder::new()
+            .name("cpp-simulator".into())
+            .spawn(move || {
+                let simulator = make();
+                for job in queue {
+                    job(&simulator);
+                }
+            })
+            .expect("failed to spawn the simulator thread");
+        Self { jobs }
+    }
+
+    /// Симуляция, не дошедшая до C++ или упавшая в нём, считается неудачной: 0.0
+    fn call(&self, simulate: impl FnOnce(&CppSimulator) -> f64 + Send + 'static) -> f64 {
+        let (reply, result) = mpsc::sync_channel(1);
+        let job: SimJob = Box::new(move |simulator| {
+            let _ = reply.send(simulate(simulator));
+        });
+        if self.jobs.send(job).is_err() {
+            return 0.0;
+        }
+        result.recv().unwrap_or(0.0)
+    }
+}
+
+#[cfg(feature = "cpp-simulator")]
+impl Simulator for CppSimulatorThread {
+    fn simulate_profit(&self, victim: &Tx, attacker: &Tx) -> f64 {
+        let (victim, attacker) = (victim.to_sim(), attacker.to_sim());
+        self.call(move |simulator| ffi::simulate_profit(simulator, &victim, &attacker))
+    }
+
+    fn simulate_sandwich(&self, front: &Tx, victim: &Tx, back: &Tx) -> f64 {
+        let legs: [SimTx; 3] = [front.to_sim(), victim.to_sim(), back.to_sim()];
+        self.call(move |simulator| ffi::simulate_sandwich(simulator, &legs[0], &legs[1], &legs[2]))
+    }
+
+    fn simulate_victim_output(&self, victim: &Tx) -> f64 {
+        let victim = victim.to_sim();
+        self.call(move |simulator| ffi::simulate_victim_output(simulator, &victim))
+    }
+
+    fn simulate_sandwiched_victim_output(&self, front: &Tx, victim: &Tx, back: &Tx) -> f64 {
+        let legs: [SimTx; 3] = [front.to_sim(), victim.to_sim(), back.to_sim()];
+        self.call(move |simulator| ffi::simulate_sandwiched_victim_output(simulator, &legs[0], &legs[1], &legs[2]))
+    }
+}
+
+/// Состояние для C++ симулятора: чтения через `StateProvider` на последнем блоке из `heads`
+#[cfg(feature = "cpp-simulator")]
+pub struct SimState {
+    state: BlockingState,
+    heads: watch::Receiver<u64>,
+    errors: Arc<TaskErrors>,
+}
+
+#[cfg(feature = "cpp-simulator")]
+impl SimState {
+    fn head(&self) -> Result<u64, StateError> {
+        match *self.heads.borrow() {
+            0 => Err(StateError::Missing { what: "chain head".into(), block: 0 }),
+            head => Ok(head),
+        }
+    }
+
+    /// Ошибки чтения видны в `errors` узла, а не только как неудачная симуляция
+    fn read<T>(&self, read: impl FnOnce(u64) -> Result<T, StateError>) -> Result<T, StateError> {
+        self.head().and_then(read).inspect_err(|e| self.errors.record(format!("simulator state: {}", e)))
+    }
+
+    pub fn account(&self, address: &str) -> Result<SimAccount, StateError> {
+        let address = parse_address(address)?;
+        let account = self.read(|block| self.state.account(address, block))?;
+        let mut balance = vec![0u8; 32];
+        account.balance.to_big_endian(&mut balance);
+        Ok(SimAccount { balance, nonce: account.nonce })
+    }
+
+    pub fn storage(&self, address: &str, slot: &[u8]) -> Result<Vec<u8>, StateError> {
+        let address = parse_address(address)?;
+        if slot.len() != 32 {
+            return Err(StateError::InvalidRequest(format!("slot of {} bytes", slot.len())));
+        }
+        let value = self.read(|block| self.state.storage(address, H256::from_slice(slot), block))?;
+        Ok(value.as_bytes().to_vec())
+    }
+
+    pub fn code(&self, address: &str) -> Result<Vec<u8>, StateError> {
+        let address = parse_address(address)?;
+        Ok(self.read(|block| self.state.code(address, block))?.to_vec())
+    }
+}
+
+#[cfg(feature = "cpp-simulator")]
+fn parse_address(address: &str) -> Result<Address, StateError> {
+    address.parse().map_err(|_| StateError::InvalidRequest(format!("address {}", address)))
+}
+
+/// C++ симулятор поверх `state`: тот же источник состояния, что у анализа безопасности,
+/// с кэшем и снимками вместо собственного клиента узла
+#[cfg(feature = "cpp-simulator")]
+pub fn state_simulator(state: SharedStateProvider, heads: watch::Receiver<u64>, errors: Arc<TaskErrors>) -> CppSimulatorThread {
+    CppSimulatorThread::spawn(move || ffi::new_simulator_with_state(Box::new(SimState { state: BlockingState::new(state), heads, errors })))
+}
@@ -0,0 +1,406 @@
+use crate::bus::AlertLevel;
+use crate::schema::MESSAGE_VERSION;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SinkError {
+    #[error("Delivery failed: {0}")]
+    DeliveryFailed(String),
+
+    #[error("Sink rejected message: {0}")]
+    Rejected(String),
+}
+
+/// Готовое к доставке сообщение: алерт, отчёт или дайджест
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Message {
+    /// Версия формата, см. `schema::SCHEMAS`
+    #[serde(default = "crate::schema::unversioned")]
+    pub schema_version: u32,
+    pub subject: String,
+    pub level: AlertLevel,
+    #[serde(default)]
+    pub kind: MessageKind,
+    pub markdown: String,
+    /// Для синков с разметкой (почта); остальные берут `markdown`
+    pub html: Option<String>,
+    pub tenant: Option<String>,
+    /// Идентификатор алерта для подтверждения эскалации (`routing::alert_id`)
+    #[serde(default)]
+    pub alert_id: Option<String>,
+    /// Кошелёк адресата для синков, пишущих пользователям (`BusAlert::wallet`)
+    #[serde(default)]
+    pub wallet: Option<String>,
+}
+
+impl Message {
+    pub fn new(subject: String, level: AlertLevel, markdown: String) -> Self {
+        Self {
+            schema_version: MESSAGE_VERSION,
+            subject,
+            level,
+            kind: MessageKind::Alert,
+            markdown,
+            html: None,
+            tenant: None,
+            alert_id: None,
+            wallet: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: MessageKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_html(mut self, html: String) -> Self {
+        self.html = Some(html);
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant: &str) -> Self {
+        self.tenant = Some(tenant.to_string());
+        self
+    }
+
+    pub fn with_alert_id(mut self, id: &str) -> Self {
+        self.alert_id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_wallet(mut self, wallet: &str) -> Self {
+        self.wallet = Some(wallet.to_string());
+        self
+    }
+}
+
+/// Что несёт сообщение: синки по-разному ограничивают отдельные алерты и плановые сводки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    #[default]
+    Alert,
+    Digest,
+}
+
+/// Состояние алерта после доставки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Triggered,
+    Acknowledged,
+    Resolved,
+}
+
+/// Канал доставки. Имя синка — ключ в конфиге (`[i18n.sinks]`, маршруты)
+#[async_trait]
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError>;
+
+    /// Подтверждение или закрытие ранее отправленного алерта; чатам и почте нечего обновлять
+    async fn transition(&self, _alert_id: &str, _state: AlertState) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    /// Смена состояния из вебхука платформы синка: `(id алерта, состояние)`
+    fn incident_update(&self, _body: &serde_json::Value) -> Option<(String, AlertState)> {
+        None
+    }
+}
+
+/// POST сообщения в JSON на произвольный URL
+#[cfg(feature = "mev")]
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "mev")]
+impl WebhookSink {
+    pub fn new(name: &str, url: &str) -> Self {
+        Self { name: name.to_string(), url: url.to_string(), client: reqwest::Client::new() }
+    }
+}
+
+#[cfg(feature = "mev")]
+#[async_trait]
+impl Sink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(message)
+            .send()
+            .await
+            .map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(SinkError::Rejected(format!("{} returned {}", self.url, response.status())));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mev")]
+async fn check_response(platform: &str, response: reqwest::Response) -> Result<(), SinkError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        Err(SinkError::Rejected(format!("{} returned {}: {}", platform, status, body)))
+    } else {
+        Err(SinkError::DeliveryFailed(format!("{} returned {}: {}", platform, status, body)))
+    }
+}
+
+#[cfg(feature = "mev")]
+fn dedup_key(message: &Message) -> Result<&str, SinkError> {
+    message.alert_id.as_deref().ok_or_else(|| SinkError::Rejected("message has no alert id to open an incident with".into()))
+}
+
+/// Инциденты PagerDuty через Events API v2; ключ дедупликации — id алерта
+#[cfg(feature = "mev")]
+pub struct PagerDutySink {
+    name: String,
+    routing_key: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "mev")]
+impl PagerDutySink {
+    pub const EVENTS_URL: &'static str = "https://events.pagerduty.com/v2/enqueue";
+
+    /// `routing_key` — ключ интеграции Events API v2 сервиса
+    pub fn new(name: &str, routing_key: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            routing_key: routing_key.to_string(),
+            url: Self::EVENTS_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    fn severity(level: AlertLevel) -> &'static str {
+        match level {
+            AlertLevel::Critical => "critical",
+            AlertLevel::High => "error",
+            AlertLevel::Medium => "warning",
+            AlertLevel::Low | AlertLevel::Info => "info",
+        }
+    }
+
+    async fn enqueue(&self, event: serde_json::Value) -> Result<(), SinkError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&event)
+            .send()
+            .await
+            .map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        check_response("PagerDuty", response).await
+    }
+
+    /// Смена состояния из вебхука PagerDuty v3: `(id алерта, состояние)`. Инциденты,
+    /// открытые не этим синком, без `incident_key` и пропускаются
+    pub fn parse_webhook(body: &serde_json::Value) -> Option<(String, AlertState)> {
+        let event = body.get("event")?;
+        let state = match event.get("event_type")?.as_str()? {
+            "incident.acknowledged" => AlertState::Acknowledged,
+            "incident.resolved" => AlertState::Resolved,
+            _ => return None,
+        };
+        Some((event.pointer("/data/incident_key")?.as_str()?.to_string(), state))
+    }
+}
+
+#[cfg(feature = "mev")]
+#[async_trait]
+impl Sink for PagerDutySink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError> {
+        let summary: String = message.subject.chars().take(1024).collect();
+        self.enqueue(serde_json::json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "dedup_key": dedup_key(message)?,
+            "payload": {
+                "summary": summary,
+                "source": "definetly",
+                "severity": Self::severity(message.level),
+                "group": message.tenant,
+                "custom_details": { "details": message.markdown },
+            },
+        }))
+        .await
+    }
+
+    async fn transition(&self, alert_id: &str, state: AlertState) -> Result<(), SinkError> {
+        let action = match state {
+            AlertState::Triggered => return Ok(()),
+            AlertState::Acknowledged => "acknowledge",
+            AlertState::Resolved => "resolve",
+        };
+        self.enqueue(serde_json::json!({
+            "routing_key": self.routing_key,
+            "event_action": action,
+            "dedup_key": alert_id,
+        }))
+        .await
+    }
+
+    fn incident_update(&self, body: &serde_json::Value) -> Option<(String, AlertState)> {
+        Self::parse_webhook(body)
+    }
+}
+
+/// Алерты Opsgenie через Alert API; повторная отправка с тем же `alias` не создаёт дубль
+#[cfg(feature = "mev")]
+pub struct OpsgenieSink {
+    name: String,
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "mev")]
+impl OpsgenieSink {
+    pub const API_URL: &'static str = "https://api.opsgenie.com";
+
+    pub fn new(name: &str, api_key: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            api_key: api_key.to_string(),
+            base_url: Self::API_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Для аккаунтов в EU: `https://api.eu.opsgenie.com`
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn priority(level: AlertLevel) -> &'static str {
+        match level {
+            AlertLevel::Critical => "P1",
+            AlertLevel::High => "P2",
+            AlertLevel::Medium => "P3",
+            AlertLevel::Low => "P4",
+            AlertLevel::Info => "P5",
+        }
+    }
+
+    async fn post(&self, path: &str, body: serde_json::Value) -> Result<(), SinkError> {
+        let response = self
+            .client
+            .post(format!("{}/v2/alerts{}", self.base_url, path))
+            .header("Authorization", format!("GenieKey {}", self.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| SinkError::DeliveryFailed(e.to_string()))?;
+        check_response("Opsgenie", response).await
+    }
+
+    /// Смена состояния из исходящего вебхука Opsgenie: `(id алерта, состояние)`
+    pub fn parse_webhook(body: &serde_json::Value) -> Option<(String, AlertState)> {
+        let state = match body.get("action")?.as_str()? {
+            "Acknowledge" => AlertState::Acknowledged,
+            "Close" => AlertState::Resolved,
+            _ => return None,
+        };
+        Some((body.pointer("/alert/alias")?.as_str()?.to_string(), state))
+    }
+}
+
+#[cfg(feature = "mev")]
+#[async_trait]
+impl Sink for OpsgenieSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), SinkError> {
+        // Лимиты Alert API: 130 символов заголовка, 15 000 описания
+        let title: String = message.subject.chars().take(130).collect();
+        let description: String = message.markdown.chars().take(15_000).collect();
+        let mut body = serde_json::json!({
+            "message": title,
+            "alias": dedup_key(message)?,
+            "description": description,
+            "priority": Self::priority(message.level),
+            "source": "definetly",
+        });
+        if let Some(tenant) = &message.tenant {
+            body["tags"] = serde_json::json!([tenant]);
+        }
+        self.post("", body).await
+    }
+
+    async fn transition(&self, alert_id: &str, state: AlertState) -> Result<(), SinkError> {
+        let action = match state {
+            AlertState::Triggered => return Ok(()),
+            AlertState::Acknowledged => "acknowledge",
+            AlertState::Resolved => "close",
+        };
+        self.post(&format!("/{}/{}?identifierType=alias", url_encode(alert_id), action), serde_json::json!({ "source": "definetly" })).await
+    }
+
+    fn incident_update(&self, body: &serde_json::Value) -> Option<(String, AlertState)> {
+        Self::parse_webhook(body)
+    }
+}
+
+/// Процентное кодирование `alias` для сегмента пути
+#[cfg(feature = "mev")]
+fn url_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "mev"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incident_webhooks_map_to_states() {
+        let pagerduty = serde_json::json!({
+            "event": { "event_type": "incident.acknowledged", "data": { "id": "Q1", "incident_key": "incident-0xabc-1700000000" } }
+        });
+        assert_eq!(
+            PagerDutySink::parse_webhook(&pagerduty),
+            Some(("incident-0xabc-1700000000".to_string(), AlertState::Acknowledged))
+        );
+        let opsgenie = serde_json::json!({ "action": "Close", "alert": { "alias": "3f2a9c" } });
+        assert_eq!(OpsgenieSink::parse_webhook(&opsgenie), Some(("3f2a9c".to_string(), AlertState::Resolved)));
+        assert_eq!(OpsgenieSink::parse_webhook(&serde_json::json!({ "action": "AddNote" })), None);
+        assert_eq!(url_encode("a b/c"), "a%20b%2Fc");
+    }
+}
@@ -0,0 +1,401 @@
+use crate::compat::{Address, Bytes, H256, U256};
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use ethers::types::{BlockId, BlockNumber};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("State of {what} at block {block} is not available")]
+    Missing { what: String, block: u64 },
+
+    #[error("Unsupported: {0}")]
+    Unsupported(&'static str),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("State bridge stopped")]
+    BridgeStopped,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Баланс и nonce аккаунта; код запрашивается отдельно
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountState {
+    pub balance: U256,
+    pub nonce: u64,
+}
+
+/// Состояние цепи на блоке N для симуляций: MEV-симулятор и динамический
+/// анализ безопасности работают через него, а не напрямую с провайдером ethers
+#[async_trait]
+pub trait StateProvider: Send + Sync {
+    async fn get_account(&self, address: Address, block: u64) -> Result<AccountState, StateError>;
+    async fn get_storage(&self, address: Address, slot: H256, block: u64) -> Result<H256, StateError>;
+    async fn get_code(&self, address: Address, block: u64) -> Result<Bytes, StateError>;
+
+    /// Для опкода BLOCKHASH; не все источники его знают
+    async fn get_block_hash(&self, _number: u64) -> Result<H256, StateError> {
+        Err(StateError::Unsupported("block hashes"))
+    }
+}
+
+pub type SharedStateProvider = Arc<dyn StateProvider>;
+
+type BridgeJob = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Синхронное чтение `StateProvider` для синхронного кода: revm и C++ симулятор.
+/// Запросы исполняются на отдельном потоке со своим рантаймом, поэтому вызывать можно
+/// откуда угодно, в том числе из рантайма `current_thread`: блокируется только вызывающий поток
+pub struct BlockingState {
+    state: SharedStateProvider,
+    jobs: mpsc::UnboundedSender<BridgeJob>,
+}
+
+impl BlockingState {
+    pub fn new(state: SharedStateProvider) -> Self {
+        let (jobs, mut pending) = mpsc::unbounded_channel::<BridgeJob>();
+        std::thread::Builder::new()
+            .name("state-bridge".into())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().expect("tokio runtime");
+                // Поток завершается, когда мост удалён
+                runtime.block_on(async move {
+                    while let Some(job) = pending.recv().await {
+                        tokio::spawn(job);
+                    }
+                });
+            })
+            .expect("state bridge thread");
+        Self { state, jobs }
+    }
+
+    fn call<T, F>(&self, read: impl FnOnce(SharedStateProvider) -> F) -> Result<T, StateError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, StateError>> + Send + 'static,
+    {
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let read = read(self.state.clone());
+        self.jobs
+            .send(Box::pin(async move {
+                let _ = tx.send(read.await);
+            }))
+            .map_err(|_| StateError::BridgeStopped)?;
+        rx.recv().map_err(|_| StateError::BridgeStopped)?
+    }
+
+    pub fn account(&self, address: Address, block: u64) -> Result<AccountState, StateError> {
+        self.call(|state| async move { state.get_account(address, block).await })
+    }
+
+    pub fn storage(&self, address: Address, slot: H256, block: u64) -> Result<H256, StateError> {
+        self.call(|state| async move { state.get_storage(address, slot, block).await })
+    }
+
+    pub fn code(&self, address: Address, block: u64) -> Result<Bytes, StateError> {
+        self.call(|state| async move { state.get_code(address, block).await })
+    }
+
+    pub fn block_hash(&self, number: u64) -> Result<H256, StateError> {
+        self.call(|state| async move { state.get_block_hash(number).await })
+    }
+}
+
+fn at(block: u64) -> Option<BlockId> {
+    Some(BlockId::Number(BlockNumber::Number(block.into())))
+}
+
+fn provider_err(e: impl std::fmt::Display) -> StateError {
+    StateError::ProviderError(e.to_string())
+}
+
+/// Состояние из JSON-RPC узла; для старых блоков нужен архивный узел
+pub struct RpcStateProvider<M> {
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> RpcStateProvider<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> StateProvider for RpcStateProvider<M> {
+    async fn get_account(&self, address: Address, block: u64) -> Result<AccountState, StateError> {
+        let (balance, nonce) = tokio::join!(
+            self.provider.get_balance(address, at(block)),
+            self.provider.get_transaction_count(address, at(block)),
+        );
+        Ok(AccountState {
+            balance: balance.map_err(provider_err)?,
+            nonce: nonce.map_err(provider_err)?.as_u64(),
+        })
+    }
+
+    async fn get_storage(&self, address: Address, slot: H256, block: u64) -> Result<H256, StateError> {
+        self.provider.get_storage_at(address, slot, at(block)).await.map_err(provider_err)
+    }
+
+    async fn get_code(&self, address: Address, block: u64) -> Result<Bytes, StateError> {
+        self.provider.get_code(address, at(block)).await.map_err(provider_err)
+    }
+
+    async fn get_block_hash(&self, number: u64) -> Result<H256, StateError> {
+        self.provider
+            .get_block(number)
+            .await
+            .map_err(provider_err)?
+            .and_then(|b| b.hash)
+            .ok_or_else(|| StateError::Missing { what: "block hash".into(), block: number })
+    }
+}
+
+#[derive(Default)]
+struct CacheState {
+    accounts: HashMap<(Address, u64), AccountState>,
+    storage: HashMap<(Address, H256, u64), H256>,
+    code: HashMap<(Address, u64), Bytes>,
+}
+
+/// Кэш поверх другого источника. Состояние блока неизменно, поэтому записи
+/// не устаревают; память освобождает `prune_before`
+pub struct CachedStateProvider<S> {
+    inner: S,
+    cache: RwLock<CacheState>,
+}
+
+impl<S: StateProvider> CachedStateProvider<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, cache: RwLock::new(CacheState::default()) }
+    }
+
+    /// Убирает записи блоков старше `block`
+    pub fn prune_before(&self, block: u64) {
+        let mut cache = self.cache.write().unwrap();
+        cache.accounts.retain(|(_, b), _| *b >= block);
+        cache.storage.retain(|(_, _, b), _| *b >= block);
+        cache.code.retain(|(_, b), _| *b >= block);
+    }
+
+    /// Слоты `address`, прочитанные на блоке `block`; по ним прогревается следующий блок
+    pub fn slots_at(&self, address: Address, block: u64) -> Vec<H256> {
+        let cache = self.cache.read().unwrap();
+        cache
+            .storage
+            .keys()
+            .filter(|(a, _, b)| *a == address && *b == block)
+            .map(|(_, slot, _)| *slot)
+            .collect()
+    }
+
+    /// Число закэшированных значений: аккаунты, слоты, код
+    pub fn len(&self) -> usize {
+        let cache = self.cache.read().unwrap();
+        cache.accounts.len() + cache.storage.len() + cache.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl<S: StateProvider> StateProvider for CachedStateProvider<S> {
+    async fn get_account(&self, address: Address, block: u64) -> Result<AccountState, StateError> {
+        if let Some(account) = self.cache.read().unwrap().accounts.get(&(address, block)) {
+            return Ok(*account);
+        }
+        let account = self.inner.get_account(address, block).await?;
+        self.cache.write().unwrap().accounts.insert((address, block), account);
+        Ok(account)
+    }
+
+    async fn get_storage(&self, address: Address, slot: H256, block: u64) -> Result<H256, StateError> {
+        if let Some(value) = self.cache.read().unwrap().storage.get(&(address, slot, block)) {
+            return Ok(*value);
+        }
+        let value = self.inner.get_storage(address, slot, block).await?;
+        self.cache.write().unwrap().storage.insert((address, slot, block), value);
+        Ok(value)
+    }
+
+    async fn get_code(&self, address: Address, block: u64) -> Result<Bytes, StateError> {
+        if let Some(code) = self.cache.read().unwrap().code.get(&(address, block)) {
+            return Ok(code.clone());
+        }
+        let code = self.inner.get_code(address, block).await?;
+        self.cache.write().unwrap().code.insert((address, block), code.clone());
+        Ok(code)
+    }
+
+    async fn get_block_hash(&self, number: u64) -> Result<H256, StateError> {
+        self.inner.get_block_hash(number).await
+    }
+}
+
+/// Аккаунт в файле снимка
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default)]
+    pub nonce: u64,
+    #[serde(default)]
+    pub code: Bytes,
+    #[serde(default)]
+    pub storage: HashMap<H256, H256>,
+}
+
+/// Состояние одного блока из файла: воспроизводимые симуляции и тесты без узла.
+/// Отсутствующие аккаунты и слоты считаются пустыми, как в EVM
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotStateProvider {
+    pub block: u64,
+    #[serde(default)]
+    pub accounts: HashMap<Address, SnapshotAccount>,
+    #[serde(default)]
+    pub block_hashes: HashMap<u64, H256>,
+}
+
+impl SnapshotStateProvider {
+    pub fn new(block: u64) -> Self {
+        Self { block, ..Default::default() }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, StateError> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StateError> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Снимает состояние перечисленных аккаунтов и слотов из другого источника
+    pub async fn capture(
+        source: &dyn StateProvider,
+        block: u64,
+        targets: &[(Address, Vec<H256>)],
+    ) -> Result<Self, StateError> {
+        let mut snapshot = Self::new(block);
+        for (address, slots) in targets {
+            let account = source.get_account(*address, block).await?;
+            let mut entry = SnapshotAccount {
+                balance: account.balance,
+                nonce: account.nonce,
+                code: source.get_code(*address, block).await?,
+                storage: HashMap::new(),
+            };
+            for slot in slots {
+                entry.storage.insert(*slot, source.get_storage(*address, *slot, block).await?);
+            }
+            snapshot.accounts.insert(*address, entry);
+        }
+        Ok(snapshot)
+    }
+
+    fn check_block(&self, what: impl FnOnce() -> String, block: u64) -> Result<(), StateError> {
+        if block == self.block {
+            Ok(())
+        } else {
+            Err(StateError::Missing { what: what(), block })
+        }
+    }
+}
+
+#[async_trait]
+impl StateProvider for SnapshotStateProvider {
+    async fn get_account(&self, address: Address, block: u64) -> Result<AccountState, StateError> {
+        self.check_block(|| format!("{:?}", address), block)?;
+        Ok(self
+            .accounts
+            .get(&address)
+            .map(|a| AccountState { balance: a.balance, nonce: a.nonce })
+            .unwrap_or_default())
+    }
+
+    async fn get_storage(&self, address: Address, slot: H256, block: u64) -> Result<H256, StateError> {
+        self.check_block(|| format!("{:?}/{:?}", address, slot), block)?;
+        Ok(self
+            .accounts
+            .get(&address)
+            .and_then(|a| a.storage.get(&slot).copied())
+            .unwrap_or_default())
+    }
+
+    async fn get_code(&self, address: Address, block: u64) -> Result<Bytes, StateError> {
+        self.check_block(|| format!("{:?}", address), block)?;
+        Ok(self.accounts.get(&address).map(|a| a.code.clone()).unwrap_or_default())
+    }
+
+    async fn get_block_hash(&self, number: u64) -> Result<H256, StateError> {
+        self.block_hashes
+            .get(&number)
+            .copied()
+            .ok_or_else(|| StateError::Missing { what: "block hash".into(), block: number })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip_through_cache() {
+        let address = Address::repeat_byte(0x11);
+        let slot = H256::from_low_u64_be(3);
+        let mut snapshot = SnapshotStateProvider::new(100);
+        snapshot.accounts.insert(
+            address,
+            SnapshotAccount {
+                balance: U256::exp10(18),
+                nonce: 4,
+                code: Bytes::from(vec![0x60, 0x00]),
+                storage: HashMap::from([(slot, H256::from_low_u64_be(42))]),
+            },
+        );
+
+        let json = serde_json::to_vec(&snapshot).unwrap();
+        let restored: SnapshotStateProvider = serde_json::from_slice(&json).unwrap();
+        let cached = CachedStateProvider::new(restored);
+
+        assert_eq!(cached.get_account(address, 100).await.unwrap().nonce, 4);
+        assert_eq!(cached.get_storage(address, slot, 100).await.unwrap(), H256::from_low_u64_be(42));
+        // Неизвестный слот пуст, как в EVM
+        assert_eq!(cached.get_storage(address, H256::zero(), 100).await.unwrap(), H256::zero());
+        assert!(matches!(cached.get_code(address, 101).await, Err(StateError::Missing { block: 101, .. })));
+        assert_eq!(cached.len(), 3);
+
+        cached.prune_before(101);
+        assert!(cached.is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_blocking_state_inside_current_thread_runtime() {
+        let address = Address::repeat_byte(0x22);
+        let mut snapshot = SnapshotStateProvider::new(7);
+        snapshot.accounts.insert(address, SnapshotAccount { nonce: 9, ..Default::default() });
+        let state = BlockingState::new(Arc::new(snapshot));
+
+        assert_eq!(state.account(address, 7).unwrap().nonce, 9);
+        assert!(matches!(state.code(address, 8), Err(StateError::Missing { block: 8, .. })));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::compat::{keccak256, Address, H256, U256};
+use crate::detector::MevAlert;
+use crate::labels::{AddressLabel, SharedLabelResolver};
+use ethers::providers::Middleware;
+use ethers::types::{
+    AccountState, DiffMode, GethDebugBuiltInTracerConfig, GethDebugBuiltInTracerType, GethDebugTracerConfig,
+    GethDebugTracerType, GethDebugTracingOptions, GethTrace, GethTraceFrame, PreStateConfig, PreStateFrame,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StateDiffError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Node returned an unexpected trace for {0:?}")]
+    UnexpectedTrace(H256),
+}
+
+/// Где в хранилище контракта лежит известная переменная
+#[derive(Debug, Clone)]
+enum SlotKind {
+    Value(String),
+    /// `mapping(address => ...)` в базовом слоте
+    AddressMapping(String),
+}
+
+/// Известные раскладки хранилища: без них слоты показываются сырыми
+#[derive(Debug, Clone, Default)]
+pub struct StorageLayouts {
+    slots: HashMap<Address, Vec<(U256, SlotKind)>>,
+}
+
+impl StorageLayouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Переменная в фиксированном слоте, например `totalSupply`
+    pub fn with_value(mut self, contract: Address, slot: u64, name: &str) -> Self {
+        self.slots.entry(contract).or_default().push((slot.into(), SlotKind::Value(name.to_string())));
+        self
+    }
+
+    /// `mapping(address => ...)` в базовом слоте, например `balanceOf` OpenZeppelin ERC-20 в слоте 0
+    pub fn with_address_mapping(mut self, contract: Address, slot: u64, name: &str) -> Self {
+        self.slots.entry(contract).or_default().push((slot.into(), SlotKind::AddressMapping(name.to_string())));
+        self
+    }
+
+    /// Имя слота: ключи отображений подбираются среди адресов, затронутых транзакцией
+    fn decode(&self, contract: Address, slot: H256, touched: &BTreeSet<Address>) -> Option<String> {
+        let slot_value = U256::from_big_endian(slot.as_bytes());
+        for (base, kind) in self.slots.get(&contract)? {
+            match kind {
+                SlotKind::Value(name) if *base == slot_value => return Some(name.clone()),
+                SlotKind::AddressMapping(name) => {
+                    let mut preimage = [0u8; 64];
+                    base.to_big_endian(&mut preimage[32..]);
+                    for key in touched {
+                        preimage[12..32].copy_from_slice(key.as_bytes());
+                        if keccak256(preimage) == slot {
+                            return Some(format!("{}[{:?}]", name, key));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct BalanceChange {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub before: U256,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub after: U256,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SlotChange {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub slot: H256,
+    /// Имя по известной раскладке, `None` — неизвестный слот
+    pub decoded: Option<String>,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub before: H256,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub after: H256,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AccountDiff {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub address: Address,
+    pub label: Option<AddressLabel>,
+    pub balance: Option<BalanceChange>,
+    #[cfg_attr(feature = "server", schema(value_type = Option<Vec<u64>>))]
+    pub nonce: Option<(u64, u64)>,
+    /// Код появился (деплой) или исчез (selfdestruct)
+    pub code_changed: bool,
+    pub storage: Vec<SlotChange>,
+}
+
+/// Что изменила транзакция: балансы, nonce, код и слоты хранилища
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct StateDiffReport {
+    #[cfg_attr(feature = "server", schema(value_type = String))]
+    pub tx_hash: H256,
+    pub accounts: Vec<AccountDiff>,
+}
+
+impl StateDiffReport {
+    pub fn changed_slots(&self) -> usize {
+        self.accounts.iter().map(|a| a.storage.len()).sum()
+    }
+}
+
+/// Отчёт по результату `prestateTracer` в режиме diff. В `post` geth кладёт только изменённые
+/// поля и опускает обнулённые слоты, поэтому отсутствие значения в `post` для слота из `pre` — ноль.
+/// Счёт из `pre` без записи в `post` удалён (selfdestruct): баланс, nonce, код и хранилище обнулены
+pub fn diff_report(tx_hash: H256, diff: &DiffMode, layouts: &StorageLayouts, labels: Option<&SharedLabelResolver>) -> StateDiffReport {
+    let touched: BTreeSet<Address> = diff.pre.keys().chain(diff.post.keys()).copied().collect();
+    let empty = AccountState::default();
+    let emptied = AccountState { balance: Some(U256::zero()), nonce: Some(U256::zero()), ..Default::default() };
+    let accounts = touched
+        .iter()
+        .filter_map(|address| {
+            let pre = diff.pre.get(address).unwrap_or(&empty);
+            let deleted = diff.pre.contains_key(address) && !diff.post.contains_key(address);
+            let post = diff.post.get(address).unwrap_or(if deleted { &emptied } else { &empty });
+            let had_code = pre.code.as_deref().is_some_and(|code| !code.is_empty() && code != "0x");
+            let account = AccountDiff {
+                address: *address,
+                label: labels.map(|l| l.resolve(&format!("{:?}", address))),
+                balance: post
+                    .balance
+                    .filter(|after| *after != pre.balance.unwrap_or_default())
+                    .map(|after| BalanceChange { before: pre.balance.unwrap_or_default(), after }),
+                nonce: post
+                    .nonce
+                    .filter(|after| *after != pre.nonce.unwrap_or_default())
+                    .map(|after| (pre.nonce.unwrap_or_default().low_u64(), after.low_u64())),
+                code_changed: if deleted { had_code } else { post.code.is_some() && post.code != pre.code },
+                storage: storage_changes(*address, pre, post, layouts, &touched),
+            };
+            let changed = account.balance.is_some() || account.nonce.is_some() || account.code_changed || !account.storage.is_empty();
+            changed.then_some(account)
+        })
+        .collect();
+    StateDiffReport { tx_hash, accounts }
+}
+
+fn storage_changes(
+    address: Address,
+    pre: &AccountState,
+    post: &AccountState,
+    layouts: &StorageLayouts,
+    touched: &BTreeSet<Address>,
+) -> Vec<SlotChange> {
+    let none = BTreeMap::new();
+    let (pre, post) = (pre.storage.as_ref().unwrap_or(&none), post.storage.as_ref().unwrap_or(&none));
+    let slots: BTreeSet<&H256> = pre.keys().chain(post.keys()).collect();
+    slots
+        .into_iter()
+        .filter_map(|slot| {
+            let before = pre.get(slot).copied().unwrap_or_default();
+            let after = post.get(slot).copied().unwrap_or_default();
+            (before != after).then(|| SlotChange { slot: *slot, decoded: layouts.decode(address, *slot, touched), before, after })
+        })
+        .collect()
+}
+
+/// Снимает state diff транзакций через `debug_traceTransaction` и прикладывает его к алертам
+pub struct StateDiffer<M> {
+    provider: Arc<M>,
+    layouts: StorageLayouts,
+    labels: Option<SharedLabelResolver>,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware> StateDiffer<M> {
+    pub fn new(provider: Arc<M>, layouts: StorageLayouts) -> Self {
+        Self { provider, layouts, labels: None, errors: TaskErrors::default() }
+    }
+
+    pub fn with_labels(mut self, labels: SharedLabelResolver) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Трассировки, которые не удалось снять
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub async fn report(&self, tx_hash: H256) -> Result<StateDiffReport, StateDiffError> {
+        let options = GethDebugTracingOptions {
+            tracer: Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::PreStateTracer)),
+            tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(GethDebugBuiltInTracerConfig::PreStateTracer(
+                PreStateConfig { diff_mode: Some(true) },
+            ))),
+            ..Default::default()
+        };
+        let trace = self
+            .provider
+            .debug_trace_transaction(tx_hash, options)
+            .await
+            .map_err(|e| StateDiffError::ProviderError(e.to_string()))?;
+        match trace {
+            GethTrace::Known(GethTraceFrame::PreStateTracer(PreStateFrame::Diff(diff))) => {
+                Ok(diff_report(tx_hash, &diff, &self.layouts, self.labels.as_ref()))
+            }
+            _ => Err(StateDiffError::UnexpectedTrace(tx_hash)),
+        }
+    }
+
+    /// Для алертов, подтверждённых `BlockAnalyzer::confirm`, добавляет `state_diff` —
+    /// отчёты по транзакциям атакующего. Возвращает число дополненных алертов
+    pub async fn attach_confirmed(&self, alerts: &mut [MevAlert]) -> usize {
+        let mut attached = 0;
+        for alert in alerts.iter_mut().filter(|a| a.confirmed_onchain == Some(true)) {
+            let attacker_txs: Vec<H256> = alert
+                .metadata
+                .pointer("/onchain/match/attacker_txs")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            let mut reports = Vec::new();
+            for tx_hash in attacker_txs {
+                reports.extend(self.errors.check(self.report(tx_hash).await));
+            }
+            if reports.is_empty() {
+                continue;
+            }
+            if let Some(metadata) = alert.metadata.as_object_mut() {
+                metadata.insert("state_diff".into(), serde_json::to_value(&reports).unwrap_or_default());
+                attached += 1;
+            }
+        }
+        attached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_balance_mapping_and_zeroed_slot() {
+        let (token, holder) = (Address::repeat_byte(0x70), Address::repeat_byte(0xaa));
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(holder.as_bytes());
+        let balance_slot = keccak256(preimage);
+        let cleared = H256::from_low_u64_be(7);
+
+        let state = |storage: Vec<(H256, H256)>| AccountState { storage: Some(storage.into_iter().collect()), ..Default::default() };
+        // Держатель попал в diff без изменений: он нужен только как кандидат ключа отображения
+        let unchanged = AccountState { balance: Some(U256::one()), ..Default::default() };
+        let diff = DiffMode {
+            pre: [
+                (token, state(vec![(balance_slot, H256::from_low_u64_be(100)), (cleared, H256::from_low_u64_be(1))])),
+                (holder, unchanged.clone()),
+            ]
+            .into(),
+            post: [(token, state(vec![(balance_slot, H256::from_low_u64_be(40))])), (holder, unchanged)].into(),
+        };
+        let layouts = StorageLayouts::new().with_address_mapping(token, 0, "balanceOf").with_value(token, 7, "locked");
+        let report = diff_report(H256::zero(), &diff, &layouts, None);
+
+        assert_eq!(report.accounts.len(), 1);
+        let storage = &report.accounts[0].storage;
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage[0].decoded.as_deref(), Some("locked"));
+        assert_eq!(storage[0].after, H256::zero());
+        assert_eq!(storage[1].decoded, Some(format!("balanceOf[{:?}]", holder)));
+    }
+
+    #[test]
+    fn test_account_missing_from_post_is_emptied() {
+        let contract = Address::repeat_byte(0x66);
+        let slot = H256::from_low_u64_be(3);
+        let pre = AccountState {
+            balance: Some(U256::from(5)),
+            nonce: Some(U256::one()),
+            code: Some("0x6000".into()),
+            storage: Some([(slot, H256::from_low_u64_be(9))].into()),
+        };
+        let diff = DiffMode { pre: [(contract, pre)].into(), post: Default::default() };
+        let report = diff_report(H256::zero(), &diff, &StorageLayouts::new(), None);
+
+        let account = &report.accounts[0];
+        assert!(account.code_changed);
+        assert_eq!(account.balance.as_ref().map(|b| (b.before, b.after)), Some((U256::from(5), U256::zero())));
+        assert_eq!(account.nonce, Some((1, 0)));
+        assert_eq!(account.storage[0].after, H256::zero());
+    }
+}
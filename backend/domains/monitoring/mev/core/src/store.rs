@@ -0,0 +1,178 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+}
+
+/// Персистентное key-value хранилище, разбитое на пространства имён
+pub trait Store: Send + Sync {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StoreError>;
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StoreError>;
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), StoreError>;
+    /// Все записи пространства имён, отсортированные по ключу
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>, StoreError>;
+}
+
+pub type SharedStore = Arc<dyn Store>;
+
+/// JSON-обёртки поверх байтового API
+pub trait StoreExt {
+    fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>, StoreError>;
+    fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<(), StoreError>;
+    fn list_json<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>, StoreError>;
+}
+
+impl<S: Store + ?Sized> StoreExt for S {
+    fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>, StoreError> {
+        match self.get(namespace, key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<(), StoreError> {
+        self.put(namespace, key, &serde_json::to_vec(value)?)
+    }
+
+    fn list_json<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>, StoreError> {
+        self.list(namespace)?
+            .into_iter()
+            .map(|(k, v)| Ok((k, serde_json::from_slice(&v)?)))
+            .collect()
+    }
+}
+
+/// Хранилище в памяти — для тестов и одноразовых запусков
+#[derive(Default)]
+pub struct MemoryStore {
+    data: RwLock<HashMap<String, BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store for MemoryStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        let data = self.data.read().unwrap();
+        Ok(data.get(namespace).and_then(|ns| ns.get(key)).cloned())
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StoreError> {
+        let mut data = self.data.write().unwrap();
+        data.entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), StoreError> {
+        let mut data = self.data.write().unwrap();
+        if let Some(ns) = data.get_mut(namespace) {
+            ns.remove(key);
+        }
+        Ok(())
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>, StoreError> {
+        let data = self.data.read().unwrap();
+        Ok(data
+            .get(namespace)
+            .map(|ns| ns.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Файловое хранилище: каталог на пространство имён, файл на ключ.
+/// Запись атомарна через временный файл и rename.
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> Result<PathBuf, StoreError> {
+        let valid = |s: &str| {
+            !s.is_empty()
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                && !s.starts_with('.')
+        };
+        if !valid(namespace) {
+            return Err(StoreError::InvalidKey(namespace.to_string()));
+        }
+        if !valid(key) {
+            return Err(StoreError::InvalidKey(key.to_string()));
+        }
+        Ok(self.root.join(namespace).join(key))
+    }
+}
+
+impl Store for FileStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        match std::fs::read(self.path(namespace, key)?) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn put(&self, namespace: &str, key: &str, value: &[u8]) -> Result<(), StoreError> {
+        let path = self.path(namespace, key)?;
+        std::fs::create_dir_all(self.root.join(namespace))?;
+        // Ключи не начинаются с точки, поэтому временный файл не пересечётся с записью
+        let tmp = self.root.join(namespace).join(format!(".{}.tmp", key));
+        std::fs::write(&tmp, value)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<(), StoreError> {
+        match std::fs::remove_file(self.path(namespace, key)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self, namespace: &str) -> Result<Vec<(String, Vec<u8>)>, StoreError> {
+        let dir = self.root.join(namespace);
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut out = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            out.push((name, std::fs::read(entry.path())?));
+        }
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(out)
+    }
+}
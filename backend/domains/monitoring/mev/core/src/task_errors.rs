@@ -0,0 +1,52 @@
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Последняя ошибка фоновой задачи
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TaskError {
+    pub at: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TaskErrorsSnapshot {
+    pub errors: u64,
+    pub last: Option<TaskError>,
+}
+
+/// Счётчик ошибок фонового цикла: цикл не останавливается на ошибке, а считает её,
+/// последняя видна в метриках вместе с моментом
+#[derive(Default)]
+pub struct TaskErrors {
+    count: AtomicU64,
+    last: Mutex<Option<TaskError>>,
+}
+
+impl TaskErrors {
+    pub fn record(&self, error: impl Display) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        *self.last.lock().unwrap() = Some(TaskError { at, message: error.to_string() });
+    }
+
+    /// Учитывает ошибку результата и отдаёт значение успешного
+    pub fn check<T, E: Display>(&self, result: Result<T, E>) -> Option<T> {
+        result.map_err(|e| self.record(e)).ok()
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> TaskErrorsSnapshot {
+        TaskErrorsSnapshot { errors: self.count(), last: self.last.lock().unwrap().clone() }
+    }
+}
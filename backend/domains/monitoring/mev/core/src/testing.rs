@@ -0,0 +1,194 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::compat::Address;
+use crate::detector::MevThresholds;
+use crate::simulator::Simulator;
+use crate::tx::Tx;
+use crate::units::Gwei;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Адрес из повторённого байта: `addr(0xaa)` — `0xAaAa…`
+pub fn addr(byte: u8) -> ChecksummedAddress {
+    Address::repeat_byte(byte).into()
+}
+
+pub fn thresholds(min_profit_eth: f64, max_gas_price_gwei: f64) -> MevThresholds {
+    MevThresholds {
+        min_profit: WeiAmount::from_eth(min_profit_eth),
+        max_gas_price: Gwei::new(max_gas_price_gwei).unwrap(),
+    }
+}
+
+/// Конструктор синтетической транзакции; по умолчанию своп на 1 ETH за 20 gwei
+#[derive(Debug, Clone)]
+pub struct TxBuilder {
+    tx: Tx,
+}
+
+impl Default for TxBuilder {
+    fn default() -> Self {
+        Self {
+            tx: Tx {
+                from: addr(0x01),
+                to: addr(0x02),
+                value: WeiAmount::from_eth(1.0),
+                gas_price: WeiAmount::from_gwei(20.0),
+                input: vec![0x38, 0xed, 0x17, 0x39],
+            },
+        }
+    }
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from(mut self, from: ChecksummedAddress) -> Self {
+        self.tx.from = from;
+        self
+    }
+
+    pub fn to(mut self, to: ChecksummedAddress) -> Self {
+        self.tx.to = to;
+        self
+    }
+
+    pub fn value_eth(mut self, eth: f64) -> Self {
+        self.tx.value = WeiAmount::from_eth(eth);
+        self
+    }
+
+    pub fn gas_price_gwei(mut self, gwei: f64) -> Self {
+        self.tx.gas_price = WeiAmount::from_gwei(gwei);
+        self
+    }
+
+    pub fn input(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.tx.input = input.into();
+        self
+    }
+
+    pub fn build(self) -> Tx {
+        self.tx
+    }
+}
+
+/// Ожидающие транзакции к одному контракту, для `MevDetector::restore_pending`
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    target: ChecksummedAddress,
+    entries: Vec<(Tx, u64)>,
+}
+
+impl PoolBuilder {
+    pub fn new(target: ChecksummedAddress) -> Self {
+        Self { target, entries: Vec::new() }
+    }
+
+    /// Транзакция к цели пула, увиденная только что
+    pub fn pending(mut self, tx: TxBuilder) -> Self {
+        self.entries.push((tx.to(self.target).build(), now()));
+        self
+    }
+
+    /// Ноги сэндвича вокруг жертвы с ценой газа `victim_gwei`:
+    /// передняя дешевле жертвы, задняя дороже, с одинаковыми данными
+    pub fn sandwich_legs(self, attacker: ChecksummedAddress, victim_gwei: f64) -> Self {
+        let leg = TxBuilder::new().from(attacker).input(vec![0xde, 0xad, 0xbe, 0xef]);
+        self.pending(leg.clone().gas_price_gwei(victim_gwei / 2.0))
+            .pending(leg.gas_price_gwei(victim_gwei * 2.0))
+    }
+
+    pub fn build(self) -> Vec<(Tx, u64)> {
+        self.entries
+    }
+}
+
+/// Симулятор с заранее заданными ответами; считает вызовы
+#[derive(Debug, Clone)]
+pub struct MockSimulator {
+    profit: f64,
+    victim_output: f64,
+    sandwiched_output: f64,
+    calls: Arc<AtomicUsize>,
+}
+
+impl MockSimulator {
+    /// Любая симуляция атаки приносит `profit_eth`; выход жертвы не меняется
+    pub fn returning(profit_eth: f64) -> Self {
+        Self { profit: profit_eth, victim_output: 0.0, sandwiched_output: 0.0, calls: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Выход жертвы без атакующего и внутри сэндвича (для `protection_value_eth`)
+    pub fn with_victim_outputs(mut self, clean_eth: f64, sandwiched_eth: f64) -> Self {
+        self.victim_output = clean_eth;
+        self.sandwiched_output = sandwiched_eth;
+        self
+    }
+
+    /// Счётчик вызовов, общий для всех клонов; его можно оставить себе до передачи в детектор
+    pub fn calls(&self) -> Arc<AtomicUsize> {
+        self.calls.clone()
+    }
+
+    fn hit(&self) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Simulator for MockSimulator {
+    fn simulate_profit(&self, _victim: &Tx, _attacker: &Tx) -> f64 {
+        self.hit();
+        self.profit
+    }
+
+    fn simulate_sandwich(&self, _front: &Tx, _victim: &Tx, _back: &Tx) -> f64 {
+        self.hit();
+        self.profit
+    }
+
+    fn simulate_victim_output(&self, _victim: &Tx) -> f64 {
+        self.hit();
+        self.victim_output
+    }
+
+    fn simulate_sandwiched_victim_output(&self, _front: &Tx, _victim: &Tx, _back: &Tx) -> f64 {
+        self.hit();
+        self.sandwiched_output
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::{MevDetector, MevType};
+
+    #[test]
+    fn test_mock_simulator_drives_sandwich_detection() {
+        let simulator = MockSimulator::returning(0.5).with_victim_outputs(10.0, 9.6);
+        let calls = simulator.calls();
+        let mut detector = MevDetector::new(simulator, 60, thresholds(0.1, 500.0));
+
+        let pool = PoolBuilder::new(addr(0x02)).sandwich_legs(addr(0xbb), 20.0).build();
+        assert_eq!(detector.restore_pending(pool), 2);
+
+        let alerts = detector.analyze(TxBuilder::new().to(addr(0x02)).build());
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].mev_type, MevType::Sandwich);
+        assert_eq!(alerts[0].profit, WeiAmount::from_eth(0.5));
+        assert!((alerts[0].metadata["protection_value_eth"].as_f64().unwrap() - 0.4).abs() < 1e-9);
+        // сэндвич, два выхода жертвы и повтор от контрольного отправителя (проверка на ловушку)
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+        assert!(alerts[0].metadata["trap_suspected"].is_null());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::address::ChecksummedAddress;
+use crate::compat::keccak256;
+use crate::detector::MevAlert;
+use crate::tx::Tx;
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+
+/// Допустимое расхождение прибыли атакующего и контрольного отправителя.
+/// Больше — токен ведёт себя по-разному в зависимости от отправителя (salmonella)
+pub const TRAP_TOLERANCE: f64 = 0.2;
+
+/// Адрес без истории, от имени которого повторяется симуляция атаки:
+/// честный токен не отличает его от атакующего
+pub fn control_sender() -> ChecksummedAddress {
+    let hash = keccak256(b"definetly.trap.control");
+    ChecksummedAddress::from(ethers::types::Address::from_slice(&hash.as_bytes()[12..]))
+}
+
+/// Та же транзакция от `sender`: отправитель и его адрес в calldata (получатель выплаты) заменены
+pub fn as_sender(tx: &Tx, sender: &ChecksummedAddress) -> Tx {
+    let from = tx.from.address();
+    let (from, to) = (from.as_bytes(), sender.address());
+    let to = to.as_bytes();
+
+    let mut input = tx.input.clone();
+    let mut i = 0;
+    while i + from.len() <= input.len() {
+        if &input[i..i + from.len()] == from {
+            input[i..i + from.len()].copy_from_slice(to);
+            i += from.len();
+        } else {
+            i += 1;
+        }
+    }
+    Tx { from: *sender, input, ..tx.clone() }
+}
+
+/// Расхождение симуляций атакующего и контрольного отправителя
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrapVerdict {
+    pub attacker_profit_eth: f64,
+    pub control_profit_eth: f64,
+    /// |attacker - control| / max(|attacker|, |control|)
+    pub discrepancy: f64,
+}
+
+/// `Some`, если прибыль зависит от того, кто отправитель
+pub fn compare(attacker_profit: f64, control_profit: f64) -> Option<TrapVerdict> {
+    let scale = attacker_profit.abs().max(control_profit.abs());
+    if scale == 0.0 {
+        return None;
+    }
+    let discrepancy = (attacker_profit - control_profit).abs() / scale;
+    (discrepancy > TRAP_TOLERANCE).then_some(TrapVerdict {
+        attacker_profit_eth: attacker_profit,
+        control_profit_eth: control_profit,
+        discrepancy,
+    })
+}
+
+/// Подозрение на ловушку: алерт остаётся для расследования, но с нулевым risk score
+/// и не считается срабатыванием детектора
+pub fn mark(alert: &mut MevAlert, verdict: TrapVerdict) {
+    alert.risk_score = 0.0;
+    alert.metadata["trap_suspected"] = json!(verdict);
+}
+
+pub fn is_trap(alert: &MevAlert) -> bool {
+    !alert.metadata["trap_suspected"].is_null()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{addr, TxBuilder};
+
+    #[test]
+    fn test_sender_substitution_and_discrepancy() {
+        let attacker = addr(0xa1);
+        let mut input = vec![0x12, 0x34, 0x56, 0x78];
+        input.extend_from_slice(&[0u8; 12]);
+        input.extend_from_slice(attacker.address().as_bytes());
+        let tx = TxBuilder::new().from(attacker).input(input).build();
+
+        let control = as_sender(&tx, &control_sender());
+        assert_eq!(control.from, control_sender());
+        assert_eq!(&control.input[16..], control_sender().address().as_bytes());
+
+        assert!(compare(1.0, 0.9).is_none());
+        assert!(compare(0.0, 0.0).is_none());
+        assert!((compare(0.1, 1.0).unwrap().discrepancy - 0.9).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::compat::U256;
+#[cfg(feature = "cpp-simulator")]
+use crate::ffi::SimTx;
+use serde::{Serialize, Deserialize};
+
+/// Ожидающая транзакция в формате детектора
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tx {
+    pub from: ChecksummedAddress,
+    /// Нулевой адрес — создание контракта
+    pub to: ChecksummedAddress,
+    pub value: WeiAmount,
+    #[serde(with = "crate::amount::wei")]
+    pub gas_price: WeiAmount,
+    pub input: Vec<u8>,
+}
+
+impl Tx {
+    /// Вид для C++ симулятора: value в ETH, gas_price в wei, оба в f64
+    #[cfg(feature = "cpp-simulator")]
+    pub(crate) fn to_sim(&self) -> SimTx {
+        SimTx {
+            from: self.from.to_string(),
+            to: self.to.to_string(),
+            value: self.value.to_eth_f64(),
+            gas_price: self.gas_price.wei().min(U256::from(u128::MAX)).as_u128() as f64,
+            input: self.input.clone(),
+        }
+    }
+
+    /// Цена газа выше, чем у `other`, больше чем на `percent` процентов; считается в wei
+    /// с 512-битным произведением, чтобы цены около `U256::MAX` не переполнялись
+    pub fn outbids(&self, other: &Tx, percent: u64) -> bool {
+        let scale = U256::from(100u64) + U256::from(percent);
+        self.gas_price.wei().full_mul(U256::from(100u64)) > other.gas_price.wei().full_mul(scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_price_stays_in_wei_and_outbids_does_not_overflow() {
+        let tx = |gas_price: U256| Tx {
+            from: ChecksummedAddress::default(),
+            to: ChecksummedAddress::default(),
+            value: WeiAmount::from_eth(1.0),
+            gas_price: WeiAmount::from_wei(gas_price),
+            input: Vec::new(),
+        };
+        let legacy: Tx = serde_json::from_value(serde_json::json!({
+            "from": "0x1111111111111111111111111111111111111111",
+            "to": "0x2222222222222222222222222222222222222222",
+            "value": "1.5",
+            "gas_price": 30_000_000_000u64,
+            "input": [],
+        }))
+        .unwrap();
+        assert_eq!(legacy.gas_price.wei(), U256::from(30_000_000_000u64));
+        assert_eq!(serde_json::to_value(&legacy).unwrap()["gas_price"], "30000000000");
+        assert_eq!(serde_json::from_value::<Tx>(serde_json::to_value(&legacy).unwrap()).unwrap(), legacy);
+
+        assert!(tx(U256::from(111)).outbids(&tx(U256::from(100)), 10));
+        assert!(!tx(U256::from(110)).outbids(&tx(U256::from(100)), 10));
+        assert!(tx(U256::MAX).outbids(&tx(U256::MAX / 2), 10));
+        assert!(!tx(U256::MAX).outbids(&tx(U256::MAX), u64::MAX));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::address::ChecksummedAddress;
+use crate::bus::AlertLevel;
+use crate::compat::U256;
+use crate::labels::{AddressLabel, LabelCategory, SharedLabelResolver};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Канонический адрес Permit2 (одинаков во всех сетях)
+pub const PERMIT2: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// Суммы от 2^128 считаются безлимитными: такого предложения нет ни у одного токена
+pub(crate) const UNLIMITED_BITS: usize = 128;
+
+/// Разрешение дольше этого срока считается долгоживущим
+const LONG_LIVED_SECONDS: u64 = 30 * 24 * 3600;
+
+/// Домен EIP-712 в том виде, в каком его присылает `eth_signTypedData_v4`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDataDomain {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Число или строка, как у разных dApp
+    #[serde(default)]
+    #[cfg_attr(feature = "server", schema(value_type = Option<Object>))]
+    pub chain_id: Option<Value>,
+    #[serde(default)]
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+    pub verifying_contract: Option<ChecksummedAddress>,
+}
+
+/// Распознанная схема подписи
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// EIP-2612 и DAI-подобный `permit`
+    Permit,
+    Permit2Single,
+    Permit2Batch,
+    Permit2Transfer,
+    SeaportOrder,
+    Unknown,
+}
+
+/// Право, которое получает контрагент после подписи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Grant {
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+    pub token: Option<ChecksummedAddress>,
+    /// Десятичная сумма; `None` — безлимитно
+    pub amount: Option<String>,
+    /// UNIX-время истечения, если задано
+    pub expires_at: Option<u64>,
+}
+
+/// Итог оценки подписи для кошелька
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TypedDataVerdict {
+    pub scheme: SignatureScheme,
+    pub level: AlertLevel,
+    /// Кто получает права: spender, оператор или получатель ордера
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
+    pub counterparty: Option<ChecksummedAddress>,
+    #[cfg_attr(feature = "server", schema(value_type = Option<Object>))]
+    pub counterparty_label: Option<AddressLabel>,
+    pub grants: Vec<Grant>,
+    pub reasons: Vec<String>,
+}
+
+fn parse_uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(n) => n.as_u64().map(U256::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(s).ok(),
+        },
+        _ => None,
+    }
+}
+
+fn parse_address(value: &Value) -> Option<ChecksummedAddress> {
+    value.as_str().and_then(|s| s.parse().ok())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+pub(crate) fn grant(token: Option<ChecksummedAddress>, amount: Option<U256>, expires_at: Option<u64>) -> Grant {
+    let unlimited = amount.is_none_or(|a| a.bits() > UNLIMITED_BITS);
+    Grant {
+        token,
+        amount: amount.filter(|_| !unlimited).map(|a| a.to_string()),
+        expires_at,
+    }
+}
+
+/// Оценка подписей EIP-712: разбирает Permit, Permit2 и ордера Seaport
+/// и проверяет контрагента по меткам и списку помеченных адресов
+pub struct TypedDataAssessor {
+    labels: Option<SharedLabelResolver>,
+    flagged: RwLock<HashSet<ChecksummedAddress>>,
+}
+
+impl TypedDataAssessor {
+    pub fn new(labels: Option<SharedLabelResolver>) -> Self {
+        Self { labels, flagged: RwLock::new(HashSet::new()) }
+    }
+
+    /// Помечает адрес как дрейнер или фишинговый
+    pub fn flag(&self, address: ChecksummedAddress) {
+        self.flagged.write().unwrap().insert(address);
+    }
+
+    pub fn assess_typed_data(&self, domain: &TypedDataDomain, message: &Value) -> TypedDataVerdict {
+        let scheme = detect_scheme(domain, message);
+        let (counterparty, grants, mut reasons, mut level) = match scheme {
+            SignatureScheme::Permit => permit(domain, message),
+            SignatureScheme::Permit2Single | SignatureScheme::Permit2Batch | SignatureScheme::Permit2Transfer => {
+                permit2(message)
+            }
+            SignatureScheme::SeaportOrder => seaport(message),
+            SignatureScheme::Unknown => (
+                None,
+                Vec::new(),
+                vec!["unrecognized typed data; review the raw message before signing".to_string()],
+                AlertLevel::Low,
+            ),
+        };
+
+        let label = counterparty.map(|c| match &self.labels {
+            Some(labels) => labels.resolve(&c.to_string()),
+            None => AddressLabel {
+                address: c.to_string(),
+                label: None,
+                category: None,
+                ens: None,
+                display: c.to_string(),
+            },
+        });
+
+        if let Some(counterparty) = &counterparty {
+            if self.flagged.read().unwrap().contains(counterparty) {
+                reasons.push(format!("{} is flagged as malicious", counterparty));
+                level = AlertLevel::Critical;
+            } else if label.as_ref().and_then(|l| l.category).is_none() && !grants.is_empty() {
+                let unlimited = grants.iter().any(|g| g.amount.is_none());
+                reasons.push(format!("{} is not a known protocol contract", counterparty));
+                if unlimited {
+                    level = level.max(AlertLevel::High);
+                }
+            } else if matches!(label.as_ref().and_then(|l| l.category), Some(LabelCategory::Router)) {
+                reasons.push(format!("{} is a known router", counterparty));
+            }
+        }
+
+        TypedDataVerdict { scheme, level, counterparty, counterparty_label: label, grants, reasons }
+    }
+}
+
+fn detect_scheme(domain: &TypedDataDomain, message: &Value) -> SignatureScheme {
+    let permit2: ChecksummedAddress = PERMIT2.parse().unwrap();
+    if domain.name.as_deref() == Some("Permit2") || domain.verifying_contract == Some(permit2) {
+        return match (&message["details"], &message["permitted"]) {
+            (Value::Array(_), _) => SignatureScheme::Permit2Batch,
+            (Value::Object(_), _) => SignatureScheme::Permit2Single,
+            (_, Value::Object(_) | Value::Array(_)) => SignatureScheme::Permit2Transfer,
+            _ => SignatureScheme::Unknown,
+        };
+    }
+    if domain.name.as_deref() == Some("Seaport") && message["offer"].is_array() && message["consideration"].is_array() {
+        return SignatureScheme::SeaportOrder;
+    }
+    let is_permit = !message["spender"].is_null() && (!message["value"].is_null() || !message["allowed"].is_null());
+    if is_permit {
+        return SignatureScheme::Permit;
+    }
+    SignatureScheme::Unknown
+}
+
+type Assessed = (Option<ChecksummedAddress>, Vec<Grant>, Vec<String>, AlertLevel);
+
+pub(crate) fn expiry_reason(expires_at: Option<u64>, reasons: &mut Vec<String>, level: &mut AlertLevel) {
+    match expires_at {
+        Some(at) if at > now() + LONG_LIVED_SECONDS => {
+            reasons.push(format!("permission stays valid until {}", at));
+            *level = (*level).max(AlertLevel::Medium);
+        }
+        None => {
+            reasons.push("permission never expires".into());
+            *level = (*level).max(AlertLevel::Medium);
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn unlimited_reason(grants: &[Grant], reasons: &mut Vec<String>, level: &mut AlertLevel) {
+    if grants.iter().any(|g| g.amount.is_none()) {
+        reasons.push("grants an unlimited allowance".into());
+        *level = (*level).max(AlertLevel::Medium);
+    }
+}
+
+/// EIP-2612 (`value`, `deadline`) или DAI (`allowed`, `expiry`); токен — verifyingContract
+fn permit(domain: &TypedDataDomain, message: &Value) -> Assessed {
+    let spender = parse_address(&message["spender"]);
+    let amount = match &message["allowed"] {
+        Value::Bool(true) => None,
+        Value::Bool(false) => Some(U256::zero()),
+        _ => parse_uint(&message["value"]),
+    };
+    let deadline = parse_uint(if message["deadline"].is_null() { &message["expiry"] } else { &message["deadline"] })
+        .filter(|d| !d.is_zero() && d.bits() <= 64)
+        .map(|d| d.as_u64());
+
+    let grants = vec![grant(domain.verifying_contract, amount, deadline)];
+    let (mut reasons, mut level) = (Vec::new(), AlertLevel::Low);
+    unlimited_reason(&grants, &mut reasons, &mut level);
+    expiry_reason(deadline, &mut reasons, &mut level);
+    (spender, grants, reasons, level)
+}
+
+/// PermitSingle / PermitBatch (`details`) и PermitTransferFrom (`permitted`)
+fn permit2(message: &Value) -> Assessed {
+    let spender = parse_address(&message["spender"]);
+    let mut grants = Vec::new();
+
+    let details = match &message["details"] {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(_) => vec![&message["details"]],
+        _ => Vec::new(),
+    };
+    for item in details {
+        // 0 в Permit2 — до конца текущего блока, а не бессрочно
+        let expiration = parse_uint(&item["expiration"]).map(|e| e.low_u64());
+        grants.push(grant(parse_address(&item["token"]), parse_uint(&item["amount"]), expiration));
+    }
+
+    // Разовый перевод: живёт до `deadline` и не оставляет allowance
+    let permitted = match &message["permitted"] {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(_) => vec![&message["permitted"]],
+        _ => Vec::new(),
+    };
+    let deadline = parse_uint(&message["deadline"]).map(|d| d.low_u64());
+    for item in permitted {
+        grants.push(grant(parse_address(&item["token"]), parse_uint(&item["amount"]), deadline));
+    }
+
+    let (mut reasons, mut level) = (Vec::new(), AlertLevel::Low);
+    unlimited_reason(&grants, &mut reasons, &mut level);
+    if message["details"].is_null() {
+        expiry_reason(deadline, &mut reasons, &mut level);
+    } else {
+        for g in &grants {
+            expiry_reason(g.expires_at, &mut reasons, &mut level);
+        }
+    }
+    if grants.len() > 1 {
+        reasons.push(format!("covers {} tokens at once", grants.len()));
+    }
+    reasons.dedup();
+    (spender, grants, reasons, level)
+}
+
+/// Ордер Seaport: всё, что уходит не офферу, — оплата другим сторонам.
+/// Ордер без оплаты офферу отдаёт предметы бесплатно — типичный фишинг
+fn seaport(message: &Value) -> Assessed {
+    let offerer = parse_address(&message["offerer"]);
+    let offer = message["offer"].as_array().cloned().unwrap_or_default();
+    let consideration = message["consideration"].as_array().cloned().unwrap_or_default();
+
+    let grants: Vec<Grant> = offer
+        .iter()
+        .map(|item| {
+            let end = parse_uint(&message["endTime"]).map(|e| e.low_u64());
+            grant(parse_address(&item["token"]), parse_uint(&item["startAmount"]), end)
+        })
+        .collect();
+
+    let to_offerer = consideration
+        .iter()
+        .filter(|item| parse_address(&item["recipient"]) == offerer)
+        .filter_map(|item| parse_uint(&item["startAmount"]))
+        .fold(U256::zero(), |acc, a| acc.saturating_add(a));
+    let others: Vec<ChecksummedAddress> = consideration
+        .iter()
+        .filter_map(|item| parse_address(&item["recipient"]))
+        .filter(|r| Some(*r) != offerer)
+        .collect();
+
+    let (mut reasons, mut level) = (Vec::new(), AlertLevel::Low);
+    if !offer.is_empty() && to_offerer.is_zero() {
+        reasons.push("order pays nothing to the signer for the offered items".into());
+        level = AlertLevel::Critical;
+    }
+    // Главный получатель чужой оплаты — тот, кто забирает ценность
+    let counterparty = others.first().copied();
+    (counterparty, grants, reasons, level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unlimited_permit2_to_unknown_spender_is_high() {
+        let assessor = TypedDataAssessor::new(None);
+        let domain = TypedDataDomain { name: Some("Permit2".into()), ..Default::default() };
+        let message = json!({
+            "details": {
+                "token": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "amount": "1461501637330902918203684832716283019655932542975",
+                "expiration": "0",
+                "nonce": "0"
+            },
+            "spender": "0x1111111111111111111111111111111111111111",
+            "sigDeadline": "1700000000"
+        });
+
+        let verdict = assessor.assess_typed_data(&domain, &message);
+        assert_eq!(verdict.scheme, SignatureScheme::Permit2Single);
+        assert_eq!(verdict.level, AlertLevel::High);
+        assert!(verdict.grants[0].amount.is_none());
+
+        assessor.flag(verdict.counterparty.unwrap());
+        assert_eq!(assessor.assess_typed_data(&domain, &message).level, AlertLevel::Critical);
+    }
+
+    #[test]
+    fn test_seaport_order_without_payment_is_critical() {
+        let domain = TypedDataDomain { name: Some("Seaport".into()), ..Default::default() };
+        let message = json!({
+            "offerer": "0x2222222222222222222222222222222222222222",
+            "offer": [{ "itemType": 2, "token": "0xbc4ca0eda7647a8ab7c2061c2e118a18a936f13d", "startAmount": "1" }],
+            "consideration": [{ "itemType": 0, "startAmount": "1", "recipient": "0x3333333333333333333333333333333333333333" }],
+            "endTime": "9999999999"
+        });
+
+        let verdict = TypedDataAssessor::new(None).assess_typed_data(&domain, &message);
+        assert_eq!(verdict.scheme, SignatureScheme::SeaportOrder);
+        assert_eq!(verdict.level, AlertLevel::Critical);
+        assert_eq!(verdict.counterparty, "0x3333333333333333333333333333333333333333".parse().ok());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::amount::WeiAmount;
+use crate::compat::U256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use thiserror::Error;
+
+const GWEI_DECIMALS: usize = 9;
+const MAX_BPS: u16 = 10_000;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UnitError {
+    #[error("Invalid gwei value '{0}'")]
+    InvalidGwei(String),
+
+    #[error("Basis points {0} exceed 10000")]
+    BpsOutOfRange(u64),
+
+    #[error("Fraction {0} is outside [0, 1]")]
+    FractionOutOfRange(f64),
+}
+
+/// Цена газа или комиссия в gwei, хранится точно в wei.
+/// В конфиге — число или десятичная строка в gwei; в бинарных форматах — wei, как у `WeiAmount`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Gwei(WeiAmount);
+
+impl Gwei {
+    pub const ZERO: Self = Self(WeiAmount::ZERO);
+
+    /// Отрицательные и нечисловые значения — ошибка, а не ноль
+    pub fn new(gwei: f64) -> Result<Self, UnitError> {
+        if !gwei.is_finite() || gwei < 0.0 {
+            return Err(UnitError::InvalidGwei(gwei.to_string()));
+        }
+        Ok(Self(WeiAmount::from_gwei(gwei)))
+    }
+
+    pub fn from_wei(wei: impl Into<U256>) -> Self {
+        Self(WeiAmount::from_wei(wei))
+    }
+
+    pub fn wei(&self) -> U256 {
+        self.0.wei()
+    }
+
+    pub fn amount(&self) -> WeiAmount {
+        self.0
+    }
+
+    /// Приближённое значение для проверки диапазонов и отображения
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::MAX)
+    }
+}
+
+impl From<Gwei> for WeiAmount {
+    fn from(gwei: Gwei) -> Self {
+        gwei.0
+    }
+}
+
+/// Десятичная запись в gwei без лишних нулей
+impl fmt::Display for Gwei {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (int, frac) = self.wei().div_mod(U256::exp10(GWEI_DECIMALS));
+        let frac = format!("{:0>width$}", frac.to_string(), width = GWEI_DECIMALS);
+        match frac.trim_end_matches('0') {
+            "" => write!(f, "{}", int),
+            frac => write!(f, "{}.{}", int, frac),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GweiInput {
+    Number(f64),
+    Decimal(String),
+}
+
+impl Serialize for Gwei {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            self.to_f64().serialize(serializer)
+        } else {
+            self.wei().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Gwei {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return U256::deserialize(deserializer).map(Self::from_wei);
+        }
+        match GweiInput::deserialize(deserializer)? {
+            GweiInput::Number(gwei) => Self::new(gwei).map_err(serde::de::Error::custom),
+            GweiInput::Decimal(s) => WeiAmount::parse_units(&s, GWEI_DECIMALS)
+                .map(Self)
+                .map_err(|_| serde::de::Error::custom(UnitError::InvalidGwei(s))),
+        }
+    }
+}
+
+/// Базисные пункты, 0..=10 000; 100 б.п. = 1%
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct Bps(u16);
+
+impl Bps {
+    pub fn new(bps: u64) -> Result<Self, UnitError> {
+        if bps > MAX_BPS as u64 {
+            return Err(UnitError::BpsOutOfRange(bps));
+        }
+        Ok(Self(bps as u16))
+    }
+
+    pub fn get(&self) -> u16 {
+        self.0
+    }
+
+    pub fn fraction(&self) -> Fraction {
+        Fraction(self.0 as f64 / MAX_BPS as f64)
+    }
+
+    /// Доля от суммы с округлением вниз
+    pub fn of(&self, amount: U256) -> U256 {
+        amount * U256::from(self.0) / U256::from(MAX_BPS)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bps {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::new(u64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Bps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bps", self.0)
+    }
+}
+
+/// Доля 0.0..=1.0 (аптайм, вероятность, часть портфеля)
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize)]
+#[serde(transparent)]
+pub struct Fraction(f64);
+
+impl Fraction {
+    pub const ZERO: Self = Self(0.0);
+    pub const ONE: Self = Self(1.0);
+
+    pub fn new(value: f64) -> Result<Self, UnitError> {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(UnitError::FractionOutOfRange(value));
+        }
+        Ok(Self(value))
+    }
+
+    /// Для вычисленных значений: выход за границы прижимается к ним, NaN даёт ноль
+    pub fn clamped(value: f64) -> Self {
+        if value.is_nan() {
+            Self::ZERO
+        } else {
+            Self(value.clamp(0.0, 1.0))
+        }
+    }
+
+    pub fn get(&self) -> f64 {
+        self.0
+    }
+
+    pub fn bps(&self) -> Bps {
+        Bps((self.0 * MAX_BPS as f64).round() as u16)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fraction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::new(f64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0 * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validated_construction_and_serde() {
+        let gwei: Gwei = serde_json::from_str("\"1.5\"").unwrap();
+        assert_eq!(gwei.wei(), U256::from(1_500_000_000u64));
+        assert_eq!(serde_json::from_str::<Gwei>("1.5").unwrap(), gwei);
+        assert_eq!(gwei.to_string(), "1.5");
+        assert!(serde_json::from_str::<Gwei>("-1").is_err());
+
+        assert_eq!(serde_json::from_str::<Bps>("250").unwrap().fraction(), Fraction::new(0.025).unwrap());
+        assert!(serde_json::from_str::<Bps>("10001").is_err());
+        assert_eq!(Bps::new(50).unwrap().of(U256::from(10_000u64)), U256::from(50u64));
+
+        assert!(serde_json::from_str::<Fraction>("1.2").is_err());
+        assert_eq!(Fraction::clamped(f64::NAN), Fraction::ZERO);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::address::ChecksummedAddress;
+use crate::amount::WeiAmount;
+use crate::detector::{MevAlert, MevType};
+use crate::trap;
+use crate::tx::Tx;
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Прибавка к risk score сэндвича за каждую единицу взвешенной истории сверх первой атаки
+const ESCALATION_PER_ATTACK: f32 = 0.1;
+
+/// Доля атак одного атакующего, с которой повтор считается охотой конкретного бота
+const TARGETED_BOT_SHARE: f64 = 0.6;
+
+/// Сколько последних атак помнить на одну жертву
+const MAX_ATTACKS_PER_VICTIM: usize = 256;
+
+/// Сколько жертв помнить; сверх этого вытесняется атакованная давнее всех
+const MAX_VICTIMS: usize = 50_000;
+
+#[derive(Debug, Clone)]
+struct Attack {
+    at: u64,
+    attacker: ChecksummedAddress,
+    target: ChecksummedAddress,
+    profit: WeiAmount,
+}
+
+/// История сэндвичей по адресу жертвы. Старые атаки весят меньше: вес
+/// `1 - age / window`, так что три атаки за минуту тревожнее трёх за час
+pub struct VictimTracker {
+    window_seconds: u64,
+    /// Взвешенная история, с которой выпускается `RepeatedTargeting`
+    threshold: f64,
+    history: HashMap<ChecksummedAddress, VecDeque<Attack>>,
+    /// Жертвы, по которым алерт уже выпущен в текущем окне
+    reported: HashSet<ChecksummedAddress>,
+    /// Когда история последний раз чистилась целиком
+    swept_at: u64,
+}
+
+impl VictimTracker {
+    pub fn new(window_seconds: u64, threshold: f64) -> Self {
+        Self {
+            window_seconds: window_seconds.max(1),
+            threshold,
+            history: HashMap::new(),
+            reported: HashSet::new(),
+            swept_at: 0,
+        }
+    }
+
+    /// Взвешенное число атак на `victim` к моменту `now`
+    pub fn weight(&self, victim: &ChecksummedAddress, now: u64) -> f64 {
+        self.history.get(victim).map_or(0.0, |attacks| {
+            attacks
+                .iter()
+                .map(|a| 1.0 - now.saturating_sub(a.at) as f64 / self.window_seconds as f64)
+                .filter(|w| *w > 0.0)
+                .sum()
+        })
+    }
+
+    /// Учитывает сэндвичи из `alerts`, поднимает их risk score по истории жертвы
+    /// и возвращает `RepeatedTargeting` для жертв, перешедших порог
+    pub fn observe(&mut self, alerts: &mut [MevAlert]) -> Vec<MevAlert> {
+        let mut repeated = Vec::new();
+        for alert in alerts.iter_mut().filter(|a| a.mev_type == MevType::Sandwich && !trap::is_trap(a)) {
+            let (Some(victim), Some(attacker)) = (leg(alert, "target"), leg(alert, "tx1")) else {
+                continue;
+            };
+            let now = alert.timestamp;
+            self.sweep(now);
+            self.prune(&victim.from, now);
+            if !self.history.contains_key(&victim.from) && self.history.len() >= MAX_VICTIMS {
+                self.evict_stalest();
+            }
+            let attacks = self.history.entry(victim.from).or_default();
+            attacks.push_back(Attack {
+                at: now,
+                attacker: attacker.from,
+                target: victim.to,
+                profit: alert.profit,
+            });
+            if attacks.len() > MAX_ATTACKS_PER_VICTIM {
+                attacks.pop_front();
+            }
+
+            let weight = self.weight(&victim.from, now);
+            alert.risk_score = (alert.risk_score + ESCALATION_PER_ATTACK * (weight - 1.0).max(0.0) as f32).min(1.0);
+            alert.metadata["victim_history_weight"] = json!(weight);
+
+            if weight >= self.threshold && self.reported.insert(victim.from) {
+                repeated.push(self.summary(&victim.from, now, weight));
+            }
+        }
+        repeated
+    }
+
+    /// Забывает атаки старше окна; жертва без атак снова может получить алерт
+    fn prune(&mut self, victim: &ChecksummedAddress, now: u64) {
+        let Some(attacks) = self.history.get_mut(victim) else {
+            return;
+        };
+        while attacks.front().is_some_and(|a| now.saturating_sub(a.at) >= self.window_seconds) {
+            attacks.pop_front();
+        }
+        if attacks.is_empty() {
+            self.history.remove(victim);
+            self.reported.remove(victim);
+        }
+    }
+
+    /// Раз в окно забывает жертв, которых с тех пор не атаковали: `prune` видит только
+    /// повторно атакованных
+    fn sweep(&mut self, now: u64) {
+        if now.saturating_sub(self.swept_at) < self.window_seconds {
+            return;
+        }
+        self.swept_at = now;
+        let window = self.window_seconds;
+        self.history.retain(|_, attacks| attacks.back().is_some_and(|a| now.saturating_sub(a.at) < window));
+        let history = &self.history;
+        self.reported.retain(|victim| history.contains_key(victim));
+    }
+
+    fn evict_stalest(&mut self) {
+        let stalest = self.history.iter().min_by_key(|(_, attacks)| attacks.back().map(|a| a.at)).map(|(victim, _)| *victim);
+        if let Some(victim) = stalest {
+            self.history.remove(&victim);
+            self.reported.remove(&victim);
+        }
+    }
+
+    fn summary(&self, victim: &ChecksummedAddress, now: u64, weight: f64) -> MevAlert {
+        let attacks = &self.history[victim];
+        let mut by_attacker: HashMap<ChecksummedAddress, usize> = HashMap::new();
+        for attack in attacks {
+            *by_attacker.entry(attack.attacker).or_default() += 1;
+        }
+        let (top_attacker, top_count) = by_attacker.iter().max_by_key(|(_, n)| **n).map(|(a, n)| (*a, *n)).unwrap();
+        let top_share = top_count as f64 / attacks.len() as f64;
+        // Один бот раз за разом — охота за адресом; много разных — утечка через RPC/мемпул кошелька
+        let pattern = if top_share >= TARGETED_BOT_SHARE { "targeted_bot" } else { "leaked_rpc" };
+
+        let targets: HashSet<ChecksummedAddress> = attacks.iter().map(|a| a.target).collect();
+        let profit = attacks.iter().fold(WeiAmount::default(), |acc, a| acc + a.profit);
+        MevAlert {
+            schema_version: crate::schema::MEV_ALERT_VERSION,
+            mev_type: MevType::RepeatedTargeting,
+            profit,
+            risk_score: (0.5 + 0.1 * weight as f32).min(1.0),
+            timestamp: now,
+            metadata: json!({
+                "victim": victim.to_string(),
+                "attacks": attacks.len(),
+                "weight": weight,
+                "window_seconds": self.window_seconds,
+                "first_seen": attacks.front().map(|a| a.at),
+                "last_seen": attacks.back().map(|a| a.at),
+                "distinct_attackers": by_attacker.len(),
+                "top_attacker": top_attacker.to_string(),
+                "top_attacker_share": top_share,
+                "targets": targets.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "pattern": pattern,
+            }),
+            latency: None,
+            confirmed_onchain: None,
+        }
+    }
+}
+
+fn leg(alert: &MevAlert, key: &str) -> Option<Tx> {
+    serde_json::from_value(alert.metadata[key].clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{addr, TxBuilder};
+
+    fn sandwich(victim: u8, attacker: u8, at: u64) -> MevAlert {
+        MevAlert {
+            schema_version: crate::schema::MEV_ALERT_VERSION,
+            mev_type: MevType::Sandwich,
+            profit: WeiAmount::from_eth(0.1),
+            risk_score: 0.3,
+            timestamp: at,
+            metadata: json!({
+                "tx1": TxBuilder::new().from(addr(attacker)).build(),
+                "target": TxBuilder::new().from(addr(victim)).build(),
+            }),
+            latency: None,
+            confirmed_onchain: None,
+        }
+    }
+
+    #[test]
+    fn test_repeated_sandwiches_escalate_once_per_window() {
+        let mut tracker = VictimTracker::new(600, 2.5);
+        assert!(tracker.observe(&mut [sandwich(0x01, 0xa1, 0)]).is_empty());
+        assert!(tracker.observe(&mut [sandwich(0x01, 0xa1, 60)]).is_empty());
+
+        let mut third = [sandwich(0x01, 0xa1, 120)];
+        let repeated = tracker.observe(&mut third);
+        assert!(third[0].risk_score > 0.3);
+        assert_eq!(repeated.len(), 1);
+        assert_eq!(repeated[0].mev_type, MevType::RepeatedTargeting);
+        assert_eq!(repeated[0].metadata["pattern"], "targeted_bot");
+        assert!(tracker.observe(&mut [sandwich(0x01, 0xa2, 130)]).is_empty());
+
+        // Атаки далеко друг от друга порог не набирают
+        let mut sparse = VictimTracker::new(600, 2.5);
+        for at in [0, 500, 1000] {
+            assert!(sparse.observe(&mut [sandwich(0x02, 0xa1, at)]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_forgets_victims_attacked_once() {
+        let mut tracker = VictimTracker::new(600, 2.5);
+        for victim in 0x10..0x20 {
+            tracker.observe(&mut [sandwich(victim, 0xa1, 0)]);
+        }
+        assert_eq!(tracker.history.len(), 16);
+
+        tracker.observe(&mut [sandwich(0x01, 0xa1, 700)]);
+        assert_eq!(tracker.history.len(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::address::ChecksummedAddress;
+use crate::compat::Address;
+use crate::enrichment::{Enricher, Enrichment};
+use crate::state::{CachedStateProvider, StateError, StateProvider};
+use crate::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use crate::tx::Tx;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Самые частые цели транзакций мемпула. Счётчики делятся пополам каждый блок,
+/// так что «горячесть» отражает последние блоки, а не всю историю
+#[derive(Default)]
+pub struct HotTargets {
+    hits: Mutex<HashMap<ChecksummedAddress, u64>>,
+    pinned: Mutex<HashSet<ChecksummedAddress>>,
+}
+
+impl HotTargets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, target: &ChecksummedAddress) {
+        if !target.is_zero() {
+            *self.hits.lock().unwrap().entry(*target).or_default() += 1;
+        }
+    }
+
+    /// Цель, которая прогревается всегда (роутеры, основные пулы)
+    pub fn pin(&self, target: ChecksummedAddress) {
+        self.pinned.lock().unwrap().insert(target);
+    }
+
+    /// Закреплённые цели и затем `k` самых частых из остальных
+    pub fn top(&self, k: usize) -> Vec<ChecksummedAddress> {
+        let pinned = self.pinned.lock().unwrap();
+        let hits = self.hits.lock().unwrap();
+        let mut ranked: Vec<(&ChecksummedAddress, &u64)> = hits.iter().filter(|(a, _)| !pinned.contains(a)).collect();
+        ranked.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        let mut top: Vec<ChecksummedAddress> = pinned.iter().copied().collect();
+        top.sort();
+        top.extend(ranked.into_iter().take(k).map(|(a, _)| *a));
+        top
+    }
+
+    fn decay(&self) {
+        self.hits.lock().unwrap().retain(|_, hits| {
+            *hits /= 2;
+            *hits > 0
+        });
+    }
+}
+
+/// Подключается как источник обогащения в стадию конвейера: видит каждую транзакцию
+impl Enricher for HotTargets {
+    fn enrich(&self, tx: &Tx, _out: &mut Enrichment) {
+        self.record(&tx.to);
+    }
+}
+
+/// Фоновый прогрев кэша состояния на каждом новом блоке: аккаунт, код и слоты,
+/// читавшиеся в прошлом блоке, для горячих целей
+pub struct StateWarmer<S> {
+    cache: Arc<CachedStateProvider<S>>,
+    hot: Arc<HotTargets>,
+    top_k: usize,
+    /// Сколько прошлых блоков держать в кэше
+    keep_blocks: u64,
+    errors: TaskErrors,
+}
+
+impl<S: StateProvider> StateWarmer<S> {
+    pub fn new(cache: Arc<CachedStateProvider<S>>, hot: Arc<HotTargets>, top_k: usize) -> Self {
+        Self { cache, hot, top_k, keep_blocks: 2, errors: TaskErrors::default() }
+    }
+
+    /// Неудачные прогревы
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    pub fn with_keep_blocks(mut self, keep_blocks: u64) -> Self {
+        self.keep_blocks = keep_blocks;
+        self
+    }
+
+    /// Прогревает состояние блока `block`; возвращает число прогретых целей.
+    /// Старые блоки убираются до прогрева, так что неудачный прогрев не оставляет их в кэше;
+    /// прошлый блок остаётся всегда — по нему выбираются слоты
+    pub async fn warm(&self, block: u64) -> usize {
+        self.cache.prune_before(block.saturating_sub(self.keep_blocks.max(1)));
+        let mut warmed = 0;
+        for target in self.hot.top(self.top_k) {
+            match self.warm_target(target.address(), block).await {
+                Ok(()) => warmed += 1,
+                Err(e) => self.errors.record(format!("state warm-up of {} at block {} failed: {}", target, block, e)),
+            }
+        }
+        self.hot.decay();
+        warmed
+    }
+
+    async fn warm_target(&self, address: Address, block: u64) -> Result<(), StateError> {
+        self.cache.get_account(address, block).await?;
+        self.cache.get_code(address, block).await?;
+        for slot in self.cache.slots_at(address, block.saturating_sub(1)) {
+            self.cache.get_storage(address, slot, block).await?;
+        }
+        Ok(())
+    }
+
+    /// Прогрев на каждый новый блок из `heads`; пропущенные промежуточные блоки
+    /// не прогреваются. Завершается, когда отправитель закрыт
+    pub async fn run(&self, mut heads: watch::Receiver<u64>) {
+        while heads.changed().await.is_ok() {
+            let block = *heads.borrow_and_update();
+            self.warm(block).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::addr;
+
+    #[test]
+    fn test_top_keeps_pinned_and_decays() {
+        let hot = HotTargets::new();
+        hot.pin(addr(0x01));
+        for _ in 0..3 {
+            hot.record(&addr(0x02));
+        }
+        hot.record(&addr(0x03));
+        hot.record(&ChecksummedAddress::ZERO);
+
+        assert_eq!(hot.top(1), [addr(0x01), addr(0x02)]);
+        hot.decay();
+        // 0x03 (1 -> 0) выпадает, 0x02 (3 -> 1) остаётся
+        assert_eq!(hot.top(5), [addr(0x01), addr(0x02)]);
+    }
+}
@@ -0,0 +1,222 @@
+use super::monitor::{asset_balance, MonitorError};
+use ethers::providers::Middleware;
+use ethers::types::{Address, U256};
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Способ оценки базовой линии потоков
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum BaselineMethod {
+    /// Среднее и дисперсия по скользящему окну последних N интервалов
+    ZScore { window: usize },
+    /// Экспоненциально взвешенные среднее и дисперсия
+    Ewma { alpha: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    pub method: BaselineMethod,
+    /// Сколько сигм отклонения считать аномалией
+    pub threshold_sigma: f64,
+    /// Интервалов до первых алертов — пока базовая линия не устоялась
+    pub warmup_samples: usize,
+    /// Отток меньше этой доли баланса не алертится даже при большом z
+    pub min_outflow_fraction: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            method: BaselineMethod::Ewma { alpha: 0.05 },
+            threshold_sigma: 4.0,
+            warmup_samples: 30,
+            min_outflow_fraction: 0.02,
+        }
+    }
+}
+
+/// Статистика относительных изменений баланса одного актива
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowBaseline {
+    mean: f64,
+    variance: f64,
+    samples: usize,
+    window: VecDeque<f64>,
+}
+
+/// Нижняя граница σ: у стабильных контрактов дисперсия почти нулевая
+const MIN_SIGMA: f64 = 1e-4;
+
+impl FlowBaseline {
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Отклонение наблюдения от текущей базовой линии в сигмах
+    pub fn z_score(&self, x: f64) -> f64 {
+        (x - self.mean) / self.variance.sqrt().max(MIN_SIGMA)
+    }
+
+    /// Добавляет наблюдение и возвращает его z-score относительно прежней линии
+    pub fn observe(&mut self, x: f64, method: BaselineMethod) -> f64 {
+        let z = self.z_score(x);
+
+        match method {
+            BaselineMethod::ZScore { window } => {
+                self.window.push_back(x);
+                while self.window.len() > window.max(2) {
+                    self.window.pop_front();
+                }
+                let n = self.window.len() as f64;
+                self.mean = self.window.iter().sum::<f64>() / n;
+                self.variance = self.window.iter().map(|v| (v - self.mean).powi(2)).sum::<f64>() / n;
+            }
+            BaselineMethod::Ewma { alpha } => {
+                if self.samples == 0 {
+                    self.mean = x;
+                } else {
+                    let diff = x - self.mean;
+                    self.mean += alpha * diff;
+                    self.variance = (1.0 - alpha) * (self.variance + alpha * diff * diff);
+                }
+            }
+        }
+        self.samples += 1;
+
+        z
+    }
+}
+
+/// Аномальный отток средств протокола
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowAnomaly {
+    pub protocol: Address,
+    pub asset: Address,
+    pub block: u64,
+    pub previous: U256,
+    pub current: U256,
+    /// Относительное изменение баланса (отрицательное — отток)
+    pub change: f64,
+    pub z_score: f64,
+}
+
+struct TrackedAsset {
+    balance: U256,
+    baseline: FlowBaseline,
+}
+
+/// Базовые линии TVL наблюдаемых протоколов — страховка от неизвестных атак,
+/// не зависящая от сигнатур эксплойтов
+pub struct BaselineEngine<M> {
+    provider: Arc<M>,
+    bus: AlertBus,
+    config: AnomalyConfig,
+    tracked: HashMap<(Address, Address), TrackedAsset>,
+}
+
+fn relative_change(previous: U256, current: U256) -> f64 {
+    if previous.is_zero() {
+        return 0.0;
+    }
+    let scale = U256::from(1_000_000u64);
+    if current >= previous {
+        ((current - previous) * scale / previous).min(U256::from(u64::MAX)).as_u64() as f64 / 1e6
+    } else {
+        -(((previous - current) * scale / previous).as_u64() as f64 / 1e6)
+    }
+}
+
+impl<M: Middleware> BaselineEngine<M> {
+    pub fn new(provider: Arc<M>, bus: AlertBus, config: AnomalyConfig) -> Self {
+        Self {
+            provider,
+            bus,
+            config,
+            tracked: HashMap::new(),
+        }
+    }
+
+    /// Начинает отслеживать балансы активов протокола; `Address::zero()` — ETH
+    pub async fn watch(&mut self, protocol: Address, assets: &[Address], block: u64) -> Result<(), MonitorError> {
+        for asset in assets {
+            let balance = asset_balance(self.provider.as_ref(), *asset, protocol, block).await?;
+            self.tracked.insert(
+                (protocol, *asset),
+                TrackedAsset { balance, baseline: FlowBaseline::default() },
+            );
+        }
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, protocol: Address) {
+        self.tracked.retain(|(p, _), _| *p != protocol);
+    }
+
+    pub fn baseline(&self, protocol: Address, asset: Address) -> Option<&FlowBaseline> {
+        self.tracked.get(&(protocol, asset)).map(|t| &t.baseline)
+    }
+
+    /// Обновляет базовые линии на блоке и публикует аномальные оттоки в шину
+    pub async fn poll(&mut self, block: u64) -> Result<Vec<FlowAnomaly>, MonitorError> {
+        let keys: Vec<(Address, Address)> = self.tracked.keys().copied().collect();
+        let mut anomalies = Vec::new();
+
+        for (protocol, asset) in keys {
+            let current = asset_balance(self.provider.as_ref(), asset, protocol, block).await?;
+            let tracked = self.tracked.get_mut(&(protocol, asset)).expect("asset is tracked");
+            let previous = std::mem::replace(&mut tracked.balance, current);
+
+            let change = relative_change(previous, current);
+            let warmed_up = tracked.baseline.samples() >= self.config.warmup_samples;
+            let z = tracked.baseline.observe(change, self.config.method);
+
+            if warmed_up && z <= -self.config.threshold_sigma && -change >= self.config.min_outflow_fraction {
+                anomalies.push(FlowAnomaly { protocol, asset, block, previous, current, change, z_score: z });
+            }
+        }
+
+        for a in &anomalies {
+            let level = if -a.change >= 0.25 { AlertLevel::Critical } else { AlertLevel::High };
+            self.bus.publish(
+                BusAlert::new(
+                    "anomaly",
+                    "tvl_outflow",
+                    level,
+                    format!("{:?}", a.protocol),
+                    format!("Anomalous outflow {:.2}% of {:?} ({:.1}σ)", -a.change * 100.0, a.asset, a.z_score),
+                )
+                .with_payload(json!(a)),
+            );
+        }
+
+        Ok(anomalies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_outflow_stands_out_after_noise() {
+        for method in [BaselineMethod::Ewma { alpha: 0.1 }, BaselineMethod::ZScore { window: 50 }] {
+            let mut baseline = FlowBaseline::default();
+            for i in 0..100 {
+                let noise = if i % 2 == 0 { 0.001 } else { -0.001 };
+                baseline.observe(noise, method);
+            }
+            assert!(baseline.z_score(-0.0015).abs() < 4.0);
+            assert!(baseline.z_score(-0.3) < -100.0);
+        }
+    }
+
+    #[test]
+    fn test_relative_change() {
+        assert_eq!(relative_change(U256::from(100u64), U256::from(75u64)), -0.25);
+        assert_eq!(relative_change(U256::zero(), U256::from(5u64)), 0.0);
+    }
+}
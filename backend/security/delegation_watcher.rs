@@ -0,0 +1,329 @@
+use super::monitor::alert_level;
+use super::report::{SecurityReport, Severity};
+use super::upgrade_watcher::{AuditError, ContractAuditor};
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockId, BlockNumber, Signature, Transaction, H256, U256, U64};
+use ethers::utils::{keccak256, rlp::RlpStream};
+use mevdetector::bus::{AlertLevel, BusAlert};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Тип транзакции EIP-7702 (set code)
+pub const SET_CODE_TX_TYPE: u64 = 4;
+/// Префикс кода делегированного EOA: `0xef0100 || address`
+pub const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+/// Префикс подписываемого сообщения авторизации
+const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// Элемент `authorizationList` в ответе узла
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAuthorization {
+    chain_id: U256,
+    address: Address,
+    nonce: U256,
+    #[serde(alias = "v")]
+    y_parity: U64,
+    r: U256,
+    s: U256,
+}
+
+impl RawAuthorization {
+    /// Подписант авторизации: `ecrecover(keccak(0x05 || rlp([chain_id, address, nonce])))`
+    fn authority(&self) -> Option<Address> {
+        let mut rlp = RlpStream::new_list(3);
+        rlp.append(&self.chain_id);
+        rlp.append(&self.address);
+        rlp.append(&self.nonce);
+        let mut message = vec![AUTHORIZATION_MAGIC];
+        message.extend_from_slice(&rlp.out());
+
+        let signature = Signature { r: self.r, s: self.s, v: self.y_parity.as_u64() };
+        signature.recover(H256::from(keccak256(message))).ok()
+    }
+}
+
+/// Авторизация 7702, затронувшая наблюдаемый адрес
+#[derive(Debug, Clone, Serialize)]
+pub struct Delegation {
+    pub authority: Address,
+    /// Отправитель транзакции; совпадает с `authority`, если EOA делегировал себя сам
+    pub sponsor: Address,
+    /// Код, к которому привязан EOA; нулевой адрес — отзыв делегирования
+    pub delegate: Address,
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub tx_hash: H256,
+    pub block: u64,
+    /// Код EOA после блока действительно указывает на `delegate`
+    pub applied: bool,
+}
+
+impl Delegation {
+    pub fn is_revocation(&self) -> bool {
+        self.delegate.is_zero()
+    }
+}
+
+/// Делегирование с результатом проверки кода
+#[derive(Debug, Clone, Serialize)]
+pub struct DelegationEvent {
+    pub delegation: Delegation,
+    /// Реализация в списке проверенных оператором
+    pub verified: bool,
+    /// Отчёт аудита кода реализации; `None` — отзыв или кода нет
+    pub report: Option<SecurityReport>,
+    pub requires_alert: bool,
+}
+
+/// Адрес, на который указывает код делегированного EOA
+pub fn delegated_to(code: &[u8]) -> Option<Address> {
+    (code.len() == 23 && code.starts_with(&DELEGATION_PREFIX)).then(|| Address::from_slice(&code[3..]))
+}
+
+/// Следит за авторизациями EIP-7702 наблюдаемых адресов и проверяет код, к которому
+/// они привязываются, тем же аудитором, что и реализации прокси
+pub struct DelegationWatcher<M> {
+    provider: Arc<M>,
+    auditor: Arc<dyn ContractAuditor>,
+    alert_threshold: Severity,
+    watched: HashSet<Address>,
+    /// Реализации, проверенные оператором (кошельки-смарт-аккаунты)
+    trusted: HashSet<Address>,
+    /// Отчёты по реализациям: одна реализация обслуживает много EOA
+    reports: HashMap<Address, SecurityReport>,
+}
+
+impl<M: Middleware> DelegationWatcher<M> {
+    pub fn new(provider: Arc<M>, auditor: Arc<dyn ContractAuditor>, alert_threshold: Severity) -> Self {
+        Self {
+            provider,
+            auditor,
+            alert_threshold,
+            watched: HashSet::new(),
+            trusted: HashSet::new(),
+            reports: HashMap::new(),
+        }
+    }
+
+    pub fn with_trusted(mut self, implementation: Address) -> Self {
+        self.trusted.insert(implementation);
+        self
+    }
+
+    pub fn watch(&mut self, address: Address) {
+        self.watched.insert(address);
+    }
+
+    pub fn unwatch(&mut self, address: Address) {
+        self.watched.remove(&address);
+    }
+
+    /// Авторизации блока, где наблюдаемый адрес — подписант или отправитель
+    pub async fn delegations(&self, block: u64) -> Result<Vec<Delegation>, AuditError> {
+        let provider_err = |e: M::Error| AuditError::ProviderError(e.to_string());
+        let Some(block_data) = self
+            .provider
+            .get_block_with_txs(BlockId::Number(BlockNumber::Number(block.into())))
+            .await
+            .map_err(provider_err)?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut delegations = Vec::new();
+        for tx in block_data.transactions.iter().filter(|tx| is_set_code(tx)) {
+            for auth in authorizations(tx) {
+                let Some(authority) = auth.authority() else {
+                    continue;
+                };
+                if !self.watched.contains(&authority) && !self.watched.contains(&tx.from) {
+                    continue;
+                }
+                delegations.push(Delegation {
+                    authority,
+                    sponsor: tx.from,
+                    delegate: auth.address,
+                    chain_id: auth.chain_id.low_u64(),
+                    nonce: auth.nonce.low_u64(),
+                    tx_hash: tx.hash,
+                    block,
+                    applied: false,
+                });
+            }
+        }
+
+        for delegation in &mut delegations {
+            let code = self
+                .provider
+                .get_code(delegation.authority, Some(BlockId::Number(BlockNumber::Number(block.into()))))
+                .await
+                .map_err(provider_err)?;
+            delegation.applied = match delegated_to(&code) {
+                Some(delegate) => delegate == delegation.delegate,
+                None => delegation.is_revocation() && code.is_empty(),
+            };
+        }
+        Ok(delegations)
+    }
+
+    /// Код реализации читается на блоке авторизации: к этому моменту относится делегирование
+    async fn audit_delegate(&mut self, authority: Address, delegate: Address, block: u64) -> Result<Option<SecurityReport>, AuditError> {
+        if let Some(report) = self.reports.get(&delegate) {
+            return Ok(Some(report.clone()));
+        }
+        let code = self
+            .provider
+            .get_code(delegate, Some(BlockId::Number(BlockNumber::Number(block.into()))))
+            .await
+            .map_err(|e| AuditError::ProviderError(e.to_string()))?;
+        if code.is_empty() {
+            return Ok(None);
+        }
+        let report = self.auditor.audit(authority, delegate, &code).await?;
+        self.reports.insert(delegate, report.clone());
+        Ok(Some(report))
+    }
+
+    /// Проверяет авторизации блока; вызывается на каждый новый блок
+    pub async fn poll(&mut self, block: u64) -> Result<Vec<DelegationEvent>, AuditError> {
+        let mut events = Vec::new();
+        for delegation in self.delegations(block).await? {
+            if delegation.is_revocation() {
+                events.push(DelegationEvent { delegation, verified: true, report: None, requires_alert: false });
+                continue;
+            }
+            let verified = self.trusted.contains(&delegation.delegate);
+            let report = self.audit_delegate(delegation.authority, delegation.delegate, block).await?;
+            let risky = report.as_ref().and_then(SecurityReport::max_severity).is_some_and(|s| s >= self.alert_threshold);
+            events.push(DelegationEvent {
+                requires_alert: delegation.applied && (!verified || risky || report.is_none()),
+                delegation,
+                verified,
+                report,
+            });
+        }
+        Ok(events)
+    }
+}
+
+fn is_set_code(tx: &Transaction) -> bool {
+    tx.transaction_type.is_some_and(|t| t.as_u64() == SET_CODE_TX_TYPE)
+}
+
+/// `authorizationList` не входит в `Transaction` ethers и приходит в `other`
+fn authorizations(tx: &Transaction) -> Vec<RawAuthorization> {
+    tx.other
+        .get("authorizationList")
+        .and_then(|list| serde_json::from_value(list.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Алерт по делегированию: о рискованных и об отзывах
+pub fn delegation_alert(event: &DelegationEvent) -> BusAlert {
+    let delegation = &event.delegation;
+    let severity = event.report.as_ref().and_then(SecurityReport::max_severity);
+    let level = match (delegation.is_revocation(), event.report.is_some(), event.verified) {
+        (true, _, _) => AlertLevel::Info,
+        (false, false, _) => AlertLevel::Critical,
+        (false, true, true) => severity.map(alert_level).unwrap_or(AlertLevel::Low),
+        (false, true, false) => severity.map(alert_level).unwrap_or(AlertLevel::High).max(AlertLevel::High),
+    };
+    let title = if delegation.is_revocation() {
+        format!("EOA {:?} revoked its EIP-7702 delegation", delegation.authority)
+    } else {
+        let status = match (event.report.is_some(), event.verified) {
+            (false, _) => "address without code",
+            (true, true) => "verified implementation",
+            (true, false) => "unverified implementation",
+        };
+        format!("EOA {:?} delegated to {} {:?}", delegation.authority, status, delegation.delegate)
+    };
+    BusAlert::new("monitor", "delegation", level, format!("{:?}", delegation.authority), title)
+        .with_payload(serde_json::to_value(event).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use ethers::providers::Provider;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{Block, Bytes};
+    use serde_json::json;
+
+    struct CleanAuditor;
+
+    #[async_trait]
+    impl ContractAuditor for CleanAuditor {
+        async fn audit(&self, proxy: Address, implementation: Address, _code: &[u8]) -> Result<SecurityReport, AuditError> {
+            let mut report = SecurityReport::new(proxy);
+            report.implementation = Some(implementation);
+            Ok(report)
+        }
+    }
+
+    fn authorization(wallet: &LocalWallet, delegate: Address) -> serde_json::Value {
+        let mut rlp = RlpStream::new_list(3);
+        rlp.append(&U256::one());
+        rlp.append(&delegate);
+        rlp.append(&U256::zero());
+        let mut message = vec![AUTHORIZATION_MAGIC];
+        message.extend_from_slice(&rlp.out());
+        let signature = wallet.sign_hash(H256::from(keccak256(message))).unwrap();
+        json!({
+            "chainId": "0x1",
+            "address": delegate,
+            "nonce": "0x0",
+            "yParity": format!("{:#x}", signature.v - 27),
+            "r": signature.r,
+            "s": signature.s,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_audits_the_delegate_at_the_authorization_block() {
+        let wallet: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318".parse().unwrap();
+        let delegate = Address::repeat_byte(0xd7);
+        let tx = Transaction {
+            hash: H256::repeat_byte(1),
+            from: Address::repeat_byte(0x99),
+            transaction_type: Some(SET_CODE_TX_TYPE.into()),
+            other: serde_json::from_value(json!({ "authorizationList": [authorization(&wallet, delegate)] })).unwrap(),
+            ..Default::default()
+        };
+        let block = Block { number: Some(100.into()), transactions: vec![tx], ..Default::default() };
+
+        let (provider, mock) = Provider::mocked();
+        let delegated: Vec<u8> = DELEGATION_PREFIX.iter().copied().chain(delegate.as_bytes().iter().copied()).collect();
+        // Ответы снимаются с конца: блок, код EOA, код реализации
+        mock.push::<Bytes, _>(Bytes::from(vec![0x60, 0x00])).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(delegated)).unwrap();
+        mock.push(block).unwrap();
+
+        let mut watcher = DelegationWatcher::new(Arc::new(provider), Arc::new(CleanAuditor), Severity::High);
+        watcher.watch(wallet.address());
+        let events = watcher.poll(100).await.unwrap();
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.delegation.authority, wallet.address());
+        assert!(event.delegation.applied);
+        assert!(!event.verified && event.requires_alert);
+        assert_eq!(delegation_alert(event).level, AlertLevel::High);
+
+        mock.assert_request("eth_getBlockByNumber", ("0x64", true)).unwrap();
+        mock.assert_request("eth_getCode", (wallet.address(), "0x64")).unwrap();
+        mock.assert_request("eth_getCode", (delegate, "0x64")).unwrap();
+    }
+
+    #[test]
+    fn test_delegated_to_reads_only_the_designator() {
+        let delegate = Address::repeat_byte(0x42);
+        let code: Vec<u8> = DELEGATION_PREFIX.iter().copied().chain(delegate.as_bytes().iter().copied()).collect();
+        assert_eq!(delegated_to(&code), Some(delegate));
+        assert_eq!(delegated_to(&code[..22]), None);
+        assert_eq!(delegated_to(&[0x60; 23]), None);
+    }
+}
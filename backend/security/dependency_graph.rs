@@ -0,0 +1,328 @@
+use super::report::SecurityReport;
+use super::upgrade_watcher::EIP1967_IMPLEMENTATION_SLOT;
+use ethers::contract::abigen;
+use ethers::providers::Middleware;
+use ethers::types::{Address, H256};
+use mevdetector::multicall::{Batch, BatchReader};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Сколько первых слотов хранилища просматривать на предмет адресов
+const SCANNED_SLOTS: u64 = 16;
+
+abigen!(
+    RoleProbe,
+    r#"[
+        function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80)
+        function WETH() external view returns (address)
+        function factory() external view returns (address)
+        function decimals() external view returns (uint8)
+        function totalSupply() external view returns (uint256)
+    ]"#
+);
+
+#[derive(Debug, Error)]
+pub enum GraphError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+/// Роль зависимости, определённая по интерфейсу
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyRole {
+    Root,
+    Oracle,
+    Router,
+    Token,
+    Contract,
+}
+
+/// Откуда взялась ссылка на адрес
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EdgeSource {
+    /// PUSH20-константа в байткоде (immutable или захардкоженный адрес)
+    Bytecode,
+    Storage { slot: u64 },
+    Implementation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyNode {
+    pub address: Address,
+    pub role: DependencyRole,
+    pub depth: usize,
+    pub upgradeable: bool,
+    pub risk_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyEdge {
+    pub from: Address,
+    pub to: Address,
+    pub source: EdgeSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyGraph {
+    pub root: Address,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+    /// Узлы, не развёрнутые из-за лимита
+    pub truncated: bool,
+}
+
+/// Адреса из PUSH20-констант байткода
+pub fn bytecode_addresses(code: &[u8]) -> Vec<Address> {
+    let mut found = Vec::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let op = code[pc];
+        if (0x60..=0x7f).contains(&op) {
+            let size = (op - 0x5f) as usize;
+            if op == 0x73 && pc + 21 <= code.len() {
+                let address = Address::from_slice(&code[pc + 1..pc + 21]);
+                if is_plausible_address(&address) && !found.contains(&address) {
+                    found.push(address);
+                }
+            }
+            pc += size;
+        }
+        pc += 1;
+    }
+
+    found
+}
+
+/// Отсекает маски (`type(uint160).max`), прекомпайлы и мелкие константы
+fn is_plausible_address(address: &Address) -> bool {
+    let bytes = address.as_bytes();
+    let leading_zeros = bytes.iter().take_while(|b| **b == 0).count();
+    leading_zeros < 8 && !bytes.iter().all(|b| *b == 0xff)
+}
+
+/// Слово хранилища, похожее на адрес: старшие 12 байт нулевые
+fn word_as_address(word: H256) -> Option<Address> {
+    let bytes = word.as_bytes();
+    if bytes[..12].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let address = Address::from_slice(&bytes[12..]);
+    is_plausible_address(&address).then_some(address)
+}
+
+/// Построитель графа зависимостей обходом в ширину
+pub struct DependencyGraphBuilder<M> {
+    provider: Arc<M>,
+    reader: BatchReader<M>,
+    max_depth: usize,
+    max_nodes: usize,
+    /// Внешние оценки риска, например `1 - security_score` из аудита
+    known_risk: HashMap<Address, f64>,
+}
+
+impl<M: Middleware + 'static> DependencyGraphBuilder<M> {
+    pub fn new(provider: Arc<M>, max_depth: usize, max_nodes: usize) -> Self {
+        Self {
+            reader: BatchReader::new(provider.clone()),
+            provider,
+            max_depth,
+            max_nodes,
+            known_risk: HashMap::new(),
+        }
+    }
+
+    /// Использует результаты аудита как риск узла вместо эвристики
+    pub fn with_reports(mut self, reports: &[SecurityReport]) -> Self {
+        for r in reports {
+            self.known_risk.insert(r.address, 1.0 - r.security_score);
+        }
+        self
+    }
+
+    /// Роль по интерфейсу: все пробные вызовы уходят одним пакетом Multicall3
+    async fn classify(&self, address: Address) -> DependencyRole {
+        let probe = RoleProbe::new(address, self.provider.clone());
+        let mut batch = Batch::new();
+        let (Ok(oracle), Ok(weth), Ok(factory), Ok(decimals), Ok(total_supply)) = (
+            batch.add(&probe.latest_round_data()),
+            batch.add(&probe.weth()),
+            batch.add(&probe.factory()),
+            batch.add(&probe.decimals()),
+            batch.add(&probe.total_supply()),
+        ) else {
+            return DependencyRole::Contract;
+        };
+        let Ok(results) = self.reader.execute(&batch, None).await else {
+            return DependencyRole::Contract;
+        };
+        if results.ok(&oracle).is_some() {
+            DependencyRole::Oracle
+        } else if results.ok(&weth).is_some() || results.ok(&factory).is_some() {
+            DependencyRole::Router
+        } else if results.ok(&decimals).is_some() && results.ok(&total_supply).is_some() {
+            DependencyRole::Token
+        } else {
+            DependencyRole::Contract
+        }
+    }
+
+    async fn storage(&self, address: Address, slot: H256) -> Result<H256, GraphError> {
+        self.provider
+            .get_storage_at(address, slot, None)
+            .await
+            .map_err(|e| GraphError::ProviderError(e.to_string()))
+    }
+
+    async fn is_contract(&self, address: Address) -> Result<bool, GraphError> {
+        let code = self
+            .provider
+            .get_code(address, None)
+            .await
+            .map_err(|e| GraphError::ProviderError(e.to_string()))?;
+        Ok(!code.is_empty())
+    }
+
+    /// Эвристика риска: апгрейдабельность и оракулы (поверхность манипуляции ценой)
+    fn heuristic_risk(&self, address: Address, role: DependencyRole, upgradeable: bool) -> f64 {
+        if let Some(risk) = self.known_risk.get(&address) {
+            return *risk;
+        }
+        let mut risk: f64 = 0.1;
+        if upgradeable {
+            risk += 0.3;
+        }
+        if role == DependencyRole::Oracle {
+            risk += 0.2;
+        }
+        risk.min(1.0)
+    }
+
+    /// Ссылки одного контракта: реализация, слоты хранилища и константы байткода
+    async fn references(&self, address: Address) -> Result<(bool, Vec<(Address, EdgeSource)>), GraphError> {
+        let mut refs = Vec::new();
+
+        let impl_slot: H256 = EIP1967_IMPLEMENTATION_SLOT.parse().expect("valid slot constant");
+        let implementation = word_as_address(self.storage(address, impl_slot).await?);
+        if let Some(implementation) = implementation {
+            refs.push((implementation, EdgeSource::Implementation));
+        }
+
+        for slot in 0..SCANNED_SLOTS {
+            let word = self.storage(address, H256::from_low_u64_be(slot)).await?;
+            if let Some(a) = word_as_address(word) {
+                refs.push((a, EdgeSource::Storage { slot }));
+            }
+        }
+
+        let code = self
+            .provider
+            .get_code(implementation.unwrap_or(address), None)
+            .await
+            .map_err(|e| GraphError::ProviderError(e.to_string()))?;
+        refs.extend(bytecode_addresses(&code).into_iter().map(|a| (a, EdgeSource::Bytecode)));
+
+        Ok((implementation.is_some(), refs))
+    }
+
+    pub async fn build(&self, root: Address) -> Result<DependencyGraph, GraphError> {
+        let mut nodes: Vec<DependencyNode> = Vec::new();
+        let mut edges = Vec::new();
+        let mut visited = HashSet::from([root]);
+        let mut queue = VecDeque::from([(root, 0usize)]);
+        let mut truncated = false;
+
+        while let Some((address, depth)) = queue.pop_front() {
+            let (upgradeable, refs) = self.references(address).await?;
+            let role = if address == root {
+                DependencyRole::Root
+            } else {
+                self.classify(address).await
+            };
+            nodes.push(DependencyNode {
+                address,
+                role,
+                depth,
+                upgradeable,
+                risk_score: self.heuristic_risk(address, role, upgradeable),
+            });
+
+            for (to, source) in refs {
+                if to == address || !self.is_contract(to).await? {
+                    continue;
+                }
+                edges.push(DependencyEdge { from: address, to, source });
+
+                if visited.contains(&to) {
+                    continue;
+                }
+                if depth + 1 > self.max_depth || visited.len() >= self.max_nodes {
+                    truncated = true;
+                    continue;
+                }
+                visited.insert(to);
+                queue.push_back((to, depth + 1));
+            }
+        }
+
+        // Рёбра к неразвёрнутым узлам убираем, чтобы граф был замкнутым
+        edges.retain(|e| visited.contains(&e.to));
+
+        Ok(DependencyGraph { root, nodes, edges, truncated })
+    }
+}
+
+impl DependencyGraph {
+    /// Совокупный риск: максимум по узлам, ослабленный с глубиной
+    pub fn blast_radius_risk(&self) -> f64 {
+        self.nodes
+            .iter()
+            .map(|n| n.risk_score * 0.8f64.powi(n.depth as i32))
+            .fold(0.0, f64::max)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("graph is serializable")
+    }
+
+    /// Экспорт в Graphviz DOT; цвет узла отражает риск
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box, style=filled];\n");
+
+        for n in &self.nodes {
+            let color = if n.risk_score >= 0.6 {
+                "#f4a6a6"
+            } else if n.risk_score >= 0.3 {
+                "#f9e0a2"
+            } else {
+                "#c8e6c9"
+            };
+            let _ = writeln!(
+                out,
+                "    \"{:?}\" [label=\"{:?}\\n{:?} risk={:.2}{}\", fillcolor=\"{}\"];",
+                n.address,
+                n.address,
+                n.role,
+                n.risk_score,
+                if n.upgradeable { "\\nupgradeable" } else { "" },
+                color
+            );
+        }
+        for e in &self.edges {
+            let label = match &e.source {
+                EdgeSource::Bytecode => "code".to_string(),
+                EdgeSource::Storage { slot } => format!("slot {}", slot),
+                EdgeSource::Implementation => "impl".to_string(),
+            };
+            let _ = writeln!(out, "    \"{:?}\" -> \"{:?}\" [label=\"{}\"];", e.from, e.to, label);
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
@@ -0,0 +1,285 @@
+use super::report::{Finding, FixSuggestion, FindingSource, SecurityReport};
+use std::collections::HashMap;
+
+/// Исходники контракта (получены из Etherscan или локального проекта)
+pub trait SourceProvider {
+    fn source(&self, file: &str) -> Option<String>;
+}
+
+impl SourceProvider for HashMap<String, String> {
+    fn source(&self, file: &str) -> Option<String> {
+        self.get(file).cloned()
+    }
+}
+
+/// Строк контекста вокруг правки в патче
+const CONTEXT: usize = 2;
+
+/// Замена `remove` строк начиная с `start` (с нуля) на `insert`
+struct Edit {
+    start: usize,
+    remove: usize,
+    insert: Vec<String>,
+}
+
+fn render_patch(file: &str, lines: &[&str], edit: &Edit) -> String {
+    let ctx_start = edit.start.saturating_sub(CONTEXT);
+    let ctx_end = (edit.start + edit.remove + CONTEXT).min(lines.len());
+    let old_len = ctx_end - ctx_start;
+    let new_len = old_len - edit.remove + edit.insert.len();
+
+    let mut out = format!(
+        "--- a/{file}\n+++ b/{file}\n@@ -{},{} +{},{} @@\n",
+        ctx_start + 1,
+        old_len,
+        ctx_start + 1,
+        new_len
+    );
+    for line in &lines[ctx_start..edit.start] {
+        out.push_str(&format!(" {}\n", line));
+    }
+    for line in &lines[edit.start..edit.start + edit.remove] {
+        out.push_str(&format!("-{}\n", line));
+    }
+    for line in &edit.insert {
+        out.push_str(&format!("+{}\n", line));
+    }
+    for line in &lines[edit.start + edit.remove..ctx_end] {
+        out.push_str(&format!(" {}\n", line));
+    }
+    out
+}
+
+fn indent_of(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Строка с объявлением функции, ближайшая к подсказке из отчёта
+fn function_header(lines: &[&str], name: &str, hint: usize) -> Option<usize> {
+    let needle = format!("function {}(", name);
+    let mut candidates: Vec<usize> = (0..lines.len()).filter(|i| lines[*i].contains(&needle)).collect();
+    candidates.sort_by_key(|i| (*i as isize - hint as isize).abs());
+    candidates.first().copied()
+}
+
+/// Первая строка тела функции (где стоит `{`)
+fn body_open(lines: &[&str], header: usize) -> Option<usize> {
+    (header..lines.len()).find(|i| lines[*i].contains('{'))
+}
+
+fn hint_line(finding: &Finding) -> usize {
+    finding
+        .location
+        .as_ref()
+        .and_then(|l| l.lines.iter().min())
+        .map(|l| (*l as usize).saturating_sub(1))
+        .unwrap_or(0)
+}
+
+/// reentrancy-*: модификатор `nonReentrant` в сигнатуре функции
+fn fix_reentrancy(finding: &Finding, lines: &[&str]) -> Option<(Edit, FixSuggestion)> {
+    let function = finding.location.as_ref()?.function.as_deref()?;
+    let header = function_header(lines, function, hint_line(finding))?;
+    let open = body_open(lines, header)?;
+
+    if lines[header..=open].iter().any(|l| l.contains("nonReentrant")) {
+        return None;
+    }
+
+    // Модификаторы в Solidity должны идти до `returns`
+    let (idx, pos) = lines[header..=open]
+        .iter()
+        .enumerate()
+        .find_map(|(i, l)| l.find(" returns").map(|p| (header + i, p)))
+        .unwrap_or_else(|| (open, lines[open].find('{').unwrap()));
+
+    let line = lines[idx];
+    let (before, after) = line.split_at(pos);
+    let patched = if before.trim().is_empty() {
+        format!("{}nonReentrant {}", before, after.trim_start())
+    } else {
+        format!("{} nonReentrant {}", before.trim_end(), after.trim_start())
+    };
+
+    Some((
+        Edit { start: idx, remove: 1, insert: vec![patched] },
+        FixSuggestion {
+            summary: format!("Add nonReentrant guard to {}", function),
+            patch: String::new(),
+            notes: vec![
+                "Inherit ReentrancyGuard from @openzeppelin/contracts/security/ReentrancyGuard.sol".into(),
+                "Prefer checks-effects-interactions: update state before the external call".into(),
+            ],
+        },
+    ))
+}
+
+/// unchecked-transfer: замена на SafeERC20
+fn fix_unchecked_transfer(finding: &Finding, lines: &[&str]) -> Option<(Edit, FixSuggestion)> {
+    let location = finding.location.as_ref()?;
+    let start = hint_line(finding);
+    let end = location
+        .lines
+        .iter()
+        .max()
+        .map(|l| (*l as usize).min(lines.len()))
+        .unwrap_or(lines.len());
+
+    let idx = (start..end).find(|i| {
+        let l = lines[*i];
+        (l.contains(".transfer(") || l.contains(".transferFrom("))
+            && !l.contains("require(")
+            && !l.contains("safeTransfer")
+            && !l.contains('=')
+    })?;
+
+    let patched = lines[idx]
+        .replacen(".transferFrom(", ".safeTransferFrom(", 1)
+        .replacen(".transfer(", ".safeTransfer(", 1);
+
+    Some((
+        Edit { start: idx, remove: 1, insert: vec![patched] },
+        FixSuggestion {
+            summary: "Use SafeERC20 to revert on failed or non-standard transfers".into(),
+            patch: String::new(),
+            notes: vec![
+                "Import @openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol".into(),
+                "Add `using SafeERC20 for IERC20;` to the contract".into(),
+            ],
+        },
+    ))
+}
+
+/// missing-zero-check: `require(param != address(0))` первой строкой тела
+fn fix_missing_zero_check(finding: &Finding, lines: &[&str]) -> Option<(Edit, FixSuggestion)> {
+    let location = finding.location.as_ref()?;
+    let function = location.function.as_deref()?;
+
+    // Описание Slither начинается с `Contract.fn(address)._param (file#line)`
+    let param = finding
+        .description
+        .split_whitespace()
+        .next()?
+        .rsplit('.')
+        .next()
+        .filter(|p| !p.is_empty() && !p.contains('('))?;
+
+    let header = function_header(lines, function, hint_line(finding))?;
+    let open = body_open(lines, header)?;
+
+    if lines[open..].iter().take(5).any(|l| l.contains(&format!("{} != address(0)", param))) {
+        return None;
+    }
+
+    let contract = location.contract.as_deref().unwrap_or("Contract");
+    let check = format!(
+        "{}    require({} != address(0), \"{}: zero address\");",
+        indent_of(lines[header]),
+        param,
+        contract
+    );
+
+    Some((
+        Edit { start: open + 1, remove: 0, insert: vec![check] },
+        FixSuggestion {
+            summary: format!("Reject zero address for {} in {}", param, function),
+            patch: String::new(),
+            notes: Vec::new(),
+        },
+    ))
+}
+
+/// Строит патч для находки, если для её детектора есть шаблон
+pub fn suggest_fix(finding: &Finding, source: &str) -> Option<FixSuggestion> {
+    if finding.source != FindingSource::Slither {
+        return None;
+    }
+    let file = &finding.location.as_ref()?.file;
+    let lines: Vec<&str> = source.lines().collect();
+
+    let (edit, mut suggestion) = match finding.detector.as_str() {
+        "reentrancy-eth" | "reentrancy-no-eth" | "reentrancy-unlimited-gas" => {
+            fix_reentrancy(finding, &lines)?
+        }
+        "unchecked-transfer" => fix_unchecked_transfer(finding, &lines)?,
+        "missing-zero-check" => fix_missing_zero_check(finding, &lines)?,
+        _ => return None,
+    };
+
+    suggestion.patch = render_patch(file, &lines, &edit);
+    Some(suggestion)
+}
+
+/// Прикладывает предложения к находкам отчёта; возвращает число предложений
+pub fn attach_suggestions(report: &mut SecurityReport, sources: &dyn SourceProvider) -> usize {
+    let mut attached = 0;
+
+    for finding in report.findings.iter_mut() {
+        let Some(file) = finding.location.as_ref().map(|l| l.file.clone()) else {
+            continue;
+        };
+        let Some(source) = sources.source(&file) else {
+            continue;
+        };
+
+        finding.suggestion = suggest_fix(finding, &source);
+        if finding.suggestion.is_some() {
+            attached += 1;
+        }
+    }
+
+    attached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::report::{Severity, SourceLocation};
+
+    const VAULT: &str = "contract Vault {\n    address owner;\n    function setOwner(address _owner) external {\n        owner = _owner;\n    }\n    function withdraw(uint amount) external returns (bool) {\n        msg.sender.call{value: amount}(\"\");\n    }\n}";
+
+    fn finding(detector: &str, function: &str, line: u32, description: &str) -> Finding {
+        Finding {
+            source: FindingSource::Slither,
+            detector: detector.into(),
+            severity: Severity::High,
+            title: detector.into(),
+            description: description.into(),
+            location: Some(SourceLocation {
+                file: "Vault.sol".into(),
+                lines: vec![line],
+                contract: Some("Vault".into()),
+                function: Some(function.into()),
+                start_column: None,
+                end_column: None,
+                byte_offset: None,
+                byte_length: None,
+            }),
+            suggestion: None,
+        }
+    }
+
+    #[test]
+    fn test_reentrancy_modifier_goes_before_returns() {
+        let f = finding("reentrancy-eth", "withdraw", 6, "");
+        let fix = suggest_fix(&f, VAULT).unwrap();
+        assert!(fix
+            .patch
+            .contains("+    function withdraw(uint amount) external nonReentrant returns (bool) {"));
+    }
+
+    #[test]
+    fn test_zero_check_inserted_first_in_body() {
+        let f = finding(
+            "missing-zero-check",
+            "setOwner",
+            3,
+            "Vault.setOwner(address)._owner (Vault.sol#3) lacks a zero-check on :",
+        );
+        let fix = suggest_fix(&f, VAULT).unwrap();
+        assert!(fix.patch.contains("@@ -2,4 +2,5 @@"));
+        assert!(fix
+            .patch
+            .contains("+        require(_owner != address(0), \"Vault: zero address\");"));
+    }
+}
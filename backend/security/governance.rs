@@ -0,0 +1,311 @@
+use super::report::{Finding, FindingSource, SecurityReport, Severity};
+use super::storage_monitor::EIP1967_ADMIN_SLOT;
+use super::upgrade_watcher::EIP1967_IMPLEMENTATION_SLOT;
+use super::zk_audit::is_zk_contract;
+use ethers::abi::{decode, ParamType, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, H256, U256};
+use serde::Serialize;
+use std::sync::Arc;
+use thiserror::Error;
+
+const GET_OWNERS: [u8; 4] = [0xa0, 0xe6, 0x7e, 0x2b];
+const GET_THRESHOLD: [u8; 4] = [0xe7, 0x52, 0x35, 0xb8];
+const GET_MIN_DELAY: [u8; 4] = [0xf2, 0x7a, 0x0c, 0x92];
+const DELAY: [u8; 4] = [0x6a, 0x42, 0xb8, 0xf8];
+const ADMIN: [u8; 4] = [0xf8, 0x51, 0xa4, 0x40];
+const OWNER: [u8; 4] = [0x8d, 0xa5, 0xcb, 0x5b];
+
+/// Глубина цепочки администраторов, дальше которой не идём
+const MAX_CHAIN_DEPTH: usize = 6;
+
+#[derive(Debug, Error)]
+pub enum GovernanceError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+}
+
+/// Звено цепочки управления контрактом
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AdminNode {
+    Eoa { address: Address },
+    Safe { address: Address, owners: Vec<Address>, threshold: u64 },
+    Timelock { address: Address, delay_seconds: u64 },
+    /// Контракт неизвестного типа — дальше цепочку проследить нельзя
+    Contract { address: Address },
+}
+
+impl AdminNode {
+    pub fn address(&self) -> Address {
+        match self {
+            AdminNode::Eoa { address }
+            | AdminNode::Safe { address, .. }
+            | AdminNode::Timelock { address, .. }
+            | AdminNode::Contract { address } => *address,
+        }
+    }
+}
+
+/// Оценка риска управления
+#[derive(Debug, Clone, Serialize)]
+pub struct GovernanceAssessment {
+    pub contract: Address,
+    pub upgradeable: bool,
+    pub zk_verifier: bool,
+    /// От управляемого контракта к конечному владельцу
+    pub chain: Vec<AdminNode>,
+    /// 0.0 — надёжно, 1.0 — один ключ без задержки
+    pub risk_score: f64,
+    pub severity: Severity,
+    pub reasons: Vec<String>,
+}
+
+/// Аудитор цепочки администраторов: прокси-админ, Safe, Timelock, EOA
+pub struct GovernanceAuditor<M> {
+    provider: Arc<M>,
+}
+
+impl<M: Middleware> GovernanceAuditor<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider }
+    }
+
+    async fn call(&self, to: Address, selector: [u8; 4]) -> Option<Bytes> {
+        let tx: TypedTransaction = TransactionRequest::new().to(to).data(selector.to_vec()).into();
+        self.provider.call(&tx, None).await.ok().filter(|b| !b.is_empty())
+    }
+
+    async fn call_address(&self, to: Address, selector: [u8; 4]) -> Option<Address> {
+        let out = self.call(to, selector).await?;
+        match decode(&[ParamType::Address], &out).ok()?.pop()? {
+            Token::Address(a) if !a.is_zero() => Some(a),
+            _ => None,
+        }
+    }
+
+    async fn call_u256(&self, to: Address, selector: [u8; 4]) -> Option<U256> {
+        let out = self.call(to, selector).await?;
+        decode(&[ParamType::Uint(256)], &out).ok()?.pop()?.into_uint()
+    }
+
+    /// Порог или задержка сверх `u64` ничем не отличаются от `u64::MAX`: контракт отвечает что угодно
+    async fn call_u64(&self, to: Address, selector: [u8; 4]) -> Option<u64> {
+        Some(self.call_u256(to, selector).await?.min(U256::from(u64::MAX)).as_u64())
+    }
+
+    async fn read_slot_address(&self, contract: Address, slot: &str) -> Result<Option<Address>, GovernanceError> {
+        let slot: H256 = slot.parse().expect("valid slot constant");
+        let word = self
+            .provider
+            .get_storage_at(contract, slot, None)
+            .await
+            .map_err(|e| GovernanceError::ProviderError(e.to_string()))?;
+        let address = Address::from_slice(&word.as_bytes()[12..]);
+        Ok((!address.is_zero()).then_some(address))
+    }
+
+    /// Определяет тип звена по коду и интерфейсу
+    async fn classify(&self, address: Address) -> Result<AdminNode, GovernanceError> {
+        let code = self
+            .provider
+            .get_code(address, None)
+            .await
+            .map_err(|e| GovernanceError::ProviderError(e.to_string()))?;
+        if code.is_empty() {
+            return Ok(AdminNode::Eoa { address });
+        }
+
+        if let Some(threshold) = self.call_u64(address, GET_THRESHOLD).await {
+            let owners = match self.call(address, GET_OWNERS).await {
+                Some(out) => decode(&[ParamType::Array(Box::new(ParamType::Address))], &out)
+                    .ok()
+                    .and_then(|mut t| t.pop())
+                    .and_then(Token::into_array)
+                    .map(|a| a.into_iter().filter_map(Token::into_address).collect())
+                    .unwrap_or_default(),
+                None => Vec::new(),
+            };
+            return Ok(AdminNode::Safe { address, owners, threshold });
+        }
+
+        if let Some(delay) = self.call_u64(address, GET_MIN_DELAY).await {
+            return Ok(AdminNode::Timelock { address, delay_seconds: delay });
+        }
+        if let Some(delay) = self.call_u64(address, DELAY).await {
+            return Ok(AdminNode::Timelock { address, delay_seconds: delay });
+        }
+
+        Ok(AdminNode::Contract { address })
+    }
+
+    /// Следующее звено: админ Compound-таймлока или владелец Ownable/ProxyAdmin
+    async fn next_admin(&self, node: &AdminNode) -> Option<Address> {
+        match node {
+            AdminNode::Timelock { address, .. } => match self.call_address(*address, ADMIN).await {
+                Some(admin) => Some(admin),
+                None => self.call_address(*address, OWNER).await,
+            },
+            AdminNode::Contract { address } => self.call_address(*address, OWNER).await,
+            _ => None,
+        }
+    }
+
+    /// Строит цепочку управления контрактом
+    pub async fn admin_chain(&self, contract: Address) -> Result<Vec<AdminNode>, GovernanceError> {
+        let first = match self.read_slot_address(contract, EIP1967_ADMIN_SLOT).await? {
+            Some(admin) => Some(admin),
+            None => self.call_address(contract, OWNER).await,
+        };
+
+        let mut chain = Vec::new();
+        let mut next = first;
+        while let Some(address) = next {
+            if chain.len() >= MAX_CHAIN_DEPTH || chain.iter().any(|n: &AdminNode| n.address() == address) {
+                break;
+            }
+            let node = self.classify(address).await?;
+            next = self.next_admin(&node).await;
+            chain.push(node);
+        }
+
+        Ok(chain)
+    }
+
+    pub async fn assess(&self, contract: Address) -> Result<GovernanceAssessment, GovernanceError> {
+        let upgradeable = self.read_slot_address(contract, EIP1967_IMPLEMENTATION_SLOT).await?;
+        let code = match upgradeable {
+            Some(implementation) => self.provider.get_code(implementation, None).await,
+            None => self.provider.get_code(contract, None).await,
+        }
+        .map_err(|e| GovernanceError::ProviderError(e.to_string()))?;
+
+        let chain = self.admin_chain(contract).await?;
+        Ok(score_chain(contract, upgradeable.is_some(), is_zk_contract(&code), chain))
+    }
+}
+
+/// Оценка цепочки: риск определяет конечный владелец, таймлок его снижает,
+/// а возможность апгрейда (особенно zk-верификатора) повышает серьёзность
+pub fn score_chain(
+    contract: Address,
+    upgradeable: bool,
+    zk_verifier: bool,
+    chain: Vec<AdminNode>,
+) -> GovernanceAssessment {
+    let mut reasons = Vec::new();
+
+    let mut risk: f64 = match chain.last() {
+        None => {
+            reasons.push("no admin found (immutable or renounced)".to_string());
+            0.0
+        }
+        Some(AdminNode::Eoa { address }) => {
+            reasons.push(format!("controlled by single EOA {:?}", address));
+            1.0
+        }
+        Some(AdminNode::Safe { threshold, owners, .. }) => {
+            reasons.push(format!("Safe with {}-of-{} threshold", threshold, owners.len()));
+            match threshold {
+                0 | 1 => 0.9,
+                2 => 0.5,
+                _ => 0.3,
+            }
+        }
+        Some(AdminNode::Timelock { .. }) => 0.3,
+        Some(AdminNode::Contract { address }) => {
+            reasons.push(format!("unknown admin contract {:?}", address));
+            0.6
+        }
+    };
+
+    let max_delay = chain
+        .iter()
+        .filter_map(|n| match n {
+            AdminNode::Timelock { delay_seconds, .. } => Some(*delay_seconds),
+            _ => None,
+        })
+        .max();
+    match max_delay {
+        Some(d) if d >= 2 * 86_400 => {
+            risk *= 0.5;
+            reasons.push(format!("timelock delay {}h", d / 3600));
+        }
+        Some(d) if d >= 86_400 => risk *= 0.7,
+        Some(d) => reasons.push(format!("timelock delay too short ({}s)", d)),
+        None if !chain.is_empty() => reasons.push("no timelock in admin chain".to_string()),
+        None => {}
+    }
+
+    if zk_verifier && upgradeable {
+        reasons.push("admin can replace zk verifier implementation".to_string());
+    }
+
+    let severity = if risk >= 0.85 && upgradeable {
+        Severity::Critical
+    } else if risk >= 0.85 || (risk >= 0.5 && upgradeable) {
+        Severity::High
+    } else if risk >= 0.3 {
+        Severity::Medium
+    } else {
+        Severity::Low
+    };
+
+    GovernanceAssessment {
+        contract,
+        upgradeable,
+        zk_verifier,
+        chain,
+        risk_score: risk.clamp(0.0, 1.0),
+        severity,
+        reasons,
+    }
+}
+
+impl SecurityReport {
+    /// Добавляет оценку управления как находку
+    pub fn add_governance(&mut self, assessment: &GovernanceAssessment) {
+        if assessment.severity <= Severity::Low {
+            return;
+        }
+
+        self.findings.push(Finding {
+            source: FindingSource::Governance,
+            detector: "admin-chain".into(),
+            severity: assessment.severity,
+            title: format!("Governance risk {:.2}", assessment.risk_score),
+            description: assessment.reasons.join("; "),
+            location: None,
+            suggestion: None,
+        });
+        self.recalculate_score();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_eoa_over_upgradeable_verifier_is_critical() {
+        let chain = vec![AdminNode::Eoa { address: Address::repeat_byte(1) }];
+        let a = score_chain(Address::zero(), true, true, chain);
+        assert_eq!(a.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_timelocked_multisig_is_low_risk() {
+        let chain = vec![
+            AdminNode::Timelock { address: Address::repeat_byte(2), delay_seconds: 3 * 86_400 },
+            AdminNode::Safe {
+                address: Address::repeat_byte(3),
+                owners: vec![Address::repeat_byte(4); 5],
+                threshold: 3,
+            },
+        ];
+        let a = score_chain(Address::zero(), true, false, chain);
+        assert!(a.risk_score < 0.2);
+        assert_eq!(a.severity, Severity::Low);
+    }
+}
@@ -0,0 +1,322 @@
+use super::report::{Finding, FindingSource, SecurityReport, Severity};
+use ethers::abi::{Abi, Function, ParamType, StateMutability};
+use ethers::types::Address;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FuzzError {
+    #[error("Invalid ABI: {0}")]
+    AbiError(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Forge failed: {0}")]
+    ForgeFailed(String),
+
+    #[error("ABI has no state-changing functions with supported argument types")]
+    NoTargetFunctions,
+}
+
+/// Параметры ограниченной фаззинг-кампании
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    pub fork_url: String,
+    pub fork_block: Option<u64>,
+    pub runs: u32,
+    pub depth: u32,
+    /// Каталог Foundry-проекта; создаётся при необходимости
+    pub workdir: PathBuf,
+}
+
+/// Сгенерированный Foundry-тест
+#[derive(Debug, Clone, Serialize)]
+pub struct Harness {
+    pub source: String,
+    pub invariants: Vec<String>,
+    pub handled_functions: Vec<String>,
+    /// Функции, которые не попали в handler из-за неподдерживаемых типов аргументов
+    pub skipped_functions: Vec<String>,
+}
+
+/// Нарушенный инвариант с контрпримером от forge
+#[derive(Debug, Clone, Serialize)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    pub reason: String,
+    pub counterexample: Value,
+}
+
+const HARNESS_CONTRACT: &str = "TargetInvariants";
+
+/// Только типы, которые forge умеет генерировать без дополнительных ограничений
+fn solidity_type(param: &ParamType) -> Option<String> {
+    match param {
+        ParamType::Address => Some("address".into()),
+        ParamType::Bool => Some("bool".into()),
+        ParamType::Uint(n) => Some(format!("uint{}", n)),
+        ParamType::Int(n) => Some(format!("int{}", n)),
+        ParamType::FixedBytes(n) if *n <= 32 => Some(format!("bytes{}", n)),
+        _ => None,
+    }
+}
+
+fn has_function(abi: &Abi, name: &str, inputs: &[ParamType]) -> bool {
+    abi.functions_by_name(name)
+        .map(|fs| fs.iter().any(|f| f.inputs.iter().map(|i| &i.kind).eq(inputs.iter())))
+        .unwrap_or(false)
+}
+
+/// Обёртка handler для одной функции цели: адреса аргументов подменяются акторами
+fn handler_function(index: usize, func: &Function, types: &[String]) -> (String, String) {
+    let payable = func.state_mutability == StateMutability::Payable;
+    let mut params = vec!["uint256 actorSeed".to_string()];
+    let mut args = Vec::new();
+    let mut prelude = Vec::new();
+
+    for (i, ty) in types.iter().enumerate() {
+        let name = format!("a{}", i);
+        params.push(format!("{} {}", ty, name));
+        if ty == "address" {
+            prelude.push(format!("        {n} = actors[bound(uint256(uint160({n})), 0, actors.length - 1)];", n = name));
+        }
+        args.push(name);
+    }
+    if payable {
+        params.push("uint256 value".into());
+        prelude.push("        value = bound(value, 0, currentActor.balance);".into());
+        // Под prank value платит сам handler, поэтому списываем её с актора вручную
+        prelude.push("        vm.deal(currentActor, currentActor.balance - value);".into());
+        prelude.push("        vm.deal(address(this), address(this).balance + value);".into());
+    }
+
+    let call_value = if payable { "{value: value}" } else { "" };
+    let interface_sig = format!(
+        "    function {}({}) external{};",
+        func.name,
+        types.join(", "),
+        if payable { " payable" } else { "" }
+    );
+    let body = format!(
+        "    function call{}_{}({}) external useActor(actorSeed) {{\n{}\n        try target.{}{}({}) {{}} catch {{}}\n    }}",
+        index,
+        func.name,
+        params.join(", "),
+        prelude.join("\n"),
+        func.name,
+        call_value,
+        args.join(", "),
+    );
+    (interface_sig, body)
+}
+
+/// Генерирует Foundry invariant-тест по ABI цели
+pub fn generate_harness(abi_json: &str) -> Result<Harness, FuzzError> {
+    let abi: Abi = serde_json::from_str(abi_json)?;
+
+    let mut interface = Vec::new();
+    let mut handlers = Vec::new();
+    let mut handled_functions = Vec::new();
+    let mut skipped_functions = Vec::new();
+
+    for (index, func) in abi.functions().enumerate() {
+        if matches!(func.state_mutability, StateMutability::View | StateMutability::Pure) {
+            continue;
+        }
+        let types: Option<Vec<String>> = func.inputs.iter().map(|p| solidity_type(&p.kind)).collect();
+        match types {
+            Some(types) => {
+                let (sig, body) = handler_function(index, func, &types);
+                interface.push(sig);
+                handlers.push(body);
+                handled_functions.push(func.signature());
+            }
+            None => skipped_functions.push(func.signature()),
+        }
+    }
+
+    if handlers.is_empty() {
+        return Err(FuzzError::NoTargetFunctions);
+    }
+
+    let mut invariants = vec!["invariant_actorsCannotCreateEth".to_string()];
+    let mut setup = Vec::new();
+    let mut checks = vec![
+        "    /// Акторы не могут вывести больше ETH, чем у них было\n    function invariant_actorsCannotCreateEth() public {\n        assertLe(handler.actorsTotalBalance(), handler.initialActorsBalance(), \"actors extracted ETH\");\n    }".to_string(),
+    ];
+
+    if has_function(&abi, "totalSupply", &[]) && has_function(&abi, "balanceOf", &[ParamType::Address]) {
+        interface.push("    function totalSupply() external view returns (uint256);".into());
+        interface.push("    function balanceOf(address) external view returns (uint256);".into());
+        invariants.push("invariant_balancesWithinSupply".into());
+        checks.push("    function invariant_balancesWithinSupply() public {\n        uint256 sum;\n        for (uint256 i = 0; i < handler.actorCount(); i++) {\n            sum += target.balanceOf(handler.actors(i));\n        }\n        assertLe(sum, target.totalSupply(), \"actor balances exceed totalSupply\");\n    }".into());
+    }
+
+    if has_function(&abi, "owner", &[]) {
+        interface.push("    function owner() external view returns (address);".into());
+        setup.push("        initialOwner = target.owner();".to_string());
+        invariants.push("invariant_ownerUnchanged".into());
+        checks.push("    /// Акторы без прав не должны менять владельца\n    function invariant_ownerUnchanged() public {\n        assertEq(target.owner(), initialOwner, \"owner changed by unprivileged actor\");\n    }".into());
+    }
+
+    let source = format!(
+        r#"// SPDX-License-Identifier: UNLICENSED
+// Сгенерировано DeFinetly invariant_fuzz; правки будут перезаписаны
+pragma solidity ^0.8.13;
+
+import "forge-std/Test.sol";
+
+interface ITarget {{
+{interface}
+}}
+
+contract Handler is Test {{
+    ITarget public target;
+    address[] public actors;
+    address internal currentActor;
+    uint256 public initialActorsBalance;
+
+    constructor(ITarget _target) {{
+        target = _target;
+        for (uint256 i = 0; i < 3; i++) {{
+            address actor = makeAddr(string(abi.encodePacked("actor", vm.toString(i))));
+            vm.deal(actor, 100 ether);
+            actors.push(actor);
+        }}
+        initialActorsBalance = 300 ether;
+    }}
+
+    modifier useActor(uint256 seed) {{
+        currentActor = actors[bound(seed, 0, actors.length - 1)];
+        vm.startPrank(currentActor);
+        _;
+        vm.stopPrank();
+    }}
+
+    function actorCount() external view returns (uint256) {{
+        return actors.length;
+    }}
+
+    function actorsTotalBalance() external view returns (uint256 total) {{
+        for (uint256 i = 0; i < actors.length; i++) {{
+            total += actors[i].balance;
+        }}
+    }}
+
+{handlers}
+}}
+
+contract {contract} is Test {{
+    ITarget internal target;
+    Handler internal handler;
+    address internal initialOwner;
+
+    function setUp() public {{
+        vm.createSelectFork(vm.envString("FORK_URL"));
+        target = ITarget(vm.envAddress("TARGET"));
+        handler = new Handler(target);
+{setup}
+        targetContract(address(handler));
+    }}
+
+{checks}
+}}
+"#,
+        interface = interface.join("\n"),
+        handlers = handlers.join("\n\n"),
+        contract = HARNESS_CONTRACT,
+        setup = setup.join("\n"),
+        checks = checks.join("\n\n"),
+    );
+
+    Ok(Harness {
+        source,
+        invariants,
+        handled_functions,
+        skipped_functions,
+    })
+}
+
+/// Запускает ограниченную кампанию `forge test` на форке и собирает нарушения
+pub fn run_campaign(
+    target: Address,
+    harness: &Harness,
+    config: &FuzzConfig,
+) -> Result<Vec<InvariantViolation>, FuzzError> {
+    let test_dir = config.workdir.join("test");
+    fs::create_dir_all(&test_dir)?;
+    fs::write(
+        config.workdir.join("foundry.toml"),
+        "[profile.default]\nsrc = \"src\"\ntest = \"test\"\nlibs = [\"lib\"]\n",
+    )?;
+    fs::write(test_dir.join("Invariants.t.sol"), &harness.source)?;
+
+    if !config.workdir.join("lib/forge-std").exists() {
+        let output = Command::new("forge")
+            .args(["install", "--no-git", "foundry-rs/forge-std"])
+            .current_dir(&config.workdir)
+            .output()?;
+        if !output.status.success() {
+            return Err(FuzzError::ForgeFailed(String::from_utf8_lossy(&output.stderr).into_owned()));
+        }
+    }
+
+    let mut cmd = Command::new("forge");
+    cmd.args(["test", "--json", "--match-contract", HARNESS_CONTRACT])
+        .current_dir(&config.workdir)
+        .env("FORK_URL", &config.fork_url)
+        .env("TARGET", format!("{:?}", target))
+        .env("FOUNDRY_INVARIANT_RUNS", config.runs.to_string())
+        .env("FOUNDRY_INVARIANT_DEPTH", config.depth.to_string())
+        .env("FOUNDRY_INVARIANT_FAIL_ON_REVERT", "false");
+    if let Some(block) = config.fork_block {
+        cmd.env("FOUNDRY_FORK_BLOCK_NUMBER", block.to_string());
+    }
+
+    // forge завершается с ошибкой при проваленных тестах, поэтому смотрим на JSON
+    let output = cmd.output()?;
+    let results: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+        FuzzError::ForgeFailed(String::from_utf8_lossy(&output.stderr).into_owned())
+    })?;
+
+    let mut violations = Vec::new();
+    for suite in results.as_object().into_iter().flat_map(|o| o.values()) {
+        let Some(tests) = suite["test_results"].as_object() else {
+            continue;
+        };
+        for (name, result) in tests {
+            if result["status"] == "Failure" {
+                violations.push(InvariantViolation {
+                    invariant: name.trim_end_matches("()").to_string(),
+                    reason: result["reason"].as_str().unwrap_or("assertion failed").to_string(),
+                    counterexample: result["counterexample"].clone(),
+                });
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+impl SecurityReport {
+    /// Добавляет нарушения инвариантов как находки высокой серьёзности
+    pub fn add_invariant_violations(&mut self, violations: &[InvariantViolation]) {
+        for v in violations {
+            self.findings.push(Finding {
+                source: FindingSource::Fuzzing,
+                detector: v.invariant.clone(),
+                severity: Severity::High,
+                title: format!("Invariant violated: {}", v.invariant),
+                description: format!("{}; counterexample: {}", v.reason, v.counterexample),
+                location: None,
+                suggestion: None,
+            });
+        }
+        self.recalculate_score();
+    }
+}
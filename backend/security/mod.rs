@@ -0,0 +1,17 @@
+pub mod anomaly;
+pub mod delegation_watcher;
+pub mod dependency_graph;
+pub mod fixes;
+pub mod governance;
+pub mod invariant_fuzz;
+pub mod monitor;
+pub mod reentrancy_trace;
+pub mod report;
+pub mod sarif;
+pub mod signatures;
+pub mod slither;
+pub mod state_db;
+pub mod storage_monitor;
+pub mod upgrade_sim;
+pub mod upgrade_watcher;
+pub mod zk_audit;
@@ -0,0 +1,653 @@
+use super::delegation_watcher::{delegation_alert, DelegationWatcher};
+use super::report::Severity;
+use super::storage_monitor::{SlotChange, StorageMonitor, StorageMonitorError};
+use super::upgrade_sim::{simulation_alert, AnvilUpgradeSimulator, Installation, SimulationError, UpgradeSimulator};
+use super::upgrade_watcher::{AuditError, BytecodeAuditor, UpgradeEvent, UpgradeWatcher};
+use async_trait::async_trait;
+use ethers::abi::{decode, encode, ParamType, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, BlockNumber, Bytes, Log, TransactionRequest, H256, U256};
+use ethers::utils::{id, keccak256};
+use mevdetector::bus::{AlertBus, AlertLevel, BusAlert};
+use mevdetector::config::MonitorConfig;
+use mevdetector::indexer::{IndexHandler, IndexedEvent, LogIndexer, Step};
+use mevdetector::shutdown::ShutdownSignal;
+use mevdetector::store::{SharedStore, StoreError, StoreExt};
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::{Serialize, Deserialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::watch;
+
+/// Пространство имён подписок в хранилище
+const SUBSCRIPTIONS_NS: &str = "monitor_subscriptions";
+const BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+/// Событие `TimelockController`, которым governance планирует вызов
+const CALL_SCHEDULED: &str = "CallScheduled(bytes32,uint256,address,uint256,bytes,bytes32,uint256)";
+
+#[derive(Debug, Error)]
+pub enum MonitorError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Storage monitor error: {0}")]
+    StorageError(#[from] StorageMonitorError),
+
+    #[error("Audit error: {0}")]
+    AuditError(#[from] AuditError),
+
+    #[error("Store error: {0}")]
+    StoreError(#[from] StoreError),
+
+    #[error("Upgrade simulation error: {0}")]
+    SimulationError(#[from] SimulationError),
+
+    #[error("Contract {0:?} is not a monitored proxy")]
+    NotMonitored(Address),
+}
+
+/// Какие проверки выполнять для контракта и как часто
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorProfile {
+    /// Владелец, админ, реализация, пауза
+    pub storage_slots: bool,
+    /// Смена реализации EIP-1967 с переаудитом
+    pub upgrades: bool,
+    /// Токены, отток которых отслеживается; `Address::zero()` — нативный ETH
+    pub outflow_assets: Vec<Address>,
+    /// Доля баланса, убыль которой за интервал считается аномальной
+    pub max_outflow_fraction: f64,
+    /// Порог серьёзности новых находок после апгрейда
+    pub findings_threshold: Severity,
+    /// Период проверок в блоках
+    pub interval_blocks: u64,
+}
+
+impl MonitorProfile {
+    /// Каждый блок, все проверки, алерт на новые High и выше
+    pub fn strict() -> Self {
+        Self {
+            storage_slots: true,
+            upgrades: true,
+            outflow_assets: vec![Address::zero()],
+            max_outflow_fraction: 0.1,
+            findings_threshold: Severity::High,
+            interval_blocks: 1,
+        }
+    }
+
+    /// Раз в ~минуту, без контроля оттоков
+    pub fn standard() -> Self {
+        Self {
+            outflow_assets: Vec::new(),
+            max_outflow_fraction: 0.3,
+            interval_blocks: 5,
+            ..Self::strict()
+        }
+    }
+}
+
+/// Подписка контракта; сохраняется в хранилище целиком
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub address: Address,
+    pub profile: MonitorProfile,
+    pub next_block: u64,
+    pub last_checked_block: Option<u64>,
+    /// Последний известный баланс по каждому активу
+    pub balances: HashMap<Address, U256>,
+    /// Прокси EIP-1967 — апгрейды отслеживаются только для них
+    pub is_proxy: bool,
+    /// Последние слова критических слотов: после рестарта сравнение идёт с ними
+    #[serde(default)]
+    pub slots: HashMap<H256, H256>,
+    /// Последняя известная реализация прокси
+    #[serde(default)]
+    pub implementation: Option<Address>,
+}
+
+pub(crate) fn alert_level(severity: Severity) -> AlertLevel {
+    match severity {
+        Severity::Informational => AlertLevel::Info,
+        Severity::Low => AlertLevel::Low,
+        Severity::Medium => AlertLevel::Medium,
+        Severity::High => AlertLevel::High,
+        Severity::Critical => AlertLevel::Critical,
+    }
+}
+
+fn slot_alert(change: &SlotChange) -> BusAlert {
+    BusAlert::new(
+        "monitor",
+        "storage_slot",
+        alert_level(change.severity),
+        format!("{:?}", change.contract),
+        change.summary(),
+    )
+    .with_payload(serde_json::to_value(change).unwrap_or_default())
+}
+
+fn upgrade_alerts(event: &UpgradeEvent, threshold: Severity) -> Vec<BusAlert> {
+    let subject = format!("{:?}", event.proxy);
+    let mut alerts = vec![BusAlert::new(
+        "monitor",
+        "upgrade",
+        if event.requires_alert { AlertLevel::Critical } else { AlertLevel::Medium },
+        subject.clone(),
+        format!(
+            "Implementation {:?} -> {:?} at block {}",
+            event.old_implementation, event.new_implementation, event.block
+        ),
+    )
+    .with_payload(serde_json::to_value(event).unwrap_or_default())];
+
+    for finding in event.diff.introduced.iter().filter(|f| f.severity >= threshold) {
+        alerts.push(
+            BusAlert::new(
+                "monitor",
+                "new_finding",
+                alert_level(finding.severity),
+                subject.clone(),
+                finding.title.clone(),
+            )
+            .with_payload(serde_json::to_value(finding).unwrap_or_default()),
+        );
+    }
+
+    alerts
+}
+
+/// Баланс актива на блоке; `Address::zero()` — нативный ETH
+pub(crate) async fn asset_balance<M: Middleware>(
+    provider: &M,
+    asset: Address,
+    holder: Address,
+    block: u64,
+) -> Result<U256, MonitorError> {
+    let at = Some(BlockId::Number(BlockNumber::Number(block.into())));
+    let provider_err = |e: M::Error| MonitorError::ProviderError(e.to_string());
+
+    if asset.is_zero() {
+        return provider.get_balance(holder, at).await.map_err(provider_err);
+    }
+
+    let mut data = BALANCE_OF.to_vec();
+    data.extend(encode(&[Token::Address(holder)]));
+    let tx: TypedTransaction = TransactionRequest::new().to(asset).data(data).into();
+    let out = provider.call(&tx, at).await.map_err(provider_err)?;
+    Ok(decode(&[ParamType::Uint(256)], &out)
+        .ok()
+        .and_then(|mut t| t.pop())
+        .and_then(Token::into_uint)
+        .unwrap_or_default())
+}
+
+/// Вызов, запланированный в таймлоке
+#[derive(Debug, Clone)]
+pub struct ScheduledCall {
+    pub target: Address,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+fn scheduled_call(log: Log) -> Option<ScheduledCall> {
+    let params = [ParamType::Address, ParamType::Uint(256), ParamType::Bytes, ParamType::FixedBytes(32), ParamType::Uint(256)];
+    match decode(&params, &log.data).ok()?.as_slice() {
+        [Token::Address(target), Token::Uint(value), Token::Bytes(data), ..] => {
+            Some(ScheduledCall { target: *target, value: *value, data: data.clone().into() })
+        }
+        _ => None,
+    }
+}
+
+/// Прокси и новая реализация из вызова апгрейда: `upgradeTo`/`upgradeToAndCall` самого прокси
+/// или `upgrade`/`upgradeAndCall` у ProxyAdmin
+pub fn proposed_upgrade(target: Address, data: &[u8]) -> Option<(Address, Address)> {
+    let (selector, args) = (data.get(..4)?, data.get(4..)?);
+    let address = |token: &Token| token.clone().into_address();
+    if selector == id("upgradeTo(address)") || selector == id("upgradeToAndCall(address,bytes)") {
+        let tokens = decode(&[ParamType::Address], args.get(..32)?).ok()?;
+        return Some((target, address(&tokens[0])?));
+    }
+    if selector == id("upgrade(address,address)") || selector == id("upgradeAndCall(address,address,bytes)") {
+        let tokens = decode(&[ParamType::Address, ParamType::Address], args.get(..64)?).ok()?;
+        return Some((address(&tokens[0])?, address(&tokens[1])?));
+    }
+    None
+}
+
+/// Запланированные вызовы между шагом индекса и их разбором в `tick`
+#[derive(Default)]
+struct ScheduledCalls(Mutex<Vec<IndexedEvent<ScheduledCall>>>);
+
+#[async_trait]
+impl IndexHandler<ScheduledCall> for ScheduledCalls {
+    async fn apply(&self, events: Vec<IndexedEvent<ScheduledCall>>) -> Result<(), String> {
+        self.0.lock().unwrap().extend(events);
+        Ok(())
+    }
+
+    async fn revert(&self, from_block: u64) -> Result<(), String> {
+        self.0.lock().unwrap().retain(|e| e.block < from_block);
+        Ok(())
+    }
+}
+
+/// Непрерывный мониторинг подписанных контрактов с расписанием на каждый контракт
+pub struct ContractMonitor<M> {
+    provider: Arc<M>,
+    store: SharedStore,
+    bus: AlertBus,
+    storage: StorageMonitor<M>,
+    upgrades: UpgradeWatcher<M>,
+    /// Прогон сценариев на прежней и новой реализации при апгрейде
+    simulator: Option<Arc<dyn UpgradeSimulator>>,
+    /// `CallScheduled` таймлоков: апгрейды наблюдаемых прокси симулируются до исполнения
+    proposals: Option<LogIndexer<M, ScheduledCall>>,
+    scheduled: ScheduledCalls,
+    /// Авторизации EIP-7702 наблюдаемых EOA
+    delegations: Option<DelegationWatcher<M>>,
+    subscriptions: HashMap<Address, Subscription>,
+    /// Ошибки тиков и симуляций, не остановившие проверки
+    errors: TaskErrors,
+}
+
+impl<M: Middleware + 'static> ContractMonitor<M> {
+    pub fn new(provider: Arc<M>, upgrades: UpgradeWatcher<M>, store: SharedStore, bus: AlertBus) -> Self {
+        Self {
+            storage: StorageMonitor::new(provider.clone()),
+            provider,
+            store,
+            bus,
+            upgrades,
+            simulator: None,
+            proposals: None,
+            scheduled: ScheduledCalls::default(),
+            delegations: None,
+            subscriptions: HashMap::new(),
+            errors: TaskErrors::default(),
+        }
+    }
+
+    /// Монитор по `[monitor]`: подписки из хранилища, `watched_contracts` со стандартным профилем,
+    /// симуляция апгрейдов из `[monitor.simulation]` и делегирования из `[monitor.delegation]`
+    pub async fn from_config(config: &MonitorConfig, provider: Arc<M>, store: SharedStore, bus: AlertBus) -> Result<Self, MonitorError> {
+        let upgrades = UpgradeWatcher::new(provider.clone(), Arc::new(BytecodeAuditor), Severity::High);
+        let mut monitor = Self::new(provider, upgrades, store, bus);
+        if let Some(simulation) = &config.simulation {
+            monitor = monitor.with_simulator(Arc::new(AnvilUpgradeSimulator::from_config(simulation)?));
+        }
+        if let Some(delegation) = &config.delegation {
+            let mut watcher = DelegationWatcher::new(monitor.provider.clone(), Arc::new(BytecodeAuditor), Severity::High);
+            for trusted in delegation.trusted.iter().filter_map(|a| a.parse::<Address>().ok()) {
+                watcher = watcher.with_trusted(trusted);
+            }
+            for account in delegation.accounts.iter().filter_map(|a| a.parse::<Address>().ok()) {
+                watcher.watch(account);
+            }
+            monitor = monitor.with_delegations(watcher);
+        }
+        monitor.restore().await?;
+        let profile = MonitorProfile { interval_blocks: config.interval_blocks, ..MonitorProfile::standard() };
+        for address in config.watched_contracts.iter().filter_map(|a| a.parse::<Address>().ok()) {
+            if !monitor.subscriptions.contains_key(&address) {
+                monitor.monitor_contract(address, profile.clone()).await?;
+            }
+        }
+        Ok(monitor)
+    }
+
+    /// Симуляция апгрейдов: обнаруженных в `tick` и запланированных в таймлоках с момента подключения
+    pub fn with_simulator(mut self, simulator: Arc<dyn UpgradeSimulator>) -> Self {
+        self.simulator = Some(simulator);
+        let topic0 = H256::from(keccak256(CALL_SCHEDULED));
+        self.proposals = Some(
+            LogIndexer::new("monitor.proposals", self.provider.clone(), self.store.clone(), 0)
+                .from_head()
+                .with_decoder(topic0, scheduled_call),
+        );
+        self
+    }
+
+    pub fn with_delegations(mut self, watcher: DelegationWatcher<M>) -> Self {
+        self.delegations = Some(watcher);
+        self
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Рискованные делегирования и отзывы в блоке; ошибка не мешает остальным проверкам тика
+    async fn scan_delegations(&mut self, block: u64) -> Vec<BusAlert> {
+        let Some(watcher) = &mut self.delegations else {
+            return Vec::new();
+        };
+        match watcher.poll(block).await {
+            Ok(events) => events
+                .iter()
+                .filter(|e| e.requires_alert || e.delegation.is_revocation())
+                .map(delegation_alert)
+                .collect(),
+            Err(e) => {
+                self.errors.record(format!("delegations at block {}: {}", block, e));
+                Vec::new()
+            }
+        }
+    }
+
+    /// Проверка апгрейда, предложенного через governance, до его исполнения;
+    /// `installation` — вызов, который исполнит предложение
+    pub async fn simulate_proposal(
+        &self,
+        proxy: Address,
+        new_implementation: Address,
+        installation: &Installation,
+        block: Option<u64>,
+    ) -> Result<Option<BusAlert>, MonitorError> {
+        let alert = self.simulate_installation(proxy, new_implementation, installation, block).await?;
+        if let Some(alert) = &alert {
+            self.bus.publish(alert.clone());
+        }
+        Ok(alert)
+    }
+
+    async fn simulate_installation(
+        &self,
+        proxy: Address,
+        new_implementation: Address,
+        installation: &Installation,
+        block: Option<u64>,
+    ) -> Result<Option<BusAlert>, MonitorError> {
+        let current = self
+            .upgrades
+            .report(proxy)
+            .and_then(|r| r.implementation)
+            .ok_or(MonitorError::NotMonitored(proxy))?;
+        let Some(simulator) = &self.simulator else {
+            return Ok(None);
+        };
+        let simulation = simulator.simulate(proxy, current, new_implementation, installation, block).await?;
+        Ok(simulation_alert(&simulation))
+    }
+
+    /// Новые `CallScheduled` до головы; апгрейды наблюдаемых прокси прогоняются на форке
+    /// на блоке `block` вызовом от имени таймлока
+    async fn scan_proposals(&self, block: u64) -> Vec<BusAlert> {
+        let Some(proposals) = &self.proposals else {
+            return Vec::new();
+        };
+        loop {
+            match proposals.step(&self.scheduled).await {
+                Ok(Step::CaughtUp) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    self.errors.record(format!("governance proposals: {}", e));
+                    break;
+                }
+            }
+        }
+
+        let scheduled = std::mem::take(&mut *self.scheduled.0.lock().unwrap());
+        let mut alerts = Vec::new();
+        for call in scheduled {
+            let Some((proxy, implementation)) = proposed_upgrade(call.event.target, &call.event.data) else {
+                continue;
+            };
+            if !self.subscriptions.get(&proxy).is_some_and(|s| s.is_proxy) {
+                continue;
+            }
+            let installation = Installation::Call {
+                from: call.address,
+                to: call.event.target,
+                data: call.event.data,
+                value: call.event.value,
+            };
+            match self.simulate_installation(proxy, implementation, &installation, Some(block)).await {
+                Ok(alert) => alerts.extend(alert),
+                Err(MonitorError::SimulationError(SimulationError::NoScenario(_))) => {}
+                Err(e) => self.errors.record(format!("proposal {:?} for {:?}: {}", call.tx_hash, proxy, e)),
+            }
+        }
+        alerts
+    }
+
+    /// Проверки на каждый новый блок из `heads` до сигнала остановки
+    pub async fn run(&mut self, mut heads: watch::Receiver<u64>, mut shutdown: ShutdownSignal) {
+        loop {
+            tokio::select! {
+                changed = heads.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    let block = *heads.borrow_and_update();
+                    self.tick(block).await;
+                }
+                _ = shutdown.wait() => return,
+            }
+        }
+    }
+
+    pub fn subscriptions(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions.values()
+    }
+
+    /// Регистрирует контракт для постоянных проверок и сохраняет подписку.
+    /// Повторный вызов заменяет профиль.
+    pub async fn monitor_contract(&mut self, address: Address, profile: MonitorProfile) -> Result<&Subscription, MonitorError> {
+        let block = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| MonitorError::ProviderError(e.to_string()))?
+            .as_u64();
+
+        let mut subscription = Subscription {
+            address,
+            next_block: block + profile.interval_blocks.max(1),
+            last_checked_block: None,
+            balances: HashMap::new(),
+            is_proxy: false,
+            slots: HashMap::new(),
+            implementation: None,
+            profile,
+        };
+        for asset in subscription.profile.outflow_assets.clone() {
+            let balance = asset_balance(self.provider.as_ref(), asset, address, block).await?;
+            subscription.balances.insert(asset, balance);
+        }
+
+        self.activate(&mut subscription, false).await?;
+        self.persist(&subscription)?;
+        self.subscriptions.insert(address, subscription);
+        Ok(&self.subscriptions[&address])
+    }
+
+    pub fn unmonitor_contract(&mut self, address: Address) -> Result<(), MonitorError> {
+        self.subscriptions.remove(&address);
+        self.storage.unwatch(address);
+        self.upgrades.unwatch(address);
+        self.store.delete(SUBSCRIPTIONS_NS, &format!("{:?}", address))?;
+        Ok(())
+    }
+
+    /// Восстанавливает подписки из хранилища после рестарта. Слоты и реализация сравниваются
+    /// с сохранёнными, поэтому изменения за время простоя придут алертами первого тика
+    pub async fn restore(&mut self) -> Result<usize, MonitorError> {
+        let saved: Vec<(String, Subscription)> = self.store.list_json(SUBSCRIPTIONS_NS)?;
+        let count = saved.len();
+        for (_, mut subscription) in saved {
+            self.activate(&mut subscription, true).await?;
+            self.subscriptions.insert(subscription.address, subscription);
+        }
+        Ok(count)
+    }
+
+    /// Запускает наблюдатели для подписки; прокси определяется по слоту реализации.
+    /// `restoring` — база из сохранённой подписки, а не текущее состояние цепи
+    async fn activate(&mut self, subscription: &mut Subscription, restoring: bool) -> Result<(), MonitorError> {
+        let address = subscription.address;
+        if subscription.profile.storage_slots {
+            if restoring {
+                self.storage.restore(address, None, &subscription.slots).await?;
+            } else {
+                self.storage.watch(address, None).await?;
+            }
+        }
+        if subscription.profile.upgrades {
+            let watched = match subscription.implementation.filter(|_| restoring) {
+                Some(implementation) => self.upgrades.restore(address, implementation, subscription.last_checked_block).await.map(|_| ()),
+                None => self.upgrades.watch(address).await.map(|_| ()),
+            };
+            subscription.is_proxy = match watched {
+                Ok(()) => true,
+                Err(AuditError::NotAProxy(_)) => false,
+                Err(e) => return Err(e.into()),
+            };
+        }
+        self.remember_baseline(subscription);
+        Ok(())
+    }
+
+    fn remember_baseline(&self, subscription: &mut Subscription) {
+        subscription.slots = self.storage.baseline(subscription.address);
+        subscription.implementation = self.upgrades.implementation(subscription.address);
+    }
+
+    fn persist(&self, subscription: &Subscription) -> Result<(), MonitorError> {
+        self.store
+            .put_json(SUBSCRIPTIONS_NS, &format!("{:?}", subscription.address), subscription)?;
+        Ok(())
+    }
+
+    /// Проверки оттока: убыль баланса за интервал выше порога профиля
+    async fn check_outflows(&self, subscription: &mut Subscription, block: u64) -> Result<Vec<BusAlert>, MonitorError> {
+        let mut alerts = Vec::new();
+        for asset in subscription.profile.outflow_assets.clone() {
+            let current = asset_balance(self.provider.as_ref(), asset, subscription.address, block).await?;
+            let previous = subscription.balances.insert(asset, current).unwrap_or_default();
+            if previous.is_zero() || current >= previous {
+                continue;
+            }
+
+            let fraction = ((previous - current) * U256::from(10_000u64) / previous).as_u64() as f64 / 10_000.0;
+            if fraction < subscription.profile.max_outflow_fraction {
+                continue;
+            }
+
+            let level = if fraction >= 0.5 { AlertLevel::Critical } else { AlertLevel::High };
+            alerts.push(
+                BusAlert::new(
+                    "monitor",
+                    "outflow",
+                    level,
+                    format!("{:?}", subscription.address),
+                    format!("{:.1}% of {:?} balance left the contract", fraction * 100.0, asset),
+                )
+                .with_payload(json!({
+                    "asset": asset,
+                    "previous": previous.to_string(),
+                    "current": current.to_string(),
+                    "block": block,
+                })),
+            );
+        }
+        Ok(alerts)
+    }
+
+    /// Ошибка симуляции не должна останавливать остальные проверки тика
+    async fn simulate_upgrade(&self, event: &UpgradeEvent) -> Option<BusAlert> {
+        let simulator = self.simulator.as_ref()?;
+        // Форк на блок до апгрейда: там ещё прежнее состояние, и транзакция апгрейда повторяется
+        // вместе с инициализатором
+        let block = event.block.checked_sub(1);
+        let installation = event.transaction.map_or(Installation::Slot, Installation::Transaction);
+        match simulator
+            .simulate(event.proxy, event.old_implementation, event.new_implementation, &installation, block)
+            .await
+        {
+            Ok(simulation) => simulation_alert(&simulation),
+            Err(SimulationError::NoScenario(_)) => None,
+            Err(e) => {
+                self.errors.record(format!("upgrade simulation for {:?}: {}", event.proxy, e));
+                None
+            }
+        }
+    }
+
+    /// Проверки одной подписки; найденное до ошибки остаётся в `alerts`
+    async fn check(&mut self, subscription: &mut Subscription, block: u64, alerts: &mut Vec<BusAlert>) -> Result<(), MonitorError> {
+        let address = subscription.address;
+        if subscription.profile.storage_slots {
+            for change in self.storage.poll_contract(address, block).await? {
+                alerts.push(slot_alert(&change));
+            }
+        }
+        if subscription.profile.upgrades && subscription.is_proxy {
+            for event in self.upgrades.poll_proxy(address, block).await? {
+                alerts.extend(upgrade_alerts(&event, subscription.profile.findings_threshold));
+                alerts.extend(self.simulate_upgrade(&event).await);
+            }
+        }
+        alerts.extend(self.check_outflows(subscription, block).await?);
+        Ok(())
+    }
+
+    /// Выполняет проверки подписок, чьё время пришло, и публикует алерты в шину.
+    /// Вызывается на каждый новый блок. Сбой проверки контракта попадает в `errors` и не теряет
+    /// уже найденные алерты; такой контракт проверяется снова на следующем блоке
+    pub async fn tick(&mut self, block: u64) -> Vec<BusAlert> {
+        let due: Vec<Address> = self
+            .subscriptions
+            .values()
+            .filter(|s| block >= s.next_block)
+            .map(|s| s.address)
+            .collect();
+
+        let mut alerts = Vec::new();
+        for address in due {
+            let mut subscription = self.subscriptions[&address].clone();
+            if let Err(e) = self.check(&mut subscription, block, &mut alerts).await {
+                self.errors.record(format!("monitor check of {:?} at block {}: {}", address, block, e));
+                continue;
+            }
+
+            subscription.last_checked_block = Some(block);
+            subscription.next_block = block + subscription.profile.interval_blocks.max(1);
+            self.remember_baseline(&mut subscription);
+            if let Err(e) = self.persist(&subscription) {
+                self.errors.record(format!("monitor subscription {:?}: {}", address, e));
+            }
+            self.subscriptions.insert(address, subscription);
+        }
+        alerts.extend(self.scan_proposals(block).await);
+        alerts.extend(self.scan_delegations(block).await);
+
+        for alert in &alerts {
+            self.bus.publish(alert.clone());
+        }
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposed_upgrade_reads_proxy_and_proxy_admin_calls() {
+        let (proxy, admin, implementation) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+        let call = |signature: &str, args: &[Token]| [id(signature).to_vec(), encode(args)].concat();
+
+        let direct = call("upgradeToAndCall(address,bytes)", &[Token::Address(implementation), Token::Bytes(vec![0x81, 0x29])]);
+        assert_eq!(proposed_upgrade(proxy, &direct), Some((proxy, implementation)));
+
+        let via_admin = call("upgrade(address,address)", &[Token::Address(proxy), Token::Address(implementation)]);
+        assert_eq!(proposed_upgrade(admin, &via_admin), Some((proxy, implementation)));
+
+        assert_eq!(proposed_upgrade(proxy, &call("pause()", &[])), None);
+    }
+}
@@ -0,0 +1,362 @@
+use super::report::{Finding, FindingSource, SecurityReport, Severity};
+use super::state_db::StateDb;
+use ethers::providers::Middleware;
+use ethers::types::{Address, Block, BlockId, BlockNumber, Transaction, TraceFilter, H256};
+use mevdetector::state::{BlockingState, StateProvider};
+use revm::db::{CacheDB, EthersDB};
+use revm::interpreter::{opcode, CallInputs, CallScheme, Gas, InstructionResult, Interpreter};
+use revm::primitives::{Bytes, TransactTo, TxEnv, B160, U256};
+use revm::{Database, EVMData, Inspector, EVM};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TraceError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("EVM error: {0}")]
+    EvmError(String),
+
+    #[error("Block not found: {0}")]
+    BlockNotFound(u64),
+}
+
+/// Повторный вход в целевой контракт во время одной транзакции
+#[derive(Debug, Clone, Serialize)]
+pub struct ReentrancyEvent {
+    pub tx_hash: Option<H256>,
+    /// Селектор внешнего вызова, во время которого произошёл повторный вход
+    pub outer_selector: String,
+    pub inner_selector: String,
+    pub depth: usize,
+    /// Повторный вход через delegatecall (код в библиотеке или прокси)
+    pub via_delegatecall: bool,
+    /// SSTORE в хранилище цели внутри повторного входа
+    pub writes_in_reentry: u32,
+    /// SSTORE внешним фреймом после возврата из повторного входа (нарушение CEI)
+    pub writes_after_reentry: u32,
+}
+
+impl ReentrancyEvent {
+    pub fn has_intervening_writes(&self) -> bool {
+        self.writes_in_reentry > 0 || self.writes_after_reentry > 0
+    }
+}
+
+struct Frame {
+    address: B160,
+    selector: String,
+    reentry_of: Option<usize>,
+}
+
+struct EventState {
+    event: ReentrancyEvent,
+    outer_index: usize,
+    inner_active: bool,
+    outer_alive: bool,
+}
+
+/// Inspector, отслеживающий повторные входы в цель по адресу хранилища,
+/// а не по адресу кода — поэтому видит вход через delegatecall-прокси
+pub struct ReentrancyInspector {
+    target: B160,
+    stack: Vec<Frame>,
+    events: Vec<EventState>,
+}
+
+fn selector_hex(input: &Bytes) -> String {
+    input
+        .get(..4)
+        .map(|s| format!("0x{}", ethers::utils::hex::encode(s)))
+        .unwrap_or_else(|| "0x".into())
+}
+
+impl ReentrancyInspector {
+    pub fn new(target: Address) -> Self {
+        Self {
+            target: B160::from(target.0),
+            stack: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn into_events(self) -> Vec<ReentrancyEvent> {
+        self.events.into_iter().map(|s| s.event).collect()
+    }
+}
+
+impl<DB: Database> Inspector<DB> for ReentrancyInspector {
+    fn step(&mut self, interp: &mut Interpreter, _data: &mut EVMData<'_, DB>) -> InstructionResult {
+        if interp.current_opcode() != opcode::SSTORE || interp.contract.address != self.target {
+            return InstructionResult::Continue;
+        }
+
+        let current = self.stack.len().saturating_sub(1);
+        for state in self.events.iter_mut() {
+            if state.inner_active {
+                state.event.writes_in_reentry += 1;
+            } else if state.outer_alive && state.outer_index == current {
+                state.event.writes_after_reentry += 1;
+            }
+        }
+
+        InstructionResult::Continue
+    }
+
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        let address = inputs.context.address;
+        let mut reentry_of = None;
+
+        if address == self.target {
+            if let Some(outer_index) = self.stack.iter().rposition(|f| f.address == self.target) {
+                let outer_selector = self.stack[outer_index].selector.clone();
+
+                reentry_of = Some(self.events.len());
+                self.events.push(EventState {
+                    event: ReentrancyEvent {
+                        tx_hash: None,
+                        outer_selector,
+                        inner_selector: selector_hex(&inputs.input),
+                        depth: self.stack.len(),
+                        via_delegatecall: inputs.context.scheme == CallScheme::DelegateCall
+                            || inputs.context.code_address != address,
+                        writes_in_reentry: 0,
+                        writes_after_reentry: 0,
+                    },
+                    outer_index,
+                    inner_active: true,
+                    outer_alive: true,
+                });
+            }
+        }
+
+        self.stack.push(Frame {
+            address,
+            selector: selector_hex(&inputs.input),
+            reentry_of,
+        });
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        _inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        let index = self.stack.len().saturating_sub(1);
+        if let Some(frame) = self.stack.pop() {
+            if let Some(event) = frame.reentry_of {
+                self.events[event].inner_active = false;
+            }
+            for state in self.events.iter_mut() {
+                if state.outer_index == index {
+                    state.outer_alive = false;
+                }
+            }
+        }
+        (ret, remaining_gas, out)
+    }
+}
+
+fn to_b160(a: Address) -> B160 {
+    B160::from(a.0)
+}
+
+fn to_u256(v: ethers::types::U256) -> U256 {
+    U256::from_limbs(v.0)
+}
+
+fn tx_env(tx: &Transaction) -> TxEnv {
+    TxEnv {
+        caller: to_b160(tx.from),
+        gas_limit: tx.gas.as_u64(),
+        gas_price: to_u256(tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default()),
+        gas_priority_fee: tx.max_priority_fee_per_gas.map(to_u256),
+        transact_to: match tx.to {
+            Some(to) => TransactTo::Call(to_b160(to)),
+            None => TransactTo::create(),
+        },
+        value: to_u256(tx.value),
+        data: tx.input.0.clone(),
+        chain_id: tx.chain_id.map(|c| c.as_u64()),
+        nonce: Some(tx.nonce.as_u64()),
+        access_list: Vec::new(),
+        ..Default::default()
+    }
+}
+
+/// Переигрывает `tx` поверх состояния родительского блока из `db`:
+/// предыдущие транзакции блока применяются без инспектора
+fn replay_on<DB: Database>(
+    db: DB,
+    block: &Block<Transaction>,
+    tx: &Transaction,
+    inspector: &mut ReentrancyInspector,
+) -> Result<(), TraceError>
+where
+    DB::Error: std::fmt::Debug,
+{
+    let mut evm = EVM::new();
+    evm.database(db);
+    evm.env.block.number = U256::from(block.number.unwrap_or_default().as_u64());
+    evm.env.block.timestamp = to_u256(block.timestamp);
+    evm.env.block.coinbase = to_b160(block.author.unwrap_or_default());
+    evm.env.block.basefee = to_u256(block.base_fee_per_gas.unwrap_or_default());
+    evm.env.block.gas_limit = to_u256(block.gas_limit);
+    evm.env.block.prevrandao = block.mix_hash.map(|h| h.0.into());
+
+    for prior in block.transactions.iter().take_while(|t| t.hash != tx.hash) {
+        evm.env.tx = tx_env(prior);
+        evm.transact_commit()
+            .map_err(|e| TraceError::EvmError(format!("{:?}", e)))?;
+    }
+
+    evm.env.tx = tx_env(tx);
+    evm.inspect_commit(inspector)
+        .map_err(|e| TraceError::EvmError(format!("{:?}", e)))?;
+    Ok(())
+}
+
+/// Повторяет недавние транзакции, затрагивающие цель, и ищет повторные входы
+pub struct ReentrancyReplayer<M> {
+    provider: Arc<M>,
+    state: Option<Arc<BlockingState>>,
+}
+
+impl<M: Middleware + 'static> ReentrancyReplayer<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self { provider, state: None }
+    }
+
+    /// Состояние для переигрывания берётся из `state` (кэш, снимок), а не из провайдера
+    pub fn with_state(mut self, state: Arc<dyn StateProvider>) -> Self {
+        self.state = Some(Arc::new(BlockingState::new(state)));
+        self
+    }
+
+    /// Хэши транзакций, в трейсах которых есть вызов цели (включая внутренние)
+    pub async fn recent_transactions(
+        &self,
+        target: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<H256>, TraceError> {
+        let filter = TraceFilter::default()
+            .from_block(BlockNumber::Number(from_block.into()))
+            .to_block(BlockNumber::Number(to_block.into()))
+            .to_address(vec![target]);
+
+        let traces = self
+            .provider
+            .trace_filter(filter)
+            .await
+            .map_err(|e| TraceError::ProviderError(e.to_string()))?;
+
+        let mut seen = HashSet::new();
+        Ok(traces
+            .into_iter()
+            .filter_map(|t| t.transaction_hash)
+            .filter(|h| seen.insert(*h))
+            .collect())
+    }
+
+    /// Переигрывает транзакцию поверх состояния её блока:
+    /// предыдущие транзакции блока применяются без инспектора
+    pub async fn replay(&self, target: Address, tx_hash: H256) -> Result<Vec<ReentrancyEvent>, TraceError> {
+        let provider_err = |e: M::Error| TraceError::ProviderError(e.to_string());
+
+        let tx = self
+            .provider
+            .get_transaction(tx_hash)
+            .await
+            .map_err(provider_err)?
+            .ok_or_else(|| TraceError::ProviderError(format!("tx {:?} not found", tx_hash)))?;
+        let block_number = tx.block_number.unwrap_or_default().as_u64();
+        let block = self
+            .provider
+            .get_block_with_txs(block_number)
+            .await
+            .map_err(provider_err)?
+            .ok_or(TraceError::BlockNotFound(block_number))?;
+
+        let parent = block_number.saturating_sub(1);
+        let mut inspector = ReentrancyInspector::new(target);
+        match &self.state {
+            Some(state) => {
+                let db = CacheDB::new(StateDb::new(state.clone(), parent));
+                replay_on(db, &block, &tx, &mut inspector)?;
+            }
+            None => {
+                let parent = BlockId::Number(BlockNumber::Number(parent.into()));
+                let ethers_db = EthersDB::new(self.provider.clone(), Some(parent))
+                    .ok_or_else(|| TraceError::EvmError("failed to create EthersDB".into()))?;
+                replay_on(CacheDB::new(ethers_db), &block, &tx, &mut inspector)?;
+            }
+        }
+
+        Ok(inspector
+            .into_events()
+            .into_iter()
+            .map(|mut e| {
+                e.tx_hash = Some(tx_hash);
+                e
+            })
+            .collect())
+    }
+
+    /// Полный проход по диапазону блоков
+    pub async fn scan(
+        &self,
+        target: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ReentrancyEvent>, TraceError> {
+        let mut events = Vec::new();
+        for hash in self.recent_transactions(target, from_block, to_block).await? {
+            events.extend(self.replay(target, hash).await?);
+        }
+        Ok(events)
+    }
+}
+
+impl SecurityReport {
+    /// Повторные входы с записями в хранилище становятся находками;
+    /// входы без записей считаются безопасными колбэками
+    pub fn add_reentrancy_events(&mut self, events: &[ReentrancyEvent]) {
+        for e in events.iter().filter(|e| e.has_intervening_writes()) {
+            let severity = if e.writes_after_reentry > 0 {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+
+            self.findings.push(Finding {
+                source: FindingSource::Dynamic,
+                detector: "dynamic-reentrancy".into(),
+                severity,
+                title: format!("Re-entry {} -> {}", e.outer_selector, e.inner_selector),
+                description: format!(
+                    "Observed re-entry at depth {} in tx {:?}{}: {} writes inside re-entry, {} writes after it",
+                    e.depth,
+                    e.tx_hash.unwrap_or_default(),
+                    if e.via_delegatecall { " via delegatecall" } else { "" },
+                    e.writes_in_reentry,
+                    e.writes_after_reentry
+                ),
+                location: None,
+                suggestion: None,
+            });
+        }
+        self.recalculate_score();
+    }
+}
@@ -0,0 +1,390 @@
+use super::slither::{compiler_findings, SlitherRun};
+use super::zk_audit::ZkAuditReport;
+use ethers::types::Address;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::{OpenApi, ToSchema};
+
+/// Серьёзность находки
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+pub enum Severity {
+    Informational,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Отображение поля `impact` из JSON-отчёта Slither
+    pub fn from_slither_impact(impact: &str) -> Self {
+        match impact {
+            "High" => Severity::High,
+            "Medium" => Severity::Medium,
+            "Low" => Severity::Low,
+            _ => Severity::Informational,
+        }
+    }
+
+    fn score_penalty(&self) -> f64 {
+        match self {
+            Severity::Critical => 0.4,
+            Severity::High => 0.3,
+            Severity::Medium => 0.1,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Анализатор, выдавший находку
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum FindingSource {
+    Slither,
+    Zk,
+    Bytecode,
+    Fuzzing,
+    Dynamic,
+    Governance,
+}
+
+/// Место в исходниках, к которому относится находка
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SourceLocation {
+    pub file: String,
+    pub lines: Vec<u32>,
+    pub contract: Option<String>,
+    pub function: Option<String>,
+    /// Колонки начала первой и конца последней строки, как их отдаёт Slither
+    #[serde(default)]
+    pub start_column: Option<u32>,
+    #[serde(default)]
+    pub end_column: Option<u32>,
+    /// Фрагмент в байтах от начала файла — из `source_mapping`, когда строк нет
+    #[serde(default)]
+    pub byte_offset: Option<u32>,
+    #[serde(default)]
+    pub byte_length: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct Finding {
+    pub source: FindingSource,
+    pub detector: String,
+    pub severity: Severity,
+    pub title: String,
+    pub description: String,
+    pub location: Option<SourceLocation>,
+    /// Предлагаемое исправление в виде патча, если для детектора есть шаблон
+    #[serde(default)]
+    pub suggestion: Option<FixSuggestion>,
+}
+
+/// Предлагаемое исправление находки
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FixSuggestion {
+    pub summary: String,
+    /// Unified diff относительно исходного файла
+    pub patch: String,
+    /// Что ещё нужно сделать вручную (импорты, наследование)
+    pub notes: Vec<String>,
+}
+
+impl Finding {
+    /// Идентификатор находки, не зависящий от номеров строк,
+    /// чтобы одна и та же проблема совпадала между версиями контракта
+    pub fn fingerprint(&self) -> String {
+        let (contract, function) = self
+            .location
+            .as_ref()
+            .map(|l| (l.contract.as_deref().unwrap_or(""), l.function.as_deref().unwrap_or("")))
+            .unwrap_or(("", ""));
+
+        format!("{:?}:{}:{}:{}:{}", self.source, self.detector, contract, function, self.title)
+    }
+}
+
+/// Единый отчёт по безопасности контракта (Slither, zk, байткод)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SecurityReport {
+    #[schema(value_type = String)]
+    pub address: Address,
+    /// Реализация за прокси, если отчёт построен для её кода
+    #[schema(value_type = Option<String>)]
+    pub implementation: Option<Address>,
+    pub findings: Vec<Finding>,
+    #[schema(value_type = Option<Object>)]
+    pub zk: Option<ZkAuditReport>,
+    /// Отпечаток verifying key для zk-верификаторов
+    pub verifying_key_hash: Option<String>,
+    pub security_score: f64,
+    pub generated_at: u64,
+    /// Проверки, которые не выполнялись, с причиной — чтобы пропуск был виден в отчёте
+    #[serde(default)]
+    pub skipped_checks: Vec<SkippedCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SkippedCheck {
+    pub analyzer: String,
+    pub check: String,
+    pub reason: String,
+}
+
+/// Схемы отчёта для спецификации API и генерируемых клиентов
+#[derive(OpenApi)]
+#[openapi(components(schemas(SecurityReport, Finding, FindingSource, Severity, SourceLocation, FixSuggestion, SkippedCheck)))]
+pub struct SecurityReportSchema;
+
+impl SecurityReport {
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            implementation: None,
+            findings: Vec::new(),
+            zk: None,
+            verifying_key_hash: None,
+            security_score: 1.0,
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            skipped_checks: Vec::new(),
+        }
+    }
+
+    /// Добавляет результат Slither вместе с пропущенными детекторами и дефектами компилятора
+    pub fn add_slither_run(&mut self, run: &SlitherRun) {
+        self.add_slither(&run.report);
+
+        for check in &run.skipped_detectors {
+            self.skipped_checks.push(SkippedCheck {
+                analyzer: "slither".into(),
+                check: check.to_string(),
+                reason: format!("not supported for {:?}", run.compiler),
+            });
+        }
+
+        self.findings.extend(compiler_findings(&run.compiler));
+        self.recalculate_score();
+    }
+
+    /// Добавляет находки из JSON-вывода Slither (`results.detectors`)
+    pub fn add_slither(&mut self, slither_json: &Value) {
+        let detectors = slither_json["results"]["detectors"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        for det in &detectors {
+            let element = &det["elements"][0];
+            let parent = &element["type_specific_fields"]["parent"];
+
+            // Для переменных и узлов CFG функция — это родитель элемента
+            let (contract, function) = if element["type"] == "function" {
+                (parent["name"].as_str(), element["name"].as_str())
+            } else if parent["type"] == "function" {
+                (
+                    parent["type_specific_fields"]["parent"]["name"].as_str(),
+                    parent["name"].as_str(),
+                )
+            } else {
+                (parent["name"].as_str(), element["name"].as_str())
+            };
+
+            let mapping = &element["source_mapping"];
+            let number = |key: &str| mapping[key].as_u64().and_then(|n| u32::try_from(n).ok());
+            let location = mapping["filename_relative"]
+                .as_str()
+                .map(|file| SourceLocation {
+                    file: file.to_string(),
+                    lines: mapping["lines"]
+                        .as_array()
+                        .map(|l| l.iter().filter_map(|n| n.as_u64()).filter_map(|n| u32::try_from(n).ok()).collect())
+                        .unwrap_or_default(),
+                    contract: contract.map(String::from),
+                    function: function.map(String::from),
+                    start_column: number("starting_column"),
+                    end_column: number("ending_column"),
+                    byte_offset: number("start"),
+                    byte_length: number("length"),
+                });
+
+            let detector = det["check"].as_str().unwrap_or("unknown").to_string();
+            self.findings.push(Finding {
+                source: FindingSource::Slither,
+                title: detector.clone(),
+                detector,
+                severity: Severity::from_slither_impact(det["impact"].as_str().unwrap_or("Low")),
+                description: det["description"].as_str().unwrap_or("").trim().to_string(),
+                location,
+                suggestion: None,
+            });
+        }
+
+        self.recalculate_score();
+    }
+
+    /// Добавляет результат zk-аудита как набор находок
+    pub fn add_zk(&mut self, zk: ZkAuditReport, verifying_key_hash: Option<String>) {
+        for op in &zk.risky_ops {
+            self.findings.push(Finding {
+                source: FindingSource::Zk,
+                detector: "zk-risky-op".into(),
+                severity: Severity::Medium,
+                title: op.clone(),
+                description: format!("Risky operation in {} verifier: {}", zk.zk_type, op),
+                location: None,
+                suggestion: None,
+            });
+        }
+
+        if !zk.math_checks.overflow_protected {
+            self.findings.push(Finding {
+                source: FindingSource::Zk,
+                detector: "zk-field-overflow".into(),
+                severity: Severity::High,
+                title: "Unprotected field arithmetic".into(),
+                description: format!("No overflow protection for {} arithmetic", zk.math_checks.curve_type),
+                location: None,
+                suggestion: None,
+            });
+        }
+
+        self.verifying_key_hash = verifying_key_hash;
+        self.zk = Some(zk);
+        self.recalculate_score();
+    }
+
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    pub(crate) fn recalculate_score(&mut self) {
+        let penalty: f64 = self.findings.iter().map(|f| f.severity.score_penalty()).sum();
+        self.security_score = (1.0 - penalty).max(0.0);
+    }
+
+    /// Разница между двумя версиями отчёта: новые, исправленные и оставшиеся находки
+    pub fn diff(old: &SecurityReport, new: &SecurityReport) -> ReportDiff {
+        let mut remaining: HashMap<String, usize> = HashMap::new();
+        for f in &old.findings {
+            *remaining.entry(f.fingerprint()).or_default() += 1;
+        }
+
+        let mut introduced = Vec::new();
+        let mut persisting = 0;
+        for f in &new.findings {
+            match remaining.get_mut(&f.fingerprint()) {
+                Some(n) if *n > 0 => {
+                    *n -= 1;
+                    persisting += 1;
+                }
+                _ => introduced.push(f.clone()),
+            }
+        }
+
+        // Всё, что не сопоставилось с новой версией, считается исправленным
+        let mut fixed = Vec::new();
+        for f in old.findings.iter().rev() {
+            if let Some(n) = remaining.get_mut(&f.fingerprint()) {
+                if *n > 0 {
+                    *n -= 1;
+                    fixed.push(f.clone());
+                }
+            }
+        }
+        fixed.reverse();
+
+        ReportDiff {
+            introduced,
+            fixed,
+            persisting,
+            verifying_key_changed: old.verifying_key_hash != new.verifying_key_hash,
+            score_delta: new.security_score - old.security_score,
+        }
+    }
+}
+
+/// Результат сравнения двух отчётов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub introduced: Vec<Finding>,
+    pub fixed: Vec<Finding>,
+    pub persisting: usize,
+    pub verifying_key_changed: bool,
+    pub score_delta: f64,
+}
+
+impl ReportDiff {
+    pub fn max_introduced_severity(&self) -> Option<Severity> {
+        self.introduced.iter().map(|f| f.severity).max()
+    }
+
+    /// Изменение требует алерта: новые находки не ниже порога или смена verifying key
+    pub fn is_regression(&self, threshold: Severity) -> bool {
+        self.verifying_key_changed
+            || self.max_introduced_severity().is_some_and(|s| s >= threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(detector: &str, function: &str, line: u32, severity: Severity) -> Finding {
+        Finding {
+            source: FindingSource::Slither,
+            detector: detector.into(),
+            severity,
+            title: detector.into(),
+            description: String::new(),
+            location: Some(SourceLocation {
+                file: "Vault.sol".into(),
+                lines: vec![line],
+                contract: Some("Vault".into()),
+                function: Some(function.into()),
+                start_column: None,
+                end_column: None,
+                byte_offset: None,
+                byte_length: None,
+            }),
+            suggestion: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_ignores_line_shifts() {
+        let mut old = SecurityReport::new(Address::zero());
+        old.findings = vec![
+            finding("reentrancy-eth", "withdraw", 10, Severity::High),
+            finding("unchecked-transfer", "sweep", 40, Severity::Medium),
+        ];
+        let mut new = SecurityReport::new(Address::zero());
+        new.findings = vec![
+            finding("reentrancy-eth", "withdraw", 25, Severity::High),
+            finding("arbitrary-send-eth", "rescue", 60, Severity::High),
+        ];
+
+        let diff = SecurityReport::diff(&old, &new);
+        assert_eq!(diff.persisting, 1);
+        assert_eq!(diff.introduced.len(), 1);
+        assert_eq!(diff.introduced[0].detector, "arbitrary-send-eth");
+        assert_eq!(diff.fixed.len(), 1);
+        assert_eq!(diff.fixed[0].detector, "unchecked-transfer");
+        assert!(diff.is_regression(Severity::High));
+    }
+
+    #[test]
+    fn test_verifying_key_change_is_regression() {
+        let mut old = SecurityReport::new(Address::zero());
+        old.verifying_key_hash = Some("aa".into());
+        let mut new = old.clone();
+        new.verifying_key_hash = Some("bb".into());
+
+        let diff = SecurityReport::diff(&old, &new);
+        assert!(diff.introduced.is_empty());
+        assert!(diff.is_regression(Severity::Critical));
+    }
+}
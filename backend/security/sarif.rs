@@ -0,0 +1,217 @@
+use super::report::{Finding, FindingSource, SecurityReport, Severity, SourceLocation};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Ключ `partialFingerprints`, по которому UI сопоставляет находки между загрузками
+const FINGERPRINT_KEY: &str = "definetlyFindingId/v1";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Informational => "note",
+    }
+}
+
+/// Числовая оценка для `security-severity` (шкала CVSS, её понимает GitHub code scanning)
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "3.0",
+        Severity::Informational => "0.0",
+    }
+}
+
+fn driver_name(source: FindingSource) -> &'static str {
+    match source {
+        FindingSource::Slither => "slither",
+        FindingSource::Zk => "definetly-zk-audit",
+        FindingSource::Bytecode => "definetly-bytecode",
+        FindingSource::Fuzzing => "definetly-invariant-fuzz",
+        FindingSource::Dynamic => "definetly-dynamic-trace",
+        FindingSource::Governance => "definetly-governance",
+    }
+}
+
+/// `region` SARIF: строки с колонками, иначе байтовый фрагмент; без них — весь артефакт
+fn region(loc: &SourceLocation) -> Option<Value> {
+    if let (Some(start), Some(end)) = (loc.lines.iter().min(), loc.lines.iter().max()) {
+        let mut region = json!({ "startLine": start, "endLine": end });
+        if let Some(column) = loc.start_column {
+            region["startColumn"] = json!(column.max(1));
+        }
+        if let Some(column) = loc.end_column {
+            region["endColumn"] = json!(column.max(1));
+        }
+        return Some(region);
+    }
+    loc.byte_offset.map(|offset| json!({ "byteOffset": offset, "byteLength": loc.byte_length.unwrap_or(0) }))
+}
+
+fn location(report: &SecurityReport, finding: &Finding) -> Value {
+    let target = format!("{:?}", report.implementation.unwrap_or(report.address));
+
+    match &finding.location {
+        Some(loc) => {
+            let mut physical = json!({ "artifactLocation": { "uri": loc.file } });
+            if let Some(region) = region(loc) {
+                physical["region"] = region;
+            }
+
+            let mut logical = Vec::new();
+            if let Some(contract) = &loc.contract {
+                let (name, kind) = match &loc.function {
+                    Some(func) => (format!("{}.{}", contract, func), "function"),
+                    None => (contract.clone(), "type"),
+                };
+                logical.push(json!({ "fullyQualifiedName": name, "kind": kind }));
+            }
+
+            json!({
+                "physicalLocation": physical,
+                "logicalLocations": logical,
+            })
+        }
+        // Находки по байткоду: артефакт — сам адрес в сети
+        None => json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": format!("{}.bin", target) },
+            },
+            "logicalLocations": [{ "fullyQualifiedName": target, "kind": "module" }],
+        }),
+    }
+}
+
+/// Сериализует отчёты в SARIF 2.1.0: один `run` на каждый анализатор
+pub fn to_sarif(reports: &[SecurityReport]) -> Value {
+    let mut by_source: BTreeMap<&'static str, Vec<(&SecurityReport, &Finding)>> = BTreeMap::new();
+    for report in reports {
+        for finding in &report.findings {
+            by_source
+                .entry(driver_name(finding.source))
+                .or_default()
+                .push((report, finding));
+        }
+    }
+
+    let runs: Vec<Value> = by_source
+        .into_iter()
+        .map(|(driver, findings)| {
+            // Правило = детектор; его уровень по умолчанию — максимальная встреченная серьёзность
+            let mut rules: BTreeMap<&str, Severity> = BTreeMap::new();
+            for (_, f) in &findings {
+                let entry = rules.entry(f.detector.as_str()).or_insert(f.severity);
+                *entry = (*entry).max(f.severity);
+            }
+            let rule_ids: Vec<&str> = rules.keys().copied().collect();
+
+            let rules_json: Vec<Value> = rules
+                .iter()
+                .map(|(id, severity)| {
+                    json!({
+                        "id": id,
+                        "name": id,
+                        "shortDescription": { "text": id },
+                        "defaultConfiguration": { "level": level(*severity) },
+                        "properties": {
+                            "security-severity": security_severity(*severity),
+                            "tags": ["security"],
+                        },
+                    })
+                })
+                .collect();
+
+            let results: Vec<Value> = findings
+                .iter()
+                .map(|(report, f)| {
+                    let message = if f.description.is_empty() { &f.title } else { &f.description };
+                    json!({
+                        "ruleId": f.detector,
+                        "ruleIndex": rule_ids.iter().position(|id| *id == f.detector),
+                        "level": level(f.severity),
+                        "message": { "text": message },
+                        "locations": [location(report, f)],
+                        "partialFingerprints": { (FINGERPRINT_KEY): f.fingerprint() },
+                        "properties": {
+                            "contract": format!("{:?}", report.address),
+                            "security-severity": security_severity(f.severity),
+                        },
+                    })
+                })
+                .collect();
+
+            json!({
+                "tool": {
+                    "driver": {
+                        "name": driver,
+                        "informationUri": "https://github.com/Gsoprgepous/DeFinetly",
+                        "rules": rules_json,
+                    }
+                },
+                "results": results,
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": runs,
+    })
+}
+
+impl SecurityReport {
+    pub fn to_sarif(&self) -> Value {
+        to_sarif(std::slice::from_ref(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Address;
+
+    fn location_of(loc: SourceLocation) -> Value {
+        let mut report = SecurityReport::new(Address::zero());
+        report.findings.push(Finding {
+            source: FindingSource::Slither,
+            detector: "reentrancy-eth".into(),
+            severity: Severity::High,
+            title: "reentrancy-eth".into(),
+            description: String::new(),
+            location: Some(loc),
+            suggestion: None,
+        });
+        report.to_sarif()["runs"][0]["results"][0]["locations"][0]["physicalLocation"].clone()
+    }
+
+    fn vault(lines: Vec<u32>) -> SourceLocation {
+        SourceLocation {
+            file: "Vault.sol".into(),
+            lines,
+            contract: Some("Vault".into()),
+            function: None,
+            start_column: None,
+            end_column: None,
+            byte_offset: None,
+            byte_length: None,
+        }
+    }
+
+    #[test]
+    fn region_is_filled_or_omitted() {
+        let lines = location_of(SourceLocation { start_column: Some(5), end_column: Some(6), ..vault(vec![12, 10, 14]) });
+        assert_eq!(lines["region"], json!({ "startLine": 10, "endLine": 14, "startColumn": 5, "endColumn": 6 }));
+
+        let bytes = location_of(SourceLocation { byte_offset: Some(340), byte_length: Some(25), ..vault(Vec::new()) });
+        assert_eq!(bytes["region"], json!({ "byteOffset": 340, "byteLength": 25 }));
+
+        let whole = location_of(vault(Vec::new()));
+        assert!(whole.get("region").is_none());
+    }
+}
@@ -0,0 +1,253 @@
+use super::report::{Finding, FindingSource, SecurityReport, Severity};
+use ethers::types::H256;
+use ethers::utils::{hex, keccak256};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid signature database: {0}")]
+    ParseError(#[from] serde_json::Error),
+
+    #[error("Invalid pattern in {id}: {msg}")]
+    InvalidPattern { id: String, msg: String },
+}
+
+/// Условие совпадения с байткодом
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Matcher {
+    /// Точное совпадение keccak256 runtime-кода
+    CodeHash { hash: H256 },
+    /// Набор селекторов в диспетчере (PUSH4-константы)
+    Selectors {
+        all_of: Vec<String>,
+        #[serde(default)]
+        any_of: Vec<String>,
+        #[serde(default)]
+        none_of: Vec<String>,
+    },
+    /// Шестнадцатеричный шаблон, `??` — любой байт
+    BytePattern { pattern: String },
+}
+
+/// Запись базы известных уязвимых версий
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnSignature {
+    pub id: String,
+    pub title: String,
+    pub severity: Severity,
+    /// CVE, GHSA и ссылки на разборы
+    pub references: Vec<String>,
+    pub matcher: Matcher,
+}
+
+/// Совпадение в аудируемом коде
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureMatch {
+    pub id: String,
+    pub title: String,
+    pub severity: Severity,
+    pub references: Vec<String>,
+}
+
+fn parse_selector(s: &str) -> Option<[u8; 4]> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).ok()?;
+    bytes.try_into().ok()
+}
+
+fn parse_pattern(pattern: &str) -> Option<Vec<Option<u8>>> {
+    let clean: String = pattern.trim_start_matches("0x").split_whitespace().collect();
+    if clean.len() % 2 != 0 {
+        return None;
+    }
+    (0..clean.len())
+        .step_by(2)
+        .map(|i| match &clean[i..i + 2] {
+            "??" => Some(None),
+            byte => u8::from_str_radix(byte, 16).ok().map(Some),
+        })
+        .collect()
+}
+
+/// PUSH4-константы байткода — приближение набора селекторов диспетчера
+pub fn bytecode_selectors(code: &[u8]) -> HashSet<[u8; 4]> {
+    let mut selectors = HashSet::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let op = code[pc];
+        if (0x60..=0x7f).contains(&op) {
+            let size = (op - 0x5f) as usize;
+            if op == 0x63 && pc + 5 <= code.len() {
+                selectors.insert([code[pc + 1], code[pc + 2], code[pc + 3], code[pc + 4]]);
+            }
+            pc += size;
+        }
+        pc += 1;
+    }
+
+    selectors
+}
+
+/// Встроенные сигнатуры; расширяются файлом через `SignatureDatabase::load`
+fn builtin_signatures() -> Vec<VulnSignature> {
+    let sel = |s: &[&str]| s.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+    vec![
+        VulnSignature {
+            id: "DFN-OZ-UUPS-UNINIT".into(),
+            title: "OpenZeppelin UUPSUpgradeable 4.1.0-4.3.1 implementation can be taken over".into(),
+            severity: Severity::Critical,
+            references: vec![
+                "CVE-2021-41264".into(),
+                "https://github.com/OpenZeppelin/openzeppelin-contracts/security/advisories/GHSA-5vp3-v4hc-gx76".into(),
+            ],
+            // upgradeTo + upgradeToAndCall без proxiableUUID (появился в 4.5)
+            matcher: Matcher::Selectors {
+                all_of: sel(&["0x3659cfe6", "0x4f1ef286"]),
+                any_of: Vec::new(),
+                none_of: sel(&["0x52d1902d"]),
+            },
+        },
+        VulnSignature {
+            id: "DFN-OZ-TRANSPARENT-CLASH".into(),
+            title: "Implementation exposes selectors shadowed by TransparentUpgradeableProxy admin functions".into(),
+            severity: Severity::Medium,
+            references: vec![
+                "CVE-2023-30541".into(),
+                "https://github.com/OpenZeppelin/openzeppelin-contracts/security/advisories/GHSA-mx2q-35m2-x2rh".into(),
+            ],
+            matcher: Matcher::Selectors {
+                all_of: Vec::new(),
+                any_of: sel(&["0xf851a440", "0x5c60da1b", "0x8f283970"]),
+                none_of: Vec::new(),
+            },
+        },
+        VulnSignature {
+            id: "DFN-OZ-2771-MULTICALL".into(),
+            title: "ERC2771Context combined with Multicall allows sender spoofing".into(),
+            severity: Severity::High,
+            references: vec![
+                "https://blog.openzeppelin.com/arbitrary-address-spoofing-vulnerability-erc2771context-multicall-public-disclosure".into(),
+            ],
+            matcher: Matcher::Selectors {
+                all_of: sel(&["0xac9650d8", "0x572b6c05"]),
+                any_of: Vec::new(),
+                none_of: Vec::new(),
+            },
+        },
+    ]
+}
+
+/// База сигнатур с предразобранными шаблонами
+pub struct SignatureDatabase {
+    signatures: Vec<VulnSignature>,
+}
+
+impl Default for SignatureDatabase {
+    fn default() -> Self {
+        Self {
+            signatures: builtin_signatures(),
+        }
+    }
+}
+
+impl SignatureDatabase {
+    /// Встроенные сигнатуры плюс записи из JSON-файла (массив `VulnSignature`)
+    pub fn load(path: &Path) -> Result<Self, SignatureError> {
+        let extra: Vec<VulnSignature> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let mut db = Self::default();
+        for sig in extra {
+            db.add(sig)?;
+        }
+        Ok(db)
+    }
+
+    /// Добавляет сигнатуру, проверяя корректность селекторов и шаблонов
+    pub fn add(&mut self, sig: VulnSignature) -> Result<(), SignatureError> {
+        let invalid = |msg: &str| SignatureError::InvalidPattern { id: sig.id.clone(), msg: msg.to_string() };
+
+        match &sig.matcher {
+            Matcher::Selectors { all_of, any_of, none_of } => {
+                if all_of.iter().chain(any_of).chain(none_of).any(|s| parse_selector(s).is_none()) {
+                    return Err(invalid("selector must be 4 bytes hex"));
+                }
+                if all_of.is_empty() && any_of.is_empty() {
+                    return Err(invalid("selector matcher needs all_of or any_of"));
+                }
+            }
+            Matcher::BytePattern { pattern } => {
+                if parse_pattern(pattern).map_or(true, |p| p.is_empty()) {
+                    return Err(invalid("byte pattern must be non-empty hex with ?? wildcards"));
+                }
+            }
+            Matcher::CodeHash { .. } => {}
+        }
+
+        self.signatures.retain(|s| s.id != sig.id);
+        self.signatures.push(sig);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Сверяет runtime-код со всеми сигнатурами
+    pub fn match_code(&self, code: &[u8]) -> Vec<SignatureMatch> {
+        let code_hash = H256(keccak256(code));
+        let selectors = bytecode_selectors(code);
+        let has = |s: &String| parse_selector(s).map_or(false, |s| selectors.contains(&s));
+
+        self.signatures
+            .iter()
+            .filter(|sig| match &sig.matcher {
+                Matcher::CodeHash { hash } => *hash == code_hash,
+                Matcher::Selectors { all_of, any_of, none_of } => {
+                    all_of.iter().all(has)
+                        && (any_of.is_empty() || any_of.iter().any(has))
+                        && !none_of.iter().any(has)
+                }
+                Matcher::BytePattern { pattern } => parse_pattern(pattern).map_or(false, |p| {
+                    !p.is_empty()
+                        && code.windows(p.len()).any(|w| {
+                            w.iter().zip(&p).all(|(b, m)| m.map_or(true, |m| m == *b))
+                        })
+                }),
+            })
+            .map(|sig| SignatureMatch {
+                id: sig.id.clone(),
+                title: sig.title.clone(),
+                severity: sig.severity,
+                references: sig.references.clone(),
+            })
+            .collect()
+    }
+}
+
+impl SecurityReport {
+    pub fn add_signature_matches(&mut self, matches: &[SignatureMatch]) {
+        for m in matches {
+            self.findings.push(Finding {
+                source: FindingSource::Bytecode,
+                detector: m.id.clone(),
+                severity: m.severity,
+                title: m.title.clone(),
+                description: format!("Matches known-vulnerable code signature; see {}", m.references.join(", ")),
+                location: None,
+                suggestion: None,
+            });
+        }
+        self.recalculate_score();
+    }
+}
@@ -1,3 +1,5 @@
+use super::report::{Finding, FindingSource, Severity};
+use std::path::Path;
 use std::process::Command;
 use serde_json::Value;
 use thiserror::Error;
@@ -8,12 +10,102 @@ pub enum SlitherError {
     ExecutionError(String),
     #[error("JSON parsing error: {0}")]
     ParseError(#[from] serde_json::Error),
+    #[error("Unknown compiler: {0}")]
+    UnknownCompiler(String),
 }
 
-/// Анализ контракта через Slither
-pub fn analyze_contract(contract_path: &str, solc_version: &str) -> Result<Value, SlitherError> {
-    let output = Command::new("solc-select")
-        .args(["install", solc_version])
+/// Компилятор, которым собран контракт
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compiler {
+    Solc(String),
+    Vyper(String),
+}
+
+/// Детекторы Slither, не применимые к Vyper (модификаторы, inline assembly,
+/// прагмы и соглашения Solidity) — исключаются, а не падают посреди анализа
+pub const VYPER_UNSUPPORTED_DETECTORS: &[&str] = &[
+    "solc-version",
+    "pragma",
+    "assembly",
+    "naming-convention",
+    "incorrect-modifier",
+    "uninitialized-storage",
+    "shadowing-builtin",
+    "unprotected-upgrade",
+];
+
+/// Версии Vyper со сломанным `@nonreentrant` (инцидент Curve, июль 2023)
+const VYPER_BROKEN_REENTRANCY_LOCK: &[&str] = &["0.2.15", "0.2.16", "0.3.0"];
+
+impl Compiler {
+    /// По полю `CompilerVersion` из Etherscan: `v0.8.19+commit.7dd6d404` или `vyper:0.3.7`
+    pub fn from_etherscan(compiler_version: &str) -> Result<Self, SlitherError> {
+        let raw = compiler_version.trim();
+        if let Some(version) = raw.strip_prefix("vyper:") {
+            return Ok(Compiler::Vyper(version.to_string()));
+        }
+
+        let version = raw
+            .trim_start_matches('v')
+            .split('+')
+            .next()
+            .filter(|v| v.chars().next().map_or(false, |c| c.is_ascii_digit()))
+            .ok_or_else(|| SlitherError::UnknownCompiler(raw.to_string()))?;
+        Ok(Compiler::Solc(version.to_string()))
+    }
+
+    /// По расширению файла и прагме версии в исходнике
+    pub fn detect(contract_path: &str, source: &str) -> Option<Self> {
+        let ext = Path::new(contract_path).extension().and_then(|e| e.to_str());
+
+        match ext {
+            Some("vy") => source
+                .lines()
+                .filter_map(|l| {
+                    let l = l.trim();
+                    l.strip_prefix("# @version")
+                        .or_else(|| l.strip_prefix("#pragma version"))
+                        .or_else(|| l.strip_prefix("# pragma version"))
+                })
+                .map(|v| v.trim().trim_start_matches(['^', '=', '~']).trim().to_string())
+                .next()
+                .map(Compiler::Vyper),
+            Some("sol") => source
+                .lines()
+                .find_map(|l| l.trim().strip_prefix("pragma solidity"))
+                .map(|v| {
+                    v.trim()
+                        .trim_end_matches(';')
+                        .trim_start_matches(['^', '=', '~', '>'])
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or("")
+                        .to_string()
+                })
+                .filter(|v| !v.is_empty())
+                .map(Compiler::Solc),
+            _ => None,
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            Compiler::Solc(v) | Compiler::Vyper(v) => v,
+        }
+    }
+}
+
+/// Результат запуска Slither с учётом компилятора
+#[derive(Debug)]
+pub struct SlitherRun {
+    pub report: Value,
+    pub compiler: Compiler,
+    /// Детекторы, которые не запускались для этого компилятора
+    pub skipped_detectors: Vec<&'static str>,
+}
+
+fn run(cmd: &mut Command) -> Result<std::process::Output, SlitherError> {
+    let output = cmd
         .output()
         .map_err(|e| SlitherError::ExecutionError(e.to_string()))?;
 
@@ -22,14 +114,86 @@ pub fn analyze_contract(contract_path: &str, solc_version: &str) -> Result<Value
             String::from_utf8_lossy(&output.stderr).into_owned(),
         ));
     }
+    Ok(output)
+}
+
+/// Устанавливает нужную версию компилятора
+fn install_compiler(compiler: &Compiler) -> Result<(), SlitherError> {
+    match compiler {
+        Compiler::Solc(version) => run(Command::new("solc-select").args(["install", version]))?,
+        Compiler::Vyper(version) => run(Command::new("python3").args([
+            "-m",
+            "pip",
+            "install",
+            "--quiet",
+            &format!("vyper=={}", version),
+        ]))?,
+    };
+    Ok(())
+}
+
+/// Анализ контракта через Slither
+pub fn analyze_contract(contract_path: &str, solc_version: &str) -> Result<Value, SlitherError> {
+    analyze_with_compiler(contract_path, &Compiler::Solc(solc_version.to_string())).map(|r| r.report)
+}
+
+/// Анализ с явным компилятором: для Vyper несовместимые детекторы исключаются
+pub fn analyze_with_compiler(contract_path: &str, compiler: &Compiler) -> Result<SlitherRun, SlitherError> {
+    install_compiler(compiler)?;
+
+    let mut cmd = Command::new("slither");
+    cmd.args([contract_path, "--json", "-"]);
 
-    let slither_output = Command::new("slither")
-        .args([contract_path, "--json", "-"])
+    let skipped_detectors = match compiler {
+        Compiler::Solc(version) => {
+            cmd.env("SOLC_VERSION", version);
+            Vec::new()
+        }
+        Compiler::Vyper(_) => {
+            cmd.args(["--exclude", &VYPER_UNSUPPORTED_DETECTORS.join(",")]);
+            VYPER_UNSUPPORTED_DETECTORS.to_vec()
+        }
+    };
+
+    // Slither возвращает ненулевой код при наличии находок, поэтому статус не проверяем
+    let slither_output = cmd
         .output()
         .map_err(|e| SlitherError::ExecutionError(e.to_string()))?;
 
     let report: Value = serde_json::from_slice(&slither_output.stdout)?;
-    Ok(report)
+    if report["success"] == false {
+        return Err(SlitherError::ExecutionError(
+            report["error"].as_str().unwrap_or("unknown error").to_string(),
+        ));
+    }
+
+    Ok(SlitherRun {
+        report,
+        compiler: compiler.clone(),
+        skipped_detectors,
+    })
+}
+
+/// Известные дефекты самого компилятора, которые Slither не видит
+pub fn compiler_findings(compiler: &Compiler) -> Vec<Finding> {
+    match compiler {
+        Compiler::Vyper(version) if VYPER_BROKEN_REENTRANCY_LOCK.contains(&version.as_str()) => {
+            vec![Finding {
+                source: FindingSource::Bytecode,
+                detector: "vyper-broken-reentrancy-lock".into(),
+                severity: Severity::Critical,
+                title: format!("Vyper {} nonreentrant lock is broken", version),
+                description: format!(
+                    "Vyper {} allocates separate storage slots for @nonreentrant locks with the same key; \
+                     cross-function reentrancy is possible",
+                    version
+                ),
+                location: None,
+                suggestion: None,
+            }]
+        }
+        _ => Vec::new(),
+    }
 }
 
 /// Расчёт security score
@@ -0,0 +1,56 @@
+use ethers::types::{Address, H256};
+use mevdetector::state::{BlockingState, StateError};
+use revm::db::DatabaseRef;
+use revm::primitives::{keccak256, AccountInfo, Bytecode, B160, B256, KECCAK_EMPTY, U256};
+use std::sync::Arc;
+
+/// База revm поверх `StateProvider`: замена `EthersDB`, работающая и со снимком,
+/// и с кэшем. Чтение состояния на блоке `block`; revm синхронный, поэтому через мост
+pub struct StateDb {
+    state: Arc<BlockingState>,
+    block: u64,
+}
+
+impl StateDb {
+    pub fn new(state: Arc<BlockingState>, block: u64) -> Self {
+        Self { state, block }
+    }
+}
+
+fn to_address(a: B160) -> Address {
+    Address::from(a.0)
+}
+
+impl DatabaseRef for StateDb {
+    type Error = StateError;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        let address = to_address(address);
+        let account = self.state.account(address, self.block)?;
+        let code = self.state.code(address, self.block)?;
+
+        let code_hash = if code.is_empty() { KECCAK_EMPTY } else { keccak256(&code) };
+        Ok(Some(AccountInfo::new(
+            U256::from_limbs(account.balance.0),
+            account.nonce,
+            code_hash,
+            Bytecode::new_raw(code.0),
+        )))
+    }
+
+    fn code_by_hash(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Код уже отдан в `basic`
+        Err(StateError::Unsupported("code by hash"))
+    }
+
+    fn storage(&self, address: B160, index: U256) -> Result<U256, Self::Error> {
+        let slot = H256::from(index.to_be_bytes());
+        let value = self.state.storage(to_address(address), slot, self.block)?;
+        Ok(U256::from_be_bytes(value.0))
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        let hash = self.state.block_hash(number.saturating_to::<u64>())?;
+        Ok(B256::from(hash.0))
+    }
+}
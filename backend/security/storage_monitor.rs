@@ -0,0 +1,341 @@
+use super::report::Severity;
+use super::upgrade_watcher::EIP1967_IMPLEMENTATION_SLOT;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockId, BlockNumber, H256, U256};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)`
+pub const EIP1967_ADMIN_SLOT: &str =
+    "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+/// `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`
+pub const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+/// ERC-7201 namespace `openzeppelin.storage.Ownable` (OZ 5.x upgradeable)
+pub const OZ5_OWNABLE_SLOT: &str =
+    "0x9016d09d72d40fdae2fd8ceac6b6234c7706214fd39c1cd1e609a0528c199300";
+/// ERC-7201 namespace `openzeppelin.storage.Pausable` (OZ 5.x upgradeable)
+pub const OZ5_PAUSABLE_SLOT: &str =
+    "0xcd5ed15c6e187e77e9aee88184c21f4f2182ab5827cb3b7e07fbedcd63f03300";
+
+#[derive(Debug, Error)]
+pub enum StorageMonitorError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Invalid storage layout: {0}")]
+    InvalidLayout(String),
+}
+
+/// Назначение отслеживаемого слота
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotKind {
+    Owner,
+    Admin,
+    Implementation,
+    Beacon,
+    Paused,
+    Guardian,
+}
+
+impl SlotKind {
+    /// Сопоставление имени переменной из storage layout
+    fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim_start_matches('_').to_lowercase();
+        match label.as_str() {
+            "owner" => Some(SlotKind::Owner),
+            "admin" | "proxyadmin" => Some(SlotKind::Admin),
+            "implementation" => Some(SlotKind::Implementation),
+            "paused" => Some(SlotKind::Paused),
+            "guardian" | "pauseguardian" | "emergencyguardian" => Some(SlotKind::Guardian),
+            _ => None,
+        }
+    }
+
+    /// Серьёзность изменения значения слота
+    pub fn change_severity(&self) -> Severity {
+        match self {
+            SlotKind::Implementation | SlotKind::Beacon | SlotKind::Admin => Severity::Critical,
+            SlotKind::Owner | SlotKind::Guardian => Severity::High,
+            SlotKind::Paused => Severity::Medium,
+        }
+    }
+}
+
+/// Слот хранилища (или его упакованная часть)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriticalSlot {
+    pub kind: SlotKind,
+    pub slot: H256,
+    /// Смещение в байтах от младшего края слова (как в solc storageLayout)
+    pub offset: u8,
+    pub size: u8,
+}
+
+impl CriticalSlot {
+    fn new(kind: SlotKind, slot: &str, size: u8) -> Self {
+        Self {
+            kind,
+            slot: slot.parse().expect("valid slot constant"),
+            offset: 0,
+            size,
+        }
+    }
+
+    /// Извлекает значение переменной из 32-байтного слова; части за пределами слова отбрасываются
+    pub fn extract(&self, word: H256) -> Vec<u8> {
+        let end = 32usize.saturating_sub(self.offset as usize);
+        let start = end.saturating_sub(self.size as usize);
+        word.as_bytes()[start..end].to_vec()
+    }
+
+    /// Человекочитаемое значение: адрес для 20 байт, bool для 1 байта
+    pub fn display(&self, word: H256) -> String {
+        let raw = self.extract(word);
+        match raw.len() {
+            20 => format!("{:?}", Address::from_slice(&raw)),
+            1 => (raw[0] != 0).to_string(),
+            _ => format!("0x{}", ethers::utils::hex::encode(&raw)),
+        }
+    }
+}
+
+/// Слоты, не зависящие от исходников: EIP-1967 и ERC-7201 OpenZeppelin 5
+pub fn conventional_slots() -> Vec<CriticalSlot> {
+    vec![
+        CriticalSlot::new(SlotKind::Implementation, EIP1967_IMPLEMENTATION_SLOT, 20),
+        CriticalSlot::new(SlotKind::Admin, EIP1967_ADMIN_SLOT, 20),
+        CriticalSlot::new(SlotKind::Beacon, EIP1967_BEACON_SLOT, 20),
+        CriticalSlot::new(SlotKind::Owner, OZ5_OWNABLE_SLOT, 20),
+        CriticalSlot::new(SlotKind::Paused, OZ5_PAUSABLE_SLOT, 1),
+    ]
+}
+
+/// Ключевые слоты из `storageLayout` компилятора solc
+pub fn slots_from_layout(layout: &Value) -> Result<Vec<CriticalSlot>, StorageMonitorError> {
+    let storage = layout["storage"]
+        .as_array()
+        .ok_or_else(|| StorageMonitorError::InvalidLayout("missing storage array".into()))?;
+
+    let mut slots = Vec::new();
+    for var in storage {
+        let Some(kind) = var["label"].as_str().and_then(SlotKind::from_label) else {
+            continue;
+        };
+        let slot = var["slot"]
+            .as_str()
+            .and_then(|s| U256::from_dec_str(s).ok())
+            .ok_or_else(|| StorageMonitorError::InvalidLayout(format!("bad slot in {}", var)))?;
+
+        let ty = var["type"].as_str().unwrap_or("");
+        let size = layout["types"][ty]["numberOfBytes"]
+            .as_str()
+            .and_then(|n| n.parse::<u8>().ok())
+            .unwrap_or(32);
+
+        let mut word = [0u8; 32];
+        slot.to_big_endian(&mut word);
+        slots.push(CriticalSlot {
+            kind,
+            slot: H256(word),
+            offset: var["offset"].as_u64().unwrap_or(0) as u8,
+            size,
+        });
+    }
+
+    Ok(slots)
+}
+
+/// Изменение критического слота
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotChange {
+    pub contract: Address,
+    pub kind: SlotKind,
+    pub slot: H256,
+    pub old_value: String,
+    pub new_value: String,
+    pub block: u64,
+    pub severity: Severity,
+}
+
+impl SlotChange {
+    pub fn summary(&self) -> String {
+        let what = match self.kind {
+            SlotKind::Owner => "Ownership transferred",
+            SlotKind::Admin => "Proxy admin changed",
+            SlotKind::Implementation => "Implementation swapped",
+            SlotKind::Beacon => "Beacon changed",
+            SlotKind::Paused => "Pause state changed",
+            SlotKind::Guardian => "Guardian changed",
+        };
+        format!(
+            "{} on {:?} at block {}: {} -> {}",
+            what, self.contract, self.block, self.old_value, self.new_value
+        )
+    }
+}
+
+struct WatchedSlot {
+    slot: CriticalSlot,
+    value: Vec<u8>,
+    word: H256,
+}
+
+/// Поблочный мониторинг владельцев, админов, реализаций и флагов паузы
+pub struct StorageMonitor<M> {
+    provider: Arc<M>,
+    watched: HashMap<Address, Vec<WatchedSlot>>,
+}
+
+impl<M: Middleware> StorageMonitor<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            watched: HashMap::new(),
+        }
+    }
+
+    async fn read(&self, contract: Address, slot: H256, block: Option<u64>) -> Result<H256, StorageMonitorError> {
+        self.provider
+            .get_storage_at(
+                contract,
+                slot,
+                block.map(|b| BlockId::Number(BlockNumber::Number(b.into()))),
+            )
+            .await
+            .map_err(|e| StorageMonitorError::ProviderError(e.to_string()))
+    }
+
+    /// Начинает наблюдение. Без layout берутся только конвенциональные слоты; пустые тоже
+    /// наблюдаются, иначе первая установка реализации, владельца или паузы пройдёт незамеченной
+    pub async fn watch(&mut self, contract: Address, layout: Option<&Value>) -> Result<Vec<CriticalSlot>, StorageMonitorError> {
+        self.watch_from(contract, layout, None).await
+    }
+
+    /// Наблюдение от сохранённой базы (`baseline`, слот -> слово) после рестарта: изменения,
+    /// случившиеся пока узел стоял, придут первым `poll`. Слоты вне базы читаются заново
+    pub async fn restore(
+        &mut self,
+        contract: Address,
+        layout: Option<&Value>,
+        baseline: &HashMap<H256, H256>,
+    ) -> Result<Vec<CriticalSlot>, StorageMonitorError> {
+        self.watch_from(contract, layout, Some(baseline)).await
+    }
+
+    /// Последние слова наблюдаемых слотов контракта — база для `restore`
+    pub fn baseline(&self, contract: Address) -> HashMap<H256, H256> {
+        self.watched.get(&contract).into_iter().flatten().map(|w| (w.slot.slot, w.word)).collect()
+    }
+
+    async fn watch_from(
+        &mut self,
+        contract: Address,
+        layout: Option<&Value>,
+        baseline: Option<&HashMap<H256, H256>>,
+    ) -> Result<Vec<CriticalSlot>, StorageMonitorError> {
+        let mut candidates = conventional_slots();
+        if let Some(layout) = layout {
+            candidates.extend(slots_from_layout(layout)?);
+        }
+
+        let mut watched = Vec::new();
+        for slot in candidates {
+            let word = match baseline.and_then(|baseline| baseline.get(&slot.slot)) {
+                Some(word) => *word,
+                None => self.read(contract, slot.slot, None).await?,
+            };
+            watched.push(WatchedSlot { value: slot.extract(word), slot, word });
+        }
+
+        let slots = watched.iter().map(|w| w.slot.clone()).collect();
+        self.watched.insert(contract, watched);
+        Ok(slots)
+    }
+
+    pub fn unwatch(&mut self, contract: Address) {
+        self.watched.remove(&contract);
+    }
+
+    /// Сверяет слоты всех контрактов на блоке и возвращает изменения
+    pub async fn poll(&mut self, block: u64) -> Result<Vec<SlotChange>, StorageMonitorError> {
+        let mut changes = Vec::new();
+        let contracts: Vec<Address> = self.watched.keys().copied().collect();
+        for contract in contracts {
+            changes.extend(self.poll_contract(contract, block).await?);
+        }
+        Ok(changes)
+    }
+
+    /// Сверяет слоты одного контракта; для ненаблюдаемого — пустой результат
+    pub async fn poll_contract(&mut self, contract: Address, block: u64) -> Result<Vec<SlotChange>, StorageMonitorError> {
+        let Some(watched) = self.watched.get(&contract) else {
+            return Ok(Vec::new());
+        };
+        let slots: Vec<H256> = watched.iter().map(|w| w.slot.slot).collect();
+        let mut words = Vec::with_capacity(slots.len());
+        for slot in slots {
+            words.push(self.read(contract, slot, Some(block)).await?);
+        }
+
+        let mut changes = Vec::new();
+        let watched = self.watched.get_mut(&contract).expect("contract is watched");
+        for (w, word) in watched.iter_mut().zip(words) {
+            let value = w.slot.extract(word);
+            if value == w.value {
+                continue;
+            }
+
+            changes.push(SlotChange {
+                contract,
+                kind: w.slot.kind.clone(),
+                slot: w.slot.slot,
+                old_value: w.slot.display(w.word),
+                new_value: w.slot.display(word),
+                block,
+                severity: w.slot.kind.change_severity(),
+            });
+            w.value = value;
+            w.word = word;
+        }
+
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_clamps_offsets_outside_the_word() {
+        let mut word = [0u8; 32];
+        word[31] = 1;
+        word[0] = 0xaa;
+        let word = H256(word);
+
+        let packed = CriticalSlot { kind: SlotKind::Paused, slot: H256::zero(), offset: 0, size: 1 };
+        assert_eq!(packed.extract(word), vec![1]);
+
+        let top = CriticalSlot { offset: 31, ..packed.clone() };
+        assert_eq!(top.extract(word), vec![0xaa]);
+
+        let outside = CriticalSlot { offset: 40, ..packed.clone() };
+        assert!(outside.extract(word).is_empty());
+
+        let oversized = CriticalSlot { offset: 0, size: 64, ..packed };
+        assert_eq!(oversized.extract(word).len(), 32);
+    }
+
+    #[test]
+    fn zero_words_keep_a_displayable_value() {
+        let owner = CriticalSlot::new(SlotKind::Owner, OZ5_OWNABLE_SLOT, 20);
+        assert_eq!(owner.display(H256::zero()), format!("{:?}", Address::zero()));
+        let paused = CriticalSlot::new(SlotKind::Paused, OZ5_PAUSABLE_SLOT, 1);
+        assert_eq!(paused.display(H256::zero()), "false");
+    }
+}
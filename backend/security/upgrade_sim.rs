@@ -0,0 +1,430 @@
+use super::upgrade_watcher::EIP1967_IMPLEMENTATION_SLOT;
+use async_trait::async_trait;
+use ethers::abi::{encode, Token};
+use ethers::providers::{Http, JsonRpcClient, Middleware, Provider, RpcError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, H256, U256};
+use ethers::utils::id;
+use mevdetector::bus::{AlertLevel, BusAlert};
+use mevdetector::config::UpgradeSimulationConfig;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Error)]
+pub enum SimulationError {
+    #[error("Fork RPC error: {0}")]
+    ForkError(String),
+
+    #[error("No scenario for proxy {0:?}")]
+    NoScenario(Address),
+
+    #[error("Invalid scenario: {0}")]
+    InvalidScenario(String),
+
+    #[error("Upgrade transaction {0:?} not found")]
+    TransactionNotFound(H256),
+
+    #[error("Upgrade of {0:?} could not be installed on the fork: {1}")]
+    InstallFailed(Address, String),
+}
+
+fn fork_err(e: impl std::fmt::Display) -> SimulationError {
+    SimulationError::ForkError(e.to_string())
+}
+
+/// Один вызов сценария; состояние после него видят следующие шаги
+#[derive(Debug, Clone, Serialize)]
+pub struct Interaction {
+    pub label: String,
+    pub from: Address,
+    pub to: Address,
+    pub data: Bytes,
+    pub value: U256,
+    /// Откат ожидаем (например, вывод сверх баланса) и сам по себе не алертит
+    pub expect_revert: bool,
+}
+
+impl Interaction {
+    pub fn call(label: &str, from: Address, to: Address, signature: &str, args: &[Token]) -> Self {
+        let mut data = id(signature).to_vec();
+        data.extend(encode(args));
+        Self {
+            label: label.to_string(),
+            from,
+            to,
+            data: data.into(),
+            value: U256::zero(),
+            expect_revert: false,
+        }
+    }
+
+    pub fn expecting_revert(mut self) -> Self {
+        self.expect_revert = true;
+        self
+    }
+}
+
+/// Круг депозит-вывод для хранилища ERC-4626 от имени держателя актива:
+/// одобрение, депозит, чтение долей и активов, вывод половины, вывод сверх депозита
+pub fn erc4626_round_trip(vault: Address, asset: Address, holder: Address, assets: U256) -> Vec<Interaction> {
+    let user = Token::Address(holder);
+    vec![
+        Interaction::call("approve", holder, asset, "approve(address,uint256)", &[Token::Address(vault), Token::Uint(assets)]),
+        Interaction::call("deposit", holder, vault, "deposit(uint256,address)", &[Token::Uint(assets), user.clone()]),
+        Interaction::call("balanceOf", holder, vault, "balanceOf(address)", std::slice::from_ref(&user)),
+        Interaction::call("totalAssets", holder, vault, "totalAssets()", &[]),
+        Interaction::call(
+            "withdraw",
+            holder,
+            vault,
+            "withdraw(uint256,address,address)",
+            &[Token::Uint(assets / 2), user.clone(), user.clone()],
+        ),
+        Interaction::call("maxWithdraw", holder, vault, "maxWithdraw(address)", std::slice::from_ref(&user)),
+        Interaction::call(
+            "withdraw_excess",
+            holder,
+            vault,
+            "withdraw(uint256,address,address)",
+            &[Token::Uint(assets), user.clone(), user],
+        )
+        .expecting_revert(),
+    ]
+}
+
+/// Как новая реализация попадает в прокси на форке
+#[derive(Debug, Clone)]
+pub enum Installation {
+    /// Подмена слота EIP-1967: вызов апгрейда неизвестен, инициализатор не исполняется
+    Slot,
+    /// Повтор транзакции апгрейда из сети вместе с её инициализатором
+    Transaction(H256),
+    /// Вызов, который исполнит governance, — от имени таймлока
+    Call { from: Address, to: Address, data: Bytes, value: U256 },
+}
+
+/// Итог шага на одной реализации
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepOutcome {
+    pub label: String,
+    pub success: bool,
+    /// Возвращённые данные; при откате — данные ошибки, если узел их отдал
+    pub output: Bytes,
+    pub revert: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// Шаг прошёл на одной реализации и откатился на другой
+    Status,
+    /// Оба прошли, но вернули разное
+    Output,
+    /// Новая реализация откатила шаг, который не должен откатываться
+    UnexpectedRevert,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    pub label: String,
+    pub kind: DivergenceKind,
+    pub old: StepOutcome,
+    pub new: StepOutcome,
+}
+
+/// Прогон сценария на прежней и новой реализации
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeSimulation {
+    pub proxy: Address,
+    pub old_implementation: Address,
+    pub new_implementation: Address,
+    pub block: Option<u64>,
+    pub old: Vec<StepOutcome>,
+    pub new: Vec<StepOutcome>,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Шаги сравниваются попарно; после первого расхождения по статусу состояние уже разное,
+/// поэтому выводы дальнейших шагов не сравниваются, только их статусы
+pub fn compare(steps: &[Interaction], old: &[StepOutcome], new: &[StepOutcome]) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    let mut diverged = false;
+    for ((step, old), new) in steps.iter().zip(old).zip(new) {
+        let kind = if !new.success && !step.expect_revert && !old.success {
+            // Откатывается на обеих: сценарий не подходит контракту, а не апгрейд сломал его
+            None
+        } else if !new.success && !step.expect_revert {
+            Some(DivergenceKind::UnexpectedRevert)
+        } else if old.success != new.success {
+            Some(DivergenceKind::Status)
+        } else if old.success && !diverged && old.output != new.output {
+            Some(DivergenceKind::Output)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            diverged |= kind != DivergenceKind::Output;
+            divergences.push(Divergence { label: step.label.clone(), kind, old: old.clone(), new: new.clone() });
+        }
+    }
+    divergences
+}
+
+/// Симуляция апгрейда до или после его исполнения
+#[async_trait]
+pub trait UpgradeSimulator: Send + Sync {
+    /// `block` — блок форка; `None` — текущее состояние форка
+    async fn simulate(
+        &self,
+        proxy: Address,
+        old_implementation: Address,
+        new_implementation: Address,
+        installation: &Installation,
+        block: Option<u64>,
+    ) -> Result<UpgradeSimulation, SimulationError>;
+}
+
+/// Симуляция на форке Anvil: прежняя реализация ставится в слот через `anvil_setStorageAt`,
+/// новая — по `Installation`; шаги исполняются от имени нужных адресов, между прогонами
+/// состояние откатывается снимком
+pub struct AnvilUpgradeSimulator<P> {
+    fork: Arc<Provider<P>>,
+    /// RPC исходной сети для `anvil_reset` на блок апгрейда
+    upstream: Option<String>,
+    scenarios: HashMap<Address, Vec<Interaction>>,
+    /// Форк один: сброс, снимки и шаги разных симуляций не должны перемежаться
+    lock: Mutex<()>,
+}
+
+impl AnvilUpgradeSimulator<Http> {
+    /// Форк и сценарии хранилищ из `[monitor.simulation]`
+    pub fn from_config(config: &UpgradeSimulationConfig) -> Result<Self, SimulationError> {
+        let fork = Provider::<Http>::try_from(config.fork_url.as_str()).map_err(fork_err)?;
+        let mut simulator = Self::new(Arc::new(fork));
+        if let Some(upstream) = &config.upstream_url {
+            simulator = simulator.with_upstream(upstream);
+        }
+        for vault in &config.vaults {
+            let invalid = |e: &dyn std::fmt::Display| SimulationError::InvalidScenario(format!("vault {}: {}", vault.proxy, e));
+            let address = |value: &str| value.parse::<Address>().map_err(|e| invalid(&e));
+            let assets = U256::from_dec_str(&vault.assets).map_err(|e| invalid(&e))?;
+            let proxy = address(&vault.proxy)?;
+            simulator = simulator.with_scenario(proxy, erc4626_round_trip(proxy, address(&vault.asset)?, address(&vault.holder)?, assets));
+        }
+        Ok(simulator)
+    }
+}
+
+impl<P: JsonRpcClient + 'static> AnvilUpgradeSimulator<P> {
+    pub fn new(fork: Arc<Provider<P>>) -> Self {
+        Self { fork, upstream: None, scenarios: HashMap::new(), lock: Mutex::new(()) }
+    }
+
+    pub fn with_upstream(mut self, rpc_url: &str) -> Self {
+        self.upstream = Some(rpc_url.to_string());
+        self
+    }
+
+    pub fn with_scenario(mut self, proxy: Address, steps: Vec<Interaction>) -> Self {
+        self.scenarios.insert(proxy, steps);
+        self
+    }
+
+    async fn rpc<T: serde::Serialize + Send + Sync + std::fmt::Debug, R: serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug + Send>(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, SimulationError> {
+        self.fork.request(method, params).await.map_err(fork_err)
+    }
+
+    /// Транзакция от имени `tx.from` без его ключа; `true`, если она не откатилась
+    async fn send_as(&self, tx: &TypedTransaction) -> Result<bool, SimulationError> {
+        let from = *tx.from().expect("sender is set");
+        let _: () = self.rpc("anvil_impersonateAccount", [from]).await?;
+        let _: () = self.rpc("anvil_setBalance", (from, U256::exp10(21))).await?;
+        let hash: H256 = self.rpc("eth_sendTransaction", [tx]).await?;
+        let receipt = self.fork.get_transaction_receipt(hash).await.map_err(fork_err)?;
+        Ok(receipt.and_then(|r| r.status).is_some_and(|s| s.as_u64() == 1))
+    }
+
+    /// Шаг: сначала `eth_call` ради возвращённых данных, затем транзакция, чтобы состояние
+    /// перешло к следующему шагу. Anvil добывает блок на каждую транзакцию
+    async fn step(&self, step: &Interaction) -> Result<StepOutcome, SimulationError> {
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(step.from)
+            .to(step.to)
+            .data(step.data.clone())
+            .value(step.value)
+            .into();
+        let (output, revert) = match self.fork.call(&tx, None).await {
+            Ok(output) => (output, None),
+            Err(e) => {
+                let data = e.as_error_response().and_then(|r| r.as_revert_data()).unwrap_or_default();
+                (data, Some(e.to_string()))
+            }
+        };
+        let success = revert.is_none() && self.send_as(&tx).await?;
+        Ok(StepOutcome { label: step.label.clone(), success, output, revert })
+    }
+
+    /// Ставит реализацию в прокси. Повтор апгрейда исполняет и его инициализатор;
+    /// после вызова слот сверяется с ожидаемой реализацией
+    async fn install(&self, proxy: Address, implementation: Address, installation: &Installation) -> Result<(), SimulationError> {
+        let slot: H256 = EIP1967_IMPLEMENTATION_SLOT.parse().expect("valid slot constant");
+        let tx: TypedTransaction = match installation {
+            Installation::Slot => {
+                let _: bool = self.rpc("anvil_setStorageAt", (proxy, slot, H256::from(implementation))).await?;
+                return Ok(());
+            }
+            Installation::Transaction(hash) => {
+                let original = self
+                    .fork
+                    .get_transaction(*hash)
+                    .await
+                    .map_err(fork_err)?
+                    .ok_or(SimulationError::TransactionNotFound(*hash))?;
+                let to = original.to.ok_or_else(|| SimulationError::InstallFailed(proxy, "upgrade transaction creates a contract".into()))?;
+                TransactionRequest::new()
+                    .from(original.from)
+                    .to(to)
+                    .data(original.input)
+                    .value(original.value)
+                    .gas(original.gas)
+                    .into()
+            }
+            Installation::Call { from, to, data, value } => {
+                TransactionRequest::new().from(*from).to(*to).data(data.clone()).value(*value).into()
+            }
+        };
+        if !self.send_as(&tx).await? {
+            return Err(SimulationError::InstallFailed(proxy, "upgrade call reverted".into()));
+        }
+        let installed = self.fork.get_storage_at(proxy, slot, None).await.map_err(fork_err)?;
+        if Address::from_slice(&installed.as_bytes()[12..]) != implementation {
+            return Err(SimulationError::InstallFailed(proxy, format!("implementation slot is not {:?}", implementation)));
+        }
+        Ok(())
+    }
+
+    /// Прогон сценария на реализации с откатом состояния после него
+    async fn run(
+        &self,
+        proxy: Address,
+        implementation: Address,
+        installation: &Installation,
+        steps: &[Interaction],
+    ) -> Result<Vec<StepOutcome>, SimulationError> {
+        let snapshot: U256 = self.rpc("evm_snapshot", ()).await?;
+        let result = async {
+            self.install(proxy, implementation, installation).await?;
+            let mut outcomes = Vec::with_capacity(steps.len());
+            for step in steps {
+                outcomes.push(self.step(step).await?);
+            }
+            Ok(outcomes)
+        }
+        .await;
+        let _: bool = self.rpc("evm_revert", [snapshot]).await?;
+        result
+    }
+}
+
+#[async_trait]
+impl<P: JsonRpcClient + 'static> UpgradeSimulator for AnvilUpgradeSimulator<P> {
+    async fn simulate(
+        &self,
+        proxy: Address,
+        old_implementation: Address,
+        new_implementation: Address,
+        installation: &Installation,
+        block: Option<u64>,
+    ) -> Result<UpgradeSimulation, SimulationError> {
+        let steps = self.scenarios.get(&proxy).ok_or(SimulationError::NoScenario(proxy))?;
+        let _fork = self.lock.lock().await;
+        if let Some(block) = block {
+            // Без `jsonRpcUrl` Anvil сбрасывается через тот же upstream, с которым запущен
+            let mut forking = json!({ "blockNumber": block });
+            if let Some(upstream) = &self.upstream {
+                forking["jsonRpcUrl"] = json!(upstream);
+            }
+            let _: () = self.rpc("anvil_reset", [json!({ "forking": forking })]).await?;
+        }
+        let old = self.run(proxy, old_implementation, &Installation::Slot, steps).await?;
+        let new = self.run(proxy, new_implementation, installation, steps).await?;
+        Ok(UpgradeSimulation {
+            proxy,
+            old_implementation,
+            new_implementation,
+            block,
+            divergences: compare(steps, &old, &new),
+            old,
+            new,
+        })
+    }
+}
+
+/// Алерт о расхождении поведения; `None`, если реализации ведут себя одинаково
+pub fn simulation_alert(simulation: &UpgradeSimulation) -> Option<BusAlert> {
+    let first = simulation.divergences.first()?;
+    let broken = simulation.divergences.iter().any(|d| d.kind != DivergenceKind::Output);
+    let title = format!(
+        "Upgrade of {:?} to {:?} changes behavior: {} step(s) diverge, first '{}' ({:?})",
+        simulation.proxy,
+        simulation.new_implementation,
+        simulation.divergences.len(),
+        first.label,
+        first.kind
+    );
+    Some(
+        BusAlert::new(
+            "monitor",
+            "upgrade_simulation",
+            if broken { AlertLevel::Critical } else { AlertLevel::High },
+            format!("{:?}", simulation.proxy),
+            title,
+        )
+        .with_payload(serde_json::to_value(simulation).unwrap_or_default()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(label: &str, success: bool, output: &[u8]) -> StepOutcome {
+        StepOutcome { label: label.into(), success, output: output.to_vec().into(), revert: None }
+    }
+
+    #[test]
+    fn test_compare_flags_reverts_and_changed_outputs() {
+        let steps = erc4626_round_trip(Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), 100.into());
+        let labels: Vec<&str> = steps.iter().map(|s| s.label.as_str()).collect();
+        let old: Vec<StepOutcome> = labels.iter().map(|l| outcome(l, *l != "withdraw_excess", &[1])).collect();
+
+        assert!(compare(&steps, &old, &old).is_empty());
+
+        let mut new = old.clone();
+        new[2].output = vec![2].into();
+        new[4].success = false;
+        let divergences = compare(&steps, &old, &new);
+        assert_eq!(divergences.len(), 2);
+        assert_eq!(divergences[0].kind, DivergenceKind::Output);
+        assert_eq!(divergences[1].kind, DivergenceKind::UnexpectedRevert);
+
+        let simulation = UpgradeSimulation {
+            proxy: Address::repeat_byte(1),
+            old_implementation: Address::repeat_byte(4),
+            new_implementation: Address::repeat_byte(5),
+            block: None,
+            old,
+            new,
+            divergences,
+        };
+        assert_eq!(simulation_alert(&simulation).unwrap().level, AlertLevel::Critical);
+    }
+}
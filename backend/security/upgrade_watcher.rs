@@ -0,0 +1,285 @@
+use super::report::{ReportDiff, SecurityReport, Severity};
+use super::slither::SlitherError;
+use super::zk_audit::{audit_zk_contract, is_zk_contract, verifying_key_fingerprint};
+use async_trait::async_trait;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockId, BlockNumber, Filter, H256, U256};
+use ethers::utils::keccak256;
+use mevdetector::task_errors::{TaskErrors, TaskErrorsSnapshot};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Слот реализации EIP-1967: `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+pub const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// Глубина поиска транзакции апгрейда, если прокси давно не проверялся
+const MAX_UPGRADE_LOOKBACK: u64 = 10_000;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    #[error("Slither error: {0}")]
+    SlitherError(#[from] SlitherError),
+
+    #[error("Not an EIP-1967 proxy: {0:?}")]
+    NotAProxy(Address),
+}
+
+/// Аудит кода конкретной реализации
+#[async_trait]
+pub trait ContractAuditor: Send + Sync {
+    async fn audit(
+        &self,
+        proxy: Address,
+        implementation: Address,
+        code: &[u8],
+    ) -> Result<SecurityReport, AuditError>;
+}
+
+/// Аудитор только по байткоду: zk-проверки и отпечаток verifying key
+pub struct BytecodeAuditor;
+
+#[async_trait]
+impl ContractAuditor for BytecodeAuditor {
+    async fn audit(
+        &self,
+        proxy: Address,
+        implementation: Address,
+        code: &[u8],
+    ) -> Result<SecurityReport, AuditError> {
+        let mut report = SecurityReport::new(proxy);
+        report.implementation = Some(implementation);
+
+        if is_zk_contract(code) {
+            let zk = audit_zk_contract(implementation, code.to_vec());
+            report.add_zk(zk, verifying_key_fingerprint(code));
+        }
+
+        Ok(report)
+    }
+}
+
+/// Событие смены реализации прокси
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeEvent {
+    pub proxy: Address,
+    pub old_implementation: Address,
+    pub new_implementation: Address,
+    /// Блок транзакции апгрейда, если она найдена, иначе блок проверки
+    pub block: u64,
+    /// Транзакция с событием `Upgraded` новой реализации
+    pub transaction: Option<H256>,
+    pub diff: ReportDiff,
+    /// Апгрейд добавил находки не ниже порога или сменил verifying key
+    pub requires_alert: bool,
+}
+
+/// Смена реализации, найденная по событию `Upgraded` или по слоту
+struct Upgrade {
+    block: u64,
+    transaction: Option<H256>,
+    implementation: Address,
+}
+
+struct WatchedProxy {
+    implementation: Address,
+    report: SecurityReport,
+    /// Последний проверенный блок: с него ищется транзакция апгрейда
+    checked_block: Option<u64>,
+}
+
+/// Следит за EIP-1967 прокси и переаудирует их при смене реализации
+pub struct UpgradeWatcher<M> {
+    provider: Arc<M>,
+    auditor: Arc<dyn ContractAuditor>,
+    watched: HashMap<Address, WatchedProxy>,
+    alert_threshold: Severity,
+    errors: TaskErrors,
+}
+
+impl<M: Middleware> UpgradeWatcher<M> {
+    pub fn new(provider: Arc<M>, auditor: Arc<dyn ContractAuditor>, alert_threshold: Severity) -> Self {
+        Self {
+            provider,
+            auditor,
+            watched: HashMap::new(),
+            alert_threshold,
+            errors: TaskErrors::default(),
+        }
+    }
+
+    pub fn errors(&self) -> TaskErrorsSnapshot {
+        self.errors.snapshot()
+    }
+
+    /// Добавляет прокси и строит базовый отчёт для текущей реализации
+    pub async fn watch(&mut self, proxy: Address) -> Result<&SecurityReport, AuditError> {
+        let implementation = self
+            .implementation_at(proxy, None)
+            .await?
+            .ok_or(AuditError::NotAProxy(proxy))?;
+        let report = self.audit_implementation(proxy, implementation).await?;
+
+        let entry = self
+            .watched
+            .entry(proxy)
+            .or_insert(WatchedProxy { implementation, report, checked_block: None });
+        Ok(&entry.report)
+    }
+
+    /// Наблюдение от сохранённой реализации после рестарта: смена, случившаяся пока узел
+    /// стоял, придёт первым `poll_proxy` с поиском транзакции апгрейда после `checked_block`
+    pub async fn restore(&mut self, proxy: Address, implementation: Address, checked_block: Option<u64>) -> Result<&SecurityReport, AuditError> {
+        let report = self.audit_implementation(proxy, implementation).await?;
+        let entry = self
+            .watched
+            .entry(proxy)
+            .or_insert(WatchedProxy { implementation, report, checked_block });
+        Ok(&entry.report)
+    }
+
+    pub fn unwatch(&mut self, proxy: Address) {
+        self.watched.remove(&proxy);
+    }
+
+    /// Реализация, с которой сравнивается следующий `poll_proxy`
+    pub fn implementation(&self, proxy: Address) -> Option<Address> {
+        self.watched.get(&proxy).map(|w| w.implementation)
+    }
+
+    /// Текущий отчёт по реализации наблюдаемого прокси
+    pub fn report(&self, proxy: Address) -> Option<&SecurityReport> {
+        self.watched.get(&proxy).map(|w| &w.report)
+    }
+
+    /// Сравнивает слоты реализаций на указанном блоке; вызывается на каждый новый блок.
+    /// Сбой одного прокси попадает в `errors` и не теряет события остальных; такой прокси
+    /// проверяется снова на следующем блоке
+    pub async fn poll(&mut self, block: u64) -> Vec<UpgradeEvent> {
+        let mut events = Vec::new();
+        let proxies: Vec<Address> = self.watched.keys().copied().collect();
+        for proxy in proxies {
+            match self.poll_proxy(proxy, block).await {
+                Ok(proxy_events) => events.extend(proxy_events),
+                Err(e) => self.errors.record(format!("upgrade check of {:?} at block {}: {}", proxy, block, e)),
+            }
+        }
+        events
+    }
+
+    /// Проверяет один прокси; при смене реализации переаудирует её. Каждый апгрейд с прошлой
+    /// проверки — отдельное событие в порядке транзакций; при ошибке состояние не меняется
+    pub async fn poll_proxy(&mut self, proxy: Address, block: u64) -> Result<Vec<UpgradeEvent>, AuditError> {
+        let Some((old_implementation, checked_block)) = self.watched.get(&proxy).map(|w| (w.implementation, w.checked_block)) else {
+            return Ok(Vec::new());
+        };
+        let Some(current) = self.implementation_at(proxy, Some(block)).await? else {
+            return Ok(Vec::new());
+        };
+        if current == old_implementation {
+            self.watched.get_mut(&proxy).expect("proxy is watched").checked_block = Some(block);
+            return Ok(Vec::new());
+        }
+
+        let from = checked_block.map_or(block, |b| b + 1).max(block.saturating_sub(MAX_UPGRADE_LOOKBACK));
+        let mut upgrades = Vec::new();
+        let mut previous = old_implementation;
+        for upgrade in self.upgrade_transactions(proxy, from, block).await? {
+            if upgrade.implementation != previous {
+                previous = upgrade.implementation;
+                upgrades.push(upgrade);
+            }
+        }
+        // Слот записан без события `Upgraded` (или лог вне глубины поиска): апгрейд на блоке проверки
+        if previous != current {
+            upgrades.push(Upgrade { block, transaction: None, implementation: current });
+        }
+
+        let mut reports = Vec::with_capacity(upgrades.len());
+        for upgrade in &upgrades {
+            reports.push(self.audit_implementation(proxy, upgrade.implementation).await?);
+        }
+
+        let watched = self.watched.get_mut(&proxy).expect("proxy is watched");
+        let mut events = Vec::with_capacity(upgrades.len());
+        for (upgrade, report) in upgrades.into_iter().zip(reports) {
+            let diff = SecurityReport::diff(&watched.report, &report);
+            events.push(UpgradeEvent {
+                proxy,
+                old_implementation: watched.implementation,
+                new_implementation: upgrade.implementation,
+                block: upgrade.block,
+                transaction: upgrade.transaction,
+                requires_alert: diff.is_regression(self.alert_threshold),
+                diff,
+            });
+            watched.implementation = upgrade.implementation;
+            watched.report = report;
+        }
+        watched.checked_block = Some(block);
+        Ok(events)
+    }
+
+    /// События `Upgraded` прокси в `[from, to]` в порядке исполнения
+    async fn upgrade_transactions(&self, proxy: Address, from: u64, to: u64) -> Result<Vec<Upgrade>, AuditError> {
+        let filter = Filter::new()
+            .address(proxy)
+            .topic0(H256::from(keccak256("Upgraded(address)")))
+            .from_block(from)
+            .to_block(to);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(|e| AuditError::ProviderError(e.to_string()))?;
+
+        let mut upgrades: Vec<(u64, U256, Upgrade)> = logs
+            .into_iter()
+            .filter_map(|log| {
+                let implementation = Address::from(*log.topics.get(1)?);
+                let block = log.block_number?.as_u64();
+                let upgrade = Upgrade { block, transaction: log.transaction_hash, implementation };
+                Some((block, log.log_index.unwrap_or_default(), upgrade))
+            })
+            .collect();
+        upgrades.sort_by_key(|(block, index, _)| (*block, *index));
+        Ok(upgrades.into_iter().map(|(_, _, upgrade)| upgrade).collect())
+    }
+
+    async fn implementation_at(
+        &self,
+        proxy: Address,
+        block: Option<u64>,
+    ) -> Result<Option<Address>, AuditError> {
+        let slot: H256 = EIP1967_IMPLEMENTATION_SLOT.parse().expect("valid slot constant");
+        let block = block.map(|b| BlockId::Number(BlockNumber::Number(b.into())));
+
+        let value = self
+            .provider
+            .get_storage_at(proxy, slot, block)
+            .await
+            .map_err(|e| AuditError::ProviderError(e.to_string()))?;
+
+        let implementation = Address::from_slice(&value.as_bytes()[12..]);
+        Ok((!implementation.is_zero()).then_some(implementation))
+    }
+
+    async fn audit_implementation(
+        &self,
+        proxy: Address,
+        implementation: Address,
+    ) -> Result<SecurityReport, AuditError> {
+        let code = self
+            .provider
+            .get_code(implementation, None)
+            .await
+            .map_err(|e| AuditError::ProviderError(e.to_string()))?;
+
+        self.auditor.audit(proxy, implementation, &code).await
+    }
+}
@@ -1,8 +1,9 @@
 use ethers::types::Address;
+use ethers::utils::{hex, keccak256};
 use revm::Inspector;
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZkAuditReport {
     pub zk_type: String,
     pub risky_ops: Vec<String>,
@@ -10,7 +11,7 @@ pub struct ZkAuditReport {
     pub security_score: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MathChecks {
     pub curve_type: String,
     pub overflow_protected: bool,
@@ -27,6 +28,32 @@ pub fn is_zk_contract(code: &[u8]) -> bool {
     zk_signatures.iter().any(|sig| code.windows(sig.len()).any(|w| w == *sig))
 }
 
+/// Отпечаток verifying key: keccak от всех PUSH32-констант байткода.
+/// Solidity-верификаторы (snarkjs, gnark) хранят точки ключа как константы,
+/// поэтому отпечаток меняется при замене ключа даже без изменения логики.
+pub fn verifying_key_fingerprint(code: &[u8]) -> Option<String> {
+    let mut constants = Vec::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let op = code[pc];
+        if (0x60..=0x7f).contains(&op) {
+            let size = (op - 0x5f) as usize;
+            if op == 0x7f && pc + 1 + size <= code.len() {
+                constants.extend_from_slice(&code[pc + 1..pc + 1 + size]);
+            }
+            pc += size;
+        }
+        pc += 1;
+    }
+
+    if constants.is_empty() {
+        None
+    } else {
+        Some(hex::encode(keccak256(&constants)))
+    }
+}
+
 /// Полный аудит zk-контракта
 pub fn audit_zk_contract(address: Address, code: Vec<u8>) -> ZkAuditReport {
     let mut report = ZkAuditReport {
@@ -0,0 +1,63 @@
+#!/usr/bin/env sh
+# Генерация клиентских SDK (TypeScript, Python) из OpenAPI-спецификации API.
+#
+#   ./clients/generate.sh [spec] [--publish]
+#
+# spec — путь к файлу или URL `/openapi.json` запущенного сервера. Полный сервер
+# объединяет схемы всех подсистем (SecurityReport, RiskParams, портфель); без
+# аргумента берётся спецификация крейта `definetly openapi` (алерты, детекторы, аудит).
+set -eu
+
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+CLIENTS="$ROOT/clients"
+VERSION="${CLIENT_VERSION:-0.1.0}"
+SPEC=""
+PUBLISH=0
+
+for arg in "$@"; do
+    case "$arg" in
+        --publish) PUBLISH=1 ;;
+        *) SPEC="$arg" ;;
+    esac
+done
+
+mkdir -p "$CLIENTS/build"
+SPEC_FILE="$CLIENTS/build/openapi.json"
+
+case "$SPEC" in
+    "")
+        cargo run --quiet \
+            --manifest-path "$ROOT/backend/domains/monitoring/mev/core/Cargo.toml" \
+            --features server --bin definetly -- openapi > "$SPEC_FILE"
+        ;;
+    http://*|https://*)
+        curl --fail --silent --show-error "$SPEC" -o "$SPEC_FILE"
+        ;;
+    *)
+        cp "$SPEC" "$SPEC_FILE"
+        ;;
+esac
+
+# Версия генератора закреплена в openapitools.json
+GENERATOR="npx --yes @openapitools/openapi-generator-cli"
+cd "$CLIENTS"
+
+rm -rf typescript python
+$GENERATOR generate \
+    -i "$SPEC_FILE" \
+    -g typescript-fetch \
+    -o typescript \
+    -c typescript.yaml \
+    --additional-properties "npmVersion=$VERSION"
+
+$GENERATOR generate \
+    -i "$SPEC_FILE" \
+    -g python \
+    -o python \
+    -c python.yaml \
+    --additional-properties "packageVersion=$VERSION"
+
+if [ "$PUBLISH" -eq 1 ]; then
+    (cd typescript && npm install && npm run build && npm publish --access public)
+    (cd python && python -m build && python -m twine upload dist/*)
+fi